@@ -0,0 +1,267 @@
+//! Throughput of the pipeline's heavy steps on large generated canvases.
+//!
+//! ```sh
+//! cargo bench --bench throughput                   # full sizes, timings printed per case
+//! cargo bench --bench throughput -- --bench-check   # reduced sizes, checking results only
+//! ```
+//!
+//! Without `--bench` (which `cargo bench` passes) it runs in check mode: reduced sizes, one
+//! iteration, asserting each case produced what it should. `cargo test` runs it that way,
+//! so the benchmarks can't rot unnoticed. Nothing is gated on timings.
+
+use std::error::Error;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::TimeZone;
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::warnings::codes;
+use syndicate_json_canvas_lib::{
+    ColorFilter, Config, DEFAULT_MAX_ITEM_BYTES, InAdjacencies, ItemSizeLimit, MAX_SLUG_BYTES, MAX_TITLE_CHARS,
+    MockClock, OutAdjacencies, OversizePolicy, ProcessOptions, SyndicationSink, SyndicationTracker, Syndicator,
+    SyntheticCanvas, default_process_node, parse_canvas, process_node_with_colors, to_syndication_format,
+};
+use syndicate_json_canvas_sinks::{DirectorySink, JjRepositorySink};
+
+/// Sizes of each case, at full size and in check mode
+struct Sizes {
+    parse: &'static [usize],
+    generate: usize,
+    tracker: usize,
+    reprocess: usize,
+    /// Bytes of text in each huge node
+    huge: usize,
+    iterations: usize,
+}
+
+const FULL: Sizes = Sizes {
+    parse: &[1_000, 10_000, 50_000],
+    generate: 1_000,
+    tracker: 100_000,
+    reprocess: 1_000,
+    huge: 16 << 20,
+    iterations: 5,
+};
+const CHECK: Sizes =
+    Sizes { parse: &[100, 500], generate: 100, tracker: 1_000, reprocess: 50, huge: 4 << 20, iterations: 1 };
+
+/// Huge nodes added to a small canvas by the huge-node case
+const HUGE_NODES: usize = 3;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let check = args.iter().any(|a| a == "--bench-check") || !args.iter().any(|a| a == "--bench");
+    let sizes = if check { &CHECK } else { &FULL };
+
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-bench-{}", std::process::id()));
+    let result = run(sizes, &scratch);
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    result?;
+    if check {
+        println!("bench check ok");
+    }
+    Ok(())
+}
+
+fn run(sizes: &Sizes, scratch: &Path) -> Result<(), Box<dyn Error>> {
+    for &nodes in sizes.parse {
+        bench_parse(nodes, sizes.iterations)?;
+    }
+    bench_generate(sizes.generate, sizes.iterations, &scratch.join("generate"))?;
+    bench_tracker(sizes.tracker, sizes.iterations, &scratch.join("tracker"))?;
+    bench_reprocess(sizes.reprocess, sizes.iterations, &scratch.join("reprocess"))?;
+    for policy in [OversizePolicy::Skip, OversizePolicy::Truncate] {
+        bench_huge_nodes(sizes.huge, policy, sizes.iterations, &scratch.join(format!("huge-{:?}", policy)))?;
+    }
+    Ok(())
+}
+
+/// Median time of `iterations` runs of `f`, and the result of the last one
+fn measure<T>(iterations: usize, mut f: impl FnMut() -> Result<T, Box<dyn Error>>) -> Result<(Duration, T), Box<dyn Error>> {
+    let mut times = Vec::with_capacity(iterations);
+    let mut last = None;
+    for _ in 0..iterations.max(1) {
+        let started = Instant::now();
+        let result = black_box(f()?);
+        times.push(started.elapsed());
+        last = Some(result);
+    }
+    Ok((median(times), last.expect("ran at least once")))
+}
+
+fn median(mut times: Vec<Duration>) -> Duration {
+    times.sort();
+    times[times.len() / 2]
+}
+
+fn print(case: &str, median: Duration, iterations: usize) {
+    println!("{:<40} {:>10.2} ms  (median of {})", case, median.as_secs_f64() * 1000.0, iterations);
+}
+
+/// Parsing a canvas file and converting it to syndication items
+fn bench_parse(nodes: usize, iterations: usize) -> Result<(), Box<dyn Error>> {
+    let shape = SyntheticCanvas::new(nodes).with_syndicated_every(2);
+    let json = shape.to_json()?;
+    let colors = ColorFilter::default();
+    let (median, items) = measure(iterations, || {
+        let (canvas, _) = parse_canvas(&json)?;
+        Ok(to_syndication_format(
+            &canvas,
+            Some(|node: &_, out: &OutAdjacencies, inc: &InAdjacencies| {
+                process_node_with_colors(node, out, inc, &colors)
+            }),
+        )?)
+    })?;
+    assert_eq!(items.len(), shape.syndicated(), "parse/{}: wrong number of items", nodes);
+    print(&format!("parse + to_syndication_format/{}", nodes), median, iterations);
+    Ok(())
+}
+
+/// Generating the jj sink's files, dry so no `jj` command runs
+fn bench_generate(nodes: usize, iterations: usize, dir: &Path) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(dir)?;
+    let items = to_syndication_format(&SyntheticCanvas::new(nodes).build()?, Some(default_process_node))?;
+    let mut sink = JjRepositorySink::new(dir, "main", "origin", "posts")?;
+    let (median, written) = measure(iterations, || {
+        sink.publish(&items, true)?;
+        Ok(sink.take_planned_writes().len())
+    })?;
+    assert_eq!(written, nodes, "generate/{}: wrong number of files", nodes);
+    print(&format!("jj content generation/{}", nodes), median, iterations);
+    Ok(())
+}
+
+/// Saving and loading a tracker
+fn bench_tracker(entries: usize, iterations: usize, dir: &Path) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(dir)?;
+    let canvas_path = dir.join("bench.canvas");
+    let node_ids: Vec<NodeId> = (0..entries).map(|i| SyntheticCanvas::node_id(i).parse()).collect::<Result<_, _>>()?;
+
+    let (save, ()) = measure(iterations, || {
+        let tracker_path = SyndicationTracker::new(&canvas_path, "bench")?.path().to_path_buf();
+        if tracker_path.exists() {
+            std::fs::remove_file(&tracker_path)?;
+        }
+        SyndicationTracker::new(&canvas_path, "bench")?.mark_published(&node_ids)?;
+        Ok(())
+    })?;
+    let (load, published) =
+        measure(iterations, || Ok(SyndicationTracker::new(&canvas_path, "bench")?.published().count()))?;
+    assert_eq!(published, entries, "tracker/{}: wrong number of entries", entries);
+    print(&format!("tracker save/{}", entries), save, iterations);
+    print(&format!("tracker load/{}", entries), load, iterations);
+    Ok(())
+}
+
+/// A first run over a whole canvas, then a run after one node was added
+fn bench_reprocess(nodes: usize, iterations: usize, dir: &Path) -> Result<(), Box<dyn Error>> {
+    let before = SyntheticCanvas::new(nodes).to_json()?;
+    let after = SyntheticCanvas::new(nodes + 1).to_json()?;
+    let clock = Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap()));
+
+    let mut full = Vec::new();
+    let mut incremental = Vec::new();
+    for iteration in 0..iterations.max(1) {
+        let run_dir = dir.join(iteration.to_string());
+        std::fs::create_dir_all(&run_dir)?;
+        let canvas_path = run_dir.join("bench.canvas");
+        std::fs::write(&canvas_path, &before)?;
+        let mut syndicator = syndicator(&canvas_path, run_dir.join("site"), clock.clone())?;
+
+        let (time, report) = measure(1, || Ok(syndicator.process_once()?))?;
+        assert_eq!(report.new_items.len(), nodes, "reprocess/{}: full run published the wrong items", nodes);
+        full.push(time);
+
+        std::fs::write(&canvas_path, &after)?;
+        let (time, report) = measure(1, || Ok(syndicator.process_once()?))?;
+        assert_eq!(report.new_items.len(), 1, "reprocess/{}: incremental run published the wrong items", nodes);
+        incremental.push(time);
+    }
+    print(&format!("full processing/{}", nodes), median(full), iterations);
+    print(&format!("incremental processing/{} + 1", nodes), median(incremental), iterations);
+    Ok(())
+}
+
+/// A run over a small canvas with a few nodes of several megabytes, which are skipped or
+/// truncated per `policy` while titles and slugs stay within their caps
+fn bench_huge_nodes(bytes: usize, policy: OversizePolicy, iterations: usize, dir: &Path) -> Result<(), Box<dyn Error>> {
+    let shape = SyntheticCanvas::new(10);
+    let mut builder = shape.builder();
+    for k in 0..HUGE_NODES {
+        builder = builder.text_node(SyntheticCanvas::node_id(shape.nodes + k), huge_text(bytes)).color(PresetColor::Red);
+    }
+    let json = serde_json::to_string(&builder.build()?)?;
+    let clock = Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap()));
+    let options = ProcessOptions { item_size: ItemSizeLimit::default().with_policy(policy), ..Default::default() };
+
+    let mut times = Vec::new();
+    for iteration in 0..iterations.max(1) {
+        let run_dir = dir.join(iteration.to_string());
+        std::fs::create_dir_all(&run_dir)?;
+        let canvas_path = run_dir.join("bench.canvas");
+        std::fs::write(&canvas_path, &json)?;
+        let site = run_dir.join("site");
+        let mut syndicator = syndicator_with(&canvas_path, site.clone(), clock.clone(), options.clone())?;
+
+        let (time, report) = measure(1, || Ok(syndicator.process_once()?))?;
+        times.push(time);
+
+        let (code, published) = match policy {
+            OversizePolicy::Skip => (codes::OVERSIZE_SKIPPED, shape.nodes),
+            OversizePolicy::Truncate => (codes::OVERSIZE_TRUNCATED, shape.nodes + HUGE_NODES),
+        };
+        assert_eq!(report.new_items.len(), published, "huge/{:?}: published the wrong items", policy);
+        let warned = report.all_warnings().filter(|w| w.code == code).count();
+        assert_eq!(warned, HUGE_NODES, "huge/{:?}: wrong number of `{}` warnings", policy, code);
+        for entry in std::fs::read_dir(&site)? {
+            check_post_caps(&entry?.path(), policy)?;
+        }
+    }
+    print(&format!("huge nodes ({:?})/{} x {} MB", policy, HUGE_NODES, bytes >> 20), median(times), iterations);
+    Ok(())
+}
+
+/// A pasted article: one word longer than any title or slug, then many paragraphs
+fn huge_text(bytes: usize) -> String {
+    let mut text = "x".repeat(bytes / 4);
+    while text.len() < bytes {
+        text.push_str("\n\nAnother paragraph of the pasted article, which goes on for a while longer.");
+    }
+    text
+}
+
+/// Check that a post's body, title and slug are within their limits
+fn check_post_caps(path: &Path, policy: OversizePolicy) -> Result<(), Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let title = contents.lines().find_map(|line| line.strip_prefix("title: \"")).unwrap_or_default();
+    assert!(title.chars().count() <= MAX_TITLE_CHARS + 1, "huge/{:?}: title of {} is too long", policy, name);
+    let slug = name.rsplit_once('-').map_or(name, |(slug, _)| slug);
+    assert!(slug.len() <= MAX_SLUG_BYTES, "huge/{:?}: slug of {} is too long", policy, name);
+    // Frontmatter of a node without neighbors is well under a kilobyte
+    assert!(contents.len() <= DEFAULT_MAX_ITEM_BYTES + 1024, "huge/{:?}: {} is too large", policy, name);
+    Ok(())
+}
+
+fn syndicator(canvas_path: &Path, site: PathBuf, clock: Arc<MockClock>) -> Result<Syndicator, Box<dyn Error>> {
+    syndicator_with(canvas_path, site, clock, ProcessOptions::default())
+}
+
+fn syndicator_with(
+    canvas_path: &Path,
+    site: PathBuf,
+    clock: Arc<MockClock>,
+    options: ProcessOptions,
+) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(canvas_path);
+    config.options = options;
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(site).with_clock(clock))?;
+    Ok(syndicator)
+}
@@ -0,0 +1,112 @@
+//! Adjacency check: join four notes with labelled and unlabelled edges, inspect what a
+//! custom filter is given for each, and syndicate only the notes that cite another.
+//!
+//! ```sh
+//! cargo run --example adjacencies   # or `just adjacencies`
+//! ```
+//!
+//! `SOURCE` cites `CITED` and also points at `OTHER`, which `DRAFT` points at too.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::jsoncanvas::node::GenericNodeInfo;
+use syndicate_json_canvas_lib::jsoncanvas::{JsonCanvas, Node};
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, InAdjacencies, OutAdjacencies, SyndicationFormat, default_process_node, to_syndication_format,
+};
+
+const SOURCE: (&str, &str) = ("ad00000000000001", "As argued elsewhere, canvases beat outlines.");
+const CITED: (&str, &str) = ("ad00000000000002", "Canvases beat outlines.");
+const OTHER: (&str, &str) = ("ad00000000000003", "Outlines have their uses.");
+const DRAFT: (&str, &str) = ("ad00000000000004", "Half a thought.");
+
+/// Label of the edges [`cites`] looks for
+const CITES: &str = "cites";
+
+/// What a filter was given for one node: its out-neighbors with their edges' labels, its
+/// in-neighbors, and whether every adjacency's node is the neighbor it names
+#[derive(Debug, Default)]
+struct Seen {
+    out: Vec<(String, Option<String>)>,
+    out_len: usize,
+    out_empty: bool,
+    inc: Vec<String>,
+    resolved: bool,
+    by_value: Vec<String>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let canvas = canvas()?;
+    let seen = RefCell::new(HashMap::new());
+    to_syndication_format(
+        &canvas,
+        Some(|node: &Node, out: &OutAdjacencies, inc: &InAdjacencies| {
+            seen.borrow_mut().insert(node.id().to_string(), observe(out, inc));
+            None
+        }),
+    )?;
+    let seen = seen.into_inner();
+    let of = |node_id: &str| seen.get(node_id).ok_or_else(|| format!("{} not seen: {:?}", node_id, seen));
+
+    let source = of(SOURCE.0)?;
+    let counted = source.out_len == 2 && !source.out_empty;
+    check("a node's out-neighbors are counted", counted, || format!("{:?}", source))?;
+    let cited = of(CITED.0)?;
+    let none = cited.out_len == 0 && cited.out_empty;
+    check("a node with no outgoing edges has none", none, || format!("{:?}", cited))?;
+    let resolved = seen.values().all(|seen| seen.resolved);
+    check("each adjacency's node is the neighbor it names", resolved, || format!("{:?}", seen))?;
+    let expected = [(CITED.0.to_string(), Some(CITES.to_string())), (OTHER.0.to_string(), None)];
+    check("adjacencies carry their edge's label, by edge ID", source.out == expected, || format!("{:?}", source))?;
+    let other = of(OTHER.0)?;
+    let mirrored = other.inc == [SOURCE.0, DRAFT.0] && cited.inc == [SOURCE.0];
+    check("in-neighbors mirror out-neighbors", mirrored, || format!("{:?} {:?}", other, cited))?;
+    let by_value = seen.values().all(|seen| seen.by_value.iter().eq(seen.out.iter().map(|(id, _)| id)));
+    check("iterating by value and by reference agree", by_value, || format!("{:?}", seen))?;
+
+    // A filter on edge labels, on top of the default one
+    let items = to_syndication_format(&canvas, Some(cites))?;
+    let ids: Vec<&str> = items.values().map(|item| item.id.as_str()).collect();
+    check("a filter on edge labels syndicates only notes citing another", ids == [SOURCE.0], || format!("{:?}", ids))?;
+    let linked = items.values().all(|item| item.out_neighbor_ids.len() == 2);
+    check("and their items still link every out-neighbor", linked, || format!("{:?}", items))?;
+    Ok(())
+}
+
+/// The four red notes with `SOURCE` citing `CITED`
+fn canvas() -> Result<JsonCanvas, Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (id, text) in [SOURCE, CITED, OTHER, DRAFT] {
+        builder = builder.text_node(id, text).color(PresetColor::Red);
+    }
+    Ok(builder.edge(SOURCE.0, CITED.0).label(CITES).edge(SOURCE.0, OTHER.0).edge(DRAFT.0, OTHER.0).build()?)
+}
+
+fn observe(out: &OutAdjacencies, inc: &InAdjacencies) -> Seen {
+    let resolved = out.iter().chain(inc).all(|adjacency| adjacency.neighbor.id() == &adjacency.neighbor_id);
+    Seen {
+        out: out.iter().map(|adjacency| (adjacency.neighbor_id.to_string(), adjacency.edge.label().cloned())).collect(),
+        out_len: out.len(),
+        out_empty: out.is_empty(),
+        inc: inc.into_iter().map(|adjacency| adjacency.neighbor_id.to_string()).collect(),
+        resolved,
+        by_value: out.clone().into_iter().map(|adjacency| adjacency.neighbor_id.to_string()).collect(),
+    }
+}
+
+/// The default filter, keeping only nodes with an outgoing edge labelled [`CITES`]
+fn cites(node: &Node, out: &OutAdjacencies, inc: &InAdjacencies) -> Option<SyndicationFormat> {
+    let citing = out.iter().any(|adjacency| adjacency.edge.label().is_some_and(|label| label == CITES));
+    if citing { default_process_node(node, out, inc) } else { None }
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
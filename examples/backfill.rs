@@ -0,0 +1,143 @@
+//! Backfill check: publish a backlog in four chunks, interrupt after the second, resume, and
+//! check every item was published exactly once.
+//!
+//! ```sh
+//! cargo run --example backfill   # exits non-zero if the resumed backfill goes wrong
+//! ```
+//!
+//! A generated canvas of eight red nodes is backfilled to a [`DirectorySink`] and a sink
+//! that fails on one item, two items per chunk, in a scratch directory. A third sink stands
+//! in for a social sink and must never be called. The first backfill is cancelled after two
+//! chunks; a new syndicator then resumes it from the saved progress.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::{
+    BackfillOptions, BackfillProgress, CancelToken, Config, OutboundQueue, ProcessReport, SinkError, SyndicationFormat,
+    SyndicationSink, Syndicator, SyntheticCanvas,
+};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+/// Items on the canvas
+const NODES: usize = 8;
+/// Items per chunk, so the backlog takes four chunks
+const CHUNK_SIZE: usize = 2;
+/// Chunk after which the first backfill is interrupted
+const INTERRUPT_AFTER: usize = 2;
+/// Item the failing sink refuses, in the second chunk
+const REFUSED: usize = 3;
+
+/// Fails every batch holding [`REFUSED`]
+struct Refusing;
+
+impl SyndicationSink for Refusing {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        if items.keys().any(|id| id.as_str() == SyntheticCanvas::node_id(REFUSED)) {
+            return Err(SinkError::Api("refused".into()));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "refusing"
+    }
+}
+
+/// Left out of the backfill, like a social sink
+struct LeftOut;
+
+impl SyndicationSink for LeftOut {
+    fn publish(&mut self, _items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        Err(SinkError::Api("a sink left out of the backfill was called".into()))
+    }
+
+    fn name(&self) -> &str {
+        "left-out"
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-backfill-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(scratch.join("site"))?;
+    let canvas_path = scratch.join("backlog.canvas");
+    std::fs::write(&canvas_path, SyntheticCanvas::new(NODES).to_json()?)?;
+
+    let result = run(&canvas_path, &scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(canvas_path: &Path, scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let options = BackfillOptions::default().with_chunk_size(CHUNK_SIZE).with_sinks(["directory", "refusing"]);
+
+    let mut syndicator = new_syndicator(canvas_path, scratch)?;
+    let plan = syndicator.plan_backfill(&options)?;
+    check("plan", plan.chunks(CHUNK_SIZE) == 4 && plan.pending.values().all(|n| *n == NODES))?;
+
+    let cancel = CancelToken::new();
+    let mut published: Vec<String> = Vec::new();
+    let mut chunks = 0;
+    let progress = syndicator.backfill_items(&options, &cancel, |report| {
+        chunks += 1;
+        published.extend(directory_items(report));
+        if chunks == INTERRUPT_AFTER {
+            cancel.cancel();
+        }
+    })?;
+    check("interrupted", !progress.finished && progress.chunks == INTERRUPT_AFTER)?;
+    check("progress saved", BackfillProgress::for_canvas(canvas_path)?.is_some_and(|p| p.chunks == INTERRUPT_AFTER))?;
+    println!("ok      interrupted after chunk {} of 4", INTERRUPT_AFTER);
+
+    // A new process picks up from the trackers and the saved progress
+    let mut syndicator = new_syndicator(canvas_path, scratch)?;
+    let plan = syndicator.plan_backfill(&options)?;
+    check("resumed plan", plan.resuming.is_some() && plan.pending.get("directory") == Some(&(NODES / 2)))?;
+    let progress = syndicator.backfill_items(&options, &CancelToken::new(), |report| {
+        published.extend(directory_items(report));
+    })?;
+    check("finished", progress.finished && progress.chunks == 4)?;
+    check("progress cleared", BackfillProgress::for_canvas(canvas_path)?.is_none())?;
+
+    let mut expected: Vec<String> = (0..NODES).map(SyntheticCanvas::node_id).collect();
+    expected.sort();
+    check("each item published once", published == expected)?;
+    let files = std::fs::read_dir(scratch.join("site"))?
+        .filter(|e| e.as_ref().is_ok_and(|e| e.path().is_file() && !e.file_name().to_string_lossy().starts_with('.')));
+    check("one post per item", files.count() == NODES)?;
+
+    let totals = progress.totals();
+    check("totals", totals.published == 2 * NODES - CHUNK_SIZE && totals.failed == CHUNK_SIZE)?;
+    let queue = OutboundQueue::for_canvas(canvas_path)?;
+    check("failures queued", queue.depth_by_sink().get("refusing") == Some(&CHUNK_SIZE))?;
+    let left_out = syndicator.tracker("left-out").ok_or("no left-out sink")?;
+    check("sink left out", expected.iter().all(|id| id.parse().is_ok_and(|id| !left_out.is_published(&id))))?;
+    println!("ok      resumed and finished without duplicates");
+    Ok(())
+}
+
+fn new_syndicator(canvas_path: &Path, scratch: &Path) -> Result<Syndicator, Box<dyn Error>> {
+    let mut syndicator = Syndicator::new(Config::new(canvas_path))?;
+    syndicator.add_sink(DirectorySink::new(scratch.join("site")))?;
+    syndicator.add_sink(Refusing)?;
+    syndicator.add_sink(LeftOut)?;
+    Ok(syndicator)
+}
+
+/// Items the directory sink took in a chunk
+fn directory_items(report: &ProcessReport) -> Vec<String> {
+    report.sinks.iter().filter(|o| o.sink == "directory" && o.error.is_none()).flat_map(|o| o.items.clone()).collect()
+}
+
+fn check(name: &str, ok: bool) -> Result<(), Box<dyn Error>> {
+    if ok {
+        return Ok(());
+    }
+    println!("FAILED  {}", name);
+    Err(format!("backfill check `{}` failed", name).into())
+}
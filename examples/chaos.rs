@@ -0,0 +1,198 @@
+//! Chaos check: publish a growing canvas over several runs to sinks that fail, hang and
+//! misreport their items, then let the failures stop, and check nothing went wrong.
+//!
+//! ```sh
+//! cargo run --example chaos   # exits non-zero if an invariant breaks
+//! ```
+//!
+//! Two transactional in-memory sinks, each wrapped in a [`ChaosSink`], are run through the
+//! scenario once per seed, publishing directly and through the outbound queue. After every
+//! run:
+//!
+//! - no item was committed to a sink twice
+//! - every committed item is in the sink's tracker on disk
+//! - no post URL was recorded for a node the batch didn't hold
+//!
+//! Once the failures stop, every item must reach every sink and the queue must run empty.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::{
+    ChaosSink, Config, DeliveryOptions, SinkError, SinkOutcome, SyndicationFormat, SyndicationSink,
+    SyndicationTracker, Syndicator, SyntheticCanvas,
+};
+
+/// Seeds the scenarios run with
+const SEEDS: std::ops::RangeInclusive<u64> = 1..=6;
+/// Runs with failures injected
+const CHAOTIC_RUNS: usize = 5;
+/// Nodes on the canvas at the first run; each run adds [`NODES_PER_RUN`]
+const FIRST_NODES: usize = 6;
+const NODES_PER_RUN: usize = 3;
+/// Runs without failures the sinks get to catch up
+const CALM_RUNS: usize = 4;
+const SINKS: &[&str] = &["alpha", "beta"];
+
+/// Items a sink committed, by node ID, shared across runs like a real destination
+type Ledger = Arc<Mutex<HashMap<String, usize>>>;
+
+/// Stages items in a batch and only records them on commit
+struct Recording {
+    name: &'static str,
+    ledger: Ledger,
+    staged: Vec<NodeId>,
+    committed: Vec<NodeId>,
+}
+
+impl SyndicationSink for Recording {
+    fn begin(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        self.staged.clear();
+        Ok(())
+    }
+
+    fn commit(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        if !dry_run {
+            let mut ledger = self.ledger.lock().unwrap();
+            for node_id in &self.staged {
+                *ledger.entry(node_id.to_string()).or_default() += 1;
+            }
+            self.committed.append(&mut self.staged);
+        }
+        Ok(())
+    }
+
+    fn abort(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        self.staged.clear();
+        Ok(())
+    }
+
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        self.staged.extend(items.keys().cloned());
+        Ok(())
+    }
+
+    fn take_post_urls(&mut self) -> Vec<(NodeId, String)> {
+        let name = self.name;
+        self.committed.drain(..).map(|id| (id.clone(), format!("https://{}.example/{}", name, id))).collect()
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Direct,
+    Queued,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    for mode in [Mode::Direct, Mode::Queued] {
+        for seed in SEEDS {
+            let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-chaos-{}", std::process::id()));
+            if scratch.exists() {
+                std::fs::remove_dir_all(&scratch)?;
+            }
+            std::fs::create_dir_all(&scratch)?;
+            let result = scenario(&scratch.join("chaos.canvas"), mode, seed);
+            std::fs::remove_dir_all(&scratch)?;
+            result.map_err(|e| format!("{:?} mode, seed {}: {}", mode, seed, e))?;
+        }
+        println!("ok      {:?} mode, seeds {} to {}", mode, SEEDS.start(), SEEDS.end());
+    }
+    Ok(())
+}
+
+fn scenario(canvas_path: &Path, mode: Mode, seed: u64) -> Result<(), Box<dyn Error>> {
+    let ledgers: Vec<Ledger> = SINKS.iter().map(|_| Ledger::default()).collect();
+
+    // One syndicator through the chaotic runs, like a watcher, so the faults play out over time
+    let mut nodes = FIRST_NODES;
+    std::fs::write(canvas_path, SyntheticCanvas::new(nodes).to_json()?)?;
+    let mut syndicator = new_syndicator(canvas_path, mode, &ledgers, Some(seed))?;
+    for run in 0..CHAOTIC_RUNS {
+        std::fs::write(canvas_path, SyntheticCanvas::new(nodes).to_json()?)?;
+        let mut outcomes = syndicator.process_once()?.sinks;
+        if let Mode::Queued = mode {
+            outcomes.extend(syndicator.drain(true)?);
+        }
+        check_invariants(canvas_path, &ledgers, &outcomes).map_err(|e| format!("run {}: {}", run + 1, e))?;
+        nodes += NODES_PER_RUN;
+    }
+
+    // Failures stop: every item reaches every sink and the queue runs empty
+    let expected: Vec<String> = (0..nodes - NODES_PER_RUN).map(SyntheticCanvas::node_id).collect();
+    let mut syndicator = new_syndicator(canvas_path, mode, &ledgers, None)?;
+    for _ in 0..CALM_RUNS {
+        let mut outcomes = syndicator.process_once()?.sinks;
+        if let Mode::Queued = mode {
+            outcomes.extend(syndicator.drain(true)?);
+        }
+        check_invariants(canvas_path, &ledgers, &outcomes).map_err(|e| format!("calm run: {}", e))?;
+    }
+    for (sink, ledger) in SINKS.iter().zip(&ledgers) {
+        let ledger = ledger.lock().unwrap();
+        if let Some(missing) = expected.iter().find(|id| ledger.get(*id) != Some(&1)) {
+            return Err(format!("{} never got {} once the failures stopped", sink, missing).into());
+        }
+    }
+    let queue = syndicator.queue()?;
+    if !queue.is_empty() {
+        return Err(format!("{} items still queued once the failures stopped", queue.len()).into());
+    }
+    Ok(())
+}
+
+fn new_syndicator(
+    canvas_path: &Path,
+    mode: Mode,
+    ledgers: &[Ledger],
+    seed: Option<u64>,
+) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(canvas_path);
+    if let Mode::Queued = mode {
+        config.options.queue = Some(DeliveryOptions::default());
+    }
+    let mut syndicator = Syndicator::new(config)?;
+    for (i, (name, ledger)) in SINKS.iter().zip(ledgers).enumerate() {
+        let sink = Recording { name, ledger: ledger.clone(), staged: Vec::new(), committed: Vec::new() };
+        match seed {
+            Some(seed) => syndicator.add_sink(
+                ChaosSink::new(sink, seed + i as u64)
+                    .with_fail_first_batches(1)
+                    .with_fail_every_nth_item(5)
+                    .with_fail_rate(0.3)
+                    .with_hang(0.1, Duration::from_millis(5))
+                    .with_wrong_ids_rate(0.3),
+            )?,
+            None => syndicator.add_sink(sink)?,
+        };
+    }
+    Ok(syndicator)
+}
+
+fn check_invariants(canvas_path: &Path, ledgers: &[Ledger], outcomes: &[SinkOutcome]) -> Result<(), Box<dyn Error>> {
+    for (sink, ledger) in SINKS.iter().zip(ledgers) {
+        let tracker = SyndicationTracker::new(canvas_path, sink)?;
+        for (node_id, count) in ledger.lock().unwrap().iter() {
+            if *count > 1 {
+                return Err(format!("{} got {} {} times", sink, node_id, count).into());
+            }
+            if !tracker.is_published(&node_id.parse()?) {
+                return Err(format!("{} took {} but its tracker lost it", sink, node_id).into());
+            }
+        }
+    }
+    for outcome in outcomes {
+        if let Some(node_id) = outcome.urls.keys().find(|id| !outcome.items.contains(id)) {
+            return Err(format!("{} recorded a URL for {}, which wasn't in the batch", outcome.sink, node_id).into());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,148 @@
+//! Clock check: hold a note in a batch window with the clock pinned, advance it past the
+//! deadline, write the post again a week later, and check every timestamp the tool writes
+//! follows the pinned clock rather than the real one.
+//!
+//! ```sh
+//! cargo run --example clock   # or `just clock`
+//! ```
+//!
+//! The clock starts half an hour before midnight, so a post dated by the run that finds
+//! the note rather than the one that publishes it would show the wrong day.
+
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    BatchPolicy, CanvasBuilder, Clock, Config, HttpClient, HttpError, HttpRequest, HttpResponse, MockClock,
+    NetworkPolicy, PoliteClient, SentKeys, SyndicationSink, Syndicator, default_process_node, to_syndication_format,
+};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTE: &str = "c10c000000000001";
+
+/// Throttles the first request until a second past the pinned time, then lets it through
+#[derive(Debug)]
+struct Throttling {
+    until: DateTime<Local>,
+    requests: AtomicUsize,
+}
+
+impl HttpClient for Throttling {
+    fn send(&self, _request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        if self.requests.fetch_add(1, Ordering::SeqCst) > 0 {
+            return Ok(HttpResponse { status: 200, headers: Vec::new(), body: String::new() });
+        }
+        let headers = vec![("Retry-After".to_string(), self.until.to_rfc2822())];
+        Ok(HttpResponse { status: 429, headers, body: String::new() })
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-clock-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let canvas_path = scratch.join("clock.canvas");
+    let site = scratch.join("site");
+    let canvas = CanvasBuilder::new().text_node(NOTE, "Held for a while.").color(PresetColor::Red).build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+    let start = Local.with_ymd_and_hms(2026, 3, 1, 23, 30, 0).unwrap();
+    let clock = Arc::new(MockClock::new(start));
+    let mut config = Config::new(&canvas_path);
+    config.options.clock = clock.clone();
+    config.options.batching = BatchPolicy::Window(Duration::from_secs(2 * 60 * 60));
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(&site).with_clock(clock.clone()))?;
+
+    // Held until the window closes, then published on the first run past it
+    let report = syndicator.process_once()?;
+    let held = report.batched == [NOTE] && list(&site).is_empty();
+    check("a new note is held while its window is open", held, || format!("{:?}", report.batched))?;
+    let stamped = report.started_at == start;
+    check("the run is stamped with the pinned time", stamped, || report.started_at.to_string())?;
+    clock.advance(chrono::Duration::hours(1));
+    let report = syndicator.process_once()?;
+    let still = report.batched == [NOTE] && list(&site).is_empty();
+    check("and still held an hour in", still, || format!("{:?}", report.batched))?;
+    clock.advance(chrono::Duration::hours(1));
+    let report = syndicator.process_once()?;
+    let published = report.new_items == [NOTE] && report.batched.is_empty() && list(&site).len() == 1;
+    check("past its deadline it's published", published, || format!("{:?}", list(&site)))?;
+    let date = post_date(&site)?;
+    check("dated the day it was published, not found", date == "2026-03-02", || date.clone())?;
+
+    // Written again a week later, the post keeps its first date
+    clock.advance(chrono::Duration::days(7));
+    let items = to_syndication_format(&canvas, Some(default_process_node))?;
+    let mut sink = DirectorySink::new(&site).with_clock(clock.clone());
+    sink.publish(&items, false)?;
+    let date = post_date(&site)?;
+    let kept = date == "2026-03-02" && list(&site).len() == 1;
+    check("a post written again a week later keeps its date", kept, || format!("{} {:?}", date, list(&site)))?;
+
+    // Everything else stamped with the time follows the clock too
+    let now = clock.now().with_timezone(&Utc);
+    let bundle = syndicator.export_state(&scratch.join("state.json"))?;
+    let created = bundle.created_at == now;
+    check("an exported state bundle is stamped with the pinned time", created, || bundle.created_at.to_string())?;
+    let node_id: NodeId = NOTE.parse()?;
+    let mut sent = SentKeys::load(scratch.join("sent-keys.json"))?.with_clock(clock.clone());
+    sent.begin_request("key", &node_id)?;
+    let logged = sent.get("key").and_then(|entry| entry.updated_at);
+    check("sent keys are logged at the pinned time", logged == Some(now), || format!("{:?}", logged))?;
+
+    // A `Retry-After` date is counted from the pinned time, which the real clock has long passed
+    let until = clock.now() + chrono::Duration::seconds(90);
+    let headers = vec![("Retry-After".to_string(), until.to_rfc2822())];
+    let response = HttpResponse { status: 429, headers, body: String::new() };
+    let wait = response.retry_after(now);
+    let counted = wait == Some(Duration::from_secs(90));
+    check("a Retry-After date is counted from the pinned time", counted, || format!("{:?}", wait))?;
+    let throttling = Throttling { until: clock.now() + chrono::Duration::seconds(1), requests: AtomicUsize::new(0) };
+    let client = PoliteClient::new(throttling, NetworkPolicy::default()).with_clock(clock.clone());
+    let started = Instant::now();
+    let response = client.send(&HttpRequest::get("https://example.com/"))?;
+    let elapsed = started.elapsed();
+    let waited = response.status == 200 && elapsed >= Duration::from_secs(1);
+    check("a throttled request waits out that date before it's retried", waited, || format!("{:?}", elapsed))?;
+    Ok(())
+}
+
+/// The `date` frontmatter of the only post in `site`
+fn post_date(site: &Path) -> Result<String, Box<dyn Error>> {
+    let name = list(site).into_iter().next().ok_or("no post")?;
+    let contents = std::fs::read_to_string(site.join(name))?;
+    let date = contents.lines().find_map(|line| line.strip_prefix("date: ")).ok_or("no date")?;
+    Ok(date.to_string())
+}
+
+/// Names of the files in a folder, hidden manifests left out
+fn list(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            let names = entries.flatten().map(|entry| entry.file_name().to_string_lossy().into_owned());
+            names.filter(|name| !name.starts_with('.')).collect()
+        })
+        .unwrap_or_default()
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,136 @@
+//! Dangling edge check: process a canvas with an edge pointing at a deleted node, strictly and
+//! leniently, and watch it while the pipeline refuses it until it's fixed.
+//!
+//! ```sh
+//! cargo run --example dangling_edges   # or `just dangling-edges`
+//! ```
+//!
+//! Obsidian never writes such an edge itself, so the canvas is built with a valid edge and the
+//! dangling one is added to its JSON by hand.
+
+use std::error::Error;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use syndicate_json_canvas_lib::jsoncanvas::JsonCanvas;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, CanvasGraphError, Config, DanglingEdges, EdgeDirection, SyndicationError, Syndicator, WatchCommand,
+    default_process_node, to_syndication_format, to_syndication_format_lenient, warnings::codes,
+};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTE: (&str, &str) = ("da00000000000001", "A thought that leads somewhere.");
+const NEXT: (&str, &str) = ("da00000000000002", "Where it leads.");
+const GONE: &str = "da000000000000ff";
+const DANGLING: &str = "da000000000000e2";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-dangling-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let broken: JsonCanvas = canvas_json(true)?.parse()?;
+
+    // Strict: the first dangling edge is the error
+    let strict = to_syndication_format(&broken, Some(default_process_node));
+    let named = matches!(&strict, Err(CanvasGraphError::DanglingEdge { edge_id, missing_node })
+        if edge_id.as_str() == DANGLING && missing_node.as_str() == GONE);
+    check("strict conversion names the edge and the missing node", named, || format!("{:?}", strict))?;
+    let fixed: JsonCanvas = canvas_json(false)?.parse()?;
+    let whole = to_syndication_format(&fixed, Some(default_process_node)).is_ok_and(|items| items.len() == 2);
+    check("a canvas without dangling edges converts", whole, String::new)?;
+
+    // Lenient: the items, without the dangling edge
+    let (items, dangling) = to_syndication_format_lenient(&broken, Some(default_process_node), EdgeDirection::Drawn);
+    let reported = dangling.len() == 1 && dangling[0].edge_id().as_str() == DANGLING;
+    check("lenient conversion returns the dangling edge", reported, || format!("{:?}", dangling))?;
+    let note = items.values().find(|item| item.id.as_str() == NOTE.0);
+    let neighbors = note.is_some_and(|item| {
+        item.out_neighbor_ids.iter().map(|id| id.as_str()).eq([NEXT.0]) && item.in_neighbor_ids.is_empty()
+    });
+    check("and keeps the valid edges only", items.len() == 2 && neighbors, || format!("{:?}", note))?;
+
+    // Skipped by the pipeline, with a warning
+    let canvas_path = scratch.join("skip/dangling.canvas");
+    let report = watched_syndicator(&canvas_path, DanglingEdges::Skip)?.process_once()?;
+    let published: Vec<&String> = report.sinks.iter().flat_map(|s| &s.items).collect();
+    check("skipping still publishes every node", published.len() == 2, || format!("{:?}", report))?;
+    let warned = report.warnings.iter().any(|w| w.code == codes::DANGLING_EDGE && w.message.contains(DANGLING));
+    check("with a dangling-edge warning", warned, || format!("{:?}", report.warnings))?;
+
+    // Failing: the run is refused, the watcher survives it and picks up the fix
+    let canvas_path = scratch.join("fail/dangling.canvas");
+    let mut syndicator = watched_syndicator(&canvas_path, DanglingEdges::Fail)?;
+    let site = canvas_path.with_file_name("site");
+    let result = syndicator.process_once();
+    let refused = matches!(&result, Err(SyndicationError::Process(e)) if e.contains(DANGLING)) && !site.exists();
+    check("failing refuses the run", refused, || format!("{:?}", result))?;
+    let (commands, commands_rx) = mpsc::channel();
+    let watcher = std::thread::spawn(move || syndicator.watch(commands_rx).map_err(|e| e.to_string()));
+    std::thread::sleep(Duration::from_millis(300));
+    let alive = !watcher.is_finished() && !site.exists();
+    check("the watcher keeps watching a refused canvas", alive, String::new)?;
+    std::fs::write(&canvas_path, canvas_json(false)?)?;
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while posts(&site) < 2 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let published = posts(&site);
+    check("and publishes once the canvas is fixed", published == 2, || published.to_string())?;
+    commands.send(WatchCommand::Shutdown)?;
+    watcher.join().map_err(|_| "the watcher panicked")??;
+    Ok(())
+}
+
+/// The two red notes joined by an edge, plus an edge from a deleted node if `dangling`
+fn canvas_json(dangling: bool) -> Result<String, Box<dyn Error>> {
+    let canvas = CanvasBuilder::new()
+        .text_node(NOTE.0, NOTE.1)
+        .color(PresetColor::Red)
+        .text_node(NEXT.0, NEXT.1)
+        .color(PresetColor::Red)
+        .edge(NOTE.0, NEXT.0)
+        .build()?;
+    let mut json = serde_json::to_value(&canvas)?;
+    if dangling {
+        let edge = serde_json::json!({ "id": DANGLING, "fromNode": GONE, "toNode": NOTE.0 });
+        json["edges"].as_array_mut().ok_or("no edges in the canvas")?.push(edge);
+    }
+    Ok(serde_json::to_string(&json)?)
+}
+
+/// A syndicator for a broken canvas at `canvas_path`, publishing to a directory next to it
+fn watched_syndicator(canvas_path: &Path, dangling_edges: DanglingEdges) -> Result<Syndicator, Box<dyn Error>> {
+    let dir = canvas_path.parent().ok_or("no parent")?;
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(canvas_path, canvas_json(true)?)?;
+    let mut config = Config::new(canvas_path);
+    config.debounce = Duration::from_millis(20);
+    config.options.dangling_edges = dangling_edges;
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator)
+}
+
+/// Posts in the site folder, hidden manifests left out
+fn posts(site: &Path) -> usize {
+    let visible = |entry: &std::fs::DirEntry| !entry.file_name().to_string_lossy().starts_with('.');
+    std::fs::read_dir(site).map_or(0, |entries| entries.flatten().filter(visible).count())
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
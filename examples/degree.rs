@@ -0,0 +1,89 @@
+//! Degree filter check: on a diamond of notes with a shortcut across it and an isolated note
+//! beside it, check which notes each degree filter selects, alone and with a color filter.
+//!
+//! ```sh
+//! cargo run --example degree   # or `just degree`
+//! ```
+//!
+//! `TOP` points at `LEFT` and `RIGHT`, which both point at `BOTTOM`, and `TOP` also points
+//! at `BOTTOM` directly. `ALONE` has no edges. Every note is red but `RIGHT`, which is cyan.
+
+use std::error::Error;
+
+use syndicate_json_canvas_lib::jsoncanvas::JsonCanvas;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, NodeFilter, has_color, is_isolated, is_leaf, is_root, max_degree, min_degree,
+    process_nodes_matching, to_syndication_format,
+};
+
+const TOP: &str = "de90000000000001";
+const LEFT: &str = "de90000000000002";
+const RIGHT: &str = "de90000000000003";
+const BOTTOM: &str = "de90000000000004";
+const ALONE: &str = "de90000000000005";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let canvas = canvas()?;
+
+    let leaves = selected(&canvas, is_leaf)?;
+    check("leaves are the notes with no outgoing edges", leaves == [BOTTOM, ALONE], || format!("{:?}", leaves))?;
+    let roots = selected(&canvas, is_root)?;
+    check("roots are the notes with no incoming edges", roots == [TOP, ALONE], || format!("{:?}", roots))?;
+    let isolated = selected(&canvas, is_isolated)?;
+    check("an isolated note has no edges at all", isolated == [ALONE], || format!("{:?}", isolated))?;
+    let connected = selected(&canvas, is_isolated.not())?;
+    check("leaving it out keeps the rest", connected == [TOP, LEFT, RIGHT, BOTTOM], || format!("{:?}", connected))?;
+
+    // Degrees count incoming and outgoing edges together
+    let hubs = selected(&canvas, min_degree(3))?;
+    check("min_degree counts edges both ways", hubs == [TOP, BOTTOM], || format!("{:?}", hubs))?;
+    let sparse = selected(&canvas, max_degree(2))?;
+    check("max_degree too", sparse == [LEFT, RIGHT, ALONE], || format!("{:?}", sparse))?;
+    let exactly = selected(&canvas, min_degree(2).and(max_degree(2)))?;
+    check("the two bound a range", exactly == [LEFT, RIGHT], || format!("{:?}", exactly))?;
+
+    // With colors
+    let red = has_color(PresetColor::Red);
+    let terminal = selected(&canvas, red.clone().and(is_leaf).and(is_isolated.not()))?;
+    check("red terminal thoughts are the leaves with edges", terminal == [BOTTOM], || format!("{:?}", terminal))?;
+    let middle = selected(&canvas, red.and(is_leaf.not()).and(is_root.not()))?;
+    check("colors narrow the other filters", middle == [LEFT], || format!("{:?}", middle))?;
+    Ok(())
+}
+
+/// The diamond, its shortcut and the isolated note, all red but `RIGHT`
+fn canvas() -> Result<JsonCanvas, Box<dyn Error>> {
+    Ok(CanvasBuilder::new()
+        .text_node(TOP, "Where it starts.")
+        .color(PresetColor::Red)
+        .text_node(LEFT, "One way down.")
+        .color(PresetColor::Red)
+        .text_node(RIGHT, "The other way down.")
+        .color(PresetColor::Cyan)
+        .text_node(BOTTOM, "Where it ends up.")
+        .color(PresetColor::Red)
+        .text_node(ALONE, "Off on its own.")
+        .color(PresetColor::Red)
+        .edge(TOP, LEFT)
+        .edge(TOP, RIGHT)
+        .edge(LEFT, BOTTOM)
+        .edge(RIGHT, BOTTOM)
+        .edge(TOP, BOTTOM)
+        .build()?)
+}
+
+/// The notes syndicated with `filter`, in the order they're declared
+fn selected(canvas: &JsonCanvas, filter: impl NodeFilter) -> Result<Vec<&'static str>, Box<dyn Error>> {
+    let items = to_syndication_format(canvas, Some(process_nodes_matching(filter)))?;
+    let ids: Vec<String> = items.keys().map(|id| id.to_string()).collect();
+    Ok([TOP, LEFT, RIGHT, BOTTOM, ALONE].into_iter().filter(|id| ids.iter().any(|own| own == id)).collect())
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,177 @@
+//! Duplicate detection check: publish a canvas holding an exact copy, a reworked note about
+//! 90% like its original and a note sharing only its opening, under each duplicate policy,
+//! and check which notes are grouped, published and linked to.
+//!
+//! ```sh
+//! cargo run --example duplicates   # or `just duplicates`
+//! ```
+//!
+//! `REPLY` points at `COPY`, so a suppressed copy left in its neighbors' links would show
+//! up in the reply's post.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::{JsonCanvas, NodeId};
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::warnings::codes;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, DuplicateOptions, DuplicatePolicy, ProcessReport, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const ORIGINAL: &str = "d0b0000000000001";
+const COPY: &str = "d0b0000000000002";
+const DRAFT: &str = "d0b0000000000003";
+const REWORKED: &str = "d0b0000000000004";
+const DISTINCT: &str = "d0b0000000000005";
+const REPLY: &str = "d0b0000000000006";
+
+const COPIED: &str = "Small tools that do one thing well compose better than large ones.";
+/// With `CLOSING`, 22 words; the draft changes the last, so 20 of their 22 word pairs are shared
+const OPENING: &str = "Writing in public every day is the surest way I know to find out what I actually think";
+const CLOSING: &str = "about anything worth thinking";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-duplicates-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    // Warned about, all published
+    let (report, site) = publish(&scratch.join("warn"), DuplicatePolicy::Warn)?;
+    let groups: Vec<(Vec<String>, bool)> = report
+        .duplicate_groups
+        .iter()
+        .map(|group| (group.node_ids.iter().map(NodeId::to_string).collect(), group.exact))
+        .collect();
+    let grouped = groups == [(ids(&[ORIGINAL, COPY]), true), (ids(&[DRAFT, REWORKED]), false)];
+    check("an exact copy and a 90% similar note are grouped with their first", grouped, || format!("{:?}", groups))?;
+    let apart = !report.duplicate_groups.iter().any(|group| group.node_ids.iter().any(|id| id.as_str() == DISTINCT));
+    check("a note sharing only its opening is below the threshold", apart, || format!("{:?}", groups))?;
+    let warned = warning(&report, COPY) == Some(format!("Duplicate of `{}`", ORIGINAL))
+        && warning(&report, REWORKED) == Some(format!("Near-duplicate of `{}`", DRAFT));
+    check("each later member is warned about, naming the first", warned, || format!("{:?}", report.warnings))?;
+    let all = list(&site).len() == 6 && read_post(&site, REPLY).is_some_and(|post| post.contains(COPY));
+    check("under `warn` every note is published", all, || format!("{:?}", list(&site)))?;
+
+    // Skipping one side of each group
+    let (_, site) = publish(&scratch.join("skip-newer"), DuplicatePolicy::SkipNewer)?;
+    let kept = published(&site) == ids(&[ORIGINAL, DRAFT, DISTINCT, REPLY]);
+    check("`skip_newer` publishes only the first of each group", kept, || format!("{:?}", list(&site)))?;
+    let reply = read_post(&site, REPLY).unwrap_or_default();
+    check("and neighbors don't link to the skipped copy", !reply.contains(COPY), || reply.clone())?;
+    let (_, site) = publish(&scratch.join("skip-older"), DuplicatePolicy::SkipOlder)?;
+    let kept = published(&site) == ids(&[COPY, REWORKED, DISTINCT, REPLY]);
+    check("`skip_older` publishes only the last", kept, || format!("{:?}", list(&site)))?;
+
+    // A published note counts as the older one, whatever its ID
+    let dir = scratch.join("published");
+    std::fs::create_dir_all(&dir)?;
+    let canvas_path = dir.join("notes.canvas");
+    let canvas = CanvasBuilder::new().text_node(COPY, COPIED).color(PresetColor::Red).build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+    let mut syndicator = syndicator(&dir, DuplicatePolicy::SkipNewer)?;
+    syndicator.process_once()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas_of(&[(ORIGINAL, COPIED), (COPY, COPIED)])?)?)?;
+    syndicator.process_once()?;
+    let first = published(&dir.join("site")) == ids(&[COPY]);
+    check("a copy of a published note is skipped even with a lower ID", first, || format!("{:?}", list(&dir)))?;
+
+    // Failing the run
+    let result = publish(&scratch.join("fail"), DuplicatePolicy::Fail);
+    let error = result.as_ref().err().map(|e| e.to_string()).unwrap_or_default();
+    let failed = error.contains("Duplicate items found") && list(&scratch.join("fail").join("site")).is_empty();
+    check("`fail` stops the run before anything is published", failed, || format!("{:?}", result.map(|r| r.1)))?;
+    Ok(())
+}
+
+/// Publish the six notes from `dir` to `dir/site` under `policy`, returning the report and the site
+fn publish(dir: &Path, policy: DuplicatePolicy) -> Result<(ProcessReport, PathBuf), Box<dyn Error>> {
+    std::fs::create_dir_all(dir)?;
+    let reworked = format!("{} {}", OPENING, CLOSING);
+    let drafted = format!("{} {}", OPENING, CLOSING.replace("worth thinking", "worth saying"));
+    let distinct = "Writing in public every day is a habit I picked up from a friend who keeps a garden log.";
+    let notes = [
+        (ORIGINAL, COPIED),
+        (COPY, COPIED),
+        (DRAFT, drafted.as_str()),
+        (REWORKED, reworked.as_str()),
+        (DISTINCT, distinct),
+        (REPLY, "Replying to the note on small tools."),
+    ];
+    let canvas = canvas_of(&notes)?;
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+    let report = syndicator(dir, policy)?.process_once()?;
+    Ok((report, dir.join("site")))
+}
+
+/// The red notes, with `REPLY` pointing at `COPY` if both are there
+fn canvas_of(notes: &[(&str, &str)]) -> Result<JsonCanvas, Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (id, text) in notes {
+        builder = builder.text_node(*id, *text).color(PresetColor::Red);
+    }
+    if notes.iter().any(|(id, _)| *id == REPLY) {
+        builder = builder.edge(REPLY, COPY);
+    }
+    Ok(builder.build()?)
+}
+
+fn syndicator(dir: &Path, policy: DuplicatePolicy) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.duplicates = Some(DuplicateOptions { policy, ..DuplicateOptions::default() });
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator)
+}
+
+/// The message of the `duplicate` warning for `node_id`
+fn warning(report: &ProcessReport, node_id: &str) -> Option<String> {
+    report
+        .warnings
+        .iter()
+        .find(|w| w.code == codes::DUPLICATE && w.node_id.as_deref() == Some(node_id))
+        .map(|w| w.message.clone())
+}
+
+/// The node IDs of the posts in `site`, sorted
+fn published(site: &Path) -> Vec<String> {
+    let names = list(site);
+    let mut ids: Vec<String> =
+        names.iter().filter_map(|name| name.strip_suffix(".md")?.rsplit('-').next()).map(str::to_string).collect();
+    ids.sort();
+    ids
+}
+
+fn ids(node_ids: &[&str]) -> Vec<String> {
+    node_ids.iter().map(|id| id.to_string()).collect()
+}
+
+/// The published post of a node
+fn read_post(site: &Path, node_id: &str) -> Option<String> {
+    let name = list(site).into_iter().find(|name| name.contains(node_id))?;
+    std::fs::read_to_string(site.join(name)).ok()
+}
+
+/// Names of the files in a folder, hidden manifests left out
+fn list(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            let names = entries.flatten().map(|entry| entry.file_name().to_string_lossy().into_owned());
+            names.filter(|name| !name.starts_with('.')).collect()
+        })
+        .unwrap_or_default()
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Edge arrow check: join two notes with edges of each arrow combination, and check which
+//! note each one counts as pointing at, as drawn and along the arrows.
+//!
+//! ```sh
+//! cargo run --example edge_arrows   # or `just edge-arrows`
+//! ```
+//!
+//! Every edge is drawn from the first note to the second; only its arrows change.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::jsoncanvas::{JsonCanvas, NodeId};
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, EdgeDirection, SyndicationFormat, Syndicator, default_process_node,
+    to_syndication_format, to_syndication_format_with,
+};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const FIRST: (&str, &str) = ("ae00000000000001", "Where the edge is drawn from.");
+const SECOND: (&str, &str) = ("ae00000000000002", "Where the edge is drawn to.");
+
+/// Whether the first note points at the second, and the second at the first
+type Pointing = (bool, bool);
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-edge-arrows-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    // Along the arrows, each combination of `fromEnd` and `toEnd`
+    let cases = [
+        ("an arrow on the `to` end points as drawn", Some((false, true)), (true, false)),
+        ("an arrow on the `from` end only reverses the edge", Some((true, false)), (false, true)),
+        ("arrows on both ends point both ways", Some((true, true)), (true, true)),
+        ("an edge with no arrows points as drawn", Some((false, false)), (true, false)),
+        ("an edge without ends set has the spec's arrow on `to`", None, (true, false)),
+    ];
+    for (name, arrows, expected) in cases {
+        let found = pointing(arrows, EdgeDirection::Arrows)?;
+        check(name, found == expected, || format!("{:?}", found))?;
+    }
+
+    // As drawn, arrows change nothing
+    let drawn = pointing(Some((true, false)), EdgeDirection::Drawn)?;
+    check("as drawn, a reversed arrow is ignored", drawn == (true, false), || format!("{:?}", drawn))?;
+    let canvas = canvas(Some((true, false)))?;
+    let default = to_syndication_format(&canvas, Some(default_process_node))?;
+    let unchanged = of(&default, FIRST.0)?.out_neighbor_ids.iter().any(|id| id.as_str() == SECOND.0);
+    check("edges point as drawn by default", unchanged, || format!("{:?}", default))?;
+
+    // Published along the arrows, the reversed edge's links swap sides
+    let canvas_path = scratch.join("arrows.canvas");
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+    let mut config = Config::new(&canvas_path);
+    config.options.edge_direction = EdgeDirection::Arrows;
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(scratch.join("site")))?;
+    syndicator.process_once()?;
+    let first = post(scratch, FIRST.0)?;
+    let second = post(scratch, SECOND.0)?;
+    let swapped = frontmatter_links(&first, "context_for_this", SECOND.0)
+        && frontmatter_links(&second, "further_thinking", FIRST.0)
+        && !frontmatter_links(&first, "further_thinking", SECOND.0);
+    check("published posts link along the arrows", swapped, || format!("{}\n{}", first, second))?;
+    Ok(())
+}
+
+/// The two red notes with one edge from the first to the second, with `arrows` on its
+/// `(from, to)` ends if set
+fn canvas(arrows: Option<(bool, bool)>) -> Result<JsonCanvas, Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new()
+        .text_node(FIRST.0, FIRST.1)
+        .color(PresetColor::Red)
+        .text_node(SECOND.0, SECOND.1)
+        .color(PresetColor::Red)
+        .edge(FIRST.0, SECOND.0);
+    if let Some((from, to)) = arrows {
+        builder = builder.arrows(from, to);
+    }
+    Ok(builder.build()?)
+}
+
+/// Which notes point at each other with the edge's `arrows`, read per `direction`, checking
+/// that every out-neighbor is matched by an in-neighbor
+fn pointing(arrows: Option<(bool, bool)>, direction: EdgeDirection) -> Result<Pointing, Box<dyn Error>> {
+    let items = to_syndication_format_with(&canvas(arrows)?, Some(default_process_node), direction)?;
+    let (first, second) = (of(&items, FIRST.0)?, of(&items, SECOND.0)?);
+    let points = |from: &SyndicationFormat, to: &SyndicationFormat| {
+        let out = from.out_neighbor_ids.contains(&to.id);
+        let into = to.in_neighbor_ids.contains(&from.id);
+        if out == into { Ok(out) } else { Err(format!("{} and {} disagree: {:?}", from.id, to.id, items)) }
+    };
+    Ok((points(first, second)?, points(second, first)?))
+}
+
+fn of<'a>(items: &'a HashMap<NodeId, SyndicationFormat>, node_id: &str) -> Result<&'a SyndicationFormat, Box<dyn Error>> {
+    Ok(items.values().find(|item| item.id.as_str() == node_id).ok_or_else(|| format!("no item for {}", node_id))?)
+}
+
+/// The published post of a node
+fn post(scratch: &Path, node_id: &str) -> Result<String, Box<dyn Error>> {
+    for entry in std::fs::read_dir(scratch.join("site"))? {
+        let path = entry?.path();
+        if path.to_string_lossy().contains(node_id) {
+            return Ok(std::fs::read_to_string(path)?);
+        }
+    }
+    Err(format!("no post of {}", node_id).into())
+}
+
+/// Whether the `key` list of a post's frontmatter has an entry linking to a node's post
+fn frontmatter_links(post: &str, key: &str, node_id: &str) -> bool {
+    let Some((_, rest)) = post.split_once(&format!("\n{}:\n", key)) else {
+        return false;
+    };
+    rest.lines().take_while(|line| line.starts_with(' ')).any(|line| line.contains(node_id))
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
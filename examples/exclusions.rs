@@ -0,0 +1,128 @@
+//! Exclusion list check: exclude a note before it's ever published, take it off the list,
+//! then exclude a note that's already published, checking posts, trackers and the report.
+//!
+//! ```sh
+//! cargo run --example exclusions   # or `just exclusions`
+//! ```
+//!
+//! Both notes are red and `FIRST` points at `SECOND`, so every run would publish both if
+//! nothing were excluded.
+
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::TimeZone;
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::warnings::codes;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, MockClock, ProcessReport, SyndicationTracker, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const FIRST: &str = "e8c0000000000001";
+const SECOND: &str = "e8c0000000000002";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-exclusions-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let canvas_path = scratch.join("excluded.canvas");
+    let site = scratch.join("site");
+    let canvas = CanvasBuilder::new()
+        .text_node(FIRST, "Worth sharing.")
+        .color(PresetColor::Red)
+        .text_node(SECOND, "Retracted.")
+        .color(PresetColor::Red)
+        .edge(FIRST, SECOND)
+        .build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+    let clock = Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()));
+    let mut config = Config::new(&canvas_path);
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(&site).with_clock(clock))?;
+    let (first, second): (NodeId, NodeId) = (FIRST.parse()?, SECOND.parse()?);
+
+    // Excluded before its first run, a matching note is suppressed and reported
+    syndicator.exclusions()?.add(SECOND)?;
+    let report = syndicator.process_once()?;
+    let posts = list(&site);
+    let suppressed = has_post(&posts, FIRST) && !has_post(&posts, SECOND);
+    check("an excluded note is never published", suppressed, || format!("{:?}", posts))?;
+    let reported = report.excluded == [SECOND] && warned(&report, SECOND) && report.items_matched == 2;
+    check("the report names it as a matching note suppressed", reported, || format!("{:?}", report.excluded))?;
+    let post = read_post(&site, FIRST)?;
+    check("its neighbors don't link to it", !post.contains(SECOND), || post.clone())?;
+    let untracked = tracker(&syndicator).is_some_and(|t| !t.is_published(&second));
+    check("nor is it tracked", untracked, || published(&syndicator))?;
+
+    // Taken off the list, it's published with the next run
+    let removed = syndicator.exclusions()?.remove(SECOND)?;
+    let report = syndicator.process_once()?;
+    let back = removed && has_post(&list(&site), SECOND) && report.excluded.is_empty();
+    check("removed from the list, it's published", back, || format!("{:?}", list(&site)))?;
+
+    // Excluding a published note keeps its post until it's pruned, and never republishes it
+    syndicator.exclusions()?.add(FIRST)?;
+    let report = syndicator.process_once()?;
+    let kept = report.excluded == [FIRST] && has_post(&list(&site), FIRST);
+    let kept = kept && tracker(&syndicator).is_some_and(|t| t.is_published(&first));
+    check("a published note excluded keeps its post and tracker entry", kept, || format!("{:?}", list(&site)))?;
+    let plans = syndicator.plan_prune()?;
+    let planned: Vec<&NodeId> = plans.iter().flat_map(|plan| plan.files.iter().map(|file| &file.node_id)).collect();
+    check("pruning plans to delete its post", planned == [&first], || format!("{:?}", planned))?;
+    syndicator.prune(&plans)?;
+    let report = syndicator.process_once()?;
+    let posts = list(&site);
+    let gone = !has_post(&posts, FIRST) && has_post(&posts, SECOND) && report.excluded == [FIRST];
+    check("once pruned it stays gone", gone, || format!("{:?}", posts))?;
+    let forgotten = tracker(&syndicator).is_some_and(|t| !t.is_published(&first) && t.is_published(&second));
+    check("and the tracker forgets it", forgotten, || published(&syndicator))?;
+    Ok(())
+}
+
+fn tracker(syndicator: &Syndicator) -> Option<&SyndicationTracker> {
+    syndicator.tracker("directory")
+}
+
+/// The nodes the directory sink's tracker has as published
+fn published(syndicator: &Syndicator) -> String {
+    format!("{:?}", tracker(syndicator).map(|t| t.published().collect::<Vec<_>>()))
+}
+
+/// Whether the report warns that `node_id` matched but is excluded
+fn warned(report: &ProcessReport, node_id: &str) -> bool {
+    report.warnings.iter().any(|w| w.code == codes::EXCLUDED_MATCH && w.node_id.as_deref() == Some(node_id))
+}
+
+fn has_post(posts: &[String], node_id: &str) -> bool {
+    posts.iter().any(|name| name.contains(node_id))
+}
+
+/// The published post of a node
+fn read_post(site: &Path, node_id: &str) -> Result<String, Box<dyn Error>> {
+    let name = list(site).into_iter().find(|name| name.contains(node_id)).ok_or(format!("no post of {}", node_id))?;
+    Ok(std::fs::read_to_string(site.join(name))?)
+}
+
+fn list(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.file_name().to_string_lossy().into_owned()).collect())
+        .unwrap_or_default()
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
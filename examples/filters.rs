@@ -0,0 +1,121 @@
+//! Filter combinator check: build the filter "(red or purple) and not in the private group"
+//! with the `NodeFilter` methods and with `Filter::all`, `any` and `not`, and check which
+//! notes each syndicates.
+//!
+//! ```sh
+//! cargo run --example filters   # or `just filters`
+//! ```
+//!
+//! The private group holds a red and a purple note; outside it are a red, a purple and a
+//! green note, and a purple link node.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::jsoncanvas::{JsonCanvas, Node};
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Filter, InAdjacencies, NodeFilter, OutAdjacencies, SizeFilter, default_node_filter,
+    default_process_node, has_color, in_group, is_text_node, process_nodes_matching, to_syndication_format,
+};
+
+const RED: &str = "f100000000000001";
+const PURPLE: &str = "f100000000000002";
+const GREEN: &str = "f100000000000003";
+const LINK: &str = "f100000000000004";
+const PRIVATE_RED: &str = "f100000000000005";
+const PRIVATE_PURPLE: &str = "f100000000000006";
+/// Wider than the other notes
+const WIDE: &str = "f100000000000007";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let canvas = canvas()?;
+    let public = [RED, PURPLE, WIDE];
+
+    // With the trait's methods
+    let red_or_purple = has_color(PresetColor::Red).or(has_color(PresetColor::Purple));
+    let chained = red_or_purple.and(in_group(&canvas, "private").not());
+    let found = syndicated(&canvas, chained)?;
+    check("red or purple notes outside the private group are syndicated", found == public, || format!("{:?}", found))?;
+
+    // With `Filter`, from a list of filters chosen at runtime
+    let colors = vec![has_color(PresetColor::Red).boxed(), has_color(PresetColor::Purple).boxed()];
+    let listed = Filter::all(vec![Filter::any(colors), Filter::not(in_group(&canvas, "private"))]);
+    let found = syndicated(&canvas, listed)?;
+    check("the same filter built with `Filter` agrees", found == public, || format!("{:?}", found))?;
+
+    // The default filter is made of the same pieces
+    let default = to_syndication_format(&canvas, Some(default_process_node))?;
+    let mut by_default: Vec<&str> = default.keys().map(|id| id.as_str()).collect();
+    by_default.sort();
+    let composed = syndicated(&canvas, default_node_filter())?;
+    let same = composed == by_default && composed == [RED, PRIVATE_RED, WIDE];
+    check("the default filter is red text nodes", same, || format!("{:?} {:?}", composed, by_default))?;
+    let text = syndicated(&canvas, is_text_node)?;
+    let every = text == [RED, PURPLE, GREEN, PRIVATE_RED, PRIVATE_PURPLE, WIDE];
+    check("its pieces work alone", every, || format!("{:?}", text))?;
+
+    // Empty lists, closures and other filters
+    let all = syndicated(&canvas, Filter::all(Vec::new()))?;
+    let none = syndicated(&canvas, Filter::any(Vec::new()))?;
+    let empty = all == text && none.is_empty();
+    check("all of no filters passes every note, any of none passes none", empty, || format!("{:?} {:?}", all, none))?;
+    let linked = |_: &Node, out: &OutAdjacencies, _: &InAdjacencies| !out.is_empty();
+    let found = syndicated(&canvas, has_color(PresetColor::Red).and(linked))?;
+    check("closures combine with the other filters", found == [RED], || format!("{:?}", found))?;
+    let wide = syndicated(&canvas, has_color(PresetColor::Red).and(SizeFilter::any().min_width(400)))?;
+    check("and so do size filters", wide == [WIDE], || format!("{:?}", wide))?;
+    let spaced = syndicated(&canvas, in_group(&canvas, "  PRIVATE "))?;
+    let matched = spaced == [PRIVATE_RED, PRIVATE_PURPLE];
+    check("group labels match ignoring case and surrounding space", matched, || format!("{:?}", spaced))?;
+    Ok(())
+}
+
+/// The notes: the private group's at the top left, the others to its right
+fn canvas() -> Result<JsonCanvas, Box<dyn Error>> {
+    Ok(CanvasBuilder::new()
+        .group_node("f1000000000000ff")
+        .label("Private")
+        .at(0, 0)
+        .size(600, 400)
+        .text_node(PRIVATE_RED, "Not for sharing.")
+        .color(PresetColor::Red)
+        .at(50, 50)
+        .text_node(PRIVATE_PURPLE, "Not this either.")
+        .color(PresetColor::Purple)
+        .at(50, 200)
+        .text_node(RED, "For everyone.")
+        .color(PresetColor::Red)
+        .at(1000, 0)
+        .text_node(PURPLE, "Also for everyone.")
+        .color(PresetColor::Purple)
+        .at(1000, 200)
+        .text_node(GREEN, "Not marked.")
+        .color(PresetColor::Green)
+        .at(1000, 400)
+        .link_node(LINK, "https://example.com")
+        .color(PresetColor::Purple)
+        .at(1000, 600)
+        .text_node(WIDE, "A long thought, given room.")
+        .color(PresetColor::Red)
+        .at(1000, 800)
+        .size(500, 300)
+        .edge(RED, PURPLE)
+        .build()?)
+}
+
+/// Node IDs of the items the filter gives, ordered
+fn syndicated(canvas: &JsonCanvas, filter: impl NodeFilter) -> Result<Vec<&'static str>, Box<dyn Error>> {
+    let items = to_syndication_format(canvas, Some(process_nodes_matching(filter)))?;
+    let ids: BTreeSet<String> = items.keys().map(|id| id.to_string()).collect();
+    let all = [RED, PURPLE, GREEN, LINK, PRIVATE_RED, PRIVATE_PURPLE, WIDE];
+    Ok(all.into_iter().filter(|id| ids.contains(*id)).collect())
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Publish history check: publish a note, edit it so it's republished, then archive it,
+//! and check the three events its history gives.
+//!
+//! ```sh
+//! cargo run --example history   # or `just history`
+//! ```
+//!
+//! The sink is an in-memory one taking updates and archiving, and each run is an hour after
+//! the one before, so the events' times can be checked.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{Duration, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, HistoryEventKind, MockClock, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+};
+
+const NOTE: &str = "415c000000000001";
+
+/// Takes everything it's given, as a sink that can edit and unlist its posts would
+struct Accepting;
+
+impl SyndicationSink for Accepting {
+    fn publish(&mut self, _items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        Ok(())
+    }
+
+    fn update(&mut self, _items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        Ok(())
+    }
+
+    fn supports_updates(&self) -> bool {
+        true
+    }
+
+    fn archive(&mut self, _node_ids: &[NodeId], _dry_run: bool) -> Result<(), SinkError> {
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "accepting"
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-history-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let canvas_path = scratch.join("history.canvas");
+    let start = chrono::Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+    let clock = Arc::new(MockClock::new(start));
+    write_canvas(&canvas_path, "First thoughts.", false)?;
+    let mut config = Config::new(&canvas_path);
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(Accepting)?;
+    let node_id: NodeId = NOTE.parse()?;
+
+    // Published, edited and republished, then archived, an hour apart
+    syndicator.process_once()?;
+    clock.advance(Duration::hours(1));
+    write_canvas(&canvas_path, "Second thoughts.", false)?;
+    syndicator.process_once()?;
+    clock.advance(Duration::hours(1));
+    write_canvas(&canvas_path, "Second thoughts.", true)?;
+    syndicator.process_once()?;
+
+    let events = syndicator.history(&node_id)?;
+    let kinds: Vec<HistoryEventKind> = events.iter().map(|event| event.kind).collect();
+    let expected = [HistoryEventKind::Published, HistoryEventKind::Updated, HistoryEventKind::Archived];
+    check("the history has the publish, the update and the archive", kinds == expected, || format!("{:?}", kinds))?;
+    let times: Vec<_> = events.iter().map(|event| event.at).collect();
+    let timed = times == [start, start + Duration::hours(1), start + Duration::hours(2)];
+    check("each at the time of its run", timed, || format!("{:?}", times))?;
+    let sinks = events.iter().all(|event| event.sink == "accepting" && event.node_id == node_id);
+    check("for the sink it happened on", sinks, || format!("{:?}", events))?;
+    let hashes: Vec<Option<&str>> = events.iter().map(|event| event.content_hash.as_deref()).collect();
+    let rehashed = hashes[0].is_some() && hashes[1].is_some() && hashes[0] != hashes[1];
+    check("publish and update hash the text of the time", rehashed, || format!("{:?}", hashes))?;
+    let slugs: Vec<Option<&str>> = events.iter().map(|event| event.slug.as_deref()).collect();
+    let slugged = slugs[0].is_some_and(|s| s.contains("first")) && slugs[1].is_some_and(|s| s.contains("second"));
+    check("and slug it", slugged, || format!("{:?}", slugs))?;
+    let other: NodeId = "415c0000000000ff".parse()?;
+    let none = syndicator.history(&other)?.is_empty();
+    check("other nodes have no history", none, || format!("{:?}", syndicator.history(&other)))?;
+
+    // A hand-edited line with a short hash still reads, and is shown whole
+    let log = scratch.join(".history.canvas.syndication-history.jsonl");
+    let mut edited = events[0].clone();
+    edited.content_hash = Some("abc".to_string());
+    writeln!(std::fs::OpenOptions::new().append(true).open(&log)?, "{}", serde_json::to_string(&edited)?)?;
+    let events = syndicator.history(&node_id)?;
+    let short: Vec<Option<&str>> = events.iter().map(|event| event.short_hash()).collect();
+    let shown = short.len() == 4 && short[3] == Some("abc") && short[0].is_some_and(|hash| hash.len() == 16);
+    check("short hashes are shown whole, others cut to 16", shown, || format!("{:?}", short))?;
+    Ok(())
+}
+
+/// Write the canvas with the note red, inside the Archive group if `archived`
+fn write_canvas(canvas_path: &Path, text: &str, archived: bool) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    if archived {
+        builder = builder.group_node("415c0000000000aa").label("Archive").at(0, 0).size(600, 400);
+    }
+    let canvas = builder.text_node(NOTE, text).color(PresetColor::Red).at(50, 50).build()?;
+    std::fs::write(canvas_path, serde_json::to_string(&canvas)?)?;
+    Ok(())
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,167 @@
+//! jj dry-run check: publish a canvas to the jj sink through a fake `jj`, dry, paranoid and
+//! armed, and check which commands ran in each mode.
+//!
+//! ```sh
+//! cargo run --example jj_dry_run   # or `just jj-dry-run`
+//! ```
+//!
+//! The fake answers `jj log` with made-up commit IDs for the bookmark, so the dry run's report
+//! can be checked for the bookmark data it looked up rather than assumed.
+
+use std::error::Error;
+use std::path::Path;
+use std::process::{ExitStatus, Output};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, ProcessReport, SinkOutcome, Syndicator};
+use syndicate_json_canvas_sinks::{CommandRunner, JjRepositorySink};
+
+const NOTE: (&str, &str) = ("d170000000000001", "Dry runs should look before they leap.");
+const LOCAL: &str = "0123456789abcdef0123456789abcdef01234567";
+const REMOTE: &str = "fedcba9876543210fedcba9876543210fedcba98";
+/// Commands that change the repository, which no dry run may run
+const MUTATING: &[&str] = &["jj new", "jj describe", "jj bookmark", "jj git push", "jj abandon"];
+
+/// Stands in for `jj`, recording every command and answering `jj log` for the bookmark
+#[derive(Clone, Default)]
+struct FakeJj {
+    ran: Arc<Mutex<Vec<String>>>,
+    /// Whether the bookmark is missing, so looking it up fails
+    missing_bookmark: bool,
+}
+
+impl CommandRunner for FakeJj {
+    fn run(&self, program: &str, args: &[&str], _current_dir: &Path) -> std::io::Result<Output> {
+        self.ran.lock().unwrap().push(format!("{} {}", program, args.join(" ")));
+        let revset = args.iter().position(|arg| *arg == "-r").and_then(|i| args.get(i + 1));
+        let (ok, stdout) = match (args.first(), revset) {
+            (Some(&"log"), Some(&"main")) if self.missing_bookmark => (false, String::new()),
+            (Some(&"log"), Some(&"main")) => (true, format!("{}\n", LOCAL)),
+            (Some(&"log"), Some(revset)) if revset.starts_with("remote_bookmarks(") => (true, format!("{}\n", REMOTE)),
+            _ => (true, String::new()),
+        };
+        let stderr = if ok { Vec::new() } else { b"Error: Revision `main` doesn't exist".to_vec() };
+        Ok(Output { status: exit_status(ok), stdout: stdout.into_bytes(), stderr })
+    }
+}
+
+impl FakeJj {
+    fn ran(&self) -> Vec<String> {
+        self.ran.lock().unwrap().clone()
+    }
+}
+
+#[cfg(unix)]
+fn exit_status(ok: bool) -> ExitStatus {
+    std::os::unix::process::ExitStatusExt::from_raw(if ok { 0 } else { 1 << 8 })
+}
+
+#[cfg(windows)]
+fn exit_status(ok: bool) -> ExitStatus {
+    std::os::windows::process::ExitStatusExt::from_raw(if ok { 0 } else { 1 })
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-jj-dry-run-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    // Dry: fetch and look the bookmark up, nothing else
+    let jj = FakeJj::default();
+    let report = publish(&scratch.join("dry"), jj.clone(), true, false)?;
+    let ran = jj.ran();
+    let read_only = ran.len() == 3
+        && ran[0] == "jj git fetch"
+        && ran[1..].iter().all(|command| command.starts_with("jj log --ignore-working-copy"));
+    check("a dry run fetches and looks up the bookmark", read_only, || format!("{:?}", ran))?;
+    let mutated = ran.iter().any(|command| MUTATING.iter().any(|m| command.starts_with(m)));
+    check("a dry run skips every mutating command", !mutated, || format!("{:?}", ran))?;
+    let outcome = jj_outcome(&report)?;
+    let commit = outcome.planned_commit.as_ref();
+    let positions = commit.is_some_and(|c| c.current.as_deref() == Some(LOCAL) && c.remote.as_deref() == Some(REMOTE));
+    check("the report has the bookmark's real positions", positions, || format!("{:?}", commit))?;
+    let target = commit.is_some_and(|c| c.bookmark == "main" && c.target.contains(&LOCAL[..12]));
+    check("and where the bookmark would move", target, || format!("{:?}", commit))?;
+    let files = commit.is_some_and(|c| {
+        c.files.len() == 1 && c.files[0].starts_with("posts") && c.files[0].to_string_lossy().contains(NOTE.0)
+    });
+    check("and the files the commit would hold", files, || format!("{:?}", commit))?;
+    let message = commit.is_some_and(|c| !c.message.is_empty());
+    check("and the commit message", message, || format!("{:?}", commit))?;
+    let markdown = report.to_markdown();
+    let rendered = markdown.contains("Would commit to `main`") && markdown.contains(&LOCAL[..12]);
+    check("the markdown report shows the commit", rendered, || markdown.clone())?;
+    let untouched = !scratch.join("dry/repo/posts").exists();
+    check("a dry run writes nothing to the repository", untouched, String::new)?;
+
+    // Dry, with the bookmark missing: fails where a real run would
+    let jj = FakeJj { missing_bookmark: true, ..FakeJj::default() };
+    let report = publish(&scratch.join("missing"), jj.clone(), true, false)?;
+    let outcome = jj_outcome(&report)?;
+    let failed = outcome.error_step.as_deref() == Some("resolve bookmark") && outcome.planned_commit.is_none();
+    check("a dry run fails on a missing bookmark", failed, || format!("{:?}", outcome))?;
+
+    // Paranoid: no commands at all, and a report without bookmark data
+    let jj = FakeJj { missing_bookmark: true, ..FakeJj::default() };
+    let report = publish(&scratch.join("paranoid"), jj.clone(), true, true)?;
+    let ran = jj.ran();
+    check("a paranoid dry run runs no commands", ran.is_empty(), || format!("{:?}", ran))?;
+    let outcome = jj_outcome(&report)?;
+    let commit = outcome.planned_commit.as_ref();
+    let blind = outcome.error.is_none() && commit.is_some_and(|c| c.current.is_none() && c.files.len() == 1);
+    check("its report has the commit but no bookmark positions", blind, || format!("{:?}", outcome))?;
+
+    // Armed: the full workflow, with no lookups
+    let jj = FakeJj::default();
+    let report = publish(&scratch.join("armed"), jj.clone(), false, false)?;
+    let ran: Vec<String> = jj.ran().into_iter().map(|c| c.split(" -m ").next().unwrap_or(&c).to_string()).collect();
+    let workflow = [
+        "jj git fetch",
+        "jj new --insert-after main",
+        "jj describe",
+        "jj bookmark move main",
+        "jj git push --remote origin --bookmark main",
+    ];
+    check("an armed run runs the whole workflow", ran == workflow, || format!("{:?}", ran))?;
+    let outcome = jj_outcome(&report)?;
+    let none = outcome.error.is_none() && outcome.planned_commit.is_none();
+    check("an armed run plans no commit", none, || format!("{:?}", outcome))?;
+    Ok(())
+}
+
+/// Publish a one-note canvas in `dir` to a jj sink running `jj`, returning the run report
+fn publish(dir: &Path, jj: FakeJj, dry_run: bool, paranoid: bool) -> Result<ProcessReport, Box<dyn Error>> {
+    let repo = dir.join("repo");
+    std::fs::create_dir_all(&repo)?;
+    let canvas_path = dir.join("thoughts.canvas");
+    let canvas = CanvasBuilder::new().text_node(NOTE.0, NOTE.1).color(PresetColor::Red).build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+    let mut config = Config::new(&canvas_path);
+    config.dry_run = dry_run;
+    let mut syndicator = Syndicator::new(config)?;
+    let sink = JjRepositorySink::new(&repo, "main", "origin", "posts")?
+        .with_command_runner(jj)
+        .with_paranoid_dry_run(paranoid);
+    syndicator.add_sink(sink)?;
+    Ok(syndicator.process_once()?)
+}
+
+fn jj_outcome(report: &ProcessReport) -> Result<&SinkOutcome, Box<dyn Error>> {
+    Ok(report.sinks.iter().find(|outcome| outcome.sink == "jj").ok_or("no outcome for the jj sink")?)
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
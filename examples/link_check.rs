@@ -0,0 +1,203 @@
+//! Link check: publish a canvas whose links lead everywhere a link can lead, against a mock
+//! web, and check each is classified, rewritten, held back and cached as it should be.
+//!
+//! ```sh
+//! cargo run --example link_check   # exits non-zero if a check fails
+//! ```
+//!
+//! Each node links to one page of the mock web. The canvas is published to a public-facing
+//! [`DirectorySink`] and a private [`ContentLakeSink`] with the clock pinned, then once more
+//! with a node added, then again after the cache expired.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::TimeZone;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, HttpClient, HttpError, HttpRequest, HttpResponse, LinkCheckOptions, LinkCheckPolicy,
+    LinkStatus, MockClock, ProcessReport, Syndicator, read_tree,
+};
+use syndicate_json_canvas_sinks::{ContentLakeSink, DirectorySink};
+
+/// How a page of the mock web answers
+#[derive(Debug, Clone, Copy)]
+enum Page {
+    Status(u16),
+    Redirect(u16, &'static str),
+    /// Answers HEAD with 405, and GET with the status
+    NoHead(u16),
+    Unreachable,
+}
+
+/// Node, the page it links to, and what checking the link must find
+const LINKS: &[(&str, &str, Page)] = &[
+    ("l1nk000000000001", "https://ok.test/", Page::Status(200)),
+    ("l1nk000000000002", "https://moved.test/old", Page::Redirect(301, "https://moved.test/new")),
+    ("l1nk000000000003", "https://hop.test/a", Page::Redirect(302, "/b")),
+    ("l1nk000000000004", "https://gone.test/", Page::Status(404)),
+    ("l1nk000000000005", "https://down.test/", Page::Status(503)),
+    ("l1nk000000000006", "https://slow.test/", Page::Unreachable),
+    ("l1nk000000000007", "https://loop.test/", Page::Redirect(307, "https://loop.test/")),
+    ("l1nk000000000008", "https://nohead.test/", Page::NoHead(200)),
+];
+
+/// Pages only reached through redirects
+const TARGETS: &[(&str, Page)] =
+    &[("https://moved.test/new", Page::Status(200)), ("https://hop.test/b", Page::Status(200))];
+
+/// Node added for the second run, linking to pages checked in the first
+const ADDED: (&str, &str) =
+    ("l1nk000000000009", "Again https://ok.test/ and https://down.test/ and https://gone.test/");
+
+/// The mock web, counting the requests for each URL
+#[derive(Debug, Default)]
+struct MockWeb {
+    pages: HashMap<String, Page>,
+    requests: Mutex<HashMap<String, usize>>,
+}
+
+impl MockWeb {
+    fn requests(&self, url: &str) -> usize {
+        self.requests.lock().unwrap().get(url).copied().unwrap_or(0)
+    }
+}
+
+impl HttpClient for MockWeb {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        *self.requests.lock().unwrap().entry(request.url.clone()).or_default() += 1;
+        let respond =
+            |status: u16, headers: Vec<(String, String)>| HttpResponse { status, headers, body: String::new() };
+        match self.pages.get(&request.url).copied().unwrap_or(Page::Status(404)) {
+            Page::Status(status) => Ok(respond(status, Vec::new())),
+            Page::Redirect(status, to) => Ok(respond(status, vec![("Location".to_string(), to.to_string())])),
+            Page::NoHead(_) if request.method == "HEAD" => Ok(respond(405, Vec::new())),
+            Page::NoHead(status) => Ok(respond(status, Vec::new())),
+            Page::Unreachable => Err(HttpError::Transport("operation timed out".to_string())),
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-link-check-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let web = Arc::new(MockWeb {
+        pages: LINKS
+            .iter()
+            .map(|(_, url, page)| (url.to_string(), *page))
+            .chain(TARGETS.iter().map(|(url, page)| (url.to_string(), *page)))
+            .collect(),
+        ..MockWeb::default()
+    });
+    let clock = Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap()));
+    let canvas_path = scratch.join("links.canvas");
+    write_canvas(&canvas_path, false)?;
+
+    let mut config = Config::new(&canvas_path);
+    config.options.clock = clock.clone();
+    config.options.link_check = Some(
+        LinkCheckOptions::default()
+            .with_policy(LinkCheckPolicy::Block)
+            .with_rewrite_redirects(true)
+            .with_http_client(web.clone()),
+    );
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(scratch.join("site")).with_clock(clock.clone()))?.public_facing = true;
+    syndicator.add_sink(ContentLakeSink::new(scratch.join("lake")))?;
+
+    // First run: every link is classified, the moved one rewritten, broken ones held back
+    let report = syndicator.process_once()?;
+    let expected: &[(&str, LinkStatus)] = &[
+        ("l1nk000000000002", LinkStatus::Redirect { to: "https://moved.test/new".to_string(), permanent: true }),
+        ("l1nk000000000003", LinkStatus::Redirect { to: "https://hop.test/b".to_string(), permanent: false }),
+        ("l1nk000000000004", LinkStatus::ClientError { code: 404 }),
+        ("l1nk000000000005", LinkStatus::ServerError { code: 503 }),
+        ("l1nk000000000006", LinkStatus::Timeout),
+        ("l1nk000000000007", LinkStatus::TooManyRedirects),
+    ];
+    let found: Vec<(&str, LinkStatus)> =
+        report.link_findings.iter().map(|f| (f.node_id.as_str(), f.status.clone())).collect();
+    check("classifications", found == expected, || format!("found {:?}", found))?;
+    let rewritten: Vec<&str> =
+        report.link_findings.iter().filter(|f| f.rewritten).map(|f| f.node_id.as_str()).collect();
+    let only_permanent = rewritten == ["l1nk000000000002"];
+    check("only the permanent redirect is rewritten", only_permanent, || format!("{:?}", rewritten))?;
+
+    let site = read_tree(&scratch.join("site"))?;
+    let post = |node_id: &str| {
+        site.iter()
+            .find(|(path, _)| path.to_string_lossy().contains(node_id))
+            .map(|(_, bytes)| String::from_utf8_lossy(bytes).into_owned())
+    };
+    let moved = post("l1nk000000000002").unwrap_or_default();
+    let pointed = moved.contains("https://moved.test/new") && !moved.contains("/old");
+    check("moved link points to its new place", pointed, || moved.clone())?;
+    for (node_id, _, _) in LINKS {
+        let broken = expected.iter().any(|(id, status)| id == node_id && status.is_broken());
+        let public = post(node_id).is_some();
+        check(&format!("{} is public only if its link works", node_id), public != broken, String::new)?;
+    }
+    let nohead = web.requests("https://nohead.test/");
+    check("HEAD refused falls back to GET", nohead == 2, || nohead.to_string())?;
+    let lake = sink_items(&report, "content-lake");
+    check("private sinks get every item", lake == LINKS.len(), || lake.to_string())?;
+
+    // Second run: cached results aren't requested again, passing failures are; nothing is
+    // republished for the rewrite
+    write_canvas(&canvas_path, true)?;
+    let before: HashMap<&str, usize> = ["https://ok.test/", "https://gone.test/", "https://down.test/"]
+        .into_iter()
+        .map(|url| (url, web.requests(url)))
+        .collect();
+    let report = syndicator.process_once()?;
+    let again = |url: &str| web.requests(url) - before[url];
+    for (name, url) in [("cached ok", "https://ok.test/"), ("cached 404", "https://gone.test/")] {
+        check(&format!("{} isn't requested again", name), again(url) == 0, || again(url).to_string())?;
+    }
+    check("server errors aren't cached", again("https://down.test/") > 0, String::new)?;
+    let republished: Vec<&String> = report.sinks.iter().flat_map(|s| &s.updated).collect();
+    check("rewrite doesn't count as an edit", republished.is_empty(), || format!("{:?}", republished))?;
+
+    // Once the cache expires, links are requested again
+    clock.advance(chrono::Duration::days(2));
+    let before = web.requests("https://ok.test/");
+    syndicator.process_once()?;
+    check("expired results are requested again", web.requests("https://ok.test/") > before, String::new)?;
+    Ok(())
+}
+
+fn write_canvas(canvas_path: &Path, with_added: bool) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (node_id, url, _) in LINKS {
+        builder = builder.text_node(*node_id, format!("Read {} for more.", url)).color(PresetColor::Red);
+    }
+    if with_added {
+        builder = builder.text_node(ADDED.0, ADDED.1).color(PresetColor::Red);
+    }
+    std::fs::write(canvas_path, serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+/// Items handed to a sink in a run
+fn sink_items(report: &ProcessReport, sink: &str) -> usize {
+    report.sinks.iter().filter(|s| s.sink == sink).map(|s| s.items.len()).sum()
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
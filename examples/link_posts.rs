@@ -0,0 +1,123 @@
+//! Link post check: publish a canvas mixing text and link nodes, with link posts off and on.
+//!
+//! ```sh
+//! cargo run --example link_posts   # or `just link-posts`
+//! ```
+//!
+//! Off, the red link node only stands for a page hosted elsewhere. On, it gets a post of its
+//! own next to the text node pointing at it, while a link node of another color still doesn't.
+
+use std::error::Error;
+use std::path::Path;
+
+use syndicate_json_canvas_lib::jsoncanvas::JsonCanvas;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, DEFAULT_LINK_POST_TEMPLATE, InAdjacencies, OutAdjacencies, ProcessReport, Syndicator,
+    link_post_of, process_node_allow_link_nodes, read_tree, to_syndication_format,
+};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTE: (&str, &str) = ("1e0c000000000001", "Some thoughts on why canvases beat outlines.");
+const ESSAY: (&str, &str) = ("1e0c000000000002", "https://example.com/essays/on-canvases");
+/// Link node of a color that isn't syndicated
+const BOOKMARK: (&str, &str) = ("1e0c000000000003", "https://example.com/bookmarked");
+
+/// Paths and contents of the files a sink wrote
+type Site = Vec<(String, String)>;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-link-posts-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let canvas = CanvasBuilder::new()
+        .text_node(NOTE.0, NOTE.1)
+        .color(PresetColor::Red)
+        .link_node(ESSAY.0, ESSAY.1)
+        .color(PresetColor::Red)
+        .link_node(BOOKMARK.0, BOOKMARK.1)
+        .color(PresetColor::Purple)
+        .edge(NOTE.0, ESSAY.0)
+        .build()?;
+
+    // The companion of the default filter keeps both kinds, only the link carrying a URL
+    let items = to_syndication_format(&canvas, Some(process_node_allow_link_nodes))?;
+    let essay = items.values().find(|item| item.id.as_str() == ESSAY.0);
+    let note = items.values().find(|item| item.id.as_str() == NOTE.0);
+    let both = items.len() == 2 && note.is_some_and(|item| item.url.is_none());
+    check("the link filter keeps text and red link nodes", both, || format!("{:?}", items.keys()))?;
+    let templated =
+        essay.is_some_and(|item| item.text == format!("🔗 {}", ESSAY.1) && item.url.as_deref() == Some(ESSAY.1));
+    check("link posts are templated and carry their URL", templated, || format!("{:?}", essay))?;
+    let url_only = |node: &_, out: &OutAdjacencies, inc: &InAdjacencies| link_post_of(node, out, inc, "{url}");
+    let plain = to_syndication_format(&canvas, Some(url_only))?;
+    let bare = plain.values().filter(|item| item.url.as_ref() == Some(&item.text)).count() == 2;
+    check("a `{url}` template makes the text the URL", bare, || format!("{:?}", plain))?;
+
+    // Off: only the note is published, linking straight to the page
+    let (report, site) = publish(&canvas, &scratch.join("off"), None)?;
+    let published: Vec<&String> = report.sinks.iter().flat_map(|s| &s.items).collect();
+    let only_text = published == [NOTE.0];
+    check("without link posts only text nodes are published", only_text, || format!("{:?}", published))?;
+    let note_post = post(&site, NOTE.0);
+    let linked_out = note_post.contains(&format!("href: \"{}\"", ESSAY.1));
+    check("the note links to the page itself", linked_out, || note_post.clone())?;
+
+    // On: both survive the pipeline, and the note links to the link post
+    let (report, site) = publish(&canvas, &scratch.join("on"), Some(DEFAULT_LINK_POST_TEMPLATE))?;
+    let published: Vec<&String> = report.sinks.iter().flat_map(|s| &s.items).collect();
+    let both = published == [NOTE.0, ESSAY.0];
+    check("text and link nodes are published", both, || format!("{:?}", published))?;
+    let essay_post = post(&site, ESSAY.0);
+    let frontmatter = essay_post.contains(&format!("external_url: \"{}\"", ESSAY.1));
+    check("the link post has external_url frontmatter", frontmatter, || essay_post.clone())?;
+    let note_post = post(&site, NOTE.0);
+    let linked_in = note_post.contains("href: \"/t/") && !note_post.contains(&format!("href: \"{}\"", ESSAY.1));
+    check("the note links to the link post", linked_in, || note_post.clone())?;
+    let unselected = post(&site, BOOKMARK.0).is_empty() && report.external.is_empty();
+    check("link nodes of other colors stay out", unselected, || format!("{:?}", report.external))?;
+    Ok(())
+}
+
+/// Publish the canvas once from `out` to a [`DirectorySink`] there, returning the report and
+/// the files written
+fn publish(
+    canvas: &JsonCanvas,
+    out: &Path,
+    link_posts: Option<&str>,
+) -> Result<(ProcessReport, Site), Box<dyn Error>> {
+    std::fs::create_dir_all(out)?;
+    let canvas_path = out.join("mixed.canvas");
+    std::fs::write(&canvas_path, serde_json::to_string(canvas)?)?;
+    let mut config = Config::new(&canvas_path);
+    config.options.link_posts = link_posts.map(str::to_string);
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(out.join("site")))?;
+    let report = syndicator.process_once()?;
+    let site = read_tree(&out.join("site"))?
+        .into_iter()
+        .map(|(path, bytes)| (path.to_string_lossy().into_owned(), String::from_utf8_lossy(&bytes).into_owned()))
+        .collect();
+    Ok((report, site))
+}
+
+/// The post written for a node, empty if there's none
+fn post(site: &Site, node_id: &str) -> String {
+    site.iter().find(|(path, _)| path.contains(node_id)).map(|(_, contents)| contents.clone()).unwrap_or_default()
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,178 @@
+//! Locale check: publish the same tagged notes under `en-US` and then `de-DE`, and check
+//! that dates and counts people read differ while those programs read don't.
+//!
+//! ```sh
+//! cargo run --example locale   # or `just locale`
+//! ```
+//!
+//! Each run publishes to a directory sink keeping tag pages, a content lake and a weekly
+//! newsletter digest (to a mock API), at the same moment and from the same paths, so
+//! anything that differs between the runs comes from the locale.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::{NaiveDate, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, HttpClient, HttpError, HttpRequest, HttpResponse, Locale, MockClock, Syndicator, TextPolicy,
+};
+use syndicate_json_canvas_sinks::{ContentLakeSink, DigestPeriod, DirectorySink, NewsletterMode, NewsletterSink};
+
+const NOTES: [(&str, &str); 2] = [("10c0000000000001", "Borrowing is a promise."), ("10c0000000000002", "Lifetimes.")];
+
+/// What one run wrote, by where it went
+struct Output {
+    /// Files of the site folder, tag pages included, by path relative to it
+    site: BTreeMap<String, String>,
+    /// Every content lake record, without its random `record_id`, ordered
+    lake: Vec<String>,
+    /// Body of the request creating the digest
+    email: String,
+}
+
+/// The mock newsletter API, keeping the body of every request
+#[derive(Debug, Default)]
+struct MockButtondown {
+    bodies: Mutex<Vec<String>>,
+}
+
+impl HttpClient for MockButtondown {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        let mut bodies = self.bodies.lock().unwrap();
+        bodies.push(request.body.clone().unwrap_or_default());
+        Ok(HttpResponse { status: 201, headers: Vec::new(), body: format!(r#"{{"id": "em{}"}}"#, bodies.len()) })
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-locale-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let english = publish(scratch, "en-US")?;
+    let german = publish(scratch, "de-DE")?;
+
+    // What people read
+    let (en_page, de_page) = (page(&english, "tags/rust.md"), page(&german, "tags/rust.md"));
+    let dated = en_page.contains("(May 12, 2024)") && de_page.contains("(12. Mai 2024)");
+    check("tag pages list dates in the locale", dated, || format!("{}\n{}", en_page, de_page))?;
+    let subjects = english.email.contains("Notes of May 12, 2024") && german.email.contains("Notes of 12. Mai 2024");
+    check("email subjects are dated in the locale", subjects, || format!("{}\n{}", english.email, german.email))?;
+    let read = english.email.contains("(1 min read)") && german.email.contains("(1 Min. Lesezeit)");
+    check("and give reading times in it", read, || format!("{}\n{}", english.email, german.email))?;
+    let en = Locale::parse("en-US")?;
+    let de = Locale::parse("de_de")?;
+    let grouped = en.number(1_234_567) == "1,234,567" && de.number(1_234_567) == "1.234.567" && de.tag() == "de-de";
+    check("counts are grouped per locale", grouped, || format!("{} {}", en.number(1_234_567), de.number(1_234_567)))?;
+
+    // What programs read
+    let posts: Vec<(&String, &String)> = english.site.iter().filter(|(path, _)| !path.starts_with("tags/")).collect();
+    let alike = |path: &String, post: &String| german.site.get(path) == Some(post);
+    let dated_alike = posts.iter().all(|(path, post)| post.contains("\ndate: 2024-05-12\n") && alike(path, post));
+    let same_posts = posts.len() == 2 && dated_alike;
+    check("posts and their frontmatter are the same in both", same_posts, || format!("{:?}", german.site))?;
+    let frontmatter = |page: &str| page.split("\n---\n").next().unwrap_or_default().to_string();
+    let same_head = frontmatter(&en_page) == frontmatter(&de_page) && en_page.contains("posts: 2");
+    check("tag page frontmatter is the same in both", same_head, || format!("{}\n{}", en_page, de_page))?;
+    let same_lake = !english.lake.is_empty() && english.lake == german.lake;
+    check("content lake records are the same in both", same_lake, || format!("{:?}", german.lake))?;
+
+    // Configuration
+    let refused = TextPolicy::from_toml("[text]\nlocale = \"tlh\"\n").is_err();
+    let loaded = TextPolicy::from_toml("[text]\nlocale = \"fr-FR\"\n")?;
+    let french = loaded.human_date(NaiveDate::from_ymd_opt(2024, 5, 12).ok_or("bad date")?) == "12 mai 2024";
+    check("the `[text]` table takes known locales only", refused && french, || format!("{:?}", loaded.locale))?;
+    let plain = TextPolicy::default().human_date(NaiveDate::from_ymd_opt(2024, 5, 12).ok_or("bad date")?);
+    check("without a locale dates stay YYYY-MM-DD", plain == "2024-05-12", || plain.clone())?;
+    Ok(())
+}
+
+/// Publish the notes afresh with the text policy in `locale`
+fn publish(scratch: &Path, locale: &str) -> Result<Output, Box<dyn Error>> {
+    let root = scratch.join("run");
+    if root.exists() {
+        std::fs::remove_dir_all(&root)?;
+    }
+    std::fs::create_dir_all(&root)?;
+    let canvas_path = root.join("notes.canvas");
+    let mut builder = CanvasBuilder::new().group_node("10c00000000000ff").label("Rust [tags: rust]").size(1000, 400);
+    for (i, (id, text)) in NOTES.iter().enumerate() {
+        builder = builder.text_node(*id, *text).color(PresetColor::Red).at(50 + 400 * i as i64, 50);
+    }
+    std::fs::write(&canvas_path, serde_json::to_string(&builder.build()?)?)?;
+
+    let text = TextPolicy { locale: Some(Locale::parse(locale)?), ..TextPolicy::default() };
+    let clock = Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2024, 5, 12, 12, 0, 0).unwrap()));
+    let api = Arc::new(MockButtondown::default());
+    let mut config = Config::new(&canvas_path);
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    let site = DirectorySink::new(root.join("site")).with_clock(clock.clone()).with_tag_pages(true);
+    syndicator.add_sink(site.with_text_policy(text.clone()))?;
+    let lake = ContentLakeSink::new(root.join("lake")).with_clock(clock.clone()).with_text_policy(text.clone());
+    syndicator.add_sink(lake)?;
+    let newsletter = NewsletterSink::buttondown("test-key", root.join("newsletter.json"))?
+        .with_mode(NewsletterMode::Digest(DigestPeriod::Weekly))
+        .with_subject("Notes of {date}: {count} notes ({reading_time})")
+        .with_clock(clock)
+        .with_http_client(api.clone())
+        .with_text_policy(text);
+    syndicator.add_sink(newsletter)?;
+    syndicator.process_once()?;
+
+    let mut lake = Vec::new();
+    for entry in std::fs::read_dir(root.join("lake"))? {
+        for line in std::fs::read_to_string(entry?.path())?.lines().filter(|line| !line.is_empty()) {
+            let mut record: serde_json::Value = serde_json::from_str(line)?;
+            record.as_object_mut().ok_or("record isn't an object")?.remove("record_id");
+            lake.push(record.to_string());
+        }
+    }
+    lake.sort();
+    let email = api.bodies.lock().unwrap().first().cloned().unwrap_or_default();
+    Ok(Output { site: files(&root.join("site"))?, lake, email })
+}
+
+/// Files under a folder by path relative to it, skipping hidden ones
+fn files(dir: &Path) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let mut files = BTreeMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(files_in(&path, &name)?);
+        } else {
+            files.insert(name, std::fs::read_to_string(&path)?);
+        }
+    }
+    Ok(files)
+}
+
+fn files_in(dir: &Path, prefix: &str) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    Ok(files(dir)?.into_iter().map(|(name, contents)| (format!("{}/{}", prefix, name), contents)).collect())
+}
+
+fn page(output: &Output, path: &str) -> String {
+    output.site.get(path).cloned().unwrap_or_else(|| format!("no {} in {:?}", path, output.site.keys()))
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Newsletter check: publish a growing canvas to a [`NewsletterSink`] against a mock
+//! Buttondown API, and check drafts are created once, updated in place and only sent when
+//! sending is on and their period is over.
+//!
+//! ```sh
+//! cargo run --example newsletter   # exits non-zero if a check fails
+//! ```
+//!
+//! The clock is pinned to a Wednesday and moved a week on to roll the digest period over.
+//! The API answers creating a draft with an error for the last two checks.
+
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::TimeZone;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, HttpClient, HttpError, HttpRequest, HttpResponse, MockClock, SinkOutcome, Syndicator,
+};
+use syndicate_json_canvas_sinks::{DigestPeriod, NewsletterMode, NewsletterSink};
+
+const API: &str = "https://buttondown.test/v1";
+
+/// A request the mock API got: method, path and body
+type Request = (String, String, String);
+
+/// The mock API, recording its requests; creating a draft answers `create_status`
+#[derive(Debug)]
+struct MockButtondown {
+    requests: Mutex<Vec<Request>>,
+    create_status: Mutex<u16>,
+}
+
+impl MockButtondown {
+    fn new() -> Self {
+        Self { requests: Mutex::new(Vec::new()), create_status: Mutex::new(201) }
+    }
+
+    /// Requests since the last call
+    fn take(&self) -> Vec<Request> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+impl HttpClient for MockButtondown {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        let path = request.url.trim_start_matches(API).to_string();
+        let body = request.body.clone().unwrap_or_default();
+        let mut requests = self.requests.lock().unwrap();
+        requests.push((request.method.clone(), path, body));
+        let status = match request.method.as_str() {
+            "POST" => *self.create_status.lock().unwrap(),
+            _ => 200,
+        };
+        let body = match status {
+            201 => format!(r#"{{"id": "em{}"}}"#, requests.len()),
+            _ => r#"{"detail": "nope"}"#.to_string(),
+        };
+        Ok(HttpResponse { status, headers: Vec::new(), body })
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-newsletter-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    // Digests, drafts only
+    let api = Arc::new(MockButtondown::new());
+    let clock = Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap()));
+    let canvas_path = scratch.join("digest.canvas");
+    write_canvas(&canvas_path, &[("n0000000000000a1", "First note"), ("n0000000000000a2", "Second note")])?;
+    let mut syndicator = new_syndicator(&canvas_path, api.clone(), clock.clone(), false)?;
+
+    syndicator.process_once()?;
+    let requests = api.take();
+    let created = methods(&requests) == ["POST /emails"] && requests[0].2.contains("Notes for 2026-W42");
+    check("one draft for the week's items", created, || format!("{:?}", requests))?;
+    let both = requests[0].2.contains("First note") && requests[0].2.contains("Second note");
+    check("the draft holds both items", both, || requests[0].2.clone())?;
+
+    syndicator.process_once()?;
+    let requests = api.take();
+    check("a re-run sends nothing", requests.is_empty(), || format!("{:?}", requests))?;
+
+    let edited = [("n0000000000000a1", "First note, edited"), ("n0000000000000a2", "Second note")];
+    write_canvas(&canvas_path, &[edited[0], edited[1], ("n0000000000000a3", "Third")])?;
+    syndicator.process_once()?;
+    let requests = api.take();
+    let patched = !requests.is_empty() && methods(&requests).iter().all(|r| r == "PATCH /emails/em1");
+    check("new and edited items update the same draft", patched, || format!("{:?}", requests))?;
+    let last = requests.last().map(|r| r.2.clone()).unwrap_or_default();
+    let updated = last.contains("First note, edited") && last.contains("Third") && !last.contains("about_to_send");
+    check("the draft has the edit and the new item", updated, || last.clone())?;
+
+    clock.advance(chrono::Duration::days(7));
+    add_note(&canvas_path, "n0000000000000a4", "Next week")?;
+    syndicator.process_once()?;
+    let requests = api.take();
+    let unsent = methods(&requests) == ["POST /emails"] && !requests.iter().any(|r| r.2.contains("about_to_send"));
+    check("nothing is sent unless sending is on", unsent, || format!("{:?}", requests))?;
+
+    // Digests, sent once their week is over
+    let api = Arc::new(MockButtondown::new());
+    let canvas_path = scratch.join("send.canvas");
+    write_canvas(&canvas_path, &[("n0000000000000b1", "First note")])?;
+    let mut syndicator = new_syndicator(&canvas_path, api.clone(), clock.clone(), true)?;
+    syndicator.process_once()?;
+    let requests = api.take();
+    check("a digest waits for its week to end", methods(&requests) == ["POST /emails"], || format!("{:?}", requests))?;
+    clock.advance(chrono::Duration::days(7));
+    add_note(&canvas_path, "n0000000000000b2", "Next week")?;
+    syndicator.process_once()?;
+    let requests = api.take();
+    let sent = methods(&requests) == ["POST /emails", "PATCH /emails/em1"] && requests[1].2.contains("about_to_send");
+    check("the last week's digest is sent with the next delivery", sent, || format!("{:?}", requests))?;
+
+    // Errors: a rejected draft is permanent and can be retried, a server error is transient
+    let api = Arc::new(MockButtondown::new());
+    let canvas_path = scratch.join("errors.canvas");
+    write_canvas(&canvas_path, &[("n0000000000000c1", "First note")])?;
+    let mut syndicator = new_syndicator(&canvas_path, api.clone(), clock.clone(), false)?;
+    for (status, permanent) in [(400, true), (503, false)] {
+        *api.create_status.lock().unwrap() = status;
+        let outcome = newsletter_outcome(syndicator.process_once()?.sinks)?;
+        let classified = outcome.error.is_some() && outcome.error_permanent == permanent;
+        check(&format!("a {} is permanent: {}", status, permanent), classified, || format!("{:?}", outcome))?;
+    }
+    *api.create_status.lock().unwrap() = 201;
+    let outcome = newsletter_outcome(syndicator.process_once()?.sinks)?;
+    let blocked = outcome.error.as_deref().is_some_and(|e| e.contains("may already have created"));
+    check("a draft lost in transit isn't created twice", blocked, || format!("{:?}", outcome))?;
+    Ok(())
+}
+
+fn new_syndicator(
+    canvas_path: &Path,
+    api: Arc<MockButtondown>,
+    clock: Arc<MockClock>,
+    send: bool,
+) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(canvas_path);
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    let state_path = canvas_path.with_extension("newsletter.json");
+    syndicator.add_sink(
+        NewsletterSink::buttondown("test-key", state_path)?
+            .with_api_base(API)
+            .with_mode(NewsletterMode::Digest(DigestPeriod::Weekly))
+            .with_send(send)
+            .with_clock(clock)
+            .with_http_client(api),
+    )?;
+    Ok(syndicator)
+}
+
+fn write_canvas(canvas_path: &Path, notes: &[(&str, &str)]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (node_id, text) in notes {
+        builder = builder.text_node(*node_id, *text).color(PresetColor::Red);
+    }
+    std::fs::write(canvas_path, serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+/// Add a note to the canvas, keeping the others as they are
+fn add_note(canvas_path: &Path, node_id: &str, text: &str) -> Result<(), Box<dyn Error>> {
+    let mut canvas: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(canvas_path)?)?;
+    let node = serde_json::to_value(CanvasBuilder::new().text_node(node_id, text).color(PresetColor::Red).build()?)?;
+    canvas["nodes"].as_array_mut().ok_or("canvas has no nodes")?.extend(node["nodes"].as_array().cloned().unwrap());
+    std::fs::write(canvas_path, serde_json::to_string(&canvas)?)?;
+    Ok(())
+}
+
+fn methods(requests: &[Request]) -> Vec<String> {
+    requests.iter().map(|(method, path, _)| format!("{} {}", method, path)).collect()
+}
+
+fn newsletter_outcome(outcomes: Vec<SinkOutcome>) -> Result<SinkOutcome, Box<dyn Error>> {
+    Ok(outcomes.into_iter().find(|o| o.sink == "newsletter").ok_or("the newsletter sink didn't run")?)
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Parallel sinks check: publish three notes to two slow independent sinks, a failing one,
+//! and a sink that must wait for one of the slow ones, and check which ran at the same
+//! time, how long the run took and that every outcome is in the report.
+//!
+//! ```sh
+//! cargo run --example parallel   # or `just parallel`
+//! ```
+//!
+//! Each sink sleeps in `publish` and logs when it started and finished, so overlaps and
+//! ordering can be read off the log.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, SinkError, SinkTarget, SyndicationFormat, SyndicationSink, SyndicationTracker, Syndicator,
+};
+
+const SLOW: Duration = Duration::from_millis(400);
+const NOTES: [&str; 3] = ["9a70000000000001", "9a70000000000002", "9a70000000000003"];
+
+/// When a sink's `publish` started and finished, and how many items it got
+#[derive(Debug, Clone, Copy)]
+struct Call {
+    start: Instant,
+    end: Instant,
+    items: usize,
+}
+
+type Log = Arc<Mutex<HashMap<String, Call>>>;
+
+/// Sleeps for `delay` on every publish, then succeeds or fails
+struct Sleepy {
+    name: String,
+    delay: Duration,
+    fail: bool,
+    log: Log,
+}
+
+impl Sleepy {
+    fn new(name: &str, delay: Duration, log: &Log) -> Self {
+        Self { name: name.to_string(), delay, fail: false, log: log.clone() }
+    }
+}
+
+impl SyndicationSink for Sleepy {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let start = Instant::now();
+        std::thread::sleep(self.delay);
+        let call = Call { start, end: Instant::now(), items: items.len() };
+        self.log.lock().unwrap().insert(self.name.clone(), call);
+        if self.fail {
+            return Err(SinkError::Api("the server went away".to_string()));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-parallel-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let canvas_path = scratch.join("parallel.canvas");
+    let mut builder = CanvasBuilder::new();
+    for (i, id) in NOTES.iter().enumerate() {
+        builder = builder.text_node(*id, format!("Note {}.", i + 1)).color(PresetColor::Red);
+    }
+    std::fs::write(&canvas_path, serde_json::to_string(&builder.build()?)?)?;
+    let log: Log = Arc::default();
+    let mut syndicator = Syndicator::new(Config::new(&canvas_path))?;
+    syndicator.add_sink(Sleepy::new("feed", SLOW, &log))?;
+    syndicator.add_sink(Sleepy::new("webhook", SLOW, &log))?;
+    syndicator.add_sink(Sleepy { fail: true, ..Sleepy::new("broken", SLOW, &log) })?;
+    let tracker = SyndicationTracker::new(&canvas_path, "mastodon")?;
+    let mastodon = Sleepy::new("mastodon", Duration::from_millis(50), &log);
+    syndicator.add_target(SinkTarget::new(mastodon, tracker).after("feed"));
+
+    let started = Instant::now();
+    let report = syndicator.process_once()?;
+    let elapsed = started.elapsed();
+    let calls = log.lock().unwrap().clone();
+    let (feed, webhook, broken, mastodon) = (calls["feed"], calls["webhook"], calls["broken"], calls["mastodon"]);
+
+    // Independent sinks overlap, so the run takes about as long as one slow sink
+    let overlap = |a: Call, b: Call| a.start < b.end && b.start < a.end;
+    let together = overlap(feed, webhook) && overlap(feed, broken) && overlap(webhook, broken);
+    check("independent sinks publish at the same time", together, || format!("{:?}", calls))?;
+    let quick = elapsed < SLOW * 2;
+    check("so the run takes about as long as the slowest, not all of them", quick, || format!("{:?}", elapsed))?;
+
+    // A sink after another waits for it
+    let waited = mastodon.start >= feed.end;
+    check("a sink set to run after another starts once it has finished", waited, || format!("{:?}", calls))?;
+
+    // Every outcome is collected
+    let mut sinks: Vec<&str> = report.sinks.iter().map(|outcome| outcome.sink.as_str()).collect();
+    sinks.sort();
+    let complete = sinks == ["broken", "feed", "mastodon", "webhook"] && calls.values().all(|call| call.items == 3);
+    check("the report has an outcome for every sink, each given every note", complete, || format!("{:?}", sinks))?;
+    let failed: Vec<&str> = report.sinks.iter().filter(|o| o.error.is_some()).map(|o| o.sink.as_str()).collect();
+    check("a failing sink fails alone", failed == ["broken"], || format!("{:?}", failed))?;
+    let published = ["feed", "webhook", "mastodon"].iter().all(|sink| {
+        let tracked = syndicator.tracker(sink).is_some_and(|t| t.published().count() == 3);
+        tracked && report.sinks.iter().any(|o| o.sink == *sink && o.items.len() == 3)
+    });
+    check("the others publish and track every note", published, || format!("{:?}", report.sinks))?;
+    let untracked = syndicator.tracker("broken").is_some_and(|t| t.published().count() == 0);
+    check("while the failing one tracks none", untracked, || format!("{:?}", report.sinks))?;
+    Ok(())
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! Plain text check: convert a markdown-heavy note to plain text, then post a long one to
+//! a mock Twitter API with and without conversion, checking what each tweet says.
+//!
+//! ```sh
+//! cargo run --example plain_text   # or `just plain-text`
+//! ```
+//!
+//! The long note is a list of accented words, so it's far more bytes than characters, and
+//! one tweet longer than fits.
+
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, HttpClient, HttpError, HttpRequest, HttpResponse, LinkStyle, PlainTextOptions, SyndicationSink,
+    default_process_node, to_plain_text, to_plain_text_with, to_syndication_format,
+};
+use syndicate_json_canvas_sinks::TwitterSink;
+
+const MARKDOWN: &str = "# Reading list
+
+Some **bold** and _soft_ words, see [the docs](https://example.com/docs) or <https://example.com>.
+
+
+
+- first ![a diagram](img.png)
+- second with `code`
+  - nested
+
+> quoted";
+
+const PLAIN: &str = "Reading list
+
+Some bold and soft words, see the docs (https://example.com/docs) or https://example.com.
+
+• first a diagram
+• second with code
+  • nested
+
+quoted";
+
+/// The mock Twitter API, keeping the text of every tweet
+#[derive(Debug, Default)]
+struct MockTwitter {
+    tweets: Mutex<Vec<String>>,
+}
+
+impl HttpClient for MockTwitter {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        let body: serde_json::Value = serde_json::from_str(request.body.as_deref().unwrap_or("{}"))
+            .map_err(|e| HttpError::InvalidRequest(e.to_string()))?;
+        let mut tweets = self.tweets.lock().unwrap();
+        tweets.push(body["text"].as_str().unwrap_or_default().to_string());
+        let body = format!(r#"{{"data": {{"id": "{}"}}}}"#, tweets.len());
+        Ok(HttpResponse { status: 201, headers: Vec::new(), body })
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-plain-text-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    // Conversion alone
+    let plain = to_plain_text(MARKDOWN);
+    check("markdown converts to plain text", plain == PLAIN, || format!("{:?}", plain))?;
+    let url_only = to_plain_text_with(MARKDOWN, &PlainTextOptions { link_style: LinkStyle::UrlOnly });
+    let urls = url_only.contains("see https://example.com/docs or") && !url_only.contains("the docs");
+    check("links can be rendered as their URL only", urls, || format!("{:?}", url_only))?;
+
+    // A short note fits one tweet as converted, keeping its lines
+    let tweets = post(scratch, "short", "Notes:\n\n- **one**\n- [two](https://example.com)", true)?;
+    let one = tweets == ["Notes:\n\n• one\n• two (https://example.com)"];
+    check("a converted note keeps its bullets on lines of their own", one, || format!("{:?}", tweets))?;
+    let tweets = post(scratch, "literal", "Notes:\n\n- **one**", false)?;
+    let literal = tweets == ["Notes:\n\n- **one**"];
+    check("without conversion markdown is posted as written", literal, || format!("{:?}", tweets))?;
+
+    // A long note is threaded after conversion, by characters, keeping its lines
+    let items: Vec<String> = (1..=30).map(|i| format!("- **élément** numéro {} — à vérifier", i)).collect();
+    let long = format!("Liste à faire :\n\n{}", items.join("\n"));
+    let converted = to_plain_text(&long);
+    let tweets = post(scratch, "thread", &long, true)?;
+    let fits = tweets.iter().all(|tweet| tweet.chars().count() <= 280);
+    let bytes = tweets.iter().any(|tweet| tweet.len() > 280);
+    check("each tweet fits in 280 characters, though not 280 bytes", fits && bytes, || format!("{:?}", tweets))?;
+    let texts: Vec<&str> = tweets.iter().map(|tweet| tweet.rsplit_once(" (").map_or("", |(text, _)| text)).collect();
+    let bullets = texts.iter().flat_map(|text| text.lines().skip(1)).filter(|line| !line.is_empty());
+    let lines = bullets.clone().all(|line| line.starts_with("• ")) && bullets.count() == 30;
+    let first = "Liste à faire :\n\n• élément numéro 1 — à vérifier\n•";
+    let kept = texts.len() > 1 && texts[0].starts_with(first);
+    check("a thread keeps one bullet per line", kept && lines, || format!("{:?}", texts))?;
+    let total = tweets.len();
+    let numbered = tweets.iter().enumerate().all(|(i, tweet)| tweet.ends_with(&format!("({}/{})", i + 1, total)));
+    let words = |text: &str| text.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+    let whole = numbered && words(&texts.join(" ")) == words(&converted);
+    check("and together the numbered tweets are the converted text", whole, || format!("{:?}", texts))?;
+    Ok(())
+}
+
+/// Post `text` as a red note through a Twitter sink with a mock API, and return the tweets
+fn post(scratch: &Path, name: &str, text: &str, plain: bool) -> Result<Vec<String>, Box<dyn Error>> {
+    let canvas = CanvasBuilder::new().text_node("9a10000000000001", text).color(PresetColor::Red).build()?;
+    let items = to_syndication_format(&canvas, Some(default_process_node))?;
+    let api = Arc::new(MockTwitter::default());
+    let tracker = scratch.join(format!("{}.toml", name));
+    let mut sink = TwitterSink::new("test-token", tracker)?.with_http_client(api.clone());
+    if plain {
+        sink = sink.with_plain_text(PlainTextOptions::default());
+    }
+    sink.publish(&items, false)?;
+    let tweets = api.tweets.lock().unwrap().clone();
+    Ok(tweets)
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
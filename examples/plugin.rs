@@ -0,0 +1,132 @@
+//! Plugin check: load the example plugin from a plugins folder and publish a canvas through
+//! it, next to a plugin speaking a protocol version this build doesn't.
+//!
+//! ```sh
+//! cargo build --example plugin_echo && cargo run --example plugin   # or `just plugin`
+//! ```
+//!
+//! The folder holds a copy of `plugin_echo` named `echo`, and `future`, a shell script claiming protocol version 99.
+//! The canvas is published to the echo plugin and a [`DirectorySink`], edited, and then
+//! given a node the plugin crashes on.
+
+use std::error::Error;
+use std::path::Path;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, SinkOutcome, Syndicator};
+use syndicate_json_canvas_sinks::{DirectorySink, PluginError, PluginSink, discover_plugins};
+
+const NOTES: &[(&str, &str)] = &[("p1a9000000000001", "First note"), ("p1a9000000000002", "Second note")];
+
+/// Node the plugin is configured to crash on
+const CRASHER: (&str, &str) = ("p1a9000000000003", "The plugin can't take this one");
+
+const FUTURE_PLUGIN: &str = "#!/bin/sh\necho '{\"abi\": 99, \"name\": \"future\"}'\n";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-plugin-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let echo = std::env::current_exe()?.with_file_name(format!("plugin_echo{}", std::env::consts::EXE_SUFFIX));
+    if !echo.is_file() {
+        return Err("build the example plugin first: cargo build --example plugin_echo".into());
+    }
+    let plugins = scratch.join("plugins");
+    std::fs::create_dir_all(&plugins)?;
+    std::fs::copy(&echo, plugins.join("echo"))?;
+    write_executable(&plugins.join("future"), FUTURE_PLUGIN)?;
+    std::fs::write(plugins.join("README.md"), "Not a plugin")?;
+
+    let found = discover_plugins(&plugins)?;
+    let names: Vec<String> = found.iter().filter_map(|p| Some(p.file_name()?.to_str()?.to_string())).collect();
+    check("only executables are discovered", names == ["echo", "future"], || format!("{:?}", names))?;
+
+    let out = scratch.join("echoed.jsonl");
+    let config = serde_json::json!({"out": out, "crash_on": CRASHER.0});
+    let future = PluginSink::load(plugins.join("future"), config.clone());
+    let rejected = matches!(future, Err(PluginError::AbiMismatch { found: 99, expected: 2, .. }));
+    check("a plugin of another protocol version is rejected", rejected, || format!("{:?}", future.err()))?;
+    let refused = PluginSink::load(plugins.join("echo"), serde_json::json!({}));
+    let refused = matches!(refused, Err(PluginError::Refused { .. }));
+    check("a plugin can refuse its configuration", refused, String::new)?;
+
+    let canvas_path = scratch.join("plugin.canvas");
+    write_canvas(&canvas_path, NOTES)?;
+    let mut syndicator = Syndicator::new(Config::new(&canvas_path))?;
+    syndicator.add_sink(PluginSink::load(plugins.join("echo"), config)?)?;
+    syndicator.add_sink(DirectorySink::new(scratch.join("site")))?;
+
+    // Round trip: items go out as JSON Lines and results come back with URLs
+    let report = syndicator.process_once()?;
+    let lines = echoed(&out)?;
+    let delivered = lines.len() == 2 && lines.iter().all(|line| line["change"] == "new");
+    check("the plugin gets every item", delivered, || format!("{:?}", lines))?;
+    let outcome = sink_outcome(&report.sinks, "echo")?;
+    let urls: Vec<&String> = outcome.urls.values().collect();
+    let reported = urls == ["echo://p1a9000000000001", "echo://p1a9000000000002"];
+    check("the plugin's URLs are reported", reported, || format!("{:?}", urls))?;
+
+    write_canvas(&canvas_path, &[(NOTES[0].0, "First note, edited"), NOTES[1]])?;
+    syndicator.process_once()?;
+    let lines = echoed(&out)?;
+    let updated = lines.len() == 3 && lines[2]["change"] == "updated" && lines[2]["text"] == "First note, edited";
+    check("edits reach a plugin that takes updates", updated, || format!("{:?}", lines))?;
+
+    // A crash fails the plugin's own batch, and nothing else
+    write_canvas(&canvas_path, &[(NOTES[0].0, "First note, edited"), NOTES[1], CRASHER])?;
+    let report = syndicator.process_once()?;
+    let crashed = sink_outcome(&report.sinks, "echo")?.error.clone().unwrap_or_default();
+    let failed = crashed.contains("did not exit normally") || crashed.contains("exited");
+    check("a crashing plugin fails its batch", failed, || crashed.clone())?;
+    let directory = sink_outcome(&report.sinks, "directory")?;
+    let others = directory.error.is_none() && directory.items == [CRASHER.0];
+    check("other sinks publish regardless", others, || format!("{:?}", directory))?;
+    let tracked = syndicator.tracker("echo").is_some_and(|t| !t.is_published(&CRASHER.0.parse().unwrap()));
+    check("the crashed item is tried again next run", tracked, String::new)?;
+    Ok(())
+}
+
+fn write_canvas(canvas_path: &Path, notes: &[(&str, &str)]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (node_id, text) in notes {
+        builder = builder.text_node(*node_id, *text).color(PresetColor::Red);
+    }
+    std::fs::write(canvas_path, serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+fn write_executable(path: &Path, contents: &str) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+/// Lines the echo plugin wrote so far
+fn echoed(out: &Path) -> Result<Vec<serde_json::Value>, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(out).unwrap_or_default();
+    Ok(contents.lines().map(serde_json::from_str).collect::<Result<_, _>>()?)
+}
+
+fn sink_outcome<'a>(outcomes: &'a [SinkOutcome], sink: &str) -> Result<&'a SinkOutcome, Box<dyn Error>> {
+    Ok(outcomes.iter().find(|o| o.sink == sink).ok_or_else(|| format!("{} didn't run", sink))?)
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
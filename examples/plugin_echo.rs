@@ -0,0 +1,57 @@
+//! A tiny sink plugin, for trying the plugin protocol and for `examples/plugin.rs`
+//!
+//! ```sh
+//! cargo build --example plugin_echo
+//! cp target/debug/examples/plugin_echo <PLUGIN_DIR>/
+//! ```
+//!
+//! It appends every item it's given to the JSON Lines file named by `out` in its
+//! configuration and answers with an `echo://<node-id>` URL for each. With `crash_on` set to a
+//! node ID, it aborts when given that node, to show a crashing plugin only fails its own sink.
+
+use std::error::Error;
+use std::io::{Read, Write};
+
+use serde_json::{Value, json};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let call = std::env::args().nth(1).unwrap_or_default();
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+
+    match call.as_str() {
+        "describe" => println!("{}", json!({"abi": 2, "name": "echo", "version": "0.2.0", "supports_updates": true})),
+        "configure" | "check" => {
+            let request: Value = serde_json::from_str(&input)?;
+            let verdict = match request["config"]["out"].as_str() {
+                Some(_) => json!({"ok": true}),
+                None => json!({"ok": false, "error": "`out` must name the file to append items to"}),
+            };
+            println!("{}", verdict);
+        }
+        "publish" => publish(&input)?,
+        _ => return Err(format!("unknown call `{}`", call).into()),
+    }
+    Ok(())
+}
+
+/// Append the JSON Lines items to `out` and print a result line for each
+fn publish(input: &str) -> Result<(), Box<dyn Error>> {
+    let config: Value = serde_json::from_str(&std::env::var("SYNDICATE_PLUGIN_CONFIG")?)?;
+    let mut lines = String::new();
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
+        let item: Value = serde_json::from_str(line)?;
+        let node_id = item["id"].as_str().ok_or("item without an id")?;
+        if config["crash_on"].as_str() == Some(node_id) {
+            std::process::abort();
+        }
+        lines.push_str(&json!({"node_id": node_id, "change": item["change"], "text": item["text"]}).to_string());
+        lines.push('\n');
+        println!("{}", json!({"node_id": node_id, "status": "ok", "url": format!("echo://{}", node_id)}));
+    }
+    if std::env::var("SYNDICATE_DRY_RUN").as_deref() != Ok("1") {
+        let out = config["out"].as_str().ok_or("no `out` configured")?;
+        std::fs::OpenOptions::new().create(true).append(true).open(out)?.write_all(lines.as_bytes())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+//! Round-trip check: publish a fixture canvas, edit it, publish again, and check after each
+//! step that the published site still holds together.
+//!
+//! ```sh
+//! cargo run --example roundtrip   # exits non-zero if any invariant breaks
+//! ```
+//!
+//! `tests/fixtures/chained.canvas` is published for real to a [`DirectorySink`] in a scratch
+//! directory with the clock pinned. After each step every site link must lead to a post, the
+//! posts must match the tracker, and a second run must change nothing. Then one node is
+//! edited, one added and one deleted (and pruned), and everything is checked again.
+
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::TimeZone;
+use serde_json::{Value, json};
+use syndicate_json_canvas_lib::{Config, MockClock, Syndicator, read_tree};
+use syndicate_json_canvas_sinks::DirectorySink;
+use syndicate_json_canvas_sinks::consistency::{Inconsistency, check_links, check_tracker, check_unchanged};
+
+/// Node whose text is edited
+const EDITED: &str = "c4a1000000000002";
+/// Node deleted from the canvas and pruned
+const DELETED: &str = "c4a1000000000004";
+/// Node added, linked from the end of the chain
+const ADDED: &str = "c4a1000000000006";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("chained.canvas");
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-roundtrip-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let canvas_path = scratch.join("chained.canvas");
+    std::fs::copy(&fixture, &canvas_path)?;
+
+    let result = run(&canvas_path, &scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    let failed = result?;
+    if failed > 0 {
+        return Err(format!("{} round-trip steps broke an invariant", failed).into());
+    }
+    Ok(())
+}
+
+/// Run every step, returning how many failed
+fn run(canvas_path: &Path, scratch: &Path) -> Result<usize, Box<dyn Error>> {
+    let site = scratch.join("site");
+    let clock = Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap()));
+    let mut config = Config::new(canvas_path);
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(&site).with_clock(clock.clone()))?;
+
+    let mut failed = 0;
+    failed += publish(&mut syndicator, &site, "first run")?;
+
+    clock.advance(chrono::Duration::days(1));
+    edit_canvas(canvas_path)?;
+    failed += publish(&mut syndicator, &site, "after editing")?;
+
+    let plans = syndicator.plan_prune()?;
+    syndicator.prune(&plans)?;
+    let removed: Vec<&str> = plans.iter().flat_map(|plan| &plan.files).map(|file| file.node_id.as_str()).collect();
+    let mut problems = check(&syndicator, &site)?;
+    if removed != [DELETED] {
+        problems.push(format!("pruned {:?} instead of the deleted node", removed));
+    }
+    failed += outcome("after pruning", &problems);
+    failed += publish(&mut syndicator, &site, "after pruning, run again")?;
+    Ok(failed)
+}
+
+/// Run the pipeline, check the site, then run again and check nothing changed
+fn publish(syndicator: &mut Syndicator, site: &Path, step: &str) -> Result<usize, Box<dyn Error>> {
+    let report = syndicator.process_once()?;
+    let mut problems = check(syndicator, site)?;
+    if let Some(error) = report.error {
+        problems.push(format!("run failed: {}", error));
+    }
+
+    let before = read_tree(site)?;
+    let again = syndicator.process_once()?;
+    if !again.new_items.is_empty() || !again.updated_items.is_empty() {
+        problems.push(format!("second run published {:?} and updated {:?}", again.new_items, again.updated_items));
+    }
+    problems.extend(check_unchanged(site, &before)?.iter().map(Inconsistency::to_string));
+    Ok(outcome(step, &problems))
+}
+
+/// Every broken invariant of the site and the sink's tracker
+fn check(syndicator: &Syndicator, site: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut problems: Vec<Inconsistency> = check_links(site)?;
+    for target in syndicator.targets() {
+        problems.extend(check_tracker(target.sink.as_ref(), &target.tracker)?);
+    }
+    Ok(problems.iter().map(Inconsistency::to_string).collect())
+}
+
+fn outcome(step: &str, problems: &[String]) -> usize {
+    if problems.is_empty() {
+        println!("ok      {}", step);
+        return 0;
+    }
+    println!("FAILED  {}", step);
+    for problem in problems {
+        println!("  {}", problem);
+    }
+    1
+}
+
+/// Edit one node's text, add a node linked from the end of the chain, and delete another
+fn edit_canvas(canvas_path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut canvas: Value = serde_json::from_str(&std::fs::read_to_string(canvas_path)?)?;
+    let nodes = canvas["nodes"].as_array_mut().ok_or("canvas has no nodes")?;
+    for node in nodes.iter_mut().filter(|node| node["id"] == EDITED) {
+        node["text"] = json!("Second link builds on the first, now with a caveat.");
+    }
+    nodes.retain(|node| node["id"] != DELETED);
+    nodes.push(json!({
+        "id": ADDED, "type": "text", "text": "A postscript to the conclusion.",
+        "x": 1440, "y": 0, "width": 360, "height": 120, "color": "1"
+    }));
+
+    let edges = canvas["edges"].as_array_mut().ok_or("canvas has no edges")?;
+    edges.retain(|edge| edge["fromNode"] != DELETED && edge["toNode"] != DELETED);
+    edges.push(json!({
+        "id": "c4e1000000000005", "fromNode": "c4a1000000000003", "fromSide": "right",
+        "toNode": ADDED, "toSide": "left"
+    }));
+
+    std::fs::write(canvas_path, serde_json::to_string_pretty(&canvas)?)?;
+    Ok(())
+}
@@ -0,0 +1,174 @@
+//! Run journal check: publish to two sinks, one of which fails partway, then resume the
+//! unfinished run after the canvas changed and check it sends only what it planned.
+//!
+//! ```sh
+//! cargo run --example run_journal   # or `just run-journal`
+//! ```
+//!
+//! The failing sink is an in-memory sink wrapped in a [`ChaosSink`] that fails its first
+//! batch; the run is resumed by a fresh syndicator without it, as after a restart.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::{
+    ChaosSink, Config, ReportFormat, ReportWriter, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+    SyntheticCanvas, run_id,
+};
+
+/// Items a sink committed, by node ID, shared across syndicators like a real destination
+type Ledger = Arc<Mutex<HashMap<String, usize>>>;
+
+/// Stages items in a batch and only records them on commit
+struct Recording {
+    name: &'static str,
+    ledger: Ledger,
+    staged: Vec<NodeId>,
+}
+
+impl Recording {
+    fn new(name: &'static str, ledger: &Ledger) -> Self {
+        Self { name, ledger: ledger.clone(), staged: Vec::new() }
+    }
+}
+
+impl SyndicationSink for Recording {
+    fn begin(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        self.staged.clear();
+        Ok(())
+    }
+
+    fn commit(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        let mut ledger = self.ledger.lock().unwrap();
+        for node_id in self.staged.drain(..) {
+            *ledger.entry(node_id.to_string()).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    fn abort(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        self.staged.clear();
+        Ok(())
+    }
+
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        self.staged.extend(items.keys().cloned());
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-run-journal-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let canvas_path = scratch.join("journal.canvas");
+    let reports = scratch.join("reports");
+    let (site, feed) = (Ledger::default(), Ledger::default());
+    std::fs::write(&canvas_path, SyntheticCanvas::new(2).to_json()?)?;
+    let first: Vec<String> = (0..2).map(SyntheticCanvas::node_id).collect();
+
+    // The feed fails, so the run stops with the site done and the feed left
+    let mut syndicator = new_syndicator(&canvas_path, &reports)?;
+    syndicator.add_sink(Recording::new("site", &site))?;
+    syndicator.add_sink(ChaosSink::new(Recording::new("feed", &feed), 1).with_fail_first_batches(1))?;
+    let report = syndicator.process_once()?;
+    let eligible: Vec<NodeId> = first.iter().map(|id| id.parse()).collect::<Result<_, _>>()?;
+    let hash = report.canvas_hash.clone().unwrap_or_default();
+    let unordered = run_id(&hash, eligible.iter().rev()) == run_id(&hash, &eligible);
+    let derived = unordered && report.run_id == Some(run_id(&hash, &eligible));
+    check("the run ID comes from the canvas hash and the eligible items", derived, || format!("{:?}", report.run_id))?;
+    let journal = syndicator.unfinished_run()?.ok_or("no journal left behind")?;
+    let left: Vec<(&str, &[String])> = journal.remaining().map(|step| (step.sink.as_str(), &*step.planned)).collect();
+    let recorded = left == [("feed", &first[..])] && journal.step("site").is_some_and(|step| step.completed);
+    check("the journal records the sink done and the one left", recorded, || format!("{:?}", journal.steps()))?;
+
+    // After a restart, with a note added since, resuming sends the feed only what it planned
+    std::fs::write(&canvas_path, SyntheticCanvas::new(3).to_json()?)?;
+    let mut syndicator = new_syndicator(&canvas_path, &reports)?;
+    syndicator.add_sink(Recording::new("site", &site))?;
+    syndicator.add_sink(Recording::new("feed", &feed))?;
+    let resumed = syndicator.resume()?;
+    let planned_only = committed(&feed) == first && committed(&site) == first;
+    check("resuming sends only the items left, nothing new", planned_only, || format!("{:?}", committed(&feed)))?;
+    let kept = resumed.run_id == report.run_id && resumed.resumed;
+    check("the resumed run keeps its ID", kept, || format!("{:?}", resumed.run_id))?;
+    let run = report.run_id.clone().unwrap_or_default();
+    let moved = syndicator.unfinished_run()?.is_none() && archived(&reports).iter().any(|name| name.contains(&run));
+    check("the finished journal is archived with the reports", moved, || format!("{:?}", list(&reports)))?;
+
+    // New work is taken by the next run, which journals and archives itself
+    let next = syndicator.process_once()?;
+    let all: Vec<String> = (0..3).map(SyntheticCanvas::node_id).collect();
+    let caught_up = committed(&site) == all && committed(&feed) == all && !next.resumed;
+    check("the next run publishes the new note to both sinks", caught_up, || format!("{:?}", committed(&site)))?;
+    let own = archived(&reports).len() == 2 && next.run_id != report.run_id;
+    check("and archives its own journal", own, || format!("{:?}", list(&reports)))?;
+
+    // A run that doesn't resume an unfinished one archives its journal as it stands
+    std::fs::write(&canvas_path, SyntheticCanvas::new(4).to_json()?)?;
+    let mut syndicator = new_syndicator(&canvas_path, &reports)?;
+    syndicator.add_sink(Recording::new("site", &site))?;
+    syndicator.add_sink(ChaosSink::new(Recording::new("feed", &feed), 1).with_fail_first_batches(1))?;
+    let failed = syndicator.process_once()?.run_id.unwrap_or_default();
+    std::fs::write(&canvas_path, SyntheticCanvas::new(5).to_json()?)?;
+    let mut syndicator = new_syndicator(&canvas_path, &reports)?;
+    syndicator.add_sink(Recording::new("site", &site))?;
+    syndicator.add_sink(Recording::new("feed", &feed))?;
+    syndicator.process_once()?;
+    let left = archived(&reports).into_iter().find(|name| name.contains(&failed)).unwrap_or_default();
+    let journal = std::fs::read_to_string(reports.join(&left)).unwrap_or_default();
+    let as_it_stood = journal.contains("\"completed\": false");
+    check("a new run archives an unfinished journal as it stands", as_it_stood, || left.clone())?;
+    let once = [&site, &feed].iter().all(|ledger| ledger.lock().unwrap().values().all(|count| *count == 1));
+    check("no sink got any item twice", once, || format!("{:?} {:?}", site, feed))?;
+    Ok(())
+}
+
+fn new_syndicator(canvas_path: &Path, reports: &Path) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(canvas_path);
+    config.options.report_writer = Some(ReportWriter::new(reports, ReportFormat::Json, 10));
+    Ok(Syndicator::new(config)?)
+}
+
+/// Node IDs a sink committed, ordered
+fn committed(ledger: &Ledger) -> Vec<String> {
+    let mut node_ids: Vec<String> = ledger.lock().unwrap().keys().cloned().collect();
+    node_ids.sort();
+    node_ids
+}
+
+/// Archived journals, oldest first
+fn archived(reports: &Path) -> Vec<String> {
+    list(reports).into_iter().filter(|name| name.starts_with("journal-")).collect()
+}
+
+fn list(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.file_name().to_string_lossy().into_owned()).collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Raw HTML check: publish notes holding a pasted script, event handlers and benign inline
+//! HTML to a directory with `HtmlPolicy::StripDangerous`, and check what each post keeps
+//! and what the run reports as stripped; then check the other two policies.
+//!
+//! ```sh
+//! cargo run --example sanitize   # or `just sanitize`
+//! ```
+//!
+//! All three notes are red, so every one is published in the same run.
+
+use std::error::Error;
+use std::path::Path;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::warnings::codes;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, HtmlPolicy, ProcessReport, Syndicator, sanitize_html};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const SCRIPT: &str = "5a10000000000001";
+const HANDLERS: &str = "5a10000000000002";
+const BENIGN: &str = "5a10000000000003";
+
+const SCRIPT_TEXT: &str = "Copied embed:\n\n<script>steal(document.cookie)</script>\n\nThe rest stays. `<script>` in code too.";
+const HANDLERS_TEXT: &str = "Read <a href=\"https://example.com\" onclick=\"steal()\">this</a> \
+    or <a href=\"javascript:steal()\">that</a>, <img src=\"cat.png\" onerror=\"steal()\" alt=\"a cat\">.";
+const BENIGN_TEXT: &str = "Press <kbd>Ctrl</kbd> and <em>wait</em>.";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-sanitize-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let canvas_path = scratch.join("pasted.canvas");
+    let canvas = CanvasBuilder::new()
+        .text_node(SCRIPT, SCRIPT_TEXT)
+        .color(PresetColor::Red)
+        .text_node(HANDLERS, HANDLERS_TEXT)
+        .color(PresetColor::Red)
+        .text_node(BENIGN, BENIGN_TEXT)
+        .color(PresetColor::Red)
+        .build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+    let site = scratch.join("site");
+    let mut syndicator = Syndicator::new(Config::new(&canvas_path))?;
+    syndicator.add_sink(DirectorySink::new(&site).with_html_policy(HtmlPolicy::StripDangerous))?;
+    let report = syndicator.process_once()?;
+
+    // Stripped from the posts
+    let post = read_post(&site, SCRIPT)?;
+    let script = !post.contains("steal") && post.contains("Copied embed:") && post.contains("The rest stays.");
+    check("a script element is removed with its contents", script, || post.clone())?;
+    let code = post.contains("`<script>` in code too");
+    check("HTML-looking text in code is left alone", code, || post.clone())?;
+    let post = read_post(&site, HANDLERS)?;
+    let handlers = !post.contains("onclick") && !post.contains("onerror");
+    check("event-handler attributes are removed", handlers, || post.clone())?;
+    let urls = !post.contains("javascript:") && post.contains("<a>that</a>");
+    check("and so are `javascript:` links", urls, || post.clone())?;
+    let kept = post.contains("<a href=\"https://example.com\">this</a>") && post.contains("alt=\"a cat\"");
+    check("the elements keep their other attributes", kept, || post.clone())?;
+    let post = read_post(&site, BENIGN)?;
+    check("benign inline HTML is kept as written", post.contains(BENIGN_TEXT), || post.clone())?;
+
+    // Reported per node
+    let script = stripped(&report, SCRIPT);
+    let reported = script.iter().any(|message| message.contains("removed <script> element"));
+    check("the run reports the script stripped from its node", reported, || format!("{:?}", script))?;
+    let handlers = stripped(&report, HANDLERS);
+    let named = handlers.len() == 1 && ["onclick", "onerror", "href"].iter().all(|attr| handlers[0].contains(attr));
+    check("and each attribute stripped from the other", named, || format!("{:?}", handlers))?;
+    let quiet = stripped(&report, BENIGN).is_empty();
+    check("with nothing reported for the benign note", quiet, || format!("{:?}", report.sinks))?;
+
+    // The other policies
+    let escaped = sanitize_html(BENIGN_TEXT, HtmlPolicy::EscapeAll);
+    let shown = escaped.text == "Press &lt;kbd&gt;Ctrl&lt;/kbd&gt; and &lt;em&gt;wait&lt;/em&gt;.";
+    check("escaping shows every tag as text", shown && escaped.stripped.len() == 4, || format!("{:?}", escaped))?;
+    let allowed = sanitize_html(SCRIPT_TEXT, HtmlPolicy::AllowAll);
+    let untouched = allowed.text == SCRIPT_TEXT && allowed.stripped.is_empty();
+    check("allowing all leaves even a script as written", untouched, || format!("{:?}", allowed))?;
+    Ok(())
+}
+
+/// Messages of the `html-stripped` warnings the sinks raised for `node_id`
+fn stripped(report: &ProcessReport, node_id: &str) -> Vec<String> {
+    report
+        .sinks
+        .iter()
+        .flat_map(|outcome| &outcome.warnings)
+        .filter(|w| w.code == codes::HTML_STRIPPED && w.node_id.as_deref() == Some(node_id))
+        .map(|w| w.message.clone())
+        .collect()
+}
+
+/// The published post of a node
+fn read_post(site: &Path, node_id: &str) -> Result<String, Box<dyn Error>> {
+    let name = list(site).into_iter().find(|name| name.contains(node_id)).ok_or(format!("no post of {}", node_id))?;
+    Ok(std::fs::read_to_string(site.join(name))?)
+}
+
+fn list(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.file_name().to_string_lossy().into_owned()).collect())
+        .unwrap_or_default()
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,274 @@
+//! Serial processing check: fire processing requests from several threads at once and
+//! check runs never overlap, and that queued requests are coalesced, dropped or waited on as
+//! configured.
+//!
+//! ```sh
+//! cargo run --example serial   # or `just serial`
+//! ```
+//!
+//! Runs publish in dry run to a sink whose batches hold a reentrancy guard, so an overlapping
+//! run would be counted. The first run blocks until every thread has sent its requests, so they
+//! are all waiting in the queue when it finishes.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    Backpressure, CanvasBuilder, Config, PipelineStatus, ProcessingOptions, ProcessingReason, ProcessingRequest,
+    ProcessingSender, SinkError, SyndicationFormat, SyndicationSink, Syndicator, WatchCommand,
+};
+
+const THREADS: usize = 4;
+
+/// What the guard sink saw, shared with the check
+#[derive(Default)]
+struct Probe {
+    /// Whether a batch is between `begin` and `commit`
+    busy: AtomicBool,
+    /// Batches begun while another was still going
+    overlaps: AtomicUsize,
+    runs: AtomicUsize,
+    /// Holds the first run until a message (or hang-up) arrives
+    gate: Mutex<Option<Receiver<()>>>,
+    /// Told when the first run has begun
+    started: Mutex<Option<Sender<()>>>,
+}
+
+struct GuardSink(Arc<Probe>);
+
+impl SyndicationSink for GuardSink {
+    fn begin(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        if self.0.busy.swap(true, Ordering::SeqCst) {
+            self.0.overlaps.fetch_add(1, Ordering::SeqCst);
+        }
+        self.0.runs.fetch_add(1, Ordering::SeqCst);
+        if let Some(started) = self.0.started.lock().unwrap().take() {
+            let _ = started.send(());
+        }
+        if let Some(gate) = self.0.gate.lock().unwrap().take() {
+            let _ = gate.recv();
+        }
+        std::thread::sleep(Duration::from_millis(2));
+        Ok(())
+    }
+
+    fn publish(&mut self, _items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        Ok(())
+    }
+
+    fn commit(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        self.0.busy.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn abort(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        self.0.busy.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "guard"
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-serial-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let requested = ProcessingRequest::new(ProcessingReason::Requested);
+
+    // Coalescing: however many identical requests queued up, one more run
+    let options = ProcessingOptions::default().with_capacity(64);
+    let watch = Watch::start(&scratch.join("coalesce"), options)?;
+    let queued = watch.fire(10, &requested);
+    check("requests from every thread are queued", queued == THREADS * 10, || queued.to_string())?;
+    let depth = watch.sender.depth();
+    check("queue depth counts waiting requests", depth == THREADS * 10, || depth.to_string())?;
+    let probe = watch.finish(2)?;
+    check("identical requests are carried out once", probe.runs() == 2, || probe.runs().to_string())?;
+    check("coalesced runs never overlap", probe.overlaps() == 0, || probe.overlaps().to_string())?;
+
+    // Without coalescing, every request gets its own run
+    let options = ProcessingOptions::default().with_capacity(64).with_coalesce(false);
+    let watch = Watch::start(&scratch.join("each"), options)?;
+    watch.fire(10, &requested);
+    let probe = watch.finish(1 + THREADS * 10)?;
+    check("without coalescing each request runs", probe.runs() == 1 + THREADS * 10, || probe.runs().to_string())?;
+    check("those runs never overlap either", probe.overlaps() == 0, || probe.overlaps().to_string())?;
+
+    // Dropping: a full queue turns requests away and counts them
+    let options = ProcessingOptions::default().with_capacity(3).with_backpressure(Backpressure::Drop);
+    let mut watch = Watch::start(&scratch.join("drop"), options)?;
+    let queued = watch.fire(5, &requested);
+    check("a full queue drops requests", queued == 3, || queued.to_string())?;
+    let dropped = watch.sender.dropped();
+    check("dropped requests are counted", dropped == THREADS * 5 - 3, || dropped.to_string())?;
+    watch.settle(2);
+    let statuses = watch.status()?;
+    let reported = statuses.iter().all(|s| s.queued == 0 && s.dropped == THREADS * 5 - 3);
+    check("status reports queue depth and drops", reported, || format!("{:?}", statuses))?;
+    let probe = watch.finish(2)?;
+    check("queued requests still run after drops", probe.runs() == 2, || probe.runs().to_string())?;
+
+    // Blocking: senders wait for room instead of dropping
+    let options = ProcessingOptions::default().with_capacity(2).with_backpressure(Backpressure::Block);
+    let mut watch = Watch::start(&scratch.join("block"), options)?;
+    let gate = watch.release.take();
+    let queued = std::thread::scope(|scope| {
+        let firing = scope.spawn(|| watch.fire(5, &requested));
+        std::thread::sleep(Duration::from_millis(200));
+        // Two in the queue, and one blocked sender per thread
+        let waiting = !firing.is_finished() && watch.sender.depth() == 2 + THREADS;
+        drop(gate);
+        (waiting, firing.join().unwrap())
+    });
+    check("blocking senders wait while the queue is full", queued.0, String::new)?;
+    check("and every request gets in", queued.1 == THREADS * 5, || queued.1.to_string())?;
+    let probe = watch.finish(2)?;
+    check("runs for blocked senders never overlap", probe.overlaps() == 0, || probe.overlaps().to_string())?;
+
+    // Every kind of trigger at once: requests, console commands and saves of the canvas
+    let mut watch = Watch::start(&scratch.join("mixed"), ProcessingOptions::default().with_coalesce(false))?;
+    watch.release();
+    let canvas_path = watch.canvas_path.clone();
+    std::thread::scope(|scope| {
+        for thread in 0..THREADS {
+            let (sender, commands) = (watch.sender.clone(), watch.commands.clone());
+            let canvas_path = &canvas_path;
+            scope.spawn(move || {
+                for i in 0..5 {
+                    let _ = sender.request(ProcessingRequest::new(ProcessingReason::Requested));
+                    let _ = commands.send(WatchCommand::Process);
+                    if thread == 0 {
+                        let _ = write_canvas(canvas_path, &format!("Edit {}", i));
+                    }
+                    std::thread::sleep(Duration::from_millis(5));
+                }
+            });
+        }
+    });
+    let probe = watch.finish(2)?;
+    check("runs from every trigger never overlap", probe.overlaps() == 0, || probe.overlaps().to_string())?;
+    Ok(())
+}
+
+/// A syndicator watching its canvas on a thread of its own
+struct Watch {
+    canvas_path: std::path::PathBuf,
+    probe: Arc<Probe>,
+    sender: ProcessingSender,
+    commands: Sender<WatchCommand>,
+    release: Option<Sender<()>>,
+    thread: std::thread::JoinHandle<Result<(), String>>,
+}
+
+impl Watch {
+    /// Start watching, returning once the first run is held at the gate
+    fn start(dir: &Path, processing: ProcessingOptions) -> Result<Self, Box<dyn Error>> {
+        std::fs::create_dir_all(dir)?;
+        let canvas_path = dir.join("serial.canvas");
+        write_canvas(&canvas_path, "A note")?;
+
+        let (release, gate) = mpsc::channel();
+        let (started_tx, started) = mpsc::channel();
+        let probe = Arc::new(Probe::default());
+        *probe.gate.lock().unwrap() = Some(gate);
+        *probe.started.lock().unwrap() = Some(started_tx);
+
+        let mut config = Config::new(&canvas_path);
+        config.dry_run = true;
+        config.debounce = Duration::from_millis(20);
+        config.processing = processing;
+        let mut syndicator = Syndicator::new(config)?;
+        syndicator.add_sink(GuardSink(probe.clone()))?;
+        let sender = syndicator.processing_sender();
+        let (commands, commands_rx) = mpsc::channel();
+        let thread = std::thread::spawn(move || syndicator.watch(commands_rx).map_err(|e| e.to_string()));
+        started.recv_timeout(Duration::from_secs(10)).map_err(|_| "the first run didn't begin")?;
+        Ok(Self { canvas_path, probe, sender, commands, release: Some(release), thread })
+    }
+
+    /// Send `count` copies of `request` from each of several threads at once, returning
+    /// how many were queued
+    fn fire(&self, count: usize, request: &ProcessingRequest) -> usize {
+        std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let sender = self.sender.clone();
+                    let queued = move |_: &usize| matches!(sender.request(request.clone()), Ok(true));
+                    scope.spawn(move || (0..count).filter(queued).count())
+                })
+                .collect();
+            threads.into_iter().map(|thread| thread.join().unwrap()).sum()
+        })
+    }
+
+    /// Let the first run finish
+    fn release(&mut self) {
+        self.release.take();
+    }
+
+    /// Release the first run and wait for `runs` runs, and a quiet moment after
+    fn settle(&mut self, runs: usize) {
+        self.release();
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while self.probe.runs() < runs && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        // Give any extra runs the chance to show up
+        std::thread::sleep(Duration::from_millis(300));
+    }
+
+    fn status(&self) -> Result<Vec<PipelineStatus>, Box<dyn Error>> {
+        let (reply, statuses) = mpsc::channel();
+        self.commands.send(WatchCommand::Status(reply))?;
+        Ok(statuses.recv_timeout(Duration::from_secs(10))?)
+    }
+
+    /// [`settle`](Self::settle), then stop watching
+    fn finish(mut self, runs: usize) -> Result<Arc<Probe>, Box<dyn Error>> {
+        self.settle(runs);
+        self.commands.send(WatchCommand::Shutdown)?;
+        self.thread.join().map_err(|_| "the watcher panicked")??;
+        Ok(self.probe)
+    }
+}
+
+impl Probe {
+    fn runs(&self) -> usize {
+        self.runs.load(Ordering::SeqCst)
+    }
+
+    fn overlaps(&self) -> usize {
+        self.overlaps.load(Ordering::SeqCst)
+    }
+}
+
+fn write_canvas(canvas_path: &Path, text: &str) -> Result<(), Box<dyn Error>> {
+    let canvas = CanvasBuilder::new().text_node("5e41000000000001", text).color(PresetColor::Red).build()?;
+    std::fs::write(canvas_path, serde_json::to_string(&canvas)?)?;
+    Ok(())
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
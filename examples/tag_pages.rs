@@ -0,0 +1,147 @@
+//! Tag page check: publish tagged notes to a directory sink keeping tag pages, then add a
+//! note, retag one and prune them, checking the pages after each run.
+//!
+//! ```sh
+//! cargo run --example tag_pages   # or `just tag-pages`
+//! ```
+//!
+//! Notes are tagged by the groups around them, and each run is a day after the one before,
+//! so the pages' newest-first order can be checked. The directory sink doesn't rewrite a
+//! post when its node is edited, so a post is retagged by editing its file, as by hand.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::TimeZone;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, MockClock, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+/// A note's ID, the tags of the group around it (if any) and its text
+type Note = (&'static str, Option<&'static str>, &'static str);
+
+const BORROWING: Note = ("7a90000000000001", Some("rust"), "Borrowing is a promise.");
+const COFFEE: Note = ("7a90000000000002", Some("Rust, Café"), "Lifetimes over coffee.");
+const UNTAGGED: Note = ("7a90000000000003", None, "Nothing to file this under.");
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-tag-pages-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let canvas_path = scratch.join("tagged.canvas");
+    let site = scratch.join("site");
+    let tags = site.join("tags");
+    let clock = Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()));
+    write_canvas(&canvas_path, &[BORROWING])?;
+    let mut config = Config::new(&canvas_path);
+    config.options.clock = clock.clone();
+    config.options.removal_guard.accept_mass_removal = true;
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(&site).with_clock(clock.clone()).with_tag_pages(true))?;
+
+    // A tagged note gets its tag's page and the index
+    syndicator.process_once()?;
+    let rust = read(&tags.join("rust.md"));
+    let listed = rust.contains(&format!("{}.md) (2026-03-01)", BORROWING.0));
+    check("a tag gets a page listing its post", listed, || rust.clone())?;
+    let index = read(&tags.join("index.md"));
+    check("the index lists the tag with its count", index.contains("[#rust](/t/tags/rust.md) (1)"), || index.clone())?;
+
+    // A second note a day later, tagged with a unicode tag and another spelling of `rust`
+    clock.advance(chrono::Duration::days(1));
+    write_canvas(&canvas_path, &[BORROWING, COFFEE])?;
+    syndicator.process_once()?;
+    let rust = read(&tags.join("rust.md"));
+    let newest_first = rust.find(COFFEE.0).zip(rust.find(BORROWING.0)).is_some_and(|(new, old)| new < old);
+    let merged = newest_first && rust.contains("posts: 2");
+    check("spellings differing in case share a page, newest first", merged, || rust.clone())?;
+    let cafe = read(&tags.join("café.md"));
+    check("a unicode tag keeps its letters in its slug", cafe.contains(COFFEE.0), || cafe.clone())?;
+    let index = read(&tags.join("index.md"));
+    let counted = index.contains("(/t/tags/café.md) (1)") && index.contains("(/t/tags/rust.md) (2)");
+    check("the index counts every tag", counted, || index.clone())?;
+
+    // Retagged in its file, a post moves to its new tag's page with the next batch
+    let post = find_post(&site, BORROWING.0)?;
+    let retagged = std::fs::read_to_string(&post)?.replace("tags:\n  - \"rust\"", "tags:\n  - \"life\"");
+    std::fs::write(&post, retagged)?;
+    clock.advance(chrono::Duration::days(1));
+    write_canvas(&canvas_path, &[BORROWING, COFFEE, UNTAGGED])?;
+    syndicator.process_once()?;
+    let rust = read(&tags.join("rust.md"));
+    let life = read(&tags.join("life.md"));
+    let moved = !rust.contains(BORROWING.0) && rust.contains("posts: 1") && life.contains(BORROWING.0);
+    check("a retagged post moves to its new tag's page", moved, || format!("{}\n{}", rust, life))?;
+
+    // Pruning the last post of a tag removes its page
+    write_canvas(&canvas_path, &[BORROWING, UNTAGGED])?;
+    syndicator.process_once()?;
+    let plans = syndicator.plan_prune()?;
+    syndicator.prune(&plans)?;
+    let gone = !tags.join("rust.md").exists() && !tags.join("café.md").exists() && tags.join("life.md").exists();
+    check("pruning the last post of a tag removes its page", gone, || format!("{:?}", list(&tags)))?;
+    let index = read(&tags.join("index.md"));
+    let only_life = index.contains("life.md") && !index.contains("rust.md") && !index.contains("café.md");
+    check("and the index drops it", only_life, || index.clone())?;
+
+    // With no tagged posts left, neither are the pages
+    write_canvas(&canvas_path, &[UNTAGGED])?;
+    syndicator.process_once()?;
+    let plans = syndicator.plan_prune()?;
+    syndicator.prune(&plans)?;
+    check("pruning every tagged post removes the tag folder", !tags.exists(), || format!("{:?}", list(&tags)))?;
+    Ok(())
+}
+
+/// Write a canvas of red notes, each inside a group giving its tags, if it has any
+fn write_canvas(canvas_path: &Path, notes: &[Note]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (i, (id, tags, text)) in notes.iter().enumerate() {
+        let x = 1000 * i as i64;
+        if let Some(tags) = tags {
+            let label = format!("Tagged [tags: {}]", tags);
+            builder = builder.group_node(format!("{}-group", id)).label(label).at(x, 0).size(400, 400);
+        }
+        builder = builder.text_node(*id, *text).color(PresetColor::Red).at(x + 50, 50);
+    }
+    std::fs::write(canvas_path, serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+/// The published post of a node
+fn find_post(site: &Path, node_id: &str) -> Result<PathBuf, Box<dyn Error>> {
+    for entry in std::fs::read_dir(site)? {
+        let path = entry?.path();
+        if path.is_file() && path.to_string_lossy().contains(node_id) {
+            return Ok(path);
+        }
+    }
+    Err(format!("no post of {}", node_id).into())
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|e| format!("{}: {}", path.display(), e))
+}
+
+fn list(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.file_name().to_string_lossy().into_owned()).collect())
+        .unwrap_or_default()
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -0,0 +1,138 @@
+//! Canvas validation check: validate a canvas with one of each structural problem, and one
+//! without any.
+//!
+//! ```sh
+//! cargo run --example validate   # or `just validate`
+//! ```
+//!
+//! Duplicate node IDs and dangling edges can't be built with [`CanvasBuilder`], so they're
+//! added to the canvas's JSON by hand.
+
+use std::error::Error;
+use std::path::Path;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, CanvasIssue, CanvasIssueKind, Config, Selection, Syndicator, duplicate_node_ids, parse_canvas,
+    validate_canvas, validate_canvas_with,
+};
+
+const NOTE: (&str, &str) = ("7a00000000000001", "A note that's fine.");
+const EMPTY: &str = "7a00000000000002";
+const LOOPED: (&str, &str) = ("7a00000000000003", "A note pointing at itself.");
+const TWICE: &str = "7a00000000000004";
+const GONE: &str = "7a000000000000ff";
+const DANGLING: &str = "7a000000000000e1";
+const FULL_GROUP: &str = "7a000000000000a1";
+const EMPTY_GROUP: &str = "7a000000000000a2";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-validate-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    let json = canvas_json(true)?;
+    let (canvas, _) = parse_canvas(&json)?;
+
+    let issues = validate_canvas(&canvas);
+    let kinds: Vec<CanvasIssueKind> = issues.iter().map(|issue| issue.kind).collect();
+    let expected = [
+        CanvasIssueKind::DanglingEdge,
+        CanvasIssueKind::SelfLoop,
+        CanvasIssueKind::EmptyMarkedNode,
+        CanvasIssueKind::EmptyGroup,
+    ];
+    check("every structural problem is found, once", kinds == expected, || format!("{:#?}", issues))?;
+    let dangling = find(&issues, CanvasIssueKind::DanglingEdge);
+    let named = dangling.is_some_and(|i| i.edge_id.as_deref() == Some(DANGLING) && i.node_id.as_deref() == Some(GONE));
+    check("a dangling edge names the edge and the missing node", named, || format!("{:?}", dangling))?;
+    let looped = find(&issues, CanvasIssueKind::SelfLoop);
+    let named = looped.is_some_and(|i| i.node_id.as_deref() == Some(LOOPED.0));
+    check("a self-loop names its node", named, || format!("{:?}", looped))?;
+    let empty = find(&issues, CanvasIssueKind::EmptyMarkedNode);
+    let named = empty.is_some_and(|i| i.node_id.as_deref() == Some(EMPTY));
+    check("an empty red node is named", named, || format!("{:?}", empty))?;
+    let group = find(&issues, CanvasIssueKind::EmptyGroup);
+    let only_empty = group.is_some_and(|i| i.node_id.as_deref() == Some(EMPTY_GROUP) && i.message.contains("Later"));
+    check("only the group with nothing in it is empty", only_empty, || format!("{:?}", group))?;
+    let readable = issues.iter().all(|issue| issue.to_string().starts_with(issue.kind.code()));
+    check("issues print with their kind", readable, || format!("{:?}", issues))?;
+
+    // Selecting every node, an empty one is skipped whatever its color
+    let all = validate_canvas_with(&canvas, &Selection::All);
+    let unmarked = find(&all, CanvasIssueKind::EmptyMarkedNode).is_none() && all.len() == 3;
+    check("selecting every node, empty nodes aren't issues", unmarked, || format!("{:?}", all))?;
+
+    // Duplicate IDs only show in the JSON
+    let duplicates = duplicate_node_ids(&json);
+    let twice = duplicates.len() == 1 && duplicates[0].node_id.as_deref() == Some(TWICE);
+    check("duplicate node IDs are found in the JSON", twice, || format!("{:?}", duplicates))?;
+
+    // The syndicator reports both, and nothing for a clean canvas
+    let canvas_path = scratch.join("broken.canvas");
+    std::fs::write(&canvas_path, &json)?;
+    let reported = Syndicator::new(Config::new(&canvas_path))?.validate()?;
+    check("the syndicator reports every issue", reported.len() == 5, || format!("{:#?}", reported))?;
+    let canvas_path = scratch.join("clean.canvas");
+    std::fs::write(&canvas_path, canvas_json(false)?)?;
+    let clean = Syndicator::new(Config::new(&canvas_path))?.validate()?;
+    check("a clean canvas has no issues", clean.is_empty(), || format!("{:#?}", clean))?;
+    Ok(())
+}
+
+/// A canvas with a note inside a group, plus one of each problem if `broken`
+fn canvas_json(broken: bool) -> Result<String, Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new()
+        .group_node(FULL_GROUP)
+        .label("Drafts")
+        .at(0, 0)
+        .size(400, 400)
+        .text_node(NOTE.0, NOTE.1)
+        .color(PresetColor::Red)
+        .at(50, 50)
+        .size(200, 100);
+    if broken {
+        builder = builder
+            .text_node(EMPTY, "  ")
+            .color(PresetColor::Red)
+            .at(1000, 0)
+            .text_node(LOOPED.0, LOOPED.1)
+            .at(1000, 200)
+            .edge(LOOPED.0, LOOPED.0)
+            .text_node(TWICE, "The first copy.")
+            .at(1000, 400)
+            .group_node(EMPTY_GROUP)
+            .label("Later")
+            .at(2000, 0)
+            .size(400, 400);
+    }
+    let mut json = serde_json::to_value(builder.build()?)?;
+    if broken {
+        let copy = serde_json::json!({
+            "id": TWICE, "type": "text", "text": "The second copy.", "x": 1000, "y": 600, "width": 200, "height": 100
+        });
+        json["nodes"].as_array_mut().ok_or("no nodes in the canvas")?.push(copy);
+        let edge = serde_json::json!({ "id": DANGLING, "fromNode": NOTE.0, "toNode": GONE });
+        json["edges"].as_array_mut().ok_or("no edges in the canvas")?.push(edge);
+    }
+    Ok(serde_json::to_string(&json)?)
+}
+
+fn find(issues: &[CanvasIssue], kind: CanvasIssueKind) -> Option<&CanvasIssue> {
+    issues.iter().find(|issue| issue.kind == kind)
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
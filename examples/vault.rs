@@ -0,0 +1,123 @@
+//! Vault root check: publish File nodes from a canvas nested two folders deep in a vault,
+//! and check their paths are read from the vault root found by its `.obsidian/` folder,
+//! while paths leading out of the vault, like `../../etc/passwd`, are refused.
+//!
+//! ```sh
+//! cargo run --example vault   # or `just vault`
+//! ```
+//!
+//! The canvas's own folder has a `notes/hello.md` too, so a path read relative to the
+//! canvas rather than the vault publishes the wrong text. `../../etc/passwd` isn't a note,
+//! so its File node is never read; resolving it through the vault is checked directly.
+
+use std::error::Error;
+use std::path::Path;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::warnings::codes;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, ProcessReport, Syndicator, Vault};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const HELLO: &str = "7a10000000000001";
+const SIBLING: &str = "7a10000000000002";
+const PASSWD: &str = "7a10000000000003";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-vault-{}", std::process::id()));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let result = run(&scratch);
+    std::fs::remove_dir_all(&scratch)?;
+    result
+}
+
+fn run(scratch: &Path) -> Result<(), Box<dyn Error>> {
+    // scratch/vault/.obsidian, with the canvas in vault/boards/2026 and a note beside the vault
+    let root = scratch.join("vault");
+    let boards = root.join("boards").join("2026");
+    std::fs::create_dir_all(root.join(".obsidian"))?;
+    std::fs::create_dir_all(root.join("notes"))?;
+    std::fs::create_dir_all(boards.join("notes"))?;
+    std::fs::write(root.join("notes").join("hello.md"), "---\ntags: [hello]\n---\nHello from the vault root.\n")?;
+    std::fs::write(boards.join("notes").join("hello.md"), "Hello from beside the canvas.\n")?;
+    std::fs::write(scratch.join("sibling.md"), "Not in the vault.\n")?;
+    let canvas_path = boards.join("board.canvas");
+    let canvas = CanvasBuilder::new()
+        .file_node(HELLO, "notes/hello.md")
+        .color(PresetColor::Red)
+        .file_node(SIBLING, "../sibling.md")
+        .color(PresetColor::Red)
+        .file_node(PASSWD, "../../etc/passwd")
+        .color(PresetColor::Red)
+        .build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+
+    // Found by walking up from the canvas
+    let detected = Vault::detect(&canvas_path).map(|vault| vault.root().to_path_buf());
+    let found = detected.as_deref() == Some(root.canonicalize()?.as_path());
+    check("the vault is found two folders above the canvas", found, || format!("{:?}", detected))?;
+    let site = scratch.join("site");
+    let mut syndicator = Syndicator::new(Config::new(&canvas_path))?;
+    syndicator.add_sink(DirectorySink::new(&site))?;
+    let report = syndicator.process_once()?;
+    let post = read_post(&site, HELLO)?;
+    let read = post.contains("Hello from the vault root.") && !post.contains("beside the canvas");
+    check("a File node's path is read from the vault root", read, || post.clone())?;
+    check("without the note's frontmatter", !post.contains("tags: [hello]"), || post.clone())?;
+
+    // Paths leading out of the vault are refused
+    let posts = list(&site);
+    let refused = posts.len() == 1 && !posts.iter().any(|name| name.contains(SIBLING) || name.contains(PASSWD));
+    check("File nodes leading out of the vault aren't published", refused, || format!("{:?}", posts))?;
+    let warned = outside(&report, SIBLING);
+    check("a note outside it is reported as such", warned, || format!("{:?}", report.warnings))?;
+    let vault = Vault::new(&root);
+    let escapes = ["../../etc/passwd", "notes/../../sibling.md", "/etc/passwd"];
+    let rejected = escapes.iter().all(|path| vault.resolve(path).is_err());
+    check("resolving `..` past the root or an absolute path fails", rejected, || format!("{:?}", vault))?;
+    let inside = vault.resolve("boards/../notes/hello.md").is_ok_and(|path| path.ends_with("vault/notes/hello.md"));
+    check("while `..` staying inside the vault resolves", inside, || format!("{:?}", vault))?;
+
+    // A configured root wins over detection, and without either the canvas's folder is used
+    let configured = Vault::for_canvas(&canvas_path, Some(&boards)).resolve("notes/hello.md")?;
+    let chosen = std::fs::read_to_string(&configured)?.contains("beside the canvas");
+    check("a configured vault root is used over the detected one", chosen, || configured.display().to_string())?;
+    std::fs::remove_dir(root.join(".obsidian"))?;
+    let fallback = Vault::for_canvas(&canvas_path, None);
+    let own = fallback.root() == boards.canonicalize()?;
+    check("with neither, paths are read from the canvas's folder", own, || format!("{:?}", fallback))?;
+    Ok(())
+}
+
+/// Whether the report warns that `node_id`'s file is outside the vault
+fn outside(report: &ProcessReport, node_id: &str) -> bool {
+    report.warnings.iter().any(|w| {
+        w.code == codes::FILE_UNREADABLE && w.node_id.as_deref() == Some(node_id) && w.message.contains("outside")
+    })
+}
+
+/// The published post of a node
+fn read_post(site: &Path, node_id: &str) -> Result<String, Box<dyn Error>> {
+    let name = list(site).into_iter().find(|name| name.contains(node_id)).ok_or(format!("no post of {}", node_id))?;
+    Ok(std::fs::read_to_string(site.join(name))?)
+}
+
+/// Names of the files in a folder, hidden manifests left out
+fn list(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            let names = entries.flatten().map(|entry| entry.file_name().to_string_lossy().into_owned());
+            names.filter(|name| !name.starts_with('.')).collect()
+        })
+        .unwrap_or_default()
+}
+
+fn check(name: &str, ok: bool, detail: impl FnOnce() -> String) -> Result<(), Box<dyn Error>> {
+    if !ok {
+        return Err(format!("{}: {}", name, detail()).into());
+    }
+    println!("ok      {}", name);
+    Ok(())
+}
@@ -1,57 +1,1154 @@
 use std::error::Error;
-use std::path::PathBuf;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
 use std::time::Duration;
 
 use syndicate_json_canvas_lib::{
-    SyndicationSink, SyndicationTracker, validate_canvas_path, watch_and_process,
+    AccessibilityOptions, AccessibilityPolicy, BackfillOptions, Backpressure, BadgeSpec, BatchPolicy, CONSOLE_HELP,
+    CancelToken, CategoryMap, ChaosSink, ColorFilter, Config, ConfigCheck, DEFAULT_BACKFILL_CHUNK_SIZE,
+    DEFAULT_BADGE_COLOR, DEFAULT_DATE_FORMAT, DEFAULT_MAX_BARE_URL_CHARS, DEFAULT_MAX_ITEM_BYTES,
+    DEFAULT_PROCESSING_CAPACITY, DEFAULT_RESURFACE_COOLDOWN_DAYS, DEFAULT_RESURFACE_MIN_AGE_DAYS,
+    DEFAULT_RESURFACE_PREFIX, DanglingEdges, DeliveryOptions, EdgeDirection, EdgeWeights, FindingKind, ItemSizeLimit,
+    LinkCheckOptions, LinkCheckPolicy, NetworkPolicy, OversizePolicy, PoliteClient, ProcessOptions, ProcessingOptions,
+    PulldownRenderer, ReconcileFix, RemovalGuard, RemovalPolicy, Replacements, ReportFormat, ReportWriter,
+    ReqwestClient, ResurfaceOptions, Selection, SinkTarget, SizeFilter, SyndicationError, SyndicationSink, Syndicator,
+    TextPolicy, TokenOptions, WarningPolicy, WatchOptions, run_console, validate_canvas_path, warnings::codes,
+    write_example_canvas,
 };
-use syndicate_json_canvas_sinks::JjRepositorySink;
-use tracing::info;
+use syndicate_json_canvas_sinks::{
+    DirectorySink, ExecSink, FilenameTemplate, JjRepositorySink, PluginSink, PreviewServer, PreviewSite,
+    discover_plugins,
+};
+use tracing::{info, warn};
 use tracing_subscriber::EnvFilter;
 
 // ===== CONFIGURATION =====
+/// The canvas to syndicate
+const CANVAS_PATH: &str = "/Users/aadalal/Documents/scratchpad/Thoughts.canvas";
+/// Force every sink into dry-run mode (same as `--dry-run`)
 const DRY_RUN: bool = false;
+/// Sinks kept in dry-run mode even when the run is armed (same as `--dry-run-sink <name>`)
+const DRY_RUN_SINKS: &[&str] = &[];
+/// Keep the jj sink from running even its read-only commands in dry runs (same as `--paranoid-dry-run`)
+const PARANOID_DRY_RUN: bool = false;
+/// How long the canvas must be quiet before a change is processed (same as `--debounce <ms>`)
 const DEBOUNCE_DURATION_MS: u64 = 500;
+/// Glob patterns of paths whose file events never start a run
+const WATCH_IGNORE: &[&str] = &[];
+/// Least time between the starts of two runs, however often the canvas is saved
+const MIN_RUN_INTERVAL_MS: u64 = 0;
+/// Requests to process (saves, console `p`, batch windows) queued while a run is going
+const PROCESSING_CAPACITY: usize = DEFAULT_PROCESSING_CAPACITY;
+/// What happens to a request when the queue is full: `Drop` (logged) or `Block` until there's room
+const PROCESSING_BACKPRESSURE: Backpressure = Backpressure::Drop;
+/// Carry out identical queued requests once, rather than one run each
+const COALESCE_REQUESTS: bool = true;
+/// Obsidian vault root; None auto-detects by looking for `.obsidian/` above the canvas
+const VAULT_ROOT: Option<&str> = None;
+/// Format of the run reports written when `--report-dir` is given
+const REPORT_FORMAT: ReportFormat = ReportFormat::Both;
+/// Number of run reports to keep
+const REPORT_KEEP_LAST: usize = 50;
+/// Queue new items and deliver them from a durable queue (with retries) instead of directly
+const USE_QUEUE: bool = false;
+/// Colors of the nodes to syndicate: preset names (`red`), numbers (`1`) or hex (`#ff0000`)
+const SYNDICATION_COLORS: &[&str] = &["red"];
+/// Also syndicate text nodes with no color at all
+const SYNDICATE_UNCOLORED: bool = false;
+/// Publish every text node (and File node of a text file) whatever its color, for a canvas
+/// that is public as a whole; refused unless `CONFIRM_CANVAS_NAME` is its file name
+const PUBLISH_ALL: bool = false;
+/// File name of the canvas (e.g. `"Public.canvas"`), confirming `PUBLISH_ALL` is meant for it
+const CONFIRM_CANVAS_NAME: Option<&str> = None;
+/// Publish selected Link nodes as link posts with this text (`{url}` is the link), e.g.
+/// `Some("🔗 {url}")`; if None, Link nodes stand for pages that live elsewhere
+const LINK_POSTS: Option<&str> = None;
+/// Edges pointing at nodes that aren't on the canvas are left out with a warning (`Skip`) or
+/// fail every run until the canvas is fixed (`Fail`)
+const DANGLING_EDGES: DanglingEdges = DanglingEdges::Skip;
+/// Which way edges point: as drawn from `fromNode` to `toNode` (`Drawn`), or along their
+/// arrows (`Arrows`), so an edge drawn backwards with its arrow flipped links the right way
+const EDGE_DIRECTION: EdgeDirection = EdgeDirection::Drawn;
+/// Only publish nodes sized like developed notes rather than quick cards (see the README)
+const ONLY_NOTE_SIZED: bool = false;
+/// Least area of nodes to publish in square canvas pixels, overriding the note default
+const MIN_NODE_AREA: Option<u64> = None;
+/// Least width of nodes to publish in canvas pixels, overriding the note default
+const MIN_NODE_WIDTH: Option<u64> = None;
+/// Range of width / height ratios of nodes to publish, e.g. `Some((0.5, 4.0))`
+const NODE_ASPECT: Option<(f64, f64)> = None;
+/// Largest node text to publish, in bytes
+const MAX_ITEM_BYTES: usize = DEFAULT_MAX_ITEM_BYTES;
+/// Whether larger texts are `Skip`ped or `Truncate`d to `MAX_ITEM_BYTES`
+const OVERSIZE_POLICY: OversizePolicy = OversizePolicy::Skip;
+/// Whether items with accessibility issues (images without alt text, bare URL links, skipped
+/// heading levels) are only warned about (`Warn`) or also held back from `PUBLIC_SINKS` (`Block`)
+const ACCESSIBILITY_POLICY: AccessibilityPolicy = AccessibilityPolicy::Warn;
+/// Longest URL allowed as a link's visible text
+const MAX_BARE_URL_CHARS: usize = DEFAULT_MAX_BARE_URL_CHARS;
+/// If set, the links of items about to be published are requested first, and broken ones are
+/// only warned about (`Warn`) or their items also held back from `PUBLIC_SINKS` (`Block`)
+const CHECK_LINKS: Option<LinkCheckPolicy> = None;
+/// Whether checked links that moved permanently are rewritten to where they moved
+const REWRITE_MOVED_LINKS: bool = false;
+/// Sinks that publish to the public, which `ACCESSIBILITY_POLICY` and `CHECK_LINKS` may hold
+/// items back from
+const PUBLIC_SINKS: &[&str] = &["jj"];
+/// Expand `{{today}}`, `{{now}}` and `{{+7d}}` / `{{-7d}}` in node text to dates as of
+/// publishing (`\{{today}}` keeps the token as written)
+const EXPAND_DATE_TOKENS: bool = false;
+/// chrono format of the dates tokens expand to
+const DATE_FORMAT: &str = DEFAULT_DATE_FORMAT;
+/// TOML file whose `[text]` table sets how titles and slugs are cut (see the README);
+/// None keeps the defaults
+const TEXT_POLICY_FILE: Option<&str> = None;
+/// TOML file of `[[replace]]` rules rewriting node text before publishing, e.g. names into
+/// initials (see the README); None publishes text as written
+const REPLACEMENTS_FILE: Option<&str> = None;
+/// How the jj sink names post files, from `{slug}`, `{id}`, `{short_id}`, `{date}` and `{title}`
+const FILENAME_TEMPLATE: &str = "{slug}-{id}";
+/// Hashtags mapped onto site categories, highest priority first (empty disables categories)
+const CATEGORIES: &[(&str, &str)] = &[];
+/// Category of items with none of the mapped tags
+const DEFAULT_CATEGORY: Option<&str> = None;
+/// Edge colors mapped onto a weight and optional label, e.g. `("red", 3.0, Some("strong"))`;
+/// cross-references are listed heaviest first (empty keeps canvas order)
+const EDGE_WEIGHTS: &[(&str, f64, Option<&str>)] = &[];
+/// Weight of edges whose color isn't in `EDGE_WEIGHTS`
+const DEFAULT_EDGE_WEIGHT: f64 = 1.0;
+/// Write each cross-reference's `weight` (and `emphasis` label) into the post frontmatter
+const LINK_WEIGHTS: bool = false;
+/// Write `canvas`, `node_id` and `canvas_hash` frontmatter into each post, tracing it back to
+/// the canvas version it was published from
+const PROVENANCE: bool = false;
+/// When new items are published: `Immediate`, `Window(duration)` after the last addition,
+/// or `Manual` (on `drain`)
+const BATCHING: BatchPolicy = BatchPolicy::Immediate;
+/// Largest fraction of published nodes that may vanish from the canvas between runs before
+/// a run is refused (a truncated canvas); `--accept-mass-removal` lets the next run proceed
+const MAX_VANISHED: f64 = 0.5;
+/// What `prune` leaves of the jj sink's posts: `Delete` them, or `Tombstone` to keep a
+/// `withdrawn: true` stub in their place
+const REMOVAL_POLICY: RemovalPolicy = RemovalPolicy::Delete;
+/// Scripts publishing to destinations without a sink of their own, as `(sink name, program,
+/// args)`; each gets the items as JSON Lines on stdin (see the README)
+const EXEC_SINKS: &[(&str, &str, &[&str])] = &[];
+/// How long an `EXEC_SINKS` script or a plugin may take over one batch before it is killed
+const EXEC_TIMEOUT_SECS: u64 = 60;
+/// Folder of plugin programs, each loaded as a sink of its own (see the README)
+const PLUGIN_DIR: Option<&str> = None;
+/// Configuration handed to plugins, as `(plugin file name, JSON)`; `{}` for the others
+const PLUGIN_CONFIGS: &[(&str, &str)] = &[];
+/// Sinks only given items first published (anywhere) within this many calendar days, as
+/// `(sink name, days)`, e.g. `("digest", 7)` for a weekly digest, even when draining a backlog
+const PUBLISHED_WITHIN: &[(&str, u32)] = &[];
+/// Sinks skipping items older than this many calendar days for good, as `(sink name, days)`,
+/// e.g. `("twitter", 30)` so a new social sink doesn't post an old canvas's backlog
+const MAX_ITEM_AGE: &[(&str, u32)] = &[];
+/// Post a random old item again on a sink every so many hours, as `(sink name, hours)`; only
+/// the Twitter sink can (see the README)
+const RESURFACE: Option<(&str, u64)> = None;
+/// Least age in days of re-surfaced items
+const RESURFACE_MIN_AGE_DAYS: u32 = DEFAULT_RESURFACE_MIN_AGE_DAYS;
+/// Days before the same item may be re-surfaced again
+const RESURFACE_COOLDOWN_DAYS: u32 = DEFAULT_RESURFACE_COOLDOWN_DAYS;
+/// Put before the text of re-surfaced items
+const RESURFACE_PREFIX: &str = DEFAULT_RESURFACE_PREFIX;
+/// shields.io badge of how many posts a sink has, as `(sink, path)`, written after each run
+const PUBLISHED_BADGE: Option<(&str, &str)> = None;
+/// shields.io badge of how many days ago a sink last got a post, as `(sink, path)`
+const LAST_POST_BADGE: Option<(&str, &str)> = None;
+/// Color of both badges
+const BADGE_COLOR: &str = DEFAULT_BADGE_COLOR;
+/// Check at startup that published files still parse (same as `verify`, but only warns)
+const VERIFY_ON_STARTUP: bool = true;
+/// Warning codes that fail the run, e.g. `"html-stripped"` (see the README for the codes)
+const PROMOTED_WARNINGS: &[&str] = &[];
+/// Sink whose would-be posts `preview` serves
+const PREVIEW_SINK: &str = "jj";
+/// Local port `preview` serves on (same as `preview --port <port>`)
+const PREVIEW_PORT: u16 = 4040;
+/// Sinks `backfill items` publishes the backlog to unless `--only-sink` is given; social sinks
+/// are left out so a whole archive isn't posted to followers
+const BACKFILL_SINKS: &[&str] = &["jj"];
+/// New items each sink takes per backfill chunk (same as `backfill items --chunk-size <n>`)
+const BACKFILL_CHUNK_SIZE: usize = DEFAULT_BACKFILL_CHUNK_SIZE;
+/// Pause between backfill chunks (same as `backfill items --chunk-delay <secs>`)
+const BACKFILL_CHUNK_DELAY_SECS: u64 = 5;
+/// Warning codes that are dropped without being reported
+const SILENCED_WARNINGS: &[&str] = &[];
+
+const USAGE: &str = "Usage: syndicate-obsidian-canvas [--report-dir <dir>] [--debounce <ms>] [--dry-run] [--paranoid-dry-run] [--dry-run-sink <name>]... [--simulate] [--show-redactions] [--accept-mass-removal] [--resume] [--interactive-console] [--validate-only] \
+[demo | config check [--with-preflight] [--json] | status | verify | backfill | backfill items [--chunk-size <n>] [--chunk-delay <secs>] [--only-sink <name>]... | import-legacy | history <node-id> [--json] | preview [--port <port>] | drain [--now] | reconcile [--mark-found] [--clear-missing] | prune [--plan] [--yes] | state export <bundle> | state import <bundle> [--force] | exclude add <node-id> | exclude remove <node-id> | exclude list]";
 
 fn main() -> Result<(), Box<dyn Error>> {
+    // ===== Arguments =====
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let report_dir = take_flag_value(&mut args, "--report-dir")?.map(PathBuf::from);
+    let debounce_ms = match take_flag_value(&mut args, "--debounce")? {
+        Some(ms) => ms.parse().map_err(|_| format!("--debounce: `{}` isn't a number of milliseconds", ms))?,
+        None => DEBOUNCE_DURATION_MS,
+    };
+    let paranoid_dry_run = take_flag(&mut args, "--paranoid-dry-run") || PARANOID_DRY_RUN;
+    let dry_run = take_flag(&mut args, "--dry-run") || DRY_RUN || paranoid_dry_run;
+    let simulate = take_flag(&mut args, "--simulate");
+    // Hidden from the usage: inject failures into every sink, seeded for replaying them
+    let chaos_seed: Option<u64> = match take_flag_value(&mut args, "--chaos")? {
+        Some(seed) => Some(seed.parse().map_err(|_| format!("--chaos: `{}` isn't a seed number", seed))?),
+        None => None,
+    };
+    let show_redactions = take_flag(&mut args, "--show-redactions");
+    let accept_mass_removal = take_flag(&mut args, "--accept-mass-removal");
+    let resume = take_flag(&mut args, "--resume");
+    if chaos_seed.is_some() && !simulate && !show_redactions {
+        return Err("--chaos only runs against a sandbox; add --simulate".into());
+    }
+    let interactive_console = take_flag(&mut args, "--interactive-console");
+    let validate_only = take_flag(&mut args, "--validate-only");
+    let mut dry_run_sinks: Vec<&str> = DRY_RUN_SINKS.to_vec();
+    while let Some(sink_name) = take_flag_value(&mut args, "--dry-run-sink")? {
+        dry_run_sinks.push(sink_name);
+    }
+
     // Initialize logging (DEBUG when dry-run, INFO otherwise)
     tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::new(if DRY_RUN { "debug" } else { "info" }))
+        .with_env_filter(EnvFilter::new(if dry_run { "debug" } else { "info" }))
         .with_line_number(true)
         .with_file(true)
         .with_target(false)
         .init();
 
+    match args.as_slice() {
+        ["demo"] => return demo_command(),
+        ["config", "check", rest @ ..] => return config_check_command(rest),
+        _ => {}
+    }
+
     // ===== Canvas Configuration =====
-    let canvas_path = PathBuf::from("/Users/aadalal/Documents/scratchpad/Thoughts.canvas");
-    validate_canvas_path(&canvas_path)?;
+    let text_policy = match TEXT_POLICY_FILE {
+        Some(path) => TextPolicy::load(Path::new(path)).map_err(|e| format!("{}: {}", path, e))?,
+        None => TextPolicy::default(),
+    };
+    let mut config = Config::new(CANVAS_PATH);
+    config.dry_run = dry_run;
+    config.debounce = Duration::from_millis(debounce_ms);
+    config.processing = ProcessingOptions::default()
+        .with_capacity(PROCESSING_CAPACITY)
+        .with_backpressure(PROCESSING_BACKPRESSURE)
+        .with_coalesce(COALESCE_REQUESTS);
+    config.options = ProcessOptions {
+        vault_root: VAULT_ROOT.map(PathBuf::from),
+        report_writer: report_dir
+            .clone()
+            .map(|dir| ReportWriter::new(dir, REPORT_FORMAT, REPORT_KEEP_LAST)),
+        badges: badges(),
+        resurface: resurface(),
+        queue: USE_QUEUE.then(DeliveryOptions::default),
+        selection: selection()?,
+        confirm_canvas_name: CONFIRM_CANVAS_NAME.map(str::to_string),
+        link_posts: LINK_POSTS.map(str::to_string),
+        dangling_edges: DANGLING_EDGES,
+        edge_direction: EDGE_DIRECTION,
+        size: size_filter(),
+        item_size: ItemSizeLimit::default().with_max_bytes(MAX_ITEM_BYTES).with_policy(OVERSIZE_POLICY),
+        tokens: EXPAND_DATE_TOKENS.then(|| TokenOptions::default().with_date_format(DATE_FORMAT)),
+        replacements: match REPLACEMENTS_FILE {
+            Some(path) => Some(Replacements::load(Path::new(path)).map_err(|e| format!("{}: {}", path, e))?),
+            None => None,
+        },
+        accessibility: AccessibilityOptions::default()
+            .with_max_bare_url_chars(MAX_BARE_URL_CHARS)
+            .with_policy(ACCESSIBILITY_POLICY),
+        link_check: CHECK_LINKS.map(link_check),
+        text: text_policy.clone(),
+        batching: BATCHING,
+        categories: category_map(),
+        edge_weights: edge_weights()?,
+        watch: WatchOptions {
+            ignore: WATCH_IGNORE.iter().map(|pattern| pattern.to_string()).collect(),
+            min_interval: Duration::from_millis(MIN_RUN_INTERVAL_MS),
+        },
+        removal_guard: RemovalGuard::default()
+            .with_max_vanished(MAX_VANISHED)
+            .with_accept_mass_removal(accept_mass_removal),
+        warnings: warning_policy(),
+        ..Default::default()
+    };
+    let mut syndicator = Syndicator::new(config)?;
+
+    // ===== Subcommands =====
+    match args.as_slice() {
+        [] if validate_only => return validate_command(&syndicator),
+        _ if validate_only => return Err(USAGE.into()),
+        [] | ["preview", ..] | ["drain", ..] | ["reconcile", ..] | ["prune", ..] | ["state", ..] | ["verify"]
+        | ["backfill", ..] | ["import-legacy"] => {}
+        ["status"] => return status_command(&syndicator, report_dir.as_deref()),
+        ["history", rest @ ..] => return history_command(&syndicator, rest),
+        ["exclude", rest @ ..] => return exclude_command(&syndicator, rest),
+        _ => return Err(USAGE.into()),
+    }
 
     // ===== Sink Configuration =====
-    let sink = JjRepositorySink::new(
-        "/Users/aadalal/dev/aadalal.github.io/",
-        "main",
-        "origin",
-        "_tiny_thoughts",
-    )?;
+    // Independent sinks publish concurrently; use `.after("jj")` to order a sink after another
+    add_sink(&mut syndicator, jj_sink(text_policy)?.with_paranoid_dry_run(paranoid_dry_run), chaos_seed)?;
+    let canvas_path = syndicator.config().canvas_path.clone();
+    for sink in exec_sinks(&canvas_path) {
+        add_sink(&mut syndicator, sink, chaos_seed)?;
+    }
+    for sink in plugin_sinks()? {
+        add_sink(&mut syndicator, sink, chaos_seed)?;
+    }
+
+    for sink_name in &dry_run_sinks {
+        if syndicator.tracker(sink_name).is_none() {
+            return Err(format!("--dry-run-sink: unknown sink `{}`", sink_name).into());
+        }
+    }
+    for target in syndicator.targets_mut() {
+        target.dry_run = dry_run_sinks.contains(&target.sink.name());
+        target.public_facing = PUBLIC_SINKS.contains(&target.sink.name());
+        target.published_within =
+            PUBLISHED_WITHIN.iter().find(|(name, _)| *name == target.sink.name()).map(|(_, days)| *days);
+        target.max_item_age = MAX_ITEM_AGE.iter().find(|(name, _)| *name == target.sink.name()).map(|(_, days)| *days);
+    }
 
-    // ===== Tracker Setup =====
-    let tracker = SyndicationTracker::new(&canvas_path, sink.name())?;
+    match args.as_slice() {
+        ["preview", rest @ ..] => return preview_command(&syndicator, rest),
+        ["drain"] => return drain_command(&mut syndicator, false),
+        ["drain", "--now"] => return drain_command(&mut syndicator, true),
+        ["drain", ..] => return Err(USAGE.into()),
+        ["reconcile", rest @ ..] => return reconcile_command(&mut syndicator, rest),
+        ["prune", rest @ ..] => return prune_command(&mut syndicator, rest),
+        ["state", rest @ ..] => return state_command(&syndicator, rest),
+        ["verify"] => return verify_command(&syndicator),
+        ["backfill"] => return backfill_command(&mut syndicator),
+        ["backfill", "items", rest @ ..] => return backfill_items_command(&mut syndicator, rest),
+        ["import-legacy"] => return import_legacy_command(&mut syndicator),
+        _ => {}
+    }
+
+    // Run once against sandboxed copies of the sinks, print what would change and exit
+    if simulate {
+        let simulation = syndicator.simulate()?;
+        println!("{} new items", simulation.report.new_items.len());
+        for (node_id, topic) in &simulation.report.content_warnings {
+            println!("{}  behind content warning `{}`", node_id, topic);
+        }
+        print!("{}", simulation.to_text());
+        return Ok(());
+    }
+
+    // Run once in a sandbox like `--simulate`, listing the replacement rules that fired
+    if show_redactions {
+        let simulation = syndicator.simulate()?;
+        if simulation.report.redactions.is_empty() {
+            println!("No replacement rule fired");
+        }
+        for redaction in &simulation.report.redactions {
+            println!("{}  {} ({}x)", redaction.node_id, redaction.rule, redaction.count);
+        }
+        return Ok(());
+    }
+
+    // Surface drift between trackers and published output; fixing it is up to `reconcile`
+    let drifted = syndicator.reconcile(ReconcileFix::default())?;
+    if drifted.iter().any(|d| !d.is_empty()) {
+        warn!("Trackers disagree with published output; run `reconcile` for details");
+    }
+    if VERIFY_ON_STARTUP {
+        for report in syndicator.verify()?.iter().filter(|r| !r.is_empty()) {
+            warn!(
+                sink = %report.sink,
+                files = report.files_with_issues(),
+                "Published files have broken frontmatter; run `verify` for details"
+            );
+        }
+    }
+
+    // Finish a run that died partway before taking new work
+    if let Some(journal) = syndicator.unfinished_run()? {
+        let remaining: Vec<&str> = journal.remaining().map(|step| step.sink.as_str()).collect();
+        println!(
+            "Run {} from {} didn't finish; left for: {}",
+            journal.run_id(),
+            journal.started_at().format("%Y-%m-%d %H:%M:%S"),
+            remaining.join(", ")
+        );
+        if resume || confirm_resume()? {
+            let report = syndicator.resume()?;
+            let failed = report.sinks.iter().filter(|outcome| outcome.error.is_some()).count();
+            println!("Resumed: {} new items, {} sinks failed", report.new_items.len(), failed);
+        } else {
+            warn!("Not resuming; the next run archives the unfinished one as it stands");
+        }
+    }
 
     // ===== Logging =====
+    let sink_names: Vec<&str> = syndicator.targets().iter().map(|t| t.sink.name()).collect();
     info!(
-        canvas_file = %canvas_path.display(),
-        debounce_ms = DEBOUNCE_DURATION_MS,
-        sink = sink.name(),
-        dry_run = DRY_RUN,
+        canvas_file = %syndicator.config().canvas_path.display(),
+        debounce_ms,
+        sinks = ?sink_names,
+        dry_run,
         "Starting syndication"
     );
+    for target in syndicator.targets() {
+        let state = if target.is_dry(dry_run) { "DRY RUN" } else { "ARMED" };
+        info!(sink = %target.sink.name(), state, "Sink state");
+    }
 
     // ===== Run =====
-    watch_and_process(
-        &canvas_path,
-        sink,
-        tracker,
-        DRY_RUN,
-        Duration::from_millis(DEBOUNCE_DURATION_MS),
-    )
+    // The sender is held for the lifetime of the process, so the watcher never stops on its own
+    let (commands_tx, commands_rx) = mpsc::channel();
+    if interactive_console {
+        if std::io::stdin().is_terminal() {
+            let commands_tx = commands_tx.clone();
+            std::thread::spawn(move || run_console(std::io::stdin().lock(), std::io::stdout(), commands_tx));
+            println!("Console ready; {}", CONSOLE_HELP);
+        } else {
+            warn!("--interactive-console: stdin isn't a terminal, console disabled");
+        }
+    }
+    syndicator.watch(commands_rx)?;
+    Ok(())
+}
+
+/// Ask whether to resume the unfinished run; without a terminal to ask on, it isn't
+fn confirm_resume() -> Result<bool, Box<dyn Error>> {
+    if !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    print!("Resume it first? [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Remove a boolean `<flag>` from the arguments, returning whether it was present
+fn take_flag(args: &mut Vec<&str>, flag: &str) -> bool {
+    let Some(pos) = args.iter().position(|arg| *arg == flag) else {
+        return false;
+    };
+    args.remove(pos);
+    true
+}
+
+/// Remove `<flag> <value>` from the arguments, returning the value if present
+fn take_flag_value<'a>(args: &mut Vec<&'a str>, flag: &str) -> Result<Option<&'a str>, Box<dyn Error>> {
+    let Some(pos) = args.iter().position(|arg| *arg == flag) else {
+        return Ok(None);
+    };
+    if pos + 1 >= args.len() {
+        return Err(format!("{} requires a value", flag).into());
+    }
+    let value = args.remove(pos + 1);
+    args.remove(pos);
+    Ok(Some(value))
+}
+
+/// Add a sink, wrapped in a [`ChaosSink`] if `--chaos` gave a seed
+fn add_sink(
+    syndicator: &mut Syndicator,
+    sink: impl SyndicationSink + Send + 'static,
+    chaos_seed: Option<u64>,
+) -> Result<&mut SinkTarget, SyndicationError> {
+    match chaos_seed {
+        Some(seed) => syndicator.add_sink(
+            ChaosSink::new(sink, seed)
+                .with_fail_first_batches(1)
+                .with_fail_every_nth_item(7)
+                .with_fail_rate(0.2)
+                .with_hang(0.1, Duration::from_secs(2))
+                .with_wrong_ids_rate(0.2),
+        ),
+        None => syndicator.add_sink(sink),
+    }
+}
+
+/// `demo`: publish the bundled example canvas into a scratch folder with a directory sink
+fn demo_command() -> Result<(), Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-demo-{}", std::process::id()));
+    let canvas_path = write_example_canvas(&scratch)?;
+    let site_dir = scratch.join("site");
+    let report_dir = scratch.join("reports");
+
+    let mut config = Config::new(&canvas_path);
+    config.options.report_writer = Some(ReportWriter::new(&report_dir, REPORT_FORMAT, REPORT_KEEP_LAST));
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(&site_dir))?;
+
+    let report = syndicator.process_once()?;
+    let published: usize = report.sinks.iter().map(|outcome| outcome.items.len()).sum();
+
+    println!("Published {} example posts from {}", published, canvas_path.display());
+    println!("  markdown: {}", site_dir.display());
+    println!("  reports:  {}", report_dir.display());
+    println!("Delete {} when done", scratch.display());
+    Ok(())
+}
+
+/// `config check [--with-preflight] [--json]`: report every problem in the configuration
+/// without running anything; fails unless there is none
+///
+/// `--with-preflight` also checks that the canvas exists and each sink could publish.
+fn config_check_command(args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let (mut with_preflight, mut json) = (false, false);
+    for arg in args {
+        match *arg {
+            "--with-preflight" => with_preflight = true,
+            "--json" => json = true,
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let mut check = ConfigCheck::new();
+    let mut files: Vec<&str> = TEXT_POLICY_FILE.into_iter().chain(REPLACEMENTS_FILE).collect();
+    files.dedup();
+    for file in files {
+        check.check_file(Path::new(file));
+    }
+
+    const SOURCE: &str = "main.rs";
+    for (i, color) in SYNDICATION_COLORS.iter().enumerate() {
+        check.check_color(SOURCE, &format!("SYNDICATION_COLORS[{}]", i), color);
+    }
+    for (i, (color, _, _)) in EDGE_WEIGHTS.iter().enumerate() {
+        check.check_color(SOURCE, &format!("EDGE_WEIGHTS[{}]", i), color);
+    }
+    check.check_template(SOURCE, "FILENAME_TEMPLATE", FILENAME_TEMPLATE, &["slug", "id", "short_id", "date", "title"]);
+
+    let mut sinks = vec!["jj"];
+    for (i, (name, _, _)) in EXEC_SINKS.iter().enumerate() {
+        if sinks.contains(name) {
+            let message = format!("sink name `{}` is taken; sinks sharing a name share a tracker", name);
+            check.push(FindingKind::Invalid, SOURCE, &format!("EXEC_SINKS[{}]", i), message);
+        }
+        sinks.push(name);
+    }
+    for (i, (_, json)) in PLUGIN_CONFIGS.iter().enumerate() {
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(json) {
+            check.push(FindingKind::Invalid, SOURCE, &format!("PLUGIN_CONFIGS[{}]", i), format!("invalid JSON: {}", e));
+        }
+    }
+    // Plugins name themselves, so they're asked to before their names can be checked
+    let plugins = match PLUGIN_DIR.map(|dir| discover_plugins(Path::new(dir))).transpose() {
+        Ok(plugins) => plugins.unwrap_or_default(),
+        Err(e) => {
+            check.push(FindingKind::Invalid, SOURCE, "PLUGIN_DIR", e.to_string());
+            Vec::new()
+        }
+    };
+    let mut plugin_names = Vec::new();
+    let mut described = Vec::new();
+    for path in &plugins {
+        match PluginSink::describe(path) {
+            Ok(manifest) => {
+                plugin_names.push(manifest.name);
+                described.push(path);
+            }
+            Err(e) => check.push(FindingKind::Invalid, "plugins", &path.display().to_string(), e.to_string()),
+        }
+    }
+    sinks.extend(plugin_names.iter().map(String::as_str));
+    let sink_references = DRY_RUN_SINKS
+        .iter()
+        .map(|name| ("DRY_RUN_SINKS", *name))
+        .chain(PUBLIC_SINKS.iter().map(|name| ("PUBLIC_SINKS", *name)))
+        .chain(PUBLISHED_WITHIN.iter().map(|(name, _)| ("PUBLISHED_WITHIN", *name)))
+        .chain(MAX_ITEM_AGE.iter().map(|(name, _)| ("MAX_ITEM_AGE", *name)))
+        .chain(RESURFACE.map(|(name, _)| ("RESURFACE", name)))
+        .chain(PUBLISHED_BADGE.map(|(name, _)| ("PUBLISHED_BADGE", name)))
+        .chain(LAST_POST_BADGE.map(|(name, _)| ("LAST_POST_BADGE", name)));
+    for (setting, name) in sink_references {
+        check.check_name(SOURCE, setting, name, &sinks, "sink");
+    }
+    let warning_codes = PROMOTED_WARNINGS
+        .iter()
+        .map(|code| ("PROMOTED_WARNINGS", *code))
+        .chain(SILENCED_WARNINGS.iter().map(|code| ("SILENCED_WARNINGS", *code)));
+    for (setting, code) in warning_codes {
+        check.check_name(SOURCE, setting, code, codes::ALL, "warning code");
+    }
+
+    if with_preflight {
+        if let Err(e) = validate_canvas_path(Path::new(CANVAS_PATH)) {
+            check.push(FindingKind::Preflight, "canvas", CANVAS_PATH, e);
+        }
+        match jj_sink(TextPolicy::default()) {
+            Ok(sink) => check.preflight(&sink),
+            Err(e) => check.push(FindingKind::Preflight, "jj", "", e.to_string()),
+        }
+        for sink in exec_sinks(Path::new(CANVAS_PATH)) {
+            check.preflight(&sink);
+        }
+        for path in described {
+            let loaded =
+                plugin_config(path).and_then(|config| PluginSink::load(path, config).map_err(|e| e.to_string()));
+            match loaded {
+                Ok(sink) => check.preflight(&sink),
+                Err(e) => check.push(FindingKind::Preflight, "plugins", &path.display().to_string(), e),
+            }
+        }
+    }
+
+    if json {
+        let output = serde_json::json!({ "valid": check.is_valid(), "findings": check.findings });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if check.is_valid() {
+        println!("Configuration is valid");
+    } else {
+        for finding in &check.findings {
+            println!("{}", finding);
+        }
+    }
+    if !check.is_valid() {
+        return Err(format!("config check: {} problems found", check.findings.len()).into());
+    }
+    Ok(())
+}
+
+/// `preview`: serve the posts a run would write to `PREVIEW_SINK` as HTML on a local port,
+/// running again in a fresh sandbox whenever the canvas is saved
+fn preview_command(syndicator: &Syndicator, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let port = match args {
+        [] => PREVIEW_PORT,
+        ["--port", port] => port.parse().map_err(|_| format!("--port: `{}` isn't a port number", port))?,
+        _ => return Err(USAGE.into()),
+    };
+    let renderer = PulldownRenderer::default();
+    let server = PreviewServer::bind(("127.0.0.1", port), preview_site(syndicator, &renderer)?)?;
+    println!("Previewing `{}` at http://{}/ (Ctrl-C to stop)", PREVIEW_SINK, server.local_addr()?);
+    let handle = server.handle();
+    let serving = std::thread::spawn(move || server.serve());
+
+    let canvas_path = syndicator.config().canvas_path.clone();
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    let mut last_modified = modified(&canvas_path);
+    while !serving.is_finished() {
+        std::thread::sleep(Duration::from_millis(DEBOUNCE_DURATION_MS));
+        let now = modified(&canvas_path);
+        if now == last_modified {
+            continue;
+        }
+        last_modified = now;
+        match preview_site(syndicator, &renderer) {
+            Ok(site) => {
+                handle.replace(site);
+                println!("Canvas changed; preview updated");
+            }
+            Err(e) => warn!(error = %e, "Failed to update the preview"),
+        }
+    }
+    serving.join().map_err(|_| "preview server panicked")??;
+    Ok(())
+}
+
+/// Simulate a run in a throwaway sandbox and render what it wrote to `PREVIEW_SINK`
+fn preview_site(syndicator: &Syndicator, renderer: &PulldownRenderer) -> Result<PreviewSite, Box<dyn Error>> {
+    let sandbox = std::env::temp_dir().join(format!(
+        "syndicate-json-canvas-preview-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos()
+    ));
+    let site = syndicator.simulate_in(&sandbox).map_err(Box::<dyn Error>::from).and_then(|simulation| {
+        if simulation.unsupported_sinks.iter().any(|sink| sink == PREVIEW_SINK) {
+            return Err(format!("sink `{}` can't be simulated", PREVIEW_SINK).into());
+        }
+        Ok(PreviewSite::load(&sandbox.join("sinks").join(PREVIEW_SINK), renderer)?)
+    });
+    if let Err(e) = std::fs::remove_dir_all(&sandbox) {
+        warn!(sandbox = %sandbox.display(), error = %e, "Failed to remove preview sandbox");
+    }
+    site
+}
+
+/// `status`: show the queue depth per sink and the latest run report
+fn status_command(syndicator: &Syndicator, report_dir: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let queue = syndicator.queue()?;
+    if queue.is_empty() {
+        println!("Queue is empty");
+    } else {
+        println!("Queued items:");
+        for (sink, depth) in queue.depth_by_sink() {
+            println!("  {}: {}", sink, depth);
+        }
+    }
+
+    if let Some(report_dir) = report_dir {
+        println!();
+        match ReportWriter::latest(report_dir)? {
+            Some(path) => print!("{}", std::fs::read_to_string(path)?),
+            None => println!("No reports in {}", report_dir.display()),
+        }
+    }
+    Ok(())
+}
+
+/// `history <node-id> [--json]`: every publish, archive and un-archive of a node per sink
+fn history_command(syndicator: &Syndicator, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let (node_id, json) = match args {
+        [node_id] => (node_id, false),
+        [node_id, "--json"] | ["--json", node_id] => (node_id, true),
+        _ => return Err(USAGE.into()),
+    };
+    let node_id = syndicator.resolve_node_id(node_id)?;
+    let events = syndicator.history(&node_id)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&events)?);
+        return Ok(());
+    }
+    if events.is_empty() {
+        println!("No history for {}", node_id);
+        return Ok(());
+    }
+    println!("{:<25}  {:<12}  {:<10}  {:<16}  SLUG", "TIME", "SINK", "EVENT", "CONTENT HASH");
+    for event in events {
+        println!(
+            "{:<25}  {:<12}  {:<10}  {:<16}  {}",
+            event.at.format("%Y-%m-%d %H:%M:%S %:z"),
+            event.sink,
+            event.kind.as_str(),
+            event.short_hash().unwrap_or("-"),
+            event.slug.as_deref().unwrap_or("-"),
+        );
+    }
+    Ok(())
+}
+
+fn jj_sink(text_policy: TextPolicy) -> Result<JjRepositorySink, Box<dyn Error>> {
+    Ok(JjRepositorySink::new("/Users/aadalal/dev/aadalal.github.io/", "main", "origin", "_tiny_thoughts")?
+        .with_text_policy(text_policy)
+        .with_filename_template(FilenameTemplate::parse(FILENAME_TEMPLATE)?)
+        .with_link_weights(LINK_WEIGHTS)
+        .with_provenance(PROVENANCE)
+        .with_removal_policy(REMOVAL_POLICY))
+}
+
+fn exec_sinks(canvas_path: &Path) -> Vec<ExecSink> {
+    EXEC_SINKS
+        .iter()
+        .map(|(name, program, args)| {
+            ExecSink::new(*name, *program, args)
+                .with_timeout(Duration::from_secs(EXEC_TIMEOUT_SECS))
+                .with_canvas_path(canvas_path)
+        })
+        .collect()
+}
+
+/// The plugins in `PLUGIN_DIR`; one that fails to load is left out with a warning, so it
+/// can't keep the other sinks from publishing
+fn plugin_sinks() -> Result<Vec<PluginSink>, Box<dyn Error>> {
+    let Some(dir) = PLUGIN_DIR else {
+        return Ok(Vec::new());
+    };
+    let mut sinks = Vec::new();
+    for path in discover_plugins(Path::new(dir))? {
+        match PluginSink::load(&path, plugin_config(&path)?) {
+            Ok(sink) => sinks.push(sink.with_timeout(Duration::from_secs(EXEC_TIMEOUT_SECS))),
+            Err(e) => warn!(error = %e, "Skipping plugin"),
+        }
+    }
+    Ok(sinks)
+}
+
+/// The `PLUGIN_CONFIGS` entry of the plugin at `path`, or `{}`
+fn plugin_config(path: &Path) -> Result<serde_json::Value, String> {
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let config = PLUGIN_CONFIGS.iter().find(|(name, _)| *name == file_name).map_or("{}", |(_, json)| *json);
+    serde_json::from_str(config).map_err(|e| format!("PLUGIN_CONFIGS for `{}`: {}", file_name, e))
+}
+
+/// The category map configured by `CATEGORIES` and `DEFAULT_CATEGORY`
+fn category_map() -> Option<CategoryMap> {
+    if CATEGORIES.is_empty() && DEFAULT_CATEGORY.is_none() {
+        return None;
+    }
+    let mut map = CATEGORIES.iter().fold(CategoryMap::new(), |map, (tag, category)| map.map(tag, *category));
+    if let Some(category) = DEFAULT_CATEGORY {
+        map = map.with_default(category);
+    }
+    Some(map)
+}
+
+/// Link checking per `CHECK_LINKS`, with a client that reports redirects instead of following them
+fn link_check(policy: LinkCheckPolicy) -> LinkCheckOptions {
+    let http = PoliteClient::new(ReqwestClient::without_redirects(), NetworkPolicy::default());
+    LinkCheckOptions::default()
+        .with_policy(policy)
+        .with_rewrite_redirects(REWRITE_MOVED_LINKS)
+        .with_http_client(Arc::new(http))
+}
+
+fn selection() -> Result<Selection, Box<dyn Error>> {
+    if PUBLISH_ALL {
+        return Ok(Selection::All);
+    }
+    Ok(Selection::Marked(ColorFilter::parse(SYNDICATION_COLORS)?.with_uncolored(SYNDICATE_UNCOLORED)))
+}
+
+fn badges() -> Vec<BadgeSpec> {
+    let published = PUBLISHED_BADGE.map(|(sink, path)| BadgeSpec::published_count(sink, path));
+    let last_post = LAST_POST_BADGE.map(|(sink, path)| BadgeSpec::days_since_last_post(sink, path));
+    published.into_iter().chain(last_post).map(|spec| spec.with_color(BADGE_COLOR)).collect()
+}
+
+fn resurface() -> Vec<ResurfaceOptions> {
+    RESURFACE
+        .map(|(sink, hours)| {
+            ResurfaceOptions::new(sink, Duration::from_secs(hours * 60 * 60))
+                .with_min_age_days(RESURFACE_MIN_AGE_DAYS)
+                .with_cooldown_days(RESURFACE_COOLDOWN_DAYS)
+                .with_prefix(RESURFACE_PREFIX)
+        })
+        .into_iter()
+        .collect()
+}
+
+fn warning_policy() -> WarningPolicy {
+    let policy = PROMOTED_WARNINGS.iter().fold(WarningPolicy::new(), |policy, code| policy.promote(*code));
+    SILENCED_WARNINGS.iter().fold(policy, |policy, code| policy.silence(*code))
+}
+
+fn edge_weights() -> Result<Option<EdgeWeights>, Box<dyn Error>> {
+    if EDGE_WEIGHTS.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(EdgeWeights::parse(EDGE_WEIGHTS)?.with_default_weight(DEFAULT_EDGE_WEIGHT)))
+}
+
+fn size_filter() -> SizeFilter {
+    let mut filter = if ONLY_NOTE_SIZED { SizeFilter::looks_like_note() } else { SizeFilter::any() };
+    if let Some(px) = MIN_NODE_AREA {
+        filter = filter.min_area(px);
+    }
+    if let Some(px) = MIN_NODE_WIDTH {
+        filter = filter.min_width(px);
+    }
+    if let Some((lo, hi)) = NODE_ASPECT {
+        filter = filter.aspect_between(lo, hi);
+    }
+    filter
+}
+
+/// `drain [--now]`: publish the pending batch and deliver queued items; `--now` ignores retry backoff and rate limits
+fn drain_command(syndicator: &mut Syndicator, force: bool) -> Result<(), Box<dyn Error>> {
+    let outcomes = syndicator.drain(force)?;
+    if outcomes.is_empty() {
+        println!("Nothing to deliver");
+    }
+    for outcome in outcomes {
+        match outcome.error {
+            Some(error) => println!("{}: failed to deliver {} items: {}", outcome.sink, outcome.items.len(), error),
+            None => println!("{}: delivered {} items", outcome.sink, outcome.items.len()),
+        }
+    }
+    Ok(())
+}
+
+/// `reconcile [--mark-found] [--clear-missing]`: compare trackers with published output
+fn reconcile_command(syndicator: &mut Syndicator, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut fix = ReconcileFix::default();
+    for arg in args {
+        match *arg {
+            "--mark-found" => fix.mark_found = true,
+            "--clear-missing" => fix.clear_missing = true,
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    for discrepancies in syndicator.reconcile(fix)? {
+        if discrepancies.is_empty() {
+            println!("{}: in sync", discrepancies.sink);
+            continue;
+        }
+        println!("{}:", discrepancies.sink);
+        for (node_id, path) in &discrepancies.untracked {
+            let action = if fix.mark_found { "marked published" } else { "use --mark-found to track" };
+            println!("  published but untracked: {} ({}) - {}", node_id, path.display(), action);
+        }
+        for node_id in &discrepancies.missing {
+            let action = if fix.clear_missing { "cleared" } else { "use --clear-missing to forget" };
+            println!("  tracked but missing: {} - {}", node_id, action);
+        }
+    }
+    Ok(())
+}
+
+/// `prune`: delete the published files of nodes removed from the canvas or excluded, and
+/// forget them, after showing exactly what goes
+///
+/// `--plan` only shows the plan. Otherwise it's carried out after `--yes` or a typed
+/// confirmation, and only if nothing changed since it was shown. Planning is refused after
+/// a mass removal unless `--accept-mass-removal` was given.
+fn prune_command(syndicator: &mut Syndicator, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let (mut plan_only, mut yes) = (false, false);
+    for arg in args {
+        match *arg {
+            "--plan" => plan_only = true,
+            "--yes" => yes = true,
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let plans = syndicator.plan_prune()?;
+    if plans.iter().all(|plan| plan.is_empty()) {
+        println!("Nothing to prune");
+        return Ok(());
+    }
+    for plan in &plans {
+        print!("{}", plan);
+    }
+    if plan_only {
+        return Ok(());
+    }
+
+    if !yes {
+        if !std::io::stdin().is_terminal() {
+            return Err("prune: pass --yes to confirm without a terminal".into());
+        }
+        print!("Remove all of the above? [y/N] ");
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            println!("Nothing removed");
+            return Ok(());
+        }
+    }
+
+    match syndicator.prune(&plans) {
+        Ok(done) => {
+            for plan in &done {
+                println!(
+                    "{}: deleted {} files, forgot {} tracker entries",
+                    plan.sink,
+                    plan.files.len(),
+                    plan.tracker_entries.len()
+                );
+            }
+            Ok(())
+        }
+        Err(SyndicationError::PlanChanged(changed)) => {
+            println!("The plan changed since it was shown; nothing was removed. It now reads:");
+            print!("{}", changed.current);
+            Err("prune: run it again to confirm the new plan".into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `state export|import`: move every state file (trackers, queue, history, ...) to or from
+/// one bundle file
+fn state_command(syndicator: &Syndicator, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    match args {
+        ["export", bundle_path] => {
+            let bundle = syndicator.export_state(Path::new(bundle_path))?;
+            for entry in &bundle.manifest {
+                println!("{} (schema {}, {} bytes)", entry.component, entry.schema_version, entry.bytes);
+            }
+            println!("Exported {} state files to {}", bundle.manifest.len(), bundle_path);
+        }
+        ["import", bundle_path, rest @ ..] => {
+            let force = match rest {
+                [] => false,
+                ["--force"] => true,
+                _ => return Err(USAGE.into()),
+            };
+            let report = syndicator.import_state(Path::new(bundle_path), force)?;
+            for component in &report.written {
+                println!("imported: {}", component);
+            }
+            for component in &report.unchanged {
+                println!("unchanged: {}", component);
+            }
+            for component in &report.skipped {
+                println!("skipped (not configured here): {}", component);
+            }
+        }
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+/// `validate` (`--validate-only`): list the canvas's structural issues, failing if there are any
+fn validate_command(syndicator: &Syndicator) -> Result<(), Box<dyn Error>> {
+    let issues = syndicator.validate()?;
+    if issues.is_empty() {
+        println!("No issues in {}", syndicator.config().canvas_path.display());
+        return Ok(());
+    }
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    Err(format!("{} canvas issues", issues.len()).into())
+}
+
+/// `verify`: check that published files parse back into valid frontmatter, without changing them
+fn verify_command(syndicator: &Syndicator) -> Result<(), Box<dyn Error>> {
+    let reports = syndicator.verify()?;
+    if reports.is_empty() {
+        println!("No sink has files to verify");
+    }
+    for report in &reports {
+        if report.is_empty() {
+            println!("{}: {} files ok", report.sink, report.files_checked);
+            continue;
+        }
+        println!("{}: {} of {} files have problems", report.sink, report.files_with_issues(), report.files_checked);
+        for issue in &report.issues {
+            println!("  {}: {}", issue.path.display(), issue.problem);
+        }
+    }
+    if reports.iter().any(|r| !r.is_empty()) {
+        return Err("verification failed".into());
+    }
+    Ok(())
+}
+
+/// `backfill`: add links to posts on other sites to the published files
+fn backfill_command(syndicator: &mut Syndicator) -> Result<(), Box<dyn Error>> {
+    let results = syndicator.backfill()?;
+    if results.is_empty() {
+        println!("No syndication links to add");
+    }
+    let mut failed = false;
+    for backfill in results {
+        match backfill.changed {
+            Ok(changed) => println!("{}: {} files updated", backfill.sink, changed),
+            Err(e) => {
+                println!("failed: {}", e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        return Err("backfill failed".into());
+    }
+    Ok(())
+}
+
+/// `backfill items [--chunk-size <n>] [--chunk-delay <secs>] [--only-sink <name>]...`: publish
+/// the backlog in chunks, resuming an interrupted backfill
+fn backfill_items_command(syndicator: &mut Syndicator, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut args = args.to_vec();
+    let chunk_size = match take_flag_value(&mut args, "--chunk-size")? {
+        Some(n) => n.parse().map_err(|_| format!("--chunk-size: `{}` isn't a number of items", n))?,
+        None => BACKFILL_CHUNK_SIZE,
+    };
+    let chunk_delay = match take_flag_value(&mut args, "--chunk-delay")? {
+        Some(secs) => secs.parse().map_err(|_| format!("--chunk-delay: `{}` isn't a number of seconds", secs))?,
+        None => BACKFILL_CHUNK_DELAY_SECS,
+    };
+    let mut sinks = Vec::new();
+    while let Some(sink_name) = take_flag_value(&mut args, "--only-sink")? {
+        sinks.push(sink_name);
+    }
+    if !args.is_empty() {
+        return Err(USAGE.into());
+    }
+    if sinks.is_empty() {
+        sinks = BACKFILL_SINKS.iter().copied().filter(|name| syndicator.tracker(name).is_some()).collect();
+    }
+    let options = BackfillOptions::default()
+        .with_chunk_size(chunk_size)
+        .with_chunk_delay(Duration::from_secs(chunk_delay))
+        .with_sinks(sinks);
+
+    let plan = syndicator.plan_backfill(&options)?;
+    if let Some(progress) = &plan.resuming {
+        println!(
+            "Resuming the backfill started {} after {} chunks",
+            progress.started_at.format("%Y-%m-%d %H:%M"),
+            progress.chunks
+        );
+    }
+    for (sink, pending) in &plan.pending {
+        let too_old = plan.too_old.get(sink).copied().unwrap_or_default();
+        println!("{}: {} items to publish, {} too old to publish", sink, pending, too_old);
+    }
+    let chunks = plan.chunks(options.chunk_size);
+    if chunks == 0 {
+        println!("Nothing to backfill");
+        return Ok(());
+    }
+    println!("{} chunks of up to {} items, {}s apart", chunks, options.chunk_size, chunk_delay);
+    if syndicator.config().dry_run {
+        return Ok(());
+    }
+
+    let progress = syndicator.backfill_items(&options, &CancelToken::new(), |report| {
+        let published: usize = report.sinks.iter().filter(|o| o.error.is_none()).map(|o| o.items.len()).sum();
+        println!("Chunk done: {} items published", published);
+        for outcome in report.sinks.iter().filter(|o| o.error.is_some()) {
+            println!("{}: failed, {} items queued for retrying", outcome.sink, outcome.items.len());
+        }
+    })?;
+    let totals = progress.totals();
+    println!(
+        "Backfill done in {} chunks: {} published, {} skipped, {} failed",
+        progress.chunks, totals.published, totals.skipped, totals.failed
+    );
+    if totals.failed > 0 {
+        println!("Run `drain` to retry the failed items");
+    }
+    Ok(())
+}
+
+/// `import-legacy`: record posts in the sinks' folders that weren't published from the canvas
+fn import_legacy_command(syndicator: &mut Syndicator) -> Result<(), Box<dyn Error>> {
+    let results = syndicator.import_legacy();
+    if results.is_empty() {
+        println!("No sink can import legacy posts");
+    }
+    let mut failed = false;
+    for import in results {
+        match import.imported {
+            Ok(imported) => {
+                println!("{}: {} legacy posts imported", import.sink, imported.len());
+                for (node_id, path) in imported {
+                    println!("  {}  {}", node_id, path.display());
+                }
+            }
+            Err(e) => {
+                println!("failed: {}", e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        return Err("import-legacy failed".into());
+    }
+    Ok(())
+}
+
+/// `exclude add|remove|list`: manage nodes that must never be syndicated
+fn exclude_command(syndicator: &Syndicator, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut exclusions = syndicator.exclusions()?;
+
+    match args {
+        ["add", node_id] => {
+            let node_id = syndicator.resolve_node_id(node_id)?;
+            if !exclusions.add(node_id.as_str())? {
+                println!("{} is already excluded", node_id);
+            }
+        }
+        ["remove", node_id] => {
+            let node_id = syndicator.resolve_node_id(node_id)?;
+            if !exclusions.remove(node_id.as_str())? {
+                println!("{} was not excluded", node_id);
+            }
+        }
+        ["list"] => {
+            for node_id in exclusions.iter() {
+                println!("{}", node_id);
+            }
+        }
+        _ => return Err(USAGE.into()),
+    }
+
+    Ok(())
 }
@@ -0,0 +1,44 @@
+//! Run the syndication pipeline from another program.
+//!
+//! ```sh
+//! cargo run -p syndicate-json-canvas-lib --example embedded -- path/to/Thoughts.canvas
+//! ```
+//!
+//! Publishes every red text node of the canvas to a sink that just prints it, once, in
+//! dry-run mode (so no tracker is updated), and prints the resulting report.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::{Config, SinkError, SyndicationFormat, SyndicationSink, Syndicator};
+
+/// A sink that prints items instead of publishing them
+struct PrintSink;
+
+impl SyndicationSink for PrintSink {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        for item in items.values() {
+            println!("{}[{}] {}", if dry_run { "(dry run) " } else { "" }, item.id, item.text);
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "print"
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let canvas_path = std::env::args().nth(1).ok_or("usage: embedded <path/to/file.canvas>")?;
+
+    let mut config = Config::new(canvas_path);
+    config.dry_run = true;
+
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(PrintSink)?;
+
+    let report = syndicator.process_once()?;
+    print!("{}", report.to_markdown());
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use crate::warnings::codes;
+
+/// Longest URL that may stand as a link's visible text by default
+pub const DEFAULT_MAX_BARE_URL_CHARS: usize = 40;
+
+/// Extensions of embeds Obsidian shows as images
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "avif"];
+
+/// What happens to an item with accessibility issues
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessibilityPolicy {
+    /// Publish it everywhere, with a warning per issue
+    #[default]
+    Warn,
+    /// Also hold it back from public-facing sinks until it's fixed
+    Block,
+}
+
+/// Accessibility checks run over each item's markdown before publishing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessibilityOptions {
+    /// Longest URL allowed as a link's visible text, in characters
+    pub max_bare_url_chars: usize,
+    pub policy: AccessibilityPolicy,
+}
+
+impl Default for AccessibilityOptions {
+    fn default() -> Self {
+        Self { max_bare_url_chars: DEFAULT_MAX_BARE_URL_CHARS, policy: AccessibilityPolicy::Warn }
+    }
+}
+
+impl AccessibilityOptions {
+    pub fn with_max_bare_url_chars(mut self, chars: usize) -> Self {
+        self.max_bare_url_chars = chars;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: AccessibilityPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+/// Something in an item's markdown that screen reader users can't make sense of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessibilityIssue {
+    /// A markdown image with empty alt text
+    MissingAlt { src: String },
+    /// An Obsidian `![[image]]` embed, which has no alt text unless written `![[image|alt]]`
+    EmbedWithoutAlt { target: String },
+    /// A link whose visible text is a long URL, read out character by character
+    BareUrl { url: String },
+    /// A heading more than one level below the one before it, e.g. h1 followed by h3
+    HeadingJump { from: u8, to: u8 },
+}
+
+impl AccessibilityIssue {
+    /// Warning code of the issue (see [`codes`])
+    pub fn code(&self) -> &'static str {
+        match self {
+            AccessibilityIssue::MissingAlt { .. } | AccessibilityIssue::EmbedWithoutAlt { .. } => codes::MISSING_ALT,
+            AccessibilityIssue::BareUrl { .. } => codes::BARE_URL_LINK,
+            AccessibilityIssue::HeadingJump { .. } => codes::HEADING_JUMP,
+        }
+    }
+}
+
+impl fmt::Display for AccessibilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessibilityIssue::MissingAlt { src } => write!(f, "Image `{}` has no alt text", src),
+            AccessibilityIssue::EmbedWithoutAlt { target } => {
+                write!(f, "Embedded image `{}` has no alt text; write it as `![[{}|alt text]]`", target, target)
+            }
+            AccessibilityIssue::BareUrl { url } => {
+                write!(f, "Link text is the bare URL `{}`; describe where it goes instead", url)
+            }
+            AccessibilityIssue::HeadingJump { from, to } => {
+                write!(f, "Heading jumps from h{} to h{}; don't skip heading levels", from, to)
+            }
+        }
+    }
+}
+
+/// Find the accessibility issues of a markdown text, in document order
+///
+/// Code spans and blocks are not checked.
+pub fn check_accessibility(markdown: &str, options: &AccessibilityOptions) -> Vec<AccessibilityIssue> {
+    let mut issues = Vec::new();
+    // Consecutive text, which the parser splits around brackets, scanned for embeds at once
+    let mut text = String::new();
+    // Destination and visible text of the image or link being read
+    let mut image: Option<(String, String)> = None;
+    let mut link: Option<String> = None;
+    let mut last_heading: Option<u8> = None;
+
+    for event in Parser::new(markdown) {
+        if let Event::Text(chunk) = &event {
+            text.push_str(chunk);
+            if let Some((_, alt)) = &mut image {
+                alt.push_str(chunk);
+            }
+            if let Some(link_text) = &mut link {
+                link_text.push_str(chunk);
+            }
+            continue;
+        }
+        issues.extend(embeds_without_alt(&text));
+        text.clear();
+
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let level = level as u8;
+                if let Some(from) = last_heading.filter(|&from| level > from + 1) {
+                    issues.push(AccessibilityIssue::HeadingJump { from, to: level });
+                }
+                last_heading = Some(level);
+            }
+            Event::Start(Tag::Image { dest_url, .. }) => image = Some((dest_url.to_string(), String::new())),
+            Event::End(TagEnd::Image) => {
+                if let Some((src, alt)) = image.take()
+                    && alt.trim().is_empty()
+                {
+                    issues.push(AccessibilityIssue::MissingAlt { src });
+                }
+            }
+            Event::Start(Tag::Link { .. }) => link = Some(String::new()),
+            Event::End(TagEnd::Link) => {
+                if let Some(link_text) = link.take() {
+                    let url = link_text.trim();
+                    if is_url(url) && url.chars().count() > options.max_bare_url_chars {
+                        issues.push(AccessibilityIssue::BareUrl { url: url.to_string() });
+                    }
+                }
+            }
+            Event::Code(code) => {
+                if let Some((_, alt)) = &mut image {
+                    alt.push_str(&code);
+                }
+                if let Some(link_text) = &mut link {
+                    link_text.push_str(&code);
+                }
+            }
+            _ => {}
+        }
+    }
+    issues.extend(embeds_without_alt(&text));
+    issues
+}
+
+fn is_url(text: &str) -> bool {
+    ["http://", "https://", "www."].iter().any(|prefix| text.starts_with(prefix))
+}
+
+/// `![[image]]` embeds in a run of text whose only `|` options are sizes (`300`, `300x200`)
+fn embeds_without_alt(text: &str) -> Vec<AccessibilityIssue> {
+    let mut issues = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("![[") {
+        rest = &rest[start + 3..];
+        let Some(end) = rest.find("]]") else {
+            break;
+        };
+        let mut parts = rest[..end].split('|');
+        let target = parts.next().unwrap_or_default().trim();
+        let has_alt = parts.map(str::trim).any(|part| !part.is_empty() && !is_size(part));
+        if is_image(target) && !has_alt {
+            issues.push(AccessibilityIssue::EmbedWithoutAlt { target: target.to_string() });
+        }
+        rest = &rest[end + 2..];
+    }
+    issues
+}
+
+fn is_image(target: &str) -> bool {
+    let path = target.split('#').next().unwrap_or_default();
+    path.rsplit_once('.')
+        .is_some_and(|(_, extension)| IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+fn is_size(option: &str) -> bool {
+    option.split('x').all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(markdown: &str) -> Vec<AccessibilityIssue> {
+        check_accessibility(markdown, &AccessibilityOptions::default())
+    }
+
+    #[test]
+    fn each_check_finds_its_issue() {
+        let long_url = format!("https://example.com/{}", "a".repeat(40));
+        let markdown = format!(
+            "# Title\n\n### Skipped\n\n![](owl.png) ![An owl](owl.png)\n\n\
+             ![[cat.jpg]] ![[cat.jpg|300]] ![[cat.jpg|A cat]] ![[Some note]]\n\n\
+             <{url}> [{url}]({url}) [a short one](https://x.io) [https://x.io](https://x.io)\n",
+            url = long_url
+        );
+        assert_eq!(
+            issues(&markdown),
+            [
+                AccessibilityIssue::HeadingJump { from: 1, to: 3 },
+                AccessibilityIssue::MissingAlt { src: "owl.png".to_string() },
+                AccessibilityIssue::EmbedWithoutAlt { target: "cat.jpg".to_string() },
+                AccessibilityIssue::EmbedWithoutAlt { target: "cat.jpg".to_string() },
+                AccessibilityIssue::BareUrl { url: long_url.clone() },
+                AccessibilityIssue::BareUrl { url: long_url.clone() },
+            ]
+        );
+        let codes: Vec<&str> = issues(&markdown).iter().map(AccessibilityIssue::code).collect();
+        let expected = ["heading-jump", "missing-alt", "missing-alt", "missing-alt", "bare-url-link", "bare-url-link"];
+        assert_eq!(codes, expected);
+        let options = AccessibilityOptions::default().with_max_bare_url_chars(100);
+        assert_eq!(check_accessibility(&format!("<{}>", long_url), &options), []);
+    }
+
+    #[test]
+    fn code_and_well_formed_text_pass() {
+        let headings = "# One\n\n## Two\n\n### Three\n\n# Back to one\n\n#### Four";
+        assert_eq!(issues(headings), [AccessibilityIssue::HeadingJump { from: 1, to: 4 }]);
+        assert_eq!(issues("`![[cat.jpg]]` and\n\n```\n![](owl.png)\n```\n"), []);
+        let embed = AccessibilityIssue::EmbedWithoutAlt { target: "cat.jpg".to_string() };
+        assert!(embed.to_string().contains("`![[cat.jpg|alt text]]`"), "{}", embed);
+    }
+}
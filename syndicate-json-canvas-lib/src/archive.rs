@@ -0,0 +1,45 @@
+use std::collections::HashSet;
+
+use crate::jsoncanvas::{JsonCanvas, Node, NodeId, node::GenericNodeInfo};
+
+/// Label of the group that published nodes are moved into to archive them
+pub const ARCHIVE_GROUP_LABEL: &str = "Archive";
+
+/// IDs of the non-group nodes lying entirely inside a group labelled "Archive"
+///
+/// The label match is case-insensitive and ignores surrounding whitespace.
+pub fn archived_node_ids(canvas: &JsonCanvas) -> HashSet<NodeId> {
+    node_ids_in_group(canvas, ARCHIVE_GROUP_LABEL)
+}
+
+/// IDs of the non-group nodes lying entirely inside a group labelled `label`, matched like
+/// the Archive group's
+pub fn node_ids_in_group(canvas: &JsonCanvas, label: &str) -> HashSet<NodeId> {
+    let nodes = canvas.get_nodes();
+    let label = label.trim();
+
+    let groups: Vec<&Node> = nodes
+        .values()
+        .filter(|node| match node {
+            Node::Group(group) => group.label().is_some_and(|own| own.trim().eq_ignore_ascii_case(label)),
+            _ => false,
+        })
+        .collect();
+
+    nodes
+        .iter()
+        .filter(|(_, node)| !matches!(node, Node::Group(_)))
+        .filter(|(_, node)| groups.iter().any(|group| contains(group, node)))
+        .map(|(node_id, _)| node_id.clone())
+        .collect()
+}
+
+/// Whether `inner`'s bounding box lies within `outer`'s
+pub(crate) fn contains(outer: &Node, inner: &Node) -> bool {
+    let (ox, oy) = (outer.get_x(), outer.get_y());
+    let (ix, iy) = (inner.get_x(), inner.get_y());
+    ix >= ox
+        && iy >= oy
+        && ix + inner.get_width() as i64 <= ox + outer.get_width() as i64
+        && iy + inner.get_height() as i64 <= oy + outer.get_height() as i64
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+/// How long a write is remembered, for recognizing the watcher events it causes
+const OWN_WRITE_TTL: Duration = Duration::from_secs(30);
+
+/// Files this process wrote recently, by path (with its directory resolved), with a hash
+/// of what was written and when
+static OWN_WRITES: LazyLock<Mutex<HashMap<PathBuf, (String, Instant)>>> = LazyLock::new(Default::default);
+
+/// Write a file by writing a sibling temp file and renaming it over the destination
+///
+/// Readers never see a half-written file. On Windows the rename fails if the destination
+/// is held open without delete sharing (editors, virus scanners), so the destination is
+/// removed and the rename retried once.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let temp_path = temp_path(path)?;
+    std::fs::write(&temp_path, contents.as_ref())?;
+
+    let result = std::fs::rename(&temp_path, path).or_else(|error| {
+        if !path.exists() {
+            return Err(error);
+        }
+        std::fs::remove_file(path)?;
+        std::fs::rename(&temp_path, path)
+    });
+    match &result {
+        Ok(()) => record_own_write(path, contents.as_ref()),
+        Err(_) => {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+    }
+    result
+}
+
+/// Remember that this process just wrote `contents` to `path`, so the file watcher can
+/// tell the change apart from an edit; [`write_atomic`] does this for every file
+pub fn record_own_write(path: &Path, contents: &[u8]) {
+    let Some(key) = own_write_key(path) else {
+        return;
+    };
+    let mut own_writes = OWN_WRITES.lock().expect("own writes lock poisoned");
+    own_writes.retain(|_, (_, at)| at.elapsed() < OWN_WRITE_TTL);
+    own_writes.insert(key, (format!("{:x}", Sha256::digest(contents)), Instant::now()));
+}
+
+/// Whether `path` still holds what this process recently wrote to it
+pub fn is_own_write(path: &Path) -> bool {
+    let Some(key) = own_write_key(path) else {
+        return false;
+    };
+    let own_writes = OWN_WRITES.lock().expect("own writes lock poisoned");
+    own_writes.get(&key).is_some_and(|(hash, at)| {
+        at.elapsed() < OWN_WRITE_TTL
+            && std::fs::read(path).is_ok_and(|contents| format!("{:x}", Sha256::digest(contents)) == *hash)
+    })
+}
+
+/// `path` with its directory canonicalized, as watcher events and writers may spell it
+/// differently (relative or absolute, through symlinks)
+fn own_write_key(path: &Path) -> Option<PathBuf> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
+
+/// `.<file-name>.tmp` next to `path`, so the rename stays on one filesystem
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    Ok(path.with_file_name(format!(".{}.tmp", file_name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-atomic-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn replaces_the_file_and_leaves_no_temp_file() {
+        let dir = scratch("replace");
+        let path = dir.join("tracker.toml");
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        let names: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(names, ["tracker.toml"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_write_keeps_the_old_contents() {
+        let dir = scratch("failed");
+        let path = dir.join("queue.json");
+        write_atomic(&path, "old").unwrap();
+        // A directory where the temp file goes makes the write fail before the rename
+        std::fs::create_dir(dir.join(".queue.json.tmp")).unwrap();
+        assert!(write_atomic(&path, "new").is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        assert!(write_atomic(Path::new("/"), "no file name").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
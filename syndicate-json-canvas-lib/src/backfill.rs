@@ -0,0 +1,271 @@
+//! Publishing a large backlog in chunks, e.g. the first time a full archive is syndicated
+//!
+//! Each chunk is an ordinary run limited to [`BackfillOptions::chunk_size`] new items per
+//! sink, so trackers record progress as each chunk lands and an interrupted backfill picks up
+//! with the next unpublished items. Running totals are kept next to the canvas until the
+//! backlog is done.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::atomic::write_atomic;
+use crate::batch::BatchPolicy;
+use crate::cancel::CancelToken;
+use crate::orchestrator::{ProcessOptions, SinkTarget, process_snapshot, run_and_report};
+use crate::report::ProcessReport;
+use crate::snapshot::CanvasSnapshot;
+use crate::tracker::canvas_state_path;
+
+/// Suffix of the file an unfinished backfill keeps its progress in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-backfill.json";
+
+/// Default for [`BackfillOptions::chunk_size`]
+pub const DEFAULT_BACKFILL_CHUNK_SIZE: usize = 50;
+
+/// How a backfill splits up the backlog
+#[derive(Debug, Clone)]
+pub struct BackfillOptions {
+    /// Most new items each sink takes per chunk
+    pub chunk_size: usize,
+    /// Pause between chunks
+    pub chunk_delay: Duration,
+    /// Names of the sinks to backfill; empty for every sink
+    pub sinks: Vec<String>,
+}
+
+impl Default for BackfillOptions {
+    fn default() -> Self {
+        Self { chunk_size: DEFAULT_BACKFILL_CHUNK_SIZE, chunk_delay: Duration::ZERO, sinks: Vec::new() }
+    }
+}
+
+impl BackfillOptions {
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    pub fn with_chunk_delay(mut self, chunk_delay: Duration) -> Self {
+        self.chunk_delay = chunk_delay;
+        self
+    }
+
+    pub fn with_sinks(mut self, sinks: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.sinks = sinks.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// What a backfill would publish, before it starts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackfillPlan {
+    /// Items waiting to be published, by sink
+    pub pending: BTreeMap<String, usize>,
+    /// Items the sink will skip for being older than its maximum item age, by sink
+    pub too_old: BTreeMap<String, usize>,
+    /// Progress of an interrupted backfill this one resumes
+    pub resuming: Option<BackfillProgress>,
+}
+
+impl BackfillPlan {
+    /// Chunks needed for the largest sink's backlog
+    pub fn chunks(&self, chunk_size: usize) -> usize {
+        self.pending.values().max().map_or(0, |pending| pending.div_ceil(chunk_size.max(1)))
+    }
+}
+
+/// Items a backfill handled for one sink
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackfillCounts {
+    pub published: usize,
+    /// Skipped for good for being older than the sink's maximum item age
+    pub skipped: usize,
+    /// Left in the outbound queue for retrying after the sink failed to take them
+    pub failed: usize,
+}
+
+impl BackfillCounts {
+    fn add(&mut self, other: BackfillCounts) {
+        self.published += other.published;
+        self.skipped += other.skipped;
+        self.failed += other.failed;
+    }
+}
+
+/// Running totals of a backfill, saved after every chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillProgress {
+    pub started_at: DateTime<Local>,
+    /// Chunks published so far
+    pub chunks: usize,
+    /// Counts so far, by sink
+    pub sinks: BTreeMap<String, BackfillCounts>,
+    /// Whether the backlog is done; an unfinished backfill resumes on the next call
+    #[serde(skip)]
+    pub finished: bool,
+}
+
+impl BackfillProgress {
+    fn new(started_at: DateTime<Local>) -> Self {
+        Self { started_at, chunks: 0, sinks: BTreeMap::new(), finished: false }
+    }
+
+    /// Progress of the canvas's unfinished backfill, if there is one
+    pub fn for_canvas(canvas_path: &Path) -> Result<Option<Self>, Box<dyn Error>> {
+        let path = canvas_state_path(canvas_path, STATE_FILE)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(&path)?)?))
+    }
+
+    /// Counts over every sink
+    pub fn totals(&self) -> BackfillCounts {
+        let mut totals = BackfillCounts::default();
+        for counts in self.sinks.values() {
+            totals.add(*counts);
+        }
+        totals
+    }
+
+    fn save(&self, canvas_path: &Path) -> Result<(), Box<dyn Error>> {
+        write_atomic(&canvas_state_path(canvas_path, STATE_FILE)?, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn clear(canvas_path: &Path) -> Result<(), Box<dyn Error>> {
+        let path = canvas_state_path(canvas_path, STATE_FILE)?;
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Count what a backfill of `backfill.sinks` would publish, with a dry run that hands no
+/// sink any item
+pub(crate) fn plan_backfill(
+    canvas_path: &Path,
+    targets: &mut Vec<SinkTarget>,
+    options: &ProcessOptions,
+    backfill: &BackfillOptions,
+) -> Result<BackfillPlan, Box<dyn Error>> {
+    let snapshot = CanvasSnapshot::read(canvas_path, options.clock.now())?;
+    let options = ProcessOptions { max_new_items: Some(0), ..chunk_options(options) };
+    let report = with_sinks(targets, &backfill.sinks, |targets| {
+        process_snapshot(&snapshot, targets, true, &options, &CancelToken::new())
+    })?;
+    if let Some(error) = report.error {
+        return Err(error.into());
+    }
+
+    let mut plan = BackfillPlan { resuming: BackfillProgress::for_canvas(canvas_path)?, ..BackfillPlan::default() };
+    for outcome in report.sinks {
+        plan.pending.insert(outcome.sink.clone(), outcome.held_back.len());
+        plan.too_old.insert(outcome.sink, outcome.age_skipped.len());
+    }
+    Ok(plan)
+}
+
+/// Publish the backlog to `backfill.sinks` chunk by chunk, until it's done or `cancel` is
+/// set, calling `on_chunk` with the report of each chunk
+///
+/// Progress is saved after every chunk, so a backfill that's cancelled or dies resumes with
+/// its totals on the next call. Returns the totals, which are forgotten once it's finished.
+pub(crate) fn backfill(
+    canvas_path: &Path,
+    targets: &mut Vec<SinkTarget>,
+    dry_run: bool,
+    options: &ProcessOptions,
+    backfill: &BackfillOptions,
+    cancel: &CancelToken,
+    mut on_chunk: impl FnMut(&ProcessReport),
+) -> Result<BackfillProgress, Box<dyn Error>> {
+    if dry_run {
+        return Err("a backfill can't run dry; plan it instead".into());
+    }
+    let options = ProcessOptions { max_new_items: Some(backfill.chunk_size.max(1)), ..chunk_options(options) };
+    let mut progress = match BackfillProgress::for_canvas(canvas_path)? {
+        Some(progress) => {
+            info!(chunks = progress.chunks, started_at = %progress.started_at, "Resuming backfill");
+            progress
+        }
+        None => BackfillProgress::new(options.clock.now()),
+    };
+
+    with_sinks(targets, &backfill.sinks, |targets| -> Result<(), Box<dyn Error>> {
+        while !cancel.is_cancelled() {
+            let snapshot = CanvasSnapshot::read(canvas_path, options.clock.now());
+            let report = run_and_report(canvas_path, snapshot, targets, false, &options, &CancelToken::new());
+            if let Some(error) = &report.error {
+                return Err(format!("chunk {} failed: {}", progress.chunks + 1, error).into());
+            }
+
+            // Dry sinks never settle their items, so they're neither counted nor waited for
+            let mut handed = 0;
+            let mut remaining = 0;
+            for outcome in report.sinks.iter().filter(|o| !o.dry_run) {
+                let items = outcome.items.len();
+                let counts = BackfillCounts {
+                    published: if outcome.error.is_none() { items } else { 0 },
+                    skipped: outcome.age_skipped.len(),
+                    failed: if outcome.error.is_some() { items } else { 0 },
+                };
+                progress.sinks.entry(outcome.sink.clone()).or_default().add(counts);
+                handed += items;
+                remaining += outcome.held_back.len();
+            }
+            progress.chunks += 1;
+            progress.save(canvas_path)?;
+            info!(chunk = progress.chunks, handed, remaining, "Published backfill chunk");
+            on_chunk(&report);
+
+            if remaining == 0 {
+                BackfillProgress::clear(canvas_path)?;
+                progress.finished = true;
+                break;
+            }
+            if handed == 0 {
+                return Err(format!("chunk {} published nothing with {} items left", progress.chunks, remaining).into());
+            }
+            std::thread::sleep(backfill.chunk_delay);
+        }
+        Ok(())
+    })??;
+    Ok(progress)
+}
+
+/// Options for the runs of a backfill: new items only, published right away
+fn chunk_options(options: &ProcessOptions) -> ProcessOptions {
+    ProcessOptions { queue: None, batching: BatchPolicy::Immediate, resurface: Vec::new(), ..options.clone() }
+}
+
+/// Call `f` with the targets named in `sinks` (every target if empty), setting the others
+/// aside until it returns
+fn with_sinks<T>(
+    targets: &mut Vec<SinkTarget>,
+    sinks: &[String],
+    f: impl FnOnce(&mut [SinkTarget]) -> T,
+) -> Result<T, Box<dyn Error>> {
+    if let Some(unknown) = sinks.iter().find(|name| !targets.iter().any(|t| t.sink.name() == name.as_str())) {
+        return Err(format!("unknown sink `{}`", unknown).into());
+    }
+    let (chosen, others): (Vec<_>, Vec<_>) = std::mem::take(targets)
+        .into_iter()
+        .enumerate()
+        .partition(|(_, t)| sinks.is_empty() || sinks.iter().any(|name| name == t.sink.name()));
+    let (positions, mut chosen): (Vec<usize>, Vec<SinkTarget>) = chosen.into_iter().unzip();
+
+    let result = f(&mut chosen);
+
+    let mut restored: Vec<(usize, SinkTarget)> = positions.into_iter().zip(chosen).chain(others).collect();
+    restored.sort_by_key(|(position, _)| *position);
+    *targets = restored.into_iter().map(|(_, target)| target).collect();
+    Ok(result)
+}
@@ -0,0 +1,115 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic::write_atomic;
+use crate::history::{HistoryEvent, HistoryEventKind};
+use crate::tracker::SyndicationTracker;
+
+/// Color of badges by default, one of shields.io's named colors or a hex code
+pub const DEFAULT_BADGE_COLOR: &str = "blue";
+
+/// A shields.io endpoint badge, as `https://img.shields.io/endpoint?url=...` reads it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Badge {
+    /// Always 1
+    pub schema_version: u32,
+    pub label: String,
+    pub message: String,
+    pub color: String,
+}
+
+impl Badge {
+    pub fn new(label: impl Into<String>, message: impl Into<String>, color: impl Into<String>) -> Self {
+        Self { schema_version: 1, label: label.into(), message: message.into(), color: color.into() }
+    }
+}
+
+/// What a badge counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BadgeKind {
+    /// Nodes published to the sink and not archived, from its tracker
+    PublishedCount,
+    /// Whole days since the newest node was published to the sink, from the history log
+    DaysSinceLastPost,
+}
+
+/// A badge file written after every run that isn't a dry run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BadgeSpec {
+    pub kind: BadgeKind,
+    /// Sink whose publications are counted
+    pub sink: String,
+    /// Where the badge JSON is written, e.g. inside the repository a sink commits
+    pub path: PathBuf,
+    pub label: String,
+    pub color: String,
+}
+
+impl BadgeSpec {
+    /// Badge of how many nodes are published to a sink, labeled "thoughts published"
+    pub fn published_count(sink: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self::new(BadgeKind::PublishedCount, sink, path, "thoughts published")
+    }
+
+    /// Badge of how many days ago a sink last got a new node, labeled "days since last post"
+    pub fn days_since_last_post(sink: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self::new(BadgeKind::DaysSinceLastPost, sink, path, "days since last post")
+    }
+
+    fn new(kind: BadgeKind, sink: impl Into<String>, path: impl Into<PathBuf>, label: &str) -> Self {
+        Self {
+            kind,
+            sink: sink.into(),
+            path: path.into(),
+            label: label.to_string(),
+            color: DEFAULT_BADGE_COLOR.to_string(),
+        }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Into<String>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// The badge as of `now`, from the sink's tracker and the canvas's history events
+    ///
+    /// A sink that never got a post shows "never" in grey.
+    pub fn badge(&self, tracker: &SyndicationTracker, history: &[HistoryEvent], now: DateTime<Local>) -> Badge {
+        match self.kind {
+            BadgeKind::PublishedCount => Badge::new(&self.label, tracker.live_count().to_string(), &self.color),
+            BadgeKind::DaysSinceLastPost => match days_since_last_post(&self.sink, history, now) {
+                Some(days) => Badge::new(&self.label, days.to_string(), &self.color),
+                None => Badge::new(&self.label, "never", "lightgrey"),
+            },
+        }
+    }
+
+    /// Write the badge to its path
+    pub fn write(&self, badge: &Badge) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        write_atomic(&self.path, serde_json::to_string(badge)? + "\n")?;
+        Ok(())
+    }
+}
+
+/// Calendar days between the newest publish event of a sink and `now`, if it has any
+pub fn days_since_last_post(sink: &str, history: &[HistoryEvent], now: DateTime<Local>) -> Option<i64> {
+    let last = history
+        .iter()
+        .filter(|event| event.sink == sink && event.kind == HistoryEventKind::Published)
+        .map(|event| event.at)
+        .max()?;
+    Some((now.date_naive() - last.date_naive()).num_days().max(0))
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::atomic::write_atomic;
+use crate::jsoncanvas::NodeId;
+use crate::tracker::canvas_state_path;
+
+/// Suffix of the file the pending batch is kept in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-batch.json";
+
+/// When newly eligible items are published
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BatchPolicy {
+    /// On the run that finds them
+    #[default]
+    Immediate,
+    /// Together, once no new item has been added for the given time
+    Window(Duration),
+    /// Together, only when flushed explicitly (e.g. by `drain`)
+    Manual,
+}
+
+/// JSON structure for the batch file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BatchFile {
+    /// Buffered node IDs with the time each was added
+    items: BTreeMap<String, DateTime<Local>>,
+}
+
+/// Items held back by a [`BatchPolicy`] until their batch is published
+///
+/// Only node IDs are kept: when the batch is published the items are read from the canvas
+/// again, so edits made while they waited are included.
+pub struct PendingBatch {
+    /// Path to the JSON batch file
+    path: PathBuf,
+    state: BatchFile,
+}
+
+impl PendingBatch {
+    /// Load the pending batch for a canvas file
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-batch.json`
+    pub fn for_canvas(canvas_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = canvas_state_path(canvas_path, STATE_FILE)?;
+        let state = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            BatchFile::default()
+        };
+
+        Ok(Self { path, state })
+    }
+
+    /// Buffer the current candidates: new ones are added at `now`, and buffered items that
+    /// are no longer candidates (deleted, recolored, published elsewhere) are dropped.
+    /// Returns the number added.
+    pub fn update(&mut self, candidates: &[NodeId], now: DateTime<Local>) -> usize {
+        self.state
+            .items
+            .retain(|node_id, _| candidates.iter().any(|c| c.as_str() == node_id));
+        let mut added = 0;
+        for node_id in candidates {
+            if !self.state.items.contains_key(node_id.as_str()) {
+                self.state.items.insert(node_id.as_str().to_string(), now);
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// When a batch closes under a window: `window` after the most recent addition, or the
+    /// latest representable time for a window too long to add
+    pub fn deadline(&self, window: Duration) -> Option<DateTime<Local>> {
+        let last_added = self.state.items.values().max()?;
+        let window = chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX);
+        let never = DateTime::<Utc>::MAX_UTC.with_timezone(&Local);
+        Some(last_added.checked_add_signed(window).unwrap_or(never))
+    }
+
+    /// Buffered node IDs
+    pub fn node_ids(&self) -> Vec<NodeId> {
+        self.state.items.keys().filter_map(|id| id.parse().ok()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.items.is_empty()
+    }
+
+    /// Forget every buffered item (they are being published)
+    pub fn clear(&mut self) {
+        self.state.items.clear();
+    }
+
+    /// Persist the batch
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_atomic(&self.path, serde_json::to_string_pretty(&self.state)?)?;
+        debug!(batch_path = %self.path.display(), buffered = self.len(), "Saved pending batch");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn batch_added_at(added: DateTime<Local>) -> PendingBatch {
+        let mut batch = PendingBatch { path: PathBuf::new(), state: BatchFile::default() };
+        batch.update(&["ba7c000000000001".parse().unwrap()], added);
+        batch
+    }
+
+    #[test]
+    fn deadline_is_window_after_last_addition() {
+        let added = Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let batch = batch_added_at(added);
+        let deadline = batch.deadline(Duration::from_secs(60 * 60));
+        assert_eq!(deadline, Some(added + chrono::Duration::hours(1)));
+    }
+
+    #[test]
+    fn deadline_saturates_for_overlong_window() {
+        let added = Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let batch = batch_added_at(added);
+        let deadline = batch.deadline(Duration::MAX).unwrap();
+        assert!(deadline > added + chrono::Duration::days(365 * 1000), "{}", deadline);
+    }
+
+    #[test]
+    fn no_deadline_when_empty() {
+        let batch = PendingBatch { path: PathBuf::new(), state: BatchFile::default() };
+        assert_eq!(batch.deadline(Duration::from_secs(1)), None);
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Asks a processing run to stop while it's still reading and filtering the canvas
+///
+/// Clones share the same flag. A run only checks it before sinks are touched: once
+/// publishing has begun the run always completes.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Clear a previous request, before starting a new run
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use jsoncanvas::color::Color;
+use jsoncanvas::edge::{Edge, End};
+use jsoncanvas::{FileNode, GroupNode, JsonCanvas, LinkNode, Node, TextNode};
+
+/// Size of nodes built without [`CanvasBuilder::size`], as Obsidian sizes new text cards
+const DEFAULT_WIDTH: u64 = 250;
+const DEFAULT_HEIGHT: u64 = 60;
+
+/// Vertical gap between nodes placed without [`CanvasBuilder::at`]
+const DEFAULT_SPACING: i64 = 40;
+
+/// Error types for [`CanvasBuilder::build`]
+#[derive(Debug, thiserror::Error)]
+pub enum CanvasBuildError {
+    #[error("Empty node ID")]
+    EmptyNodeId,
+
+    #[error("Duplicate node ID `{0}`")]
+    DuplicateNode(String),
+
+    #[error("Edge `{edge}` references node `{node}`, which doesn't exist")]
+    DanglingEdge { edge: String, node: String },
+
+    #[error("Invalid URL `{url}` for link node `{node}`: {reason}")]
+    InvalidUrl { node: String, url: String, reason: String },
+
+    #[error("`{method}` can't be applied here: {reason}")]
+    Misplaced { method: &'static str, reason: &'static str },
+}
+
+enum NodeKind {
+    Text(String),
+    File(PathBuf),
+    Link(String),
+    Group,
+}
+
+struct NodeSpec {
+    id: String,
+    kind: NodeKind,
+    position: Option<(i64, i64)>,
+    size: (u64, u64),
+    color: Option<Color>,
+    label: Option<String>,
+}
+
+struct EdgeSpec {
+    id: String,
+    from: String,
+    to: String,
+    color: Option<Color>,
+    label: Option<String>,
+    /// Whether the `from` and `to` ends have an arrow, if set; unset ends get the spec's
+    /// defaults, an arrow on the `to` end only
+    arrows: Option<(bool, bool)>,
+}
+
+/// What the modifier methods (`color`, `at`, `label`, ...) apply to
+enum Last {
+    Nothing,
+    Node,
+    Edge,
+}
+
+/// Builds a [`JsonCanvas`] in code, for tests and for scripts generating canvases
+///
+/// Each node or edge method adds an element; the modifiers that follow (`color`, `at`,
+/// `size`, `label`, `arrows`) apply to the element added last. Problems are reported by
+/// [`build`](Self::build), so calls can be chained freely.
+///
+/// For example `CanvasBuilder::new().text_node("a", "First").color(PresetColor::Red)
+/// .link_node("b", "https://example.com").edge("a", "b").label("see also").build()`.
+pub struct CanvasBuilder {
+    nodes: Vec<NodeSpec>,
+    edges: Vec<EdgeSpec>,
+    edge_ids: HashSet<String>,
+    last: Last,
+    /// First modifier applied to the wrong kind of element, reported by `build`
+    misplaced: Option<CanvasBuildError>,
+}
+
+impl Default for CanvasBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CanvasBuilder {
+    pub fn new() -> Self {
+        Self { nodes: Vec::new(), edges: Vec::new(), edge_ids: HashSet::new(), last: Last::Nothing, misplaced: None }
+    }
+
+    /// Add a text node
+    pub fn text_node(self, id: impl Into<String>, text: impl Into<String>) -> Self {
+        self.node(id.into(), NodeKind::Text(text.into()))
+    }
+
+    /// Add a file node, with a vault-relative path
+    pub fn file_node(self, id: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.node(id.into(), NodeKind::File(path.into()))
+    }
+
+    /// Add a link node
+    pub fn link_node(self, id: impl Into<String>, url: impl Into<String>) -> Self {
+        self.node(id.into(), NodeKind::Link(url.into()))
+    }
+
+    /// Add a group node
+    pub fn group_node(self, id: impl Into<String>) -> Self {
+        self.node(id.into(), NodeKind::Group)
+    }
+
+    /// Add an edge between two nodes, which may be added before or after it
+    ///
+    /// The edge ID is `<from>-<to>`, with a numeric suffix for parallel edges.
+    pub fn edge(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        let (from, to) = (from.into(), to.into());
+        let base = format!("{}-{}", from, to);
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while self.edge_ids.contains(&id) {
+            suffix += 1;
+            id = format!("{}-{}", base, suffix);
+        }
+        self.edge_ids.insert(id.clone());
+        self.edges.push(EdgeSpec { id, from, to, color: None, label: None, arrows: None });
+        self.last = Last::Edge;
+        self
+    }
+
+    /// Color the last node or edge
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        let color = Some(color.into());
+        match self.last {
+            Last::Node => self.nodes.last_mut().expect("a node was added").color = color,
+            Last::Edge => self.edges.last_mut().expect("an edge was added").color = color,
+            Last::Nothing => return self.misplaced("color", "no node or edge added yet"),
+        }
+        self
+    }
+
+    /// Position the last node
+    pub fn at(mut self, x: i64, y: i64) -> Self {
+        match self.last {
+            Last::Node => self.nodes.last_mut().expect("a node was added").position = Some((x, y)),
+            _ => return self.misplaced("at", "only nodes have a position"),
+        }
+        self
+    }
+
+    /// Size the last node
+    pub fn size(mut self, width: u64, height: u64) -> Self {
+        match self.last {
+            Last::Node => self.nodes.last_mut().expect("a node was added").size = (width, height),
+            _ => return self.misplaced("size", "only nodes have a size"),
+        }
+        self
+    }
+
+    /// Label the last edge or group node
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        let label = Some(label.into());
+        match self.last {
+            Last::Edge => self.edges.last_mut().expect("an edge was added").label = label,
+            Last::Node => match self.nodes.last_mut().expect("a node was added") {
+                node @ NodeSpec { kind: NodeKind::Group, .. } => node.label = label,
+                _ => return self.misplaced("label", "only edges and group nodes have a label"),
+            },
+            Last::Nothing => return self.misplaced("label", "no edge or group node added yet"),
+        }
+        self
+    }
+
+    /// Set which ends of the last edge have an arrow
+    pub fn arrows(mut self, from: bool, to: bool) -> Self {
+        match self.last {
+            Last::Edge => self.edges.last_mut().expect("an edge was added").arrows = Some((from, to)),
+            _ => return self.misplaced("arrows", "only edges have arrows"),
+        }
+        self
+    }
+
+    /// Check the canvas and build it
+    ///
+    /// Nodes without a position are stacked in a column, in the order they were added.
+    pub fn build(self) -> Result<JsonCanvas, CanvasBuildError> {
+        if let Some(error) = self.misplaced {
+            return Err(error);
+        }
+
+        let mut node_ids = HashSet::new();
+        for node in &self.nodes {
+            if node.id.is_empty() {
+                return Err(CanvasBuildError::EmptyNodeId);
+            }
+            if !node_ids.insert(node.id.as_str()) {
+                return Err(CanvasBuildError::DuplicateNode(node.id.clone()));
+            }
+        }
+        for edge in &self.edges {
+            for node in [&edge.from, &edge.to] {
+                if !node_ids.contains(node.as_str()) {
+                    return Err(CanvasBuildError::DanglingEdge { edge: edge.id.clone(), node: node.clone() });
+                }
+            }
+        }
+
+        let mut canvas = JsonCanvas::default();
+        let mut next_y = 0;
+        for spec in self.nodes {
+            let (x, y) = spec.position.unwrap_or((0, next_y));
+            let (width, height) = spec.size;
+            next_y = next_y.max(y + height as i64 + DEFAULT_SPACING);
+            let id = spec.id.parse().expect("node IDs were checked to be non-empty");
+            let node: Node = match spec.kind {
+                NodeKind::Text(text) => TextNode::new(id, x, y, width, height, spec.color, text).into(),
+                NodeKind::File(path) => FileNode::new(id, x, y, width, height, spec.color, path, None).into(),
+                NodeKind::Link(url) => {
+                    let parsed = url::Url::parse(&url).map_err(|e| CanvasBuildError::InvalidUrl {
+                        node: spec.id.clone(),
+                        url: url.clone(),
+                        reason: e.to_string(),
+                    })?;
+                    LinkNode::new(id, x, y, width, height, spec.color, parsed).into()
+                }
+                NodeKind::Group => GroupNode::new(id, x, y, width, height, spec.color, spec.label, None).into(),
+            };
+            canvas.add_node(node).expect("node IDs were checked to be unique");
+        }
+        for spec in self.edges {
+            let id = spec.id.parse().expect("edge IDs are never empty");
+            let from = spec.from.parse().expect("edge ends were checked to exist");
+            let to = spec.to.parse().expect("edge ends were checked to exist");
+            let end = |arrow: bool| if arrow { End::Arrow } else { End::None };
+            let (from_end, to_end) = spec.arrows.map_or((None, None), |(f, t)| (Some(end(f)), Some(end(t))));
+            let edge = Edge::new(id, from, None, from_end, to, None, to_end, spec.color, spec.label);
+            canvas.add_edge(edge).expect("edges were checked to be unique and to connect existing nodes");
+        }
+
+        Ok(canvas)
+    }
+
+    fn node(mut self, id: String, kind: NodeKind) -> Self {
+        self.nodes.push(NodeSpec {
+            id,
+            kind,
+            position: None,
+            size: (DEFAULT_WIDTH, DEFAULT_HEIGHT),
+            color: None,
+            label: None,
+        });
+        self.last = Last::Node;
+        self
+    }
+
+    fn misplaced(mut self, method: &'static str, reason: &'static str) -> Self {
+        self.misplaced.get_or_insert(CanvasBuildError::Misplaced { method, reason });
+        self
+    }
+}
@@ -0,0 +1,81 @@
+/// `#tags` in the text, without the `#`, in order of first appearance
+///
+/// A tag runs until whitespace; trailing punctuation other than `-`, `_` and `/` is
+/// dropped, and `##` (markdown headings) is not a tag.
+pub fn hashtags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        let Some(tag) = word.strip_prefix('#') else { continue };
+        let tag = tag.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '_' && c != '/');
+        if !tag.is_empty() && !tag.starts_with('#') && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Maps hashtags onto a site's categories
+///
+/// Mappings are checked in the order they were added, so an item tagged both `#rust` and
+/// `#life` gets the category of whichever of the two was mapped first. Tags are compared
+/// without the `#` and ignoring case.
+#[derive(Debug, Clone, Default)]
+pub struct CategoryMap {
+    /// (tag, category) pairs, highest priority first
+    mappings: Vec<(String, String)>,
+    /// Category of items with no mapped tag
+    default: Option<String>,
+}
+
+impl CategoryMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map a tag (`#rust` or `rust`) to a category, below the mappings added before it
+    pub fn map(mut self, tag: &str, category: impl Into<String>) -> Self {
+        self.mappings.push((tag.trim_start_matches('#').to_lowercase(), category.into()));
+        self
+    }
+
+    /// Give items with no mapped tag this category
+    pub fn with_default(mut self, category: impl Into<String>) -> Self {
+        self.default = Some(category.into());
+        self
+    }
+
+    /// The category of an item with these tags (without `#`)
+    pub fn category_for(&self, tags: &[String]) -> Option<String> {
+        self.mappings
+            .iter()
+            .find(|(tag, _)| tags.iter().any(|t| t.to_lowercase() == *tag))
+            .map(|(_, category)| category.clone())
+            .or_else(|| self.default.clone())
+    }
+
+    /// The category of an item with this text
+    pub fn category_of(&self, text: &str) -> Option<String> {
+        self.category_for(&hashtags(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_mapping_wins_over_text_order() {
+        let categories = CategoryMap::new().map("#rust", "engineering").map("life", "personal");
+        assert_eq!(categories.category_of("#life lessons from #Rust").as_deref(), Some("engineering"));
+        assert_eq!(categories.category_of("Just #life.").as_deref(), Some("personal"));
+        assert_eq!(categories.category_of("## Heading and #unmapped"), None);
+    }
+
+    #[test]
+    fn unmapped_items_get_the_default() {
+        let categories = CategoryMap::new().map("rust", "engineering").with_default("notes");
+        assert_eq!(categories.category_of("No tags at all").as_deref(), Some("notes"));
+        assert_eq!(categories.category_of("#rust").as_deref(), Some("engineering"));
+        assert_eq!(hashtags("#a, #b/c and #a again ##h"), ["a", "b/c"]);
+    }
+}
@@ -0,0 +1,329 @@
+//! Failure injection, for checking that retries, queueing and partial failures compose
+//!
+//! [`ChaosSink`] wraps any sink and makes some of its batches fail, hang or misreport what
+//! they published, driven by a seeded generator so a failing scenario can be replayed.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::jsoncanvas::NodeId;
+use crate::node_ids::cmp_node_ids;
+use crate::preview::{PlannedCommit, PlannedWrite};
+use crate::prune::DestructionPlan;
+use crate::sink::{SinkError, SyndicationLinks, SyndicationSink};
+use crate::state::StateRegistry;
+use crate::verify::VerifyReport;
+use crate::warnings::Warning;
+use crate::SyndicationFormat;
+
+/// Suffix a [`ChaosSink`] misreporting its posts appends to their node IDs
+pub const WRONG_ID_SUFFIX: &str = "-chaos";
+
+/// What a [`ChaosSink`] does to the batch in progress, decided when it begins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    None,
+    /// Fail the first publish or update
+    Fail,
+    /// Sleep past the client's timeout, then fail the first publish or update
+    Hang,
+    /// Succeed, but report the posts' URLs under node IDs that weren't in the batch
+    WrongIds,
+}
+
+/// A sink that passes everything on to another one, except for the failures it injects
+///
+/// Each batch rolls its fault when it begins: the first
+/// [`fail_first_batches`](Self::with_fail_first_batches) batches fail, then each batch hangs,
+/// fails or misreports its IDs at the configured rates. On top of that, every
+/// [`fail_every_nth_item`](Self::with_fail_every_nth_item)-th item handed over fails its
+/// batch after the items before it reached the wrapped sink. Failures happen while
+/// publishing, so the wrapped sink's [`abort`](SyndicationSink::abort) is exercised.
+///
+/// The wrapped sink's name is kept, so it's tracked like the sink itself.
+pub struct ChaosSink {
+    inner: Box<dyn SyndicationSink + Send>,
+    seed: u64,
+    rng: ChaosRng,
+    fail_first_batches: usize,
+    fail_every_nth_item: Option<usize>,
+    fail_rate: f64,
+    hang_rate: f64,
+    hang: Duration,
+    wrong_ids_rate: f64,
+    batches: usize,
+    items: usize,
+    fault: Fault,
+    /// Items handed over in the batch in progress, for misreporting their URLs
+    handed: Vec<NodeId>,
+}
+
+impl ChaosSink {
+    /// Wrap `inner` without injecting anything yet
+    pub fn new(inner: impl SyndicationSink + Send + 'static, seed: u64) -> Self {
+        Self::boxed(Box::new(inner), seed)
+    }
+
+    /// Like [`Self::new`], for a sink that's already boxed
+    pub fn boxed(inner: Box<dyn SyndicationSink + Send>, seed: u64) -> Self {
+        Self {
+            inner,
+            seed,
+            rng: ChaosRng::new(seed),
+            fail_first_batches: 0,
+            fail_every_nth_item: None,
+            fail_rate: 0.0,
+            hang_rate: 0.0,
+            hang: Duration::ZERO,
+            wrong_ids_rate: 0.0,
+            batches: 0,
+            items: 0,
+            fault: Fault::None,
+            handed: Vec::new(),
+        }
+    }
+
+    /// Fail the first `batches` batches, then go on as configured
+    pub fn with_fail_first_batches(mut self, batches: usize) -> Self {
+        self.fail_first_batches = batches;
+        self
+    }
+
+    /// Fail the batch holding every `n`th item handed over, counting across batches
+    pub fn with_fail_every_nth_item(mut self, n: usize) -> Self {
+        self.fail_every_nth_item = Some(n.max(1));
+        self
+    }
+
+    /// Fail this share of batches
+    pub fn with_fail_rate(mut self, rate: f64) -> Self {
+        self.fail_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Have this share of batches sleep for `hang` and then fail as timed out, like a
+    /// request outliving its client's timeout
+    pub fn with_hang(mut self, rate: f64, hang: Duration) -> Self {
+        self.hang_rate = rate.clamp(0.0, 1.0);
+        self.hang = hang;
+        self
+    }
+
+    /// Have this share of successful batches report their post URLs under wrong node IDs
+    pub fn with_wrong_ids_rate(mut self, rate: f64) -> Self {
+        self.wrong_ids_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Hand `items` over with `deliver` (a publish or update call), failing as the batch
+    /// in progress is set to
+    fn inject(
+        &mut self,
+        items: &HashMap<NodeId, SyndicationFormat>,
+        dry_run: bool,
+        deliver: impl Fn(&mut (dyn SyndicationSink + Send), &HashMap<NodeId, SyndicationFormat>, bool)
+            -> Result<(), SinkError>,
+    ) -> Result<(), SinkError> {
+        let mut node_ids: Vec<&NodeId> = items.keys().collect();
+        node_ids.sort_by(|a, b| cmp_node_ids(a, b));
+        self.handed.extend(node_ids.iter().map(|id| (*id).clone()));
+
+        match self.fault {
+            Fault::Fail => return Err(SinkError::Api(format!("injected failure of batch {}", self.batches))),
+            Fault::Hang => {
+                std::thread::sleep(self.hang);
+                return Err(SinkError::Api(format!("injected timeout after {:?}", self.hang)));
+            }
+            Fault::None | Fault::WrongIds => {}
+        }
+
+        if let Some(n) = self.fail_every_nth_item {
+            let first = self.items;
+            self.items += node_ids.len();
+            if let Some(offset) = (first..self.items).position(|i| (i + 1) % n == 0) {
+                // The wrapped sink takes the items before the failing one
+                let before: HashMap<NodeId, SyndicationFormat> = node_ids[..offset]
+                    .iter()
+                    .filter_map(|id| Some(((*id).clone(), items.get(*id)?.clone())))
+                    .collect();
+                if !before.is_empty() {
+                    deliver(self.inner.as_mut(), &before, dry_run)?;
+                }
+                return Err(SinkError::Api("injected failure of an item".to_string()).for_item(node_ids[offset]));
+            }
+        }
+        deliver(self.inner.as_mut(), items, dry_run)
+    }
+}
+
+impl SyndicationSink for ChaosSink {
+    fn begin(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        self.batches += 1;
+        self.handed.clear();
+        self.fault = if self.batches <= self.fail_first_batches {
+            Fault::Fail
+        } else if self.rng.chance(self.hang_rate) {
+            Fault::Hang
+        } else if self.rng.chance(self.fail_rate) {
+            Fault::Fail
+        } else if self.rng.chance(self.wrong_ids_rate) {
+            Fault::WrongIds
+        } else {
+            Fault::None
+        };
+        self.inner.begin(dry_run)
+    }
+
+    fn commit(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        self.inner.commit(dry_run)
+    }
+
+    fn abort(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        self.inner.abort(dry_run)
+    }
+
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.inject(items, dry_run, |sink, items, dry_run| sink.publish(items, dry_run))
+    }
+
+    fn publish_chunk(
+        &mut self,
+        items: &HashMap<NodeId, SyndicationFormat>,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+        dry_run: bool,
+    ) -> Result<(), SinkError> {
+        self.inject(items, dry_run, |sink, items, dry_run| sink.publish_chunk(items, all_items, dry_run))
+    }
+
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.inject(items, dry_run, |sink, items, dry_run| sink.update(items, dry_run))
+    }
+
+    fn supports_updates(&self) -> bool {
+        self.inner.supports_updates()
+    }
+
+    fn preflight(&self) -> Result<(), SinkError> {
+        self.inner.preflight()
+    }
+
+    fn resurface(&mut self, item: &SyndicationFormat, dry_run: bool) -> Result<(), SinkError> {
+        self.inner.resurface(item, dry_run)
+    }
+
+    fn take_post_urls(&mut self) -> Vec<(NodeId, String)> {
+        let urls = self.inner.take_post_urls();
+        if self.fault != Fault::WrongIds {
+            return urls;
+        }
+        // Misreport the wrapped sink's URLs, or make some up if it reports none
+        let urls = if urls.is_empty() {
+            self.handed.iter().map(|id| (id.clone(), format!("chaos://{}/{}", self.name(), id))).collect()
+        } else {
+            urls
+        };
+        warn!(sink = %self.name(), count = urls.len(), "Injecting post URLs under wrong node IDs");
+        urls.into_iter()
+            .filter_map(|(node_id, url)| Some((format!("{}{}", node_id, WRONG_ID_SUFFIX).parse().ok()?, url)))
+            .collect()
+    }
+
+    fn take_planned_writes(&mut self) -> Vec<PlannedWrite> {
+        self.inner.take_planned_writes()
+    }
+
+    fn take_planned_commit(&mut self) -> Option<PlannedCommit> {
+        self.inner.take_planned_commit()
+    }
+
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        self.inner.take_warnings()
+    }
+
+    fn add_syndication_links(&mut self, links: &SyndicationLinks, dry_run: bool) -> Result<usize, SinkError> {
+        self.inner.add_syndication_links(links, dry_run)
+    }
+
+    fn archive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        self.inner.archive(node_ids, dry_run)
+    }
+
+    fn unarchive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        self.inner.unarchive(node_ids, dry_run)
+    }
+
+    fn plan_removal(&self, node_ids: &[NodeId]) -> Result<DestructionPlan, SinkError> {
+        self.inner.plan_removal(node_ids)
+    }
+
+    fn remove(&mut self, plan: &DestructionPlan, dry_run: bool) -> Result<(), SinkError> {
+        self.inner.remove(plan, dry_run)
+    }
+
+    fn import_legacy(
+        &mut self,
+        is_tracked: &dyn Fn(&NodeId) -> bool,
+        dry_run: bool,
+    ) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        self.inner.import_legacy(is_tracked, dry_run)
+    }
+
+    fn list_published(&self) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        self.inner.list_published()
+    }
+
+    fn verify(&self) -> Result<VerifyReport, SinkError> {
+        self.inner.verify()
+    }
+
+    fn register_state(&self, registry: &mut StateRegistry) {
+        self.inner.register_state(registry)
+    }
+
+    /// The wrapped sink's sandboxed copy, injecting the same failures from the same seed
+    fn sandboxed(&self, root: &Path) -> Result<Box<dyn SyndicationSink + Send>, SinkError> {
+        let sandboxed = Self {
+            fail_first_batches: self.fail_first_batches,
+            fail_every_nth_item: self.fail_every_nth_item,
+            fail_rate: self.fail_rate,
+            hang_rate: self.hang_rate,
+            hang: self.hang,
+            wrong_ids_rate: self.wrong_ids_rate,
+            ..Self::boxed(self.inner.sandboxed(root)?, self.seed)
+        };
+        Ok(Box::new(sandboxed))
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+/// SplitMix64, enough for reproducible dice without another dependency
+#[derive(Debug, Clone)]
+struct ChaosRng {
+    state: u64,
+}
+
+impl ChaosRng {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// True with probability `rate`; never draws for a rate of zero, so unused faults
+    /// don't shift the others' dice
+    fn chance(&mut self, rate: f64) -> bool {
+        rate > 0.0 && ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < rate
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local};
+
+/// Source of the current time
+///
+/// Anything time-dependent (dates in generated files, report timestamps) should ask a
+/// `Clock` rather than calling `Local::now()` directly, so it can be pinned in tests.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+}
+
+/// The real system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// A clock that only moves when told to
+#[derive(Debug)]
+pub struct MockClock {
+    now: Mutex<DateTime<Local>>,
+}
+
+impl MockClock {
+    /// Create a clock pinned at the given time
+    pub fn new(now: DateTime<Local>) -> Self {
+        Self { now: Mutex::new(now) }
+    }
+
+    /// Move the clock to a specific time
+    pub fn set(&self, now: DateTime<Local>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: chrono::Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Local> {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,208 @@
+use crate::jsoncanvas::color::{Color, HexColor, PresetColor};
+
+/// Preset colors by the names Obsidian shows, in preset order
+pub(crate) const PRESETS: &[(&str, PresetColor)] = &[
+    ("red", PresetColor::Red),
+    ("orange", PresetColor::Orange),
+    ("yellow", PresetColor::Yellow),
+    ("green", PresetColor::Green),
+    ("cyan", PresetColor::Cyan),
+    ("purple", PresetColor::Purple),
+];
+
+/// A color string that isn't a preset name, preset number or hex color
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "invalid color `{0}`: expected a preset name (red, orange, yellow, green, cyan, purple), \
+     a preset number 1-6, or a hex color like #ff0000"
+)]
+pub struct ColorError(pub String);
+
+/// Parse `red`, `1`, `#FF0000`, `ff0000` or `#f00` into a canvas color
+///
+/// Names and hex digits are case-insensitive.
+pub fn parse_color(input: &str) -> Result<Color, ColorError> {
+    let trimmed = input.trim();
+    let lower = trimmed.to_ascii_lowercase();
+
+    if let Some((_, preset)) = PRESETS.iter().find(|(name, _)| *name == lower) {
+        return Ok(Color::Preset(copy_preset(preset)));
+    }
+    if let Ok(number) = lower.parse::<usize>()
+        && let Some((_, preset)) = number.checked_sub(1).and_then(|i| PRESETS.get(i))
+    {
+        return Ok(Color::Preset(copy_preset(preset)));
+    }
+
+    let hex = if lower.starts_with('#') { lower } else { format!("#{}", lower) };
+    HexColor::parse_rgb(&hex)
+        .map(Color::Color)
+        .map_err(|_| ColorError(input.to_string()))
+}
+
+/// Normalize a hex color to lowercase `#rrggbb`, or None if it isn't one
+pub fn normalize_hex(input: &str) -> Option<String> {
+    match parse_color(input).ok()? {
+        Color::Color(hex) => Some(hex_string(hex)),
+        Color::Preset(_) => None,
+    }
+}
+
+/// Canonical form of a color: the preset number (`"1"`) or lowercase `#rrggbb`
+///
+/// jsoncanvas doesn't define the hex value of presets (themes decide), so a preset
+/// only equals itself.
+pub fn color_key(color: &Color) -> String {
+    match color {
+        Color::Preset(preset) => preset_index(preset).to_string(),
+        Color::Color(hex) => hex_string(*hex),
+    }
+}
+
+/// Whether two colors are the same once normalized (hex case and alpha are ignored)
+pub fn same_color(a: &Color, b: &Color) -> bool {
+    color_key(a) == color_key(b)
+}
+
+/// Display form of a color for logs and frontmatter: the preset name or `#rrggbb`
+pub fn color_name(color: &Color) -> String {
+    match color {
+        Color::Preset(preset) => PRESETS[preset_index(preset) - 1].0.to_string(),
+        Color::Color(hex) => hex_string(*hex),
+    }
+}
+
+/// Colors a node must have to be syndicated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorFilter {
+    /// Canonical keys (see [`color_key`]) of the accepted colors
+    keys: Vec<String>,
+    /// Whether uncolored nodes pass too
+    uncolored: bool,
+}
+
+impl Default for ColorFilter {
+    /// Only red nodes, the original convention
+    fn default() -> Self {
+        Self { keys: vec![color_key(&Color::Preset(PresetColor::Red))], uncolored: false }
+    }
+}
+
+impl ColorFilter {
+    /// Accept any of the given colors, in any form [`parse_color`] understands
+    pub fn parse(inputs: &[&str]) -> Result<Self, ColorError> {
+        let mut keys = Vec::new();
+        for input in inputs {
+            let key = color_key(&parse_color(input)?);
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        Ok(Self { keys, uncolored: false })
+    }
+
+    /// Accept this color only
+    pub fn only(color: impl Into<Color>) -> Self {
+        Self { keys: vec![color_key(&color.into())], uncolored: false }
+    }
+
+    /// Let uncolored nodes pass too
+    pub fn with_uncolored(mut self, uncolored: bool) -> Self {
+        self.uncolored = uncolored;
+        self
+    }
+
+    /// Whether a node with this color (None if uncolored) passes
+    pub fn matches(&self, color: Option<&Color>) -> bool {
+        match color {
+            Some(color) => self.keys.contains(&color_key(color)),
+            None => self.uncolored,
+        }
+    }
+
+    /// Display names of the accepted colors, and `uncolored` if uncolored nodes pass
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .keys
+            .iter()
+            .filter_map(|key| parse_color(key).ok())
+            .map(|color| color_name(&color))
+            .collect();
+        if self.uncolored {
+            names.push("uncolored".to_string());
+        }
+        names
+    }
+}
+
+fn hex_string(hex: HexColor) -> String {
+    let (r, g, b) = hex.split_rgb();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn preset_index(preset: &PresetColor) -> usize {
+    PRESETS.iter().position(|(_, p)| p == preset).map_or(1, |i| i + 1)
+}
+
+/// `PresetColor` isn't `Clone`
+fn copy_preset(preset: &PresetColor) -> PresetColor {
+    match preset {
+        PresetColor::Red => PresetColor::Red,
+        PresetColor::Orange => PresetColor::Orange,
+        PresetColor::Yellow => PresetColor::Yellow,
+        PresetColor::Green => PresetColor::Green,
+        PresetColor::Cyan => PresetColor::Cyan,
+        PresetColor::Purple => PresetColor::Purple,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_input_form_parses_to_its_canonical_color() {
+        for input in ["red", "RED", " 1 "] {
+            assert_eq!(color_key(&parse_color(input).unwrap()), "1", "{}", input);
+        }
+        assert_eq!(color_name(&parse_color("6").unwrap()), "purple");
+        for input in ["#FF0000", "ff0000", "#f00", "F00"] {
+            assert_eq!(color_name(&parse_color(input).unwrap()), "#ff0000", "{}", input);
+        }
+        assert_eq!(normalize_hex("#ABCDEF").as_deref(), Some("#abcdef"));
+        assert_eq!(normalize_hex("red"), None);
+        assert!(same_color(&parse_color("#AbCdEf").unwrap(), &parse_color("abcdef").unwrap()));
+        assert!(!same_color(&parse_color("red").unwrap(), &parse_color("#ff0000").unwrap()));
+    }
+
+    #[test]
+    fn invalid_colors_are_rejected_with_the_accepted_forms() {
+        for input in ["crimson", "0", "7", "#ff00", "#gg0000", ""] {
+            let error = parse_color(input).unwrap_err().to_string();
+            assert!(error.starts_with(&format!("invalid color `{}`", input)), "{}", error);
+            assert!(error.contains("preset number 1-6"), "{}", error);
+        }
+        assert!(ColorFilter::parse(&["red", "blurple"]).is_err());
+    }
+
+    #[test]
+    fn filter_matches_any_listed_color() {
+        let filter = ColorFilter::parse(&["red", "1", "#00FF00"]).unwrap();
+        assert_eq!(filter.names(), ["red", "#00ff00"]);
+        assert!(filter.matches(Some(&parse_color("0f0").unwrap())));
+        assert!(!filter.matches(Some(&parse_color("orange").unwrap())));
+        assert!(!filter.matches(None));
+        assert!(ColorFilter::default().matches(Some(&Color::Preset(PresetColor::Red))));
+    }
+
+    #[test]
+    fn uncolored_nodes_pass_only_when_enabled() {
+        let filter = ColorFilter::parse(&["#AbCdEf"]).unwrap().with_uncolored(true);
+        assert!(filter.matches(None));
+        assert!(filter.matches(Some(&parse_color("#abcdef").unwrap())));
+        assert!(filter.matches(Some(&parse_color("#ABCDEF").unwrap())));
+        assert!(!filter.matches(Some(&parse_color("red").unwrap())));
+        assert_eq!(filter.names(), ["#abcdef", "uncolored"]);
+        assert!(!filter.with_uncolored(false).matches(None));
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::jsoncanvas::{JsonCanvas, Node, edge::Edge};
+
+/// Top-level keys of the canvas format this crate understands
+const CANVAS_FIELDS: &[&str] = &["nodes", "edges"];
+/// Node keys understood for any node type
+const NODE_FIELDS: &[&str] = &["id", "type", "x", "y", "width", "height", "color"];
+/// Node kinds and the type-specific keys understood for each
+const NODE_KINDS: &[(&str, &[&str])] = &[
+    ("text", &["text"]),
+    ("file", &["file", "subpath"]),
+    ("link", &["url"]),
+    ("group", &["label", "background", "backgroundStyle"]),
+];
+/// Edge keys this crate understands
+const EDGE_FIELDS: &[&str] = &[
+    "id", "fromNode", "fromSide", "fromEnd", "toNode", "toSide", "toEnd", "color", "label",
+];
+
+/// A node or edge left out of the parsed canvas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedElement {
+    /// ID of the node or edge, or its position if it has none
+    pub id: String,
+    pub reason: String,
+}
+
+/// What parsing a canvas had to ignore or skip
+///
+/// Newer Obsidian versions add fields and node kinds; these are reported instead of
+/// failing the run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatibilityReport {
+    /// Error of the strict parse, if the lenient fallback was used
+    pub strict_error: Option<String>,
+    /// Keys that were ignored, e.g. `metadata` or `nodes[].styleAttributes`
+    pub ignored_fields: Vec<String>,
+    /// Nodes that couldn't be parsed, e.g. of an unknown kind
+    pub skipped_nodes: Vec<SkippedElement>,
+    /// Edges that couldn't be parsed or point at skipped nodes
+    pub skipped_edges: Vec<SkippedElement>,
+}
+
+impl CompatibilityReport {
+    /// Whether the canvas parsed without ignoring anything
+    pub fn is_empty(&self) -> bool {
+        self.strict_error.is_none()
+            && self.ignored_fields.is_empty()
+            && self.skipped_nodes.is_empty()
+            && self.skipped_edges.is_empty()
+    }
+}
+
+/// Parse a canvas, tolerating unknown fields and node kinds
+///
+/// Strict parsing is tried first. If it fails, nodes and edges are parsed one by one and
+/// the ones that don't parse are skipped. Only content that isn't a JSON object with node
+/// and edge lists is an error.
+pub fn parse_canvas(content: &str) -> Result<(JsonCanvas, CompatibilityReport), serde_json::Error> {
+    let value: Value = serde_json::from_str(content)?;
+    let mut report = CompatibilityReport {
+        ignored_fields: ignored_fields(&value),
+        ..Default::default()
+    };
+
+    let strict_error = match JsonCanvas::from_str(content) {
+        Ok(canvas) => return Ok((canvas, report)),
+        Err(e) => e.to_string(),
+    };
+    warn!(error = %strict_error, "Canvas failed strict parsing, falling back to lenient parsing");
+    report.strict_error = Some(strict_error);
+
+    let object: Map<String, Value> = serde_json::from_value(value)?;
+    let nodes: Vec<Value> = match object.get("nodes") {
+        Some(nodes) => serde_json::from_value(nodes.clone())?,
+        None => Vec::new(),
+    };
+    let edges: Vec<Value> = match object.get("edges") {
+        Some(edges) => serde_json::from_value(edges.clone())?,
+        None => Vec::new(),
+    };
+
+    let mut canvas = JsonCanvas::default();
+    for (index, node) in nodes.into_iter().enumerate() {
+        let id = element_id(&node, "nodes", index);
+        let kind = node.get("type").and_then(Value::as_str).unwrap_or_default().to_string();
+        let result = if NODE_KINDS.iter().any(|(k, _)| *k == kind) {
+            serde_json::from_value::<Node>(node)
+                .map_err(|e| e.to_string())
+                .and_then(|node| canvas.add_node(node).map_err(|e| e.to_string()))
+        } else {
+            Err(format!("unsupported node type `{}`", kind))
+        };
+        if let Err(reason) = result {
+            warn!(node_id = %id, reason = %reason, "Skipping canvas node");
+            report.skipped_nodes.push(SkippedElement { id, reason });
+        }
+    }
+
+    for (index, edge) in edges.into_iter().enumerate() {
+        let id = element_id(&edge, "edges", index);
+        let result = serde_json::from_value::<Edge>(edge)
+            .map_err(|e| e.to_string())
+            .and_then(|edge| canvas.add_edge(edge).map_err(|e| e.to_string()));
+        if let Err(reason) = result {
+            warn!(edge_id = %id, reason = %reason, "Skipping canvas edge");
+            report.skipped_edges.push(SkippedElement { id, reason });
+        }
+    }
+
+    Ok((canvas, report))
+}
+
+/// The `id` of a node or edge, or `<list>[<index>]` if it has none
+fn element_id(element: &Value, list: &str, index: usize) -> String {
+    element
+        .get("id")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}[{}]", list, index))
+}
+
+/// Keys of the canvas, its nodes and its edges that aren't part of the known format
+fn ignored_fields(value: &Value) -> Vec<String> {
+    let mut ignored = BTreeSet::new();
+    let Some(object) = value.as_object() else {
+        return Vec::new();
+    };
+
+    for key in object.keys().filter(|k| !CANVAS_FIELDS.contains(&k.as_str())) {
+        ignored.insert(key.clone());
+    }
+
+    for node in object.get("nodes").and_then(Value::as_array).into_iter().flatten() {
+        let Some(node) = node.as_object() else { continue };
+        let kind = node.get("type").and_then(Value::as_str);
+        let Some((_, kind_fields)) = NODE_KINDS.iter().find(|(k, _)| Some(*k) == kind) else {
+            continue;
+        };
+        for key in node.keys() {
+            if !NODE_FIELDS.contains(&key.as_str()) && !kind_fields.contains(&key.as_str()) {
+                ignored.insert(format!("nodes[].{}", key));
+            }
+        }
+    }
+
+    for edge in object.get("edges").and_then(Value::as_array).into_iter().flatten() {
+        let Some(edge) = edge.as_object() else { continue };
+        for key in edge.keys().filter(|k| !EDGE_FIELDS.contains(&k.as_str())) {
+            ignored.insert(format!("edges[].{}", key));
+        }
+    }
+
+    ignored.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A canvas from a future Obsidian: a top-level `metadata` key, an extra node field and
+    /// a node kind this crate doesn't know, with an edge pointing at it
+    const FUTURE_CANVAS: &str = r#"{
+        "metadata": {"version": "2.0"},
+        "nodes": [
+            {"id": "c0a7000000000001", "type": "text", "text": "Kept", "x": 0, "y": 0, "width": 100,
+             "height": 50, "color": "1", "styleAttributes": {}},
+            {"id": "c0a7000000000002", "type": "embed", "x": 200, "y": 0, "width": 100, "height": 50}
+        ],
+        "edges": [
+            {"id": "c0a7000000000003", "fromNode": "c0a7000000000001", "toNode": "c0a7000000000002"}
+        ]
+    }"#;
+
+    #[test]
+    fn unknown_node_kinds_are_skipped_and_reported() {
+        let (canvas, report) = parse_canvas(FUTURE_CANVAS).unwrap();
+        assert_eq!(canvas.get_nodes().len(), 1);
+        assert!(report.strict_error.is_some());
+        assert_eq!(report.ignored_fields, ["metadata", "nodes[].styleAttributes"]);
+        assert_eq!(report.skipped_nodes.len(), 1);
+        assert_eq!(report.skipped_nodes[0].id, "c0a7000000000002");
+        assert!(report.skipped_nodes[0].reason.contains("embed"), "{}", report.skipped_nodes[0].reason);
+        assert_eq!(report.skipped_edges.len(), 1);
+        assert_eq!(report.skipped_edges[0].id, "c0a7000000000003");
+    }
+
+    #[test]
+    fn known_canvas_parses_strictly_with_nothing_to_report() {
+        let (canvas, report) = parse_canvas(r#"{"nodes": [], "edges": []}"#).unwrap();
+        assert!(canvas.get_nodes().is_empty());
+        assert!(report.is_empty(), "{:?}", report);
+        assert!(parse_canvas(r#"{"nodes": "#).is_err());
+    }
+}
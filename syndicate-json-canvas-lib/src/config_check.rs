@@ -0,0 +1,387 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::Serialize;
+
+use crate::color::{PRESETS, parse_color};
+use crate::network::NetworkPolicy;
+use crate::replace::RuleEntry;
+use crate::sink::SyndicationSink;
+use crate::text::TextPolicy;
+
+/// Tables a configuration TOML file may have
+const TABLES: &[&str] = &["text", "network", "replace"];
+
+/// What is wrong in a [`Finding`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// The file isn't TOML, or can't be read
+    Syntax,
+    /// A value has the wrong type, e.g. a string where a number belongs
+    Type,
+    /// A key nothing reads
+    UnknownKey,
+    /// A name that refers to nothing, e.g. a sink that isn't configured
+    UnknownName,
+    /// A `{token}` in a template that nothing replaces
+    UnknownToken,
+    /// A value of the right type that still can't be used, e.g. an unparsable color
+    Invalid,
+    /// A sink's [`preflight`](SyndicationSink::preflight) failed
+    Preflight,
+}
+
+/// One problem in the configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    /// File or setting the problem is in
+    pub source: String,
+    /// Where in it, e.g. `text.title_max_words` or `replace[2].regex`; empty for all of it
+    pub path: String,
+    pub message: String,
+    /// A known name close to the unknown one, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.source)?;
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{}`?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Every problem found in a configuration, without running anything
+///
+/// Each check adds what it finds instead of stopping at the first problem, so one
+/// `config check` lists everything to fix.
+#[derive(Debug, Default, Serialize)]
+pub struct ConfigCheck {
+    pub findings: Vec<Finding>,
+}
+
+impl ConfigCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether nothing was found
+    pub fn is_valid(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Add a finding of a check made elsewhere
+    pub fn push(&mut self, kind: FindingKind, source: &str, path: &str, message: impl Into<String>) {
+        self.findings.push(Finding {
+            kind,
+            source: source.to_string(),
+            path: path.to_string(),
+            message: message.into(),
+            suggestion: None,
+        });
+    }
+
+    fn push_suggested(&mut self, kind: FindingKind, source: &str, path: &str, message: String, suggestion: Option<&str>) {
+        self.push(kind, source, path, message);
+        if let Some(finding) = self.findings.last_mut() {
+            finding.suggestion = suggestion.map(str::to_string);
+        }
+    }
+
+    /// Check a configuration TOML file, including that the stopwords file it names exists
+    pub fn check_file(&mut self, path: &Path) {
+        let source = path.display().to_string();
+        let toml = match std::fs::read_to_string(path) {
+            Ok(toml) => toml,
+            Err(e) => return self.push(FindingKind::Syntax, &source, "", format!("can't be read: {}", e)),
+        };
+        let Some(document) = self.check_document(&source, &toml) else {
+            return;
+        };
+        if let Some(stopwords_file) = document.get("text").and_then(|text| text.get("stopwords_file"))
+            && let Some(stopwords_file) = stopwords_file.as_str()
+        {
+            let stopwords_path = path.parent().unwrap_or(Path::new(".")).join(stopwords_file);
+            if !stopwords_path.is_file() {
+                let message = format!("stopwords file {} doesn't exist", stopwords_path.display());
+                self.push(FindingKind::Invalid, &source, "text.stopwords_file", message);
+            }
+        }
+    }
+
+    /// Check a configuration TOML document: its `[text]`, `[network]` and `[[replace]]` tables
+    pub fn check_toml(&mut self, source: &str, toml: &str) {
+        self.check_document(source, toml);
+    }
+
+    fn check_document(&mut self, source: &str, toml: &str) -> Option<toml::Table> {
+        let mut document = match toml.parse::<toml::Table>() {
+            Ok(document) => document,
+            Err(e) => {
+                let path = e.span().map_or_else(String::new, |span| line_and_column(toml, span.start));
+                self.push(FindingKind::Syntax, source, &path, e.message().trim().replace('\n', "; "));
+                return None;
+            }
+        };
+        let checked = document.clone();
+
+        self.check_keys(source, "", &mut document, TABLES);
+        if let Some(text) = document.remove("text") {
+            self.check_table::<TextPolicy>(source, "text", text);
+        }
+        if let Some(network) = document.remove("network") {
+            self.check_table::<NetworkPolicy>(source, "network", network);
+        }
+        match document.remove("replace") {
+            Some(toml::Value::Array(entries)) => {
+                for (i, entry) in entries.into_iter().enumerate() {
+                    let path = format!("replace[{}]", i);
+                    if let Some(entry) = self.check_table::<RuleEntry>(source, &path, entry)
+                        && let Err((key, message)) = entry.into_rule()
+                    {
+                        self.push(FindingKind::Invalid, source, &format!("{}.{}", path, key), message);
+                    }
+                }
+            }
+            Some(other) => {
+                let message = format!("expected an array of tables (`[[replace]]`), found {}", other.type_str());
+                self.push(FindingKind::Type, source, "replace", message);
+            }
+            None => {}
+        }
+        Some(checked)
+    }
+
+    /// Report and drop the keys of a table that aren't `known`
+    fn check_keys(&mut self, source: &str, path: &str, table: &mut toml::Table, known: &[&str]) {
+        let unknown: Vec<String> = table.keys().filter(|key| !known.contains(&key.as_str())).cloned().collect();
+        for key in unknown {
+            table.remove(&key);
+            let key_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+            let message = format!("unknown key `{}`", key);
+            self.push_suggested(FindingKind::UnknownKey, source, &key_path, message, suggest(&key, known));
+        }
+    }
+
+    /// Check a table against the type reading it, returning the value if it has no problems
+    fn check_table<T: for<'de> Deserialize<'de>>(&mut self, source: &str, path: &str, value: toml::Value) -> Option<T> {
+        let toml::Value::Table(mut table) = value else {
+            self.push(FindingKind::Type, source, path, format!("expected a table, found {}", value.type_str()));
+            return None;
+        };
+        self.check_keys(source, path, &mut table, fields_of::<T>());
+        match serde_path_to_error::deserialize(toml::Value::Table(table)) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                let inner = e.path().to_string();
+                let path = if inner == "." { path.to_string() } else { format!("{}.{}", path, inner) };
+                self.push(FindingKind::Type, source, &path, e.inner().message().trim());
+                None
+            }
+        }
+    }
+
+    /// Check that a name refers to one of the `known` ones, e.g. a sink name
+    pub fn check_name(&mut self, source: &str, path: &str, name: &str, known: &[&str], what: &str) {
+        if !known.contains(&name) {
+            let message = format!("unknown {} `{}`", what, name);
+            self.push_suggested(FindingKind::UnknownName, source, path, message, suggest(name, known));
+        }
+    }
+
+    /// Check that every `{token}` in a template is one of `tokens`
+    pub fn check_template(&mut self, source: &str, path: &str, template: &str, tokens: &[&str]) {
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                let message = format!("unclosed `{{` in `{}`", template);
+                return self.push(FindingKind::Invalid, source, path, message);
+            };
+            let token = &rest[start + 1..start + end];
+            if !tokens.contains(&token) {
+                let message = format!("unknown token `{{{}}}` in `{}`", token, template);
+                let suggestion = suggest(token, tokens).map(|token| format!("{{{}}}", token));
+                self.push_suggested(FindingKind::UnknownToken, source, path, message, suggestion.as_deref());
+            }
+            rest = &rest[start + end + 1..];
+        }
+    }
+
+    /// Check that a color string parses, suggesting the preset name it's closest to
+    pub fn check_color(&mut self, source: &str, path: &str, input: &str) {
+        if let Err(e) = parse_color(input) {
+            let names: Vec<&str> = PRESETS.iter().map(|(name, _)| *name).collect();
+            let suggestion = suggest(&input.trim().to_ascii_lowercase(), &names);
+            self.push_suggested(FindingKind::Invalid, source, path, e.to_string(), suggestion);
+        }
+    }
+
+    /// Run a sink's [`preflight`](SyndicationSink::preflight)
+    pub fn preflight(&mut self, sink: &dyn SyndicationSink) {
+        if let Err(e) = sink.preflight() {
+            self.push(FindingKind::Preflight, sink.name(), "", e.to_string());
+        }
+    }
+}
+
+/// The known name closest to `name` by edit distance, if close enough to be a typo
+pub fn suggest<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    known
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Edit distance between two strings in characters, counting two swapped neighbors as one
+/// edit like typos do (optimal string alignment)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i];
+        for j in 1..=b.len() {
+            let mut distance = (rows[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]))
+                .min(rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            row.push(distance);
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+/// `line L, column C` of a byte offset, both counted from 1
+fn line_and_column(text: &str, offset: usize) -> String {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    format!("line {}, column {}", line, column)
+}
+
+/// Field names of a struct deriving `Deserialize`, as serde hands them to the deserializer
+fn fields_of<T: for<'de> Deserialize<'de>>() -> &'static [&'static str] {
+    struct Fields<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> Deserializer<'de> for Fields<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("fields captured"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+            unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(Fields(&mut fields));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kind, path and suggestion of each finding
+    fn summary(check: &ConfigCheck) -> Vec<(FindingKind, &str, Option<&str>)> {
+        check.findings.iter().map(|f| (f.kind, f.path.as_str(), f.suggestion.as_deref())).collect()
+    }
+
+    #[test]
+    fn each_problem_in_a_file_is_found_at_its_path() {
+        let toml = "[txet]\n\n[text]\ntitle_max_wrods = 5\nslug_max_words = \"six\"\n\n\
+                    [network]\nretries = 2\n\n\
+                    [[replace]]\nfind = \"a\"\nwith = \"b\"\n\n\
+                    [[replace]]\nregex = \"(unclosed\"\nwith = \"\"\n\n\
+                    [[replace]]\nwith = \"nothing to find\"\n";
+        let mut check = ConfigCheck::new();
+        check.check_toml("config.toml", toml);
+        assert_eq!(
+            summary(&check),
+            [
+                (FindingKind::UnknownKey, "txet", Some("text")),
+                (FindingKind::UnknownKey, "text.title_max_wrods", Some("title_max_words")),
+                (FindingKind::Type, "text.slug_max_words", None),
+                (FindingKind::Invalid, "replace[1].regex", None),
+                (FindingKind::Invalid, "replace[2].find", None),
+            ]
+        );
+        assert_eq!(check.findings[4].message, "needs exactly one of `find` and `regex`");
+        assert_eq!(
+            check.findings[1].to_string(),
+            "config.toml: text.title_max_wrods: unknown key `title_max_wrods` (did you mean `title_max_words`?)"
+        );
+        assert!(!check.is_valid());
+    }
+
+    #[test]
+    fn syntax_errors_point_at_the_line_and_column() {
+        let mut check = ConfigCheck::new();
+        check.check_toml("config.toml", "[text]\ntitle_max_words = 5\nellipsis = \n");
+        assert_eq!(summary(&check), [(FindingKind::Syntax, "line 3, column 12", None)]);
+    }
+
+    #[test]
+    fn names_tokens_and_colors_are_resolved() {
+        let mut check = ConfigCheck::new();
+        check.check_name("main.rs", "SINKS", "twiter", &["twitter", "directory"], "sink");
+        check.check_name("main.rs", "SINKS", "directory", &["twitter", "directory"], "sink");
+        check.check_template("main.rs", "FILENAME_TEMPLATE", "{date}-{slgu}/{unheard_of}", &["date", "slug", "id"]);
+        check.check_template("main.rs", "FILENAME_TEMPLATE", "{slug", &["slug"]);
+        check.check_color("main.rs", "COLOR", "gren");
+        check.check_color("main.rs", "COLOR", "#ff8800");
+        assert_eq!(
+            summary(&check),
+            [
+                (FindingKind::UnknownName, "SINKS", Some("twitter")),
+                (FindingKind::UnknownToken, "FILENAME_TEMPLATE", Some("{slug}")),
+                (FindingKind::UnknownToken, "FILENAME_TEMPLATE", None),
+                (FindingKind::Invalid, "FILENAME_TEMPLATE", None),
+                (FindingKind::Invalid, "COLOR", Some("green")),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_well_formed_file_has_no_findings() {
+        let mut check = ConfigCheck::new();
+        check.check_toml("config.toml", "[text]\ntitle_max_words = 5\n\n[[replace]]\nregex = \"a+\"\nwith = \"a\"\n");
+        assert!(check.is_valid(), "{:?}", check.findings);
+        assert_eq!(serde_json::to_string(&check).unwrap(), r#"{"findings":[]}"#);
+    }
+
+    #[test]
+    fn suggestions_only_cover_typos() {
+        assert_eq!(suggest("retires", &["retries", "max_concurrent"]), Some("retries"));
+        assert_eq!(suggest("colour", &["color", "locale"]), Some("color"));
+        assert_eq!(suggest("something_else", &["retries", "max_concurrent"]), None);
+    }
+}
@@ -0,0 +1,370 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::jsoncanvas::{FileNode, Node};
+use crate::vault::{Vault, VaultError};
+
+/// Default limit on the size of a File node's file read by [`content_of`]
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 256 * 1024;
+
+/// Extensions of files whose contents are read as text
+const TEXT_EXTENSIONS: &[&str] = &["md", "markdown", "txt"];
+
+/// What a canvas node holds, independent of its kind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeContent<'a> {
+    /// A text node's markdown
+    Text(&'a str),
+    /// A File node's vault-relative path, and the file's contents if it's a text file
+    /// that could be read
+    File { path: &'a Path, resolved_text: Option<String> },
+    /// A Link node's URL, and a label for it if one is known (see [`ResolveOptions::link_labels`])
+    Link { url: &'a str, label: Option<&'a str> },
+    /// A group's label
+    Group { label: Option<&'a str> },
+}
+
+impl NodeContent<'_> {
+    /// The best text available for any kind of node
+    ///
+    /// Text nodes give their text, File nodes their contents (else their path), Link nodes
+    /// their label (else their URL) and groups their label (else nothing).
+    pub fn as_text_lossy(&self) -> Cow<'_, str> {
+        match self {
+            NodeContent::Text(text) => Cow::Borrowed(text),
+            NodeContent::File { resolved_text: Some(text), .. } => Cow::Borrowed(text),
+            NodeContent::File { path, resolved_text: None } => path.to_string_lossy(),
+            NodeContent::Link { label: Some(label), .. } => Cow::Borrowed(label),
+            NodeContent::Link { url, label: None } => Cow::Borrowed(url),
+            NodeContent::Group { label } => Cow::Borrowed(label.unwrap_or_default()),
+        }
+    }
+
+    /// The text of a Text node, or None for other kinds
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            NodeContent::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Name of the node kind, as in the canvas file (`text`, `file`, `link`, `group`)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NodeContent::Text(_) => "text",
+            NodeContent::File { .. } => "file",
+            NodeContent::Link { .. } => "link",
+            NodeContent::Group { .. } => "group",
+        }
+    }
+}
+
+/// How [`content_of`] resolves File and Link nodes
+#[derive(Debug, Clone)]
+pub struct ResolveOptions {
+    /// Vault File node paths are resolved against; None leaves `resolved_text` empty
+    pub vault: Option<Vault>,
+    /// Files larger than this aren't read
+    pub max_file_bytes: u64,
+    /// Known labels of Link nodes by URL (e.g. page titles), since the canvas stores none
+    pub link_labels: HashMap<String, String>,
+}
+
+impl Default for ResolveOptions {
+    fn default() -> Self {
+        Self {
+            vault: None,
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            link_labels: HashMap::new(),
+        }
+    }
+}
+
+impl ResolveOptions {
+    /// Resolve File nodes against a vault
+    pub fn with_vault(mut self, vault: Vault) -> Self {
+        self.vault = Some(vault);
+        self
+    }
+}
+
+/// The content of a node, with File nodes read through the vault when possible
+///
+/// Only markdown and plain text files within the vault and under
+/// [`ResolveOptions::max_file_bytes`] are read; anything else leaves `resolved_text` None.
+pub fn content_of<'a>(node: &'a Node, options: &'a ResolveOptions) -> NodeContent<'a> {
+    match node {
+        Node::Text(text_node) => NodeContent::Text(text_node.text()),
+        Node::File(file_node) => {
+            let path = file_node.file().as_path();
+            NodeContent::File { path, resolved_text: read_text_file(path, options) }
+        }
+        Node::Link(link_node) => {
+            let url = link_node.url().as_str();
+            NodeContent::Link { url, label: options.link_labels.get(url).map(String::as_str) }
+        }
+        Node::Group(group_node) => NodeContent::Group { label: group_node.label().map(String::as_str) },
+    }
+}
+
+fn read_text_file(path: &Path, options: &ResolveOptions) -> Option<String> {
+    read_vault_file(path, options).unwrap_or_else(|e| {
+        debug!(path = %path.display(), error = %e, "Not reading File node");
+        None
+    })
+}
+
+/// Why a File node's file couldn't be read
+#[derive(Debug, thiserror::Error)]
+pub enum FileNodeError {
+    #[error(transparent)]
+    OutsideVault(#[from] VaultError),
+    #[error("File not found: {0}")]
+    Missing(String),
+    #[error("File is {size} bytes, over the {max} byte limit")]
+    TooLarge { size: u64, max: u64 },
+    #[error("Failed to read file: {0}")]
+    Unreadable(String),
+    #[error("No section `{0}` in the file")]
+    NoSection(String),
+}
+
+/// The contents of a text file in the vault; None without a vault or for other kinds of file
+fn read_vault_file(path: &Path, options: &ResolveOptions) -> Result<Option<String>, FileNodeError> {
+    let Some(vault) = options.vault.as_ref() else {
+        return Ok(None);
+    };
+    let is_text = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| TEXT_EXTENSIONS.iter().any(|t| t.eq_ignore_ascii_case(ext)));
+    if !is_text {
+        return Ok(None);
+    }
+
+    let resolved = vault.resolve(&path.to_string_lossy())?;
+    let size = match std::fs::metadata(&resolved) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(FileNodeError::Missing(path.display().to_string()));
+        }
+        Err(e) => return Err(FileNodeError::Unreadable(e.to_string())),
+    };
+    if size > options.max_file_bytes {
+        return Err(FileNodeError::TooLarge { size, max: options.max_file_bytes });
+    }
+    std::fs::read_to_string(&resolved).map(Some).map_err(|e| FileNodeError::Unreadable(e.to_string()))
+}
+
+/// What a File node publishes: its file's text without the frontmatter, cut down to the
+/// node's `subpath` if it has one
+///
+/// Ok(None) without a vault and for files [`content_of`] doesn't read as text.
+pub fn read_file_node(file_node: &FileNode, options: &ResolveOptions) -> Result<Option<String>, FileNodeError> {
+    let Some(text) = read_vault_file(file_node.file(), options)? else {
+        return Ok(None);
+    };
+    let body = strip_frontmatter(&text);
+    let body = match file_node.subpath().filter(|subpath| !subpath.is_empty()) {
+        Some(subpath) => subpath_section(body, subpath).ok_or_else(|| FileNodeError::NoSection(subpath.clone()))?,
+        None => body.to_string(),
+    };
+    Ok(Some(body.trim_matches(['\n', '\r']).to_string()))
+}
+
+/// The part of a note that a `#Heading`, `#Heading#Subheading` or `#^block-id` subpath
+/// points at
+///
+/// A heading's section runs from its line to the next heading of the same or a higher level.
+/// A block is the paragraph or list item ending in `^block-id`, without the marker.
+pub fn subpath_section(markdown: &str, subpath: &str) -> Option<String> {
+    let subpath = subpath.strip_prefix('#').unwrap_or(subpath);
+    if let Some(block_id) = subpath.strip_prefix('^') {
+        return block_section(markdown, block_id);
+    }
+    let mut section = markdown;
+    for heading in subpath.split('#') {
+        section = heading_section(section, heading.trim())?;
+    }
+    Some(section.to_string())
+}
+
+fn heading_section<'a>(markdown: &'a str, heading: &str) -> Option<&'a str> {
+    // Start and level of the heading once found
+    let mut found: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    let mut in_fence = false;
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        match atx_heading(line).filter(|_| !in_fence) {
+            Some((level, _)) if found.is_some_and(|(_, open)| level <= open) => {
+                return found.map(|(start, _)| &markdown[start..offset]);
+            }
+            Some((level, text)) if found.is_none() && text.eq_ignore_ascii_case(heading) => {
+                found = Some((offset, level));
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+    found.map(|(start, _)| &markdown[start..])
+}
+
+/// Level and text of a `## Heading` line
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim_end();
+    let level = line.len() - line.trim_start_matches('#').len();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn block_section(markdown: &str, block_id: &str) -> Option<String> {
+    let marker = format!("^{}", block_id);
+    let mut block: Vec<&str> = Vec::new();
+    for line in markdown.lines() {
+        if line.trim().is_empty() || list_item(line).is_some() || atx_heading(line).is_some() {
+            block.clear();
+        }
+        let content = line.trim_end().strip_suffix(marker.as_str());
+        let Some(content) = content.filter(|c| c.is_empty() || c.ends_with(char::is_whitespace)) else {
+            if !line.trim().is_empty() {
+                block.push(line);
+            }
+            continue;
+        };
+        // A marker on a line of its own names the block above it
+        if !content.trim().is_empty() {
+            block.push(content.trim_end());
+        }
+        // A list item stands on its own, without its marker
+        if let Some(item) = block.first_mut().and_then(|first| list_item(first)) {
+            block[0] = item;
+        }
+        return (!block.is_empty()).then(|| block.join("\n"));
+    }
+    None
+}
+
+/// The text of a `- item` or `1. item` line, after its marker
+fn list_item(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let rest = match trimmed.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = trimmed.len() - trimmed.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            trimmed[digits..].strip_prefix('.').filter(|_| digits > 0)?
+        }
+    };
+    rest.strip_prefix(' ').map(str::trim_start)
+}
+
+/// A node that already lives elsewhere, linked to instead of getting a post of its own
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalLink {
+    pub url: String,
+    /// Text to title the link with: the frontmatter `title`, else the text after the
+    /// frontmatter, else (for Link nodes) the label or URL
+    pub text: String,
+}
+
+/// Where a node lives outside the canvas, if it does: a Link node's URL, or the
+/// `external_url` in the frontmatter of a Text or File node
+pub fn external_link_of(content: &NodeContent) -> Option<ExternalLink> {
+    match content {
+        NodeContent::Link { url, label } => {
+            Some(ExternalLink { url: url.to_string(), text: label.unwrap_or(url).to_string() })
+        }
+        NodeContent::Group { .. } => None,
+        _ => {
+            let text = content.as_text_lossy();
+            let url = frontmatter_value(&text, "external_url").filter(|url| !url.is_empty())?;
+            let title = frontmatter_value(&text, "title").unwrap_or_else(|| strip_frontmatter(&text).trim());
+            Some(ExternalLink { url: url.to_string(), text: title.to_string() })
+        }
+    }
+}
+
+/// The value of `key` in the leading `---` frontmatter block of a node's text, unquoted
+pub fn frontmatter_value<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let (block, _) = split_frontmatter(text)?;
+    block.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix(':')?;
+        Some(value.trim().trim_matches(|c| c == '"' || c == '\''))
+    })
+}
+
+/// A node's text without its leading `---` frontmatter block, if it has one
+pub fn strip_frontmatter(text: &str) -> &str {
+    split_frontmatter(text).map_or(text, |(_, body)| body)
+}
+
+/// The lines between a leading `---` line and the next one, and what follows that
+fn split_frontmatter(text: &str) -> Option<(&str, &str)> {
+    let rest = text.trim_start_matches('\u{feff}').strip_prefix("---")?;
+    let rest = rest.strip_prefix('\n').or_else(|| rest.strip_prefix("\r\n"))?;
+    let end = rest.find("\n---")?;
+    let body = rest[end + 4..].split_once('\n').map_or("", |(_, body)| body);
+    Some((&rest[..end], body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CanvasBuilder;
+    use crate::jsoncanvas::JsonCanvas;
+
+    /// The only node of a canvas
+    fn only(canvas: &JsonCanvas) -> &Node {
+        canvas.get_nodes().values().next().unwrap()
+    }
+
+    #[test]
+    fn each_node_kind_has_its_content() {
+        let text = CanvasBuilder::new().text_node("c047000000000001", "A thought").build().unwrap();
+        let options = ResolveOptions::default();
+        let content = content_of(only(&text), &options);
+        assert_eq!((content.kind(), content.as_text()), ("text", Some("A thought")));
+
+        let group = CanvasBuilder::new().group_node("c047000000000002").label("Ideas").build().unwrap();
+        assert_eq!(content_of(only(&group), &options), NodeContent::Group { label: Some("Ideas") });
+
+        let link = CanvasBuilder::new().link_node("c047000000000003", "https://example.com/").build().unwrap();
+        assert_eq!(content_of(only(&link), &options).as_text_lossy(), "https://example.com/");
+        let labelled = ResolveOptions {
+            link_labels: HashMap::from([("https://example.com/".to_string(), "Example".to_string())]),
+            ..Default::default()
+        };
+        let content = content_of(only(&link), &labelled);
+        assert_eq!(content, NodeContent::Link { url: "https://example.com/", label: Some("Example") });
+        assert_eq!(content.as_text(), None);
+    }
+
+    #[test]
+    fn file_nodes_read_text_files_through_the_vault_and_fall_back_to_the_path() {
+        let vault = std::env::temp_dir().join(format!("syndicate-json-canvas-content-{}", std::process::id()));
+        std::fs::create_dir_all(vault.join("notes")).unwrap();
+        std::fs::write(vault.join("notes/idea.md"), "From the file").unwrap();
+        let file = CanvasBuilder::new().file_node("c047000000000004", "notes/idea.md").build().unwrap();
+        let image = CanvasBuilder::new().file_node("c047000000000005", "notes/photo.png").build().unwrap();
+
+        let without_vault = ResolveOptions::default();
+        assert_eq!(content_of(only(&file), &without_vault).as_text_lossy(), "notes/idea.md");
+        let options = ResolveOptions::default().with_vault(Vault::new(&vault));
+        assert_eq!(content_of(only(&file), &options).as_text_lossy(), "From the file");
+        assert_eq!(content_of(only(&image), &options).as_text_lossy(), "notes/photo.png");
+        let small = ResolveOptions { max_file_bytes: 4, ..options };
+        let content = content_of(only(&file), &small);
+        assert_eq!(content, NodeContent::File { path: Path::new("notes/idea.md"), resolved_text: None });
+        std::fs::remove_dir_all(&vault).unwrap();
+    }
+}
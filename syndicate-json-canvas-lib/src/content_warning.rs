@@ -0,0 +1,45 @@
+//! Content warnings: the `cw` setting of a node or group, or `#cw:topic` tokens in its text
+//!
+//! An item behind a warning carries it as [`SyndicationFormat::content_warning`]; each sink
+//! shows it its own way (frontmatter for site themes, a prefix on tweets, ...).
+//!
+//! [`SyndicationFormat::content_warning`]: crate::SyndicationFormat::content_warning
+
+/// Start of a content warning token in node text, followed by its topic
+pub const CW_TOKEN: &str = "#cw:";
+
+/// Text with its `#cw:topic` tokens taken out, and their topics in order
+///
+/// A token is a word starting with [`CW_TOKEN`]; trailing punctuation isn't part of the
+/// topic. Lines left blank by taking out their tokens are dropped. Lines without tokens are
+/// left exactly as they are.
+pub fn take_cw_tokens(text: &str) -> (String, Vec<String>) {
+    let mut topics: Vec<String> = Vec::new();
+    let mut kept = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        if !line.contains(CW_TOKEN) {
+            kept.push_str(line);
+            continue;
+        }
+        let content = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[content.len()..];
+        let indent = &content[..content.len() - content.trim_start().len()];
+        let mut words = Vec::new();
+        for word in content.split_whitespace() {
+            match word.strip_prefix(CW_TOKEN).map(|topic| topic.trim_end_matches(['.', ',', ';', '!', '?'])) {
+                Some(topic) if !topic.is_empty() => {
+                    if !topics.iter().any(|t| t == topic) {
+                        topics.push(topic.to_string());
+                    }
+                }
+                _ => words.push(word),
+            }
+        }
+        if !words.is_empty() {
+            kept.push_str(indent);
+            kept.push_str(&words.join(" "));
+            kept.push_str(ending);
+        }
+    }
+    (kept, topics)
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+
+use crate::watch::FilteredEvents;
+
+/// Help shown for unknown console commands
+pub const CONSOLE_HELP: &str = "commands: p = process now, s = status, d = toggle dry run, q = quit";
+
+/// A request to a running watch loop
+///
+/// Every way of controlling the watcher (the stdin console, or a program embedding it)
+/// sends these over the channel given to `watch`. Dropping every sender stops the loop
+/// like [`Shutdown`](Self::Shutdown).
+#[derive(Debug)]
+pub enum WatchCommand {
+    /// Process every pipeline now, whether or not its canvas changed
+    Process,
+    /// Reply with the state of every pipeline
+    Status(Sender<Vec<PipelineStatus>>),
+    /// Switch every pipeline between dry run and armed
+    ToggleDryRun,
+    /// Stop watching once the current run is done
+    Shutdown,
+}
+
+/// State of one watched pipeline, as reported by [`WatchCommand::Status`]
+#[derive(Debug, Clone)]
+pub struct PipelineStatus {
+    /// None when it's the only pipeline
+    pub name: Option<String>,
+    pub canvas_path: PathBuf,
+    pub dry_run: bool,
+    /// Whether a change is waiting to be processed
+    pub changed: bool,
+    /// Time since the latest run started
+    pub last_run: Option<Duration>,
+    /// Time left until the pending batch closes
+    pub batch_closes_in: Option<Duration>,
+    /// Warnings raised by the latest run
+    pub warnings: usize,
+    /// Watcher events dropped so far
+    pub filtered: FilteredEvents,
+    /// Requests to process waiting in the queue all pipelines share, counting those of
+    /// senders blocked on it
+    pub queued: usize,
+    /// Requests dropped so far because that queue was full
+    pub dropped: usize,
+}
+
+impl fmt::Display for PipelineStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} ({})", name, self.canvas_path.display())?,
+            None => write!(f, "{}", self.canvas_path.display())?,
+        }
+        write!(f, ": {}", if self.dry_run { "dry run" } else { "armed" })?;
+        write!(f, ", {}", if self.changed { "change pending" } else { "up to date" })?;
+        match self.last_run {
+            Some(elapsed) => write!(f, ", last run {}s ago", elapsed.as_secs())?,
+            None => write!(f, ", not run yet")?,
+        }
+        if let Some(remaining) = self.batch_closes_in {
+            write!(f, ", batch closes in {}s", remaining.as_secs())?;
+        }
+        if self.warnings > 0 {
+            write!(f, ", {} warnings in the last run", self.warnings)?;
+        }
+        write!(f, ", {} watcher events filtered", self.filtered.total())?;
+        if self.queued > 0 {
+            write!(f, ", {} requests queued", self.queued)?;
+        }
+        if self.dropped > 0 {
+            write!(f, ", {} requests dropped while the queue was full", self.dropped)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read single-letter commands from `input` and send them to a watch loop, writing
+/// replies and help to `output`
+///
+/// Returns after `q`, at the end of the input, or once the watch loop has stopped.
+/// Reaching the end of the input doesn't stop the loop.
+pub fn run_console(input: impl BufRead, mut output: impl Write, commands: Sender<WatchCommand>) -> io::Result<()> {
+    for line in input.lines() {
+        let command = match line?.trim() {
+            "" => continue,
+            "p" => WatchCommand::Process,
+            "d" => WatchCommand::ToggleDryRun,
+            "q" => WatchCommand::Shutdown,
+            "s" => {
+                let (reply_tx, reply_rx) = mpsc::channel();
+                if commands.send(WatchCommand::Status(reply_tx)).is_err() {
+                    return Ok(());
+                }
+                let Ok(statuses) = reply_rx.recv() else {
+                    return Ok(());
+                };
+                for status in statuses {
+                    writeln!(output, "{}", status)?;
+                }
+                continue;
+            }
+            other => {
+                writeln!(output, "unknown command `{}`; {}", other, CONSOLE_HELP)?;
+                continue;
+            }
+        };
+        let quit = matches!(command, WatchCommand::Shutdown);
+        if commands.send(command).is_err() || quit {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
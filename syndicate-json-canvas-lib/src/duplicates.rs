@@ -0,0 +1,172 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::SyndicationFormat;
+use crate::jsoncanvas::NodeId;
+use crate::node_ids::cmp_node_ids;
+
+/// What to do when duplicate items are found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Report duplicates but publish all of them
+    #[default]
+    Warn,
+    /// Keep the oldest member of each group
+    SkipNewer,
+    /// Keep the newest member of each group
+    SkipOlder,
+    /// Abort the run
+    Fail,
+}
+
+/// Configuration for duplicate detection
+#[derive(Debug, Clone)]
+pub struct DuplicateOptions {
+    pub policy: DuplicatePolicy,
+    /// Jaccard similarity of token shingles at or above which two items are near-duplicates
+    pub similarity_threshold: f64,
+    /// Number of consecutive tokens per shingle
+    pub shingle_size: usize,
+}
+
+impl Default for DuplicateOptions {
+    fn default() -> Self {
+        Self {
+            policy: DuplicatePolicy::Warn,
+            similarity_threshold: 0.8,
+            shingle_size: 2,
+        }
+    }
+}
+
+/// A set of items with identical or near-identical content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// Members, oldest first
+    pub node_ids: Vec<NodeId>,
+    /// True if every member has exactly the same content
+    pub exact: bool,
+}
+
+/// Group items whose content is identical or at least `threshold`-similar
+///
+/// Canvases carry no creation time, so "older" means already published (per
+/// `is_published`), with ties broken by NodeId for determinism.
+pub fn find_duplicate_groups(
+    items: &HashMap<NodeId, SyndicationFormat>,
+    options: &DuplicateOptions,
+    is_published: impl Fn(&NodeId) -> bool,
+) -> Vec<DuplicateGroup> {
+    let mut ids: Vec<&NodeId> = items.keys().collect();
+    ids.sort_by(|a, b| {
+        is_published(b)
+            .cmp(&is_published(a))
+            .then_with(|| cmp_node_ids(a, b))
+    });
+
+    let hashes: Vec<String> = ids.iter().map(|id| content_hash(&items[*id].text)).collect();
+    let shingles: Vec<HashSet<String>> = ids
+        .iter()
+        .map(|id| shingles(&items[*id].text, options.shingle_size))
+        .collect();
+
+    // Union-find over all pairs; canvases are small enough for O(n^2)
+    let mut parent: Vec<usize> = (0..ids.len()).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+
+    for i in 0..ids.len() {
+        for j in (i + 1)..ids.len() {
+            let duplicate = hashes[i] == hashes[j]
+                || jaccard(&shingles[i], &shingles[j]) >= options.similarity_threshold;
+            if duplicate {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                // Keep the oldest member as the root so groups stay ordered
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..ids.len() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(i);
+    }
+
+    let mut result: Vec<DuplicateGroup> = groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| DuplicateGroup {
+            exact: members.iter().all(|&m| hashes[m] == hashes[members[0]]),
+            node_ids: members.iter().map(|&m| ids[m].clone()).collect(),
+        })
+        .collect();
+    result.sort_by(|a, b| a.node_ids.iter().map(NodeId::as_str).cmp(b.node_ids.iter().map(NodeId::as_str)));
+    result
+}
+
+/// Node IDs that should be suppressed for a group under a policy
+pub fn suppressed_by_policy(group: &DuplicateGroup, policy: DuplicatePolicy) -> &[NodeId] {
+    match policy {
+        DuplicatePolicy::Warn | DuplicatePolicy::Fail => &[],
+        DuplicatePolicy::SkipNewer => &group.node_ids[1..],
+        DuplicatePolicy::SkipOlder => &group.node_ids[..group.node_ids.len() - 1],
+    }
+}
+
+/// SHA-256 of the text with surrounding whitespace trimmed
+pub fn content_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.trim().as_bytes()))
+}
+
+/// Lowercased word shingles of `size` consecutive tokens
+fn shingles(text: &str, size: usize) -> HashSet<String> {
+    let tokens: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    if tokens.len() <= size {
+        return std::iter::once(tokens.join(" ")).collect();
+    }
+    tokens.windows(size).map(|w| w.join(" ")).collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(node_ids: &[&str]) -> DuplicateGroup {
+        DuplicateGroup { node_ids: node_ids.iter().map(|id| id.parse().unwrap()).collect(), exact: true }
+    }
+
+    #[test]
+    fn policies_suppress_node_ids() {
+        let group = group(&["d0b0000000000001", "d0b0000000000002", "d0b0000000000003"]);
+        let suppressed = |policy| -> Vec<&str> {
+            suppressed_by_policy(&group, policy).iter().map(NodeId::as_str).collect()
+        };
+        assert!(suppressed(DuplicatePolicy::Warn).is_empty());
+        assert!(suppressed(DuplicatePolicy::Fail).is_empty());
+        assert_eq!(suppressed(DuplicatePolicy::SkipNewer), ["d0b0000000000002", "d0b0000000000003"]);
+        assert_eq!(suppressed(DuplicatePolicy::SkipOlder), ["d0b0000000000001", "d0b0000000000002"]);
+    }
+}
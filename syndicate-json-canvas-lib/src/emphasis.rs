@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::color::{ColorError, color_key, color_name, parse_color};
+use crate::jsoncanvas::color::Color;
+use crate::jsoncanvas::{JsonCanvas, NodeId};
+use crate::{EdgeDirection, SyndicationFormat};
+
+/// Weight of edges whose color isn't mapped, by default
+pub const DEFAULT_EDGE_WEIGHT: f64 = 1.0;
+
+/// How strongly an item is connected to one of its neighbors, from the color of the edge
+/// between them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EdgeEmphasis {
+    /// Display name of the edge's color (see [`color_name`]), None if uncolored
+    pub color: Option<String>,
+    pub weight: f64,
+    /// Label of the weight, e.g. `strong`
+    pub label: Option<String>,
+}
+
+/// One mapped edge color
+#[derive(Debug, Clone, PartialEq)]
+struct WeightedColor {
+    /// Canonical key (see [`color_key`])
+    key: String,
+    weight: f64,
+    label: Option<String>,
+}
+
+/// Weights (and optional labels) of edges by color, e.g. red edges for strong dependencies
+///
+/// Neighbors of an item are ordered by the weight of the edge to them, heaviest first.
+/// Edges of unmapped colors, and uncolored ones, get the default weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeWeights {
+    colors: Vec<WeightedColor>,
+    default_weight: f64,
+}
+
+impl Default for EdgeWeights {
+    fn default() -> Self {
+        Self { colors: Vec::new(), default_weight: DEFAULT_EDGE_WEIGHT }
+    }
+}
+
+impl EdgeWeights {
+    /// Map colors, in any form [`parse_color`] understands, to a weight and label; a color
+    /// given twice keeps its first entry
+    pub fn parse(entries: &[(&str, f64, Option<&str>)]) -> Result<Self, ColorError> {
+        let mut weights = Self::default();
+        for (input, weight, label) in entries {
+            let key = color_key(&parse_color(input)?);
+            if !weights.colors.iter().any(|c| c.key == key) {
+                weights.colors.push(WeightedColor { key, weight: *weight, label: label.map(str::to_string) });
+            }
+        }
+        Ok(weights)
+    }
+
+    /// Weight of edges whose color isn't mapped (default [`DEFAULT_EDGE_WEIGHT`])
+    pub fn with_default_weight(mut self, weight: f64) -> Self {
+        self.default_weight = weight;
+        self
+    }
+
+    /// Emphasis of an edge of this color (None if uncolored)
+    pub fn emphasis(&self, color: Option<&Color>) -> EdgeEmphasis {
+        let mapped = color.and_then(|color| {
+            let key = color_key(color);
+            self.colors.iter().find(|c| c.key == key)
+        });
+        EdgeEmphasis {
+            color: color.map(color_name),
+            weight: mapped.map_or(self.default_weight, |c| c.weight),
+            label: mapped.and_then(|c| c.label.clone()),
+        }
+    }
+
+    /// Emphasis of every edge of a canvas by `(from, to)`, with edges pointing per `direction`;
+    /// of several edges between the same nodes, the heaviest
+    pub fn of_canvas(&self, canvas: &JsonCanvas, direction: EdgeDirection) -> HashMap<(NodeId, NodeId), EdgeEmphasis> {
+        let mut emphasis: HashMap<(NodeId, NodeId), EdgeEmphasis> = HashMap::new();
+        for edge in canvas.get_edges().values() {
+            let this = self.emphasis(edge.color());
+            for (from, to) in direction.links(edge) {
+                let key = (from.clone(), to.clone());
+                match emphasis.get(&key) {
+                    Some(existing) if existing.weight >= this.weight => {}
+                    _ => {
+                        emphasis.insert(key, this.clone());
+                    }
+                }
+            }
+        }
+        emphasis
+    }
+
+    /// Record the emphasis of each of the item's edges in `neighbor_emphasis` and order
+    /// its neighbors heaviest first, keeping the existing order between equal weights
+    pub fn apply(&self, item: &mut SyndicationFormat, edges: &HashMap<(NodeId, NodeId), EdgeEmphasis>) {
+        let default = self.emphasis(None);
+        for node_id in &item.in_neighbor_ids {
+            let emphasis = edges.get(&(node_id.clone(), item.id.clone())).unwrap_or(&default);
+            item.neighbor_emphasis.insert(node_id.clone(), emphasis.clone());
+        }
+        for node_id in &item.out_neighbor_ids {
+            let emphasis = edges.get(&(item.id.clone(), node_id.clone())).unwrap_or(&default);
+            let heavier = item.neighbor_emphasis.get(node_id).is_none_or(|e| e.weight < emphasis.weight);
+            if heavier {
+                item.neighbor_emphasis.insert(node_id.clone(), emphasis.clone());
+            }
+        }
+
+        let weight = |node_id: &NodeId| item.neighbor_emphasis.get(node_id).map_or(self.default_weight, |e| e.weight);
+        let by_weight = |ids: &[NodeId]| {
+            let mut ids = ids.to_vec();
+            ids.sort_by(|a, b| weight(b).total_cmp(&weight(a)));
+            ids
+        };
+        item.in_neighbor_ids = by_weight(&item.in_neighbor_ids);
+        item.out_neighbor_ids = by_weight(&item.out_neighbor_ids);
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::atomic::write_atomic;
+use crate::markdown::find_url;
+use crate::network::{HttpClient, HttpRequest};
+use crate::sanitize::parse_tag;
+use crate::tracker::canvas_state_path;
+
+/// Suffix of the file the link metadata cache is kept in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-links.json";
+
+/// Metadata about the first link in an item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkMeta {
+    /// The link that was fetched
+    pub url: String,
+    /// `og:title`, falling back to `<title>`
+    pub title: Option<String>,
+    /// `og:description`, falling back to `<meta name="description">`
+    pub description: Option<String>,
+}
+
+/// Options for fetching link metadata
+#[derive(Debug, Clone)]
+pub struct LinkEnrichmentOptions {
+    /// Per-request timeout
+    pub timeout: Duration,
+    /// Maximum number of bytes read from each page
+    pub max_bytes: u64,
+    /// Client pages are fetched with, e.g. a [`PoliteClient`](crate::network::PoliteClient)
+    /// shared with the sinks; None uses a plain client with the `link-enrichment` feature
+    /// and fetches nothing without it
+    pub http: Option<Arc<dyn HttpClient>>,
+}
+
+impl Default for LinkEnrichmentOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            max_bytes: 512 * 1024,
+            http: None,
+        }
+    }
+}
+
+/// Fetches and caches link metadata
+///
+/// Results are cached on disk next to the canvas keyed by URL, so repeated runs don't
+/// refetch. Failed fetches aren't cached and degrade to no metadata.
+pub struct LinkEnricher {
+    /// Path to the JSON cache file
+    cache_path: PathBuf,
+    /// Cached metadata by URL
+    cache: HashMap<String, LinkMeta>,
+    options: LinkEnrichmentOptions,
+    /// Whether the cache changed since it was loaded
+    dirty: bool,
+}
+
+impl LinkEnricher {
+    /// Load the link metadata cache for a canvas
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-links.json`
+    pub fn for_canvas(canvas_path: &Path, options: LinkEnrichmentOptions) -> Result<Self, Box<dyn Error>> {
+        let cache_path = canvas_state_path(canvas_path, STATE_FILE)?;
+        let cache = if cache_path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&cache_path)?)?
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self { cache_path, cache, options, dirty: false })
+    }
+
+    /// Metadata for the first URL in `text`, from the cache or the network
+    pub fn enrich(&mut self, text: &str) -> Option<LinkMeta> {
+        let url = find_url(text)?;
+        if let Some(meta) = self.cache.get(&url) {
+            debug!(url = %url, "Link metadata cache hit");
+            return Some(meta.clone());
+        }
+
+        let html = fetch(&url, &self.options)?;
+        let meta = parse_link_meta(&url, &html);
+        self.cache.insert(url, meta.clone());
+        self.dirty = true;
+        Some(meta)
+    }
+
+    /// Persist the cache if anything was fetched
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        if self.dirty {
+            write_atomic(&self.cache_path, serde_json::to_string_pretty(&self.cache)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn fetch(url: &str, options: &LinkEnrichmentOptions) -> Option<String> {
+    let client = options.http.clone().or_else(default_client)?;
+    let request = HttpRequest::get(url).timeout(options.timeout).max_bytes(options.max_bytes);
+    let response = client.send(&request).ok()?;
+    if !response.is_success() {
+        debug!(url = %url, status = response.status, "Link fetch failed");
+        return None;
+    }
+    Some(response.body)
+}
+
+#[cfg(feature = "link-enrichment")]
+fn default_client() -> Option<Arc<dyn HttpClient>> {
+    Some(Arc::new(crate::network::ReqwestClient::new()))
+}
+
+/// Without the `link-enrichment` feature nothing is fetched unless a client is given, so
+/// only cached metadata is used
+#[cfg(not(feature = "link-enrichment"))]
+fn default_client() -> Option<Arc<dyn HttpClient>> {
+    None
+}
+
+/// Extract the title and description from a page's HTML
+pub fn parse_link_meta(url: &str, html: &str) -> LinkMeta {
+    let mut title = None;
+    let mut og_title = None;
+    let mut description = None;
+    let mut og_description = None;
+
+    let lower = html.to_ascii_lowercase();
+    if let Some(start) = lower.find("<title")
+        && let Some(open_end) = lower[start..].find('>')
+    {
+        let body_start = start + open_end + 1;
+        if let Some(len) = lower[body_start..].find("</title") {
+            title = Some(decode_entities(html[body_start..body_start + len].trim()));
+        }
+    }
+
+    let mut rest = html;
+    while let Some(start) = rest.to_ascii_lowercase().find("<meta") {
+        rest = &rest[start..];
+        let Some(end) = rest.find('>') else { break };
+        if let Some(tag) = parse_tag(&rest[..=end]) {
+            let attr = |name: &str| {
+                tag.attributes
+                    .iter()
+                    .find(|(k, _)| k == name)
+                    .and_then(|(_, v)| v.clone())
+            };
+            let key = attr("property").or_else(|| attr("name"));
+            let content = attr("content").map(|c| decode_entities(c.trim()));
+            match key.as_deref() {
+                Some("og:title") => og_title = content,
+                Some("og:description") => og_description = content,
+                Some("description") => description = content,
+                _ => {}
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+
+    LinkMeta {
+        url: url.to_string(),
+        title: og_title.or(title).filter(|t| !t.is_empty()),
+        description: og_description.or(description).filter(|d| !d.is_empty()),
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::network::{HttpError, HttpResponse};
+
+    const PAGE: &str = r#"<html><head><title>Plain title</title>
+        <meta name="description" content="Plain description">
+        <meta property="og:title" content="Tom &amp; Jerry"></head></html>"#;
+
+    /// Serves `PAGE` for every URL, counting requests
+    #[derive(Debug, Default)]
+    struct Pages(AtomicUsize);
+
+    impl HttpClient for Pages {
+        fn send(&self, _request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(HttpResponse { status: 200, headers: Vec::new(), body: PAGE.to_string() })
+        }
+    }
+
+    #[test]
+    fn open_graph_wins_over_plain_tags() {
+        let meta = parse_link_meta("https://example.com", PAGE);
+        assert_eq!(meta.title.as_deref(), Some("Tom & Jerry"));
+        assert_eq!(meta.description.as_deref(), Some("Plain description"));
+    }
+
+    #[test]
+    fn links_are_fetched_once_across_runs() {
+        let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-enrich-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let canvas_path = dir.join("notes.canvas");
+        let pages = Arc::new(Pages::default());
+        let options = LinkEnrichmentOptions { http: Some(pages.clone()), ..Default::default() };
+
+        let mut enricher = LinkEnricher::for_canvas(&canvas_path, options.clone()).unwrap();
+        let meta = enricher.enrich("Read https://example.com/post, it's good").unwrap();
+        assert_eq!(meta.url, "https://example.com/post");
+        assert!(enricher.enrich("Again: https://example.com/post").is_some());
+        assert_eq!(enricher.enrich("No link here"), None);
+        enricher.save().unwrap();
+
+        let mut next_run = LinkEnricher::for_canvas(&canvas_path, options).unwrap();
+        assert_eq!(next_run.enrich("https://example.com/post"), Some(meta));
+        assert_eq!(pages.0.load(Ordering::SeqCst), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
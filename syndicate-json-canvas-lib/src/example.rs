@@ -0,0 +1,23 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::exclusions::ExclusionList;
+
+/// A small bundled canvas for demos and tests
+///
+/// Three red nodes form a chain, a fourth cross-references the first, one red node is
+/// excluded by [`write_example_canvas`] and one uncolored node is never syndicated.
+pub const EXAMPLE_CANVAS: &str = include_str!("../fixtures/example.canvas");
+
+/// The red node in [`EXAMPLE_CANVAS`] that [`write_example_canvas`] excludes
+pub const EXAMPLE_EXCLUDED_NODE_ID: &str = "a1b2c3d4e5f60005";
+
+/// Write [`EXAMPLE_CANVAS`] into `dir` with its exclusion list, returning the canvas path
+pub fn write_example_canvas(dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    std::fs::create_dir_all(dir)?;
+    let canvas_path = dir.join("example.canvas");
+    std::fs::write(&canvas_path, EXAMPLE_CANVAS)?;
+
+    ExclusionList::for_canvas(&canvas_path)?.add(EXAMPLE_EXCLUDED_NODE_ID)?;
+    Ok(canvas_path)
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::atomic::write_atomic;
+use crate::jsoncanvas::NodeId;
+use crate::tracker::canvas_state_path;
+
+/// Suffix of the file the exclusion list is kept in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-exclusions.toml";
+
+/// TOML structure for the exclusion file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExclusionFile {
+    pub excluded_node_ids: Vec<String>,
+}
+
+/// Denylist of nodes that must never be syndicated, regardless of color or filters
+///
+/// Shared by all sinks of a canvas and stored next to the trackers.
+pub struct ExclusionList {
+    /// Path to the TOML exclusion file
+    path: PathBuf,
+    /// Excluded node IDs, ordered for stable output
+    excluded_ids: BTreeSet<String>,
+}
+
+impl ExclusionList {
+    /// Load the exclusion list for a canvas file
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-exclusions.toml`
+    pub fn for_canvas(canvas_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = canvas_state_path(canvas_path, STATE_FILE)?;
+
+        let excluded_ids = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            let file: ExclusionFile = toml::from_str(&content)?;
+            file.excluded_node_ids.into_iter().collect()
+        } else {
+            BTreeSet::new()
+        };
+
+        Ok(Self { path, excluded_ids })
+    }
+
+    /// Check if a node is excluded
+    pub fn is_excluded(&self, node_id: &NodeId) -> bool {
+        self.excluded_ids.contains(node_id.as_str())
+    }
+
+    /// Iterate over the excluded node IDs
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.excluded_ids.iter().map(String::as_str)
+    }
+
+    /// Add a node to the denylist and save; returns false if it was already excluded
+    pub fn add(&mut self, node_id: &str) -> Result<bool, Box<dyn Error>> {
+        let added = self.excluded_ids.insert(node_id.to_string());
+        if added {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    /// Remove a node from the denylist and save; returns false if it wasn't excluded
+    pub fn remove(&mut self, node_id: &str) -> Result<bool, Box<dyn Error>> {
+        let removed = self.excluded_ids.remove(node_id);
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let file = ExclusionFile {
+            excluded_node_ids: self.excluded_ids.iter().cloned().collect(),
+        };
+
+        let toml_content = toml::to_string_pretty(&file)?;
+        let content_with_header = format!(
+            "# Generated by syndicate-json-canvas - Edit with `exclude add/remove`\n\n{}",
+            toml_content
+        );
+
+        write_atomic(&self.path, content_with_header)?;
+        info!(exclusions_path = %self.path.display(), total_excluded = self.excluded_ids.len(), "Saved exclusions");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+//! Node filters that combine: small tests of a node, joined with `and`, `or` and `not` or
+//! [`Filter::all`] and [`Filter::any`], and turned into a node processor by [`process_nodes_matching`]
+
+use std::collections::HashSet;
+
+use crate::archive::node_ids_in_group;
+use crate::color::ColorFilter;
+use crate::jsoncanvas::color::{Color, PresetColor};
+use crate::jsoncanvas::node::GenericNodeInfo;
+use crate::jsoncanvas::{JsonCanvas, Node, NodeId};
+use crate::size::SizeFilter;
+use crate::{InAdjacencies, OutAdjacencies, SyndicationFormat, process_node_with_filter};
+
+/// A test of whether a node is syndicated, given the node and its neighbors
+///
+/// Implemented by functions and closures of the same arguments returning `bool`, by
+/// [`ColorFilter`] and [`SizeFilter`], and by the filters built here.
+pub trait NodeFilter {
+    /// Whether the node passes
+    fn matches(&self, node: &Node, out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool;
+
+    /// Pass nodes both filters pass
+    fn and<F: NodeFilter>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Pass nodes either filter passes
+    fn or<F: NodeFilter>(self, other: F) -> Or<Self, F>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    /// Pass nodes this filter doesn't
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+
+    /// This filter as a [`Filter`], e.g. to keep filters of different types in one list
+    fn boxed(self) -> Filter
+    where
+        Self: Sized + Send + Sync + 'static,
+    {
+        Filter(Box::new(self))
+    }
+}
+
+impl<F> NodeFilter for F
+where
+    F: Fn(&Node, &OutAdjacencies, &InAdjacencies) -> bool,
+{
+    fn matches(&self, node: &Node, out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool {
+        self(node, out_adjacencies, in_adjacencies)
+    }
+}
+
+impl NodeFilter for ColorFilter {
+    fn matches(&self, node: &Node, _out_adjacencies: &OutAdjacencies, _in_adjacencies: &InAdjacencies) -> bool {
+        ColorFilter::matches(self, node.color().as_ref())
+    }
+}
+
+impl NodeFilter for &ColorFilter {
+    fn matches(&self, node: &Node, _out_adjacencies: &OutAdjacencies, _in_adjacencies: &InAdjacencies) -> bool {
+        ColorFilter::matches(self, node.color().as_ref())
+    }
+}
+
+impl NodeFilter for SizeFilter {
+    fn matches(&self, node: &Node, _out_adjacencies: &OutAdjacencies, _in_adjacencies: &InAdjacencies) -> bool {
+        self.matches_node(node)
+    }
+}
+
+/// Nodes two filters both pass; see [`NodeFilter::and`]
+#[derive(Debug, Clone, Copy)]
+pub struct And<A, B>(A, B);
+
+impl<A: NodeFilter, B: NodeFilter> NodeFilter for And<A, B> {
+    fn matches(&self, node: &Node, out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool {
+        self.0.matches(node, out_adjacencies, in_adjacencies) && self.1.matches(node, out_adjacencies, in_adjacencies)
+    }
+}
+
+/// Nodes either of two filters passes; see [`NodeFilter::or`]
+#[derive(Debug, Clone, Copy)]
+pub struct Or<A, B>(A, B);
+
+impl<A: NodeFilter, B: NodeFilter> NodeFilter for Or<A, B> {
+    fn matches(&self, node: &Node, out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool {
+        self.0.matches(node, out_adjacencies, in_adjacencies) || self.1.matches(node, out_adjacencies, in_adjacencies)
+    }
+}
+
+/// Nodes a filter doesn't pass; see [`NodeFilter::not`]
+#[derive(Debug, Clone, Copy)]
+pub struct Not<A>(A);
+
+impl<A: NodeFilter> NodeFilter for Not<A> {
+    fn matches(&self, node: &Node, out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool {
+        !self.0.matches(node, out_adjacencies, in_adjacencies)
+    }
+}
+
+/// A filter of any type, for combining filters chosen at runtime
+pub struct Filter(Box<dyn NodeFilter + Send + Sync>);
+
+impl Filter {
+    pub fn new(filter: impl NodeFilter + Send + Sync + 'static) -> Self {
+        filter.boxed()
+    }
+
+    /// Pass nodes every filter passes; every node if there are none
+    pub fn all(filters: Vec<Filter>) -> Self {
+        Self::new(move |node: &Node, out: &OutAdjacencies, inc: &InAdjacencies| {
+            filters.iter().all(|filter| filter.matches(node, out, inc))
+        })
+    }
+
+    /// Pass nodes any of the filters passes; none if there are none
+    pub fn any(filters: Vec<Filter>) -> Self {
+        Self::new(move |node: &Node, out: &OutAdjacencies, inc: &InAdjacencies| {
+            filters.iter().any(|filter| filter.matches(node, out, inc))
+        })
+    }
+
+    /// Pass nodes `filter` doesn't
+    pub fn not(filter: impl NodeFilter + Send + Sync + 'static) -> Self {
+        Self::new(Not(filter))
+    }
+}
+
+impl NodeFilter for Filter {
+    fn matches(&self, node: &Node, out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool {
+        self.0.matches(node, out_adjacencies, in_adjacencies)
+    }
+}
+
+impl std::fmt::Debug for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Filter(..)")
+    }
+}
+
+/// Nodes lying inside a group of some label, found when the filter is made; see
+/// [`in_group`]
+#[derive(Debug, Clone, Default)]
+pub struct InGroup(HashSet<NodeId>);
+
+impl NodeFilter for InGroup {
+    fn matches(&self, node: &Node, _out_adjacencies: &OutAdjacencies, _in_adjacencies: &InAdjacencies) -> bool {
+        self.0.contains(node.id())
+    }
+}
+
+/// Whether a node is a Text node
+pub fn is_text_node(node: &Node, _out_adjacencies: &OutAdjacencies, _in_adjacencies: &InAdjacencies) -> bool {
+    matches!(node, Node::Text(_))
+}
+
+/// Whether a node has no outgoing edges; isolated nodes are leaves too
+pub fn is_leaf(_node: &Node, out_adjacencies: &OutAdjacencies, _in_adjacencies: &InAdjacencies) -> bool {
+    out_adjacencies.is_empty()
+}
+
+/// Whether a node has no incoming edges; isolated nodes are roots too
+pub fn is_root(_node: &Node, _out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool {
+    in_adjacencies.is_empty()
+}
+
+/// Whether a node has no edges at all
+pub fn is_isolated(node: &Node, out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool {
+    is_leaf(node, out_adjacencies, in_adjacencies) && is_root(node, out_adjacencies, in_adjacencies)
+}
+
+/// A node's degree: its incoming and outgoing edges, an edge pointing both ways counting
+/// once each way
+fn degree(out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> usize {
+    out_adjacencies.len() + in_adjacencies.len()
+}
+
+/// Nodes with at least this many edges; see [`min_degree`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinDegree(usize);
+
+impl NodeFilter for MinDegree {
+    fn matches(&self, _node: &Node, out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool {
+        degree(out_adjacencies, in_adjacencies) >= self.0
+    }
+}
+
+/// Nodes with at most this many edges; see [`max_degree`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxDegree(usize);
+
+impl NodeFilter for MaxDegree {
+    fn matches(&self, _node: &Node, out_adjacencies: &OutAdjacencies, in_adjacencies: &InAdjacencies) -> bool {
+        degree(out_adjacencies, in_adjacencies) <= self.0
+    }
+}
+
+/// Nodes with at least `n` edges, incoming and outgoing together
+pub fn min_degree(n: usize) -> MinDegree {
+    MinDegree(n)
+}
+
+/// Nodes with at most `n` edges, incoming and outgoing together
+pub fn max_degree(n: usize) -> MaxDegree {
+    MaxDegree(n)
+}
+
+/// Nodes of this color only
+pub fn has_color(color: impl Into<Color>) -> ColorFilter {
+    ColorFilter::only(color)
+}
+
+/// Non-group nodes lying entirely inside a group of `canvas` labelled `label`, matched like
+/// the Archive group's (see [`node_ids_in_group`])
+pub fn in_group(canvas: &JsonCanvas, label: &str) -> InGroup {
+    InGroup(node_ids_in_group(canvas, label))
+}
+
+/// Red Text nodes, the filter of [`default_process_node`](crate::default_process_node)
+pub fn default_node_filter() -> impl NodeFilter + Clone + Send + Sync + 'static {
+    is_text_node.and(has_color(PresetColor::Red))
+}
+
+/// A node processor for [`to_syndication_format`](crate::to_syndication_format) syndicating
+/// the nodes `filter` passes by their text, as [`process_node_with_filter`] does
+pub fn process_nodes_matching(
+    filter: impl NodeFilter,
+) -> impl Fn(&Node, &OutAdjacencies, &InAdjacencies) -> Option<SyndicationFormat> {
+    move |node, out_adjacencies, in_adjacencies| {
+        process_node_with_filter(node, out_adjacencies, in_adjacencies, &filter)
+    }
+}
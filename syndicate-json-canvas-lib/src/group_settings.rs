@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::archive::contains;
+use crate::content::frontmatter_value;
+use crate::jsoncanvas::{JsonCanvas, Node, NodeId, node::GenericNodeInfo};
+use crate::warnings::{Warning, codes};
+
+/// Settings given to a node by the groups around it and its own frontmatter
+///
+/// A group sets them with `[key: value]` parts in its label, e.g.
+/// `Essays [tags: essays, long] [category: Essays] [sinks: jj] [template: {date}-{slug}] [cw: politics]`;
+/// a node with the same keys in its frontmatter. Each key left unset falls back to the next
+/// group out, then to the global configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemSettings {
+    /// Tags added to the item's hashtags, without `#`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Category, over any the item's tags map to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Sinks the item goes to, instead of all of them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sinks: Option<Vec<String>>,
+    /// How the markdown sinks name the item's file, instead of their own template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Content warning the item is published behind, e.g. `politics`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_warning: Option<String>,
+}
+
+impl ItemSettings {
+    /// The `[key: value]` parts of a group label; text outside brackets is the group's name
+    ///
+    /// Fails on an unclosed bracket, a part without `key: value`, an unknown key or an empty
+    /// value.
+    pub fn from_label(label: &str) -> Result<Self, String> {
+        let mut settings = Self::default();
+        let mut rest = label;
+        while let Some(start) = rest.find('[') {
+            let end = rest[start..].find(']').ok_or_else(|| format!("`{}` has no closing `]`", &rest[start..]))?;
+            let part = &rest[start + 1..start + end];
+            let (key, value) = part
+                .split_once(':')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .ok_or_else(|| format!("`[{}]` isn't `[key: value]`", part))?;
+            if value.is_empty() {
+                return Err(format!("`{}` has no value", key));
+            }
+            settings.set(key, value).ok_or_else(|| format!("unknown setting `{}`", key))?;
+            rest = &rest[start + end + 1..];
+        }
+        Ok(settings)
+    }
+
+    /// The `tags`, `category`, `sinks`, `template` and `cw` keys of a node's frontmatter
+    pub fn from_frontmatter(text: &str) -> Self {
+        let mut settings = Self::default();
+        for key in ["tags", "category", "sinks", "template", "cw"] {
+            if let Some(value) = frontmatter_value(text, key).filter(|value| !value.is_empty()) {
+                settings.set(key, value);
+            }
+        }
+        settings
+    }
+
+    /// Set `key` from its written value, if it's a known key
+    fn set(&mut self, key: &str, value: &str) -> Option<()> {
+        match key {
+            "tags" => {
+                self.tags = Some(list(value).into_iter().map(|tag| tag.trim_start_matches('#').to_string()).collect())
+            }
+            "category" => self.category = Some(value.to_string()),
+            "sink" | "sinks" => self.sinks = Some(list(value)),
+            "template" => self.template = Some(value.to_string()),
+            "cw" | "content_warning" => self.content_warning = Some(value.to_string()),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// These settings, with the keys they leave unset taken from `fallback`
+    pub fn or(self, fallback: &Self) -> Self {
+        Self {
+            tags: self.tags.or_else(|| fallback.tags.clone()),
+            category: self.category.or_else(|| fallback.category.clone()),
+            sinks: self.sinks.or_else(|| fallback.sinks.clone()),
+            template: self.template.or_else(|| fallback.template.clone()),
+            content_warning: self.content_warning.or_else(|| fallback.content_warning.clone()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether the item goes to the sink named `sink`
+    pub fn routes_to(&self, sink: &str) -> bool {
+        self.sinks.as_ref().is_none_or(|sinks| sinks.iter().any(|s| s == sink))
+    }
+}
+
+/// `a, b` or `[a, b]`, with quotes around items dropped
+fn list(value: &str) -> Vec<String> {
+    let value = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')).unwrap_or(value);
+    value
+        .split(',')
+        .map(|item| item.trim().trim_matches(|c| c == '"' || c == '\''))
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Settings each non-group node gets from the groups it lies inside, the innermost group's
+/// keys over the outer ones'
+///
+/// Groups whose settings don't parse, or route to none of `sink_names`, are ignored with a
+/// warning.
+pub fn group_settings(canvas: &JsonCanvas, sink_names: &[&str]) -> (HashMap<NodeId, ItemSettings>, Vec<Warning>) {
+    let nodes = canvas.get_nodes();
+    let mut warnings = Vec::new();
+    let mut groups: Vec<(&Node, ItemSettings)> = Vec::new();
+    for (group_id, node) in nodes {
+        let Node::Group(group) = node else {
+            continue;
+        };
+        let Some(label) = group.label() else {
+            continue;
+        };
+        let settings = ItemSettings::from_label(label).and_then(|settings| match &settings.sinks {
+            Some(sinks) if !sinks.iter().any(|sink| sink_names.contains(&sink.as_str())) => {
+                Err(format!("no sink named {}", sinks.join(" or ")))
+            }
+            _ => Ok(settings),
+        });
+        match settings {
+            Ok(settings) if !settings.is_empty() => groups.push((node, settings)),
+            Ok(_) => {}
+            Err(reason) => {
+                let message = format!("Settings of group `{}` ignored: {}", label, reason);
+                warnings.push(Warning::new(codes::GROUP_SETTINGS, "groups", message).for_node(group_id));
+            }
+        }
+    }
+    warnings.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+    // Innermost (smallest) first
+    groups.sort_by_key(|(group, _)| group.get_width().saturating_mul(group.get_height()));
+
+    let settings = nodes
+        .iter()
+        .filter(|(_, node)| !matches!(node, Node::Group(_)))
+        .filter_map(|(node_id, node)| {
+            let settings = groups
+                .iter()
+                .filter(|(group, _)| contains(group, node))
+                .fold(ItemSettings::default(), |settings, (_, group)| settings.or(group));
+            (!settings.is_empty()).then(|| (node_id.clone(), settings))
+        })
+        .collect();
+    (settings, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_parse_into_settings_or_say_why_not() {
+        let settings = ItemSettings::from_label("Essays [tags: #essays, long] [sink: jj] [template: {slug}]").unwrap();
+        assert_eq!(settings.tags, Some(vec!["essays".to_string(), "long".to_string()]));
+        assert_eq!(settings.sinks, Some(vec!["jj".to_string()]));
+        assert_eq!(settings.template.as_deref(), Some("{slug}"));
+        assert!(settings.routes_to("jj") && !settings.routes_to("twitter"));
+        assert!(ItemSettings::from_label("Just a name").unwrap().is_empty());
+
+        assert_eq!(ItemSettings::from_label("A [tags: x").unwrap_err(), "`[tags: x` has no closing `]`");
+        assert_eq!(ItemSettings::from_label("A [tags]").unwrap_err(), "`[tags]` isn't `[key: value]`");
+        assert_eq!(ItemSettings::from_label("A [colour: red]").unwrap_err(), "unknown setting `colour`");
+        assert_eq!(ItemSettings::from_label("A [category: ]").unwrap_err(), "`category` has no value");
+    }
+
+    #[test]
+    fn unset_keys_fall_back_key_by_key() {
+        let node = ItemSettings::from_frontmatter("---\ncategory: Mine\n---\nText");
+        let group = ItemSettings::from_label("[category: Theirs] [tags: a]").unwrap();
+        let merged = node.or(&group);
+        assert_eq!(merged.category.as_deref(), Some("Mine"));
+        assert_eq!(merged.tags, Some(vec!["a".to_string()]));
+        assert_eq!(merged.sinks, None);
+    }
+}
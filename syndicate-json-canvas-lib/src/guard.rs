@@ -0,0 +1,168 @@
+use std::collections::{BTreeSet, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, warn};
+
+use crate::atomic::write_atomic;
+use crate::jsoncanvas::NodeId;
+use crate::tracker::canvas_state_path;
+
+/// Suffix of the file the removal snapshot is kept in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-snapshot.json";
+
+/// Fraction of published nodes that may vanish in one run by default
+pub const DEFAULT_MAX_VANISHED: f64 = 0.5;
+
+/// Refuses runs in which many published nodes disappeared from the canvas at once
+///
+/// A canvas truncated by a sync conflict or a bad merge looks like a mass deletion; the
+/// guard stops the run before any state or sink is touched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RemovalGuard {
+    /// Largest fraction of the previously published nodes that may vanish between runs
+    pub max_vanished: f64,
+    /// Run anyway, e.g. after deliberately clearing out the canvas
+    pub accept_mass_removal: bool,
+}
+
+impl Default for RemovalGuard {
+    fn default() -> Self {
+        Self { max_vanished: DEFAULT_MAX_VANISHED, accept_mass_removal: false }
+    }
+}
+
+impl RemovalGuard {
+    /// Allow up to this fraction (0.0 to 1.0) of published nodes to vanish
+    pub fn with_max_vanished(mut self, max_vanished: f64) -> Self {
+        self.max_vanished = max_vanished;
+        self
+    }
+
+    /// Let the next run proceed whatever vanished
+    pub fn with_accept_mass_removal(mut self, accept: bool) -> Self {
+        self.accept_mass_removal = accept;
+        self
+    }
+
+    /// Check a canvas with these node IDs against its snapshot from the last run
+    ///
+    /// Runs and prunes both come through here before touching anything. A mass removal is
+    /// refused with [`MassRemoval`], or only logged once accepted. Returns the snapshot, to
+    /// record the canvas in once done.
+    pub fn check(&self, canvas_path: &Path, node_ids: &HashSet<NodeId>) -> Result<RemovalSnapshot, Box<dyn Error>> {
+        let snapshot =
+            RemovalSnapshot::for_canvas(canvas_path).map_err(|e| format!("Failed to load removal snapshot: {}", e))?;
+        if let Err(removal) = snapshot.check(node_ids, self.max_vanished) {
+            if !self.accept_mass_removal {
+                error!(vanished = ?removal.vanished, "{}", removal);
+                return Err(removal.into());
+            }
+            warn!(
+                vanished = removal.vanished.len(),
+                published = removal.published,
+                "Accepting mass removal of published nodes"
+            );
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Too many published nodes vanished from the canvas since the last run
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "{} of {published} published nodes vanished from the canvas since the last run ({nodes_before} nodes before, {nodes_after} now); \
+refusing to publish or prune until the removal is accepted",
+    .vanished.len()
+)]
+pub struct MassRemoval {
+    /// Published node IDs missing from the canvas
+    pub vanished: Vec<String>,
+    /// Published node IDs in the canvas at the last run
+    pub published: usize,
+    pub nodes_before: usize,
+    pub nodes_after: usize,
+}
+
+/// JSON structure for the snapshot file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SnapshotFile {
+    /// Number of nodes in the canvas
+    node_count: usize,
+    /// Published node IDs that were in the canvas
+    published: BTreeSet<String>,
+}
+
+/// What the canvas looked like at the end of the last run, for [`RemovalGuard`]
+pub struct RemovalSnapshot {
+    /// Path to the JSON snapshot file
+    path: PathBuf,
+    /// None before the first run
+    state: Option<SnapshotFile>,
+}
+
+impl RemovalSnapshot {
+    /// Load the snapshot for a canvas file
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-snapshot.json`
+    pub fn for_canvas(canvas_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = canvas_state_path(canvas_path, STATE_FILE)?;
+        let state = if path.exists() {
+            Some(serde_json::from_str(&std::fs::read_to_string(&path)?)?)
+        } else {
+            None
+        };
+
+        Ok(Self { path, state })
+    }
+
+    /// Published node IDs from the last run missing from `node_ids`
+    pub fn vanished(&self, node_ids: &HashSet<NodeId>) -> Vec<String> {
+        let Some(state) = &self.state else {
+            return Vec::new();
+        };
+        let present: HashSet<&str> = node_ids.iter().map(NodeId::as_str).collect();
+        state.published.iter().filter(|id| !present.contains(id.as_str())).cloned().collect()
+    }
+
+    /// Check that no more than `max_vanished` of the published nodes are missing from a
+    /// canvas with these node IDs; the snapshot is only updated by [`record`](Self::record)
+    pub fn check(&self, node_ids: &HashSet<NodeId>, max_vanished: f64) -> Result<(), MassRemoval> {
+        let Some(state) = &self.state else {
+            return Ok(());
+        };
+        let vanished = self.vanished(node_ids);
+        if vanished.is_empty() || (vanished.len() as f64) <= max_vanished * state.published.len() as f64 {
+            return Ok(());
+        }
+        Err(MassRemoval {
+            vanished,
+            published: state.published.len(),
+            nodes_before: state.node_count,
+            nodes_after: node_ids.len(),
+        })
+    }
+
+    /// Remember the canvas of a completed run
+    pub fn record(&mut self, node_ids: &HashSet<NodeId>, is_published: impl Fn(&NodeId) -> bool) {
+        self.state = Some(SnapshotFile {
+            node_count: node_ids.len(),
+            published: node_ids
+                .iter()
+                .filter(|node_id| is_published(node_id))
+                .map(|node_id| node_id.as_str().to_string())
+                .collect(),
+        });
+    }
+
+    /// Persist the snapshot
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let Some(state) = &self.state else {
+            return Ok(());
+        };
+        write_atomic(&self.path, serde_json::to_string_pretty(state)?)?;
+        debug!(snapshot_path = %self.path.display(), published = state.published.len(), "Saved removal snapshot");
+        Ok(())
+    }
+}
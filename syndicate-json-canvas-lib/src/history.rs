@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::atomic::write_atomic;
+use crate::jsoncanvas::NodeId;
+use crate::report::SinkOutcome;
+use crate::sink::SyndicationLinks;
+use crate::text::TextPolicy;
+use crate::tracker::canvas_state_path;
+use crate::SyndicationFormat;
+
+/// Suffix of the file the history log is kept in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-history.jsonl";
+
+/// Size above which the history log is compacted by default
+pub const DEFAULT_HISTORY_MAX_BYTES: u64 = 1024 * 1024;
+
+/// What happened to a node on a sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryEventKind {
+    Published,
+    Archived,
+    Unarchived,
+    Updated,
+    /// Posted again from the archive
+    Resurfaced,
+}
+
+impl HistoryEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Published => "published",
+            Self::Archived => "archived",
+            Self::Unarchived => "unarchived",
+            Self::Updated => "updated",
+            Self::Resurfaced => "resurfaced",
+        }
+    }
+}
+
+/// One entry of the publish audit trail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    pub at: DateTime<Local>,
+    pub sink: String,
+    pub node_id: NodeId,
+    pub kind: HistoryEventKind,
+    /// Slug derived from the text at the time, if the text was known
+    pub slug: Option<String>,
+    /// SHA-256 of the text at the time, if the text was known
+    pub content_hash: Option<String>,
+    /// Where the post can be seen, for sinks posting to other sites
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl HistoryEvent {
+    /// The content hash cut to 16 characters for display; a shorter one, e.g. from a
+    /// hand-edited log, is kept whole
+    pub fn short_hash(&self) -> Option<&str> {
+        self.content_hash.as_deref().map(|hash| hash.get(..16).unwrap_or(hash))
+    }
+}
+
+/// Append-only log of publish, archive and un-archive events per node and sink
+///
+/// Stored as JSON lines next to the trackers. Once the file grows past `max_bytes` it is
+/// compacted to the latest event per node and sink.
+pub struct HistoryLog {
+    /// Path to the JSONL log file
+    path: PathBuf,
+    max_bytes: u64,
+}
+
+impl HistoryLog {
+    /// Open the history log of a canvas file
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-history.jsonl`
+    pub fn for_canvas(canvas_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = canvas_state_path(canvas_path, STATE_FILE)?;
+        Ok(Self { path, max_bytes: DEFAULT_HISTORY_MAX_BYTES })
+    }
+
+    /// Compact once the log grows past `max_bytes`
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Events of the successful, non-dry-run outcomes
+    ///
+    /// `items` supplies the text for slugs (cut by `text`) and content hashes; nodes
+    /// missing from it are logged without them.
+    pub fn events_for_outcomes(
+        outcomes: &[SinkOutcome],
+        items: &HashMap<NodeId, SyndicationFormat>,
+        at: DateTime<Local>,
+        text: &TextPolicy,
+    ) -> Vec<HistoryEvent> {
+        let mut events = Vec::new();
+        for outcome in outcomes.iter().filter(|o| !o.dry_run && o.error.is_none()) {
+            let kinds = [
+                (HistoryEventKind::Published, &outcome.items),
+                (HistoryEventKind::Archived, &outcome.archived),
+                (HistoryEventKind::Unarchived, &outcome.unarchived),
+                (HistoryEventKind::Updated, &outcome.updated),
+                (HistoryEventKind::Resurfaced, &outcome.resurfaced),
+            ];
+            for (kind, node_ids) in kinds {
+                for node_id in node_ids.iter().filter_map(|id| id.parse::<NodeId>().ok()) {
+                    let item = items.get(&node_id);
+                    events.push(HistoryEvent {
+                        at,
+                        sink: outcome.sink.clone(),
+                        kind,
+                        slug: item.map(|item| text.slug(&item.text)),
+                        content_hash: item.map(|item| format!("{:x}", Sha256::digest(item.text.as_bytes()))),
+                        url: (kind == HistoryEventKind::Published)
+                            .then(|| outcome.urls.get(node_id.as_str()).cloned())
+                            .flatten(),
+                        node_id,
+                    });
+                }
+            }
+        }
+        events
+    }
+
+    /// Append events, compacting the log if it grew too large
+    pub fn append(&self, events: &[HistoryEvent]) -> Result<(), Box<dyn Error>> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        for event in events {
+            writeln!(file, "{}", serde_json::to_string(event)?)?;
+        }
+
+        if file.metadata()?.len() > self.max_bytes {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// All events, oldest first
+    ///
+    /// Lines that don't parse (e.g. a write cut short by a crash) are skipped.
+    pub fn events(&self) -> Result<Vec<HistoryEvent>, Box<dyn Error>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(std::fs::read_to_string(&self.path)?
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Events of one node across all sinks, oldest first
+    pub fn events_for(&self, node_id: &NodeId) -> Result<Vec<HistoryEvent>, Box<dyn Error>> {
+        let mut events = self.events()?;
+        events.retain(|event| event.node_id == *node_id);
+        Ok(events)
+    }
+
+    /// The latest URL of every node's post on each sink that reported one
+    pub fn syndication_links(&self) -> Result<SyndicationLinks, Box<dyn Error>> {
+        let mut latest: HashMap<(NodeId, String), String> = HashMap::new();
+        for event in self.events()? {
+            if let Some(url) = event.url {
+                latest.insert((event.node_id, event.sink), url);
+            }
+        }
+        let mut links = SyndicationLinks::new();
+        for ((node_id, sink), url) in latest {
+            links.entry(node_id).or_default().push((sink, url));
+        }
+        for urls in links.values_mut() {
+            urls.sort();
+        }
+        Ok(links)
+    }
+
+    /// Rewrite the log keeping only the latest event per node and sink
+    pub fn compact(&self) -> Result<(), Box<dyn Error>> {
+        let events = self.events()?;
+        let total = events.len();
+
+        let mut latest: HashMap<(NodeId, String), usize> = HashMap::new();
+        for (index, event) in events.iter().enumerate() {
+            latest.insert((event.node_id.clone(), event.sink.clone()), index);
+        }
+        let mut keep: Vec<usize> = latest.into_values().collect();
+        keep.sort_unstable();
+
+        let mut contents = String::new();
+        for index in &keep {
+            contents.push_str(&serde_json::to_string(&events[*index])?);
+            contents.push('\n');
+        }
+        write_atomic(&self.path, contents)?;
+
+        info!(history_path = %self.path.display(), before = total, after = keep.len(), "Compacted history");
+        Ok(())
+    }
+}
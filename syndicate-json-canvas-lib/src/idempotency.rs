@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::atomic::write_atomic;
+use crate::clock::{Clock, SystemClock};
+use crate::jsoncanvas::NodeId;
+use crate::sink::SinkError;
+use crate::SyndicationFormat;
+
+/// Stable key of delivering one version of a node to one sink
+///
+/// The same node, text and sink always give the same key, so a retried delivery can be
+/// recognized; an edit gives a new one.
+pub fn idempotency_key(node_id: &NodeId, content_hash: &str, sink_name: &str) -> String {
+    let digest = Sha256::digest(format!("{}\n{}\n{}", sink_name, node_id.as_str(), content_hash).as_bytes());
+    format!("{:x}", digest)[..32].to_string()
+}
+
+/// The item's key as set by the orchestrator, or derived from it for callers that didn't
+pub fn key_of(item: &SyndicationFormat, sink_name: &str) -> String {
+    item.idempotency_key
+        .clone()
+        .unwrap_or_else(|| idempotency_key(&item.id, &item.content_hash(), sink_name))
+}
+
+/// Where a delivery stood when it was last recorded
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SentEntry {
+    pub node_id: String,
+    /// IDs of what was posted so far, in order (e.g. the tweets of a thread)
+    pub parts: Vec<String>,
+    /// A request was about to be sent and its outcome was never recorded, so it may or may
+    /// not have gone through
+    pub in_flight: bool,
+    /// Every part was posted
+    pub complete: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Write-ahead log of network deliveries, for sinks whose protocol has no idempotency keys
+///
+/// A sink records each request before sending it and its result right after, so a run
+/// that crashed before the tracker was saved can tell what already went out instead of
+/// posting it again. Every change is saved immediately.
+pub struct SentKeys {
+    path: PathBuf,
+    entries: BTreeMap<String, SentEntry>,
+    clock: Arc<dyn Clock>,
+}
+
+impl SentKeys {
+    /// Load the log kept beside a sink's tracker file
+    ///
+    /// File naming: `<tracker-file-name>.sent-keys.json`
+    pub fn beside(tracker_path: &Path) -> Result<Self, SinkError> {
+        let mut name = tracker_path.file_name().unwrap_or_default().to_os_string();
+        name.push(".sent-keys.json");
+        Self::load(tracker_path.with_file_name(name))
+    }
+
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, SinkError> {
+        let path = path.into();
+        let entries = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .map_err(|e| SinkError::Serialization(e.to_string()))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, entries, clock: Arc::new(SystemClock) })
+    }
+
+    /// Use a custom clock for the entries' `updated_at`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SentEntry> {
+        self.entries.get(key)
+    }
+
+    /// Record that a request for `key` is about to be sent
+    pub fn begin_request(&mut self, key: &str, node_id: &NodeId) -> Result<(), SinkError> {
+        let entry = self.touch(key);
+        entry.node_id = node_id.as_str().to_string();
+        entry.in_flight = true;
+        self.save()
+    }
+
+    /// Record that the request in flight posted `part_id`
+    pub fn record_part(&mut self, key: &str, part_id: &str) -> Result<(), SinkError> {
+        let entry = self.touch(key);
+        entry.parts.push(part_id.to_string());
+        entry.in_flight = false;
+        self.save()
+    }
+
+    /// Record that the request in flight was rejected, so nothing went out
+    pub fn record_rejected(&mut self, key: &str) -> Result<(), SinkError> {
+        self.touch(key).in_flight = false;
+        self.save()
+    }
+
+    /// Record that every part of the delivery was posted
+    pub fn complete(&mut self, key: &str) -> Result<(), SinkError> {
+        let entry = self.touch(key);
+        entry.complete = true;
+        entry.in_flight = false;
+        self.save()
+    }
+
+    /// Drop a key, e.g. to retry a delivery whose outcome is unknown
+    pub fn forget(&mut self, key: &str) -> Result<bool, SinkError> {
+        let removed = self.entries.remove(key).is_some();
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Drop completed deliveries, once the sink's tracker records them as published
+    pub fn prune_complete(&mut self) -> Result<usize, SinkError> {
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| !entry.complete);
+        let pruned = before - self.entries.len();
+        if pruned > 0 {
+            self.save()?;
+        }
+        Ok(pruned)
+    }
+
+    fn touch(&mut self, key: &str) -> &mut SentEntry {
+        let entry = self.entries.entry(key.to_string()).or_default();
+        entry.updated_at = Some(self.clock.now().with_timezone(&Utc));
+        entry
+    }
+
+    fn save(&self) -> Result<(), SinkError> {
+        let json = serde_json::to_string_pretty(&self.entries).map_err(|e| SinkError::Serialization(e.to_string()))?;
+        write_atomic(&self.path, json)?;
+        debug!(sent_keys_path = %self.path.display(), entries = self.entries.len(), "Saved sent keys");
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info};
+
+use crate::atomic::write_atomic;
+use crate::jsoncanvas::NodeId;
+use crate::node_ids::cmp_node_ids;
+use crate::report::ReportWriter;
+use crate::tracker::canvas_state_path;
+
+/// Suffix of the file the journal of the run in progress is kept in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-journal.json";
+
+/// Prefix of journals archived into the reports directory
+const ARCHIVE_PREFIX: &str = "journal-";
+
+/// ID of a run over the canvas version with hash `canvas_hash` and these eligible items
+///
+/// The same canvas with the same items always gives the same ID, whatever order the items
+/// come in.
+pub fn run_id<'a>(canvas_hash: &str, eligible: impl IntoIterator<Item = &'a NodeId>) -> String {
+    let mut node_ids: Vec<&NodeId> = eligible.into_iter().collect();
+    node_ids.sort_by(|a, b| cmp_node_ids(a, b));
+    node_ids.dedup();
+    let mut hasher = Sha256::new();
+    hasher.update(canvas_hash.as_bytes());
+    for node_id in node_ids {
+        hasher.update(b"\n");
+        hasher.update(node_id.as_str().as_bytes());
+    }
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+/// One sink's share of a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalStep {
+    pub sink: String,
+    /// Node IDs the sink was to be sent (published, updated, archived or un-archived)
+    pub planned: Vec<String>,
+    /// The sink took everything planned and its tracker was saved
+    pub completed: bool,
+}
+
+/// JSON structure for the journal file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalFile {
+    run_id: String,
+    canvas_hash: String,
+    started_at: DateTime<Local>,
+    steps: Vec<JournalStep>,
+}
+
+/// Write-ahead journal of a run: what each sink is to be sent, written before any sink is
+/// sent anything, and which sinks are done
+///
+/// A journal left behind with steps still open belongs to a run that failed or was killed
+/// partway; resuming it sends the open steps their planned items again, and nothing new.
+/// Once every step is done, or the next run starts without resuming it, the journal is
+/// archived next to the run reports.
+#[derive(Debug, Clone)]
+pub struct RunJournal {
+    /// Path to the JSON journal file
+    path: PathBuf,
+    state: JournalFile,
+}
+
+impl RunJournal {
+    /// Load the journal of an unfinished run of a canvas, if one was left behind
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-journal.json`
+    pub fn for_canvas(canvas_path: &Path) -> Result<Option<Self>, Box<dyn Error>> {
+        let path = canvas_state_path(canvas_path, STATE_FILE)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let state = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        Ok(Some(Self { path, state }))
+    }
+
+    /// Start the journal of a run, saving it before returning; steps with nothing planned
+    /// are left out
+    pub fn begin(
+        canvas_path: &Path,
+        run_id: impl Into<String>,
+        canvas_hash: impl Into<String>,
+        started_at: DateTime<Local>,
+        steps: impl IntoIterator<Item = JournalStep>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let state = JournalFile {
+            run_id: run_id.into(),
+            canvas_hash: canvas_hash.into(),
+            started_at,
+            steps: steps.into_iter().filter(|step| !step.planned.is_empty()).collect(),
+        };
+        let journal = Self { path: canvas_state_path(canvas_path, STATE_FILE)?, state };
+        journal.save()?;
+        Ok(journal)
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.state.run_id
+    }
+
+    /// Hash of the canvas version the run started from
+    pub fn canvas_hash(&self) -> &str {
+        &self.state.canvas_hash
+    }
+
+    pub fn started_at(&self) -> DateTime<Local> {
+        self.state.started_at
+    }
+
+    pub fn steps(&self) -> &[JournalStep] {
+        &self.state.steps
+    }
+
+    /// The step of a sink, if it had anything planned
+    pub fn step(&self, sink: &str) -> Option<&JournalStep> {
+        self.state.steps.iter().find(|step| step.sink == sink)
+    }
+
+    /// Steps not done yet
+    pub fn remaining(&self) -> impl Iterator<Item = &JournalStep> {
+        self.state.steps.iter().filter(|step| !step.completed)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.remaining().next().is_none()
+    }
+
+    /// Record that a sink's step is done, saving the journal
+    pub fn complete_step(&mut self, sink: &str) -> Result<(), Box<dyn Error>> {
+        let Some(step) = self.state.steps.iter_mut().find(|step| step.sink == sink) else {
+            return Ok(());
+        };
+        if !step.completed {
+            step.completed = true;
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Move the journal out of the way: into the reports directory as
+    /// `journal-<started>-<run-id>.json`, keeping as many journals as reports, or just
+    /// removed without one. Returns where it was archived.
+    pub fn archive(self, reports: Option<&ReportWriter>) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let archived = match reports {
+            Some(reports) => {
+                std::fs::create_dir_all(reports.dir())?;
+                let started = self.state.started_at.format("%Y%m%dT%H%M%S%.3f");
+                let name = format!("{}{}-{}.json", ARCHIVE_PREFIX, started, self.state.run_id);
+                let path = reports.dir().join(name);
+                write_atomic(&path, serde_json::to_string_pretty(&self.state)?)?;
+                info!(journal = %path.display(), run_id = %self.state.run_id, "Archived run journal");
+                prune_archive(reports.dir(), reports.keep_last())?;
+                Some(path)
+            }
+            None => None,
+        };
+        if self.path.exists() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(archived)
+    }
+
+    /// Persist the journal
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_atomic(&self.path, serde_json::to_string_pretty(&self.state)?)?;
+        debug!(journal_path = %self.path.display(), run_id = %self.state.run_id, "Saved run journal");
+        Ok(())
+    }
+}
+
+/// Delete all but the most recent `keep_last` archived journals in a directory
+fn prune_archive(dir: &Path, keep_last: usize) -> Result<(), Box<dyn Error>> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)?
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with(ARCHIVE_PREFIX) && name.ends_with(".json"))
+        .collect();
+    names.sort();
+    let excess = names.len().saturating_sub(keep_last);
+    for name in &names[..excess] {
+        std::fs::remove_file(dir.join(name))?;
+        debug!(journal = name, "Pruned old run journal");
+    }
+    Ok(())
+}
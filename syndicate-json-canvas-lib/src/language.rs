@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use crate::SyndicationFormat;
+
+/// Configuration for language detection and per-language routing
+#[derive(Debug, Clone)]
+pub struct LanguageOptions {
+    /// Detections below this confidence (0.0 - 1.0) yield no language
+    pub min_confidence: f64,
+    /// Language code (e.g. "en") to the names of the sinks that should receive it
+    pub routes: HashMap<String, Vec<String>>,
+    /// Sinks for items with no detected or no routed language (None means every sink)
+    pub default_route: Option<Vec<String>>,
+}
+
+impl Default for LanguageOptions {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.5,
+            routes: HashMap::new(),
+            default_route: None,
+        }
+    }
+}
+
+impl LanguageOptions {
+    /// Whether an item in `language` should be published to the sink named `sink_name`
+    pub fn routes_to(&self, language: Option<&str>, sink_name: &str) -> bool {
+        let route = language
+            .and_then(|lang| self.routes.get(lang))
+            .or(self.default_route.as_ref());
+
+        match route {
+            Some(sinks) => sinks.iter().any(|s| s == sink_name),
+            None => true,
+        }
+    }
+}
+
+/// Detect the language of a text as an ISO 639-1 code where known (ISO 639-3 otherwise)
+///
+/// Returns None when detection is unreliable or below `min_confidence`. Always returns
+/// None unless the `language-detection` feature is enabled.
+#[cfg(feature = "language-detection")]
+pub fn detect_language(text: &str, min_confidence: f64) -> Option<String> {
+    let info = whatlang::detect(text)?;
+    if !info.is_reliable() || info.confidence() < min_confidence {
+        return None;
+    }
+
+    let code = info.lang().code();
+    let short = match code {
+        "eng" => "en",
+        "deu" => "de",
+        "fra" => "fr",
+        "spa" => "es",
+        "ita" => "it",
+        "por" => "pt",
+        "nld" => "nl",
+        "rus" => "ru",
+        "pol" => "pl",
+        "swe" => "sv",
+        "dan" => "da",
+        "nob" => "nb",
+        "fin" => "fi",
+        "tur" => "tr",
+        "jpn" => "ja",
+        "kor" => "ko",
+        "cmn" => "zh",
+        "ara" => "ar",
+        "hin" => "hi",
+        other => other,
+    };
+    Some(short.to_string())
+}
+
+/// Detect the language of a text (disabled: the `language-detection` feature is off)
+#[cfg(not(feature = "language-detection"))]
+pub fn detect_language(_text: &str, _min_confidence: f64) -> Option<String> {
+    None
+}
+
+/// Predicate matching items whose detected language is `code`
+pub fn language_is(code: &str) -> impl Fn(&SyndicationFormat) -> bool + '_ {
+    move |item| item.language.as_deref() == Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_route_to_their_sinks_and_the_rest_to_the_default() {
+        let mut options = LanguageOptions::default();
+        options.routes.insert("de".to_string(), vec!["mastodon-de".to_string()]);
+        assert!(options.routes_to(Some("de"), "mastodon-de"));
+        assert!(!options.routes_to(Some("de"), "twitter"));
+        // Without a default route, everything else goes everywhere
+        assert!(options.routes_to(Some("en"), "twitter") && options.routes_to(None, "mastodon-de"));
+
+        options.default_route = Some(vec!["twitter".to_string()]);
+        assert!(options.routes_to(Some("en"), "twitter"));
+        assert!(!options.routes_to(None, "mastodon-de"));
+    }
+
+    #[cfg(feature = "language-detection")]
+    #[test]
+    fn detects_short_codes_and_drops_unreliable_guesses() {
+        let german = "Heute habe ich im Garten gearbeitet und danach ein langes Buch über die Geschichte gelesen.";
+        assert_eq!(detect_language(german, 0.5).as_deref(), Some("de"));
+        assert_eq!(detect_language("ok", 0.5), None);
+    }
+}
@@ -4,71 +4,569 @@
 //!
 //! - **Data types**: [`SyndicationFormat`] for representing content to syndicate
 //! - **Canvas processing**: [`to_syndication_format`] and [`default_process_node`] for
-//!   parsing and filtering JSON Canvas files
+//!   parsing and filtering JSON Canvas files, with edges pointing as drawn or along their
+//!   arrows ([`EdgeDirection`]), and each node's neighbors given to filters as
+//!   [`OutAdjacencies`] and [`InAdjacencies`]
+//! - **Node filters**: [`NodeFilter`]s such as [`is_text_node`], [`has_color`],
+//!   [`in_group`], [`is_leaf`] and [`min_degree`], combined with `and`, `or` and `not` or as
+//!   a [`Filter`], and syndicated by [`process_nodes_matching`]
+//! - **Node content**: [`content_of`] viewing Text, File, Link and Group nodes uniformly as
+//!   [`NodeContent`], and [`read_file_node`] reading what a File node publishes
+//! - **Node IDs**: [`cmp_node_ids`] ordering IDs the same way everywhere, [`ShortIds`] for
+//!   file names and [`resolve_node_id`] taking full or short IDs in commands
+//! - **Colors**: [`parse_color`] and [`ColorFilter`] for user-supplied node colors, or a
+//!   [`Selection`] of every node on a canvas that is public as a whole
+//! - **Edge weights**: [`EdgeWeights`] ordering neighbors by the color of the edges to them
+//! - **Sizes**: [`SizeFilter`] for telling developed notes from quick cards by [`NodeSize`],
+//!   and [`ItemSizeLimit`] for text too large to publish
 //! - **Sink trait**: [`SyndicationSink`] trait that sink implementations must implement
 //! - **Tracker**: [`SyndicationTracker`] for deduplication (tracking published nodes)
-//! - **Orchestration**: [`watch_and_process`] for file watching and publishing workflow
+//! - **Compatibility**: [`parse_canvas`] for tolerating canvas format additions, reported
+//!   in a [`CompatibilityReport`]
+//! - **Snapshots**: [`CanvasSnapshot`], the canvas read and hashed once per run so every
+//!   step sees the same version, which published items record as their provenance
+//! - **Archiving**: [`archived_node_ids`] for the "Archive" group convention
+//! - **History**: [`HistoryLog`], an append-only audit trail of publish events per node
+//! - **Simulation**: [`Simulation`], what a real run would change, computed in a sandbox
+//! - **Dry-run preview**: [`PlannedWrite`]s, each file a dry run would write compared with
+//!   the published one
+//! - **Reconciliation**: [`reconcile`] for finding drift between trackers and published output
+//! - **Legacy posts**: [`import_legacy_posts`] recording posts that predate the canvas, so
+//!   new posts link to them while they're never pruned or rewritten
+//! - **Pruning**: [`plan_prune`] listing what removing deleted or excluded nodes would
+//!   delete as [`DestructionPlan`]s, carried out by [`prune`] only while unchanged, or
+//!   replace by tombstones under a sink's [`RemovalPolicy`]
+//! - **Verification**: [`verify`] for published files whose frontmatter no longer parses
+//! - **Validation**: [`validate_canvas`] for structural problems with a canvas (dangling edges,
+//!   duplicate node IDs, empty groups, ...), reported as [`CanvasIssue`]s
+//! - **Exclusions**: [`ExclusionList`] for nodes that must never be syndicated
+//! - **Orchestration**: [`watch_and_process`] for file watching and publishing workflow, with
+//!   [`WatchOptions`] deciding which file changes start a run and [`WatchCommand`]s (e.g.
+//!   typed at [`run_console`]) steering a running watcher
+//! - **Embedding**: [`Syndicator`], the whole pipeline behind one type configured by [`Config`],
+//!   and [`Pipelines`] for hosting several canvases in one process
+//! - **Queue**: [`OutboundQueue`] decoupling detection from delivery, drained by [`drain_queue`]
+//! - **Backfill**: [`BackfillOptions`] for publishing a large backlog in chunks, resuming
+//!   with its [`BackfillProgress`] after an interruption
+//! - **Idempotency**: [`idempotency_key`] per item and sink, and [`SentKeys`] for sinks whose
+//!   protocol can't deduplicate retried requests
+//! - **Run journal**: [`RunJournal`], written before a run sends anything under its
+//!   deterministic [`run_id`], so a run that died partway can be resumed exactly
+//! - **State bundles**: [`StateRegistry`] of every state file, moved between machines as a
+//!   [`StateBundle`]
+//! - **Batching**: [`BatchPolicy`] for publishing new items together, held in a [`PendingBatch`]
+//! - **Removal guard**: [`RemovalGuard`] refusing runs after a canvas lost many published nodes at once
+//! - **Reports**: [`ProcessReport`] describing each run, persisted by [`ReportWriter`]
+//! - **Badges**: [`BadgeSpec`]s of shields.io [`Badge`]s counting a sink's posts, written
+//!   after each run
+//! - **Warnings**: [`Warning`]s about nodes, summarized at the end of each run and promoted
+//!   or silenced by a [`WarningPolicy`]
+//! - **Clock**: [`Clock`] so time-dependent behavior can be pinned with [`MockClock`]
+//! - **Duplicates**: [`find_duplicate_groups`] for catching copied nodes before publishing
+//! - **Language**: [`detect_language`] and per-language routing via [`LanguageOptions`]
+//! - **Categories**: [`CategoryMap`] mapping [`hashtags`] onto a site's categories
+//! - **Group settings**: [`ItemSettings`] that groups give the nodes inside them (tags,
+//!   category, sinks, file name template), found by [`group_settings`]
+//! - **Content warnings**: the `cw` setting or [`take_cw_tokens`] putting an item behind a
+//!   content warning, which each sink shows its own way
+//! - **Link enrichment**: [`LinkEnricher`] for fetching titles of linked pages
+//! - **Link checking**: [`LinkChecker`] requesting every link of the items about to be
+//!   published, warning about broken ones or holding them back per [`LinkCheckPolicy`]
+//! - **Network**: [`HttpClient`] for every outgoing request, wrapped in a [`PoliteClient`]
+//!   keeping to a [`NetworkPolicy`] of per-host and overall limits
+//! - **Markdown**: [`to_plain_text`] for sinks that can't render markdown, and
+//!   [`extract_footnotes`] for turning footnotes into structured [`Reference`]s
+//! - **Rendering**: [`MarkdownRenderer`] turning markdown into HTML the same way for every
+//!   sink, by default a [`PulldownRenderer`] configured by [`RenderOptions`]
+//! - **Date tokens**: [`expand_tokens`] turning `{{today}}`, `{{now}}` and `{{+7d}}` in node
+//!   text into dates as of publishing, formatted per [`TokenOptions`]
+//! - **Replacements**: [`Replacements`], ordered literal or regex [`ReplacementRule`]s
+//!   redacting or expanding text before publishing, reported as [`Redaction`]s
+//! - **Recency**: [`published_within`] routing only recently published items to a sink,
+//!   [`ResurfaceOptions`] for a sink posting a random old item again now and then, and a
+//!   sink's maximum item age (per [`frontmatter_date`] or the [`FirstSeenLog`]) skipping old items
+//! - **Text**: [`smart_truncate`], [`slugify`] and [`canonical_url`] for length-limited sinks,
+//!   and [`TextPolicy`] shaping titles, slugs and previews the same way everywhere
+//! - **Locales**: [`Locale`] writing dates, counts and reading times for people in their
+//!   language, leaving machine-read dates alone
+//! - **Accessibility**: [`check_accessibility`] finding images without alt text, bare URL
+//!   links and skipped heading levels, warned about or held back from public-facing sinks
+//!   per [`AccessibilityPolicy`]
+//! - **Vault**: [`Vault`] for resolving vault-relative paths of File nodes and embeds
+//! - **Sanitization**: [`sanitize_html`] for applying an [`HtmlPolicy`] to raw HTML in node text
+//! - **Files**: [`write_atomic`] for temp-file-and-rename writes of state and output files,
+//!   remembered so the watcher can tell them from edits ([`is_own_write`])
+//! - **Config checks**: [`ConfigCheck`] collecting every [`Finding`] in a configuration (type
+//!   errors, unknown keys with suggestions, dangling names) without running anything
+//! - **Example**: [`EXAMPLE_CANVAS`], a bundled canvas written out by [`write_example_canvas`]
+//! - **Building canvases**: [`CanvasBuilder`] for generating canvases in code, and
+//!   [`SyntheticCanvas`] for large ones of a known shape (benchmarks)
+//! - **Failure injection**: [`ChaosSink`] wrapping a sink to make its batches fail, hang or
+//!   misreport their items, reproducibly from a seed
 //!
-//! Sink implementations (JJ repository, Twitter) are in the `syndicate-json-canvas-sinks` crate.
+//! Sink implementations (JJ repository, directory, Twitter) are in the `syndicate-json-canvas-sinks` crate.
 
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 pub use jsoncanvas;
-use jsoncanvas::{JsonCanvas, node::GenericNodeInfo, NodeId, EdgeId};
+use jsoncanvas::{JsonCanvas, edge::{Edge, End}, node::GenericNodeInfo, NodeId, EdgeId};
 
 pub mod sink;
 pub mod tracker;
+pub mod node_ids;
+pub mod exclusions;
+pub mod archive;
+pub mod compat;
 pub mod orchestrator;
+pub mod syndicator;
+pub mod pipelines;
+pub mod report;
+pub mod badge;
+pub mod warnings;
+pub mod reconcile;
+pub mod prune;
+pub mod verify;
+pub mod history;
+pub mod simulate;
+pub mod preview;
+pub mod queue;
+pub mod backfill;
+pub mod idempotency;
+pub mod state;
+pub mod batch;
+pub mod cancel;
+pub mod control;
+pub mod processing;
+pub mod guard;
+pub mod clock;
+pub mod duplicates;
+pub mod language;
+pub mod category;
+pub mod enrich;
+pub mod link_check;
+pub mod network;
+pub mod markdown;
+pub mod render;
+pub mod sanitize;
+pub mod accessibility;
+pub mod text;
+pub mod tokens;
+pub mod recency;
+pub mod resurface;
+pub mod replace;
+pub mod vault;
+pub mod example;
+pub mod atomic;
+pub mod color;
+pub mod selection;
+pub mod emphasis;
+pub mod size;
+pub mod limits;
+pub mod content;
+pub mod watch;
+pub mod canvas_builder;
+pub mod synthetic;
+pub mod chaos;
+pub mod snapshot;
+pub mod config_check;
+pub mod seen;
+pub mod group_settings;
+pub mod content_warning;
+pub mod validate;
+pub mod journal;
+pub mod filter;
+pub mod locale;
 
 // Re-exports for convenient access
-pub use sink::{SinkError, SyndicationSink};
-pub use tracker::SyndicationTracker;
-pub use orchestrator::{validate_canvas_path, process_canvas, watch_and_process};
+pub use sink::{ItemChange, SinkError, SyndicationLinks, SyndicationSink};
+pub use tracker::{PublishState, SyndicationTracker};
+pub use node_ids::{NodeIdError, SHORT_ID_LEN, ShortIds, cmp_node_ids, resolve_node_id};
+pub use archive::{ARCHIVE_GROUP_LABEL, archived_node_ids, node_ids_in_group};
+pub use exclusions::ExclusionList;
+pub use compat::{CompatibilityReport, SkippedElement, parse_canvas};
+pub use orchestrator::{
+    LegacyImport, LinkBackfill, ProcessOptions, SinkTarget, backfill_syndication_links, drain_queue, import_legacy_posts,
+    validate_canvas_path, process_canvas, process_canvas_cancellable, process_snapshot, watch_and_process,
+};
+pub use syndicator::{Config, SyndicationError, Syndicator};
+pub use pipelines::Pipelines;
+pub use history::{DEFAULT_HISTORY_MAX_BYTES, HistoryEvent, HistoryEventKind, HistoryLog};
+pub use simulate::{ChangeKind, FileChange, Simulation, compare_trees, copy_dir_all, line_diff, read_tree};
+pub use preview::{DEFAULT_PREVIEW_DIFF_LINES, PlannedCommit, PlannedWrite, WriteKind, WriteSummary};
+pub use reconcile::{Discrepancies, ReconcileFix, reconcile};
+pub use prune::{DestructionPlan, PlanChanged, PlannedFile, RemovalPolicy, plan_prune, prune};
+pub use verify::{FileIssue, FileProblem, VerifyReport, verify};
+pub use validate::{
+    CanvasIssue, CanvasIssueKind, duplicate_node_ids, validate_canvas, validate_canvas_with, validate_snapshot,
+};
+pub use queue::{DeliveryOptions, OutboundQueue, QueueEntry};
+pub use backfill::{BackfillCounts, BackfillOptions, BackfillPlan, BackfillProgress, DEFAULT_BACKFILL_CHUNK_SIZE};
+pub use idempotency::{SentEntry, SentKeys, idempotency_key};
+pub use journal::{JournalStep, RunJournal, run_id};
+pub use filter::{
+    And, Filter, InGroup, MaxDegree, MinDegree, NodeFilter, Not, Or, default_node_filter, has_color, in_group,
+    is_isolated, is_leaf, is_root, is_text_node, max_degree, min_degree, process_nodes_matching,
+};
+pub use state::{
+    BUNDLE_FORMAT_VERSION, CANVAS_STATE_SCHEMA, ImportReport, ManifestEntry, StateBundle, StateError, StateFile,
+    StateRegistry,
+};
+pub use batch::{BatchPolicy, PendingBatch};
+pub use cancel::CancelToken;
+pub use control::{CONSOLE_HELP, PipelineStatus, WatchCommand, run_console};
+pub use processing::{
+    Backpressure, DEFAULT_PROCESSING_CAPACITY, ProcessingClosed, ProcessingOptions, ProcessingQueue, ProcessingReason,
+    ProcessingRequest, ProcessingSender,
+};
+pub use guard::{DEFAULT_MAX_VANISHED, MassRemoval, RemovalGuard, RemovalSnapshot};
+pub use duplicates::{DuplicateGroup, DuplicateOptions, DuplicatePolicy, find_duplicate_groups};
+pub use enrich::{LinkEnricher, LinkEnrichmentOptions, LinkMeta, parse_link_meta};
+pub use link_check::{
+    LinkCheckOptions, LinkCheckPolicy, LinkChecker, LinkFinding, LinkStatus, find_links, rewrite_links,
+};
+pub use network::{HttpClient, HttpError, HttpRequest, HttpResponse, NetworkPolicy, PoliteClient};
+#[cfg(feature = "http")]
+pub use network::ReqwestClient;
+pub use language::{LanguageOptions, detect_language, language_is};
+pub use category::{CategoryMap, hashtags};
+pub use group_settings::{ItemSettings, group_settings};
+pub use content_warning::{CW_TOKEN, take_cw_tokens};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use badge::{Badge, BadgeKind, BadgeSpec, DEFAULT_BADGE_COLOR, days_since_last_post};
+pub use report::{ProcessReport, ReportFormat, ReportWriter, SinkOutcome};
+pub use warnings::{Warning, WarningGroup, WarningPolicy, group_warnings};
+pub use markdown::{
+    FootnoteExtraction, LinkStyle, PlainTextOptions, Reference, extract_footnotes, to_plain_text,
+    to_plain_text_with,
+};
+pub use render::{MarkdownRenderer, PulldownRenderer, RenderOptions, to_html};
+pub use accessibility::{
+    AccessibilityIssue, AccessibilityOptions, AccessibilityPolicy, DEFAULT_MAX_BARE_URL_CHARS, check_accessibility,
+};
+pub use sanitize::{HtmlPolicy, SanitizeOutcome, sanitize_html};
+pub use text::{
+    ContinuationPolicy, MAX_SLUG_BYTES, MAX_TITLE_CHARS, TextPolicy, TitleCase, canonical_url, slugify, smart_truncate,
+};
+pub use locale::{Locale, LocaleError, reading_minutes};
+pub use tokens::{DEFAULT_DATE_FORMAT, DEFAULT_DATETIME_FORMAT, Expansion, TokenOptions, expand_tokens};
+pub use replace::{Redaction, ReplacementRule, Replacements, RuleScope, group_labels};
+pub use recency::{days_ago, first_published, frontmatter_date, older_than, published_within};
+pub use seen::FirstSeenLog;
+pub use resurface::{
+    DEFAULT_RESURFACE_COOLDOWN_DAYS, DEFAULT_RESURFACE_MIN_AGE_DAYS, DEFAULT_RESURFACE_PREFIX, ResurfaceLog,
+    ResurfaceOptions,
+};
+pub use vault::{Vault, VaultError};
+pub use atomic::{is_own_write, record_own_write, write_atomic};
+pub use canvas_builder::{CanvasBuildError, CanvasBuilder};
+pub use synthetic::SyntheticCanvas;
+pub use chaos::ChaosSink;
+pub use snapshot::CanvasSnapshot;
+pub use config_check::{ConfigCheck, Finding, FindingKind, suggest};
+pub use watch::{FilteredEvents, WatchOptions};
+pub use content::{
+    DEFAULT_MAX_FILE_BYTES, ExternalLink, FileNodeError, NodeContent, ResolveOptions, content_of, external_link_of,
+    frontmatter_value, read_file_node, strip_frontmatter, subpath_section,
+};
+pub use limits::{DEFAULT_MAX_ITEM_BYTES, ItemSizeLimit, OversizePolicy};
+pub use size::{DEFAULT_NOTE_ASPECT, DEFAULT_NOTE_MIN_AREA, DEFAULT_NOTE_MIN_WIDTH, NodeSize, SizeFilter};
+pub use selection::{Selection, SelectionError};
+pub use color::{ColorError, ColorFilter, color_key, color_name, normalize_hex, parse_color, same_color};
+pub use emphasis::{DEFAULT_EDGE_WEIGHT, EdgeEmphasis, EdgeWeights};
+pub use example::{EXAMPLE_CANVAS, EXAMPLE_EXCLUDED_NODE_ID, write_example_canvas};
 
 // Simplified SyndicationFormat without lifetimes
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyndicationFormat {
     pub id: NodeId,
     pub text: String,
     pub in_neighbor_ids: Vec<NodeId>,  // nodes that point TO this node
     pub out_neighbor_ids: Vec<NodeId>, // nodes that this node points TO
+    pub language: Option<String>,      // detected language code (e.g. "en"), if any
+    pub references: Vec<Reference>,    // footnote definitions, in source order
+    pub link_meta: Option<LinkMeta>,   // metadata of the first linked page, if enriched
+    pub category: Option<String>,      // site category mapped from hashtags, if configured
+    pub idempotency_key: Option<String>, // key of this version's delivery, set per sink
+    /// Heaviest edge between this item and each neighbor, if edge weights are configured
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub neighbor_emphasis: HashMap<NodeId, EdgeEmphasis>,
+    /// Held back from public-facing sinks for accessibility issues, per the policy
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub accessibility_blocked: bool,
+    /// Held back from public-facing sinks for broken links, per the link check policy
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub broken_links_blocked: bool,
+    /// Hash of the text before date tokens were expanded, if expanding changed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_hash: Option<String>,
+    /// When the node was first published to any sink, if it was and the time is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<chrono::DateTime<chrono::Local>>,
+    /// What the item's age counts from: the `date` in its frontmatter, else when the node was
+    /// first seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dated_at: Option<chrono::DateTime<chrono::Local>>,
+    /// Where the node already lives, if elsewhere (see [`external_link_of`]); such items get
+    /// no post of their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    /// Neighbors that live elsewhere, to link to instead of their posts
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub external_neighbors: HashMap<NodeId, ExternalLink>,
+    /// Hash of the canvas version the item was read from (see [`CanvasSnapshot`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canvas_hash: Option<String>,
+    /// File name of the canvas the item was read from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canvas_name: Option<String>,
+    /// Settings from the node's frontmatter and the groups around it
+    #[serde(default, skip_serializing_if = "ItemSettings::is_empty")]
+    pub settings: ItemSettings,
+    /// Topic of the content warning the item is published behind, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_warning: Option<String>,
+    /// URL a link post points at, for items made from Link nodes (see [`link_post_of`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
 }
 
-// Simplified adjacency types - just store IDs
-#[derive(Clone, Debug)]
-pub struct OutAdjacencies(pub Vec<(NodeId, EdgeId)>);
+impl SyndicationFormat {
+    /// Hash telling edits of the node apart, unaffected by the dates its tokens expand to
+    pub fn content_hash(&self) -> String {
+        self.source_hash.clone().unwrap_or_else(|| duplicates::content_hash(&self.text))
+    }
 
+    /// Whether the item is held back from public-facing sinks, for accessibility issues or
+    /// broken links
+    pub fn blocked_from_public(&self) -> bool {
+        self.accessibility_blocked || self.broken_links_blocked
+    }
+}
+
+/// A neighbor of a node, and the edge joining them
 #[derive(Clone, Debug)]
-pub struct InAdjacencies(pub Vec<(NodeId, EdgeId)>);
+pub struct Adjacency<'a> {
+    /// The node at the other end of the edge
+    pub neighbor: &'a jsoncanvas::Node,
+    pub neighbor_id: NodeId,
+    pub edge: &'a Edge,
+}
+
+/// A node's out-neighbors, each with the edge pointing at it, ordered by edge ID
+#[derive(Clone, Debug, Default)]
+pub struct OutAdjacencies<'a>(Vec<Adjacency<'a>>);
+
+impl<'a> OutAdjacencies<'a> {
+    pub fn new(adjacencies: Vec<Adjacency<'a>>) -> Self {
+        Self(adjacencies)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Adjacency<'a>> {
+        self.0.iter()
+    }
+}
+
+impl<'a> IntoIterator for OutAdjacencies<'a> {
+    type Item = Adjacency<'a>;
+    type IntoIter = std::vec::IntoIter<Adjacency<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'b, 'a> IntoIterator for &'b OutAdjacencies<'a> {
+    type Item = &'b Adjacency<'a>;
+    type IntoIter = std::slice::Iter<'b, Adjacency<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// A node's in-neighbors, each with the edge pointing from it, ordered by edge ID
+#[derive(Clone, Debug, Default)]
+pub struct InAdjacencies<'a>(Vec<Adjacency<'a>>);
+
+impl<'a> InAdjacencies<'a> {
+    pub fn new(adjacencies: Vec<Adjacency<'a>>) -> Self {
+        Self(adjacencies)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Adjacency<'a>> {
+        self.0.iter()
+    }
+}
+
+impl<'a> IntoIterator for InAdjacencies<'a> {
+    type Item = Adjacency<'a>;
+    type IntoIter = std::vec::IntoIter<Adjacency<'a>>;
 
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'b, 'a> IntoIterator for &'b InAdjacencies<'a> {
+    type Item = &'b Adjacency<'a>;
+    type IntoIter = std::slice::Iter<'b, Adjacency<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// An edge that doesn't connect two nodes of its canvas
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CanvasGraphError {
+    /// The edge ends at a node that isn't on the canvas, e.g. one deleted while the canvas
+    /// wasn't open in Obsidian
+    #[error("edge `{edge_id}` points at node `{missing_node}`, which isn't on the canvas")]
+    DanglingEdge { edge_id: EdgeId, missing_node: NodeId },
+}
+
+impl CanvasGraphError {
+    /// The edge at fault
+    pub fn edge_id(&self) -> &EdgeId {
+        match self {
+            CanvasGraphError::DanglingEdge { edge_id, .. } => edge_id,
+        }
+    }
+}
+
+/// What the pipeline does with edges ending at nodes that aren't on the canvas
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DanglingEdges {
+    /// Leave them out, with a `dangling-edge` warning each
+    #[default]
+    Skip,
+    /// Fail the run with [`CanvasGraphError::DanglingEdge`] until the canvas is fixed
+    Fail,
+}
+
+/// Which way an edge points, for telling a node's in-neighbors from its out-neighbors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgeDirection {
+    /// From `fromNode` to `toNode`, as drawn, whatever its arrows
+    #[default]
+    Drawn,
+    /// Along its arrows: an arrow on the `from` end only reverses it, arrows on both ends
+    /// point both ways, and an edge with no arrows points as drawn
+    Arrows,
+}
+
+impl EdgeDirection {
+    /// The `(from, to)` pairs of nodes an edge links, one per way it points
+    pub fn links(self, edge: &Edge) -> Vec<(&NodeId, &NodeId)> {
+        let (from, to) = (edge.from_node(), edge.to_node());
+        if self == EdgeDirection::Drawn {
+            return vec![(from, to)];
+        }
+        // Per the JSON Canvas spec, `fromEnd` defaults to no arrow and `toEnd` to an arrow
+        let from_arrow = matches!(edge.from_end(), Some(End::Arrow));
+        let to_arrow = !matches!(edge.to_end(), Some(End::None));
+        match (from_arrow, to_arrow) {
+            (true, false) => vec![(to, from)],
+            (true, true) if from != to => vec![(from, to), (to, from)],
+            _ => vec![(from, to)],
+        }
+    }
+}
+
+/// Turn the canvas's nodes into items, passing each node's neighbors to `process_node`
+/// (the default filter if None)
+///
+/// Edges point as drawn; see [`to_syndication_format_with`] to follow their arrows. Fails on
+/// the first edge (by ID) that ends at a node not on the canvas; see
+/// [`to_syndication_format_lenient`] to leave those out instead.
 pub fn to_syndication_format<F>(
-    canvas: JsonCanvas,
+    canvas: &JsonCanvas,
+    process_node: Option<F>,
+) -> Result<HashMap<NodeId, SyndicationFormat>, CanvasGraphError>
+where
+    F: Fn(&jsoncanvas::Node, &OutAdjacencies, &InAdjacencies) -> Option<SyndicationFormat>,
+{
+    to_syndication_format_with(canvas, process_node, EdgeDirection::default())
+}
+
+/// [`to_syndication_format`], reading which way edges point per `direction`
+pub fn to_syndication_format_with<F>(
+    canvas: &JsonCanvas,
     process_node: Option<F>,
-) -> HashMap<NodeId, SyndicationFormat>
+    direction: EdgeDirection,
+) -> Result<HashMap<NodeId, SyndicationFormat>, CanvasGraphError>
+where
+    F: Fn(&jsoncanvas::Node, &OutAdjacencies, &InAdjacencies) -> Option<SyndicationFormat>,
+{
+    let (items, mut dangling) = to_syndication_format_lenient(canvas, process_node, direction);
+    if dangling.is_empty() { Ok(items) } else { Err(dangling.remove(0)) }
+}
+
+/// [`to_syndication_format_with`], leaving out edges that end at nodes not on the canvas and
+/// returning them, ordered by edge ID, next to the items
+pub fn to_syndication_format_lenient<F>(
+    canvas: &JsonCanvas,
+    process_node: Option<F>,
+    direction: EdgeDirection,
+) -> (HashMap<NodeId, SyndicationFormat>, Vec<CanvasGraphError>)
 where
     F: Fn(&jsoncanvas::Node, &OutAdjacencies, &InAdjacencies) -> Option<SyndicationFormat>,
 {
     let nodes = canvas.get_nodes();
     let edges = canvas.get_edges();
 
-    type AdjacencyMap = HashMap<NodeId, Vec<(NodeId, EdgeId)>>;
+    type AdjacencyMap<'a> = HashMap<NodeId, Vec<Adjacency<'a>>>;
 
     let mut out_adjacency_map = AdjacencyMap::new();
     let mut in_adjacency_map = AdjacencyMap::new();
+    let mut dangling = Vec::new();
 
     for (edge_id, edge) in edges.iter() {
-        out_adjacency_map
-            .entry(edge.from_node().clone())
-            .or_insert_with(Vec::new)
-            .push((edge.to_node().clone(), edge_id.clone()));
+        let missing = [edge.from_node(), edge.to_node()].into_iter().find(|node_id| !nodes.contains_key(*node_id));
+        if let Some(missing_node) = missing {
+            let missing_node = missing_node.clone();
+            dangling.push(CanvasGraphError::DanglingEdge { edge_id: edge_id.clone(), missing_node });
+            continue;
+        }
+
+        for (from, to) in direction.links(edge) {
+            out_adjacency_map
+                .entry(from.clone())
+                .or_default()
+                .push(Adjacency { neighbor: &nodes[to], neighbor_id: to.clone(), edge });
 
-        in_adjacency_map
-            .entry(edge.to_node().clone())
-            .or_insert_with(Vec::new)
-            .push((edge.from_node().clone(), edge_id.clone()));
+            in_adjacency_map
+                .entry(to.clone())
+                .or_default()
+                .push(Adjacency { neighbor: &nodes[from], neighbor_id: from.clone(), edge });
+        }
     }
 
-    nodes
+    // Edges come out of a HashMap; order neighbors by edge ID so output is reproducible
+    for adjacencies in out_adjacency_map.values_mut().chain(in_adjacency_map.values_mut()) {
+        adjacencies.sort_by(|a, b| a.edge.id().as_str().cmp(b.edge.id().as_str()));
+    }
+    dangling.sort_by(|a, b| a.edge_id().as_str().cmp(b.edge_id().as_str()));
+
+    let items = nodes
         .iter()
         .filter_map(|(node_id, node)| {
             let out_edges = out_adjacency_map
@@ -92,52 +590,201 @@ where
 
             Some((item.id.clone(), item))
         })
-        .collect()
+        .collect();
+    (items, dangling)
 }
 
 /// Default node processor that filters for red text nodes and converts them to SyndicationFormat
 /// Returns Some(SyndicationFormat) if the node should be syndicated, None otherwise
+///
+/// The filter is [`default_node_filter`].
 pub fn default_process_node(
     node: &jsoncanvas::Node,
-    _out_adjacencies: &OutAdjacencies,
-    _in_adjacencies: &InAdjacencies
+    out_adjacencies: &OutAdjacencies,
+    in_adjacencies: &InAdjacencies
 ) -> Option<SyndicationFormat> {
-    use jsoncanvas::color::{Color, PresetColor};
+    process_node_with_filter(node, out_adjacencies, in_adjacencies, &default_node_filter())
+}
 
-    // Filter: Only process Text nodes
-    let text_node = match node {
-        jsoncanvas::Node::Text(text_node) => text_node,
-        _ => return None,
-    };
+/// Like [`default_process_node`], but for text nodes of any color accepted by `colors`
+pub fn process_node_with_colors(
+    node: &jsoncanvas::Node,
+    out_adjacencies: &OutAdjacencies,
+    in_adjacencies: &InAdjacencies,
+    colors: &ColorFilter,
+) -> Option<SyndicationFormat> {
+    process_node_with_filter(node, out_adjacencies, in_adjacencies, &is_text_node.and(colors))
+}
+
+/// Like [`default_process_node`], but for the nodes `filter` passes
+///
+/// Items are made from the node's text, so nodes without any (non-Text nodes, or empty
+/// ones) are left out whatever the filter says.
+pub fn process_node_with_filter(
+    node: &jsoncanvas::Node,
+    out_adjacencies: &OutAdjacencies,
+    in_adjacencies: &InAdjacencies,
+    filter: &(impl NodeFilter + ?Sized),
+) -> Option<SyndicationFormat> {
+    // Filter: Only nodes with text
+    let resolve_options = ResolveOptions::default();
+    let content = content_of(node, &resolve_options);
+    let text = content.as_text()?;
 
     // Filter: Skip empty text
-    if text_node.text().is_empty() {
+    if text.is_empty() {
         return None;
     }
 
-    // Filter: Only red colored nodes
-    match node.color() {
-        Some(color) if *color == Color::Preset(PresetColor::Red) => {},
-        _ => return None,
+    // Filter: Only nodes the filter passes
+    if !filter.matches(node, out_adjacencies, in_adjacencies) {
+        return None;
     }
 
     // Map: Convert to SyndicationFormat
-    let in_neighbor_ids = _in_adjacencies.0.iter()
-        .map(|(node_id, _)| node_id.clone())
+    Some(item_of(node, text.to_string(), out_adjacencies, in_adjacencies))
+}
+
+/// Text of link posts unless configured otherwise; `{url}` is replaced with the link's URL
+pub const DEFAULT_LINK_POST_TEMPLATE: &str = "🔗 {url}";
+
+/// Like [`default_process_node`], but red Link nodes also become link posts, with the text
+/// [`DEFAULT_LINK_POST_TEMPLATE`] gives
+pub fn process_node_allow_link_nodes(
+    node: &jsoncanvas::Node,
+    out_adjacencies: &OutAdjacencies,
+    in_adjacencies: &InAdjacencies
+) -> Option<SyndicationFormat> {
+    if !ColorFilter::default().matches(node.color().as_ref()) {
+        return None;
+    }
+    link_post_of(node, out_adjacencies, in_adjacencies, DEFAULT_LINK_POST_TEMPLATE)
+        .or_else(|| default_process_node(node, out_adjacencies, in_adjacencies))
+}
+
+/// The link post of a Link node, whatever its color: its text is `template` with `{url}`
+/// replaced, and [`SyndicationFormat::url`] is the URL
+///
+/// None for other nodes.
+pub fn link_post_of(
+    node: &jsoncanvas::Node,
+    out_adjacencies: &OutAdjacencies,
+    in_adjacencies: &InAdjacencies,
+    template: &str,
+) -> Option<SyndicationFormat> {
+    let jsoncanvas::Node::Link(link_node) = node else {
+        return None;
+    };
+    let url = link_node.url().as_str();
+    let mut item = item_of(node, template.replace("{url}", url), out_adjacencies, in_adjacencies);
+    item.url = Some(url.to_string());
+    Some(item)
+}
+
+/// A node's item with the given text and its neighbors, before any enrichment
+pub(crate) fn item_of(
+    node: &jsoncanvas::Node,
+    text: String,
+    out_adjacencies: &OutAdjacencies,
+    in_adjacencies: &InAdjacencies,
+) -> SyndicationFormat {
+    let in_neighbor_ids = in_adjacencies.iter()
+        .map(|adjacency| adjacency.neighbor_id.clone())
         .collect();
 
-    let out_neighbor_ids = _out_adjacencies.0.iter()
-        .map(|(node_id, _)| node_id.clone())
+    let out_neighbor_ids = out_adjacencies.iter()
+        .map(|adjacency| adjacency.neighbor_id.clone())
         .collect();
 
-    Some(SyndicationFormat {
-        id: text_node.id().clone(),
-        text: text_node.text().to_string(),
+    SyndicationFormat {
+        id: node.id().clone(),
+        text,
         in_neighbor_ids,
         out_neighbor_ids,
-    })
+        language: None,
+        references: Vec::new(),
+        link_meta: None,
+        category: None,
+        idempotency_key: None,
+        neighbor_emphasis: HashMap::new(),
+        accessibility_blocked: false,
+        broken_links_blocked: false,
+        source_hash: None,
+        published_at: None,
+        dated_at: None,
+        external_url: None,
+        external_neighbors: HashMap::new(),
+        canvas_hash: None,
+        canvas_name: None,
+        settings: ItemSettings::default(),
+        content_warning: None,
+        url: None,
+    }
 }
 
+#[cfg(test)]
 mod tests {
-    // TODO: add a test for cyclic nodes
+    use super::*;
+    use jsoncanvas::color::PresetColor;
+
+    fn ids(ids: &[NodeId]) -> Vec<&str> {
+        ids.iter().map(NodeId::as_str).collect()
+    }
+
+    fn items(canvas: CanvasBuilder) -> HashMap<String, SyndicationFormat> {
+        let canvas = canvas.build().unwrap();
+        let items = to_syndication_format(&canvas, Some(default_process_node)).unwrap();
+        items.into_iter().map(|(node_id, item)| (node_id.to_string(), item)).collect()
+    }
+
+    #[test]
+    fn neighbors_follow_incoming_and_outgoing_edges() {
+        let items = items(
+            CanvasBuilder::new()
+                .text_node("a", "A").color(PresetColor::Red)
+                .text_node("b", "B").color(PresetColor::Red)
+                .text_node("c", "C").color(PresetColor::Red)
+                .text_node("lone", "Lone").color(PresetColor::Red)
+                .edge("a", "b")
+                .edge("b", "c"),
+        );
+        assert_eq!((ids(&items["a"].in_neighbor_ids), ids(&items["a"].out_neighbor_ids)), (vec![], vec!["b"]));
+        assert_eq!((ids(&items["b"].in_neighbor_ids), ids(&items["b"].out_neighbor_ids)), (vec!["a"], vec!["c"]));
+        assert_eq!((ids(&items["c"].in_neighbor_ids), ids(&items["c"].out_neighbor_ids)), (vec!["b"], vec![]));
+        assert!(items["lone"].in_neighbor_ids.is_empty() && items["lone"].out_neighbor_ids.is_empty());
+    }
+
+    #[test]
+    fn default_mapper_maps_a_text_node_and_its_edges() {
+        let items = items(
+            CanvasBuilder::new()
+                .text_node("hub", "The hub note.").color(PresetColor::Red)
+                .text_node("source", "Source").color(PresetColor::Red)
+                .text_node("first", "First").color(PresetColor::Red)
+                .text_node("second", "Second").color(PresetColor::Red)
+                .edge("source", "hub")
+                .edge("hub", "first")
+                .edge("hub", "second"),
+        );
+        let hub = &items["hub"];
+        assert_eq!(hub.id.as_str(), "hub");
+        assert_eq!(hub.text, "The hub note.");
+        assert_eq!(ids(&hub.out_neighbor_ids), ["first", "second"]);
+        assert_eq!(ids(&hub.in_neighbor_ids), ["source"]);
+        assert!(hub.references.is_empty() && hub.url.is_none() && hub.canvas_name.is_none());
+    }
+
+    #[test]
+    fn cyclic_nodes_are_each_other_neighbors() {
+        let items = items(
+            CanvasBuilder::new()
+                .text_node("a", "A").color(PresetColor::Red)
+                .text_node("b", "B").color(PresetColor::Red)
+                .edge("a", "b")
+                .edge("b", "a"),
+        );
+        assert_eq!(items.len(), 2);
+        assert_eq!((ids(&items["a"].in_neighbor_ids), ids(&items["a"].out_neighbor_ids)), (vec!["b"], vec!["b"]));
+        assert_eq!((ids(&items["b"].in_neighbor_ids), ids(&items["b"].out_neighbor_ids)), (vec!["a"], vec!["a"]));
+    }
 }
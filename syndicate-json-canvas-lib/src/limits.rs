@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+/// Largest item body published by default, in bytes
+pub const DEFAULT_MAX_ITEM_BYTES: usize = 256 * 1024;
+
+/// Appended to a body cut down to the size limit
+pub const TRUNCATION_MARKER: &str = "\n\n…";
+
+/// What happens to an item whose text is over the size limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizePolicy {
+    /// Don't publish the item
+    #[default]
+    Skip,
+    /// Publish the start of the text, cut at a paragraph or word boundary
+    Truncate,
+}
+
+/// Limit on the size of an item's text, checked right after the canvas is read
+///
+/// A node holding a pasted article would otherwise be carried through every later step
+/// (footnotes, duplicates, language detection) and into every sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemSizeLimit {
+    /// Largest text in bytes
+    pub max_bytes: usize,
+    pub policy: OversizePolicy,
+}
+
+impl Default for ItemSizeLimit {
+    fn default() -> Self {
+        Self { max_bytes: DEFAULT_MAX_ITEM_BYTES, policy: OversizePolicy::Skip }
+    }
+}
+
+impl ItemSizeLimit {
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: OversizePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Whether a text is over the limit
+    pub fn exceeds(&self, text: &str) -> bool {
+        text.len() > self.max_bytes
+    }
+
+    /// Cut a text down to the limit in place, ending in [`TRUNCATION_MARKER`]
+    ///
+    /// Cuts at the last paragraph break in the second half of the budget, otherwise at the
+    /// last whitespace. Returns whether the text was cut.
+    pub fn truncate(&self, text: &mut String) -> bool {
+        if !self.exceeds(text) {
+            return false;
+        }
+        let budget = self.max_bytes.saturating_sub(TRUNCATION_MARKER.len());
+        let prefix = head(text, budget);
+        let cut = prefix
+            .rfind("\n\n")
+            .filter(|&end| end >= budget / 2)
+            .or_else(|| prefix.rfind(char::is_whitespace))
+            .filter(|&end| end > 0)
+            .unwrap_or(prefix.len());
+        text.truncate(cut);
+        text.truncate(text.trim_end().len());
+        text.push_str(TRUNCATION_MARKER);
+        text.shrink_to_fit();
+        true
+    }
+}
+
+/// The longest start of `text` of at most `max_bytes` bytes that ends on a character boundary
+pub fn head(text: &str, max_bytes: usize) -> &str {
+    &text[..text.floor_char_boundary(max_bytes)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncation_cuts_at_a_boundary_within_the_limit() {
+        let limit = ItemSizeLimit::default().with_max_bytes(40).with_policy(OversizePolicy::Truncate);
+        let mut short = "Fits as it is".to_string();
+        assert!(!limit.truncate(&mut short));
+        assert_eq!(short, "Fits as it is");
+
+        let mut paragraphs = "First paragraph here.\n\nSecond one runs well past the limit".to_string();
+        assert!(limit.truncate(&mut paragraphs));
+        assert_eq!(paragraphs, format!("First paragraph here.{}", TRUNCATION_MARKER));
+
+        let mut words = "one two three four five six seven eight nine ten".to_string();
+        assert!(limit.truncate(&mut words));
+        assert_eq!(words, format!("one two three four five six seven{}", TRUNCATION_MARKER));
+
+        // Never inside a character, even with no whitespace to cut at
+        let mut wide = "é".repeat(30);
+        assert!(limit.truncate(&mut wide));
+        assert!(wide.len() <= 40 && wide.ends_with(TRUNCATION_MARKER), "{}", wide);
+        assert_eq!(head("aé", 2), "a");
+    }
+
+    #[test]
+    fn multi_megabyte_text_is_cut_to_the_limit() {
+        let mut text = "A long pasted article. ".repeat(200_000);
+        let limit = ItemSizeLimit::default();
+        assert!(text.len() > 4 << 20 && limit.exceeds(&text));
+        assert!(limit.truncate(&mut text));
+        assert!(text.len() <= DEFAULT_MAX_ITEM_BYTES && text.ends_with(TRUNCATION_MARKER));
+        assert!(text.capacity() <= DEFAULT_MAX_ITEM_BYTES, "the cut text kept its old buffer");
+        assert!(!limit.exceeds(&text));
+    }
+}
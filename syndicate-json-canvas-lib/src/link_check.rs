@@ -0,0 +1,295 @@
+//! Checking outbound links before publishing
+//!
+//! [`LinkChecker`] requests every external URL of the items about to be published and
+//! classifies the answer, so a link that 404s is caught before anyone follows it.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::atomic::write_atomic;
+use crate::markdown::url_spans;
+use crate::network::{HttpClient, HttpError, HttpRequest, HttpResponse};
+use crate::tracker::canvas_state_path;
+
+/// Suffix of the file the link check cache is kept in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-link-checks.json";
+
+/// Redirects followed before a link counts as broken
+const MAX_REDIRECTS: usize = 5;
+
+/// Bytes read of a page fetched with GET when HEAD wasn't answered
+const GET_MAX_BYTES: u64 = 1024;
+
+/// What requesting a link came back with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LinkStatus {
+    Ok,
+    /// Answered after following redirects to `to`; permanent if every one was a 301 or 308
+    Redirect { to: String, permanent: bool },
+    /// A 4xx, at the link or where it redirects to
+    ClientError { code: u16 },
+    /// A 5xx, at the link or where it redirects to
+    ServerError { code: u16 },
+    /// No answer within the timeout, or no connection at all
+    Timeout,
+    /// More than [`MAX_REDIRECTS`] redirects, usually a loop
+    TooManyRedirects,
+}
+
+impl LinkStatus {
+    /// Whether the link doesn't lead anywhere readers can see
+    pub fn is_broken(&self) -> bool {
+        !matches!(self, LinkStatus::Ok | LinkStatus::Redirect { .. })
+    }
+
+    /// Whether the result is worth caching; server errors and timeouts often pass quickly
+    fn is_lasting(&self) -> bool {
+        !matches!(self, LinkStatus::ServerError { .. } | LinkStatus::Timeout)
+    }
+}
+
+impl fmt::Display for LinkStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkStatus::Ok => write!(f, "ok"),
+            LinkStatus::Redirect { to, permanent: true } => write!(f, "moved permanently to `{}`", to),
+            LinkStatus::Redirect { to, permanent: false } => write!(f, "redirects to `{}`", to),
+            LinkStatus::ClientError { code } => write!(f, "client error {}", code),
+            LinkStatus::ServerError { code } => write!(f, "server error {}", code),
+            LinkStatus::Timeout => write!(f, "timed out"),
+            LinkStatus::TooManyRedirects => write!(f, "too many redirects"),
+        }
+    }
+}
+
+/// What happens to an item with a broken link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkCheckPolicy {
+    /// Publish it everywhere, with a warning per broken link
+    #[default]
+    Warn,
+    /// Also hold it back from public-facing sinks until its links work
+    Block,
+}
+
+/// Options for checking links before publishing
+#[derive(Debug, Clone)]
+pub struct LinkCheckOptions {
+    /// Per-request timeout
+    pub timeout: Duration,
+    /// How long a result is trusted before the link is requested again
+    pub cache_ttl: Duration,
+    /// Whether links that moved permanently are rewritten to where they moved
+    pub rewrite_redirects: bool,
+    pub policy: LinkCheckPolicy,
+    /// Client links are requested with, e.g. a [`PoliteClient`](crate::network::PoliteClient)
+    /// around [`ReqwestClient::without_redirects`](crate::network::ReqwestClient::without_redirects);
+    /// one that follows redirects itself hides them. None uses such a polite client with the
+    /// `link-check` feature and only reads the cache without it
+    pub http: Option<Arc<dyn HttpClient>>,
+}
+
+impl Default for LinkCheckOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            cache_ttl: Duration::from_secs(24 * 60 * 60),
+            rewrite_redirects: false,
+            policy: LinkCheckPolicy::Warn,
+            http: None,
+        }
+    }
+}
+
+impl LinkCheckOptions {
+    pub fn with_policy(mut self, policy: LinkCheckPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_rewrite_redirects(mut self, rewrite: bool) -> Self {
+        self.rewrite_redirects = rewrite;
+        self
+    }
+
+    pub fn with_http_client(mut self, http: Arc<dyn HttpClient>) -> Self {
+        self.http = Some(http);
+        self
+    }
+}
+
+/// A link that wasn't plainly ok, for the run report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkFinding {
+    pub node_id: String,
+    pub url: String,
+    pub status: LinkStatus,
+    /// Whether the link was rewritten to where it moved
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub rewritten: bool,
+}
+
+/// A cached result and when it was found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedCheck {
+    status: LinkStatus,
+    checked_at: DateTime<Local>,
+}
+
+/// Checks links and caches the results
+///
+/// Results are cached on disk next to the canvas keyed by URL, for
+/// [`cache_ttl`](LinkCheckOptions::cache_ttl), so repeated runs don't request the same links.
+/// Server errors and timeouts aren't cached.
+pub struct LinkChecker {
+    /// Path to the JSON cache file
+    cache_path: PathBuf,
+    /// Cached results by URL
+    cache: HashMap<String, CachedCheck>,
+    options: LinkCheckOptions,
+    client: Option<Arc<dyn HttpClient>>,
+    /// Whether the cache changed since it was loaded
+    dirty: bool,
+}
+
+impl LinkChecker {
+    /// Load the link check cache for a canvas, dropping results that expired by `now`
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-link-checks.json`
+    pub fn for_canvas(
+        canvas_path: &Path,
+        options: LinkCheckOptions,
+        now: DateTime<Local>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let cache_path = canvas_state_path(canvas_path, STATE_FILE)?;
+        let mut cache: HashMap<String, CachedCheck> = if cache_path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&cache_path)?)?
+        } else {
+            HashMap::new()
+        };
+        let loaded = cache.len();
+        cache.retain(|_, check| is_fresh(check, now, options.cache_ttl));
+        let dirty = cache.len() != loaded;
+
+        let client = options.http.clone().or_else(default_client);
+        Ok(Self { cache_path, cache, options, client, dirty })
+    }
+
+    /// Status of a link, from the cache or the network; None if it isn't cached and there's
+    /// no client to request it with
+    pub fn check(&mut self, url: &str, now: DateTime<Local>) -> Option<LinkStatus> {
+        if let Some(check) = self.cache.get(url).filter(|check| is_fresh(check, now, self.options.cache_ttl)) {
+            debug!(url = %url, "Link check cache hit");
+            return Some(check.status.clone());
+        }
+
+        let status = probe(self.client.as_deref()?, url, self.options.timeout);
+        debug!(url = %url, status = %status, "Checked link");
+        if status.is_lasting() {
+            self.cache.insert(url.to_string(), CachedCheck { status: status.clone(), checked_at: now });
+            self.dirty = true;
+        }
+        Some(status)
+    }
+
+    /// Persist the cache if anything changed
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        if self.dirty {
+            write_atomic(&self.cache_path, serde_json::to_string_pretty(&self.cache)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_fresh(check: &CachedCheck, now: DateTime<Local>, ttl: Duration) -> bool {
+    (now - check.checked_at).to_std().is_ok_and(|age| age < ttl)
+}
+
+/// Request `url`, following redirects, and classify where it ends up
+fn probe(client: &dyn HttpClient, url: &str, timeout: Duration) -> LinkStatus {
+    let mut at = url.to_string();
+    let mut permanent = true;
+    for _ in 0..=MAX_REDIRECTS {
+        let response = match head_or_get(client, &at, timeout) {
+            Ok(response) => response,
+            Err(e) => {
+                debug!(url = %at, error = %e, "Link request failed");
+                return LinkStatus::Timeout;
+            }
+        };
+        let next = response
+            .header("location")
+            .filter(|_| (300..400).contains(&response.status))
+            .and_then(|location| url::Url::parse(&at).ok()?.join(location).ok());
+        let Some(next) = next else {
+            return match response.status {
+                400..=499 => LinkStatus::ClientError { code: response.status },
+                500..=599 => LinkStatus::ServerError { code: response.status },
+                _ if at == url => LinkStatus::Ok,
+                _ => LinkStatus::Redirect { to: at, permanent },
+            };
+        };
+        permanent &= matches!(response.status, 301 | 308);
+        at = next.to_string();
+    }
+    LinkStatus::TooManyRedirects
+}
+
+/// HEAD, then GET for servers that answer HEAD with an error
+fn head_or_get(client: &dyn HttpClient, url: &str, timeout: Duration) -> Result<HttpResponse, HttpError> {
+    let response = client.send(&HttpRequest::head(url).timeout(timeout))?;
+    if response.status < 400 {
+        return Ok(response);
+    }
+    client.send(&HttpRequest::get(url).timeout(timeout).max_bytes(GET_MAX_BYTES))
+}
+
+/// External URLs in `text`, each once, in order
+pub fn find_links(text: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    for span in url_spans(text) {
+        if !links.iter().any(|link| *link == text[span.clone()]) {
+            links.push(text[span].to_string());
+        }
+    }
+    links
+}
+
+/// `text` with each URL that's a key of `moved` replaced by its value
+pub fn rewrite_links(text: &str, moved: &HashMap<String, String>) -> String {
+    let mut rewritten = String::with_capacity(text.len());
+    let mut copied = 0;
+    for span in url_spans(text) {
+        if let Some(to) = moved.get(&text[span.clone()]) {
+            rewritten.push_str(&text[copied..span.start]);
+            rewritten.push_str(to);
+            copied = span.end;
+        }
+    }
+    rewritten.push_str(&text[copied..]);
+    rewritten
+}
+
+#[cfg(feature = "link-check")]
+fn default_client() -> Option<Arc<dyn HttpClient>> {
+    use crate::network::{NetworkPolicy, PoliteClient, ReqwestClient};
+
+    Some(Arc::new(PoliteClient::new(ReqwestClient::without_redirects(), NetworkPolicy::default())))
+}
+
+/// Without the `link-check` feature nothing is requested unless a client is given, so only
+/// cached results are used
+#[cfg(not(feature = "link-check"))]
+fn default_client() -> Option<Arc<dyn HttpClient>> {
+    None
+}
@@ -0,0 +1,195 @@
+//! Dates, counts and reading times written for people, in the reader's language
+//!
+//! Only text meant to be read is localized: tag pages, email subjects and the like.
+//! Frontmatter, feeds, state files and anything else a program parses keep their fixed
+//! formats whatever the locale.
+
+use std::fmt;
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Words read per minute, for reading times
+const WORDS_PER_MINUTE: usize = 200;
+
+/// How one language writes dates, numbers and reading times
+#[derive(Debug, PartialEq, Eq)]
+struct Rules {
+    /// Language subtag, lowercase
+    language: &'static str,
+    months: [&'static str; 12],
+    /// `{day}`, `{month}` and `{year}` placeholders
+    date: &'static str,
+    /// Put between groups of three digits
+    thousands: &'static str,
+    /// `{minutes}` placeholder
+    reading_time: &'static str,
+}
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November",
+    "December",
+];
+
+const RULES: &[Rules] = &[
+    Rules {
+        language: "en",
+        months: ENGLISH_MONTHS,
+        date: "{day} {month} {year}",
+        thousands: ",",
+        reading_time: "{minutes} min read",
+    },
+    Rules {
+        language: "de",
+        months: [
+            "Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober", "November",
+            "Dezember",
+        ],
+        date: "{day}. {month} {year}",
+        thousands: ".",
+        reading_time: "{minutes} Min. Lesezeit",
+    },
+    Rules {
+        language: "fr",
+        months: [
+            "janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre", "novembre",
+            "décembre",
+        ],
+        date: "{day} {month} {year}",
+        thousands: "\u{202f}",
+        reading_time: "{minutes} min de lecture",
+    },
+    Rules {
+        language: "es",
+        months: [
+            "enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto", "septiembre", "octubre",
+            "noviembre", "diciembre",
+        ],
+        date: "{day} de {month} de {year}",
+        thousands: ".",
+        reading_time: "{minutes} min de lectura",
+    },
+    Rules {
+        language: "it",
+        months: [
+            "gennaio", "febbraio", "marzo", "aprile", "maggio", "giugno", "luglio", "agosto", "settembre", "ottobre",
+            "novembre", "dicembre",
+        ],
+        date: "{day} {month} {year}",
+        thousands: ".",
+        reading_time: "{minutes} min di lettura",
+    },
+    Rules {
+        language: "nl",
+        months: [
+            "januari", "februari", "maart", "april", "mei", "juni", "juli", "augustus", "september", "oktober",
+            "november", "december",
+        ],
+        date: "{day} {month} {year}",
+        thousands: ".",
+        reading_time: "{minutes} min leestijd",
+    },
+];
+
+/// American English puts the month first
+const EN_US: Rules = Rules {
+    language: "en",
+    months: ENGLISH_MONTHS,
+    date: "{month} {day}, {year}",
+    thousands: ",",
+    reading_time: "{minutes} min read",
+};
+
+/// A locale tag isn't one of the languages known
+#[derive(Debug, thiserror::Error)]
+#[error("unknown locale `{0}`; known languages are en, de, fr, es, it and nl, e.g. `de-DE`")]
+pub struct LocaleError(String);
+
+/// A language, and for English a region, to write dates and numbers for people in
+///
+/// Parsed from a tag such as `de-DE`, `en-US` or `fr`; the region only matters for
+/// English, where `en-US` puts the month first and every other region the day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Locale {
+    /// The tag as given, with `_` read as `-`
+    tag: String,
+    rules: &'static Rules,
+}
+
+impl Locale {
+    pub fn parse(tag: &str) -> Result<Self, LocaleError> {
+        let tag = tag.trim().replace('_', "-");
+        let mut parts = tag.split('-');
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts.next().map(str::to_ascii_uppercase);
+        let rules = match (language.as_str(), region.as_deref()) {
+            ("en", Some("US")) => &EN_US,
+            (language, _) => {
+                RULES.iter().find(|rules| rules.language == language).ok_or_else(|| LocaleError(tag.clone()))?
+            }
+        };
+        Ok(Self { tag, rules })
+    }
+
+    /// The tag the locale was parsed from
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// A date as written in running text, e.g. `12. Mai 2024` or `May 12, 2024`
+    pub fn date(&self, date: NaiveDate) -> String {
+        self.rules
+            .date
+            .replace("{day}", &date.day().to_string())
+            .replace("{month}", self.rules.months[date.month0() as usize])
+            .replace("{year}", &date.year().to_string())
+    }
+
+    /// A count with its thousands grouped, e.g. `1.234` or `1,234`
+    pub fn number(&self, n: u64) -> String {
+        group_thousands(n, self.rules.thousands)
+    }
+
+    /// How long a text takes to read, e.g. `3 Min. Lesezeit`
+    pub fn reading_time(&self, text: &str) -> String {
+        self.rules.reading_time.replace("{minutes}", &self.number(reading_minutes(text) as u64))
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.tag)
+    }
+}
+
+impl TryFrom<String> for Locale {
+    type Error = LocaleError;
+
+    fn try_from(tag: String) -> Result<Self, Self::Error> {
+        Self::parse(&tag)
+    }
+}
+
+impl From<Locale> for String {
+    fn from(locale: Locale) -> Self {
+        locale.tag
+    }
+}
+
+/// Minutes a text takes to read, at least one
+pub fn reading_minutes(text: &str) -> usize {
+    text.split_whitespace().count().div_ceil(WORDS_PER_MINUTE).max(1)
+}
+
+fn group_thousands(n: u64, separator: &str) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
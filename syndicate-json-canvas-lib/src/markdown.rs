@@ -0,0 +1,253 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+/// How links are rendered when converting markdown to plain text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkStyle {
+    /// Render as `text (url)`, or just `url` when the text is the url itself
+    #[default]
+    TextAndUrl,
+    /// Replace the link text with the url
+    UrlOnly,
+}
+
+/// Options for [`to_plain_text_with`]
+#[derive(Debug, Clone, Default)]
+pub struct PlainTextOptions {
+    pub link_style: LinkStyle,
+}
+
+/// Convert markdown to plain text using the default [`PlainTextOptions`]
+pub fn to_plain_text(markdown: &str) -> String {
+    to_plain_text_with(markdown, &PlainTextOptions::default())
+}
+
+/// Convert markdown to plain text for sinks that post text literally (e.g. Twitter)
+///
+/// Emphasis is unwrapped, links are rendered per [`LinkStyle`], list items become `• ` lines,
+/// images are dropped but their alt text is kept, raw HTML is dropped, and runs of blank lines
+/// are collapsed into one.
+pub fn to_plain_text_with(markdown: &str, options: &PlainTextOptions) -> String {
+    let mut out = String::new();
+    // Stack of (destination url, offset in `out` where the link text starts)
+    let mut links: Vec<(String, usize)> = Vec::new();
+    let mut list_depth: usize = 0;
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(text) | Event::Code(text) => out.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => out.push('\n'),
+            Event::Start(Tag::List(_)) => {
+                list_depth += 1;
+                ensure_newline(&mut out);
+            }
+            Event::End(TagEnd::List(_)) => {
+                list_depth = list_depth.saturating_sub(1);
+                if list_depth == 0 {
+                    out.push_str("\n\n");
+                } else {
+                    ensure_newline(&mut out);
+                }
+            }
+            Event::Start(Tag::Item) => {
+                ensure_newline(&mut out);
+                out.push_str(&"  ".repeat(list_depth.saturating_sub(1)));
+                out.push_str("• ");
+            }
+            Event::End(TagEnd::Item) => ensure_newline(&mut out),
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                links.push((dest_url.to_string(), out.len()));
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some((url, start)) = links.pop() {
+                    let text = out[start..].to_string();
+                    match options.link_style {
+                        LinkStyle::UrlOnly => {
+                            out.truncate(start);
+                            out.push_str(&url);
+                        }
+                        LinkStyle::TextAndUrl if text.is_empty() => out.push_str(&url),
+                        LinkStyle::TextAndUrl if text != url => {
+                            out.push_str(&format!(" ({})", url));
+                        }
+                        LinkStyle::TextAndUrl => {}
+                    }
+                }
+            }
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::CodeBlock
+                | TagEnd::BlockQuote(_),
+            )
+            | Event::Rule => out.push_str("\n\n"),
+            // Emphasis, strong, strikethrough, images (alt text arrives as Text) and raw HTML
+            _ => {}
+        }
+    }
+
+    collapse_blank_lines(&out)
+}
+
+fn ensure_newline(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Trim trailing whitespace on each line and collapse consecutive blank lines into one
+fn collapse_blank_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// A source cited with a markdown footnote (`[^1]: Author, Title, 2020`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reference {
+    /// Footnote label (the `1` in `[^1]`)
+    pub label: String,
+    /// Raw text of the footnote definition
+    pub text: String,
+    /// First URL found in the definition, if any
+    pub url: Option<String>,
+}
+
+/// Footnotes found in a piece of markdown
+#[derive(Debug, Clone, Default)]
+pub struct FootnoteExtraction {
+    /// Definitions in source order
+    pub references: Vec<Reference>,
+    /// Markers (`[^x]`) used in the text without a matching definition
+    pub undefined_markers: Vec<String>,
+    /// Definitions never referenced by a marker
+    pub unused_definitions: Vec<String>,
+}
+
+/// Extract footnote definitions as structured references, leaving the markdown untouched
+pub fn extract_footnotes(markdown: &str) -> FootnoteExtraction {
+    let mut references = Vec::new();
+    let mut current: Option<(String, Option<String>)> = None;
+
+    for (event, range) in Parser::new_ext(markdown, Options::ENABLE_FOOTNOTES).into_offset_iter() {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                current = Some((label.to_string(), None));
+                let raw = markdown[range].trim();
+                let text = raw
+                    .strip_prefix(&format!("[^{}]:", label))
+                    .unwrap_or(raw)
+                    .trim()
+                    .to_string();
+                references.push(Reference { label: label.to_string(), text, url: None });
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                if let Some((_, url @ None)) = &mut current {
+                    *url = Some(dest_url.to_string());
+                }
+            }
+            Event::End(TagEnd::FootnoteDefinition) => {
+                if let (Some((_, link_url)), Some(reference)) = (current.take(), references.last_mut()) {
+                    reference.url = link_url.or_else(|| find_url(&reference.text));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let defined: BTreeSet<&str> = references.iter().map(|r| r.label.as_str()).collect();
+    let used = footnote_markers(markdown);
+
+    FootnoteExtraction {
+        undefined_markers: used
+            .iter()
+            .filter(|label| !defined.contains(label.as_str()))
+            .cloned()
+            .collect(),
+        unused_definitions: defined
+            .iter()
+            .filter(|label| !used.contains(**label))
+            .map(|label| label.to_string())
+            .collect(),
+        references,
+    }
+}
+
+/// Labels of `[^label]` markers that are not definitions (`[^label]:`)
+fn footnote_markers(markdown: &str) -> BTreeSet<String> {
+    let mut markers = BTreeSet::new();
+    let mut rest = markdown;
+    while let Some(start) = rest.find("[^") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find(']') else { break };
+        let label = &rest[..end];
+        let is_definition = rest[end + 1..].starts_with(':');
+        if !is_definition && !label.is_empty() && !label.contains(char::is_whitespace) {
+            markers.insert(label.to_string());
+        }
+        rest = &rest[end + 1..];
+    }
+    markers
+}
+
+/// First `http(s)://` URL in plain text, without trailing punctuation
+pub(crate) fn find_url(text: &str) -> Option<String> {
+    let start = text.find("https://").or_else(|| text.find("http://"))?;
+    let url: String = text[start..]
+        .chars()
+        .take_while(|c| !c.is_whitespace() && !matches!(c, '<' | '>' | '"'))
+        .collect();
+    Some(url.trim_end_matches(['.', ',', ';', ':', ')', ']']).to_string())
+}
+
+/// Byte ranges of every `http(s)://` URL in plain text, without trailing punctuation
+pub(crate) fn url_spans(text: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = text[from..].find("http") {
+        let start = from + offset;
+        let rest = &text[start..];
+        if !(rest.starts_with("https://") || rest.starts_with("http://")) {
+            from = start + "http".len();
+            continue;
+        }
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"'))
+            .unwrap_or(rest.len());
+        let url = rest[..len].trim_end_matches(['.', ',', ';', ':', ')', ']']);
+        if !url.ends_with("://") {
+            spans.push(start..start + url.len());
+        }
+        from = start + len;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn footnotes_become_references_with_their_urls() {
+        let markdown = "Cited twice[^1] and once[^missing].\n\n\
+                        [^1]: Jane Doe, [A Title](https://example.com/a), 2020\n\n\
+                        [^2]: Unused, see https://example.com/b.\n";
+        let extraction = extract_footnotes(markdown);
+        let urls: Vec<_> = extraction.references.iter().map(|r| (r.label.as_str(), r.url.as_deref())).collect();
+        assert_eq!(urls, [("1", Some("https://example.com/a")), ("2", Some("https://example.com/b"))]);
+        assert!(extraction.references[0].text.starts_with("Jane Doe"), "{:?}", extraction.references[0]);
+        assert_eq!(extraction.undefined_markers, ["missing"]);
+        assert_eq!(extraction.unused_definitions, ["2"]);
+    }
+}
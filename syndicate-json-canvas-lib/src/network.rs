@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::clock::{Clock, SystemClock};
+
+/// An outgoing HTTP request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    /// Time allowed for the whole request; None for the client's default
+    pub timeout: Option<Duration>,
+    /// Bytes of the response body read at most; None reads all of it
+    pub max_bytes: Option<u64>,
+}
+
+impl HttpRequest {
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new("GET", url, None)
+    }
+
+    pub fn patch(url: impl Into<String>, body: impl Into<String>) -> Self {
+        Self::new("PATCH", url, Some(body.into()))
+    }
+
+    pub fn head(url: impl Into<String>) -> Self {
+        Self::new("HEAD", url, None)
+    }
+
+    pub fn post(url: impl Into<String>, body: impl Into<String>) -> Self {
+        Self::new("POST", url, Some(body.into()))
+    }
+
+    fn new(method: &str, url: impl Into<String>, body: Option<String>) -> Self {
+        Self { method: method.to_string(), url: url.into(), headers: Vec::new(), body, timeout: None, max_bytes: None }
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Host the request goes to, which politeness limits are kept per
+    fn host(&self) -> String {
+        url::Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_else(|| self.url.clone())
+    }
+}
+
+/// A response to an [`HttpRequest`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Value of a header, compared ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// How long the server asked to wait before the next request, from a `Retry-After`
+    /// header in seconds or as an HTTP date, counted from `now`
+    pub fn retry_after(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let value = self.header("retry-after")?.trim();
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+        let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+        Some((at - now).to_std().unwrap_or_default())
+    }
+}
+
+/// Why an HTTP request failed
+#[derive(Debug, thiserror::Error)]
+pub enum HttpError {
+    /// The request was refused before being sent (e.g. a malformed URL)
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+
+    /// Sending or reading failed, so the server may or may not have acted on the request
+    #[error("request failed: {0}")]
+    Transport(String),
+}
+
+/// Sends HTTP requests
+///
+/// Network sinks and link enrichment go through this rather than an HTTP library, so
+/// requests can be shaped by a [`PoliteClient`] and replaced by a mock.
+pub trait HttpClient: Debug + Send + Sync {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError>;
+}
+
+impl<C: HttpClient + ?Sized> HttpClient for Arc<C> {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        (**self).send(request)
+    }
+}
+
+/// [`HttpClient`] backed by reqwest's blocking client
+#[cfg(feature = "http")]
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestClient {
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "http")]
+impl ReqwestClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A client returning redirects as they are instead of following them, for checking
+    /// where links lead
+    pub fn without_redirects() -> Self {
+        let client = reqwest::blocking::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap_or_default();
+        Self { client }
+    }
+}
+
+#[cfg(feature = "http")]
+impl HttpClient for ReqwestClient {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        use std::io::Read;
+
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|e| HttpError::InvalidRequest(e.to_string()))?;
+        let mut builder = self.client.request(method, &request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        let response = builder.send().map_err(|e| {
+            if e.is_builder() {
+                HttpError::InvalidRequest(e.to_string())
+            } else {
+                HttpError::Transport(e.to_string())
+            }
+        })?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.as_str().to_string(), value.to_str().unwrap_or_default().to_string()))
+            .collect();
+        let mut body = Vec::new();
+        response
+            .take(request.max_bytes.unwrap_or(u64::MAX))
+            .read_to_end(&mut body)
+            .map_err(|e| HttpError::Transport(e.to_string()))?;
+
+        Ok(HttpResponse { status, headers, body: String::from_utf8_lossy(&body).into_owned() })
+    }
+}
+
+/// Limits on outgoing requests, so draining a backlog doesn't hammer anyone's server
+///
+/// [`NetworkPolicy::load`] reads it from the `[network]` table of a TOML file; missing
+/// keys keep their defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkPolicy {
+    /// Requests in flight to one host at a time
+    pub per_host_concurrency: usize,
+    /// Least time between the starts of two requests to one host, in milliseconds
+    pub per_host_delay_ms: u64,
+    /// Requests in flight at a time across all hosts
+    pub max_concurrent: usize,
+    /// Longest `Retry-After` honored by waiting, in seconds; responses asking for longer
+    /// are returned to the caller as they are
+    pub max_retry_after_secs: u64,
+    /// Times a request answered with 429 or 503 and a `Retry-After` is sent again
+    pub retries: usize,
+}
+
+impl Default for NetworkPolicy {
+    fn default() -> Self {
+        Self {
+            per_host_concurrency: 2,
+            per_host_delay_ms: 500,
+            max_concurrent: 8,
+            max_retry_after_secs: 300,
+            retries: 1,
+        }
+    }
+}
+
+/// TOML file holding a `[network]` table
+#[derive(Debug, Default, Deserialize)]
+struct NetworkPolicyFile {
+    #[serde(default)]
+    network: NetworkPolicy,
+}
+
+impl NetworkPolicy {
+    /// Read the `[network]` table of a TOML file (the defaults if it has none)
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse the `[network]` table of a TOML document
+    pub fn from_toml(toml: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(toml::from_str::<NetworkPolicyFile>(toml)?.network)
+    }
+}
+
+/// Requests in flight, overall and per host
+#[derive(Debug, Default)]
+struct Slots {
+    in_flight: usize,
+    hosts: HashMap<String, HostSlots>,
+}
+
+#[derive(Debug, Default)]
+struct HostSlots {
+    in_flight: usize,
+    /// No request to the host starts before this
+    not_before: Option<Instant>,
+}
+
+/// [`HttpClient`] that holds requests back to keep within a [`NetworkPolicy`]
+///
+/// Share one (behind an `Arc`) between every sink and the link enricher, so the limits
+/// apply to all of their requests together. Callers block until their request may go.
+#[derive(Debug)]
+pub struct PoliteClient {
+    inner: Box<dyn HttpClient>,
+    policy: NetworkPolicy,
+    slots: Mutex<Slots>,
+    freed: Condvar,
+    clock: Arc<dyn Clock>,
+}
+
+impl PoliteClient {
+    pub fn new(inner: impl HttpClient + 'static, policy: NetworkPolicy) -> Self {
+        Self {
+            inner: Box::new(inner),
+            policy,
+            slots: Mutex::default(),
+            freed: Condvar::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Use a custom clock to read `Retry-After` dates against
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn policy(&self) -> &NetworkPolicy {
+        &self.policy
+    }
+
+    /// Wait for a free slot for `host` and take it
+    fn acquire(&self, host: &str) {
+        let mut slots = self.slots.lock().expect("slots lock poisoned");
+        loop {
+            let now = Instant::now();
+            let global_free = slots.in_flight < self.policy.max_concurrent.max(1);
+            let host_slots = slots.hosts.entry(host.to_string()).or_default();
+            let wait = host_slots.not_before.filter(|at| *at > now).map(|at| at - now);
+            if global_free && host_slots.in_flight < self.policy.per_host_concurrency.max(1) && wait.is_none() {
+                host_slots.in_flight += 1;
+                host_slots.not_before = Some(now + Duration::from_millis(self.policy.per_host_delay_ms));
+                slots.in_flight += 1;
+                return;
+            }
+            slots = match wait {
+                Some(wait) => self.freed.wait_timeout(slots, wait).expect("slots lock poisoned").0,
+                None => self.freed.wait(slots).expect("slots lock poisoned"),
+            };
+        }
+    }
+
+    /// Give back the slot of `host`, keeping requests to it back for `retry_after`
+    fn release(&self, host: &str, retry_after: Option<Duration>) {
+        let mut slots = self.slots.lock().expect("slots lock poisoned");
+        slots.in_flight -= 1;
+        if let Some(host_slots) = slots.hosts.get_mut(host) {
+            host_slots.in_flight -= 1;
+            if let Some(retry_after) = retry_after {
+                let until = Instant::now() + retry_after;
+                host_slots.not_before = Some(host_slots.not_before.map_or(until, |at| at.max(until)));
+            }
+        }
+        self.freed.notify_all();
+    }
+}
+
+impl HttpClient for PoliteClient {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        let host = request.host();
+        let max_retry_after = Duration::from_secs(self.policy.max_retry_after_secs);
+        let mut attempt = 0;
+        loop {
+            self.acquire(&host);
+            let result = self.inner.send(request);
+            let now = self.clock.now().with_timezone(&Utc);
+            let retry_after = match &result {
+                Ok(response) if matches!(response.status, 429 | 503) => response.retry_after(now),
+                _ => None,
+            };
+            self.release(&host, retry_after.map(|wait| wait.min(max_retry_after)));
+
+            match retry_after {
+                Some(wait) if attempt < self.policy.retries && wait <= max_retry_after => {
+                    debug!(host = %host, wait_secs = wait.as_secs(), "Throttled, retrying after the requested wait");
+                    attempt += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A request as the server saw it
+    #[derive(Debug, Clone)]
+    struct Seen {
+        host: String,
+        started: Instant,
+    }
+
+    /// Requests in flight per host, and the most seen at once per host and overall
+    #[derive(Debug, Default)]
+    struct Load {
+        now: HashMap<String, usize>,
+        most: HashMap<String, usize>,
+        most_total: usize,
+    }
+
+    /// Takes `delay` to answer, throttling the first request to each `/throttled` URL
+    #[derive(Debug, Default)]
+    struct Mock {
+        delay: Duration,
+        seen: Mutex<Vec<Seen>>,
+        load: Mutex<Load>,
+    }
+
+    impl HttpClient for Mock {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+            let host = request.host();
+            let first = {
+                let mut seen = self.seen.lock().unwrap();
+                let first = !seen.iter().any(|s| s.host == host);
+                seen.push(Seen { host: host.clone(), started: Instant::now() });
+                first
+            };
+            {
+                let mut load = self.load.lock().unwrap();
+                *load.now.entry(host.clone()).or_default() += 1;
+                let count = load.now[&host];
+                let most = load.most.entry(host.clone()).or_default();
+                *most = (*most).max(count);
+                load.most_total = load.most_total.max(load.now.values().sum());
+            }
+            std::thread::sleep(self.delay);
+            *self.load.lock().unwrap().now.get_mut(&host).unwrap() -= 1;
+
+            let throttled = first && request.url.ends_with("/throttled");
+            let (status, headers) = match throttled {
+                true => (429, vec![("Retry-After".to_string(), "1".to_string())]),
+                false => (200, Vec::new()),
+            };
+            Ok(HttpResponse { status, headers, body: String::new() })
+        }
+    }
+
+    /// Send every URL from its own thread through one client
+    fn send_all(client: &Arc<PoliteClient>, urls: &[&str]) -> Vec<u16> {
+        let handles: Vec<_> = urls
+            .iter()
+            .map(|url| {
+                let client = client.clone();
+                let request = HttpRequest::get(*url);
+                std::thread::spawn(move || client.send(&request).unwrap().status)
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    }
+
+    fn starts(mock: &Mock, host: &str) -> Vec<Instant> {
+        let mut starts: Vec<_> =
+            mock.seen.lock().unwrap().iter().filter(|s| s.host == host).map(|s| s.started).collect();
+        starts.sort();
+        starts
+    }
+
+    #[test]
+    fn requests_to_a_host_are_spaced_and_bounded() {
+        let mock = Arc::new(Mock { delay: Duration::from_millis(150), ..Mock::default() });
+        let policy = NetworkPolicy { per_host_concurrency: 2, per_host_delay_ms: 40, ..NetworkPolicy::default() };
+        let client = Arc::new(PoliteClient::new(mock.clone(), policy));
+
+        let urls = ["https://a.example/1", "https://a.example/2", "https://a.example/3", "https://a.example/4"];
+        let mut all = urls.to_vec();
+        all.push("https://b.example/1");
+        assert!(send_all(&client, &all).iter().all(|status| *status == 200));
+
+        let starts = starts(&mock, "a.example");
+        assert_eq!(starts.len(), 4);
+        for pair in starts.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(40), "{:?}", pair[1] - pair[0]);
+        }
+        assert_eq!(mock.load.lock().unwrap().most["a.example"], 2);
+        // Another host isn't held back by the first one's limits
+        assert!(mock.seen.lock().unwrap().iter().any(|s| s.host == "b.example"));
+    }
+
+    #[test]
+    fn global_cap_bounds_requests_across_hosts() {
+        let mock = Arc::new(Mock { delay: Duration::from_millis(30), ..Mock::default() });
+        let policy = NetworkPolicy { max_concurrent: 2, per_host_delay_ms: 0, ..NetworkPolicy::default() };
+        let client = Arc::new(PoliteClient::new(mock.clone(), policy));
+
+        let urls = ["https://a.example/", "https://b.example/", "https://c.example/", "https://d.example/"];
+        send_all(&client, &urls);
+        assert_eq!(mock.seen.lock().unwrap().len(), 4);
+        assert_eq!(mock.load.lock().unwrap().most_total, 2);
+    }
+
+    #[test]
+    fn retry_after_holds_the_host_back_then_retries() {
+        let mock = Arc::new(Mock::default());
+        let policy = NetworkPolicy { per_host_delay_ms: 0, ..NetworkPolicy::default() };
+        let client = Arc::new(PoliteClient::new(mock.clone(), policy));
+
+        assert_eq!(send_all(&client, &["https://a.example/throttled"]), [200]);
+        let starts = starts(&mock, "a.example");
+        assert_eq!(starts.len(), 2);
+        assert!(starts[1] - starts[0] >= Duration::from_secs(1));
+
+        // Without retries left the throttled response comes back to the caller
+        let mock = Arc::new(Mock::default());
+        let policy = NetworkPolicy { retries: 0, ..NetworkPolicy::default() };
+        let client = Arc::new(PoliteClient::new(mock.clone(), policy));
+        assert_eq!(send_all(&client, &["https://a.example/throttled"]), [429]);
+        assert_eq!(mock.seen.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn retry_after_reads_seconds_and_dates() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT").unwrap().with_timezone(&Utc);
+        let response = |value: &str| HttpResponse {
+            status: 503,
+            headers: vec![("retry-after".to_string(), value.to_string())],
+            body: String::new(),
+        };
+        assert_eq!(response("120").retry_after(now), Some(Duration::from_secs(120)));
+        assert_eq!(response("Wed, 21 Oct 2026 07:28:30 GMT").retry_after(now), Some(Duration::from_secs(30)));
+        assert_eq!(response("Wed, 21 Oct 2026 07:27:00 GMT").retry_after(now), Some(Duration::ZERO));
+        assert_eq!(response("soon").retry_after(now), None);
+    }
+}
@@ -0,0 +1,99 @@
+//! Node IDs as they show up in file names, logs, frontmatter, trackers and commands
+//!
+//! Obsidian's IDs are 16 hex digits. [`ShortIds`] cuts them down for file names, and
+//! [`resolve_node_id`] lets commands take either form.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::jsoncanvas::NodeId;
+
+/// Characters a short ID has at least
+pub const SHORT_ID_LEN: usize = 8;
+
+/// Order of node IDs wherever output must be reproducible: by their text, which is also
+/// how they're displayed
+pub fn cmp_node_ids(a: &NodeId, b: &NodeId) -> Ordering {
+    a.as_str().cmp(b.as_str())
+}
+
+/// Short forms of a set of node IDs: the first [`SHORT_ID_LEN`] characters, or as many more
+/// as it takes to tell an ID from the others in the set
+#[derive(Debug, Clone, Default)]
+pub struct ShortIds {
+    ids: HashMap<NodeId, String>,
+}
+
+impl ShortIds {
+    pub fn new<'a>(node_ids: impl IntoIterator<Item = &'a NodeId>) -> Self {
+        let mut sorted: Vec<&NodeId> = node_ids.into_iter().collect();
+        sorted.sort_by(|a, b| cmp_node_ids(a, b));
+        sorted.dedup();
+
+        // Sorted, an ID shares its longest prefix with a neighbor
+        let ids = sorted
+            .iter()
+            .enumerate()
+            .map(|(i, node_id)| {
+                let shared = [i.checked_sub(1), Some(i + 1)]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|j| sorted.get(j))
+                    .map(|other| common_prefix(node_id.as_str(), other.as_str()))
+                    .max()
+                    .unwrap_or(0);
+                let len = (shared + 1).max(SHORT_ID_LEN);
+                ((*node_id).clone(), node_id.as_str().chars().take(len).collect())
+            })
+            .collect();
+        Self { ids }
+    }
+
+    /// Short form of `node_id`, or the whole ID if it isn't in the set
+    pub fn get<'a>(&'a self, node_id: &'a NodeId) -> &'a str {
+        self.ids.get(node_id).map_or(node_id.as_str(), String::as_str)
+    }
+}
+
+/// Characters two strings start with in common
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.chars().zip(b.chars()).take_while(|(a, b)| a == b).count()
+}
+
+/// A node ID typed in a command that can't be resolved
+#[derive(Debug, thiserror::Error)]
+pub enum NodeIdError {
+    #[error("Node ID is empty")]
+    Empty,
+
+    #[error("`{input}` could be any of {}; type more of the ID", .candidates.join(", "))]
+    Ambiguous { input: String, candidates: Vec<String> },
+}
+
+/// The node a command means by `input`: a node of `known` with exactly that ID, else the
+/// only one whose ID starts with it
+///
+/// Input that matches no known node is taken as a full ID as typed, e.g. to exclude a node
+/// before it's seen. Fails if it starts more than one known ID.
+pub fn resolve_node_id<'a>(input: &str, known: impl IntoIterator<Item = &'a NodeId>) -> Result<NodeId, NodeIdError> {
+    let input = input.trim();
+    let node_id: NodeId = input.parse().map_err(|_| NodeIdError::Empty)?;
+    let mut candidates: Vec<&NodeId> = Vec::new();
+    for known in known {
+        if *known == node_id {
+            return Ok(node_id);
+        }
+        if known.as_str().starts_with(input) && !candidates.contains(&known) {
+            candidates.push(known);
+        }
+    }
+    candidates.sort_by(|a, b| cmp_node_ids(a, b));
+    match candidates.as_slice() {
+        [] => Ok(node_id),
+        [only] => Ok((*only).clone()),
+        _ => Err(NodeIdError::Ambiguous {
+            input: input.to_string(),
+            candidates: candidates.iter().map(|id| id.to_string()).collect(),
+        }),
+    }
+}
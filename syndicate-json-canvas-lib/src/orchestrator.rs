@@ -1,16 +1,249 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
-use std::path::Path;
-use std::str::FromStr;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant};
 
-use notify_debouncer_mini::{DebouncedEventKind, new_debouncer, notify::RecursiveMode};
-use tracing::{debug, error, info};
+use chrono::{DateTime, Local};
+use notify_debouncer_mini::{DebounceEventResult, DebouncedEvent, new_debouncer, notify::RecursiveMode};
+use tracing::{debug, error, info, warn};
 
-use crate::jsoncanvas::JsonCanvas;
-use crate::sink::SyndicationSink;
+use crate::accessibility::{AccessibilityOptions, AccessibilityPolicy, check_accessibility};
+use crate::archive::archived_node_ids;
+use crate::badge::BadgeSpec;
+use crate::batch::{BatchPolicy, PendingBatch};
+use crate::cancel::CancelToken;
+use crate::category::{CategoryMap, hashtags};
+use crate::clock::{Clock, SystemClock};
+use crate::limits::{ItemSizeLimit, OversizePolicy};
+use crate::selection::Selection;
+use crate::size::SizeFilter;
+use crate::content_warning::take_cw_tokens;
+use crate::content::{ExternalLink, ResolveOptions, content_of, external_link_of};
+use crate::control::{PipelineStatus, WatchCommand};
+use crate::processing::{ProcessingQueue, ProcessingReason, ProcessingRequest, ProcessingSender};
+use crate::text::TextPolicy;
+use crate::tokens::{TokenOptions, expand_tokens};
+use crate::group_settings::{ItemSettings, group_settings};
+use crate::replace::{Redaction, Replacements, group_labels};
+use crate::recency::{first_published, frontmatter_date, older_than, published_within};
+use crate::resurface::{ResurfaceLog, ResurfaceOptions, pick};
+use crate::seen::FirstSeenLog;
+use crate::idempotency::idempotency_key;
+use crate::node_ids::cmp_node_ids;
+use crate::duplicates::{DuplicateOptions, DuplicatePolicy, find_duplicate_groups, suppressed_by_policy};
+use crate::emphasis::EdgeWeights;
+use crate::enrich::{LinkEnricher, LinkEnrichmentOptions};
+use crate::link_check::{
+    LinkCheckOptions, LinkCheckPolicy, LinkChecker, LinkFinding, LinkStatus, find_links, rewrite_links,
+};
+use crate::exclusions::ExclusionList;
+use crate::guard::RemovalGuard;
+use crate::history::{DEFAULT_HISTORY_MAX_BYTES, HistoryLog};
+use crate::journal::{self, JournalStep, RunJournal};
+use crate::language::{LanguageOptions, detect_language};
+use crate::markdown::extract_footnotes;
+use crate::preview::{PlannedWrite, WriteKind, WriteSummary};
+use crate::queue::{DeliveryOptions, OutboundQueue};
+use crate::jsoncanvas::{JsonCanvas, Node, NodeId, node::GenericNodeInfo};
+use crate::report::{ProcessReport, ReportWriter, SinkOutcome};
+use crate::snapshot::CanvasSnapshot;
+use crate::sink::{ItemChange, SinkError, SyndicationLinks, SyndicationSink};
 use crate::tracker::SyndicationTracker;
-use crate::{default_process_node, to_syndication_format};
+use crate::validate::validate_snapshot;
+use crate::vault::Vault;
+use crate::warnings::{Warning, WarningPolicy, codes, log_summary};
+use crate::watch::{EventFilter, FileStamp, FilteredEvents, WatchOptions, file_stamp};
+use crate::{
+    DanglingEdges, EdgeDirection, InAdjacencies, OutAdjacencies, SyndicationFormat, to_syndication_format_lenient,
+};
+
+/// Options that affect how the canvas is processed
+#[derive(Debug, Clone)]
+pub struct ProcessOptions {
+    /// Root of the Obsidian vault the canvas lives in (auto-detected from `.obsidian/` if None)
+    pub vault_root: Option<PathBuf>,
+    /// If set, a report file is written after every run
+    pub report_writer: Option<ReportWriter>,
+    /// Badge files written after every run that isn't a dry run
+    pub badges: Vec<BadgeSpec>,
+    /// Sinks posting an old item again on a schedule
+    pub resurface: Vec<ResurfaceOptions>,
+    /// Source of the current time for reports and scheduling
+    pub clock: Arc<dyn Clock>,
+    /// If set, identical or near-identical items are detected before publishing
+    pub duplicates: Option<DuplicateOptions>,
+    /// If set, each item's language is detected and used to route items to sinks
+    pub language: Option<LanguageOptions>,
+    /// If set, metadata of the first link in each item is fetched (or read from cache)
+    pub link_enrichment: Option<LinkEnrichmentOptions>,
+    /// If set, the links of items about to be published are checked first
+    pub link_check: Option<LinkCheckOptions>,
+    /// If set, each item's hashtags are mapped onto a category
+    pub categories: Option<CategoryMap>,
+    /// If set, new items are queued and delivered by [`drain_queue`] instead of directly
+    pub queue: Option<DeliveryOptions>,
+    /// If set, each sink takes at most this many new items per run, lowest node IDs first,
+    /// and items a sink fails to take are queued for [`drain_queue`] instead of tried again
+    pub max_new_items: Option<usize>,
+    /// Size above which the publish history log is compacted
+    pub history_max_bytes: u64,
+    /// Which nodes to syndicate: those of some colors, or all of them
+    pub selection: Selection,
+    /// File name of the canvas (e.g. `Public.canvas`), required to match before
+    /// [`Selection::All`] publishes anything
+    pub confirm_canvas_name: Option<String>,
+    /// If set, selected Link nodes become link posts whose text is this template with
+    /// `{url}` replaced (e.g. [`DEFAULT_LINK_POST_TEMPLATE`](crate::DEFAULT_LINK_POST_TEMPLATE)),
+    /// instead of standing for a page that lives elsewhere
+    pub link_posts: Option<String>,
+    /// Whether edges ending at nodes that aren't on the canvas are left out or fail the run
+    pub dangling_edges: DanglingEdges,
+    /// Whether edges point as drawn or along their arrows, which decides a node's in- and
+    /// out-neighbors
+    pub edge_direction: EdgeDirection,
+    /// Sizes of the nodes to syndicate
+    pub size: SizeFilter,
+    /// Largest item text, and whether larger ones are skipped or cut down
+    pub item_size: ItemSizeLimit,
+    /// If set, date tokens like `{{today}}` in node text are expanded as of each run
+    pub tokens: Option<TokenOptions>,
+    /// If set, text replacements applied to every item before publishing
+    pub replacements: Option<Replacements>,
+    /// Accessibility checks, and whether items failing them reach public-facing sinks
+    pub accessibility: AccessibilityOptions,
+    /// If set, neighbors are weighted and ordered by the color of the edges to them
+    pub edge_weights: Option<EdgeWeights>,
+    /// How titles and slugs are cut from node text; sinks get their own copy
+    pub text: TextPolicy,
+    /// Whether new items are published right away or held back and published together
+    pub batching: BatchPolicy,
+    /// How many published nodes may vanish from the canvas between runs
+    pub removal_guard: RemovalGuard,
+    /// Which warnings fail the run and which are dropped
+    pub warnings: WarningPolicy,
+    /// Which file changes start a run when watching
+    pub watch: WatchOptions,
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            vault_root: None,
+            report_writer: None,
+            badges: Vec::new(),
+            resurface: Vec::new(),
+            clock: Arc::new(SystemClock),
+            duplicates: None,
+            language: None,
+            link_enrichment: None,
+            link_check: None,
+            categories: None,
+            queue: None,
+            max_new_items: None,
+            history_max_bytes: DEFAULT_HISTORY_MAX_BYTES,
+            selection: Selection::default(),
+            confirm_canvas_name: None,
+            link_posts: None,
+            dangling_edges: DanglingEdges::default(),
+            edge_direction: EdgeDirection::default(),
+            size: SizeFilter::any(),
+            item_size: ItemSizeLimit::default(),
+            tokens: None,
+            replacements: None,
+            accessibility: AccessibilityOptions::default(),
+            edge_weights: None,
+            text: TextPolicy::default(),
+            batching: BatchPolicy::Immediate,
+            removal_guard: RemovalGuard::default(),
+            warnings: WarningPolicy::default(),
+            watch: WatchOptions::default(),
+        }
+    }
+}
+
+/// A sink together with its tracker and ordering constraints
+pub struct SinkTarget {
+    /// The sink to publish to
+    pub sink: Box<dyn SyndicationSink + Send>,
+    /// Tracker of nodes already published to this sink
+    pub tracker: SyndicationTracker,
+    /// Names of sinks that must finish publishing before this one starts
+    pub depends_on: Vec<String>,
+    /// Keep this sink in dry-run mode even when the run is armed
+    pub dry_run: bool,
+    /// Whether the sink publishes to the public, so items failing the accessibility checks
+    /// are held back under [`AccessibilityPolicy::Block`]
+    pub public_facing: bool,
+    /// If set, only items first published (to any sink) within this many calendar days are
+    /// routed to the sink, e.g. 7 for a weekly digest
+    pub published_within: Option<u32>,
+    /// If set, unpublished items more than this many calendar days old are skipped for the
+    /// sink for good instead of published, e.g. so a new social sink doesn't post the backlog
+    pub max_item_age: Option<u32>,
+}
+
+impl SinkTarget {
+    /// Create a target with no ordering constraints
+    pub fn new(sink: impl SyndicationSink + Send + 'static, tracker: SyndicationTracker) -> Self {
+        Self {
+            sink: Box::new(sink),
+            tracker,
+            depends_on: Vec::new(),
+            dry_run: false,
+            public_facing: false,
+            published_within: None,
+            max_item_age: None,
+        }
+    }
+
+    /// Keep this sink in dry-run mode regardless of the run's `dry_run` flag
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Mark this sink as publishing to the public
+    pub fn with_public_facing(mut self, public_facing: bool) -> Self {
+        self.public_facing = public_facing;
+        self
+    }
+
+    /// Only route items first published within the last `days` calendar days to this sink
+    ///
+    /// Items not yet published anywhere count as published today.
+    pub fn with_published_within(mut self, days: u32) -> Self {
+        self.published_within = Some(days);
+        self
+    }
+
+    /// Skip items dated more than `days` calendar days ago for this sink instead of publishing them
+    ///
+    /// Items are dated by the `date` in their frontmatter, else by when the node was first
+    /// seen. Skipped items are never offered to the sink again.
+    pub fn with_max_item_age(mut self, days: u32) -> Self {
+        self.max_item_age = Some(days);
+        self
+    }
+
+    /// Whether an item is too old for this sink's maximum item age
+    pub fn is_too_old(&self, item: &SyndicationFormat, now: DateTime<Local>) -> bool {
+        self.max_item_age.is_some_and(|days| older_than(item.dated_at, days, now))
+    }
+
+    /// Whether this sink only pretends to publish in a run with the given global flag
+    pub fn is_dry(&self, global_dry_run: bool) -> bool {
+        global_dry_run || self.dry_run
+    }
+
+    /// Only start publishing after the named sink has finished
+    pub fn after(mut self, sink_name: impl Into<String>) -> Self {
+        self.depends_on.push(sink_name.into());
+        self
+    }
+}
 
 /// Validate that the path points to a .canvas file
 pub fn validate_canvas_path(path: &Path) -> Result<(), &'static str> {
@@ -23,112 +256,2248 @@ pub fn validate_canvas_path(path: &Path) -> Result<(), &'static str> {
     Ok(())
 }
 
-/// Process the canvas file and publish only new items
+/// Process the canvas file and publish only new items to each sink
+///
+/// Independent sinks publish concurrently; sinks with `depends_on` constraints wait for
+/// their dependencies. `dry_run` forces every sink into dry-run mode; otherwise each
+/// target's own `dry_run` decides. Returns a [`ProcessReport`] describing what happened; errors are
+/// recorded in the report (and logged) rather than returned, so watching can continue.
 pub fn process_canvas(
     canvas_path: &Path,
-    sink: &mut impl SyndicationSink,
-    tracker: &mut SyndicationTracker,
+    targets: &mut [SinkTarget],
     dry_run: bool,
-) {
-    let content = match std::fs::read_to_string(canvas_path) {
-        Ok(c) => c,
+    options: &ProcessOptions,
+) -> ProcessReport {
+    process_canvas_cancellable(canvas_path, targets, dry_run, options, &CancelToken::new())
+}
+
+/// [`process_canvas`], stopping early if `cancel` is triggered before publishing begins
+///
+/// A cancelled run touches no sink and returns a report with `cancelled` set.
+pub fn process_canvas_cancellable(
+    canvas_path: &Path,
+    targets: &mut [SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+    cancel: &CancelToken,
+) -> ProcessReport {
+    let snapshot = CanvasSnapshot::read(canvas_path, options.clock.now());
+    process_read(canvas_path, snapshot, targets, dry_run, options, cancel, None)
+}
+
+/// [`process_canvas_cancellable`] for a canvas already read into a [`CanvasSnapshot`]
+///
+/// The file isn't read again, so the whole run sees the snapshot's version of the canvas.
+pub fn process_snapshot(
+    snapshot: &CanvasSnapshot,
+    targets: &mut [SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+    cancel: &CancelToken,
+) -> ProcessReport {
+    process_snapshot_resuming(snapshot, targets, dry_run, options, cancel, None)
+}
+
+/// [`process_snapshot`], resuming the run of `resume` if given
+fn process_snapshot_resuming(
+    snapshot: &CanvasSnapshot,
+    targets: &mut [SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+    cancel: &CancelToken,
+    resume: Option<RunJournal>,
+) -> ProcessReport {
+    let started = Instant::now();
+    let mut report = ProcessReport::new(&snapshot.path, options.clock.now());
+    report.selection = Some(options.selection.to_string());
+    run_pipeline(snapshot, targets, dry_run, options, cancel, resume, &mut report);
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    report
+}
+
+/// Process the canvas as read, or report that it couldn't be
+fn process_read(
+    canvas_path: &Path,
+    snapshot: std::io::Result<CanvasSnapshot>,
+    targets: &mut [SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+    cancel: &CancelToken,
+    resume: Option<RunJournal>,
+) -> ProcessReport {
+    match snapshot {
+        Ok(snapshot) => process_snapshot_resuming(&snapshot, targets, dry_run, options, cancel, resume),
         Err(e) => {
             error!(error = %e, "Failed to read file");
-            return;
+            let mut report = ProcessReport::new(canvas_path, options.clock.now());
+            report.selection = Some(options.selection.to_string());
+            report.error = Some(format!("Failed to read file: {}", e));
+            report
         }
-    };
+    }
+}
 
-    let canvas = match JsonCanvas::from_str(&content) {
-        Ok(c) => c,
+/// Stop the run if it was cancelled, recording it in the report
+fn cancelled(cancel: &CancelToken, report: &mut ProcessReport, phase: &str) -> bool {
+    if cancel.is_cancelled() {
+        info!(phase, "Canvas changed again, cancelling run");
+        report.cancelled = true;
+    }
+    report.cancelled
+}
+
+/// Run the pipeline over a snapshot, resuming the run of `resume` if given
+fn run_pipeline(
+    snapshot: &CanvasSnapshot,
+    targets: &mut [SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+    cancel: &CancelToken,
+    resume: Option<RunJournal>,
+    report: &mut ProcessReport,
+) {
+    let canvas_path = snapshot.path.as_path();
+    if let Err(e) = options.selection.confirm(canvas_path, options.confirm_canvas_name.as_deref()) {
+        error!(error = %e, "Refusing to run");
+        report.error = Some(e.to_string());
+        return;
+    }
+
+    let vault = Vault::for_canvas(canvas_path, options.vault_root.as_deref());
+    debug!(vault_root = %vault.root().display(), "Resolved vault root");
+    let resolve = ResolveOptions::default().with_vault(vault);
+
+    report.canvas_hash = Some(snapshot.hash.clone());
+    if cancelled(cancel, report, "read") {
+        return;
+    }
+
+    let canvas = match snapshot.canvas() {
+        Ok(c) => {
+            let compatibility = snapshot.compatibility().cloned().unwrap_or_default();
+            for node in &compatibility.skipped_nodes {
+                let message = format!("Couldn't be read, skipped: {}", node.reason);
+                report.warnings.push(Warning { node_id: Some(node.id.clone()), ..Warning::new(codes::SKIPPED_NODE, "compat", message) });
+            }
+            if !compatibility.is_empty() {
+                report.compatibility = Some(compatibility);
+            }
+            c
+        }
         Err(e) => {
             error!(error = %e, "Failed to parse canvas");
+            report.error = Some(format!("Failed to parse canvas: {}", e));
             return;
         }
     };
+    report.nodes_considered = canvas.get_nodes().len();
+    for issue in validate_snapshot(snapshot, &options.selection).unwrap_or_default() {
+        let (node_id, edge_id) = (issue.node_id.as_deref().unwrap_or(""), issue.edge_id.as_deref().unwrap_or(""));
+        warn!(kind = issue.kind.code(), node_id, edge_id, "Canvas issue: {}", issue.message);
+    }
+    if cancelled(cancel, report, "parse") {
+        return;
+    }
 
-    let all_items = to_syndication_format(canvas, Some(default_process_node));
-    let total_count = all_items.len();
-    info!(total_items = total_count, "Found items matching filter");
+    // A canvas that lost most of its published nodes at once is more likely truncated
+    // (sync conflict, bad merge) than pruned, so nothing is touched until that's confirmed
+    let node_ids: HashSet<NodeId> = canvas.get_nodes().keys().cloned().collect();
+    let mut removal_snapshot = match options.removal_guard.check(canvas_path, &node_ids) {
+        Ok(s) => s,
+        Err(e) => {
+            report.error = Some(e.to_string());
+            return;
+        }
+    };
+    let archive_ids = archived_node_ids(canvas);
+    let groups = options.replacements.as_ref().map(|_| group_labels(canvas)).unwrap_or_default();
 
-    // Filter out already-published items
-    let new_items: HashMap<_, _> = all_items
-        .into_iter()
-        .filter(|(node_id, _)| !tracker.is_published(node_id))
-        .collect();
+    let exclusions = match ExclusionList::for_canvas(canvas_path) {
+        Ok(e) => e,
+        Err(e) => {
+            error!(error = %e, "Failed to load exclusions");
+            report.error = Some(format!("Failed to load exclusions: {}", e));
+            return;
+        }
+    };
 
-    let already_published = total_count - new_items.len();
-    debug!(
-        new_items = new_items.len(),
-        already_published = already_published,
-        "Filtered to new items only"
+    let edge_emphasis = options.edge_weights.as_ref().map(|weights| weights.of_canvas(canvas, options.edge_direction));
+    let unreadable = RefCell::new(Vec::new());
+    let select = |node: &Node, out: &OutAdjacencies, inc: &InAdjacencies| {
+        if !options.size.matches_node(node) {
+            return None;
+        }
+        if let Some(template) = &options.link_posts
+            && let Some(item) = options.selection.select_link(node, out, inc, template)
+        {
+            return Some(item);
+        }
+        options.selection.select(node, out, inc, &resolve).unwrap_or_else(|e| {
+            unreadable.borrow_mut().push((node.id().clone(), e));
+            None
+        })
+    };
+    let suppressed = RefCell::new(Vec::new());
+    let (mut all_items, dangling) = to_syndication_format_lenient(
+        canvas,
+        Some(|node: &Node, out: &OutAdjacencies, inc: &InAdjacencies| {
+            let item = select(node, out, inc)?;
+            if exclusions.is_excluded(node.id()) {
+                suppressed.borrow_mut().push(item.id);
+                return None;
+            }
+            Some(item)
+        }),
+        options.edge_direction,
     );
+    if let (DanglingEdges::Fail, Some(e)) = (options.dangling_edges, dangling.first()) {
+        error!(error = %e, "Canvas has a dangling edge");
+        report.error = Some(e.to_string());
+        return;
+    }
+    for e in &dangling {
+        report.warnings.push(Warning::new(codes::DANGLING_EDGE, "pipeline", e.to_string()));
+    }
+    for (node_id, e) in unreadable.into_inner() {
+        warn!(node_id = %node_id, error = %e, "Skipping File node");
+        let message = format!("Skipped: {}", e);
+        report.warnings.push(Warning::new(codes::FILE_UNREADABLE, "pipeline", message).for_node(&node_id));
+    }
+    let canvas_name = snapshot.path.file_name().and_then(|name| name.to_str()).map(str::to_string);
+    for item in all_items.values_mut() {
+        item.canvas_hash = Some(snapshot.hash.clone());
+        item.canvas_name = canvas_name.clone();
+    }
+    link_external(canvas, &mut all_items, &resolve);
+    apply_settings(canvas, &mut all_items, targets, report);
+
+    // Excluded nodes are dropped as items are made, so they never reach any later step nor
+    // appear as resolvable neighbors of other items
+    let mut suppressed = suppressed.into_inner();
+    suppressed.sort_by(cmp_node_ids);
+    for node_id in &suppressed {
+        warn!(node_id = %node_id, "Suppressed excluded node that matched the filter");
+        let message = "Matched the filter but is excluded, so it wasn't published";
+        report.warnings.push(Warning::new(codes::EXCLUDED_MATCH, "exclusions", message).for_node(node_id));
+    }
+    remove_items(&mut all_items, &suppressed);
+    report.excluded = suppressed.iter().map(|id| id.as_str().to_string()).collect();
+    report.items_matched = all_items.len() + suppressed.len();
+
+    // Oversized texts are dealt with before anything else looks at them
+    let oversized = limit_item_size(&mut all_items, &options.item_size, report);
+    remove_items(&mut all_items, &oversized);
+
+    // Transformations that changed each item's text, named if it ends up empty
+    let mut transformed: HashMap<NodeId, Vec<&'static str>> = HashMap::new();
+
+    // `#cw:topic` tokens come out of the text; a `cw` setting wins over them
+    for item in all_items.values_mut() {
+        let (text, topics) = take_cw_tokens(&item.text);
+        if !topics.is_empty() {
+            item.source_hash = Some(item.content_hash());
+            item.text = text;
+            transformed.entry(item.id.clone()).or_default().push("content warning tokens");
+        }
+        item.content_warning =
+            item.settings.content_warning.clone().or_else(|| (!topics.is_empty()).then(|| topics.join(", ")));
+        if let Some(topic) = &item.content_warning {
+            report.content_warnings.insert(item.id.as_str().to_string(), topic.clone());
+        }
+    }
+
+    // Every sink sees the expanded text; changes are still detected on the text as written
+    if let Some(token_options) = &options.tokens {
+        let now = options.clock.now();
+        for item in all_items.values_mut() {
+            let expansion = expand_tokens(&item.text, now, token_options);
+            for token in &expansion.unknown {
+                warn!(node_id = %item.id, token = %token, "Unknown token left as is");
+                let message = format!("Unknown token {} left as is", token);
+                report.warnings.push(Warning::new(codes::UNKNOWN_TOKEN, "tokens", message).for_node(&item.id));
+            }
+            if expansion.text != item.text {
+                item.source_hash = Some(item.content_hash());
+                item.text = expansion.text;
+                transformed.entry(item.id.clone()).or_default().push("date tokens");
+            }
+        }
+    }
+
+    // Like tokens, replacements come after the text as written is hashed, so editing a rule
+    // doesn't republish every item it touches
+    if let Some(replacements) = &options.replacements {
+        for item in all_items.values_mut() {
+            let (text, fired) = replacements.apply(&item.text, groups.get(&item.id).map_or(&[], Vec::as_slice));
+            for (rule, count) in fired {
+                debug!(node_id = %item.id, rule = %rule, count, "Replacement rule fired");
+                report.redactions.push(Redaction { node_id: item.id.to_string(), rule, count });
+            }
+            if text != item.text {
+                item.source_hash = Some(item.content_hash());
+                item.text = text;
+                transformed.entry(item.id.clone()).or_default().push("replacements");
+            }
+        }
+        report.redactions.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+    }
+
+    // Nothing is published for items left blank; like oversized ones they're not tracked,
+    // so they publish normally once they have content again
+    let empty = empty_items(&all_items, &transformed, report);
+    remove_items(&mut all_items, &empty);
+
+    if let (Some(weights), Some(edges)) = (&options.edge_weights, &edge_emphasis) {
+        for item in all_items.values_mut() {
+            weights.apply(item, edges);
+        }
+    }
+
+    for item in all_items.values_mut() {
+        let footnotes = extract_footnotes(&item.text);
+        for label in &footnotes.undefined_markers {
+            warn!(node_id = %item.id, label = %label, "Footnote marker has no definition");
+            let message = format!("Footnote marker [^{}] has no definition", label);
+            report.warnings.push(Warning::new(codes::FOOTNOTE_UNDEFINED, "footnotes", message).for_node(&item.id));
+        }
+        for label in &footnotes.unused_definitions {
+            warn!(node_id = %item.id, label = %label, "Footnote definition is never referenced");
+            let message = format!("Footnote definition [^{}] is never referenced", label);
+            report.warnings.push(Warning::new(codes::FOOTNOTE_UNUSED, "footnotes", message).for_node(&item.id));
+        }
+        item.references = footnotes.references;
+    }
+
+    for item in all_items.values_mut() {
+        let issues = check_accessibility(&item.text, &options.accessibility);
+        for issue in &issues {
+            warn!(node_id = %item.id, code = issue.code(), "{}", issue);
+            let message = match options.accessibility.policy {
+                AccessibilityPolicy::Warn => issue.to_string(),
+                AccessibilityPolicy::Block => format!("{}; not published to public-facing sinks until fixed", issue),
+            };
+            report.warnings.push(Warning::new(issue.code(), "accessibility", message).for_node(&item.id));
+        }
+        item.accessibility_blocked = !issues.is_empty() && options.accessibility.policy == AccessibilityPolicy::Block;
+    }
+
+    if let Some(enrichment_options) = &options.link_enrichment {
+        match LinkEnricher::for_canvas(canvas_path, enrichment_options.clone()) {
+            Ok(mut enricher) => {
+                for item in all_items.values_mut() {
+                    item.link_meta = enricher.enrich(&item.text);
+                }
+                if let Err(e) = enricher.save() {
+                    warn!(error = %e, "Failed to save link metadata cache");
+                }
+            }
+            Err(e) => warn!(error = %e, "Failed to load link metadata cache"),
+        }
+    }
+
+    if let Some(language_options) = &options.language {
+        for item in all_items.values_mut() {
+            item.language = detect_language(&item.text, language_options.min_confidence);
+            debug!(node_id = %item.id, language = ?item.language, "Detected language");
+        }
+    }
+
+    // A category set on the node or its groups wins over the tags, which include the set tags
+    for item in all_items.values_mut() {
+        let mut tags = hashtags(&item.text);
+        for tag in item.settings.tags.iter().flatten() {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        item.category =
+            item.settings.category.clone().or_else(|| options.categories.as_ref()?.category_for(&tags));
+    }
 
-    if new_items.is_empty() {
-        info!("No new items to publish");
+    for item in all_items.values_mut() {
+        item.published_at = first_published(targets.iter().map(|t| &t.tracker), &item.id);
+    }
+    if cancelled(cancel, report, "filter") {
         return;
     }
 
+    // Nodes in the Archive group are archived (if published) and never published anew
+    let in_archive: Vec<NodeId> = all_items
+        .keys()
+        .filter(|node_id| archive_ids.contains(*node_id))
+        .cloned()
+        .collect();
+    remove_items(&mut all_items, &in_archive);
+
+    // Nodes that live elsewhere get no post; they stay neighbors, so posts link to them
+    let mut external: Vec<(NodeId, String)> = all_items
+        .values()
+        .filter_map(|item| Some((item.id.clone(), item.external_url.clone()?)))
+        .collect();
+    external.sort_by(|a, b| cmp_node_ids(&a.0, &b.0));
+    all_items.retain(|_, item| item.external_url.is_none());
+    report.external = external.iter().map(|(node_id, _)| node_id.as_str().to_string()).collect();
+    // Only nodes that can still be published are dated; the log is saved past the last
+    // cancel point
+    let mut seen = date_items(canvas_path, &mut all_items, options);
     info!(
-        new_items = new_items.len(),
-        "Publishing new items"
+        total_items = all_items.len(),
+        excluded = suppressed.len(),
+        external = external.len(),
+        "Found items matching filter"
     );
 
-    // Collect node IDs before publishing (for tracking)
-    let published_ids: Vec<_> = new_items.keys().cloned().collect();
+    if let Some(duplicate_options) = &options.duplicates {
+        let groups = find_duplicate_groups(&all_items, duplicate_options, |node_id| {
+            targets.iter().any(|t| t.tracker.is_published(node_id))
+        });
 
-    match sink.publish(&new_items, dry_run) {
-        Ok(()) => {
-            info!("Successfully published all items");
+        let mut duplicates = Vec::new();
+        for group in &groups {
+            warn!(node_ids = ?group.node_ids, exact = group.exact, "Found duplicate items");
+            let kind = if group.exact { "Duplicate" } else { "Near-duplicate" };
+            for node_id in group.node_ids.iter().skip(1) {
+                let message = format!("{} of `{}`", kind, group.node_ids[0]);
+                report.warnings.push(Warning { node_id: Some(node_id.to_string()), ..Warning::new(codes::DUPLICATE, "duplicates", message) });
+            }
+            duplicates.extend_from_slice(suppressed_by_policy(group, duplicate_options.policy));
+        }
+        report.duplicate_groups = groups;
 
-            // Mark as published (skip in dry-run mode)
-            if !dry_run {
-                if let Err(e) = tracker.mark_published(&published_ids) {
-                    error!(error = %e, "Failed to save tracker");
-                }
+        if duplicate_options.policy == DuplicatePolicy::Fail && !report.duplicate_groups.is_empty() {
+            error!("Aborting run because duplicate items were found");
+            report.error = Some("Duplicate items found".to_string());
+            return;
+        }
+        remove_items(&mut all_items, &duplicates);
+    }
+
+    if let Some(link_check_options) = &options.link_check {
+        check_links(canvas_path, targets, &mut all_items, link_check_options, options.clock.now(), report);
+    }
+
+    report.already_published = all_items
+        .keys()
+        .filter(|node_id| targets.iter().all(|t| t.tracker.is_published(node_id)))
+        .count();
+
+    if let Some(promoted) = options.warnings.apply(&mut report.warnings) {
+        error!("Aborting run because of {}", promoted);
+        report.error = Some(promoted);
+        return;
+    }
+
+    // Last chance to cancel: from here on state and sinks are changed
+    if cancelled(cancel, report, "preflight") {
+        return;
+    }
+
+    for target in targets.iter_mut().filter(|t| !t.is_dry(dry_run)) {
+        if let Err(e) = target.tracker.mark_external(&external) {
+            error!(sink = %target.sink.name(), error = %e, "Failed to save tracker");
+        }
+    }
+    if !dry_run
+        && let Some(seen) = &mut seen
+        && let Err(e) = seen.save()
+    {
+        warn!(error = %e, "Failed to save first-seen log");
+    }
+
+    // Under a batch policy new items wait in the pending batch; a dry run publishes directly
+    if !dry_run
+        && let Err(e) = hold_back_batch(canvas_path, targets, &mut all_items, options, report)
+    {
+        error!(error = %e, "Failed to update pending batch");
+        report.error = Some(format!("Failed to update pending batch: {}", e));
+        return;
+    }
+
+    // In queue mode new items are only enqueued here; a dry run publishes directly instead.
+    // Limited runs leave items queued after failing alone
+    let queue = match &options.queue {
+        Some(_) if !dry_run => match enqueue_new_items(canvas_path, targets, &all_items, options) {
+            Ok(queue) => Some(queue),
+            Err(e) => {
+                error!(error = %e, "Failed to enqueue items");
+                report.error = Some(format!("Failed to enqueue items: {}", e));
+                return;
             }
+        },
+        None if options.max_new_items.is_some() => match OutboundQueue::for_canvas(canvas_path) {
+            Ok(queue) => Some(queue),
+            Err(e) => {
+                error!(error = %e, "Failed to load queue");
+                report.error = Some(format!("Failed to load queue: {}", e));
+                return;
+            }
+        },
+        _ => None,
+    };
+
+    // Every sink's share is planned, and journaled, before any sink is sent anything
+    let mut plans: HashMap<String, TargetPlan> = targets
+        .iter()
+        .map(|t| (t.sink.name().to_string(), plan_target(t, &all_items, &archive_ids, queue.as_ref(), options)))
+        .collect();
+    report.run_id = Some(journal::run_id(&snapshot.hash, all_items.keys()));
+    let mut journal = match open_journal(snapshot, &mut plans, resume, dry_run, options, report) {
+        Ok(journal) => journal,
+        Err(e) => {
+            error!(error = %e, "Failed to write run journal");
+            report.error = Some(format!("Failed to write run journal: {}", e));
+            return;
+        }
+    };
+
+    // Publish in waves: every sink whose dependencies have finished runs concurrently.
+    // Sinks left without a plan (done in the run being resumed) count as finished
+    let known: Vec<String> = targets.iter().map(|t| t.sink.name().to_string()).collect();
+    let (mut remaining, skipped): (Vec<&mut SinkTarget>, Vec<&mut SinkTarget>) =
+        targets.iter_mut().partition(|t| plans.contains_key(t.sink.name()));
+    let mut finished: Vec<String> = skipped.iter().map(|t| t.sink.name().to_string()).collect();
+
+    while !remaining.is_empty() {
+        let (mut wave, blocked): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|t| {
+            t.depends_on
+                .iter()
+                .all(|dep| finished.contains(dep) || !known.contains(dep))
+        });
+        remaining = blocked;
+
+        if wave.is_empty() {
+            warn!("Sink dependencies form a cycle, publishing the remaining sinks serially");
+            wave = std::mem::take(&mut remaining);
+            for target in wave {
+                let plan = plans.remove(target.sink.name()).expect("every remaining sink has a plan");
+                let outcome = publish_to_target(target, plan, dry_run, options);
+                complete_step(journal.as_mut(), &outcome);
+                report.sinks.push(outcome);
+            }
+            break;
+        }
+
+        let outcomes: Vec<SinkOutcome> = std::thread::scope(|scope| {
+            let handles: Vec<_> = wave
+                .into_iter()
+                .map(|target| {
+                    let plan = plans.remove(target.sink.name()).expect("every remaining sink has a plan");
+                    scope.spawn(move || publish_to_target(target, plan, dry_run, options))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Sink publishing thread panicked"))
+                .collect()
+        });
+
+        for outcome in &outcomes {
+            complete_step(journal.as_mut(), outcome);
+        }
+        finished.extend(outcomes.iter().map(|o| o.sink.clone()));
+        report.sinks.extend(outcomes);
+    }
+    if let Some(journal) = journal {
+        close_journal(journal, options);
+    }
+
+    if options.queue.is_none()
+        && options.max_new_items.is_some()
+        && !dry_run
+        && let Some(mut queue) = queue
+    {
+        enqueue_failed_items(&mut queue, &report.sinks, &all_items, options);
+    }
+
+    report.sinks.extend(resurface_due(canvas_path, targets, &all_items, dry_run, options));
+
+    record_history(canvas_path, &report.sinks, &all_items, options);
+    backfill_after_run(targets, &report.sinks, dry_run);
+
+    if !dry_run {
+        removal_snapshot.record(&node_ids, |node_id| targets.iter().any(|t| t.tracker.is_published(node_id)));
+        if let Err(e) = removal_snapshot.save() {
+            warn!(error = %e, "Failed to save removal snapshot");
         }
-        Err(e) => error!(error = %e, "Failed to publish items"),
     }
+
+    let mut new_items: Vec<String> = report.sinks.iter().flat_map(|o| o.items.clone()).collect();
+    new_items.sort();
+    new_items.dedup();
+    report.new_items = new_items;
+
+    let mut updated_items: Vec<String> = report.sinks.iter().flat_map(|o| o.updated.clone()).collect();
+    updated_items.sort();
+    updated_items.dedup();
+    report.updated_items = updated_items;
 }
 
-/// Watch the canvas file and process changes
+/// The journal of this run, saved before any sink is sent anything
 ///
-/// This function processes the canvas on startup, then watches for file changes
-/// and re-processes when modifications are detected.
-pub fn watch_and_process(
-    canvas_path: &Path,
-    mut sink: impl SyndicationSink,
-    mut tracker: SyndicationTracker,
+/// Resuming a journal skips the sinks it records as done and sends the others only what it
+/// planned for them. Otherwise an unfinished journal left behind is archived as it stands,
+/// and a run with nothing to send keeps no journal. Dry runs keep none either, but a
+/// resumed one still only shows what's left.
+fn open_journal(
+    snapshot: &CanvasSnapshot,
+    plans: &mut HashMap<String, TargetPlan>,
+    resume: Option<RunJournal>,
     dry_run: bool,
-    debounce_duration: Duration,
+    options: &ProcessOptions,
+    report: &mut ProcessReport,
+) -> Result<Option<RunJournal>, Box<dyn Error>> {
+    if let Some(journal) = resume {
+        info!(run_id = journal.run_id(), remaining = journal.remaining().count(), "Resuming unfinished run");
+        report.run_id = Some(journal.run_id().to_string());
+        report.resumed = true;
+        plans.retain(|sink, plan| match journal.step(sink) {
+            Some(step) if !step.completed => {
+                plan.restrict(&step.planned);
+                true
+            }
+            _ => {
+                debug!(sink, "Nothing left for the sink in the resumed run");
+                false
+            }
+        });
+        return Ok((!dry_run).then_some(journal));
+    }
+    if dry_run {
+        return Ok(None);
+    }
+    if let Some(unfinished) = RunJournal::for_canvas(&snapshot.path)? {
+        warn!(run_id = unfinished.run_id(), "Starting a new run over an unfinished one, archiving its journal");
+        unfinished.archive(options.report_writer.as_ref())?;
+    }
+
+    let run_id = report.run_id.clone().unwrap_or_default();
+    let mut steps: Vec<JournalStep> = plans
+        .iter()
+        .map(|(sink, plan)| JournalStep { sink: sink.clone(), planned: plan.node_ids(), completed: false })
+        .collect();
+    if steps.iter().all(|step| step.planned.is_empty()) {
+        return Ok(None);
+    }
+    steps.sort_by(|a, b| a.sink.cmp(&b.sink));
+    Ok(Some(RunJournal::begin(&snapshot.path, run_id, &snapshot.hash, options.clock.now(), steps)?))
+}
+
+/// Record a sink's step of the run done, unless it failed
+fn complete_step(journal: Option<&mut RunJournal>, outcome: &SinkOutcome) {
+    if let Some(journal) = journal
+        && outcome.error.is_none()
+        && let Err(e) = journal.complete_step(&outcome.sink)
+    {
+        warn!(sink = %outcome.sink, error = %e, "Failed to save run journal");
+    }
+}
+
+/// Archive the journal of a run whose every step is done into the reports directory (or
+/// remove it), or leave it to be resumed
+fn close_journal(journal: RunJournal, options: &ProcessOptions) {
+    if !journal.is_complete() {
+        let remaining: Vec<&str> = journal.remaining().map(|step| step.sink.as_str()).collect();
+        warn!(run_id = journal.run_id(), ?remaining, "Run left unfinished; resuming it retries only what it planned");
+        return;
+    }
+    if let Err(e) = journal.archive(options.report_writer.as_ref()) {
+        warn!(error = %e, "Failed to archive run journal");
+    }
+}
+
+/// Buffer new items per the batch policy, removing the ones still waiting from `all_items`
+///
+/// With [`BatchPolicy::Immediate`] a leftover batch (e.g. from before the policy changed,
+/// or flushed by `drain`) is simply published along with everything else.
+fn hold_back_batch(
+    canvas_path: &Path,
+    targets: &[SinkTarget],
+    all_items: &mut HashMap<NodeId, SyndicationFormat>,
+    options: &ProcessOptions,
+    report: &mut ProcessReport,
 ) -> Result<(), Box<dyn Error>> {
-    // Process on startup
-    info!("Processing canvas file on startup...");
-    process_canvas(canvas_path, &mut sink, &mut tracker, dry_run);
+    let mut batch = PendingBatch::for_canvas(canvas_path)?;
+    if options.batching == BatchPolicy::Immediate {
+        if !batch.is_empty() {
+            info!(count = batch.len(), "Publishing pending batch");
+            batch.clear();
+            batch.save()?;
+        }
+        return Ok(());
+    }
 
-    // Setup file watcher
-    let (tx, rx) = std::sync::mpsc::channel();
-    let mut debouncer = new_debouncer(debounce_duration, tx)?;
+    let now = options.clock.now();
+    let was_empty = batch.is_empty();
+    let mut candidates: Vec<NodeId> = all_items
+        .keys()
+        .filter(|node_id| {
+            targets
+                .iter()
+                .any(|t| !t.dry_run && !t.tracker.is_published(node_id) && !t.tracker.is_skipped(node_id))
+        })
+        .cloned()
+        .collect();
+    candidates.sort_by(cmp_node_ids);
+    let added = batch.update(&candidates, now);
 
-    debouncer
-        .watcher()
-        .watch(canvas_path, RecursiveMode::NonRecursive)?;
+    let due = match options.batching {
+        BatchPolicy::Window(window) => batch.deadline(window).is_some_and(|deadline| now >= deadline),
+        _ => false,
+    };
+    if due {
+        info!(count = batch.len(), "Batch window closed, publishing batch");
+        batch.clear();
+    } else if !batch.is_empty() {
+        let held = batch.node_ids();
+        info!(added, buffered = held.len(), policy = ?options.batching, "Holding new items in pending batch");
+        remove_items(all_items, &held);
+        report.batched = held.iter().map(|id| id.as_str().to_string()).collect();
+    }
+    if !(was_empty && batch.is_empty()) {
+        batch.save()?;
+    }
+    Ok(())
+}
 
-    info!("Watching for file changes...");
+/// When the pending batch of a canvas closes, if it's batched by a window
+pub(crate) fn batch_deadline(canvas_path: &Path, options: &ProcessOptions) -> Option<DateTime<Local>> {
+    let BatchPolicy::Window(window) = options.batching else {
+        return None;
+    };
+    PendingBatch::for_canvas(canvas_path).ok()?.deadline(window)
+}
 
-    for res in rx {
-        match res {
-            Ok(events) => {
-                for event in events {
-                    if let DebouncedEventKind::Any = event.kind {
-                        info!("File changed, processing...");
-                        process_canvas(canvas_path, &mut sink, &mut tracker, dry_run);
-                    }
+/// Re-surface an old item on every sink whose turn it is, each in a batch of its own
+///
+/// Dry sinks show what they'd post without it counting as their turn.
+fn resurface_due(
+    canvas_path: &Path,
+    targets: &mut [SinkTarget],
+    all_items: &HashMap<NodeId, SyndicationFormat>,
+    dry_run: bool,
+    options: &ProcessOptions,
+) -> Vec<SinkOutcome> {
+    let now = options.clock.now();
+    let mut outcomes = Vec::new();
+    for resurface in &options.resurface {
+        let Some(target) = targets.iter_mut().find(|t| t.sink.name() == resurface.sink) else {
+            warn!(sink = %resurface.sink, "Re-surfacing on an unknown sink, skipping");
+            continue;
+        };
+        let dry_run = target.is_dry(dry_run);
+        let mut log = match ResurfaceLog::for_canvas(canvas_path, &resurface.sink) {
+            Ok(log) => log,
+            Err(e) => {
+                error!(sink = %resurface.sink, error = %e, "Failed to load re-surface log");
+                continue;
+            }
+        };
+        if !log.is_due(resurface.every, now) {
+            continue;
+        }
+
+        let eligible = all_items
+            .values()
+            .filter(|item| item.settings.routes_to(&resurface.sink))
+            .filter(|item| !(item.blocked_from_public() && target.public_facing));
+        let candidates = resurface.candidates(eligible.map(|item| &item.id), &target.tracker, &log, now);
+        let picked = pick(&candidates, now).cloned();
+        let mut outcome = SinkOutcome::new(&resurface.sink, dry_run, &[]);
+        match &picked {
+            Some(node_id) => {
+                let item = &all_items[node_id];
+                // Keyed by the time too, so no earlier post of the item is taken for this one
+                let occasion = format!("{}@{}", resurface.sink, now.to_rfc3339());
+                let key = idempotency_key(node_id, &item.content_hash(), &occasion);
+                let item = SyndicationFormat {
+                    text: resurface.prefixed(&item.text),
+                    idempotency_key: Some(key),
+                    ..item.clone()
+                };
+                info!(sink = %resurface.sink, node_id = %node_id, candidates = candidates.len(), "Re-surfacing item");
+                match resurface_item(target, &item, dry_run) {
+                    Ok(()) => outcome.resurfaced.push(node_id.as_str().to_string()),
+                    Err(e) => outcome.set_error(&e),
                 }
+                take_warnings(target, &mut outcome, options);
+            }
+            None => info!(sink = %resurface.sink, "Nothing old enough to re-surface"),
+        }
+
+        // A failed attempt counts as the turn too, so a broken sink isn't retried every run
+        if !dry_run {
+            log.record(picked.as_ref().filter(|_| outcome.error.is_none()), now);
+            if let Err(e) = log.save() {
+                error!(sink = %resurface.sink, error = %e, "Failed to save re-surface log");
             }
-            Err(error) => error!(error = ?error, "Watch error"),
+        }
+        if picked.is_some() {
+            outcomes.push(outcome);
         }
     }
+    outcomes
+}
 
-    Ok(())
+/// Post an item again in a batch of its own, aborting it if that fails
+fn resurface_item(target: &mut SinkTarget, item: &SyndicationFormat, dry_run: bool) -> Result<(), SinkError> {
+    let sink_name = target.sink.name().to_string();
+    if let Err(e) = target.sink.begin(dry_run) {
+        let e = e.in_sink(&sink_name);
+        error!(error = %e, "Failed to begin re-surface batch");
+        return Err(e);
+    }
+    let posted = target
+        .sink
+        .resurface(item, dry_run)
+        .and_then(|()| target.sink.commit(dry_run))
+        .map_err(|e| e.for_item(&item.id).in_sink(&sink_name));
+    if let Err(e) = &posted {
+        error!(error = %e, "Failed to re-surface item, aborting batch");
+        if let Err(abort_error) = target.sink.abort(dry_run) {
+            error!(sink = %sink_name, error = %abort_error, "Failed to abort re-surface batch");
+        }
+    }
+    posted
+}
+
+/// When the next sink is due to re-surface an item, leaving out sinks that are dry
+pub(crate) fn resurface_deadline(
+    canvas_path: &Path,
+    targets: &[SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+) -> Option<DateTime<Local>> {
+    options
+        .resurface
+        .iter()
+        .filter(|resurface| targets.iter().any(|t| t.sink.name() == resurface.sink && !t.is_dry(dry_run)))
+        .filter_map(|resurface| ResurfaceLog::for_canvas(canvas_path, &resurface.sink).ok()?.next_due(resurface.every))
+        .min()
+}
+
+/// Append the events of successful outcomes to the canvas's history log
+fn record_history(
+    canvas_path: &Path,
+    outcomes: &[SinkOutcome],
+    items: &HashMap<NodeId, SyndicationFormat>,
+    options: &ProcessOptions,
+) {
+    let events = HistoryLog::events_for_outcomes(outcomes, items, options.clock.now(), &options.text);
+    if events.is_empty() {
+        return;
+    }
+    let appended = HistoryLog::for_canvas(canvas_path)
+        .and_then(|log| log.with_max_bytes(options.history_max_bytes).append(&events));
+    if let Err(e) = appended {
+        error!(error = %e, "Failed to record history");
+    }
+}
+
+/// Cut items over the size limit down to it, or list them to be skipped, per the policy
+fn limit_item_size(
+    items: &mut HashMap<NodeId, SyndicationFormat>,
+    limit: &ItemSizeLimit,
+    report: &mut ProcessReport,
+) -> Vec<NodeId> {
+    let mut skipped = Vec::new();
+    for item in items.values_mut().filter(|item| limit.exceeds(&item.text)) {
+        let bytes = item.text.len();
+        match limit.policy {
+            OversizePolicy::Skip => {
+                warn!(node_id = %item.id, bytes, max_bytes = limit.max_bytes, "Text over the size limit, skipping");
+                let message = format!("{} bytes is over the limit of {}, skipped", bytes, limit.max_bytes);
+                report.warnings.push(Warning::new(codes::OVERSIZE_SKIPPED, "limits", message).for_node(&item.id));
+                skipped.push(item.id.clone());
+            }
+            OversizePolicy::Truncate => {
+                limit.truncate(&mut item.text);
+                warn!(node_id = %item.id, bytes, max_bytes = limit.max_bytes, "Text over the size limit, truncated");
+                let message = format!("{} bytes is over the limit of {}, truncated", bytes, limit.max_bytes);
+                report.warnings.push(Warning::new(codes::OVERSIZE_TRUNCATED, "limits", message).for_node(&item.id));
+            }
+        }
+    }
+    skipped
+}
+
+/// Items whose text is empty or whitespace, with a warning naming the transformations that
+/// left it so
+fn empty_items(
+    items: &HashMap<NodeId, SyndicationFormat>,
+    transformed: &HashMap<NodeId, Vec<&'static str>>,
+    report: &mut ProcessReport,
+) -> Vec<NodeId> {
+    let mut empty: Vec<NodeId> =
+        items.values().filter(|item| item.text.trim().is_empty()).map(|item| item.id.clone()).collect();
+    empty.sort_by(cmp_node_ids);
+    for node_id in &empty {
+        let message = match transformed.get(node_id) {
+            Some(steps) => format!("Nothing left to publish after {}, skipped", steps.join(" and ")),
+            None => "Text is empty, skipped".to_string(),
+        };
+        warn!(node_id = %node_id, "{}", message);
+        report.warnings.push(Warning::new(codes::EMPTY_ITEM, "pipeline", message).for_node(node_id));
+    }
+    empty
+}
+
+/// Remove items and scrub them from the remaining items' neighbor lists
+fn remove_items(items: &mut HashMap<NodeId, SyndicationFormat>, node_ids: &[NodeId]) {
+    if node_ids.is_empty() {
+        return;
+    }
+    for node_id in node_ids {
+        items.remove(node_id);
+    }
+    for item in items.values_mut() {
+        item.in_neighbor_ids.retain(|id| !node_ids.contains(id));
+        item.out_neighbor_ids.retain(|id| !node_ids.contains(id));
+        item.neighbor_emphasis.retain(|id, _| !node_ids.contains(id));
+    }
+}
+
+/// Items not yet published to a target, excluding items routed to other sinks
+fn unpublished_items(
+    target: &SinkTarget,
+    all_items: &HashMap<NodeId, SyndicationFormat>,
+    options: &ProcessOptions,
+) -> HashMap<NodeId, SyndicationFormat> {
+    let now = options.clock.now();
+    let mut items = routed_items(target, all_items, options);
+    items.retain(|node_id, item| {
+        !target.tracker.is_published(node_id) && !target.tracker.is_skipped(node_id) && !target.is_too_old(item, now)
+    });
+    items
+}
+
+/// Record which items and neighbors of items live elsewhere (see [`external_link_of`])
+///
+/// Only the nodes of items and their neighbors are looked at, so File nodes elsewhere on
+/// the canvas aren't read. Link posts get posts of their own, so they're linked to like any
+/// other item.
+fn link_external(canvas: &JsonCanvas, all_items: &mut HashMap<NodeId, SyndicationFormat>, resolve: &ResolveOptions) {
+    let nodes = canvas.get_nodes();
+    let mut links: HashMap<NodeId, Option<ExternalLink>> =
+        all_items.values().filter(|item| item.url.is_some()).map(|item| (item.id.clone(), None)).collect();
+    for item in all_items.values() {
+        for node_id in std::iter::once(&item.id).chain(&item.in_neighbor_ids).chain(&item.out_neighbor_ids) {
+            if let (false, Some(node)) = (links.contains_key(node_id), nodes.get(node_id)) {
+                links.insert(node_id.clone(), external_link_of(&content_of(node, resolve)));
+            }
+        }
+    }
+
+    for item in all_items.values_mut() {
+        item.external_url = links.get(&item.id).cloned().flatten().map(|link| link.url);
+        item.external_neighbors = item
+            .in_neighbor_ids
+            .iter()
+            .chain(&item.out_neighbor_ids)
+            .filter_map(|node_id| Some((node_id.clone(), links.get(node_id)?.clone()?)))
+            .collect();
+    }
+}
+
+/// Unpublished items too old for a target with a maximum item age, not skipped yet
+fn aged_out_items(
+    target: &SinkTarget,
+    all_items: &HashMap<NodeId, SyndicationFormat>,
+    options: &ProcessOptions,
+) -> Vec<NodeId> {
+    let now = options.clock.now();
+    let mut node_ids: Vec<NodeId> = routed_items(target, all_items, options)
+        .into_iter()
+        .filter(|(node_id, item)| {
+            !target.tracker.is_published(node_id) && !target.tracker.is_skipped(node_id) && target.is_too_old(item, now)
+        })
+        .map(|(node_id, _)| node_id)
+        .collect();
+    node_ids.sort_by(cmp_node_ids);
+    node_ids
+}
+
+/// Date every item for maximum item ages: by its frontmatter `date`, else by when the node
+/// was first seen, recording nodes seen for the first time in the log returned for saving
+///
+/// Nodes published before first-seen times were kept count as first seen when first published.
+fn date_items(
+    canvas_path: &Path,
+    all_items: &mut HashMap<NodeId, SyndicationFormat>,
+    options: &ProcessOptions,
+) -> Option<FirstSeenLog> {
+    let now = options.clock.now();
+    let mut seen = match FirstSeenLog::for_canvas(canvas_path) {
+        Ok(seen) => Some(seen),
+        Err(e) => {
+            warn!(error = %e, "Failed to load first-seen log, dating items by their frontmatter only");
+            None
+        }
+    };
+    for item in all_items.values_mut() {
+        let seen_at = item.published_at.map_or(now, |at| at.min(now));
+        let first_seen = seen.as_mut().map(|seen| seen.record(&item.id, seen_at));
+        item.dated_at = frontmatter_date(&item.text).or(first_seen);
+    }
+    seen
+}
+
+/// Items routed to a target, published or not, with their idempotency keys for it
+///
+/// Items held back for accessibility issues or broken links aren't routed to public-facing
+/// targets, and items published too long ago aren't routed to targets taking only recent ones.
+fn routed_items(
+    target: &SinkTarget,
+    all_items: &HashMap<NodeId, SyndicationFormat>,
+    options: &ProcessOptions,
+) -> HashMap<NodeId, SyndicationFormat> {
+    let sink_name = target.sink.name();
+    let now = options.clock.now();
+    all_items
+        .iter()
+        .filter(|(_, item)| {
+            options
+                .language
+                .as_ref()
+                .is_none_or(|l| l.routes_to(item.language.as_deref(), sink_name))
+        })
+        .filter(|(_, item)| item.settings.routes_to(sink_name))
+        .filter(|(_, item)| !(item.blocked_from_public() && target.public_facing))
+        .filter(|(_, item)| target.published_within.is_none_or(|days| published_within(item.published_at, days, now)))
+        .map(|(node_id, item)| {
+            let key = idempotency_key(node_id, &item.content_hash(), sink_name);
+            (node_id.clone(), SyndicationFormat { idempotency_key: Some(key), ..item.clone() })
+        })
+        .collect()
+}
+
+/// Check the links of the items some target is about to get new or edited, rewriting those
+/// that moved permanently and holding back items with broken ones, per `link_options`
+fn check_links(
+    canvas_path: &Path,
+    targets: &[SinkTarget],
+    all_items: &mut HashMap<NodeId, SyndicationFormat>,
+    link_options: &LinkCheckOptions,
+    now: DateTime<Local>,
+    report: &mut ProcessReport,
+) {
+    let mut checker = match LinkChecker::for_canvas(canvas_path, link_options.clone(), now) {
+        Ok(checker) => checker,
+        Err(e) => {
+            warn!(error = %e, "Failed to load link check cache");
+            return;
+        }
+    };
+    let pending = |item: &SyndicationFormat| {
+        targets.iter().any(|t| {
+            !t.tracker.is_published(&item.id)
+                || t.tracker.content_hash(&item.id).is_some_and(|hash| hash != item.content_hash())
+        })
+    };
+    let mut node_ids: Vec<NodeId> = all_items
+        .values()
+        .filter(|item| pending(item))
+        .map(|item| item.id.clone())
+        .collect();
+    node_ids.sort_by(cmp_node_ids);
+
+    for node_id in &node_ids {
+        let Some(item) = all_items.get_mut(node_id) else { continue };
+        let mut moved = HashMap::new();
+        for url in find_links(&item.text) {
+            let status = match checker.check(&url, now) {
+                Some(LinkStatus::Ok) | None => continue,
+                Some(status) => status,
+            };
+            let rewritten = match &status {
+                LinkStatus::Redirect { to, permanent: true } if link_options.rewrite_redirects => {
+                    moved.insert(url.clone(), to.clone());
+                    true
+                }
+                _ => false,
+            };
+            let blocked = status.is_broken() && link_options.policy == LinkCheckPolicy::Block;
+            item.broken_links_blocked |= blocked;
+
+            warn!(node_id = %item.id, url = %url, status = %status, "Link isn't plainly ok");
+            let mut message = format!("Link <{}> {}", url, status);
+            if rewritten {
+                message.push_str("; rewritten to where it moved");
+            } else if blocked {
+                message.push_str("; not published to public-facing sinks until fixed");
+            }
+            let code = if status.is_broken() { codes::BROKEN_LINK } else { codes::REDIRECTED_LINK };
+            report.warnings.push(Warning::new(code, "link-check", message).for_node(&item.id));
+            report.link_findings.push(LinkFinding { node_id: item.id.to_string(), url, status, rewritten });
+        }
+        // Like replacements, a rewrite doesn't count as an edit of the node
+        if !moved.is_empty() {
+            item.source_hash = Some(item.content_hash());
+            item.text = rewrite_links(&item.text, &moved);
+        }
+    }
+
+    if let Err(e) = checker.save() {
+        warn!(error = %e, "Failed to save link check cache");
+    }
+}
+
+/// Give each item the settings of its frontmatter over those of the groups around it
+fn apply_settings(
+    canvas: &JsonCanvas,
+    all_items: &mut HashMap<NodeId, SyndicationFormat>,
+    targets: &[SinkTarget],
+    report: &mut ProcessReport,
+) {
+    let sink_names: Vec<&str> = targets.iter().map(|target| target.sink.name()).collect();
+    let (groups, warnings) = group_settings(canvas, &sink_names);
+    for warning in warnings {
+        warn!(node_id = ?warning.node_id, "{}", warning.message);
+        report.warnings.push(warning);
+    }
+    for item in all_items.values_mut() {
+        let inherited = groups.get(&item.id).cloned().unwrap_or_default();
+        item.settings = ItemSettings::from_frontmatter(&item.text).or(&inherited);
+    }
+}
+
+/// Queue every target's unpublished items and save the queue
+fn enqueue_new_items(
+    canvas_path: &Path,
+    targets: &[SinkTarget],
+    all_items: &HashMap<NodeId, SyndicationFormat>,
+    options: &ProcessOptions,
+) -> Result<OutboundQueue, Box<dyn Error>> {
+    let mut queue = OutboundQueue::for_canvas(canvas_path)?;
+    let now = options.clock.now();
+
+    let mut enqueued = 0;
+    // Dry sinks publish directly, so nothing they'd "deliver" lingers in the queue
+    for target in targets.iter().filter(|t| !t.dry_run) {
+        for item in unpublished_items(target, all_items, options).values() {
+            if queue.enqueue(target.sink.name(), item, now) {
+                enqueued += 1;
+            }
+        }
+    }
+
+    if enqueued > 0 {
+        info!(enqueued, queued = queue.len(), "Enqueued new items");
+        queue.save()?;
+    }
+    Ok(queue)
+}
+
+/// Queue the items each failed sink was handed, with the failure recorded, so limited runs
+/// move on to the next items and [`drain_queue`] retries these
+fn enqueue_failed_items(
+    queue: &mut OutboundQueue,
+    outcomes: &[SinkOutcome],
+    all_items: &HashMap<NodeId, SyndicationFormat>,
+    options: &ProcessOptions,
+) {
+    let now = options.clock.now();
+    let delivery = DeliveryOptions::default();
+    let mut enqueued = 0;
+    for outcome in outcomes.iter().filter(|o| !o.dry_run) {
+        let Some(error) = &outcome.error else {
+            continue;
+        };
+        let node_ids: Vec<NodeId> = outcome.items.iter().filter_map(|id| id.parse().ok()).collect();
+        for item in node_ids.iter().filter_map(|node_id| all_items.get(node_id)) {
+            if queue.enqueue(&outcome.sink, item, now) {
+                enqueued += 1;
+            }
+        }
+        queue.record_failure(&outcome.sink, &node_ids, error, outcome.error_permanent, now, &delivery);
+    }
+
+    if enqueued > 0 {
+        warn!(enqueued, "Queued items sinks failed to take for retrying");
+        if let Err(e) = queue.save() {
+            error!(error = %e, "Failed to save queue");
+        }
+    }
+}
+
+/// What one sink is to be sent in a run, worked out from its tracker before any sink is
+/// sent anything
+struct TargetPlan {
+    new_items: HashMap<NodeId, SyndicationFormat>,
+    /// New items over the per-run limit, left for a later run
+    held_back: Vec<NodeId>,
+    updated_items: HashMap<NodeId, SyndicationFormat>,
+    to_archive: Vec<NodeId>,
+    to_unarchive: Vec<NodeId>,
+    /// Content hashes to record, including those of edits the sink doesn't take
+    hashes: Vec<(NodeId, String)>,
+    /// Unpublished items too old for the sink
+    aged_out: Vec<NodeId>,
+}
+
+impl TargetPlan {
+    /// Every node the sink is to be sent, ordered by ID
+    fn node_ids(&self) -> Vec<String> {
+        let mut node_ids: Vec<&NodeId> = self
+            .new_items
+            .keys()
+            .chain(self.updated_items.keys())
+            .chain(&self.to_archive)
+            .chain(&self.to_unarchive)
+            .collect();
+        node_ids.sort_by(|a, b| cmp_node_ids(a, b));
+        node_ids.dedup();
+        node_ids.into_iter().map(|id| id.as_str().to_string()).collect()
+    }
+
+    /// Keep only what was `planned` for the sink by an earlier run, so resuming it sends
+    /// nothing new; the hashes of edits dropped here aren't recorded either
+    fn restrict(&mut self, planned: &[String]) {
+        let is_planned = |node_id: &NodeId| planned.iter().any(|p| p == node_id.as_str());
+        let dropped: HashSet<NodeId> = self
+            .new_items
+            .keys()
+            .chain(self.updated_items.keys())
+            .filter(|node_id| !is_planned(node_id))
+            .cloned()
+            .collect();
+        self.new_items.retain(|node_id, _| is_planned(node_id));
+        self.updated_items.retain(|node_id, _| is_planned(node_id));
+        self.to_archive.retain(|node_id| is_planned(node_id));
+        self.to_unarchive.retain(|node_id| is_planned(node_id));
+        self.hashes.retain(|(node_id, _)| !dropped.contains(node_id));
+        self.held_back.clear();
+    }
+}
+
+/// Work out what a target is to be sent in this run
+fn plan_target(
+    target: &SinkTarget,
+    all_items: &HashMap<NodeId, SyndicationFormat>,
+    archive_ids: &HashSet<NodeId>,
+    queue: Option<&OutboundQueue>,
+    options: &ProcessOptions,
+) -> TargetPlan {
+    let sink_name = target.sink.name();
+
+    let mut new_items = unpublished_items(target, all_items, options);
+    if let Some(queue) = queue {
+        new_items.retain(|node_id, _| !queue.contains(sink_name, node_id));
+    }
+
+    debug!(
+        sink = %sink_name,
+        new_items = new_items.len(),
+        already_published = all_items.len() - new_items.len(),
+        "Filtered to new items only"
+    );
+
+    let mut held_back = Vec::new();
+    if let Some(limit) = options.max_new_items
+        && new_items.len() > limit
+    {
+        let mut node_ids: Vec<NodeId> = new_items.keys().cloned().collect();
+        node_ids.sort_by(cmp_node_ids);
+        held_back = node_ids.split_off(limit);
+        let chunk: HashSet<NodeId> = node_ids.into_iter().collect();
+        new_items.retain(|node_id, _| chunk.contains(node_id));
+        info!(sink = %sink_name, limit, held_back = held_back.len(), "Holding back new items over the limit");
+    }
+
+    let mut to_archive: Vec<NodeId> = archive_ids
+        .iter()
+        .filter(|node_id| target.tracker.is_published(node_id) && !target.tracker.is_archived(node_id))
+        .cloned()
+        .collect();
+    to_archive.sort_by(cmp_node_ids);
+
+    let mut to_unarchive: Vec<NodeId> = all_items
+        .keys()
+        .filter(|node_id| target.tracker.is_archived(node_id))
+        .cloned()
+        .collect();
+    to_unarchive.sort_by(cmp_node_ids);
+
+    // Edited items go to the sink as updates; published items without a recorded hash
+    // (from before hashes were tracked) get one now, without being delivered again
+    let mut updated_items = HashMap::new();
+    let mut hashes: Vec<(NodeId, String)> = new_items
+        .values()
+        .map(|item| (item.id.clone(), item.content_hash()))
+        .collect();
+    for (node_id, item) in routed_items(target, all_items, options) {
+        if target.tracker.is_archived(&node_id) || new_items.contains_key(&node_id) {
+            continue;
+        }
+        match ItemChange::of(&item, &target.tracker) {
+            ItemChange::Updated if target.sink.supports_updates() => {
+                hashes.push((node_id.clone(), item.content_hash()));
+                updated_items.insert(node_id, item);
+            }
+            ItemChange::Updated => {
+                debug!(sink = %sink_name, node_id = %node_id, "Sink doesn't take updates, recording the edit only");
+                hashes.push((node_id, item.content_hash()));
+            }
+            ItemChange::Unchanged if target.tracker.content_hash(&node_id).is_none() => {
+                hashes.push((node_id, item.content_hash()));
+            }
+            _ => {}
+        }
+    }
+
+    let aged_out = aged_out_items(target, all_items, options);
+    TargetPlan { new_items, held_back, updated_items, to_archive, to_unarchive, hashes, aged_out }
+}
+
+/// Publish the items not yet published to a single sink and update its tracker
+///
+/// Published nodes in `archive_ids` are archived first, and archived nodes that are
+/// syndication candidates again are un-archived. Items in `queue` are left to
+/// [`drain_queue`].
+fn publish_to_target(
+    target: &mut SinkTarget,
+    plan: TargetPlan,
+    dry_run: bool,
+    options: &ProcessOptions,
+) -> SinkOutcome {
+    let sink_name = target.sink.name().to_string();
+    let dry_run = target.is_dry(dry_run);
+    let TargetPlan { new_items, held_back, updated_items, to_archive, to_unarchive, hashes, aged_out } = plan;
+
+    // Collect node IDs before publishing (for tracking)
+    let mut published_ids: Vec<_> = new_items.keys().cloned().collect();
+    published_ids.sort_by(cmp_node_ids);
+
+    // Items too old for the sink are settled as skipped without reaching it
+    if !aged_out.is_empty() {
+        let max_item_age = target.max_item_age;
+        info!(sink = %sink_name, count = aged_out.len(), max_item_age, "Skipping items too old for the sink");
+        if !dry_run && let Err(e) = target.tracker.mark_skipped(&aged_out) {
+            error!(sink = %sink_name, error = %e, "Failed to save tracker");
+        }
+    }
+
+    let mut outcome = SinkOutcome::new(&sink_name, dry_run, &published_ids);
+    outcome.age_skipped = aged_out.iter().map(|id| id.as_str().to_string()).collect();
+    outcome.held_back = held_back.iter().map(|id| id.as_str().to_string()).collect();
+
+    if new_items.is_empty() && updated_items.is_empty() && to_archive.is_empty() && to_unarchive.is_empty() {
+        info!(sink = %sink_name, "No new items to publish");
+        if !dry_run && let Err(e) = target.tracker.record_content_hashes(&hashes) {
+            error!(sink = %sink_name, error = %e, "Failed to save tracker");
+        }
+        return outcome;
+    }
+
+    match deliver(target, &new_items, &updated_items, &to_archive, &to_unarchive, dry_run) {
+        Ok(changes) => {
+            info!(sink = %sink_name, "Successfully published all items");
+
+            // Update the tracker (skip in dry-run mode)
+            if !dry_run {
+                let sources = canvas_hashes(new_items.values().chain(updated_items.values()));
+                let saved = target
+                    .tracker
+                    .mark_published_at(&published_ids, options.clock.now())
+                    .and_then(|()| target.tracker.mark_archived(&changes.archived))
+                    .and_then(|()| target.tracker.mark_unarchived(&changes.unarchived))
+                    .and_then(|()| target.tracker.record_content_hashes(&hashes))
+                    .and_then(|()| target.tracker.record_canvas_hashes(&sources));
+                if let Err(e) = saved {
+                    error!(sink = %sink_name, error = %e, "Failed to save tracker");
+                }
+            }
+
+            outcome.archived = changes.archived.iter().map(|id| id.as_str().to_string()).collect();
+            outcome.unarchived = changes.unarchived.iter().map(|id| id.as_str().to_string()).collect();
+            outcome.updated = changes.updated.iter().map(|id| id.as_str().to_string()).collect();
+        }
+        Err(e) => outcome.set_error(&e),
+    }
+    outcome.urls = post_urls(target, |node_id| new_items.contains_key(node_id) || updated_items.contains_key(node_id));
+    outcome.planned_writes = planned_writes(target);
+    outcome.planned_commit = target.sink.take_planned_commit();
+    take_warnings(target, &mut outcome, options);
+
+    outcome
+}
+
+/// Canvas version each item was read from, for the items that carry one
+fn canvas_hashes<'a>(items: impl Iterator<Item = &'a SyndicationFormat>) -> Vec<(NodeId, String)> {
+    items.filter_map(|item| Some((item.id.clone(), item.canvas_hash.clone()?))).collect()
+}
+
+/// URLs the target's sink reported for the posts of the last batch, by node ID
+///
+/// URLs for nodes the batch didn't hand the sink are dropped, so a sink mixing up IDs never
+/// links a post to the wrong node.
+fn post_urls(target: &mut SinkTarget, handed: impl Fn(&NodeId) -> bool) -> BTreeMap<String, String> {
+    let sink_name = target.sink.name().to_string();
+    target
+        .sink
+        .take_post_urls()
+        .into_iter()
+        .filter(|(node_id, url)| {
+            let ok = handed(node_id);
+            if !ok {
+                warn!(sink = %sink_name, node_id = %node_id, url = %url, "Ignoring URL for a node not in the batch");
+            }
+            ok
+        })
+        .map(|(node_id, url)| (node_id.as_str().to_string(), url))
+        .collect()
+}
+
+/// Files the target's sink would have written in the last dry batch, summarized in the log
+fn planned_writes(target: &mut SinkTarget) -> Vec<PlannedWrite> {
+    let mut writes = target.sink.take_planned_writes();
+    if writes.is_empty() {
+        return writes;
+    }
+    writes.sort_by(|a, b| a.path.cmp(&b.path));
+    let summary = WriteSummary::of(&writes);
+    info!(
+        sink = %target.sink.name(),
+        create = summary.create,
+        modify = summary.modify,
+        identical = summary.identical,
+        "[DRY RUN] Compared with published files"
+    );
+    for write in writes.iter().filter(|w| w.kind == WriteKind::Identical) {
+        info!(file = %write.path.display(), "[DRY RUN] Identical to the published file, a real run would leave it as it is");
+    }
+    writes
+}
+
+/// Warnings the target's sink raised in the last batch, under the warning policy; a
+/// promoted warning fails the outcome
+fn take_warnings(target: &mut SinkTarget, outcome: &mut SinkOutcome, options: &ProcessOptions) {
+    let mut warnings = target.sink.take_warnings();
+    if let Some(promoted) = options.warnings.apply(&mut warnings)
+        && outcome.error.is_none()
+    {
+        error!(sink = %outcome.sink, "{}", promoted);
+        outcome.error = Some(promoted);
+    }
+    outcome.warnings = warnings;
+}
+
+/// The `(sink name, URL)` pairs of every node posted elsewhere in a run
+fn syndication_links(outcomes: &[SinkOutcome]) -> SyndicationLinks {
+    let mut links = SyndicationLinks::new();
+    for outcome in outcomes.iter().filter(|o| !o.dry_run) {
+        for (node_id, url) in &outcome.urls {
+            if let Ok(node_id) = node_id.parse::<NodeId>() {
+                links.entry(node_id).or_default().push((outcome.sink.clone(), url.clone()));
+            }
+        }
+    }
+    links
+}
+
+/// Link the posts made on other sites in a run from the canonical copies, logging failures
+fn backfill_after_run(targets: &mut [SinkTarget], outcomes: &[SinkOutcome], dry_run: bool) {
+    let links = syndication_links(outcomes);
+    for backfill in backfill_syndication_links(targets, &links, dry_run) {
+        if let Err(e) = backfill.changed {
+            error!(error = %e, node_id = ?e.node_id().map(NodeId::as_str), "Failed to add syndication links");
+        }
+    }
+}
+
+/// What adding syndication links did to one sink's published copies
+#[derive(Debug)]
+pub struct LinkBackfill {
+    pub sink: String,
+    /// Number of copies changed, or why the sink failed
+    pub changed: Result<usize, SinkError>,
+}
+
+/// Add syndication links (`(sink name, URL)` pairs per node) to the published copies each
+/// sink keeps, in a batch of its own
+///
+/// Sinks that don't keep copies are skipped. Running it again with the same links
+/// changes nothing.
+pub fn backfill_syndication_links(
+    targets: &mut [SinkTarget],
+    links: &SyndicationLinks,
+    dry_run: bool,
+) -> Vec<LinkBackfill> {
+    let mut results = Vec::new();
+    if links.is_empty() {
+        return results;
+    }
+
+    for target in targets.iter_mut() {
+        let sink_name = target.sink.name().to_string();
+        let dry_run = target.is_dry(dry_run);
+
+        let batch = target
+            .sink
+            .begin(dry_run)
+            .and_then(|()| target.sink.add_syndication_links(links, dry_run))
+            .and_then(|changed| target.sink.commit(dry_run).map(|()| changed));
+        if batch.is_err() && let Err(abort_error) = target.sink.abort(dry_run) {
+            error!(sink = %sink_name, error = %abort_error, "Failed to abort syndication links batch");
+        }
+        match batch {
+            Err(e) if e.is_unsupported() => debug!(sink = %sink_name, reason = %e.kind(), "Skipping syndication links"),
+            Ok(changed) => {
+                info!(sink = %sink_name, changed, "Added syndication links");
+                results.push(LinkBackfill { sink: sink_name, changed: Ok(changed) });
+            }
+            Err(e) => {
+                let e = e.in_sink(&sink_name);
+                results.push(LinkBackfill { sink: sink_name, changed: Err(e) });
+            }
+        }
+    }
+    results
+}
+
+/// What importing legacy posts did to one sink
+#[derive(Debug)]
+pub struct LegacyImport {
+    pub sink: String,
+    /// Posts newly recorded, or why the sink failed
+    pub imported: Result<Vec<(NodeId, PathBuf)>, SinkError>,
+}
+
+/// Record the posts each sink holds that weren't published from the canvas, in a batch of
+/// its own; sinks that can't are left out
+pub fn import_legacy_posts(targets: &mut [SinkTarget], dry_run: bool) -> Vec<LegacyImport> {
+    let mut results = Vec::new();
+    for target in targets.iter_mut() {
+        let sink_name = target.sink.name().to_string();
+        let dry_run = target.is_dry(dry_run);
+        let tracker = &target.tracker;
+        let is_tracked = |node_id: &NodeId| tracker.is_published(node_id);
+
+        let batch = target
+            .sink
+            .begin(dry_run)
+            .and_then(|()| target.sink.import_legacy(&is_tracked, dry_run))
+            .and_then(|imported| target.sink.commit(dry_run).map(|()| imported));
+        if batch.is_err() && let Err(abort_error) = target.sink.abort(dry_run) {
+            error!(sink = %sink_name, error = %abort_error, "Failed to abort legacy import batch");
+        }
+        match batch {
+            Err(e) if e.is_unsupported() => debug!(sink = %sink_name, reason = %e.kind(), "Skipping legacy import"),
+            Ok(imported) => {
+                info!(sink = %sink_name, imported = imported.len(), "Imported legacy posts");
+                results.push(LegacyImport { sink: sink_name, imported: Ok(imported) });
+            }
+            Err(e) => {
+                let e = e.in_sink(&sink_name);
+                results.push(LegacyImport { sink: sink_name, imported: Err(e) });
+            }
+        }
+    }
+    results
+}
+
+/// What a batch changed besides publishing new items
+struct BatchChanges {
+    archived: Vec<NodeId>,
+    unarchived: Vec<NodeId>,
+    /// Updated items the sink accepted
+    updated: Vec<NodeId>,
+}
+
+/// Run one batch against a sink: begin, archive/un-archive/publish, then commit
+///
+/// If any step fails the batch is aborted, so the caller must leave the tracker untouched.
+fn deliver(
+    target: &mut SinkTarget,
+    new_items: &HashMap<NodeId, SyndicationFormat>,
+    updated_items: &HashMap<NodeId, SyndicationFormat>,
+    to_archive: &[NodeId],
+    to_unarchive: &[NodeId],
+    dry_run: bool,
+) -> Result<BatchChanges, SinkError> {
+    let sink_name = target.sink.name().to_string();
+
+    if let Err(e) = target.sink.begin(dry_run) {
+        let e = e.in_sink(&sink_name);
+        error!(error = %e, step = ?e.step(), exit_code = ?e.exit_code(), "Failed to begin publish batch");
+        return Err(e);
+    }
+
+    let batch = run_batch(target, new_items, updated_items, to_archive, to_unarchive, dry_run)
+        .and_then(|changed| target.sink.commit(dry_run).map(|()| changed))
+        .map_err(|e| e.in_sink(&sink_name));
+
+    if let Err(e) = &batch {
+        error!(
+            error = %e,
+            node_id = ?e.node_id().map(NodeId::as_str),
+            step = ?e.step(),
+            exit_code = ?e.exit_code(),
+            "Failed to publish items, aborting batch"
+        );
+        if let Err(abort_error) = target.sink.abort(dry_run) {
+            error!(sink = %sink_name, error = %abort_error, "Failed to abort publish batch");
+        }
+    }
+    batch
+}
+
+/// Deliver due items from the canvas's outbound queue to each sink
+///
+/// Items already in a sink's tracker (e.g. delivered just before a crash) are dropped
+/// without republishing. Sinks delivered to within their `min_interval` are skipped, and
+/// failed items are retried with exponential backoff. `force` ignores both delays.
+pub fn drain_queue(
+    canvas_path: &Path,
+    targets: &mut [SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+    force: bool,
+) -> Result<Vec<SinkOutcome>, Box<dyn Error>> {
+    let delivery = options.queue.clone().unwrap_or_default();
+    let mut queue = OutboundQueue::for_canvas(canvas_path)?;
+    let now = options.clock.now();
+    let mut outcomes = Vec::new();
+    let mut delivered_items = HashMap::new();
+    // Taken before delivering, so an item published by an earlier sink of this drain is recent
+    let first_published_at: HashMap<NodeId, DateTime<Local>> = queue
+        .entries()
+        .filter_map(|entry| {
+            let at = first_published(targets.iter().map(|t| &t.tracker), &entry.item.id)?;
+            Some((entry.item.id.clone(), at))
+        })
+        .collect();
+
+    for target in targets.iter_mut() {
+        let sink_name = target.sink.name().to_string();
+        let dry_run = target.is_dry(dry_run);
+
+        let delivered: Vec<NodeId> = queue
+            .entries_for(&sink_name)
+            .filter(|entry| target.tracker.is_published(&entry.item.id))
+            .map(|entry| entry.item.id.clone())
+            .collect();
+        if !delivered.is_empty() {
+            debug!(sink = %sink_name, count = delivered.len(), "Dropping queued items that were already delivered");
+            queue.remove(&sink_name, &delivered);
+        }
+
+        // A backlog drained late still only delivers what's recent to sinks that want that
+        if let Some(days) = target.published_within {
+            let stale: Vec<NodeId> = queue
+                .entries_for(&sink_name)
+                .filter(|entry| !published_within(first_published_at.get(&entry.item.id).copied(), days, now))
+                .map(|entry| entry.item.id.clone())
+                .collect();
+            if !stale.is_empty() {
+                info!(sink = %sink_name, count = stale.len(), days, "Dropping queued items published too long ago");
+                queue.remove(&sink_name, &stale);
+            }
+        }
+
+        if !force
+            && let Some(interval) = delivery.min_interval.get(&sink_name)
+            && let Some(last) = queue.last_delivery(&sink_name)
+            && chrono::Duration::from_std(*interval).is_ok_and(|interval| now < last + interval)
+        {
+            debug!(sink = %sink_name, "Rate limited, leaving items queued");
+            continue;
+        }
+
+        let due: HashMap<NodeId, SyndicationFormat> = queue
+            .entries_for(&sink_name)
+            .filter(|entry| force || entry.not_before <= now)
+            .map(|entry| (entry.item.id.clone(), entry.item.clone()))
+            .collect();
+        if due.is_empty() {
+            continue;
+        }
+
+        let mut node_ids: Vec<NodeId> = due.keys().cloned().collect();
+        node_ids.sort_by(cmp_node_ids);
+        info!(sink = %sink_name, count = node_ids.len(), "Delivering queued items");
+
+        let mut outcome = SinkOutcome::new(&sink_name, dry_run, &node_ids);
+
+        match deliver(target, &due, &HashMap::new(), &[], &[], dry_run) {
+            Ok(_) if dry_run => {}
+            Ok(_) => {
+                let hashes: Vec<_> = due.values().map(|item| (item.id.clone(), item.content_hash())).collect();
+                let sources = canvas_hashes(due.values());
+                let saved = target
+                    .tracker
+                    .mark_published_at(&node_ids, now)
+                    .and_then(|()| target.tracker.record_content_hashes(&hashes))
+                    .and_then(|()| target.tracker.record_canvas_hashes(&sources));
+                if let Err(e) = saved {
+                    error!(sink = %sink_name, error = %e, "Failed to save tracker");
+                }
+                queue.remove(&sink_name, &node_ids);
+                queue.record_delivery(&sink_name, now);
+                delivered_items.extend(due);
+            }
+            Err(e) => {
+                if !dry_run {
+                    queue.record_failure(&sink_name, &node_ids, &e.to_string(), !e.is_transient(), now, &delivery);
+                }
+                outcome.set_error(&e);
+            }
+        }
+        outcome.urls = post_urls(target, |node_id| node_ids.contains(node_id));
+        outcome.planned_writes = planned_writes(target);
+        outcome.planned_commit = target.sink.take_planned_commit();
+        take_warnings(target, &mut outcome, options);
+        outcomes.push(outcome);
+    }
+
+    if !dry_run {
+        queue.save()?;
+    }
+    record_history(canvas_path, &outcomes, &delivered_items, options);
+    backfill_after_run(targets, &outcomes, dry_run);
+    Ok(outcomes)
+}
+
+/// Archive, un-archive, publish and update inside an open batch
+///
+/// Returns the IDs that were archived, un-archived and updated. Sinks that don't support
+/// archiving or updates are skipped for those steps.
+fn run_batch(
+    target: &mut SinkTarget,
+    new_items: &HashMap<NodeId, SyndicationFormat>,
+    updated_items: &HashMap<NodeId, SyndicationFormat>,
+    to_archive: &[NodeId],
+    to_unarchive: &[NodeId],
+    dry_run: bool,
+) -> Result<BatchChanges, SinkError> {
+    let sink_name = target.sink.name().to_string();
+    let mut archived = Vec::new();
+    let mut unarchived = Vec::new();
+    let mut updated = Vec::new();
+
+    if !to_archive.is_empty() {
+        info!(sink = %sink_name, count = to_archive.len(), "Archiving items");
+        match target.sink.archive(to_archive, dry_run) {
+            Ok(()) => archived = to_archive.to_vec(),
+            Err(e) if e.is_unsupported() => debug!(sink = %sink_name, reason = %e.kind(), "Skipping archive"),
+            Err(e) => return Err(e),
+        }
+    }
+
+    if !to_unarchive.is_empty() {
+        info!(sink = %sink_name, count = to_unarchive.len(), "Un-archiving items");
+        match target.sink.unarchive(to_unarchive, dry_run) {
+            Ok(()) => unarchived = to_unarchive.to_vec(),
+            Err(e) if e.is_unsupported() => debug!(sink = %sink_name, reason = %e.kind(), "Skipping un-archive"),
+            Err(e) => return Err(e),
+        }
+    }
+
+    if !new_items.is_empty() {
+        info!(sink = %sink_name, new_items = new_items.len(), "Publishing new items");
+        target.sink.publish(new_items, dry_run)?;
+    }
+
+    if !updated_items.is_empty() {
+        info!(sink = %sink_name, count = updated_items.len(), "Updating edited items");
+        match target.sink.update(updated_items, dry_run) {
+            Ok(()) => {
+                updated = updated_items.keys().cloned().collect();
+                updated.sort_by(cmp_node_ids);
+            }
+            Err(e) if e.is_unsupported() => debug!(sink = %sink_name, reason = %e.kind(), "Skipping updates"),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(BatchChanges { archived, unarchived, updated })
+}
+
+/// Process the canvas as read for the run, drain the queue in queue mode and persist the
+/// report if a report writer is configured
+pub(crate) fn run_and_report(
+    canvas_path: &Path,
+    snapshot: std::io::Result<CanvasSnapshot>,
+    targets: &mut [SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+    cancel: &CancelToken,
+) -> ProcessReport {
+    let report = process_read(canvas_path, snapshot, targets, dry_run, options, cancel, None);
+    finish_run(canvas_path, report, targets, dry_run, options)
+}
+
+/// [`run_and_report`] for the run of an unfinished journal, re-executing its open steps
+/// over the canvas as it is now
+pub(crate) fn resume_and_report(
+    canvas_path: &Path,
+    journal: RunJournal,
+    targets: &mut [SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+) -> ProcessReport {
+    let snapshot = CanvasSnapshot::read(canvas_path, options.clock.now());
+    let report = process_read(canvas_path, snapshot, targets, dry_run, options, &CancelToken::new(), Some(journal));
+    finish_run(canvas_path, report, targets, dry_run, options)
+}
+
+/// Drain the queue in queue mode after a run and persist its report and badges
+fn finish_run(
+    canvas_path: &Path,
+    mut report: ProcessReport,
+    targets: &mut [SinkTarget],
+    dry_run: bool,
+    options: &ProcessOptions,
+) -> ProcessReport {
+    // A cancelled run is superseded by the one for the newer change
+    if report.cancelled {
+        return report;
+    }
+
+    if options.queue.is_some() && !dry_run {
+        match drain_queue(canvas_path, targets, dry_run, options, false) {
+            Ok(outcomes) => report.sinks.extend(outcomes),
+            Err(e) => error!(error = %e, "Failed to drain queue"),
+        }
+    }
+
+    log_summary(report.all_warnings());
+
+    if let Some(writer) = &options.report_writer
+        && let Err(e) = writer.write(&report)
+    {
+        error!(error = %e, "Failed to write run report");
+    }
+
+    if !dry_run {
+        write_badges(canvas_path, targets, options);
+    }
+
+    report
+}
+
+/// Write every configured badge as it stands after the run
+fn write_badges(canvas_path: &Path, targets: &[SinkTarget], options: &ProcessOptions) {
+    if options.badges.is_empty() {
+        return;
+    }
+    let history = match HistoryLog::for_canvas(canvas_path).and_then(|log| log.events()) {
+        Ok(events) => events,
+        Err(e) => {
+            error!(error = %e, "Failed to read history for badges");
+            return;
+        }
+    };
+    let now = options.clock.now();
+    for spec in &options.badges {
+        let Some(target) = targets.iter().find(|t| t.sink.name() == spec.sink) else {
+            warn!(sink = %spec.sink, path = %spec.path.display(), "Badge counts an unknown sink, skipping");
+            continue;
+        };
+        let badge = spec.badge(&target.tracker, &history, now);
+        if let Err(e) = spec.write(&badge) {
+            error!(path = %spec.path.display(), error = %e, "Failed to write badge");
+        }
+    }
+}
+
+/// Watch the canvas file and process changes
+///
+/// This function processes the canvas on startup, then watches for file changes
+/// and re-processes when modifications are detected.
+pub fn watch_and_process(
+    canvas_path: &Path,
+    mut targets: Vec<SinkTarget>,
+    dry_run: bool,
+    debounce_duration: Duration,
+    options: ProcessOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut pipelines = [WatchedPipeline::new(None, canvas_path, &mut targets, dry_run, &options)];
+    watch_loop(&mut pipelines, debounce_duration, None, &ProcessingQueue::default())
+}
+
+/// How often the watch loop checks for commands
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// One canvas and its sinks, as seen by [`watch_loop`]
+pub(crate) struct WatchedPipeline<'a> {
+    /// Tags the pipeline's logs; None when it's the only one
+    name: Option<&'a str>,
+    canvas_path: &'a Path,
+    targets: &'a mut [SinkTarget],
+    dry_run: bool,
+    options: &'a ProcessOptions,
+    last_drain: Instant,
+    /// When the pending batch closes, under a window batch policy
+    batch_deadline: Option<DateTime<Local>>,
+    /// When the next sink is due to re-surface an item
+    resurface_deadline: Option<DateTime<Local>>,
+    /// Triggered by the watcher when the canvas changes during a run
+    cancel: CancelToken,
+    /// Stamp of the canvas when the latest run started, shared with the watcher
+    run_stamp: Arc<Mutex<Option<FileStamp>>>,
+    /// Picks the watcher events that are changes to the canvas
+    filter: EventFilter,
+    /// Watcher events dropped so far, counted on the watcher's thread
+    filtered: Arc<Mutex<FilteredEvents>>,
+    /// Whether a change is waiting for `min_interval` to pass, or cancelled the latest run
+    changed: bool,
+    /// When the latest run started
+    last_run: Option<Instant>,
+    /// Warnings raised by the latest run
+    last_warnings: usize,
+}
+
+impl<'a> WatchedPipeline<'a> {
+    pub(crate) fn new(
+        name: Option<&'a str>,
+        canvas_path: &'a Path,
+        targets: &'a mut [SinkTarget],
+        dry_run: bool,
+        options: &'a ProcessOptions,
+    ) -> Self {
+        let run_stamp = Arc::new(Mutex::new(None));
+        Self {
+            name,
+            canvas_path,
+            targets,
+            dry_run,
+            options,
+            last_drain: Instant::now(),
+            batch_deadline: None,
+            resurface_deadline: None,
+            cancel: CancelToken::new(),
+            filter: EventFilter::new(canvas_path, &options.watch, run_stamp.clone()),
+            run_stamp,
+            filtered: Arc::default(),
+            changed: false,
+            last_run: None,
+            last_warnings: 0,
+        }
+    }
+
+    /// Run `f` in a span tagged with the pipeline name, containing panics
+    ///
+    /// A panicking sink or processor is logged and the watch goes on, so one pipeline
+    /// can't take the others down.
+    fn isolated(&mut self, f: impl FnOnce(&mut Self)) {
+        let span = match self.name {
+            Some(name) => tracing::info_span!("pipeline", name = %name),
+            None => tracing::Span::none(),
+        };
+        let _entered = span.enter();
+        if std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(self))).is_err() {
+            error!(canvas_file = %self.canvas_path.display(), "Processing panicked; will retry on the next change");
+        }
+    }
+
+    /// Run once; a run cancelled by a newer change leaves the pipeline marked changed
+    fn process(&mut self) {
+        self.last_run = Some(Instant::now());
+        // Events stamped like the version read are this run's own; anything else is a newer change
+        let snapshot = CanvasSnapshot::read(self.canvas_path, self.options.clock.now());
+        *self.run_stamp.lock().expect("stamp lock poisoned") = match &snapshot {
+            Ok(snapshot) => snapshot.stamp(),
+            Err(_) => file_stamp(self.canvas_path),
+        };
+        self.cancel.reset();
+        self.isolated(|p| {
+            let report = run_and_report(p.canvas_path, snapshot, p.targets, p.dry_run, p.options, &p.cancel);
+            p.changed = report.cancelled;
+            p.last_warnings = report.all_warnings().count();
+        });
+        self.batch_deadline = batch_deadline(self.canvas_path, self.options);
+        self.resurface_deadline = resurface_deadline(self.canvas_path, self.targets, self.dry_run, self.options);
+    }
+
+    /// Time left until `min_interval` since the latest run has passed
+    fn until_next_run(&self) -> Duration {
+        self.last_run
+            .map_or(Duration::ZERO, |last| self.options.watch.min_interval.saturating_sub(last.elapsed()))
+    }
+
+    /// Time left until the pending batch closes
+    fn until_batch_closes(&self) -> Option<Duration> {
+        let deadline = self.batch_deadline?;
+        Some((deadline - self.options.clock.now()).to_std().unwrap_or(Duration::ZERO))
+    }
+
+    /// Time left until a sink is due to re-surface an item
+    fn until_resurface(&self) -> Option<Duration> {
+        let deadline = self.resurface_deadline?;
+        Some((deadline - self.options.clock.now()).to_std().unwrap_or(Duration::ZERO))
+    }
+
+    /// A request to process this pipeline alone
+    fn request(&self, reason: ProcessingReason) -> ProcessingRequest {
+        ProcessingRequest { reason, pipeline: self.name.map(str::to_string) }
+    }
+
+    fn status(&self, processing: &ProcessingQueue) -> PipelineStatus {
+        PipelineStatus {
+            name: self.name.map(str::to_string),
+            canvas_path: self.canvas_path.to_path_buf(),
+            dry_run: self.dry_run,
+            changed: self.changed,
+            last_run: self.last_run.map(|last| last.elapsed()),
+            batch_closes_in: self.until_batch_closes(),
+            warnings: self.last_warnings,
+            filtered: *self.filtered.lock().expect("filtered events lock poisoned"),
+            queued: processing.depth(),
+            dropped: processing.dropped(),
+        }
+    }
+}
+
+/// What the watcher's thread needs of a pipeline to turn events into requests
+struct CanvasWatch {
+    request: ProcessingRequest,
+    canvas_path: PathBuf,
+    filter: EventFilter,
+    cancel: CancelToken,
+    filtered: Arc<Mutex<FilteredEvents>>,
+}
+
+impl CanvasWatch {
+    /// Count the events dropped, and on a change to the canvas cancel the run in progress
+    /// and ask for another
+    fn on_events(&self, events: &[DebouncedEvent], sender: &ProcessingSender) {
+        let (changed, filtered) = self.filter.check(events);
+        if filtered.total() > 0 {
+            let mut total = self.filtered.lock().expect("filtered events lock poisoned");
+            total.add(filtered);
+            debug!(
+                canvas_file = %self.canvas_path.display(),
+                ignored = filtered.ignored,
+                other_files = filtered.other_files,
+                own_writes = filtered.own_writes,
+                unchanged = filtered.unchanged,
+                filtered_total = total.total(),
+                "Filtered watcher events"
+            );
+        }
+        if changed {
+            self.cancel.cancel();
+            if sender.request(self.request.clone()).is_err() {
+                debug!(canvas_file = %self.canvas_path.display(), "Watch loop stopped, change not processed");
+            }
+        }
+    }
+}
+
+/// Process on startup, then on every change until `commands` asks to shut down or hangs up
+///
+/// All pipelines share one watcher; a change to a canvas only processes its own pipeline.
+///
+/// Every trigger (the watcher, commands, batch windows, re-surfacing, and other threads
+/// through [`ProcessingQueue::sender`]) becomes a [`ProcessingRequest`], and this loop is
+/// the only one carrying them out, strictly one run at a time.
+///
+/// Bursts of saves (e.g. while Obsidian reindexes) are coalesced. A change arriving while
+/// a run is still reading and filtering the canvas cancels it, and the run restarts with
+/// the new content. A change arriving once publishing has begun lets the run finish and
+/// queues a request, so with coalescing the pipeline runs exactly once more however many
+/// saves happened.
+pub(crate) fn watch_loop(
+    pipelines: &mut [WatchedPipeline],
+    debounce_duration: Duration,
+    commands: Option<&Receiver<WatchCommand>>,
+    processing: &ProcessingQueue,
+) -> Result<(), Box<dyn Error>> {
+    // Process on startup
+    info!("Processing canvas file on startup...");
+    carry_out(pipelines, vec![ProcessingRequest::new(ProcessingReason::Startup)]);
+
+    // Setup file watcher, which turns changes into requests and cancels runs in progress
+    // from its own thread
+    let sender = processing.sender();
+    let watches: Vec<CanvasWatch> = pipelines
+        .iter()
+        .map(|p| CanvasWatch {
+            request: p.request(ProcessingReason::CanvasChanged),
+            canvas_path: p.canvas_path.to_path_buf(),
+            filter: p.filter.clone(),
+            cancel: p.cancel.clone(),
+            filtered: p.filtered.clone(),
+        })
+        .collect();
+    let mut debouncer = new_debouncer(debounce_duration, move |res: DebounceEventResult| match res {
+        Ok(events) => {
+            for watch in &watches {
+                watch.on_events(&events, &sender);
+            }
+        }
+        Err(error) => error!(error = ?error, "Watch error"),
+    })?;
+
+    // Watch the directory rather than the file: editors that save by writing a temp file and
+    // renaming it over the canvas (common on Windows) replace the watched file, after which a
+    // file watch goes quiet
+    let mut watched_dirs: Vec<&Path> = Vec::new();
+    for pipeline in pipelines.iter() {
+        let canvas_dir = match pipeline.canvas_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        pipeline.canvas_path.file_name().ok_or("Canvas path has no file name")?;
+        if !watched_dirs.contains(&canvas_dir) {
+            debouncer
+                .watcher()
+                .watch(canvas_dir, RecursiveMode::NonRecursive)?;
+            watched_dirs.push(canvas_dir);
+        }
+    }
+
+    info!("Watching for file changes...");
+
+    // In queue mode, wake up periodically so backed-off and rate-limited items get delivered
+    let poll_interval = pipelines
+        .iter()
+        .filter_map(|p| p.options.queue.as_ref().map(|q| q.poll_interval))
+        .min();
+    let wait = match (poll_interval, commands) {
+        (Some(interval), Some(_)) => Some(interval.min(COMMAND_POLL_INTERVAL)),
+        (Some(interval), None) => Some(interval),
+        (None, Some(_)) => Some(COMMAND_POLL_INTERVAL),
+        (None, None) => None,
+    };
+
+    loop {
+        // Requests from this thread; they're never queued, so a full queue can't block it
+        let mut requests = Vec::new();
+        if let Some(commands) = commands
+            && !handle_commands(pipelines, commands, processing, &mut requests)
+        {
+            break;
+        }
+
+        // Publish batches whose window closed without further changes, and re-surface on time
+        for pipeline in pipelines.iter_mut() {
+            if pipeline.until_batch_closes() == Some(Duration::ZERO) {
+                info!(canvas_file = %pipeline.canvas_path.display(), "Batch window closed");
+                pipeline.batch_deadline = None;
+                requests.push(pipeline.request(ProcessingReason::BatchClosed));
+            }
+            if pipeline.until_resurface() == Some(Duration::ZERO) {
+                info!(canvas_file = %pipeline.canvas_path.display(), "Time to re-surface an old item");
+                pipeline.resurface_deadline = None;
+                requests.push(pipeline.request(ProcessingReason::Resurface));
+            }
+        }
+
+        for pipeline in pipelines.iter_mut() {
+            if let Some(interval) = pipeline.options.queue.as_ref().map(|q| q.poll_interval)
+                && pipeline.last_drain.elapsed() >= interval
+                && !pipeline.dry_run
+            {
+                pipeline.last_drain = Instant::now();
+                pipeline.isolated(|p| {
+                    if let Err(e) = drain_queue(p.canvas_path, p.targets, p.dry_run, p.options, false) {
+                        error!(error = %e, "Failed to drain queue");
+                    }
+                });
+            }
+        }
+
+        // Changed pipelines held back by `min_interval` run once it has passed
+        if requests.is_empty() {
+            let batch_wait = pipelines.iter().filter_map(WatchedPipeline::until_batch_closes).min();
+            let resurface_wait = pipelines.iter().filter_map(WatchedPipeline::until_resurface).min();
+            let interval_wait = pipelines.iter().filter(|p| p.changed).map(WatchedPipeline::until_next_run).min();
+            let wait = wait.into_iter().chain(batch_wait).chain(resurface_wait).chain(interval_wait).min();
+            requests = processing.next_batch(wait);
+        } else {
+            requests.extend(processing.take_queued());
+        }
+        carry_out(pipelines, processing.coalesced(requests));
+
+        // Requests that arrive during these runs are carried out before waiting again
+        loop {
+            let requests = processing.coalesced(processing.take_queued());
+            let mut due = pipelines
+                .iter_mut()
+                .filter(|p| p.changed && p.until_next_run().is_zero())
+                .peekable();
+            if requests.is_empty() && due.peek().is_none() {
+                break;
+            }
+            for pipeline in due {
+                info!(canvas_file = %pipeline.canvas_path.display(), "Canvas changed, processing...");
+                pipeline.process();
+            }
+            carry_out(pipelines, requests);
+        }
+    }
+
+    // Requests left over are moot now; taking them frees any sender blocked on a full queue
+    processing.take_queued();
+    Ok(())
+}
+
+/// Carry out requests in order, one run at a time
+///
+/// Startup and explicit requests run right away. Other requests run a pipeline only once
+/// `min_interval` has passed since its latest run, and until then leave it marked changed.
+fn carry_out(pipelines: &mut [WatchedPipeline], requests: Vec<ProcessingRequest>) {
+    for request in requests {
+        let mut covered = 0;
+        for pipeline in pipelines.iter_mut().filter(|p| request.covers(p.name)) {
+            covered += 1;
+            let immediate = matches!(request.reason, ProcessingReason::Startup | ProcessingReason::Requested);
+            if immediate || pipeline.until_next_run().is_zero() {
+                info!(canvas_file = %pipeline.canvas_path.display(), reason = %request.reason, "Processing...");
+                pipeline.process();
+            } else {
+                pipeline.changed = true;
+            }
+        }
+        if covered == 0 {
+            warn!(pipeline = ?request.pipeline, reason = %request.reason, "No such pipeline, request ignored");
+        }
+    }
+}
+
+/// Carry out the commands received since the last check; false once the loop should stop
+///
+/// Requests to process are added to `requests`, to be carried out with the queued ones.
+fn handle_commands(
+    pipelines: &mut [WatchedPipeline],
+    commands: &Receiver<WatchCommand>,
+    processing: &ProcessingQueue,
+    requests: &mut Vec<ProcessingRequest>,
+) -> bool {
+    loop {
+        let command = match commands.try_recv() {
+            Ok(command) => command,
+            Err(TryRecvError::Empty) => return true,
+            Err(TryRecvError::Disconnected) => {
+                info!("Command channel closed, stopping watcher");
+                return false;
+            }
+        };
+        match command {
+            WatchCommand::Process => {
+                info!("Processing on request...");
+                requests.push(ProcessingRequest::new(ProcessingReason::Requested));
+            }
+            WatchCommand::Status(reply) => {
+                let _ = reply.send(pipelines.iter().map(|p| p.status(processing)).collect());
+            }
+            WatchCommand::ToggleDryRun => {
+                // Mixed pipelines all go armed only if every one was in dry run
+                let dry_run = !pipelines.iter().all(|p| p.dry_run);
+                for pipeline in pipelines.iter_mut() {
+                    pipeline.dry_run = dry_run;
+                }
+                if dry_run {
+                    info!("Switched to dry run");
+                } else {
+                    warn!("Armed: runs publish for real");
+                }
+            }
+            WatchCommand::Shutdown => {
+                info!("Shutdown requested, stopping watcher");
+                return false;
+            }
+        }
+    }
 }
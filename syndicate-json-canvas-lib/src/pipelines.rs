@@ -0,0 +1,115 @@
+use std::sync::mpsc::Receiver;
+
+use tracing::info_span;
+
+use crate::control::WatchCommand;
+use crate::orchestrator::watch_loop;
+use crate::processing::{ProcessingOptions, ProcessingQueue, ProcessingSender};
+use crate::report::ProcessReport;
+use crate::syndicator::{SyndicationError, Syndicator};
+
+/// Several independent named pipelines hosted in one process
+///
+/// Each pipeline is a [`Syndicator`] with its own canvas, options, trackers and sinks.
+/// They share one file watcher; their logs are tagged with the pipeline name, and a
+/// failure (or panic) in one doesn't stop the others. Requests to process any of them go
+/// through one queue, so runs never overlap.
+#[derive(Default)]
+pub struct Pipelines {
+    pipelines: Vec<(String, Syndicator)>,
+    /// Requests to process while watching, shared by every pipeline
+    processing: ProcessingQueue,
+}
+
+impl Pipelines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue requests to process while watching as `options` say, instead of the defaults;
+    /// the pipelines' own [`Config::processing`](crate::Config::processing) isn't used
+    pub fn with_processing(mut self, options: &ProcessingOptions) -> Self {
+        self.processing = ProcessingQueue::new(options);
+        self
+    }
+
+    /// A handle for asking [`watch`](Self::watch) to process from other threads; requests
+    /// naming a pipeline (see [`ProcessingRequest::for_pipeline`](crate::ProcessingRequest::for_pipeline))
+    /// process only that one
+    pub fn processing_sender(&self) -> ProcessingSender {
+        self.processing.sender()
+    }
+
+    /// Add a pipeline; returns its syndicator for adding sinks
+    ///
+    /// Names must be unique, and so must canvases: pipelines sharing a canvas would share
+    /// its state files.
+    pub fn add(&mut self, name: impl Into<String>, syndicator: Syndicator) -> Result<&mut Syndicator, SyndicationError> {
+        let name = name.into();
+        if self.get(&name).is_some() {
+            return Err(SyndicationError::Pipeline(format!("duplicate pipeline name `{}`", name)));
+        }
+        let canvas_path = &syndicator.config().canvas_path;
+        if let Some((other, _)) = self.pipelines.iter().find(|(_, s)| s.config().canvas_path == *canvas_path) {
+            return Err(SyndicationError::Pipeline(format!(
+                "pipelines `{}` and `{}` both use canvas {}",
+                other,
+                name,
+                canvas_path.display()
+            )));
+        }
+        self.pipelines.push((name, syndicator));
+        Ok(&mut self.pipelines.last_mut().expect("pipeline was just pushed").1)
+    }
+
+    /// The pipeline named `name`
+    pub fn get(&self, name: &str) -> Option<&Syndicator> {
+        self.pipelines.iter().find(|(n, _)| n == name).map(|(_, s)| s)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Syndicator> {
+        self.pipelines.iter_mut().find(|(n, _)| n == name).map(|(_, s)| s)
+    }
+
+    /// Pipelines with their names, in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Syndicator)> {
+        self.pipelines.iter().map(|(n, s)| (n.as_str(), s))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Syndicator)> {
+        self.pipelines.iter_mut().map(|(n, s)| (n.as_str(), s))
+    }
+
+    /// Process every pipeline once, returning each one's report or error by name
+    pub fn process_once(&mut self) -> Vec<(String, Result<ProcessReport, SyndicationError>)> {
+        self.pipelines
+            .iter_mut()
+            .map(|(name, syndicator)| {
+                let _entered = info_span!("pipeline", name = %name).entered();
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| syndicator.process_once()))
+                    .unwrap_or_else(|_| Err(SyndicationError::Process("processing panicked".to_string())));
+                (name.clone(), result)
+            })
+            .collect()
+    }
+
+    /// Process every pipeline now and on every change to its canvas, carrying out
+    /// `commands` until one asks to shut down or every sender is dropped
+    ///
+    /// Changes are debounced by the shortest `debounce` of the pipelines' configs.
+    pub fn watch(&mut self, commands: Receiver<WatchCommand>) -> Result<(), SyndicationError> {
+        let debounce = self
+            .pipelines
+            .iter()
+            .map(|(_, s)| s.config().debounce)
+            .min()
+            .ok_or_else(|| SyndicationError::Pipeline("no pipelines to watch".to_string()))?;
+        let mut watched: Vec<_> = self
+            .pipelines
+            .iter_mut()
+            .map(|(name, syndicator)| syndicator.watched(Some(name.as_str())).0)
+            .collect();
+        watch_loop(&mut watched, debounce, Some(&commands), &self.processing)
+            .map_err(|e| SyndicationError::Watch(e.to_string()))
+    }
+}
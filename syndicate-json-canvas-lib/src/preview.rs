@@ -0,0 +1,110 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::simulate::line_diff;
+
+/// Lines of diff kept per modified file, by default
+pub const DEFAULT_PREVIEW_DIFF_LINES: usize = 40;
+
+/// How a file a dry run would write compares with the one on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteKind {
+    /// No file there yet
+    Create,
+    /// A different file there
+    Modify,
+    /// The same file there already, so a real run wouldn't change it
+    Identical,
+}
+
+/// A file a dry run would have written, compared with what's published
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedWrite {
+    pub path: PathBuf,
+    /// Node the file was generated from, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    pub kind: WriteKind,
+    /// Unified diff against the file on disk, for modified files, cut to a number of lines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+impl PlannedWrite {
+    /// Compare `contents` with the file at `path`, keeping at most `max_diff_lines` lines
+    /// of diff
+    pub fn classify(path: &Path, contents: &str, max_diff_lines: usize) -> std::io::Result<Self> {
+        let (kind, diff) = match std::fs::read_to_string(path) {
+            Ok(existing) if existing == contents => (WriteKind::Identical, None),
+            Ok(existing) => (WriteKind::Modify, Some(cap_lines(&line_diff(&existing, contents), max_diff_lines))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (WriteKind::Create, None),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path: path.to_path_buf(), node_id: None, kind, diff })
+    }
+
+    pub fn with_node_id(mut self, node_id: impl Into<String>) -> Self {
+        self.node_id = Some(node_id.into());
+        self
+    }
+}
+
+/// The first `max_lines` lines of a diff, noting how many were left out
+fn cap_lines(diff: &str, max_lines: usize) -> String {
+    let total = diff.lines().count();
+    if total <= max_lines {
+        return diff.to_string();
+    }
+    let mut capped: String = diff.lines().take(max_lines).map(|line| format!("{}\n", line)).collect();
+    capped.push_str(&format!("... {} more lines\n", total - max_lines));
+    capped
+}
+
+/// Counts of planned writes by kind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteSummary {
+    pub create: usize,
+    pub modify: usize,
+    pub identical: usize,
+}
+
+impl WriteSummary {
+    pub fn of(writes: &[PlannedWrite]) -> Self {
+        let mut summary = Self::default();
+        for write in writes {
+            match write.kind {
+                WriteKind::Create => summary.create += 1,
+                WriteKind::Modify => summary.modify += 1,
+                WriteKind::Identical => summary.identical += 1,
+            }
+        }
+        summary
+    }
+}
+
+impl fmt::Display for WriteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} to create, {} to modify, {} identical", self.create, self.modify, self.identical)
+    }
+}
+
+/// The commit a dry run would have made, for sinks that publish by committing to a repository
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedCommit {
+    pub message: String,
+    /// Files the commit would create or change, relative to the repository
+    pub files: Vec<PathBuf>,
+    /// Bookmark (or branch) the commit would be pushed under
+    pub bookmark: String,
+    /// Commit the bookmark points at now; None if it wasn't looked up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+    /// Commit the bookmark points at on the remote; None if it isn't there or wasn't looked up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    /// Where the bookmark would be moved to
+    pub target: String,
+}
@@ -0,0 +1,252 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TryRecvError, TrySendError};
+use std::time::Duration;
+
+use tracing::warn;
+
+/// Requests the processing queue holds by default before back-pressure applies
+pub const DEFAULT_PROCESSING_CAPACITY: usize = 16;
+
+/// Why a run was asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProcessingReason {
+    /// The watcher started
+    Startup,
+    /// The file watcher saw the canvas change
+    CanvasChanged,
+    /// The window of a pending batch closed
+    BatchClosed,
+    /// A sink is due to re-surface an old item
+    Resurface,
+    /// Asked for from outside, e.g. the console's `p` or a program embedding the watcher
+    Requested,
+}
+
+impl fmt::Display for ProcessingReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProcessingReason::Startup => "startup",
+            ProcessingReason::CanvasChanged => "canvas changed",
+            ProcessingReason::BatchClosed => "batch closed",
+            ProcessingReason::Resurface => "re-surface due",
+            ProcessingReason::Requested => "requested",
+        })
+    }
+}
+
+/// A request for the watch loop to process one pipeline, or all of them
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessingRequest {
+    pub reason: ProcessingReason,
+    /// Name of the pipeline to process (see [`Pipelines`](crate::Pipelines)); None for every
+    /// pipeline
+    pub pipeline: Option<String>,
+}
+
+impl ProcessingRequest {
+    /// A request to process every pipeline
+    pub fn new(reason: ProcessingReason) -> Self {
+        Self { reason, pipeline: None }
+    }
+
+    /// Process only the pipeline named `name`
+    pub fn for_pipeline(mut self, name: impl Into<String>) -> Self {
+        self.pipeline = Some(name.into());
+        self
+    }
+
+    /// Whether the request covers the pipeline named `name` (None for a lone syndicator)
+    pub(crate) fn covers(&self, name: Option<&str>) -> bool {
+        self.pipeline.is_none() || self.pipeline.as_deref() == name
+    }
+}
+
+/// What a sender does when the processing queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Drop the request and log it; the queued requests already lead to a run that reads
+    /// the latest canvas
+    #[default]
+    Drop,
+    /// Wait until the watch loop takes a request off the queue
+    Block,
+}
+
+/// How requests to process are queued for the watch loop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessingOptions {
+    /// Requests held before [`backpressure`](Self::backpressure) applies (at least 1)
+    pub capacity: usize,
+    pub backpressure: Backpressure,
+    /// Whether identical queued requests are carried out once
+    pub coalesce: bool,
+}
+
+impl Default for ProcessingOptions {
+    fn default() -> Self {
+        Self { capacity: DEFAULT_PROCESSING_CAPACITY, backpressure: Backpressure::default(), coalesce: true }
+    }
+}
+
+impl ProcessingOptions {
+    /// Hold at most `capacity` requests
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Drop or wait when the queue is full
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+
+    /// Carry out identical queued requests once (the default) or each on its own
+    pub fn with_coalesce(mut self, coalesce: bool) -> Self {
+        self.coalesce = coalesce;
+        self
+    }
+}
+
+/// The watch loop stopped, so the request can't be carried out
+#[derive(Debug, thiserror::Error)]
+#[error("the watch loop has stopped")]
+pub struct ProcessingClosed;
+
+/// Counts shared by a queue and its senders
+#[derive(Debug, Default)]
+struct Counts {
+    queued: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+/// The one bounded queue every request to process goes through
+///
+/// The watch loop is its only consumer and carries requests out strictly one after the
+/// other, so runs never overlap however many threads ask for them.
+#[derive(Debug)]
+pub struct ProcessingQueue {
+    receiver: Receiver<ProcessingRequest>,
+    sender: ProcessingSender,
+    coalesce: bool,
+}
+
+impl Default for ProcessingQueue {
+    fn default() -> Self {
+        Self::new(&ProcessingOptions::default())
+    }
+}
+
+impl ProcessingQueue {
+    pub fn new(options: &ProcessingOptions) -> Self {
+        let (tx, receiver) = mpsc::sync_channel(options.capacity.max(1));
+        let sender = ProcessingSender { tx, backpressure: options.backpressure, counts: Arc::default() };
+        Self { receiver, sender, coalesce: options.coalesce }
+    }
+
+    /// A handle for asking the watch loop to process, from any thread
+    pub fn sender(&self) -> ProcessingSender {
+        self.sender.clone()
+    }
+
+    /// Requests waiting to be carried out, counting those whose senders are blocked on a
+    /// full queue
+    pub fn depth(&self) -> usize {
+        self.sender.depth()
+    }
+
+    /// Requests dropped so far because the queue was full
+    pub fn dropped(&self) -> usize {
+        self.sender.dropped()
+    }
+
+    /// Wait up to `timeout` (forever if None) for a request, then take every request
+    /// queued behind it; empty if none came in time
+    pub(crate) fn next_batch(&self, timeout: Option<Duration>) -> Vec<ProcessingRequest> {
+        let first = match timeout {
+            Some(timeout) => match self.receiver.recv_timeout(timeout) {
+                Ok(request) => request,
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return Vec::new(),
+            },
+            // The queue holds a sender of its own, so this only returns with a request
+            None => match self.receiver.recv() {
+                Ok(request) => request,
+                Err(_) => return Vec::new(),
+            },
+        };
+        self.sender.counts.queued.fetch_sub(1, Ordering::SeqCst);
+        std::iter::once(first).chain(self.take_queued()).collect()
+    }
+
+    /// Requests queued right now, without waiting
+    pub(crate) fn take_queued(&self) -> Vec<ProcessingRequest> {
+        let mut requests = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(request) => {
+                    self.sender.counts.queued.fetch_sub(1, Ordering::SeqCst);
+                    requests.push(request);
+                }
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => return requests,
+            }
+        }
+    }
+
+    /// `requests` with later duplicates left out, if coalescing
+    pub(crate) fn coalesced(&self, mut requests: Vec<ProcessingRequest>) -> Vec<ProcessingRequest> {
+        if self.coalesce {
+            let mut seen = std::collections::HashSet::new();
+            requests.retain(|request| seen.insert(request.clone()));
+        }
+        requests
+    }
+}
+
+/// Asks a watch loop to process; cheap to clone and send to other threads
+#[derive(Debug, Clone)]
+pub struct ProcessingSender {
+    tx: SyncSender<ProcessingRequest>,
+    backpressure: Backpressure,
+    counts: Arc<Counts>,
+}
+
+impl ProcessingSender {
+    /// Queue a request, applying the queue's [`Backpressure`] if it's full
+    ///
+    /// Returns whether the request was queued: false if it was dropped.
+    pub fn request(&self, request: ProcessingRequest) -> Result<bool, ProcessingClosed> {
+        // Counted before sending, so the loop never takes a request it hasn't counted
+        self.counts.queued.fetch_add(1, Ordering::SeqCst);
+        let sent = match self.backpressure {
+            Backpressure::Block => self.tx.send(request).map_err(|_| ProcessingClosed),
+            Backpressure::Drop => match self.tx.try_send(request) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(request)) => {
+                    self.counts.queued.fetch_sub(1, Ordering::SeqCst);
+                    self.counts.dropped.fetch_add(1, Ordering::SeqCst);
+                    let pipeline = request.pipeline.as_deref().unwrap_or("all");
+                    warn!(reason = %request.reason, pipeline, "Processing queue full, request dropped");
+                    return Ok(false);
+                }
+                Err(TrySendError::Disconnected(_)) => Err(ProcessingClosed),
+            },
+        };
+        if sent.is_err() {
+            self.counts.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+        sent.map(|()| true)
+    }
+
+    /// Requests waiting to be carried out, counting those whose senders are blocked on a
+    /// full queue
+    pub fn depth(&self) -> usize {
+        self.counts.queued.load(Ordering::SeqCst)
+    }
+
+    /// Requests dropped so far because the queue was full
+    pub fn dropped(&self) -> usize {
+        self.counts.dropped.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{debug, error, info};
+
+use crate::compat::parse_canvas;
+use crate::exclusions::ExclusionList;
+use crate::guard::{RemovalGuard, RemovalSnapshot};
+use crate::jsoncanvas::NodeId;
+use crate::node_ids::cmp_node_ids;
+use crate::orchestrator::SinkTarget;
+
+/// What removing a node leaves behind in a sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemovalPolicy {
+    /// Delete what was published
+    #[default]
+    Delete,
+    /// Replace it with a tombstone saying it was withdrawn and when, so readers of the
+    /// sink's output know it was retracted rather than lost
+    Tombstone,
+}
+
+/// A published file a sink would delete
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    pub node_id: NodeId,
+    pub path: PathBuf,
+    /// Title in the file's frontmatter, if it has one
+    pub title: Option<String>,
+    /// Hash of the file's contents when planned, to tell if it changed before deletion
+    pub content_hash: String,
+}
+
+/// Everything removing nodes from one sink would delete, to review before doing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DestructionPlan {
+    /// Name of the sink
+    pub sink: String,
+    pub files: Vec<PlannedFile>,
+    /// Whether the files are deleted or replaced by tombstones
+    pub policy: RemovalPolicy,
+    /// Nodes whose tracker entries would be dropped, so they count as never published
+    pub tracker_entries: Vec<NodeId>,
+}
+
+impl DestructionPlan {
+    pub fn new(sink: impl Into<String>) -> Self {
+        Self { sink: sink.into(), files: Vec::new(), policy: RemovalPolicy::Delete, tracker_entries: Vec::new() }
+    }
+
+    pub fn with_policy(mut self, policy: RemovalPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Whether carrying out the plan would change nothing
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.tracker_entries.is_empty()
+    }
+
+    /// Hash of everything the plan would delete, including file contents
+    ///
+    /// Two plans with the same fingerprint delete the same things in the same state.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.sink.as_bytes());
+        if self.policy == RemovalPolicy::Tombstone {
+            hasher.update("\ntombstone");
+        }
+        for file in &self.files {
+            hasher.update(format!("\nfile {} {} {}", file.node_id.as_str(), file.path.display(), file.content_hash));
+        }
+        for node_id in &self.tracker_entries {
+            hasher.update(format!("\ntracked {}", node_id.as_str()));
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+impl fmt::Display for DestructionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}:", self.sink)?;
+        if self.is_empty() {
+            return writeln!(f, "  nothing to remove");
+        }
+        if !self.files.is_empty() {
+            match self.policy {
+                RemovalPolicy::Delete => writeln!(f, "  delete {} files:", self.files.len())?,
+                RemovalPolicy::Tombstone => writeln!(f, "  leave tombstones for {} files:", self.files.len())?,
+            }
+            for file in &self.files {
+                match &file.title {
+                    Some(title) => writeln!(f, "    - {} \"{}\"", file.path.display(), title)?,
+                    None => writeln!(f, "    - {}", file.path.display())?,
+                }
+            }
+        }
+        if !self.tracker_entries.is_empty() {
+            writeln!(f, "  forget {} tracker entries:", self.tracker_entries.len())?;
+            for node_id in &self.tracker_entries {
+                writeln!(f, "    - {}", node_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The plan of a sink changed between being shown and being carried out
+#[derive(Debug, thiserror::Error)]
+#[error("what {sink} would remove changed since it was planned; review the new plan and confirm it")]
+pub struct PlanChanged {
+    pub sink: String,
+    /// The plan as it stands now
+    pub current: DestructionPlan,
+}
+
+/// Nodes published to a sink that are gone from the canvas or excluded
+///
+/// Nodes recorded as living elsewhere are never candidates: what's elsewhere isn't ours to
+/// remove.
+fn prune_candidates(target: &SinkTarget, on_canvas: &HashSet<NodeId>, exclusions: &ExclusionList) -> Vec<NodeId> {
+    let mut node_ids: Vec<NodeId> = target
+        .tracker
+        .published()
+        .filter_map(|node_id| node_id.parse().ok())
+        .filter(|node_id| target.tracker.external_url(node_id).is_none())
+        .filter(|node_id| !on_canvas.contains(node_id) || exclusions.is_excluded(node_id))
+        .collect();
+    node_ids.sort_by(cmp_node_ids);
+    node_ids
+}
+
+/// Node IDs on the canvas as it is now
+fn canvas_node_ids(canvas_path: &Path) -> Result<HashSet<NodeId>, Box<dyn Error>> {
+    let (canvas, _) = parse_canvas(&std::fs::read_to_string(canvas_path)?)?;
+    Ok(canvas.get_nodes().keys().cloned().collect())
+}
+
+/// Plan removing the published output of nodes that were deleted from the canvas or
+/// excluded, one plan per sink; nothing is changed
+///
+/// A truncated canvas would make every published node a candidate, so planning is refused
+/// like a run when `guard` finds a mass removal. Sinks that can't remove what they
+/// published are left out, and so keep tracking those nodes.
+pub fn plan_prune(
+    canvas_path: &Path,
+    targets: &[SinkTarget],
+    guard: &RemovalGuard,
+) -> Result<Vec<DestructionPlan>, Box<dyn Error>> {
+    let on_canvas = canvas_node_ids(canvas_path)?;
+    guard.check(canvas_path, &on_canvas)?;
+    let exclusions = ExclusionList::for_canvas(canvas_path)?;
+
+    let mut plans = Vec::new();
+    for target in targets {
+        let sink_name = target.sink.name();
+        let node_ids = prune_candidates(target, &on_canvas, &exclusions);
+        let mut plan = match target.sink.plan_removal(&node_ids) {
+            Ok(plan) => plan,
+            Err(e) if e.is_unsupported() => {
+                debug!(sink = %sink_name, reason = %e.kind(), "Skipping prune");
+                continue;
+            }
+            Err(e) => return Err(e.in_sink(sink_name).into()),
+        };
+        plan.tracker_entries = node_ids;
+        plans.push(plan);
+    }
+    Ok(plans)
+}
+
+/// Carry out plans from [`plan_prune`] after they were reviewed
+///
+/// Every plan is made again first; if any differs from the reviewed one (a file was
+/// edited, a node came back, ...) nothing is removed and [`PlanChanged`] is returned.
+/// Each sink removes its files in a batch, and its tracker entries are dropped once the
+/// batch is committed. The canvas is then recorded for `guard`, as after a run. Returns the
+/// plans carried out.
+pub fn prune(
+    canvas_path: &Path,
+    targets: &mut [SinkTarget],
+    reviewed: &[DestructionPlan],
+    guard: &RemovalGuard,
+    dry_run: bool,
+) -> Result<Vec<DestructionPlan>, Box<dyn Error>> {
+    let current = plan_prune(canvas_path, targets, guard)?;
+    for plan in &current {
+        let matches = reviewed
+            .iter()
+            .find(|r| r.sink == plan.sink)
+            .is_some_and(|r| r.fingerprint() == plan.fingerprint());
+        if !matches && !plan.is_empty() {
+            return Err(PlanChanged { sink: plan.sink.clone(), current: plan.clone() }.into());
+        }
+    }
+
+    let mut done = Vec::new();
+    for plan in current.into_iter().filter(|plan| !plan.is_empty()) {
+        let Some(target) = targets.iter_mut().find(|t| t.sink.name() == plan.sink) else {
+            continue;
+        };
+        let dry_run = target.is_dry(dry_run);
+
+        target.sink.begin(dry_run).map_err(|e| e.in_sink(&plan.sink))?;
+        let removed = target.sink.remove(&plan, dry_run).and_then(|()| target.sink.commit(dry_run));
+        if let Err(e) = removed {
+            let e = e.in_sink(&plan.sink);
+            error!(error = %e, step = ?e.step(), "Failed to remove files, aborting batch");
+            if let Err(abort_error) = target.sink.abort(dry_run) {
+                error!(sink = %plan.sink, error = %abort_error, "Failed to abort removal batch");
+            }
+            return Err(e.into());
+        }
+
+        if !dry_run {
+            target.tracker.forget(&plan.tracker_entries)?;
+        }
+        info!(sink = %plan.sink, files = plan.files.len(), tracker_entries = plan.tracker_entries.len(), "Pruned");
+        done.push(plan);
+    }
+
+    if !dry_run && !done.is_empty() {
+        let on_canvas = canvas_node_ids(canvas_path)?;
+        let mut snapshot = RemovalSnapshot::for_canvas(canvas_path)?;
+        snapshot.record(&on_canvas, |node_id| targets.iter().any(|t| t.tracker.is_published(node_id)));
+        snapshot.save()?;
+    }
+    Ok(done)
+}
@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::SyndicationFormat;
+use crate::atomic::write_atomic;
+use crate::jsoncanvas::NodeId;
+use crate::tracker::canvas_state_path;
+
+/// Suffix of the file the outbound queue is kept in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-queue.json";
+
+/// An item waiting to be delivered to one sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueEntry {
+    /// Name of the sink the item is for
+    pub sink: String,
+    /// The item as it was when enqueued
+    pub item: SyndicationFormat,
+    pub enqueued_at: DateTime<Local>,
+    /// Failed delivery attempts so far
+    pub attempts: u32,
+    /// Earliest time of the next delivery attempt
+    pub not_before: DateTime<Local>,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+}
+
+/// JSON structure for the queue file
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QueueFile {
+    pub entries: Vec<QueueEntry>,
+    /// Time of the last successful delivery per sink, for rate limiting
+    #[serde(default)]
+    pub last_delivery: HashMap<String, DateTime<Local>>,
+}
+
+/// Options for draining the outbound queue
+#[derive(Debug, Clone)]
+pub struct DeliveryOptions {
+    /// Backoff after the first failed attempt; doubles on every further failure
+    pub retry_base: Duration,
+    /// Upper bound for the retry backoff
+    pub retry_max: Duration,
+    /// Minimum time between deliveries to a sink, by sink name
+    pub min_interval: HashMap<String, Duration>,
+    /// How often the watcher drains the queue when the canvas doesn't change
+    pub poll_interval: Duration,
+}
+
+impl Default for DeliveryOptions {
+    fn default() -> Self {
+        Self {
+            retry_base: Duration::from_secs(30),
+            retry_max: Duration::from_secs(60 * 60),
+            min_interval: HashMap::new(),
+            poll_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl DeliveryOptions {
+    /// Backoff before the attempt following `attempts` failures
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.retry_base.saturating_mul(factor).min(self.retry_max)
+    }
+}
+
+/// Durable queue of items waiting to be delivered to sinks
+///
+/// Detection enqueues and delivery drains, so outages and retries survive restarts.
+/// Entries are only removed once the sink's tracker records them, giving at-least-once
+/// delivery with the tracker deduplicating replays.
+pub struct OutboundQueue {
+    /// Path to the JSON queue file
+    path: PathBuf,
+    state: QueueFile,
+}
+
+impl OutboundQueue {
+    /// Load the queue for a canvas file
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-queue.json`
+    pub fn for_canvas(canvas_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = canvas_state_path(canvas_path, STATE_FILE)?;
+        let state = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            QueueFile::default()
+        };
+
+        Ok(Self { path, state })
+    }
+
+    /// Whether an item is already queued for a sink
+    pub fn contains(&self, sink: &str, node_id: &NodeId) -> bool {
+        self.state.entries.iter().any(|e| e.sink == sink && e.item.id == *node_id)
+    }
+
+    /// Queue an item for a sink; returns false if it was already queued
+    pub fn enqueue(&mut self, sink: &str, item: &SyndicationFormat, now: DateTime<Local>) -> bool {
+        if self.contains(sink, &item.id) {
+            return false;
+        }
+        self.state.entries.push(QueueEntry {
+            sink: sink.to_string(),
+            item: item.clone(),
+            enqueued_at: now,
+            attempts: 0,
+            not_before: now,
+            last_error: None,
+        });
+        true
+    }
+
+    /// Every entry, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &QueueEntry> {
+        self.state.entries.iter()
+    }
+
+    /// Entries for a sink, oldest first
+    pub fn entries_for<'a>(&'a self, sink: &'a str) -> impl Iterator<Item = &'a QueueEntry> + 'a {
+        self.state.entries.iter().filter(move |e| e.sink == sink)
+    }
+
+    /// Remove the given items from a sink's queue
+    pub fn remove(&mut self, sink: &str, node_ids: &[NodeId]) {
+        self.state
+            .entries
+            .retain(|e| e.sink != sink || !node_ids.contains(&e.item.id));
+    }
+
+    /// Record a failed attempt and schedule the retry
+    ///
+    /// Permanent failures wait the longest backoff straight away: retrying sooner fails the
+    /// same way, but whatever was wrong may get fixed in the meantime.
+    pub fn record_failure(
+        &mut self,
+        sink: &str,
+        node_ids: &[NodeId],
+        error: &str,
+        permanent: bool,
+        now: DateTime<Local>,
+        options: &DeliveryOptions,
+    ) {
+        for entry in &mut self.state.entries {
+            if entry.sink == sink && node_ids.contains(&entry.item.id) {
+                entry.attempts += 1;
+                entry.last_error = Some(error.to_string());
+                let backoff = if permanent { options.retry_max } else { options.backoff(entry.attempts) };
+                let backoff = chrono::Duration::from_std(backoff).unwrap_or(chrono::Duration::MAX);
+                entry.not_before = now.checked_add_signed(backoff).unwrap_or(now);
+            }
+        }
+    }
+
+    /// Time of the last successful delivery to a sink
+    pub fn last_delivery(&self, sink: &str) -> Option<DateTime<Local>> {
+        self.state.last_delivery.get(sink).copied()
+    }
+
+    /// Record a successful delivery to a sink
+    pub fn record_delivery(&mut self, sink: &str, now: DateTime<Local>) {
+        self.state.last_delivery.insert(sink.to_string(), now);
+    }
+
+    /// Number of queued entries per sink
+    pub fn depth_by_sink(&self) -> BTreeMap<String, usize> {
+        let mut depth = BTreeMap::new();
+        for entry in &self.state.entries {
+            *depth.entry(entry.sink.clone()).or_default() += 1;
+        }
+        depth
+    }
+
+    /// Total number of queued entries
+    pub fn len(&self) -> usize {
+        self.state.entries.len()
+    }
+
+    /// Whether nothing is queued
+    pub fn is_empty(&self) -> bool {
+        self.state.entries.is_empty()
+    }
+
+    /// Persist the queue
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_atomic(&self.path, serde_json::to_string_pretty(&self.state)?)?;
+        info!(queue_path = %self.path.display(), queued = self.len(), "Saved queue");
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use chrono::{DateTime, Local, NaiveDate};
+
+use crate::content::frontmatter_value;
+use crate::jsoncanvas::NodeId;
+use crate::tracker::SyndicationTracker;
+
+/// Calendar days from `at` to `now` in the local time zone, so a post from late last night
+/// is a day old this morning however few hours ago it was
+pub fn days_ago(at: DateTime<Local>, now: DateTime<Local>) -> i64 {
+    (now.date_naive() - at.with_timezone(&Local).date_naive()).num_days()
+}
+
+/// Whether a node first published at `published_at` was posted within the last `days`
+/// calendar days, today being the first
+///
+/// A node that was never published counts as posted today, since it's about to be.
+pub fn published_within(published_at: Option<DateTime<Local>>, days: u32, now: DateTime<Local>) -> bool {
+    published_at.is_none_or(|at| days_ago(at, now) < i64::from(days))
+}
+
+/// When a node was first published to any of the sinks of these trackers, if it was and the
+/// time was recorded
+pub fn first_published<'a>(
+    trackers: impl IntoIterator<Item = &'a SyndicationTracker>,
+    node_id: &NodeId,
+) -> Option<DateTime<Local>> {
+    trackers.into_iter().filter_map(|tracker| tracker.published_at(node_id)).min()
+}
+
+/// The `date` in a leading `---` frontmatter block of a node's text, e.g. from a vault note
+///
+/// Takes `YYYY-MM-DD`, optionally quoted or followed by a time, as local midnight that day.
+pub fn frontmatter_date(text: &str) -> Option<DateTime<Local>> {
+    let value = frontmatter_value(text, "date")?;
+    let date = NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+    date.and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest()
+}
+
+/// Whether an item dated `dated_at` is more than `max_age` calendar days old
+pub fn older_than(dated_at: Option<DateTime<Local>>, max_age: u32, now: DateTime<Local>) -> bool {
+    dated_at.is_some_and(|at| days_ago(at, now) > i64::from(max_age))
+}
@@ -0,0 +1,96 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use tracing::{debug, info, warn};
+
+use crate::jsoncanvas::NodeId;
+use crate::node_ids::cmp_node_ids;
+use crate::orchestrator::SinkTarget;
+
+/// Which discrepancies [`reconcile`] should fix rather than just report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReconcileFix {
+    /// Record items found in the sink but missing from the tracker as published
+    pub mark_found: bool,
+    /// Drop tracker entries whose published output no longer exists
+    pub clear_missing: bool,
+}
+
+/// Differences between a sink's tracker and what the sink actually holds
+#[derive(Debug, Clone)]
+pub struct Discrepancies {
+    /// Name of the sink
+    pub sink: String,
+    /// Published in the sink but unknown to the tracker
+    pub untracked: Vec<(NodeId, PathBuf)>,
+    /// In the tracker but gone from the sink
+    pub missing: Vec<NodeId>,
+    /// Whether the fixes requested were applied
+    pub fixed: bool,
+}
+
+impl Discrepancies {
+    /// Whether tracker and sink agree
+    pub fn is_empty(&self) -> bool {
+        self.untracked.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// Compare each sink's tracker against the sink's published output and optionally fix it
+///
+/// Sinks that can't list what they published are skipped.
+pub fn reconcile(targets: &mut [SinkTarget], fix: ReconcileFix) -> Result<Vec<Discrepancies>, Box<dyn Error>> {
+    let mut results = Vec::new();
+
+    for target in targets.iter_mut() {
+        let sink_name = target.sink.name().to_string();
+        let published = match target.sink.list_published() {
+            Ok(published) => published,
+            Err(e) if e.is_unsupported() => {
+                debug!(sink = %sink_name, reason = %e.kind(), "Skipping reconciliation");
+                continue;
+            }
+            Err(e) => return Err(e.in_sink(&sink_name).into()),
+        };
+
+        let mut untracked: Vec<(NodeId, PathBuf)> = published
+            .iter()
+            .filter(|(node_id, _)| !target.tracker.is_published(node_id))
+            .cloned()
+            .collect();
+        untracked.sort_by(|a, b| cmp_node_ids(&a.0, &b.0));
+
+        let mut missing: Vec<NodeId> = target
+            .tracker
+            .published()
+            .filter(|node_id| !published.iter().any(|(id, _)| id.as_str() == *node_id))
+            .filter_map(|node_id| node_id.parse().ok())
+            .collect();
+        missing.sort_by(cmp_node_ids);
+
+        for (node_id, path) in &untracked {
+            warn!(sink = %sink_name, node_id = %node_id, file = %path.display(), "Published but not in tracker");
+        }
+        for node_id in &missing {
+            warn!(sink = %sink_name, node_id = %node_id, "In tracker but not published");
+        }
+
+        let mut fixed = false;
+        if fix.mark_found && !untracked.is_empty() {
+            let ids: Vec<NodeId> = untracked.iter().map(|(id, _)| id.clone()).collect();
+            target.tracker.mark_published(&ids)?;
+            fixed = true;
+        }
+        if fix.clear_missing && !missing.is_empty() {
+            target.tracker.forget(&missing)?;
+            fixed = true;
+        }
+        if fixed {
+            info!(sink = %sink_name, "Reconciled tracker");
+        }
+
+        results.push(Discrepancies { sink: sink_name, untracked, missing, fixed });
+    }
+
+    Ok(results)
+}
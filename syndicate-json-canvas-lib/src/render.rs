@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+/// How markdown is rendered as HTML, shared by every sink that renders it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    /// Render GFM pipe tables
+    pub tables: bool,
+    /// Render `[^1]` footnotes, with their definitions where they were written
+    pub footnotes: bool,
+    /// Turn straight quotes, `--` and `...` into curly quotes, dashes and ellipses
+    pub smart_punctuation: bool,
+    /// Levels added to every heading, e.g. 1 to render `#` as `<h2>` under a page's own
+    /// `<h1>`; headings never go below `<h6>`
+    pub heading_shift: u8,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self { tables: true, footnotes: true, smart_punctuation: false, heading_shift: 0 }
+    }
+}
+
+impl RenderOptions {
+    pub fn with_tables(mut self, enabled: bool) -> Self {
+        self.tables = enabled;
+        self
+    }
+
+    pub fn with_footnotes(mut self, enabled: bool) -> Self {
+        self.footnotes = enabled;
+        self
+    }
+
+    pub fn with_smart_punctuation(mut self, enabled: bool) -> Self {
+        self.smart_punctuation = enabled;
+        self
+    }
+
+    pub fn with_heading_shift(mut self, levels: u8) -> Self {
+        self.heading_shift = levels;
+        self
+    }
+
+    /// Parser options for these render options; strikethrough is always on, as in Obsidian
+    fn parser_options(&self) -> Options {
+        let mut options = Options::ENABLE_STRIKETHROUGH;
+        options.set(Options::ENABLE_TABLES, self.tables);
+        options.set(Options::ENABLE_FOOTNOTES, self.footnotes);
+        options.set(Options::ENABLE_SMART_PUNCTUATION, self.smart_punctuation);
+        options
+    }
+}
+
+/// Renders an item's markdown as an HTML fragment
+///
+/// Sinks that publish HTML take one at construction, so every sink renders an item the
+/// same way. [`PulldownRenderer`] is the default; programs embedding the library can supply
+/// their own (e.g. one with GFM autolinks).
+pub trait MarkdownRenderer: Send + Sync {
+    fn render(&self, markdown: &str) -> String;
+}
+
+/// The default [`MarkdownRenderer`], built on pulldown-cmark
+#[derive(Debug, Clone, Default)]
+pub struct PulldownRenderer {
+    options: RenderOptions,
+}
+
+impl PulldownRenderer {
+    pub fn new(options: RenderOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn options(&self) -> &RenderOptions {
+        &self.options
+    }
+}
+
+impl MarkdownRenderer for PulldownRenderer {
+    fn render(&self, markdown: &str) -> String {
+        let mut writer = HtmlWriter::new(self.options.heading_shift);
+        for event in Parser::new_ext(markdown, self.options.parser_options()) {
+            writer.event(event);
+        }
+        writer.out
+    }
+}
+
+/// Render markdown as HTML with the default [`RenderOptions`]
+pub fn to_html(markdown: &str) -> String {
+    PulldownRenderer::default().render(markdown)
+}
+
+/// Walks parser events, writing HTML close to pulldown-cmark's own `push_html`
+struct HtmlWriter {
+    out: String,
+    heading_shift: u8,
+    /// Alignments of the table being written
+    alignments: Vec<Alignment>,
+    in_table_head: bool,
+    in_table_body: bool,
+    cell: usize,
+    /// Nesting of images being written, whose text goes into `alt`
+    image_depth: usize,
+    /// Numbers of footnotes, in order of first appearance
+    footnotes: HashMap<String, usize>,
+}
+
+impl HtmlWriter {
+    fn new(heading_shift: u8) -> Self {
+        Self {
+            out: String::new(),
+            heading_shift,
+            alignments: Vec::new(),
+            in_table_head: false,
+            in_table_body: false,
+            cell: 0,
+            image_depth: 0,
+            footnotes: HashMap::new(),
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        if self.image_depth > 0 {
+            match event {
+                Event::Start(Tag::Image { .. }) => self.image_depth += 1,
+                Event::End(TagEnd::Image) => {
+                    self.image_depth -= 1;
+                    if self.image_depth == 0 {
+                        self.out.push_str("\" />");
+                    }
+                }
+                Event::Text(text) | Event::Code(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                    self.out.push_str(&escape(&text))
+                }
+                Event::SoftBreak | Event::HardBreak => self.out.push(' '),
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.out.push_str(&escape(&text)),
+            Event::Code(text) => self.out.push_str(&format!("<code>{}</code>", escape(&text))),
+            Event::Html(html) | Event::InlineHtml(html) => self.out.push_str(&html),
+            Event::SoftBreak => self.out.push('\n'),
+            Event::HardBreak => self.out.push_str("<br />\n"),
+            Event::Rule => {
+                self.block_start();
+                self.out.push_str("<hr />\n");
+            }
+            Event::FootnoteReference(label) => {
+                let number = self.footnote_number(&label);
+                self.out.push_str(&format!(
+                    "<sup class=\"footnote-reference\"><a href=\"#{}\">{}</a></sup>",
+                    escape(&label),
+                    number
+                ));
+            }
+            Event::TaskListMarker(checked) => {
+                let checked = if checked { " checked=\"\"" } else { "" };
+                self.out.push_str(&format!("<input disabled=\"\" type=\"checkbox\"{} />\n", checked));
+            }
+            Event::InlineMath(math) | Event::DisplayMath(math) => self.out.push_str(&escape(&math)),
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {
+                self.block_start();
+                self.out.push_str("<p>");
+            }
+            Tag::Heading { level, id, .. } => {
+                self.block_start();
+                let level = self.heading_level(level as usize);
+                match id {
+                    Some(id) => self.out.push_str(&format!("<h{} id=\"{}\">", level, escape(&id))),
+                    None => self.out.push_str(&format!("<h{}>", level)),
+                }
+            }
+            Tag::BlockQuote(_) => {
+                self.block_start();
+                self.out.push_str("<blockquote>\n");
+            }
+            Tag::CodeBlock(kind) => {
+                self.block_start();
+                match kind {
+                    CodeBlockKind::Fenced(info) if !info.is_empty() => {
+                        let language = info.split([' ', ',']).next().unwrap_or_default();
+                        self.out.push_str(&format!("<pre><code class=\"language-{}\">", escape(language)));
+                    }
+                    _ => self.out.push_str("<pre><code>"),
+                }
+            }
+            Tag::List(Some(1)) => {
+                self.block_start();
+                self.out.push_str("<ol>\n");
+            }
+            Tag::List(Some(start)) => {
+                self.block_start();
+                self.out.push_str(&format!("<ol start=\"{}\">\n", start));
+            }
+            Tag::List(None) => {
+                self.block_start();
+                self.out.push_str("<ul>\n");
+            }
+            Tag::Item => {
+                self.block_start();
+                self.out.push_str("<li>");
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.block_start();
+                let number = self.footnote_number(&label);
+                self.out.push_str(&format!(
+                    "<div class=\"footnote-definition\" id=\"{}\"><sup class=\"footnote-definition-label\">{}</sup>",
+                    escape(&label),
+                    number
+                ));
+            }
+            Tag::Table(alignments) => {
+                self.block_start();
+                self.alignments = alignments;
+                self.out.push_str("<table>");
+            }
+            Tag::TableHead => {
+                self.in_table_head = true;
+                self.cell = 0;
+                self.out.push_str("<thead><tr>");
+            }
+            Tag::TableRow => {
+                if !self.in_table_body {
+                    self.in_table_body = true;
+                    self.out.push_str("<tbody>\n");
+                }
+                self.cell = 0;
+                self.out.push_str("<tr>");
+            }
+            Tag::TableCell => {
+                let element = if self.in_table_head { "th" } else { "td" };
+                match self.alignments.get(self.cell) {
+                    Some(Alignment::Left) => self.out.push_str(&format!("<{} style=\"text-align: left\">", element)),
+                    Some(Alignment::Center) => {
+                        self.out.push_str(&format!("<{} style=\"text-align: center\">", element))
+                    }
+                    Some(Alignment::Right) => self.out.push_str(&format!("<{} style=\"text-align: right\">", element)),
+                    _ => self.out.push_str(&format!("<{}>", element)),
+                }
+            }
+            Tag::Emphasis => self.out.push_str("<em>"),
+            Tag::Strong => self.out.push_str("<strong>"),
+            Tag::Strikethrough => self.out.push_str("<del>"),
+            Tag::Superscript => self.out.push_str("<sup>"),
+            Tag::Subscript => self.out.push_str("<sub>"),
+            Tag::Link { dest_url, title, .. } => {
+                self.out.push_str(&format!("<a href=\"{}\"", escape(&dest_url)));
+                if !title.is_empty() {
+                    self.out.push_str(&format!(" title=\"{}\"", escape(&title)));
+                }
+                self.out.push('>');
+            }
+            Tag::Image { dest_url, title, .. } => {
+                self.out.push_str(&format!("<img src=\"{}\"", escape(&dest_url)));
+                if !title.is_empty() {
+                    self.out.push_str(&format!(" title=\"{}\"", escape(&title)));
+                }
+                self.out.push_str(" alt=\"");
+                self.image_depth = 1;
+            }
+            Tag::HtmlBlock
+            | Tag::DefinitionList
+            | Tag::DefinitionListTitle
+            | Tag::DefinitionListDefinition
+            | Tag::MetadataBlock(_) => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.out.push_str("</p>\n"),
+            TagEnd::Heading(level) => {
+                let level = self.heading_level(level as usize);
+                self.out.push_str(&format!("</h{}>\n", level));
+            }
+            TagEnd::BlockQuote(_) => self.out.push_str("</blockquote>\n"),
+            TagEnd::CodeBlock => self.out.push_str("</code></pre>\n"),
+            TagEnd::List(true) => self.out.push_str("</ol>\n"),
+            TagEnd::List(false) => self.out.push_str("</ul>\n"),
+            TagEnd::Item => self.out.push_str("</li>\n"),
+            TagEnd::FootnoteDefinition => self.out.push_str("</div>\n"),
+            TagEnd::Table => {
+                if self.in_table_body {
+                    self.out.push_str("</tbody>");
+                }
+                self.out.push_str("</table>\n");
+                self.in_table_body = false;
+            }
+            TagEnd::TableHead => {
+                self.out.push_str("</tr></thead>\n");
+                self.in_table_head = false;
+            }
+            TagEnd::TableRow => self.out.push_str("</tr>\n"),
+            TagEnd::TableCell => {
+                self.out.push_str(if self.in_table_head { "</th>" } else { "</td>" });
+                self.cell += 1;
+            }
+            TagEnd::Emphasis => self.out.push_str("</em>"),
+            TagEnd::Strong => self.out.push_str("</strong>"),
+            TagEnd::Strikethrough => self.out.push_str("</del>"),
+            TagEnd::Superscript => self.out.push_str("</sup>"),
+            TagEnd::Subscript => self.out.push_str("</sub>"),
+            TagEnd::Link => self.out.push_str("</a>"),
+            TagEnd::Image
+            | TagEnd::HtmlBlock
+            | TagEnd::DefinitionList
+            | TagEnd::DefinitionListTitle
+            | TagEnd::DefinitionListDefinition
+            | TagEnd::MetadataBlock(_) => {}
+        }
+    }
+
+    /// Start block elements on a line of their own
+    fn block_start(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn heading_level(&self, level: usize) -> usize {
+        (level + self.heading_shift as usize).min(6)
+    }
+
+    fn footnote_number(&mut self, label: &str) -> usize {
+        let next = self.footnotes.len() + 1;
+        *self.footnotes.entry(label.to_string()).or_insert(next)
+    }
+}
+
+/// Escape text for HTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "| a | b |\n|---|:-:|\n| 1 | 2 |\n";
+    const FOOTNOTE: &str = "Said[^n].\n\n[^n]: A note\n";
+
+    fn render(options: RenderOptions, markdown: &str) -> String {
+        PulldownRenderer::new(options).render(markdown)
+    }
+
+    #[test]
+    fn options_toggle_each_extension() {
+        let on = RenderOptions::default();
+        assert_eq!(
+            render(on, TABLE),
+            "<table><thead><tr><th>a</th><th style=\"text-align: center\">b</th></tr></thead>\n<tbody>\n\
+             <tr><td>1</td><td style=\"text-align: center\">2</td></tr>\n</tbody></table>\n"
+        );
+        assert_eq!(render(on.with_tables(false), TABLE), "<p>| a | b |\n|---|:-:|\n| 1 | 2 |</p>\n");
+
+        assert_eq!(
+            render(on, FOOTNOTE),
+            "<p>Said<sup class=\"footnote-reference\"><a href=\"#n\">1</a></sup>.</p>\n\
+             <div class=\"footnote-definition\" id=\"n\"><sup class=\"footnote-definition-label\">1</sup>\n\
+             <p>A note</p>\n</div>\n"
+        );
+        assert_eq!(render(on.with_footnotes(false), FOOTNOTE), "<p>Said[^n].</p>\n<p>[^n]: A note</p>\n");
+
+        let quoted = "\"Quoted\" -- and so...";
+        assert_eq!(render(on, quoted), "<p>&quot;Quoted&quot; -- and so...</p>\n");
+        let smart = "<p>\u{201c}Quoted\u{201d} \u{2013} and so\u{2026}</p>\n";
+        assert_eq!(render(on.with_smart_punctuation(true), quoted), smart);
+
+        // Shifted headings stop at h6
+        assert_eq!(render(on.with_heading_shift(2), "# One\n\n##### Five\n"), "<h3>One</h3>\n<h6>Five</h6>\n");
+    }
+
+    #[test]
+    fn text_is_escaped_and_images_keep_their_alt_text() {
+        assert_eq!(
+            to_html("![An <owl>](owl.png \"t\") and *em* `x<y`\n"),
+            "<p><img src=\"owl.png\" title=\"t\" alt=\"An &lt;owl&gt;\" /> and <em>em</em> <code>x&lt;y</code></p>\n"
+        );
+    }
+
+    /// Wraps another renderer's output, as a program embedding the library might
+    struct Article(PulldownRenderer);
+
+    impl MarkdownRenderer for Article {
+        fn render(&self, markdown: &str) -> String {
+            format!("<article>{}</article>", self.0.render(markdown))
+        }
+    }
+
+    #[test]
+    fn renderers_are_interchangeable_behind_the_trait() {
+        let renderers: [Box<dyn MarkdownRenderer>; 2] =
+            [Box::new(PulldownRenderer::default()), Box::new(Article(PulldownRenderer::default()))];
+        let html: Vec<String> = renderers.iter().map(|renderer| renderer.render("*Hi*")).collect();
+        assert_eq!(html, ["<p><em>Hi</em></p>\n", "<article><p><em>Hi</em></p>\n</article>"]);
+        assert_eq!(to_html("*Hi*"), html[0]);
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::archive::contains;
+use crate::category::hashtags;
+use crate::jsoncanvas::{JsonCanvas, Node, NodeId, node::GenericNodeInfo};
+
+/// Which items a replacement rule applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleScope {
+    /// Every item
+    Global,
+    /// Items tagged `#tag`, compared without the `#` and ignoring case
+    Tag(String),
+    /// Items lying inside a group of this label, compared ignoring case
+    Group(String),
+}
+
+/// What a rule looks for
+#[derive(Debug, Clone)]
+enum Pattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+/// A text replacement applied to items before publishing, e.g. a name turned into initials
+#[derive(Debug, Clone)]
+pub struct ReplacementRule {
+    /// Shown when the rule fires; the pattern unless given
+    pub name: String,
+    pattern: Pattern,
+    /// Text put in place of each match; `$1` or `${name}` insert a regex's capture groups
+    pub replacement: String,
+    pub scope: RuleScope,
+}
+
+impl ReplacementRule {
+    /// Replace every occurrence of `find`, as written
+    pub fn literal(find: impl Into<String>, replacement: impl Into<String>) -> Self {
+        let find = find.into();
+        Self {
+            name: find.clone(),
+            pattern: Pattern::Literal(find),
+            replacement: replacement.into(),
+            scope: RuleScope::Global,
+        }
+    }
+
+    /// Replace every match of a regular expression (`regex` crate syntax)
+    pub fn regex(pattern: &str, replacement: impl Into<String>) -> Result<Self, regex::Error> {
+        Ok(Self {
+            name: pattern.to_string(),
+            pattern: Pattern::Regex(Regex::new(pattern)?),
+            replacement: replacement.into(),
+            scope: RuleScope::Global,
+        })
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_scope(mut self, scope: RuleScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Whether the rule applies to an item of these tags inside groups of these labels
+    fn applies(&self, tags: &[String], groups: &[String]) -> bool {
+        match &self.scope {
+            RuleScope::Global => true,
+            RuleScope::Tag(tag) => tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim_start_matches('#'))),
+            RuleScope::Group(label) => groups.iter().any(|g| g.trim().eq_ignore_ascii_case(label.trim())),
+        }
+    }
+
+    /// The text with the rule applied, and how many matches were replaced
+    fn apply(&self, text: &str) -> (String, usize) {
+        match &self.pattern {
+            Pattern::Literal(find) if find.is_empty() => (text.to_string(), 0),
+            Pattern::Literal(find) => {
+                (text.replace(find.as_str(), &self.replacement), text.matches(find.as_str()).count())
+            }
+            Pattern::Regex(regex) => {
+                let count = regex.find_iter(text).filter(|m| !m.is_empty()).count();
+                if count == 0 {
+                    return (text.to_string(), 0);
+                }
+                (regex.replace_all(text, self.replacement.as_str()).into_owned(), count)
+            }
+        }
+    }
+}
+
+/// A rule that changed an item's text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Redaction {
+    pub node_id: String,
+    /// Name of the rule
+    pub rule: String,
+    /// Matches replaced
+    pub count: usize,
+}
+
+/// An ordered list of replacement rules
+///
+/// Each rule sees the text as the rules before it left it, so the order matters: a rule
+/// turning `Jane Doe` into `J.D.` must come before one turning `Jane` into `J.`.
+/// [`Replacements::load`] reads the rules from the `[[replace]]` tables of a TOML file.
+#[derive(Debug, Clone, Default)]
+pub struct Replacements {
+    rules: Vec<ReplacementRule>,
+}
+
+/// A `[[replace]]` table
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RuleEntry {
+    find: Option<String>,
+    regex: Option<String>,
+    with: String,
+    name: Option<String>,
+    tag: Option<String>,
+    group: Option<String>,
+}
+
+impl RuleEntry {
+    /// The rule the table describes, or the key it's wrong in with why
+    pub(crate) fn into_rule(self) -> Result<ReplacementRule, (&'static str, String)> {
+        let mut rule = match (self.find, self.regex) {
+            (Some(find), None) => ReplacementRule::literal(find, self.with),
+            (None, Some(regex)) => ReplacementRule::regex(&regex, self.with).map_err(|e| ("regex", e.to_string()))?,
+            _ => return Err(("find", "needs exactly one of `find` and `regex`".to_string())),
+        };
+        rule.scope = match (self.tag, self.group) {
+            (None, None) => RuleScope::Global,
+            (Some(tag), None) => RuleScope::Tag(tag),
+            (None, Some(group)) => RuleScope::Group(group),
+            (Some(_), Some(_)) => return Err(("group", "`tag` and `group` can't be combined".to_string())),
+        };
+        if let Some(name) = self.name {
+            rule.name = name;
+        }
+        Ok(rule)
+    }
+}
+
+/// TOML file holding `[[replace]]` tables
+#[derive(Debug, Default, Deserialize)]
+struct ReplacementsFile {
+    #[serde(default)]
+    replace: Vec<RuleEntry>,
+}
+
+impl Replacements {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule, applied after the rules added before it
+    pub fn with_rule(mut self, rule: ReplacementRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Read the `[[replace]]` tables of a TOML file, in order
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse the `[[replace]]` tables of a TOML document, in order
+    ///
+    /// Each table has `find` (literal) or `regex`, the replacement `with`, and optionally a
+    /// `name` and a `tag` or `group` the rule is limited to.
+    pub fn from_toml(toml: &str) -> Result<Self, Box<dyn Error>> {
+        let mut replacements = Self::new();
+        for (i, entry) in toml::from_str::<ReplacementsFile>(toml)?.replace.into_iter().enumerate() {
+            let rule = entry.into_rule().map_err(|(_, message)| format!("replace #{}: {}", i + 1, message))?;
+            replacements.rules.push(rule);
+        }
+        Ok(replacements)
+    }
+
+    pub fn rules(&self) -> &[ReplacementRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply the rules in order to the text of a node inside groups of these labels, returning
+    /// the new text and the names of the rules that fired with their counts
+    ///
+    /// Tag scopes are decided on the text as written, so a rule can't take an item out of
+    /// the scope of a later one.
+    pub fn apply(&self, text: &str, groups: &[String]) -> (String, Vec<(String, usize)>) {
+        let tags = hashtags(text);
+        let mut text = text.to_string();
+        let mut fired = Vec::new();
+        for rule in self.rules.iter().filter(|rule| rule.applies(&tags, groups)) {
+            let (replaced, count) = rule.apply(&text);
+            if count > 0 {
+                fired.push((rule.name.clone(), count));
+                text = replaced;
+            }
+        }
+        (text, fired)
+    }
+}
+
+/// Labels of the groups each non-group node lies entirely inside, outermost first
+pub fn group_labels(canvas: &JsonCanvas) -> HashMap<NodeId, Vec<String>> {
+    let nodes = canvas.get_nodes();
+    let mut groups: Vec<(&Node, &String)> = nodes
+        .values()
+        .filter_map(|node| match node {
+            Node::Group(group) => group.label().map(|label| (node, label)),
+            _ => None,
+        })
+        .collect();
+    groups.sort_by_key(|(group, _)| std::cmp::Reverse(area(group)));
+
+    nodes
+        .iter()
+        .filter(|(_, node)| !matches!(node, Node::Group(_)))
+        .filter_map(|(node_id, node)| {
+            let labels: Vec<String> = groups
+                .iter()
+                .filter(|(group, _)| contains(group, node))
+                .map(|(_, label)| label.to_string())
+                .collect();
+            (!labels.is_empty()).then(|| (node_id.clone(), labels))
+        })
+        .collect()
+}
+
+fn area(node: &Node) -> u64 {
+    node.get_width().saturating_mul(node.get_height())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_run_in_order_with_capture_groups() {
+        let replacements = Replacements::new()
+            .with_rule(ReplacementRule::literal("Jane Doe", "J.D.").with_name("full name"))
+            .with_rule(ReplacementRule::literal("Jane", "J."))
+            .with_rule(ReplacementRule::regex(r"(?P<user>\w+)@(\w+)\.com", "$user at ${2}").unwrap());
+        let (text, fired) = replacements.apply("Jane Doe met Jane; mail jd@example.com or x@y.com", &[]);
+        assert_eq!(text, "J.D. met J.; mail jd at example or x at y");
+        let names: Vec<(&str, usize)> = fired.iter().map(|(name, count)| (name.as_str(), *count)).collect();
+        assert_eq!(names, [("full name", 1), ("Jane", 1), (r"(?P<user>\w+)@(\w+)\.com", 2)]);
+
+        // The other way round, the shorter name eats the longer one
+        let reversed = Replacements::new()
+            .with_rule(ReplacementRule::literal("Jane", "J."))
+            .with_rule(ReplacementRule::literal("Jane Doe", "J.D."));
+        assert_eq!(reversed.apply("Jane Doe", &[]), ("J. Doe".to_string(), vec![("Jane".to_string(), 1)]));
+    }
+
+    #[test]
+    fn scoped_rules_only_touch_their_tag_or_group() {
+        let toml = r##"
+            [[replace]]
+            find = "Acme"
+            with = "a client"
+            tag = "#work"
+
+            [[replace]]
+            regex = "\\bBob\\b"
+            with = "B."
+            group = "Family"
+        "##;
+        let replacements = Replacements::from_toml(toml).unwrap();
+        assert_eq!(replacements.apply("Acme and Bob #Work", &[]).0, "a client and Bob #Work");
+        assert_eq!(replacements.apply("Acme and Bob", &[" family ".to_string()]).0, "Acme and B.");
+        assert_eq!(replacements.apply("Acme and Bob", &["Friends".to_string()]).0, "Acme and Bob");
+
+        // A rule can't take an item out of a later rule's tag scope
+        let untagging = Replacements::new()
+            .with_rule(ReplacementRule::literal("#work", ""))
+            .with_rule(ReplacementRule::literal("Acme", "a client").with_scope(RuleScope::Tag("work".to_string())));
+        assert_eq!(untagging.apply("Acme #work", &[]).0, "a client ");
+
+        let bad = Replacements::from_toml("[[replace]]\nfind = \"x\"\nregex = \"y\"\nwith = \"z\"\n").unwrap_err();
+        assert_eq!(bad.to_string(), "replace #1: needs exactly one of `find` and `regex`");
+        assert!(Replacements::from_toml("[[replace]]\nregex = \"(\"\nwith = \"z\"\n").is_err());
+    }
+}
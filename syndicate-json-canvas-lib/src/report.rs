@@ -0,0 +1,569 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::compat::CompatibilityReport;
+use crate::duplicates::DuplicateGroup;
+use crate::jsoncanvas::NodeId;
+use crate::link_check::LinkFinding;
+use crate::preview::{PlannedCommit, PlannedWrite, WriteKind, WriteSummary};
+use crate::replace::Redaction;
+use crate::warnings::{Warning, group_warnings};
+use crate::sink::SinkError;
+
+/// Outcome of publishing to a single sink during a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SinkOutcome {
+    /// Name of the sink
+    pub sink: String,
+    /// Whether the sink ran in dry-run mode
+    pub dry_run: bool,
+    /// Node IDs handed to the sink
+    pub items: Vec<String>,
+    /// Node IDs archived in this run
+    #[serde(default)]
+    pub archived: Vec<String>,
+    /// Node IDs un-archived in this run
+    #[serde(default)]
+    pub unarchived: Vec<String>,
+    /// Node IDs whose edits were delivered in this run
+    #[serde(default)]
+    pub updated: Vec<String>,
+    /// Node IDs posted again from the archive in this run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resurfaced: Vec<String>,
+    /// Node IDs skipped for good in this run for being older than the sink's maximum item age
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub age_skipped: Vec<String>,
+    /// Node IDs held back for a later run by the run's limit on new items
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub held_back: Vec<String>,
+    /// URLs of the posts created on other sites, by node ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub urls: BTreeMap<String, String>,
+    /// Files the sink would have written, when dry, compared with the published ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub planned_writes: Vec<PlannedWrite>,
+    /// Commit the sink would have made, when dry, for sinks publishing to a repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planned_commit: Option<PlannedCommit>,
+    /// Warnings the sink raised
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// Error message if publishing failed
+    pub error: Option<String>,
+    /// Workflow step that failed, for command failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_step: Option<String>,
+    /// Exit code of the failed command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_exit_code: Option<i32>,
+    /// Node the error is about, if it was about one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_node_id: Option<String>,
+    /// Whether the error won't go away by retrying, e.g. the destination rejected the request
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub error_permanent: bool,
+}
+
+impl SinkOutcome {
+    /// Outcome for handing `node_ids` to a sink, with no error yet
+    pub fn new(sink: &str, dry_run: bool, node_ids: &[NodeId]) -> Self {
+        Self {
+            sink: sink.to_string(),
+            dry_run,
+            items: node_ids.iter().map(|id| id.as_str().to_string()).collect(),
+            archived: Vec::new(),
+            unarchived: Vec::new(),
+            updated: Vec::new(),
+            resurfaced: Vec::new(),
+            age_skipped: Vec::new(),
+            held_back: Vec::new(),
+            urls: BTreeMap::new(),
+            planned_writes: Vec::new(),
+            planned_commit: None,
+            warnings: Vec::new(),
+            error: None,
+            error_step: None,
+            error_exit_code: None,
+            error_node_id: None,
+            error_permanent: false,
+        }
+    }
+
+    /// Record a sink error, keeping its step, exit code and node as separate fields
+    pub fn set_error(&mut self, error: &SinkError) {
+        self.error = Some(error.to_string());
+        self.error_step = error.step().map(str::to_string);
+        self.error_exit_code = error.exit_code();
+        self.error_node_id = error.node_id().map(|node_id| node_id.as_str().to_string());
+        self.error_permanent = !error.is_transient();
+    }
+}
+
+/// Structured result of a single processing run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessReport {
+    /// Canvas file that was processed
+    pub canvas_path: PathBuf,
+    /// SHA-256 of the canvas file contents (None if the file couldn't be read)
+    pub canvas_hash: Option<String>,
+    /// Deterministic ID of the run, from the canvas hash and the eligible items (see
+    /// [`run_id`](crate::journal::run_id)); None if the run stopped before publishing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// Whether the run re-executed an interrupted run from its journal
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub resumed: bool,
+    /// When the run started
+    pub started_at: DateTime<Local>,
+    /// Total run duration in milliseconds
+    pub duration_ms: u64,
+    /// Which nodes were selected for syndication, e.g. `marked (red)` or `all nodes`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<String>,
+    /// Number of nodes in the canvas
+    pub nodes_considered: usize,
+    /// Number of nodes that matched the filter (before exclusions)
+    pub items_matched: usize,
+    /// Matching nodes suppressed by the exclusion list
+    pub excluded: Vec<String>,
+    /// Matching nodes that live elsewhere, so only linked to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external: Vec<String>,
+    /// Groups of identical or near-identical items
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    /// Matching nodes skipped because they were already published
+    pub already_published: usize,
+    /// Node IDs that were new in this run
+    pub new_items: Vec<String>,
+    /// Node IDs whose edits were delivered in this run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub updated_items: Vec<String>,
+    /// New node IDs held back in the pending batch
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub batched: Vec<String>,
+    /// Per-sink outcomes
+    pub sinks: Vec<SinkOutcome>,
+    /// Replacement rules that changed items' text, in rule order per node
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redactions: Vec<Redaction>,
+    /// Topics of the content warnings items are published behind, by node ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub content_warnings: BTreeMap<String, String>,
+    /// Links of the items about to be published that weren't plainly ok, by node
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub link_findings: Vec<LinkFinding>,
+    /// Warnings raised before publishing (those raised by sinks are in their outcomes)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<Warning>,
+    /// What parsing the canvas ignored or skipped, if anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compatibility: Option<CompatibilityReport>,
+    /// Error that aborted the run before publishing (e.g. unreadable canvas)
+    pub error: Option<String>,
+    /// Whether the run was cancelled by a newer change before publishing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+}
+
+impl ProcessReport {
+    /// Create an empty report for a run starting at `started_at`
+    pub fn new(canvas_path: &Path, started_at: DateTime<Local>) -> Self {
+        Self {
+            canvas_path: canvas_path.to_path_buf(),
+            canvas_hash: None,
+            run_id: None,
+            resumed: false,
+            started_at,
+            duration_ms: 0,
+            selection: None,
+            nodes_considered: 0,
+            items_matched: 0,
+            excluded: Vec::new(),
+            external: Vec::new(),
+            duplicate_groups: Vec::new(),
+            already_published: 0,
+            new_items: Vec::new(),
+            updated_items: Vec::new(),
+            batched: Vec::new(),
+            sinks: Vec::new(),
+            redactions: Vec::new(),
+            content_warnings: BTreeMap::new(),
+            link_findings: Vec::new(),
+            warnings: Vec::new(),
+            compatibility: None,
+            error: None,
+            cancelled: false,
+        }
+    }
+
+    /// Every warning of the run, the pipeline's first and then each sink's
+    pub fn all_warnings(&self) -> impl Iterator<Item = &Warning> {
+        self.warnings.iter().chain(self.sinks.iter().flat_map(|outcome| &outcome.warnings))
+    }
+
+    /// Render the report as markdown
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!(
+            "# Syndication run {}\n\n\
+             - Canvas: `{}`\n\
+             - Canvas hash: `{}`\n\
+             - Selection: {}\n\
+             - Duration: {} ms\n\
+             - Nodes considered: {}\n\
+             - Items matched: {}\n\
+             - Excluded: {}\n\
+             - Already published: {}\n\
+             - New items: {}\n\
+             - Updated items: {}\n",
+            self.started_at.to_rfc3339(),
+            self.canvas_path.display(),
+            self.canvas_hash.as_deref().unwrap_or("-"),
+            self.selection.as_deref().unwrap_or("-"),
+            self.duration_ms,
+            self.nodes_considered,
+            self.items_matched,
+            self.excluded.len(),
+            self.already_published,
+            self.new_items.len(),
+            self.updated_items.len(),
+        );
+
+        if let Some(run_id) = &self.run_id {
+            let resumed = if self.resumed { " (resumed)" } else { "" };
+            md.push_str(&format!("- Run: `{}`{}\n", run_id, resumed));
+        }
+
+        if let Some(error) = &self.error {
+            md.push_str(&format!("\n**Error:** {}\n", error));
+        }
+        if self.cancelled {
+            md.push_str("\n**Cancelled:** the canvas changed again before publishing began\n");
+        }
+
+        if let Some(compatibility) = &self.compatibility {
+            md.push_str("\n## Compatibility\n\n");
+            if let Some(error) = &compatibility.strict_error {
+                md.push_str(&format!("Parsed leniently after: {}\n\n", error));
+            }
+            for field in &compatibility.ignored_fields {
+                md.push_str(&format!("- Ignored field `{}`\n", field));
+            }
+            for node in &compatibility.skipped_nodes {
+                md.push_str(&format!("- Skipped node `{}`: {}\n", node.id, node.reason));
+            }
+            for edge in &compatibility.skipped_edges {
+                md.push_str(&format!("- Skipped edge `{}`: {}\n", edge.id, edge.reason));
+            }
+        }
+
+        let groups = group_warnings(self.all_warnings());
+        if !groups.is_empty() {
+            md.push_str("\n## Warnings\n");
+            for group in groups {
+                md.push_str(&format!("\n### `{}` from {} ({})\n\n", group.code, group.source, group.warnings.len()));
+                for warning in &group.warnings {
+                    match &warning.node_id {
+                        Some(node_id) => md.push_str(&format!("- `{}`: {}\n", node_id, warning.message)),
+                        None => md.push_str(&format!("- {}\n", warning.message)),
+                    }
+                }
+            }
+        }
+
+        if !self.content_warnings.is_empty() {
+            md.push_str("\n## Content warnings\n\n");
+            for (node_id, topic) in &self.content_warnings {
+                md.push_str(&format!("- `{}`: {}\n", node_id, topic));
+            }
+        }
+
+        if !self.link_findings.is_empty() {
+            md.push_str("\n## Link check\n\n");
+            for finding in &self.link_findings {
+                let rewritten = if finding.rewritten { ", rewritten" } else { "" };
+                md.push_str(&format!("- `{}`: <{}> {}{}\n", finding.node_id, finding.url, finding.status, rewritten));
+            }
+        }
+
+        if !self.batched.is_empty() {
+            md.push_str("\n## Held in pending batch\n\n");
+            for node_id in &self.batched {
+                md.push_str(&format!("- `{}`\n", node_id));
+            }
+        }
+
+        if !self.excluded.is_empty() {
+            md.push_str("\n## Excluded\n\n");
+            for node_id in &self.excluded {
+                md.push_str(&format!("- `{}`\n", node_id));
+            }
+        }
+
+        if !self.external.is_empty() {
+            md.push_str("\n## Hosted elsewhere\n\n");
+            for node_id in &self.external {
+                md.push_str(&format!("- `{}`\n", node_id));
+            }
+        }
+
+        if !self.duplicate_groups.is_empty() {
+            md.push_str("\n## Duplicates\n\n");
+            for group in &self.duplicate_groups {
+                let ids: Vec<String> = group.node_ids.iter().map(|id| format!("`{}`", id)).collect();
+                md.push_str(&format!(
+                    "- {}{}\n",
+                    ids.join(", "),
+                    if group.exact { " (exact)" } else { "" }
+                ));
+            }
+        }
+
+        for outcome in &self.sinks {
+            md.push_str(&format!(
+                "\n## Sink `{}`{}\n\n",
+                outcome.sink,
+                if outcome.dry_run { " (dry run)" } else { "" }
+            ));
+            match &outcome.error {
+                Some(error) => {
+                    md.push_str(&format!("Failed: {}\n\n", error));
+                    if let Some(step) = &outcome.error_step {
+                        md.push_str(&format!("- Step: `{}`\n", step));
+                    }
+                    if let Some(code) = outcome.error_exit_code {
+                        md.push_str(&format!("- Exit code: {}\n", code));
+                    }
+                    if let Some(node_id) = &outcome.error_node_id {
+                        md.push_str(&format!("- Node: `{}`\n", node_id));
+                    }
+                    if outcome.error_permanent {
+                        md.push_str("- Permanent: retrying won't help until it's fixed\n");
+                    }
+                }
+                None => md.push_str("Succeeded\n\n"),
+            }
+            for node_id in &outcome.items {
+                match outcome.urls.get(node_id) {
+                    Some(url) => md.push_str(&format!("- `{}` at <{}>\n", node_id, url)),
+                    None => md.push_str(&format!("- `{}`\n", node_id)),
+                }
+            }
+            for node_id in &outcome.archived {
+                md.push_str(&format!("- `{}` (archived)\n", node_id));
+            }
+            for node_id in &outcome.unarchived {
+                md.push_str(&format!("- `{}` (un-archived)\n", node_id));
+            }
+            for node_id in &outcome.resurfaced {
+                md.push_str(&format!("- `{}` (re-surfaced)\n", node_id));
+            }
+            if !outcome.age_skipped.is_empty() {
+                md.push_str(&format!("- {} too old for the sink, skipped\n", outcome.age_skipped.len()));
+            }
+            if !outcome.planned_writes.is_empty() {
+                md.push_str(&planned_writes_markdown(&outcome.planned_writes));
+            }
+            if let Some(commit) = &outcome.planned_commit {
+                md.push_str(&planned_commit_markdown(commit));
+            }
+        }
+
+        md
+    }
+}
+
+/// The files a dry run would have written, identical ones first since a real run would
+/// leave them alone
+fn planned_writes_markdown(writes: &[PlannedWrite]) -> String {
+    let mut md = format!("\n### Would write: {}\n\n", WriteSummary::of(writes));
+    for kind in [WriteKind::Identical, WriteKind::Create, WriteKind::Modify] {
+        for write in writes.iter().filter(|w| w.kind == kind) {
+            let label = match kind {
+                WriteKind::Identical => "**identical**",
+                WriteKind::Create => "create",
+                WriteKind::Modify => "modify",
+            };
+            md.push_str(&format!("- {} `{}`\n", label, write.path.display()));
+            if let Some(diff) = &write.diff {
+                md.push_str(&format!("\n```diff\n{}```\n\n", diff));
+            }
+        }
+    }
+    md
+}
+
+/// The commit a dry run would have made, and where its bookmark would move
+fn planned_commit_markdown(commit: &PlannedCommit) -> String {
+    let short = |id: &str| id[..id.len().min(12)].to_string();
+    let mut md = format!("\n### Would commit to `{}`\n\n", commit.bookmark);
+    match &commit.current {
+        Some(id) => md.push_str(&format!("- bookmark now at `{}`\n", short(id))),
+        None => md.push_str("- bookmark position not looked up\n"),
+    }
+    if let Some(id) = &commit.remote {
+        md.push_str(&format!("- on the remote at `{}`\n", short(id)));
+    }
+    md.push_str(&format!("- would move to {}\n", commit.target));
+    for file in &commit.files {
+        md.push_str(&format!("- `{}`\n", file.display()));
+    }
+    md.push_str(&format!("\n```\n{}\n```\n", commit.message));
+    md
+}
+
+/// File format(s) for persisted reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Markdown,
+    Both,
+}
+
+/// Writes a timestamped report file after every run and prunes old ones
+#[derive(Debug, Clone)]
+pub struct ReportWriter {
+    /// Directory reports are written to
+    dir: PathBuf,
+    /// Which file format(s) to write
+    format: ReportFormat,
+    /// Number of most recent runs to keep
+    keep_last: usize,
+}
+
+impl ReportWriter {
+    const PREFIX: &'static str = "report-";
+
+    /// Create a report writer for a directory
+    pub fn new(dir: impl AsRef<Path>, format: ReportFormat, keep_last: usize) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            format,
+            keep_last,
+        }
+    }
+
+    /// Directory reports are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Number of most recent runs kept
+    pub fn keep_last(&self) -> usize {
+        self.keep_last
+    }
+
+    /// Write the report and prune old reports, returning the paths written
+    pub fn write(&self, report: &ProcessReport) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        let stem = format!(
+            "{}{}",
+            Self::PREFIX,
+            report.started_at.format("%Y%m%dT%H%M%S%.3f")
+        );
+        let mut written = Vec::new();
+
+        if matches!(self.format, ReportFormat::Json | ReportFormat::Both) {
+            let path = self.dir.join(format!("{}.json", stem));
+            std::fs::write(&path, serde_json::to_string_pretty(report)?)?;
+            written.push(path);
+        }
+        if matches!(self.format, ReportFormat::Markdown | ReportFormat::Both) {
+            let path = self.dir.join(format!("{}.md", stem));
+            std::fs::write(&path, report.to_markdown())?;
+            written.push(path);
+        }
+
+        info!(report = %self.dir.join(&stem).display(), "Wrote run report");
+        self.prune()?;
+
+        Ok(written)
+    }
+
+    /// Delete all but the most recent `keep_last` runs
+    pub fn prune(&self) -> Result<(), Box<dyn Error>> {
+        let stems = Self::report_stems(&self.dir)?;
+        let excess = stems.len().saturating_sub(self.keep_last);
+
+        for stem in &stems[..excess] {
+            for ext in ["json", "md"] {
+                let path = self.dir.join(format!("{}.{}", stem, ext));
+                if path.exists() {
+                    std::fs::remove_file(&path)?;
+                    debug!(report = %path.display(), "Pruned old report");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Path of the most recent report in a directory, preferring markdown over JSON
+    pub fn latest(dir: &Path) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let Some(stem) = Self::report_stems(dir)?.pop() else {
+            return Ok(None);
+        };
+        let markdown = dir.join(format!("{}.md", stem));
+        if markdown.exists() {
+            return Ok(Some(markdown));
+        }
+        Ok(Some(dir.join(format!("{}.json", stem))))
+    }
+
+    /// Report file stems in the directory, oldest first
+    fn report_stems(dir: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut stems: Vec<String> = std::fs::read_dir(dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let stem = path.file_stem()?.to_str()?;
+                stem.starts_with(Self::PREFIX).then(|| stem.to_string())
+            })
+            .collect();
+        stems.sort();
+        stems.dedup();
+
+        Ok(stems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn writer_keeps_the_last_runs_and_finds_the_newest() {
+        let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-reports-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let writer = ReportWriter::new(&dir, ReportFormat::Both, 2);
+        for minute in 0..3 {
+            let started_at = Local.with_ymd_and_hms(2026, 3, 1, 12, minute, 0).unwrap();
+            let mut report = ProcessReport::new(Path::new("notes.canvas"), started_at);
+            report.new_items = vec!["ab00000000000001".to_string(); minute as usize];
+            writer.write(&report).unwrap();
+        }
+
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 4, "{:?}", files);
+        let latest = ReportWriter::latest(&dir).unwrap().unwrap();
+        assert!(latest.ends_with("report-20260301T120200.000.md"), "{}", latest.display());
+        assert!(std::fs::read_to_string(&latest).unwrap().contains("- New items: 2\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
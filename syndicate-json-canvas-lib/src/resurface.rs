@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::atomic::write_atomic;
+use crate::jsoncanvas::NodeId;
+use crate::node_ids::cmp_node_ids;
+use crate::recency::days_ago;
+use crate::tracker::{SyndicationTracker, canvas_state_path};
+
+/// Put before the text of re-surfaced items by default
+pub const DEFAULT_RESURFACE_PREFIX: &str = "From the archive:";
+
+/// Age in days an item must have reached before it's re-surfaced, by default
+pub const DEFAULT_RESURFACE_MIN_AGE_DAYS: u32 = 30;
+
+/// Days before the same item may be re-surfaced again, by default
+pub const DEFAULT_RESURFACE_COOLDOWN_DAYS: u32 = 180;
+
+/// A sink that now and then posts an old item again, e.g. one a year old on Twitter
+///
+/// Every `every`, one item published to the sink more than `min_age_days` ago and not
+/// re-surfaced in the last `cooldown_days` is picked at random and handed to the sink's
+/// [`resurface`](crate::SyndicationSink::resurface) with `prefix` before its text. Only
+/// items still on the canvas and selected are picked, so excluded and archived ones stay
+/// buried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResurfaceOptions {
+    /// Name of the sink posting the old items
+    pub sink: String,
+    /// Time between re-surfaced items
+    pub every: Duration,
+    pub min_age_days: u32,
+    pub cooldown_days: u32,
+    pub prefix: String,
+}
+
+impl ResurfaceOptions {
+    /// Re-surface an item on a sink every `every`
+    pub fn new(sink: impl Into<String>, every: Duration) -> Self {
+        Self {
+            sink: sink.into(),
+            every,
+            min_age_days: DEFAULT_RESURFACE_MIN_AGE_DAYS,
+            cooldown_days: DEFAULT_RESURFACE_COOLDOWN_DAYS,
+            prefix: DEFAULT_RESURFACE_PREFIX.to_string(),
+        }
+    }
+
+    pub fn with_min_age_days(mut self, days: u32) -> Self {
+        self.min_age_days = days;
+        self
+    }
+
+    pub fn with_cooldown_days(mut self, days: u32) -> Self {
+        self.cooldown_days = days;
+        self
+    }
+
+    /// Text put before the item's, separated by a space; empty for none
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// The text of an item as re-surfaced
+    pub fn prefixed(&self, text: &str) -> String {
+        match self.prefix.trim() {
+            "" => text.to_string(),
+            prefix => format!("{} {}", prefix, text),
+        }
+    }
+
+    /// Items of `node_ids` that may be re-surfaced as of `now`: published to the sink more
+    /// than `min_age_days` calendar days ago and not re-surfaced within `cooldown_days`
+    ///
+    /// Items published before publish times were tracked have no known age and are never
+    /// picked; archived ones neither.
+    pub fn candidates<'a>(
+        &self,
+        node_ids: impl IntoIterator<Item = &'a NodeId>,
+        tracker: &SyndicationTracker,
+        log: &ResurfaceLog,
+        now: DateTime<Local>,
+    ) -> Vec<NodeId> {
+        let mut candidates: Vec<NodeId> = node_ids
+            .into_iter()
+            .filter(|node_id| !tracker.is_archived(node_id))
+            .filter(|node_id| {
+                tracker.published_at(node_id).is_some_and(|at| days_ago(at, now) > i64::from(self.min_age_days))
+            })
+            .filter(|node_id| {
+                log.last_resurfaced(node_id).is_none_or(|at| days_ago(at, now) >= i64::from(self.cooldown_days))
+            })
+            .cloned()
+            .collect();
+        candidates.sort_by(cmp_node_ids);
+        candidates
+    }
+}
+
+/// Pick one of the candidates at random, seeded by `now` so a pinned clock picks the same
+pub fn pick(candidates: &[NodeId], now: DateTime<Local>) -> Option<&NodeId> {
+    if candidates.is_empty() {
+        return None;
+    }
+    let digest = Sha256::digest(now.to_rfc3339().as_bytes());
+    let seed = u64::from_be_bytes(digest[..8].try_into().expect("digest is 32 bytes"));
+    candidates.get((seed % candidates.len() as u64) as usize)
+}
+
+/// JSON structure for a re-surface log
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ResurfaceFile {
+    /// When an item was last due, whether or not one was re-surfaced
+    last_run: Option<DateTime<Local>>,
+    /// When each node was last re-surfaced
+    #[serde(default)]
+    resurfaced: BTreeMap<String, DateTime<Local>>,
+}
+
+/// What a sink re-surfaced and when, so re-surfacing keeps to its schedule and cooldown
+pub struct ResurfaceLog {
+    /// Path to the JSON log file
+    path: PathBuf,
+    state: ResurfaceFile,
+}
+
+impl ResurfaceLog {
+    /// Load the re-surface log of a sink for a canvas file
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-resurface.<sink-name>.json`
+    pub fn for_canvas(canvas_path: &Path, sink_name: &str) -> Result<Self, Box<dyn Error>> {
+        let path = state_path(canvas_path, sink_name)?;
+        let state = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            ResurfaceFile::default()
+        };
+        Ok(Self { path, state })
+    }
+
+    /// Path of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// When the next item is due: `every` after the last one, or right away if none was
+    pub fn next_due(&self, every: Duration) -> Option<DateTime<Local>> {
+        let last = self.state.last_run?;
+        let every = chrono::Duration::from_std(every).unwrap_or(chrono::Duration::MAX);
+        Some(last.checked_add_signed(every).unwrap_or(last))
+    }
+
+    /// Whether an item is due as of `now`
+    pub fn is_due(&self, every: Duration, now: DateTime<Local>) -> bool {
+        self.next_due(every).is_none_or(|due| now >= due)
+    }
+
+    /// When a node was last re-surfaced, if ever
+    pub fn last_resurfaced(&self, node_id: &NodeId) -> Option<DateTime<Local>> {
+        self.state.resurfaced.get(node_id.as_str()).copied()
+    }
+
+    /// Record that an item was due at `now`, and the node re-surfaced if one was
+    pub fn record(&mut self, node_id: Option<&NodeId>, now: DateTime<Local>) {
+        self.state.last_run = Some(now);
+        if let Some(node_id) = node_id {
+            self.state.resurfaced.insert(node_id.as_str().to_string(), now);
+        }
+    }
+
+    /// Persist the log
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_atomic(&self.path, serde_json::to_string_pretty(&self.state)?)?;
+        debug!(resurface_path = %self.path.display(), resurfaced = self.state.resurfaced.len(), "Saved re-surface log");
+        Ok(())
+    }
+}
+
+/// Path of a sink's re-surface log
+pub(crate) fn state_path(canvas_path: &Path, sink_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    canvas_state_path(canvas_path, &format!("syndication-resurface.{}.json", sink_name))
+}
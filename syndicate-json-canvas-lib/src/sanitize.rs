@@ -0,0 +1,215 @@
+use pulldown_cmark::{Event, Parser};
+
+/// Policy for raw HTML embedded in node text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmlPolicy {
+    /// Leave raw HTML untouched
+    AllowAll,
+    /// Remove dangerous tags (script, iframe, ...), event-handler attributes and `javascript:` urls
+    StripDangerous,
+    /// Escape all raw HTML so it is displayed as text
+    EscapeAll,
+}
+
+/// Result of sanitizing a piece of markdown
+#[derive(Debug, Clone)]
+pub struct SanitizeOutcome {
+    /// The sanitized markdown
+    pub text: String,
+    /// Human-readable descriptions of what was stripped or escaped
+    pub stripped: Vec<String>,
+}
+
+/// Tags that are removed entirely under [`HtmlPolicy::StripDangerous`]
+const DANGEROUS_TAGS: &[&str] = &[
+    "script", "style", "iframe", "frame", "frameset", "object", "embed", "applet", "form",
+    "input", "button", "textarea", "select", "link", "meta", "base",
+];
+
+/// Tags whose contents are removed along with the tag itself
+const CONTENT_TAGS: &[&str] = &["script", "style"];
+
+/// Apply an [`HtmlPolicy`] to the raw HTML inside markdown
+///
+/// Only HTML recognized by the markdown parser is touched, so code spans, code blocks and
+/// autolinks (`<https://...>`) are left alone.
+pub fn sanitize_html(markdown: &str, policy: HtmlPolicy) -> SanitizeOutcome {
+    let mut stripped = Vec::new();
+
+    if policy == HtmlPolicy::AllowAll {
+        return SanitizeOutcome { text: markdown.to_string(), stripped };
+    }
+
+    let mut edits: Vec<(std::ops::Range<usize>, String)> = Vec::new();
+    // Set while inside a <script>/<style> element whose contents must be dropped
+    let mut skipping: Option<String> = None;
+
+    for (event, range) in Parser::new(markdown).into_offset_iter() {
+        match event {
+            Event::Html(html) | Event::InlineHtml(html) => {
+                let replacement = match policy {
+                    HtmlPolicy::EscapeAll => {
+                        stripped.push(format!("escaped `{}`", html.trim()));
+                        escape_html(&html)
+                    }
+                    _ => strip_dangerous(&html, &mut skipping, &mut stripped),
+                };
+                edits.push((range, replacement));
+            }
+            Event::Text(_) if skipping.is_some() => edits.push((range, String::new())),
+            _ => {}
+        }
+    }
+
+    let mut text = String::with_capacity(markdown.len());
+    let mut cursor = 0;
+    for (range, replacement) in edits {
+        if range.start < cursor {
+            continue;
+        }
+        text.push_str(&markdown[cursor..range.start]);
+        text.push_str(&replacement);
+        cursor = range.end;
+    }
+    text.push_str(&markdown[cursor..]);
+
+    SanitizeOutcome { text, stripped }
+}
+
+fn escape_html(html: &str) -> String {
+    html.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Remove dangerous tags and attributes from an HTML fragment
+fn strip_dangerous(html: &str, skipping: &mut Option<String>, stripped: &mut Vec<String>) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(name) = skipping.clone() {
+            let close = format!("</{}", name);
+            match rest.to_ascii_lowercase().find(&close) {
+                Some(pos) => {
+                    let end = rest[pos..].find('>').map_or(rest.len(), |i| pos + i + 1);
+                    rest = &rest[end..];
+                    *skipping = None;
+                }
+                None => return out,
+            }
+            continue;
+        }
+
+        let Some(start) = rest.find('<') else {
+            out.push_str(rest);
+            break;
+        };
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('>') else {
+            out.push_str(rest);
+            break;
+        };
+        let tag = &rest[..=end];
+        rest = &rest[end + 1..];
+
+        match parse_tag(tag) {
+            Some(parsed) if DANGEROUS_TAGS.contains(&parsed.name.as_str()) => {
+                if !parsed.closing {
+                    stripped.push(format!("removed <{}> element", parsed.name));
+                    if CONTENT_TAGS.contains(&parsed.name.as_str()) && !parsed.self_closing {
+                        *skipping = Some(parsed.name);
+                    }
+                }
+            }
+            Some(parsed) if !parsed.closing => {
+                let mut rebuilt = format!("<{}", parsed.name);
+                for (attr, value) in &parsed.attributes {
+                    let is_handler = attr.starts_with("on");
+                    let is_script_url = value.as_deref().is_some_and(|v| {
+                        v.trim().to_ascii_lowercase().starts_with("javascript:")
+                    });
+                    if is_handler || is_script_url {
+                        stripped.push(format!("removed `{}` attribute from <{}>", attr, parsed.name));
+                        continue;
+                    }
+                    match value {
+                        Some(v) => rebuilt.push_str(&format!(" {}=\"{}\"", attr, v.replace('"', "&quot;"))),
+                        None => rebuilt.push_str(&format!(" {}", attr)),
+                    }
+                }
+                rebuilt.push_str(if parsed.self_closing { " />" } else { ">" });
+                out.push_str(&rebuilt);
+            }
+            // Closing tags of allowed elements, comments and anything unparseable are kept
+            _ => out.push_str(tag),
+        }
+    }
+
+    out
+}
+
+pub(crate) struct ParsedTag {
+    pub(crate) name: String,
+    pub(crate) closing: bool,
+    pub(crate) self_closing: bool,
+    pub(crate) attributes: Vec<(String, Option<String>)>,
+}
+
+/// Parse a single `<...>` tag; returns None for comments, doctypes and malformed tags
+pub(crate) fn parse_tag(tag: &str) -> Option<ParsedTag> {
+    let inner = tag.strip_prefix('<')?.strip_suffix('>')?;
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, inner),
+    };
+    let (self_closing, inner) = match inner.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, inner),
+    };
+
+    let name_end = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or(inner.len());
+    if name_end == 0 {
+        return None;
+    }
+    let name = inner[..name_end].to_ascii_lowercase();
+
+    let mut attributes = Vec::new();
+    let mut chars = inner[name_end..].trim_start();
+    while !chars.is_empty() {
+        let attr_end = chars
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(chars.len());
+        let attr = chars[..attr_end].to_ascii_lowercase();
+        chars = chars[attr_end..].trim_start();
+
+        let mut value = None;
+        if let Some(after_eq) = chars.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            let (v, rest) = match after_eq.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &after_eq[1..];
+                    let close = body.find(quote).unwrap_or(body.len());
+                    (&body[..close], body.get(close + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+                    (&after_eq[..end], &after_eq[end..])
+                }
+            };
+            value = Some(v.to_string());
+            chars = rest.trim_start();
+        }
+
+        if !attr.is_empty() {
+            attributes.push((attr, value));
+        } else if value.is_none() {
+            // Avoid looping forever on stray characters
+            chars = chars.get(1..).unwrap_or("");
+        }
+    }
+
+    Some(ParsedTag { name, closing, self_closing, attributes })
+}
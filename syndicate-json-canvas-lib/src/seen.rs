@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+
+use crate::atomic::write_atomic;
+use crate::jsoncanvas::NodeId;
+use crate::tracker::canvas_state_path;
+
+/// Suffix of the file first-seen times are kept in, next to the canvas
+pub(crate) const STATE_FILE: &str = "syndication-seen.json";
+
+/// When each node was first seen matching the filter, for telling old items from new ones
+pub struct FirstSeenLog {
+    /// Path to the JSON file
+    path: PathBuf,
+    seen: BTreeMap<String, DateTime<Local>>,
+    changed: bool,
+}
+
+impl FirstSeenLog {
+    /// Load the first-seen times of a canvas file
+    ///
+    /// File naming: `.<canvas-name>.canvas.syndication-seen.json`
+    pub fn for_canvas(canvas_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = canvas_state_path(canvas_path, STATE_FILE)?;
+        let seen = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self { path, seen, changed: false })
+    }
+
+    /// When a node was first seen, if it was
+    pub fn first_seen(&self, node_id: &NodeId) -> Option<DateTime<Local>> {
+        self.seen.get(node_id.as_str()).copied()
+    }
+
+    /// Record a node as seen at `at`, keeping an earlier time, and return when it was first seen
+    pub fn record(&mut self, node_id: &NodeId, at: DateTime<Local>) -> DateTime<Local> {
+        match self.seen.get(node_id.as_str()) {
+            Some(seen) if *seen <= at => *seen,
+            _ => {
+                self.seen.insert(node_id.as_str().to_string(), at);
+                self.changed = true;
+                at
+            }
+        }
+    }
+
+    /// Save to disk if anything was recorded since loading
+    pub fn save(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.changed {
+            return Ok(());
+        }
+        write_atomic(&self.path, serde_json::to_string_pretty(&self.seen)?)?;
+        self.changed = false;
+        Ok(())
+    }
+}
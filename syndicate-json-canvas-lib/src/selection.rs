@@ -0,0 +1,131 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::color::ColorFilter;
+use crate::jsoncanvas::node::GenericNodeInfo;
+use crate::content::{FileNodeError, NodeContent, ResolveOptions, content_of, read_file_node};
+use crate::{InAdjacencies, OutAdjacencies, SyndicationFormat, item_of, link_post_of, process_node_with_colors};
+
+/// Which canvas nodes are syndicated
+///
+/// Exclusions, the Archive group and the item size limit apply either way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Text nodes of the filter's colors, the usual convention, and File nodes of those
+    /// colors whose file reads as text
+    Marked(ColorFilter),
+    /// Every non-empty text node, and every File node whose file reads as text, whatever
+    /// its color
+    ///
+    /// Only for a canvas that is public as a whole. Runs are refused unless
+    /// [`ProcessOptions::confirm_canvas_name`](crate::ProcessOptions::confirm_canvas_name)
+    /// is the canvas's file name, so pointing the pipeline at the wrong canvas publishes
+    /// nothing.
+    All,
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        Self::Marked(ColorFilter::default())
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selection::Marked(colors) => write!(f, "marked ({})", colors.names().join(", ")),
+            Selection::All => write!(f, "all nodes"),
+        }
+    }
+}
+
+/// [`Selection::All`] wasn't confirmed for this canvas
+#[derive(Debug, thiserror::Error)]
+pub enum SelectionError {
+    #[error("publishing every node needs `confirm_canvas_name` set to the canvas's file name, `{0}`")]
+    Unconfirmed(String),
+    #[error("`confirm_canvas_name` is `{confirmed}` but the canvas is `{actual}`; refusing to publish every node")]
+    Mismatch { confirmed: String, actual: String },
+}
+
+impl Selection {
+    /// Check that [`Selection::All`] was confirmed with the file name of this canvas
+    ///
+    /// Always passes for [`Selection::Marked`].
+    pub fn confirm(&self, canvas_path: &Path, confirm_canvas_name: Option<&str>) -> Result<(), SelectionError> {
+        if *self != Selection::All {
+            return Ok(());
+        }
+        let actual = canvas_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        match confirm_canvas_name {
+            None => Err(SelectionError::Unconfirmed(actual)),
+            Some(confirmed) if confirmed != actual => {
+                Err(SelectionError::Mismatch { confirmed: confirmed.to_string(), actual })
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// The item of a node if the selection passes it, reading File nodes through `resolve`
+    ///
+    /// Fails for a selected File node whose file should read as text but can't be read.
+    pub fn select(
+        &self,
+        node: &jsoncanvas::Node,
+        out_adjacencies: &OutAdjacencies,
+        in_adjacencies: &InAdjacencies,
+        resolve: &ResolveOptions,
+    ) -> Result<Option<SyndicationFormat>, FileNodeError> {
+        match (self, node) {
+            (Selection::Marked(colors), jsoncanvas::Node::File(file_node)) => {
+                if !colors.matches(node.color().as_ref()) {
+                    return Ok(None);
+                }
+                file_item(node, file_node, out_adjacencies, in_adjacencies, resolve)
+            }
+            (Selection::Marked(colors), _) => {
+                Ok(process_node_with_colors(node, out_adjacencies, in_adjacencies, colors))
+            }
+            (Selection::All, jsoncanvas::Node::File(file_node)) => {
+                file_item(node, file_node, out_adjacencies, in_adjacencies, resolve)
+            }
+            (Selection::All, _) => {
+                let NodeContent::Text(text) = content_of(node, resolve) else {
+                    return Ok(None);
+                };
+                Ok((!text.trim().is_empty()).then(|| item_of(node, text.to_string(), out_adjacencies, in_adjacencies)))
+            }
+        }
+    }
+
+    /// The link post of a Link node if the selection passes it (see [`link_post_of`])
+    ///
+    /// Only used when [`ProcessOptions::link_posts`](crate::ProcessOptions::link_posts) is
+    /// set; otherwise Link nodes stand for pages that live elsewhere.
+    pub fn select_link(
+        &self,
+        node: &jsoncanvas::Node,
+        out_adjacencies: &OutAdjacencies,
+        in_adjacencies: &InAdjacencies,
+        template: &str,
+    ) -> Option<SyndicationFormat> {
+        if let Selection::Marked(colors) = self
+            && !colors.matches(node.color().as_ref())
+        {
+            return None;
+        }
+        link_post_of(node, out_adjacencies, in_adjacencies, template)
+    }
+}
+
+/// The item of a File node, with the text [`read_file_node`] gives, if there is any
+fn file_item(
+    node: &jsoncanvas::Node,
+    file_node: &jsoncanvas::FileNode,
+    out_adjacencies: &OutAdjacencies,
+    in_adjacencies: &InAdjacencies,
+    resolve: &ResolveOptions,
+) -> Result<Option<SyndicationFormat>, FileNodeError> {
+    let text = read_file_node(file_node, resolve)?.filter(|text| !text.trim().is_empty());
+    Ok(text.map(|text| item_of(node, text, out_adjacencies, in_adjacencies)))
+}
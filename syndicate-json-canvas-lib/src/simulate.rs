@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::cancel::CancelToken;
+use crate::orchestrator::{ProcessOptions, SinkTarget, run_and_report};
+use crate::report::ProcessReport;
+use crate::snapshot::CanvasSnapshot;
+use crate::tracker::SyndicationTracker;
+
+/// Files larger than this are reported as changed without a diff
+const MAX_DIFF_BYTES: usize = 64 * 1024;
+/// Unchanged lines shown around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+
+/// How a file differs after a simulated run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+/// A file a real run would add, modify or remove
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    /// Path within the sandbox: `state/...` for the canvas's state files, `sinks/<name>/...`
+    /// for sink output
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+    /// Unified diff, for small text files
+    pub diff: Option<String>,
+}
+
+/// What a real run would do, computed against a throwaway sandbox
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Simulation {
+    /// Report of the simulated run
+    pub report: ProcessReport,
+    /// Files that would change, by path
+    pub changes: Vec<FileChange>,
+    /// Sinks left out because they can't be sandboxed
+    pub unsupported_sinks: Vec<String>,
+}
+
+impl Simulation {
+    /// Render the changes as a human-readable summary with diffs
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if self.changes.is_empty() {
+            text.push_str("No files would change\n");
+        }
+        for change in &self.changes {
+            let marker = match change.kind {
+                ChangeKind::Added => "A",
+                ChangeKind::Modified => "M",
+                ChangeKind::Removed => "D",
+            };
+            text.push_str(&format!("{} {}\n", marker, change.path.display()));
+            if let Some(diff) = &change.diff {
+                text.push_str(diff);
+            }
+        }
+        for sink in &self.unsupported_sinks {
+            text.push_str(&format!("Sink `{}` can't be simulated and was left out\n", sink));
+        }
+        text
+    }
+}
+
+/// Run the pipeline against sandboxed copies of the canvas state and every sink
+///
+/// The canvas and its state files are copied into a temporary directory, each sink is
+/// replaced by its [`crate::SyndicationSink::sandboxed`] copy, and the run is wet. The
+/// sandbox is compared to its starting point and deleted afterwards.
+pub(crate) fn simulate(
+    canvas_path: &Path,
+    targets: &[SinkTarget],
+    options: &ProcessOptions,
+) -> Result<Simulation, Box<dyn Error>> {
+    let sandbox = std::env::temp_dir().join(format!(
+        "syndicate-json-canvas-simulate-{}-{}",
+        std::process::id(),
+        chrono::Local::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let result = simulate_in(&sandbox, canvas_path, targets, options);
+    if let Err(e) = std::fs::remove_dir_all(&sandbox) {
+        warn!(sandbox = %sandbox.display(), error = %e, "Failed to remove simulation sandbox");
+    }
+    result
+}
+
+/// Like [`simulate`], but in `sandbox`, which is left in place with the sinks' output under
+/// `sinks/<name>/`
+pub(crate) fn simulate_in(
+    sandbox: &Path,
+    canvas_path: &Path,
+    targets: &[SinkTarget],
+    options: &ProcessOptions,
+) -> Result<Simulation, Box<dyn Error>> {
+    let canvas_name = canvas_path.file_name().and_then(|n| n.to_str()).ok_or("Invalid canvas filename")?;
+    let canvas_dir = match canvas_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    // The canvas and its state files (`.<canvas-name>.*`)
+    let state_dir = sandbox.join("state");
+    std::fs::create_dir_all(&state_dir)?;
+    let state_prefix = format!(".{}.", canvas_name);
+    for entry in std::fs::read_dir(canvas_dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let is_state = name.to_str().is_some_and(|n| n == canvas_name || n.starts_with(&state_prefix));
+        if is_state && entry.file_type()?.is_file() {
+            std::fs::copy(entry.path(), state_dir.join(&name))?;
+        }
+    }
+    let sandbox_canvas = state_dir.join(canvas_name);
+
+    let mut sandbox_targets = Vec::new();
+    let mut unsupported_sinks = Vec::new();
+    for target in targets {
+        let name = target.sink.name();
+        let sink = match target.sink.sandboxed(&sandbox.join("sinks").join(name)) {
+            Ok(sink) => sink,
+            Err(e) if e.is_unsupported() => {
+                warn!(sink = %name, reason = %e.kind(), "Leaving sink out of the simulation");
+                unsupported_sinks.push(name.to_string());
+                continue;
+            }
+            Err(e) => return Err(e.in_sink(name).into()),
+        };
+        sandbox_targets.push(SinkTarget {
+            sink,
+            tracker: SyndicationTracker::new(&sandbox_canvas, name)?,
+            depends_on: target.depends_on.clone(),
+            dry_run: false,
+            public_facing: target.public_facing,
+            published_within: target.published_within,
+            max_item_age: target.max_item_age,
+        });
+    }
+
+    let before = read_tree(sandbox)?;
+    info!(sandbox = %sandbox.display(), sinks = sandbox_targets.len(), "Simulating run");
+    let options = ProcessOptions { report_writer: None, badges: Vec::new(), ..options.clone() };
+    let canvas = CanvasSnapshot::read(&sandbox_canvas, options.clock.now());
+    let report = run_and_report(&sandbox_canvas, canvas, &mut sandbox_targets, false, &options, &CancelToken::new());
+    let after = read_tree(sandbox)?;
+
+    Ok(Simulation { report, changes: compare_trees(&before, &after), unsupported_sinks })
+}
+
+/// Recursively copy a directory; a missing `src` leaves an empty `dst`
+pub fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dst)?;
+    if !src.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Contents of every file under `root`, by path relative to it
+pub fn read_tree(root: &Path) -> std::io::Result<BTreeMap<PathBuf, Vec<u8>>> {
+    fn walk(root: &Path, dir: &Path, files: &mut BTreeMap<PathBuf, Vec<u8>>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, files)?;
+            } else {
+                let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+                files.insert(relative, std::fs::read(&path)?);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = BTreeMap::new();
+    walk(root, root, &mut files)?;
+    Ok(files)
+}
+
+/// Files added, modified or removed between two [`read_tree`]s, by path
+pub fn compare_trees(before: &BTreeMap<PathBuf, Vec<u8>>, after: &BTreeMap<PathBuf, Vec<u8>>) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    for (path, new) in after {
+        let (kind, old) = match before.get(path) {
+            Some(old) if old == new => continue,
+            Some(old) => (ChangeKind::Modified, old.as_slice()),
+            None => (ChangeKind::Added, &[][..]),
+        };
+        changes.push(FileChange { path: path.clone(), kind, diff: text_diff(old, new) });
+    }
+    for (path, old) in before {
+        if !after.contains_key(path) {
+            changes.push(FileChange { path: path.clone(), kind: ChangeKind::Removed, diff: text_diff(old, &[]) });
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// Unified diff of two small UTF-8 files
+fn text_diff(old: &[u8], new: &[u8]) -> Option<String> {
+    if old.len() > MAX_DIFF_BYTES || new.len() > MAX_DIFF_BYTES {
+        return None;
+    }
+    Some(line_diff(std::str::from_utf8(old).ok()?, std::str::from_utf8(new).ok()?))
+}
+
+/// Unified diff of two texts, line by line; empty if they have the same lines
+pub fn line_diff(old: &str, new: &str) -> String {
+    unified_diff(&old.lines().collect::<Vec<_>>(), &new.lines().collect::<Vec<_>>())
+}
+
+/// Line diff in unified format (`@@ -l,n +l,n @@` hunks with context)
+fn unified_diff(old: &[&str], new: &[&str]) -> String {
+    // Longest common subsequence table, filled from the end
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<(char, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((' ', old[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', old[i]));
+            i += 1;
+        } else {
+            ops.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    // Group changes that are close enough to share context into hunks
+    let changed: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != ' ').collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &k in &changed {
+        let start = k.saturating_sub(DIFF_CONTEXT);
+        let end = (k + DIFF_CONTEXT + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut diff = String::new();
+    for (start, end) in hunks {
+        let old_start = ops[..start].iter().filter(|(op, _)| *op != '+').count();
+        let new_start = ops[..start].iter().filter(|(op, _)| *op != '-').count();
+        let old_len = ops[start..end].iter().filter(|(op, _)| *op != '+').count();
+        let new_len = ops[start..end].iter().filter(|(op, _)| *op != '-').count();
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + usize::from(old_len > 0),
+            old_len,
+            new_start + usize::from(new_len > 0),
+            new_len
+        ));
+        for (op, line) in &ops[start..end] {
+            diff.push_str(&format!("{}{}\n", op, line));
+        }
+    }
+    diff
+}
@@ -1,5 +1,12 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use crate::{SyndicationFormat, jsoncanvas::NodeId};
+use crate::preview::{PlannedCommit, PlannedWrite};
+use crate::prune::DestructionPlan;
+use crate::state::StateRegistry;
+use crate::tracker::SyndicationTracker;
+use crate::verify::VerifyReport;
+use crate::warnings::Warning;
 
 /// Error types for syndication sinks
 #[derive(Debug, thiserror::Error)]
@@ -7,21 +14,212 @@ pub enum SinkError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("Command execution failed: {0}")]
-    CommandFailed(String),
+    #[error(
+        "{step} failed: `{program} {}` {}: {}",
+        .args.join(" "),
+        exit_status(.exit_code),
+        .stderr.trim()
+    )]
+    CommandFailed {
+        /// Which step of the sink's workflow ran the command (e.g. "fetch", "push")
+        step: &'static str,
+        program: String,
+        args: Vec<String>,
+        /// None if the command couldn't be started or was killed by a signal
+        exit_code: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+
+    #[error("API request failed: {0}")]
+    Api(String),
+
+    /// The destination refused the request and would refuse it again as it is (e.g. invalid
+    /// input or credentials), so retrying on a schedule won't help
+    #[error("Request rejected: {0}")]
+    Rejected(String),
 
     #[error("Configuration error: {0}")]
     Config(String),
 
     #[error("Serialization error: {0}")]
     Serialization(String),
+
+    #[error("Unsupported operation: {0}")]
+    Unsupported(String),
+
+    /// Another error, with the sink and item it happened in
+    ///
+    /// Built with [`in_sink`](Self::in_sink) and [`for_item`](Self::for_item) rather than
+    /// by hand, so context never nests.
+    #[error("{}{inner}", context_prefix(.sink, .node_id))]
+    Context {
+        sink: Option<String>,
+        node_id: Option<NodeId>,
+        inner: Box<SinkError>,
+    },
+}
+
+impl SinkError {
+    /// Attach the node the error is about
+    pub fn for_item(self, node_id: &NodeId) -> Self {
+        self.with_context(None, Some(node_id))
+    }
+
+    /// Attach the name of the sink the error happened in, unless it already has one
+    pub fn in_sink(self, sink: &str) -> Self {
+        self.with_context(Some(sink), None)
+    }
+
+    fn with_context(self, sink: Option<&str>, node_id: Option<&NodeId>) -> Self {
+        let (old_sink, old_node_id, inner) = match self {
+            SinkError::Context { sink, node_id, inner } => (sink, node_id, inner),
+            other => (None, None, Box::new(other)),
+        };
+        SinkError::Context {
+            sink: old_sink.or_else(|| sink.map(str::to_string)),
+            node_id: old_node_id.or_else(|| node_id.cloned()),
+            inner,
+        }
+    }
+
+    /// The error without its context
+    pub fn kind(&self) -> &SinkError {
+        match self {
+            SinkError::Context { inner, .. } => inner,
+            other => other,
+        }
+    }
+
+    /// Name of the sink the error happened in, if attached
+    pub fn sink(&self) -> Option<&str> {
+        match self {
+            SinkError::Context { sink, .. } => sink.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The node the error is about, if attached
+    pub fn node_id(&self) -> Option<&NodeId> {
+        match self {
+            SinkError::Context { node_id, .. } => node_id.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the sink doesn't implement the operation, so it can be skipped
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self.kind(), SinkError::Unsupported(_))
+    }
+
+    /// Whether trying again later may succeed, as after an outage or a timeout, rather than
+    /// failing the same way until something is fixed
+    pub fn is_transient(&self) -> bool {
+        !matches!(
+            self.kind(),
+            SinkError::Rejected(_) | SinkError::Config(_) | SinkError::Serialization(_) | SinkError::Unsupported(_)
+        )
+    }
+
+    /// The workflow step that failed, for command failures
+    pub fn step(&self) -> Option<&str> {
+        match self.kind() {
+            SinkError::CommandFailed { step, .. } => Some(step),
+            _ => None,
+        }
+    }
+
+    /// The exit code of a failed command
+    pub fn exit_code(&self) -> Option<i32> {
+        match self.kind() {
+            SinkError::CommandFailed { exit_code, .. } => *exit_code,
+            _ => None,
+        }
+    }
+}
+
+/// `<sink>: node <id>: ` for whichever of the two are known
+fn context_prefix(sink: &Option<String>, node_id: &Option<NodeId>) -> String {
+    let mut prefix = String::new();
+    if let Some(sink) = sink {
+        prefix.push_str(&format!("{}: ", sink));
+    }
+    if let Some(node_id) = node_id {
+        prefix.push_str(&format!("node {}: ", node_id));
+    }
+    prefix
+}
+
+fn exit_status(exit_code: &Option<i32>) -> String {
+    match exit_code {
+        Some(code) => format!("exited with code {}", code),
+        None => "did not exit normally".to_string(),
+    }
+}
+
+/// `(sink name, URL)` pairs of the posts made of each node on other sites
+pub type SyndicationLinks = HashMap<NodeId, Vec<(String, String)>>;
+
+/// How an item differs from what a sink last received
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemChange {
+    /// Never published to the sink
+    New,
+    /// Published, and edited since
+    Updated,
+    /// Published as is (or published before content hashes were tracked)
+    Unchanged,
+}
+
+impl ItemChange {
+    /// Classify an item against a sink's tracker
+    pub fn of(item: &SyndicationFormat, tracker: &SyndicationTracker) -> Self {
+        if !tracker.is_published(&item.id) {
+            return ItemChange::New;
+        }
+        match tracker.content_hash(&item.id) {
+            Some(hash) if hash != item.content_hash() => ItemChange::Updated,
+            _ => ItemChange::Unchanged,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemChange::New => "new",
+            ItemChange::Updated => "updated",
+            ItemChange::Unchanged => "unchanged",
+        }
+    }
 }
 
 /// Trait for syndication sinks
 ///
 /// Implementors can publish SyndicationFormat items to various destinations
 /// (Twitter, git repositories, etc.)
+///
+/// Each run is a batch: [`begin`](Self::begin), then any of [`archive`](Self::archive),
+/// [`unarchive`](Self::unarchive) and [`publish`](Self::publish), then
+/// [`commit`](Self::commit). If a step fails, [`abort`](Self::abort) is called instead of
+/// `commit`, so sinks can defer externally visible effects until the batch succeeded.
 pub trait SyndicationSink {
+    /// Prepare for a batch (e.g. fetch remote state or open a transaction)
+    fn begin(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        let _ = dry_run;
+        Ok(())
+    }
+
+    /// Make the batch's changes visible (e.g. push or commit a transaction)
+    fn commit(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        let _ = dry_run;
+        Ok(())
+    }
+
+    /// Discard the batch's changes after a failure
+    fn abort(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        let _ = dry_run;
+        Ok(())
+    }
+
     /// Publish all items to the sink
     ///
     /// # Arguments
@@ -35,6 +233,178 @@ pub trait SyndicationSink {
     /// Takes all items at once to enable computing slugs and creating cross-references between posts
     fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError>;
 
+    /// Publish one chunk of a larger batch
+    ///
+    /// `all_items` is the whole batch, so sinks that cross-reference posts can still link
+    /// to items in other chunks. The default ignores it and publishes `items`.
+    fn publish_chunk(
+        &mut self,
+        items: &HashMap<NodeId, SyndicationFormat>,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+        dry_run: bool,
+    ) -> Result<(), SinkError> {
+        let _ = all_items;
+        self.publish(items, dry_run)
+    }
+
+    /// Deliver new versions of items that were edited since they were published
+    ///
+    /// Only called with [`ItemChange::Updated`] items, and only if
+    /// [`supports_updates`](Self::supports_updates); unchanged items are never handed to a
+    /// sink. Sinks that can't revise what they published keep the default, which returns
+    /// [`SinkError::Unsupported`], and edits are recorded without delivery.
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        let _ = (items, dry_run);
+        Err(SinkError::Unsupported(format!("{} does not support updates", self.name())))
+    }
+
+    /// Whether [`update`](Self::update) is implemented, so edits are worth a batch
+    fn supports_updates(&self) -> bool {
+        false
+    }
+
+    /// Check without changing anything that the sink could publish, e.g. that its
+    /// repository exists or its credentials are set
+    ///
+    /// Run by `config check --with-preflight`; the default finds nothing wrong.
+    fn preflight(&self) -> Result<(), SinkError> {
+        Ok(())
+    }
+
+    /// Post an item published long ago once more, its text already marked as re-surfaced
+    ///
+    /// Called in its own batch on the schedule of the sink's
+    /// [`ResurfaceOptions`](crate::ResurfaceOptions), with an idempotency key of its own so the
+    /// earlier post isn't taken for this one. The new post is not a syndication link of the
+    /// node. Sinks that can't post again keep the default, which returns
+    /// [`SinkError::Unsupported`].
+    fn resurface(&mut self, item: &SyndicationFormat, dry_run: bool) -> Result<(), SinkError> {
+        let _ = (item, dry_run);
+        Err(SinkError::Unsupported(format!("{} can't re-surface items", self.name())))
+    }
+
+    /// URLs of the posts created since the last call, for linking them from canonical copies
+    ///
+    /// Sinks posting to other sites (e.g. Twitter) report where each node ended up; the
+    /// default has none.
+    fn take_post_urls(&mut self) -> Vec<(NodeId, String)> {
+        Vec::new()
+    }
+
+    /// Files dry-run batches would have written since the last call, compared with the
+    /// ones on disk
+    ///
+    /// Sinks that write files record them instead of writing when dry; the default has none.
+    fn take_planned_writes(&mut self) -> Vec<PlannedWrite> {
+        Vec::new()
+    }
+
+    /// The commit the last dry-run batch would have made, for sinks publishing to a repository;
+    /// the default has none
+    fn take_planned_commit(&mut self) -> Option<PlannedCommit> {
+        None
+    }
+
+    /// Warnings about items raised since the last call (e.g. stripped HTML), for the
+    /// end-of-run summary; the default has none
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        Vec::new()
+    }
+
+    /// Record where nodes were syndicated to in their published copies (IndieWeb
+    /// `syndication:` links), given `(sink name, URL)` pairs per node
+    ///
+    /// Called in its own batch after a run. Must be idempotent; returns the number of
+    /// copies changed. Sinks that don't keep canonical copies keep the default, which
+    /// returns [`SinkError::Unsupported`].
+    fn add_syndication_links(
+        &mut self,
+        links: &SyndicationLinks,
+        dry_run: bool,
+    ) -> Result<usize, SinkError> {
+        let _ = (links, dry_run);
+        Err(SinkError::Unsupported(format!("{} doesn't keep syndication links", self.name())))
+    }
+
+    /// Archive previously published nodes: keep them reachable but mark them unlisted
+    ///
+    /// Must be idempotent. Sinks that can't archive keep the default, which returns
+    /// [`SinkError::Unsupported`].
+    fn archive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        let _ = (node_ids, dry_run);
+        Err(SinkError::Unsupported(format!("{} does not support archiving", self.name())))
+    }
+
+    /// Undo [`SyndicationSink::archive`] for the given nodes
+    fn unarchive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        let _ = (node_ids, dry_run);
+        Err(SinkError::Unsupported(format!("{} does not support archiving", self.name())))
+    }
+
+    /// What removing the published output of the given nodes would delete, without
+    /// deleting anything
+    ///
+    /// Sinks that can't remove what they published keep the default, which returns
+    /// [`SinkError::Unsupported`]. The caller fills in the plan's tracker entries.
+    fn plan_removal(&self, node_ids: &[NodeId]) -> Result<DestructionPlan, SinkError> {
+        let _ = node_ids;
+        Err(SinkError::Unsupported(format!("{} does not support removal", self.name())))
+    }
+
+    /// Delete what a plan from [`plan_removal`](Self::plan_removal) lists, inside a batch,
+    /// or leave tombstones in its place if the plan's policy says so
+    fn remove(&mut self, plan: &DestructionPlan, dry_run: bool) -> Result<(), SinkError> {
+        let _ = (plan, dry_run);
+        Err(SinkError::Unsupported(format!("{} does not support removal", self.name())))
+    }
+
+    /// Record the posts in the sink that weren't published from the canvas (e.g. written by
+    /// hand before it), so new posts can link to them while they're never pruned or rewritten
+    ///
+    /// `is_tracked` tells the canvas's own posts apart. Called in its own batch; must be
+    /// idempotent. Returns the posts newly recorded. Sinks that don't keep their posts as
+    /// files keep the default, which returns [`SinkError::Unsupported`].
+    fn import_legacy(
+        &mut self,
+        is_tracked: &dyn Fn(&NodeId) -> bool,
+        dry_run: bool,
+    ) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        let _ = (is_tracked, dry_run);
+        Err(SinkError::Unsupported(format!("{} can't import legacy posts", self.name())))
+    }
+
+    /// List every item this sink holds, with where it lives, for reconciling trackers
+    ///
+    /// Only sinks whose output can be inspected (e.g. files in a repository) implement this;
+    /// the default returns [`SinkError::Unsupported`].
+    fn list_published(&self) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        Err(SinkError::Unsupported(format!("{} can't list published items", self.name())))
+    }
+
+    /// Check that every published file reads back as a valid post, without changing anything
+    ///
+    /// Only sinks that write files implement this; the default returns
+    /// [`SinkError::Unsupported`].
+    fn verify(&self) -> Result<VerifyReport, SinkError> {
+        Err(SinkError::Unsupported(format!("{} can't verify published items", self.name())))
+    }
+
+    /// Register the state files the sink keeps itself, besides its tracker, so they're
+    /// included in state bundles; the default has none
+    fn register_state(&self, registry: &mut StateRegistry) {
+        let _ = registry;
+    }
+
+    /// A copy of this sink working against a sandbox under `root`, for simulated runs
+    ///
+    /// The copy starts from the sink's current state (files are copied into `root`), skips
+    /// external commands and renders network requests into `root` instead of sending them.
+    /// The default returns [`SinkError::Unsupported`].
+    fn sandboxed(&self, root: &Path) -> Result<Box<dyn SyndicationSink + Send>, SinkError> {
+        let _ = root;
+        Err(SinkError::Unsupported(format!("{} can't be simulated", self.name())))
+    }
+
     /// Returns the name of this sink. This name should not have spaces & be unique.
     ///
     /// # Examples
@@ -0,0 +1,134 @@
+use crate::jsoncanvas::node::GenericNodeInfo;
+
+/// Least area of a note by default, in square canvas pixels (e.g. 300 × 200)
+pub const DEFAULT_NOTE_MIN_AREA: u64 = 60_000;
+/// Least width of a note by default, in canvas pixels
+pub const DEFAULT_NOTE_MIN_WIDTH: u64 = 300;
+/// Range of width / height ratios of a note by default, leaving out one-line banners and
+/// narrow columns
+pub const DEFAULT_NOTE_ASPECT: (f64, f64) = (0.5, 4.0);
+
+/// Width and height of a node as stored in the canvas
+///
+/// Canvas pixels are the units of the canvas file, i.e. screen pixels at 100% zoom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeSize {
+    pub width: u64,
+    pub height: u64,
+}
+
+impl NodeSize {
+    pub fn of(node: &impl GenericNodeInfo) -> Self {
+        Self { width: node.get_width(), height: node.get_height() }
+    }
+
+    /// Area in square canvas pixels
+    pub fn area(&self) -> u64 {
+        self.width.saturating_mul(self.height)
+    }
+
+    /// Width divided by height; None for a node of no height
+    pub fn aspect_ratio(&self) -> Option<f64> {
+        (self.height > 0).then(|| self.width as f64 / self.height as f64)
+    }
+}
+
+/// Passes nodes by their size on the canvas, e.g. to tell developed notes from quick cards
+///
+/// The default passes every node. Each constraint narrows it further.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SizeFilter {
+    /// Least area in square canvas pixels
+    pub min_area: Option<u64>,
+    /// Least width in canvas pixels
+    pub min_width: Option<u64>,
+    /// Inclusive range of width / height ratios
+    pub aspect: Option<(f64, f64)>,
+}
+
+impl SizeFilter {
+    /// Pass every node
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Pass large, not too elongated nodes (see the `DEFAULT_NOTE_*` constants); each
+    /// bound can be changed by chaining the other constructors
+    pub fn looks_like_note() -> Self {
+        Self::any()
+            .min_area(DEFAULT_NOTE_MIN_AREA)
+            .min_width(DEFAULT_NOTE_MIN_WIDTH)
+            .aspect_between(DEFAULT_NOTE_ASPECT.0, DEFAULT_NOTE_ASPECT.1)
+    }
+
+    /// Only nodes of at least this area, in square canvas pixels
+    pub fn min_area(mut self, px: u64) -> Self {
+        self.min_area = Some(px);
+        self
+    }
+
+    /// Only nodes at least this wide, in canvas pixels
+    pub fn min_width(mut self, px: u64) -> Self {
+        self.min_width = Some(px);
+        self
+    }
+
+    /// Only nodes whose width / height ratio is between `lo` and `hi`, inclusive
+    pub fn aspect_between(mut self, lo: f64, hi: f64) -> Self {
+        self.aspect = Some((lo, hi));
+        self
+    }
+
+    /// Whether a node of this size passes
+    pub fn matches(&self, size: NodeSize) -> bool {
+        if self.min_area.is_some_and(|min| size.area() < min) {
+            return false;
+        }
+        if self.min_width.is_some_and(|min| size.width < min) {
+            return false;
+        }
+        match (self.aspect, size.aspect_ratio()) {
+            (Some((lo, hi)), Some(ratio)) => lo <= ratio && ratio <= hi,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+
+    /// Whether a canvas node passes
+    pub fn matches_node(&self, node: &impl GenericNodeInfo) -> bool {
+        self.matches(NodeSize::of(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(width: u64, height: u64) -> NodeSize {
+        NodeSize { width, height }
+    }
+
+    #[test]
+    fn each_constraint_narrows_the_filter() {
+        let card = size(250, 60);
+        let note = size(400, 300);
+        assert!(SizeFilter::any().matches(card) && SizeFilter::any().matches(size(0, 0)));
+        assert!(!SizeFilter::any().min_area(60_000).matches(card));
+        assert!(SizeFilter::any().min_area(120_000).matches(note));
+        assert!(!SizeFilter::any().min_width(401).matches(note));
+        assert!(SizeFilter::any().aspect_between(1.0, 2.0).matches(size(200, 100)));
+        assert!(!SizeFilter::any().aspect_between(1.0, 2.0).matches(size(201, 100)));
+        assert!(!SizeFilter::any().aspect_between(0.0, 10.0).matches(size(100, 0)));
+    }
+
+    #[test]
+    fn looks_like_note_skips_cards_banners_and_columns() {
+        let filter = SizeFilter::looks_like_note();
+        assert!(filter.matches(size(400, 300)));
+        assert!(!filter.matches(size(250, 60)), "card");
+        assert!(!filter.matches(size(200, 200)), "small square");
+        assert!(!filter.matches(size(1200, 100)), "banner");
+        assert!(!filter.matches(size(300, 900)), "column");
+        assert!(filter.min_area(200_000).matches(size(600, 400)) && !filter.min_area(200_000).matches(size(400, 300)));
+    }
+}
@@ -0,0 +1,65 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local};
+use sha2::{Digest, Sha256};
+
+use crate::compat::{CompatibilityReport, parse_canvas};
+use crate::jsoncanvas::JsonCanvas;
+use crate::watch::FileStamp;
+
+/// The canvas file as read once at the start of a run
+///
+/// Every step of the run works from this one copy, so a save in the middle of a run can't
+/// have the guard, the pipeline and the report see different versions. The next run sees
+/// the newer one.
+#[derive(Debug)]
+pub struct CanvasSnapshot {
+    pub path: PathBuf,
+    /// The file's contents as read
+    pub bytes: Vec<u8>,
+    /// Hex SHA-256 of `bytes`, recorded with published items as the version they came from
+    pub hash: String,
+    /// Modification time of the file as read, if the filesystem keeps one
+    pub modified: Option<SystemTime>,
+    pub read_at: DateTime<Local>,
+    /// The parsed canvas, or why it didn't parse
+    parsed: Result<(JsonCanvas, CompatibilityReport), String>,
+}
+
+impl CanvasSnapshot {
+    /// Read and parse the canvas file; only failing to read it is an error
+    pub fn read(path: &Path, now: DateTime<Local>) -> std::io::Result<Self> {
+        let mut file = std::fs::File::open(path)?;
+        // Taken from the open file, so it belongs to the contents read below
+        let modified = file.metadata()?.modified().ok();
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok(Self::from_bytes(path, bytes, modified, now))
+    }
+
+    /// A snapshot of contents read elsewhere
+    pub fn from_bytes(path: &Path, bytes: Vec<u8>, modified: Option<SystemTime>, now: DateTime<Local>) -> Self {
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+        let parsed = std::str::from_utf8(&bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|content| parse_canvas(content).map_err(|e| e.to_string()));
+        Self { path: path.to_path_buf(), bytes, hash, modified, read_at: now, parsed }
+    }
+
+    /// The parsed canvas, or why it didn't parse
+    pub fn canvas(&self) -> Result<&JsonCanvas, &str> {
+        self.parsed.as_ref().map(|(canvas, _)| canvas).map_err(String::as_str)
+    }
+
+    /// What had to be tolerated to parse the canvas, if it parsed
+    pub fn compatibility(&self) -> Option<&CompatibilityReport> {
+        self.parsed.as_ref().ok().map(|(_, compatibility)| compatibility)
+    }
+
+    /// Stamp of the file as read, for telling later writes from this version
+    pub(crate) fn stamp(&self) -> Option<FileStamp> {
+        Some((self.modified?, self.bytes.len() as u64))
+    }
+}
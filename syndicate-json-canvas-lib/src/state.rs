@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::atomic::write_atomic;
+use crate::tracker::canvas_state_path;
+use crate::{backfill, batch, enrich, exclusions, guard, history, journal, link_check, queue, seen};
+
+/// Version of the bundle layout written by [`StateBundle::collect`]
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Version of the layout of the state files kept next to the canvas, trackers included
+pub const CANVAS_STATE_SCHEMA: u32 = 1;
+
+/// State each subsystem keeps next to the canvas: component and file suffix
+const CANVAS_STATE: &[(&str, &str)] = &[
+    ("exclusions", exclusions::STATE_FILE),
+    ("history", history::STATE_FILE),
+    ("queue", queue::STATE_FILE),
+    ("backfill", backfill::STATE_FILE),
+    ("batch", batch::STATE_FILE),
+    ("snapshot", guard::STATE_FILE),
+    ("link-cache", enrich::STATE_FILE),
+    ("link-checks", link_check::STATE_FILE),
+    ("seen", seen::STATE_FILE),
+    ("journal", journal::STATE_FILE),
+];
+
+/// A file of state owned by the tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateFile {
+    /// Name of what the file holds (e.g. `queue` or `tracker.jj`), the same on every machine
+    pub component: String,
+    /// Where the file lives on this machine
+    pub path: PathBuf,
+    /// Version of the file's layout
+    pub schema_version: u32,
+}
+
+/// Every state file the tool owns, registered by the subsystems that own them
+///
+/// Files are registered whether or not they exist yet.
+#[derive(Debug, Clone, Default)]
+pub struct StateRegistry {
+    files: Vec<StateFile>,
+}
+
+impl StateRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The state files kept next to a canvas, except the trackers (exclusions, history,
+    /// queue, ...)
+    pub fn for_canvas(canvas_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut registry = Self::new();
+        for (component, suffix) in CANVAS_STATE {
+            registry.register(*component, canvas_state_path(canvas_path, suffix)?, CANVAS_STATE_SCHEMA);
+        }
+        Ok(registry)
+    }
+
+    /// Register a file; registering a component again replaces it
+    pub fn register(&mut self, component: impl Into<String>, path: impl Into<PathBuf>, schema_version: u32) {
+        let file = StateFile { component: component.into(), path: path.into(), schema_version };
+        match self.files.iter_mut().find(|f| f.component == file.component) {
+            Some(existing) => *existing = file,
+            None => self.files.push(file),
+        }
+    }
+
+    pub fn files(&self) -> &[StateFile] {
+        &self.files
+    }
+
+    pub fn get(&self, component: &str) -> Option<&StateFile> {
+        self.files.iter().find(|f| f.component == component)
+    }
+}
+
+/// Why a state bundle couldn't be imported
+#[derive(Debug, thiserror::Error)]
+pub enum StateError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("not a state bundle: {0}")]
+    Format(String),
+
+    #[error("bundle format {found} is newer than this version supports ({supported}); upgrade first")]
+    NewerBundle { found: u32, supported: u32 },
+
+    #[error("{component} is at schema {found}, newer than this version supports ({supported}); upgrade first")]
+    NewerSchema { component: String, found: u32, supported: u32 },
+
+    #[error("{component} doesn't match its checksum in the manifest")]
+    Corrupt { component: String },
+
+    #[error("state here changed after the bundle was made ({}); pass --force to overwrite it", .components.join(", "))]
+    WouldClobber { components: Vec<String> },
+}
+
+/// One file listed in a bundle's manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub component: String,
+    pub schema_version: u32,
+    /// SHA-256 of the contents
+    pub sha256: String,
+    pub bytes: u64,
+}
+
+/// The tool's state in one file, for moving it to another machine
+///
+/// A JSON document with a manifest of the files it holds and their schema versions,
+/// followed by their contents. Files are stored by component rather than path, so they
+/// land wherever the importing machine keeps them. Files that didn't exist are left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateBundle {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub manifest: Vec<ManifestEntry>,
+    /// Contents by component
+    contents: BTreeMap<String, String>,
+}
+
+/// What importing a bundle did, by component
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    /// Written from the bundle
+    pub written: Vec<String>,
+    /// Already identical here
+    pub unchanged: Vec<String>,
+    /// In the bundle but not registered here (e.g. a sink this machine doesn't use)
+    pub skipped: Vec<String>,
+}
+
+fn sha256(contents: &str) -> String {
+    format!("{:x}", Sha256::digest(contents.as_bytes()))
+}
+
+impl StateBundle {
+    /// Gather every registered file that exists, as of `now`
+    pub fn collect(registry: &StateRegistry, now: DateTime<Utc>) -> Result<Self, StateError> {
+        let mut manifest = Vec::new();
+        let mut contents = BTreeMap::new();
+        for file in registry.files() {
+            if !file.path.exists() {
+                continue;
+            }
+            let text = std::fs::read_to_string(&file.path)?;
+            manifest.push(ManifestEntry {
+                component: file.component.clone(),
+                schema_version: file.schema_version,
+                sha256: sha256(&text),
+                bytes: text.len() as u64,
+            });
+            contents.insert(file.component.clone(), text);
+        }
+        Ok(Self { format_version: BUNDLE_FORMAT_VERSION, created_at: now, manifest, contents })
+    }
+
+    /// Read a bundle, refusing ones written in a newer format
+    pub fn read(path: &Path) -> Result<Self, StateError> {
+        let text = std::fs::read_to_string(path)?;
+        let value: serde_json::Value = serde_json::from_str(&text).map_err(|e| StateError::Format(e.to_string()))?;
+        let found = value["format_version"]
+            .as_u64()
+            .ok_or_else(|| StateError::Format("no format_version".to_string()))? as u32;
+        if found > BUNDLE_FORMAT_VERSION {
+            return Err(StateError::NewerBundle { found, supported: BUNDLE_FORMAT_VERSION });
+        }
+        serde_json::from_value(value).map_err(|e| StateError::Format(e.to_string()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), StateError> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| StateError::Format(e.to_string()))?;
+        write_atomic(path, json)?;
+        info!(bundle = %path.display(), files = self.manifest.len(), "Wrote state bundle");
+        Ok(())
+    }
+
+    /// Place the bundle's files where `registry` keeps them
+    ///
+    /// Everything is checked before anything is written: schema versions, checksums, and
+    /// that no file here was changed after the bundle was made (unless `force`). Files
+    /// the bundle doesn't have are left alone.
+    pub fn import(&self, registry: &StateRegistry, force: bool) -> Result<ImportReport, StateError> {
+        let mut report = ImportReport::default();
+        let mut writes = Vec::new();
+        let mut clobbered = Vec::new();
+
+        for entry in &self.manifest {
+            let Some(file) = registry.get(&entry.component) else {
+                warn!(component = %entry.component, "Not registered here, skipping");
+                report.skipped.push(entry.component.clone());
+                continue;
+            };
+            if entry.schema_version > file.schema_version {
+                return Err(StateError::NewerSchema {
+                    component: entry.component.clone(),
+                    found: entry.schema_version,
+                    supported: file.schema_version,
+                });
+            }
+            let contents = self
+                .contents
+                .get(&entry.component)
+                .filter(|contents| sha256(contents) == entry.sha256)
+                .ok_or_else(|| StateError::Corrupt { component: entry.component.clone() })?;
+
+            if file.path.exists() {
+                if std::fs::read_to_string(&file.path)? == *contents {
+                    report.unchanged.push(entry.component.clone());
+                    continue;
+                }
+                let modified: DateTime<Utc> = std::fs::metadata(&file.path)?.modified()?.into();
+                if modified > self.created_at && !force {
+                    clobbered.push(entry.component.clone());
+                }
+            }
+            writes.push((file, contents));
+        }
+        if !clobbered.is_empty() {
+            return Err(StateError::WouldClobber { components: clobbered });
+        }
+
+        for (file, contents) in writes {
+            if let Some(parent) = file.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            write_atomic(&file.path, contents)?;
+            info!(component = %file.component, path = %file.path.display(), "Imported state file");
+            report.written.push(file.component.clone());
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// A scratch directory removed when dropped
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-state-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        /// Registry of a queue and a tracker kept under `machine`, at `schema`
+        fn registry(&self, machine: &str, schema: u32) -> StateRegistry {
+            let mut registry = StateRegistry::new();
+            registry.register("queue", self.0.join(machine).join("notes.canvas.queue.json"), schema);
+            registry.register("tracker.jj", self.0.join(machine).join("tracker/jj.toml"), schema);
+            registry
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn populate(registry: &StateRegistry) {
+        for file in registry.files() {
+            std::fs::create_dir_all(file.path.parent().unwrap()).unwrap();
+            std::fs::write(&file.path, format!("{} state", file.component)).unwrap();
+        }
+    }
+
+    fn made_at() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn bundle_round_trips_to_another_machine() {
+        let scratch = Scratch::new("round-trip");
+        let old = scratch.registry("old", CANVAS_STATE_SCHEMA);
+        populate(&old);
+        let path = scratch.0.join("state.bundle");
+        StateBundle::collect(&old, made_at()).unwrap().write(&path).unwrap();
+
+        let new = scratch.registry("new", CANVAS_STATE_SCHEMA);
+        let report = StateBundle::read(&path).unwrap().import(&new, false).unwrap();
+        assert_eq!(report.written, ["queue", "tracker.jj"]);
+        for (old, new) in old.files().iter().zip(new.files()) {
+            assert_eq!(std::fs::read_to_string(&new.path).unwrap(), std::fs::read_to_string(&old.path).unwrap());
+        }
+        // Importing again finds nothing to do
+        let report = StateBundle::read(&path).unwrap().import(&new, false).unwrap();
+        assert_eq!((report.written.len(), report.unchanged.len()), (0, 2));
+    }
+
+    #[test]
+    fn newer_schema_is_refused_before_anything_is_written() {
+        let scratch = Scratch::new("newer-schema");
+        let newer = scratch.registry("newer", CANVAS_STATE_SCHEMA + 1);
+        populate(&newer);
+        let bundle = StateBundle::collect(&newer, made_at()).unwrap();
+
+        let older = scratch.registry("older", CANVAS_STATE_SCHEMA);
+        match bundle.import(&older, true) {
+            Err(StateError::NewerSchema { component, found, supported }) => {
+                assert_eq!(component, "queue");
+                assert_eq!((found, supported), (CANVAS_STATE_SCHEMA + 1, CANVAS_STATE_SCHEMA));
+            }
+            other => panic!("expected a schema refusal, got {:?}", other),
+        }
+        assert!(older.files().iter().all(|file| !file.path.exists()));
+    }
+
+    #[test]
+    fn newer_bundle_format_is_refused() {
+        let scratch = Scratch::new("newer-format");
+        let path = scratch.0.join("state.bundle");
+        let mut bundle = StateBundle::collect(&StateRegistry::new(), made_at()).unwrap();
+        bundle.format_version = BUNDLE_FORMAT_VERSION + 1;
+        bundle.write(&path).unwrap();
+        assert!(matches!(StateBundle::read(&path), Err(StateError::NewerBundle { .. })));
+    }
+
+    #[test]
+    fn partial_bundle_leaves_missing_components_alone() {
+        let scratch = Scratch::new("partial");
+        let old = scratch.registry("old", CANVAS_STATE_SCHEMA);
+        populate(&old);
+        std::fs::remove_file(&old.get("queue").unwrap().path).unwrap();
+        let bundle = StateBundle::collect(&old, made_at()).unwrap();
+
+        let new = scratch.registry("new", CANVAS_STATE_SCHEMA);
+        let report = bundle.import(&new, false).unwrap();
+        assert_eq!(report.written, ["tracker.jj"]);
+        assert!(!new.get("queue").unwrap().path.exists());
+    }
+
+    #[test]
+    fn state_changed_after_the_bundle_needs_force() {
+        let scratch = Scratch::new("clobber");
+        let old = scratch.registry("old", CANVAS_STATE_SCHEMA);
+        populate(&old);
+        let bundle = StateBundle::collect(&old, made_at()).unwrap();
+
+        let new = scratch.registry("new", CANVAS_STATE_SCHEMA);
+        std::fs::create_dir_all(new.get("queue").unwrap().path.parent().unwrap()).unwrap();
+        std::fs::write(&new.get("queue").unwrap().path, "newer queue").unwrap();
+        assert!(matches!(bundle.import(&new, false), Err(StateError::WouldClobber { .. })));
+        assert_eq!(std::fs::read_to_string(&new.get("queue").unwrap().path).unwrap(), "newer queue");
+        assert_eq!(bundle.import(&new, true).unwrap().written, ["queue", "tracker.jj"]);
+    }
+}
@@ -0,0 +1,433 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::backfill::{BackfillOptions, BackfillPlan, BackfillProgress, backfill, plan_backfill};
+use crate::batch::{BatchPolicy, PendingBatch};
+use crate::cancel::CancelToken;
+use crate::control::WatchCommand;
+use crate::exclusions::ExclusionList;
+use crate::history::{HistoryEvent, HistoryLog};
+use crate::journal::RunJournal;
+use crate::jsoncanvas::NodeId;
+use crate::node_ids::{NodeIdError, resolve_node_id};
+use crate::orchestrator::{
+    LegacyImport, LinkBackfill, ProcessOptions, SinkTarget, WatchedPipeline, backfill_syndication_links, drain_queue,
+    import_legacy_posts, resume_and_report, run_and_report, validate_canvas_path, watch_loop,
+};
+use crate::processing::{ProcessingOptions, ProcessingQueue, ProcessingSender};
+use crate::prune::{DestructionPlan, PlanChanged, plan_prune, prune};
+use crate::queue::OutboundQueue;
+use crate::reconcile::{Discrepancies, ReconcileFix, reconcile};
+use crate::resurface;
+use crate::report::{ProcessReport, SinkOutcome};
+use crate::selection::SelectionError;
+use crate::simulate::{Simulation, simulate, simulate_in};
+use crate::snapshot::CanvasSnapshot;
+use crate::state::{CANVAS_STATE_SCHEMA, ImportReport, StateBundle, StateError, StateRegistry};
+use crate::sink::SyndicationSink;
+use crate::tracker::SyndicationTracker;
+use crate::validate::{CanvasIssue, validate_snapshot};
+use crate::verify::{VerifyReport, verify};
+
+/// Errors returned by [`Syndicator`]
+#[derive(Debug, thiserror::Error)]
+pub enum SyndicationError {
+    #[error("Invalid canvas path: {0}")]
+    InvalidCanvas(&'static str),
+
+    #[error("Failed to load state: {0}")]
+    State(String),
+
+    #[error("Processing failed: {0}")]
+    Process(String),
+
+    #[error("Watching failed: {0}")]
+    Watch(String),
+
+    #[error("Invalid pipeline: {0}")]
+    Pipeline(String),
+
+    #[error(transparent)]
+    PlanChanged(Box<PlanChanged>),
+
+    #[error(transparent)]
+    Bundle(#[from] StateError),
+
+    #[error(transparent)]
+    Selection(#[from] SelectionError),
+
+    #[error(transparent)]
+    NodeId(#[from] NodeIdError),
+}
+
+/// Everything needed to run the pipeline for one canvas
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The `.canvas` file to syndicate
+    pub canvas_path: PathBuf,
+    /// Force every sink into dry-run mode
+    pub dry_run: bool,
+    /// How long to wait for the canvas to settle before processing a change
+    pub debounce: Duration,
+    /// How requests to process are queued while watching
+    pub processing: ProcessingOptions,
+    pub options: ProcessOptions,
+}
+
+impl Config {
+    /// Defaults for a canvas: armed, 500ms debounce, default processing options
+    pub fn new(canvas_path: impl Into<PathBuf>) -> Self {
+        Self {
+            canvas_path: canvas_path.into(),
+            dry_run: false,
+            debounce: Duration::from_millis(500),
+            processing: ProcessingOptions::default(),
+            options: ProcessOptions::default(),
+        }
+    }
+}
+
+/// The whole syndication pipeline for one canvas, for embedding in other programs
+///
+/// The `syndicate-obsidian-canvas` binary is a thin wrapper around this. Nothing here
+/// writes to stdout; progress is reported through `tracing`.
+pub struct Syndicator {
+    config: Config,
+    targets: Vec<SinkTarget>,
+    /// Requests to process while watching
+    processing: ProcessingQueue,
+}
+
+impl Syndicator {
+    /// Create a syndicator with no sinks
+    ///
+    /// Fails if [`Selection::All`](crate::Selection::All) isn't confirmed for the canvas.
+    pub fn new(config: Config) -> Result<Self, SyndicationError> {
+        validate_canvas_path(&config.canvas_path).map_err(SyndicationError::InvalidCanvas)?;
+        let options = &config.options;
+        options.selection.confirm(&config.canvas_path, options.confirm_canvas_name.as_deref())?;
+        let processing = ProcessingQueue::new(&config.processing);
+        Ok(Self { config, targets: Vec::new(), processing })
+    }
+
+    /// Add a sink, loading its tracker; returns the target for further configuration
+    pub fn add_sink(
+        &mut self,
+        sink: impl SyndicationSink + Send + 'static,
+    ) -> Result<&mut SinkTarget, SyndicationError> {
+        let tracker = SyndicationTracker::new(&self.config.canvas_path, sink.name())
+            .map_err(|e| SyndicationError::State(e.to_string()))?;
+        Ok(self.add_target(SinkTarget::new(sink, tracker)))
+    }
+
+    /// Add a fully configured target
+    pub fn add_target(&mut self, target: SinkTarget) -> &mut SinkTarget {
+        self.targets.push(target);
+        self.targets.last_mut().expect("target was just pushed")
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn targets(&self) -> &[SinkTarget] {
+        &self.targets
+    }
+
+    pub fn targets_mut(&mut self) -> &mut [SinkTarget] {
+        &mut self.targets
+    }
+
+    /// Tracker of the sink named `sink_name`
+    pub fn tracker(&self, sink_name: &str) -> Option<&SyndicationTracker> {
+        self.targets
+            .iter()
+            .find(|t| t.sink.name() == sink_name)
+            .map(|t| &t.tracker)
+    }
+
+    /// Current state of the outbound queue
+    pub fn queue(&self) -> Result<OutboundQueue, SyndicationError> {
+        OutboundQueue::for_canvas(&self.config.canvas_path).map_err(|e| SyndicationError::State(e.to_string()))
+    }
+
+    /// Current exclusion list
+    pub fn exclusions(&self) -> Result<ExclusionList, SyndicationError> {
+        ExclusionList::for_canvas(&self.config.canvas_path).map_err(|e| SyndicationError::State(e.to_string()))
+    }
+
+    /// Every state file of the canvas and its sinks: the files next to the canvas, each
+    /// sink's tracker (`tracker.<sink>`) and re-surface log (`resurface.<sink>`), and whatever
+    /// the sinks register themselves
+    pub fn state_registry(&self) -> Result<StateRegistry, SyndicationError> {
+        let mut registry =
+            StateRegistry::for_canvas(&self.config.canvas_path).map_err(|e| SyndicationError::State(e.to_string()))?;
+        for target in &self.targets {
+            registry.register(format!("tracker.{}", target.sink.name()), target.tracker.path(), CANVAS_STATE_SCHEMA);
+            target.sink.register_state(&mut registry);
+        }
+        for resurface in &self.config.options.resurface {
+            let path = resurface::state_path(&self.config.canvas_path, &resurface.sink)
+                .map_err(|e| SyndicationError::State(e.to_string()))?;
+            registry.register(format!("resurface.{}", resurface.sink), path, CANVAS_STATE_SCHEMA);
+        }
+        Ok(registry)
+    }
+
+    /// Write every existing state file into a bundle at `path`
+    pub fn export_state(&self, path: &Path) -> Result<StateBundle, SyndicationError> {
+        let now = self.config.options.clock.now().with_timezone(&Utc);
+        let bundle = StateBundle::collect(&self.state_registry()?, now)?;
+        bundle.write(path)?;
+        Ok(bundle)
+    }
+
+    /// Place the files of a bundle where this syndicator keeps them
+    ///
+    /// Trackers already loaded aren't reloaded, so create a new syndicator to use them.
+    pub fn import_state(&self, path: &Path, force: bool) -> Result<ImportReport, SyndicationError> {
+        Ok(StateBundle::read(path)?.import(&self.state_registry()?, force)?)
+    }
+
+    /// Run once against sandboxed copies of every sink and report what would change
+    ///
+    /// Nothing outside a temporary sandbox is touched; sinks that can't be sandboxed are
+    /// left out and listed in the result.
+    pub fn simulate(&self) -> Result<Simulation, SyndicationError> {
+        simulate(&self.config.canvas_path, &self.targets, &self.config.options)
+            .map_err(|e| SyndicationError::Process(e.to_string()))
+    }
+
+    /// Like [`Self::simulate`], but in `sandbox`, a folder that doesn't exist yet, which is
+    /// kept: each sink's output is left under `sinks/<name>/`, e.g. to preview the posts
+    pub fn simulate_in(&self, sandbox: &Path) -> Result<Simulation, SyndicationError> {
+        simulate_in(sandbox, &self.config.canvas_path, &self.targets, &self.config.options)
+            .map_err(|e| SyndicationError::Process(e.to_string()))
+    }
+
+    /// The node a command means by `input`, a full node ID or the start of one
+    ///
+    /// Looked up among the canvas's nodes, the nodes in the publish history or a tracker,
+    /// and excluded nodes. Fails with [`SyndicationError::NodeId`] listing the candidates
+    /// if `input` starts several of them.
+    pub fn resolve_node_id(&self, input: &str) -> Result<NodeId, SyndicationError> {
+        let mut known: Vec<NodeId> = Vec::new();
+        // A canvas that can't be read only leaves its nodes out
+        if let Ok(snapshot) = CanvasSnapshot::read(&self.config.canvas_path, self.config.options.clock.now())
+            && let Ok(canvas) = snapshot.canvas()
+        {
+            known.extend(canvas.get_nodes().keys().cloned());
+        }
+        let history = HistoryLog::for_canvas(&self.config.canvas_path)
+            .and_then(|log| log.events())
+            .map_err(|e| SyndicationError::State(e.to_string()))?;
+        known.extend(history.into_iter().map(|event| event.node_id));
+        known.extend(self.targets.iter().flat_map(|t| t.tracker.published()).filter_map(|id| id.parse().ok()));
+        known.extend(self.exclusions()?.iter().filter_map(|id| id.parse().ok()));
+        Ok(resolve_node_id(input, &known)?)
+    }
+
+    /// Publish history of a node across all sinks, oldest first
+    pub fn history(&self, node_id: &NodeId) -> Result<Vec<HistoryEvent>, SyndicationError> {
+        HistoryLog::for_canvas(&self.config.canvas_path)
+            .and_then(|log| log.events_for(node_id))
+            .map_err(|e| SyndicationError::State(e.to_string()))
+    }
+
+    /// Process the canvas once (draining the queue in queue mode) and return the report
+    ///
+    /// Per-sink failures are recorded in the report; only failures that stopped the whole
+    /// run (unreadable canvas, ...) are returned as errors.
+    pub fn process_once(&mut self) -> Result<ProcessReport, SyndicationError> {
+        let report = run_and_report(
+            &self.config.canvas_path,
+            CanvasSnapshot::read(&self.config.canvas_path, self.config.options.clock.now()),
+            &mut self.targets,
+            self.config.dry_run,
+            &self.config.options,
+            &CancelToken::new(),
+        );
+        match &report.error {
+            Some(error) => Err(SyndicationError::Process(error.clone())),
+            None => Ok(report),
+        }
+    }
+
+    /// The journal of a run that didn't finish (a sink failed, or the process died partway),
+    /// if one was left behind
+    pub fn unfinished_run(&self) -> Result<Option<RunJournal>, SyndicationError> {
+        RunJournal::for_canvas(&self.config.canvas_path).map_err(|e| SyndicationError::State(e.to_string()))
+    }
+
+    /// Re-execute the unfinished run: sinks it records as done are skipped, and the others
+    /// are sent only what it planned for them, as the canvas has them now
+    pub fn resume(&mut self) -> Result<ProcessReport, SyndicationError> {
+        let journal = self
+            .unfinished_run()?
+            .ok_or_else(|| SyndicationError::State("no unfinished run to resume".to_string()))?;
+        let report = resume_and_report(
+            &self.config.canvas_path,
+            journal,
+            &mut self.targets,
+            self.config.dry_run,
+            &self.config.options,
+        );
+        match &report.error {
+            Some(error) => Err(SyndicationError::Process(error.clone())),
+            None => Ok(report),
+        }
+    }
+
+    /// Publish any pending batch, then deliver queued items; `force` ignores retry backoff
+    /// and rate limits
+    pub fn drain(&mut self, force: bool) -> Result<Vec<SinkOutcome>, SyndicationError> {
+        let mut flushed = Vec::new();
+        if self.config.options.batching != BatchPolicy::Immediate {
+            let batch = PendingBatch::for_canvas(&self.config.canvas_path)
+                .map_err(|e| SyndicationError::State(e.to_string()))?;
+            if !batch.is_empty() {
+                let options = ProcessOptions { batching: BatchPolicy::Immediate, ..self.config.options.clone() };
+                let report = run_and_report(
+                    &self.config.canvas_path,
+                    CanvasSnapshot::read(&self.config.canvas_path, options.clock.now()),
+                    &mut self.targets,
+                    self.config.dry_run,
+                    &options,
+                    &CancelToken::new(),
+                );
+                if let Some(error) = report.error {
+                    return Err(SyndicationError::Process(error));
+                }
+                flushed = report.sinks;
+            }
+        }
+
+        let mut outcomes = drain_queue(
+            &self.config.canvas_path,
+            &mut self.targets,
+            self.config.dry_run,
+            &self.config.options,
+            force,
+        )
+        .map_err(|e| SyndicationError::Process(e.to_string()))?;
+        flushed.append(&mut outcomes);
+        Ok(flushed)
+    }
+
+    /// Compare trackers with published output, applying the requested fixes
+    pub fn reconcile(&mut self, fix: ReconcileFix) -> Result<Vec<Discrepancies>, SyndicationError> {
+        reconcile(&mut self.targets, fix).map_err(|e| SyndicationError::State(e.to_string()))
+    }
+
+    /// What pruning nodes deleted from the canvas or excluded would remove, per sink;
+    /// changes nothing
+    ///
+    /// Refused like a run if most published nodes vanished at once, unless the configured
+    /// [`RemovalGuard`](crate::RemovalGuard) accepts it.
+    pub fn plan_prune(&self) -> Result<Vec<DestructionPlan>, SyndicationError> {
+        plan_prune(&self.config.canvas_path, &self.targets, &self.config.options.removal_guard)
+            .map_err(|e| SyndicationError::State(e.to_string()))
+    }
+
+    /// Carry out reviewed plans from [`plan_prune`](Self::plan_prune)
+    ///
+    /// Fails with [`SyndicationError::PlanChanged`] without removing anything if the plans
+    /// no longer match what is on disk.
+    pub fn prune(&mut self, reviewed: &[DestructionPlan]) -> Result<Vec<DestructionPlan>, SyndicationError> {
+        let guard = &self.config.options.removal_guard;
+        prune(&self.config.canvas_path, &mut self.targets, reviewed, guard, self.config.dry_run).map_err(|e| {
+            match e.downcast::<PlanChanged>() {
+                Ok(changed) => SyndicationError::PlanChanged(changed),
+                Err(e) => SyndicationError::Process(e.to_string()),
+            }
+        })
+    }
+
+    /// Structural problems with the canvas as it is now, found the way every run finds them
+    /// (see [`validate_snapshot`]); fails if the canvas can't be read or parsed
+    pub fn validate(&self) -> Result<Vec<CanvasIssue>, SyndicationError> {
+        let snapshot = CanvasSnapshot::read(&self.config.canvas_path, self.config.options.clock.now())
+            .map_err(|e| SyndicationError::Process(format!("Failed to read file: {}", e)))?;
+        validate_snapshot(&snapshot, &self.config.options.selection)
+            .map_err(|e| SyndicationError::Process(format!("Failed to parse canvas: {}", e)))
+    }
+
+    /// Check that every sink's published files read back as valid posts; never modifies them
+    pub fn verify(&self) -> Result<Vec<VerifyReport>, SyndicationError> {
+        verify(&self.targets).map_err(|e| SyndicationError::State(e.to_string()))
+    }
+
+    /// Add the URL of every post on another site in the publish history to the published
+    /// copies, e.g. after enabling a sink that keeps them; safe to repeat
+    pub fn backfill(&mut self) -> Result<Vec<LinkBackfill>, SyndicationError> {
+        let links = HistoryLog::for_canvas(&self.config.canvas_path)
+            .and_then(|log| log.syndication_links())
+            .map_err(|e| SyndicationError::State(e.to_string()))?;
+        Ok(backfill_syndication_links(&mut self.targets, &links, self.config.dry_run))
+    }
+
+    /// What publishing the backlog with `options` would do, counted with a dry run
+    pub fn plan_backfill(&mut self, options: &BackfillOptions) -> Result<BackfillPlan, SyndicationError> {
+        plan_backfill(&self.config.canvas_path, &mut self.targets, &self.config.options, options)
+            .map_err(|e| SyndicationError::Process(e.to_string()))
+    }
+
+    /// Publish the backlog to the sinks in `options`, one chunk per run, until it's done or
+    /// `cancel` is set; `on_chunk` gets each chunk's report
+    ///
+    /// An unfinished backfill resumes where it stopped. Items a sink failed to take are
+    /// left in the outbound queue for [`drain`](Self::drain).
+    pub fn backfill_items(
+        &mut self,
+        options: &BackfillOptions,
+        cancel: &CancelToken,
+        on_chunk: impl FnMut(&ProcessReport),
+    ) -> Result<BackfillProgress, SyndicationError> {
+        backfill(
+            &self.config.canvas_path,
+            &mut self.targets,
+            self.config.dry_run,
+            &self.config.options,
+            options,
+            cancel,
+            on_chunk,
+        )
+        .map_err(|e| SyndicationError::Process(e.to_string()))
+    }
+
+    /// Record posts the sinks hold that weren't published from the canvas, e.g. written by
+    /// hand before it, so new posts can link to them; safe to repeat
+    pub fn import_legacy(&mut self) -> Vec<LegacyImport> {
+        import_legacy_posts(&mut self.targets, self.config.dry_run)
+    }
+
+    /// Process now and on every change to the canvas, carrying out `commands` until one
+    /// asks to shut down or every sender is dropped
+    pub fn watch(&mut self, commands: Receiver<WatchCommand>) -> Result<(), SyndicationError> {
+        let debounce = self.config.debounce;
+        let (watched, processing) = self.watched(None);
+        watch_loop(&mut [watched], debounce, Some(&commands), processing)
+            .map_err(|e| SyndicationError::Watch(e.to_string()))
+    }
+
+    /// A handle for asking [`watch`](Self::watch) to process from other threads, e.g. an
+    /// HTTP endpoint or a timer of your own
+    pub fn processing_sender(&self) -> ProcessingSender {
+        self.processing.sender()
+    }
+
+    /// This syndicator as a pipeline of the watch loop, and its processing queue
+    pub(crate) fn watched<'a>(&'a mut self, name: Option<&'a str>) -> (WatchedPipeline<'a>, &'a ProcessingQueue) {
+        let pipeline = WatchedPipeline::new(
+            name,
+            &self.config.canvas_path,
+            &mut self.targets,
+            self.config.dry_run,
+            &self.config.options,
+        );
+        (pipeline, &self.processing)
+    }
+}
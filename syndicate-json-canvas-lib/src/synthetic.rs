@@ -0,0 +1,76 @@
+use jsoncanvas::JsonCanvas;
+use jsoncanvas::color::PresetColor;
+
+use crate::canvas_builder::{CanvasBuildError, CanvasBuilder};
+
+/// Words node texts are made of, so titles and slugs vary like real ones
+const WORDS: &[&str] = &[
+    "canvas", "thought", "edge", "draft", "note", "garden", "link", "idea", "reading", "essay", "question", "sketch",
+];
+
+/// Generates large canvases of a known shape, for benchmarks and tests
+///
+/// Node `i` has the 16-digit hex ID `i` (like Obsidian's IDs) and a unique multi-word
+/// text. Every node links to the next one and to one further away, and every
+/// `syndicated_every`-th node is red, so it is syndicated with the default colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntheticCanvas {
+    pub nodes: usize,
+    pub syndicated_every: usize,
+}
+
+impl SyntheticCanvas {
+    /// A canvas of `nodes` nodes, all of them red
+    pub fn new(nodes: usize) -> Self {
+        Self { nodes, syndicated_every: 1 }
+    }
+
+    /// Color only every `every`-th node red
+    pub fn with_syndicated_every(mut self, every: usize) -> Self {
+        self.syndicated_every = every.max(1);
+        self
+    }
+
+    /// ID of node `i`
+    pub fn node_id(i: usize) -> String {
+        format!("{:016x}", i)
+    }
+
+    /// Text of node `i`
+    pub fn node_text(i: usize) -> String {
+        let words: Vec<&str> = (0..6).map(|k| WORDS[(i * 7 + k * 5) % WORDS.len()]).collect();
+        format!("Thought {} on the {}.\n\nMore about {}, {} and {}.", i, words[..3].join(" "), words[3], words[4], words[5])
+    }
+
+    /// Number of red nodes
+    pub fn syndicated(&self) -> usize {
+        self.nodes.div_ceil(self.syndicated_every)
+    }
+
+    pub fn builder(&self) -> CanvasBuilder {
+        let mut builder = CanvasBuilder::new();
+        for i in 0..self.nodes {
+            builder = builder.text_node(Self::node_id(i), Self::node_text(i)).size(400, 200);
+            if i % self.syndicated_every == 0 {
+                builder = builder.color(PresetColor::Red);
+            }
+        }
+        for i in 0..self.nodes.saturating_sub(1) {
+            builder = builder.edge(Self::node_id(i), Self::node_id(i + 1));
+            let far = (i * 7 + 3) % self.nodes;
+            if far != i && far != i + 1 {
+                builder = builder.edge(Self::node_id(i), Self::node_id(far));
+            }
+        }
+        builder
+    }
+
+    pub fn build(&self) -> Result<JsonCanvas, CanvasBuildError> {
+        self.builder().build()
+    }
+
+    /// The canvas as a `.canvas` file's contents
+    pub fn to_json(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(serde_json::to_string(&self.build()?)?)
+    }
+}
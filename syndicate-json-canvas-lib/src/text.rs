@@ -0,0 +1,299 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::SyndicationFormat;
+use crate::limits::head;
+use crate::locale::{Locale, reading_minutes};
+
+/// Most characters in any title (and so any link text), whatever the policy says
+pub const MAX_TITLE_CHARS: usize = 200;
+/// Most bytes in any slug, whatever the policy says, leaving room in file names
+pub const MAX_SLUG_BYTES: usize = 120;
+/// Bytes at the start of a text that titles and slugs are made from
+const SCAN_BYTES: usize = 16 * 1024;
+
+/// How a length-limited sink handles items longer than one post
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContinuationPolicy {
+    /// Split into a thread of posts (where the sink supports threads)
+    #[default]
+    Thread,
+    /// Post the truncated text followed by a "read more" link to the canonical post
+    Truncate,
+    /// Don't post the item
+    Skip,
+}
+
+/// Whether titles keep the casing of the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleCase {
+    #[default]
+    Preserve,
+    Lowercase,
+}
+
+/// How titles, slugs and previews are cut from an item's text
+///
+/// Built once at startup and handed to the pipeline and every sink, so a post's title and
+/// slug agree wherever they show up. [`TextPolicy::load`] reads it from the `[text]` table
+/// of a TOML file; missing keys keep their defaults, which reproduce [`slugify`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextPolicy {
+    /// Words in a title
+    pub title_max_words: usize,
+    /// Characters in a title, ending in `ellipsis` when cut; None for no limit
+    pub title_max_chars: Option<usize>,
+    pub title_case: TitleCase,
+    /// Words a slug is made from, not counting stopwords
+    pub slug_max_words: usize,
+    /// Characters in a slug, cut at a `-` where possible; None for no limit
+    pub slug_max_chars: Option<usize>,
+    /// Words left out of slugs, compared ignoring case
+    pub stopwords: Vec<String>,
+    /// File of more stopwords, one per line (`#` starts a comment), relative to the TOML file
+    pub stopwords_file: Option<PathBuf>,
+    /// Characters of text in commit message previews
+    pub preview_max_chars: usize,
+    /// Appended to shortened titles and previews
+    pub ellipsis: String,
+    /// Language of dates, counts and reading times written for people (tag pages, email
+    /// subjects), e.g. `de-DE`; None keeps dates as `YYYY-MM-DD` and counts ungrouped
+    pub locale: Option<Locale>,
+}
+
+impl Default for TextPolicy {
+    fn default() -> Self {
+        Self {
+            title_max_words: 8,
+            title_max_chars: None,
+            title_case: TitleCase::Preserve,
+            slug_max_words: 8,
+            slug_max_chars: None,
+            stopwords: Vec::new(),
+            stopwords_file: None,
+            preview_max_chars: 50,
+            ellipsis: "...".to_string(),
+            locale: None,
+        }
+    }
+}
+
+/// TOML file holding a `[text]` table
+#[derive(Debug, Default, Deserialize)]
+struct TextPolicyFile {
+    #[serde(default)]
+    text: TextPolicy,
+}
+
+impl TextPolicy {
+    /// Read the `[text]` table of a TOML file (the defaults if it has none), with the
+    /// stopwords file it names
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut policy = Self::from_toml(&std::fs::read_to_string(path)?)?;
+        if let Some(stopwords_file) = &policy.stopwords_file {
+            let stopwords_path = path.parent().unwrap_or(Path::new(".")).join(stopwords_file);
+            let contents = std::fs::read_to_string(&stopwords_path)
+                .map_err(|e| format!("stopwords file {}: {}", stopwords_path.display(), e))?;
+            policy.stopwords.extend(
+                contents
+                    .lines()
+                    .map(|line| line.split('#').next().unwrap_or_default().trim())
+                    .filter(|word| !word.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        Ok(policy)
+    }
+
+    /// Parse the `[text]` table of a TOML document, without reading a stopwords file
+    pub fn from_toml(toml: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(toml::from_str::<TextPolicyFile>(toml)?.text)
+    }
+
+    /// Title of an item's text, never longer than [`MAX_TITLE_CHARS`]
+    pub fn title(&self, text: &str) -> String {
+        let title = head(text, SCAN_BYTES).split_whitespace().take(self.title_max_words).collect::<Vec<_>>().join(" ");
+        let title = match self.title_case {
+            TitleCase::Preserve => title,
+            TitleCase::Lowercase => title.to_lowercase(),
+        };
+        let max_chars = self.title_max_chars.map_or(MAX_TITLE_CHARS, |max_chars| max_chars.min(MAX_TITLE_CHARS));
+        smart_truncate(&title, max_chars, &self.ellipsis)
+    }
+
+    /// Slug of an item's text: its first words without stopwords, lowercased with
+    /// punctuation removed, never longer than [`MAX_SLUG_BYTES`]
+    pub fn slug(&self, text: &str) -> String {
+        let slug = head(text, SCAN_BYTES)
+            .split_whitespace()
+            .filter(|word| !self.is_stopword(word))
+            .take(self.slug_max_words)
+            .map(|word| {
+                word.chars()
+                    .filter(|c| c.is_alphanumeric() || *c == '-')
+                    .collect::<String>()
+                    .to_lowercase()
+            })
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        let slug = match self.slug_max_chars {
+            Some(max_chars) if slug.chars().count() > max_chars => {
+                let prefix: String = slug.chars().take(max_chars).collect();
+                cut_at_dash(&prefix).to_string()
+            }
+            _ => slug,
+        };
+        if slug.len() > MAX_SLUG_BYTES {
+            return cut_at_dash(head(&slug, MAX_SLUG_BYTES)).to_string();
+        }
+        slug
+    }
+
+    /// Start of an item's text for a commit message
+    pub fn preview(&self, text: &str) -> String {
+        match text.char_indices().nth(self.preview_max_chars) {
+            Some((end, _)) => format!("{}{}", &text[..end], self.ellipsis),
+            None => text.to_string(),
+        }
+    }
+
+    /// A date for people to read, per the locale
+    pub fn human_date(&self, date: NaiveDate) -> String {
+        match &self.locale {
+            Some(locale) => locale.date(date),
+            None => date.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    /// A count for people to read, per the locale
+    pub fn human_number(&self, n: u64) -> String {
+        match &self.locale {
+            Some(locale) => locale.number(n),
+            None => n.to_string(),
+        }
+    }
+
+    /// How long a text takes to read, per the locale (in English without one)
+    pub fn reading_time(&self, text: &str) -> String {
+        match &self.locale {
+            Some(locale) => locale.reading_time(text),
+            None => format!("{} min read", reading_minutes(text)),
+        }
+    }
+
+    /// Canonical URL of an item from a template with `{slug}` and `{id}` placeholders
+    pub fn canonical_url(&self, template: &str, item: &SyndicationFormat) -> String {
+        template
+            .replace("{slug}", &self.slug(&item.text))
+            .replace("{id}", item.id.as_str())
+    }
+
+    /// Whether a word is a stopword, ignoring case and punctuation
+    fn is_stopword(&self, word: &str) -> bool {
+        let key = |word: &str| word.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+        let word = key(word);
+        !word.is_empty() && self.stopwords.iter().any(|stopword| key(stopword) == word)
+    }
+}
+
+/// A cut-off slug up to its last `-`, if it has one past the start
+fn cut_at_dash(prefix: &str) -> &str {
+    let cut = prefix.rfind('-').filter(|&end| end > 0).unwrap_or(prefix.len());
+    &prefix[..cut]
+}
+
+/// Slug from the first 8 words of a text, lowercased with punctuation removed
+pub fn slugify(text: &str) -> String {
+    TextPolicy::default().slug(text)
+}
+
+/// Canonical URL of an item from a template with `{slug}` and `{id}` placeholders
+///
+/// E.g. `https://example.com/t/{slug}-{id}` for posts published by the jj sink.
+pub fn canonical_url(template: &str, item: &SyndicationFormat) -> String {
+    TextPolicy::default().canonical_url(template, item)
+}
+
+/// Shorten text to at most `max_chars` characters including `ellipsis`
+///
+/// Cuts at the last sentence end in the second half of the budget, otherwise at the last
+/// word boundary, and only mid-word as a last resort. Text that fits is returned as is.
+pub fn smart_truncate(text: &str, max_chars: usize, ellipsis: &str) -> String {
+    if text.char_indices().nth(max_chars).is_none() {
+        return text.to_string();
+    }
+
+    let budget = max_chars.saturating_sub(ellipsis.chars().count());
+    let prefix: String = text.chars().take(budget).collect();
+
+    let sentence_end = prefix
+        .char_indices()
+        .filter(|(i, c)| {
+            matches!(c, '.' | '!' | '?')
+                && prefix[i + c.len_utf8()..].starts_with(char::is_whitespace)
+        })
+        .map(|(i, c)| i + c.len_utf8())
+        .next_back()
+        .filter(|&end| prefix[..end].chars().count() >= budget / 2);
+
+    let cut = sentence_end
+        .or_else(|| prefix.rfind(char::is_whitespace))
+        .filter(|&end| end > 0)
+        .unwrap_or(prefix.len());
+
+    format!("{}{}", prefix[..cut].trim_end(), ellipsis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_loads_from_a_text_table_with_its_stopwords_file() {
+        let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-text-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("stopwords.txt"), "# articles\nthe\na # and this one\n").unwrap();
+        let toml = "[text]\ntitle_max_words = 3\ntitle_case = \"lowercase\"\nslug_max_words = 2\n\
+                    stopwords = [\"of\"]\nstopwords_file = \"stopwords.txt\"\nellipsis = \"…\"\n";
+        std::fs::write(dir.join("syndicate.toml"), toml).unwrap();
+        let policy = TextPolicy::load(&dir.join("syndicate.toml")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(policy.stopwords, ["of", "the", "a"]);
+        assert_eq!(policy.title("The Shape of a Thought, Revisited"), "the shape of");
+        assert_eq!(policy.slug("The Shape of a Thought, Revisited"), "shape-thought");
+        assert_eq!(policy.preview_max_chars, TextPolicy::default().preview_max_chars);
+        assert!(TextPolicy::load(Path::new("/nonexistent/syndicate.toml")).is_err());
+    }
+
+    #[test]
+    fn defaults_match_slugify_and_missing_tables() {
+        let text = "Eight words make a title, and the rest is body";
+        assert_eq!(TextPolicy::default().slug(text), slugify(text));
+        assert_eq!(TextPolicy::default().title(text), "Eight words make a title, and the rest");
+        assert_eq!(TextPolicy::from_toml("[other]\nkey = 1\n").unwrap(), TextPolicy::default());
+        assert!(TextPolicy::from_toml("[text]\ntitle_case = \"shouting\"\n").is_err());
+    }
+
+    #[test]
+    fn titles_and_slugs_stay_capped_for_huge_text() {
+        let policy = TextPolicy { title_max_words: usize::MAX, slug_max_words: usize::MAX, ..TextPolicy::default() };
+        let words = "Unreasonably long words go on ".repeat(200_000);
+        let unbroken = "x".repeat(5 << 20);
+        for text in [&words, &unbroken] {
+            let title = policy.title(text);
+            let slug = policy.slug(text);
+            assert!(title.chars().count() <= MAX_TITLE_CHARS, "{}", title.len());
+            assert!(!slug.is_empty() && slug.len() <= MAX_SLUG_BYTES, "{}", slug.len());
+        }
+        assert!(!policy.slug(&words).ends_with('-'));
+        assert_eq!(smart_truncate(&unbroken, 10, "…"), format!("{}…", "x".repeat(9)));
+    }
+}
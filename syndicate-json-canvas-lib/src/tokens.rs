@@ -0,0 +1,140 @@
+use chrono::{DateTime, Days, Local};
+
+/// Format of `{{today}}` and offset dates by default
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Format of `{{now}}` by default
+pub const DEFAULT_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Formats of the dates that `{{...}}` tokens in node text expand to
+///
+/// The formats are chrono `strftime` strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenOptions {
+    /// Format of `{{today}}`, `{{+Nd}}` and `{{-Nd}}`
+    pub date_format: String,
+    /// Format of `{{now}}`
+    pub datetime_format: String,
+}
+
+impl Default for TokenOptions {
+    fn default() -> Self {
+        Self { date_format: DEFAULT_DATE_FORMAT.to_string(), datetime_format: DEFAULT_DATETIME_FORMAT.to_string() }
+    }
+}
+
+impl TokenOptions {
+    pub fn with_date_format(mut self, format: impl Into<String>) -> Self {
+        self.date_format = format.into();
+        self
+    }
+
+    pub fn with_datetime_format(mut self, format: impl Into<String>) -> Self {
+        self.datetime_format = format.into();
+        self
+    }
+}
+
+/// A text with its tokens expanded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    pub text: String,
+    /// Tokens left as they were because they mean nothing, e.g. `{{tomorrow}}`
+    pub unknown: Vec<String>,
+}
+
+/// Expand the date tokens of a text as of `now`
+///
+/// `{{today}}` and `{{now}}` become the current date and time, `{{+7d}}` and `{{-7d}}` the
+/// date that many days later or earlier. `\{{today}}` is kept as the literal `{{today}}`.
+pub fn expand_tokens(text: &str, now: DateTime<Local>, options: &TokenOptions) -> Expansion {
+    let mut expanded = String::with_capacity(text.len());
+    let mut unknown = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}").filter(|&len| !rest[start + 2..start + 2 + len].contains('\n'))
+        else {
+            expanded.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        };
+        let end = start + 2 + len + 2;
+        let token = &rest[start..end];
+
+        if let Some(before) = rest[..start].strip_suffix('\\') {
+            expanded.push_str(before);
+            expanded.push_str(token);
+        } else {
+            expanded.push_str(&rest[..start]);
+            match expand(token[2..token.len() - 2].trim(), now, options) {
+                Some(value) => expanded.push_str(&value),
+                None => {
+                    expanded.push_str(token);
+                    unknown.push(token.to_string());
+                }
+            }
+        }
+        rest = &rest[end..];
+    }
+    expanded.push_str(rest);
+    Expansion { text: expanded, unknown }
+}
+
+/// The value of a token's name, if it's one
+fn expand(name: &str, now: DateTime<Local>, options: &TokenOptions) -> Option<String> {
+    match name {
+        "today" => return Some(now.format(&options.date_format).to_string()),
+        "now" => return Some(now.format(&options.datetime_format).to_string()),
+        _ => {}
+    }
+    let (sign, days) = name.strip_suffix('d')?.split_at_checked(1)?;
+    if days.is_empty() || !days.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let days = Days::new(days.parse().ok()?);
+    let date = match sign {
+        "+" => now.date_naive().checked_add_days(days)?,
+        "-" => now.date_naive().checked_sub_days(days)?,
+        _ => return None,
+    };
+    Some(date.format(&options.date_format).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at_noon() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 2, 27, 12, 5, 0).unwrap()
+    }
+
+    fn expanded(text: &str) -> String {
+        expand_tokens(text, at_noon(), &TokenOptions::default()).text
+    }
+
+    #[test]
+    fn each_token_expands_against_the_clock() {
+        assert_eq!(expanded("Written {{today}}"), "Written 2026-02-27");
+        assert_eq!(expanded("At {{ now }}"), "At 2026-02-27 12:05");
+        assert_eq!(expanded("Due {{+3d}}, began {{-30d}}"), "Due 2026-03-02, began 2026-01-28");
+        assert_eq!(expanded("{{+0d}}"), "2026-02-27");
+
+        let options = TokenOptions::default().with_date_format("%-d %B").with_datetime_format("%H.%M");
+        assert_eq!(expand_tokens("{{today}} {{+2d}} {{now}}", at_noon(), &options).text, "27 February 1 March 12.05");
+    }
+
+    #[test]
+    fn escaped_and_unknown_tokens_stay_as_written() {
+        assert_eq!(expanded(r"Type \{{today}} for {{today}}"), "Type {{today}} for 2026-02-27");
+
+        let expansion = expand_tokens("{{tomorrow}}, {{+d}}, {{3d}} and {{+1w}}", at_noon(), &TokenOptions::default());
+        assert_eq!(expansion.text, "{{tomorrow}}, {{+d}}, {{3d}} and {{+1w}}");
+        assert_eq!(expansion.unknown, ["{{tomorrow}}", "{{+d}}", "{{3d}}", "{{+1w}}"]);
+
+        // Braces that don't close on the same line aren't tokens
+        let expansion = expand_tokens("{{today\n}} and {{", at_noon(), &TokenOptions::default());
+        assert_eq!((expansion.text.as_str(), expansion.unknown.len()), ("{{today\n}} and {{", 0));
+    }
+}
@@ -1,16 +1,55 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
+use crate::atomic::write_atomic;
 use crate::jsoncanvas::NodeId;
 
 /// TOML structure for the tracker file
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct TrackerFile {
     pub published_node_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub archived_node_ids: Vec<String>,
+    /// Content hash of each node as last published, to detect edits
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub content_hashes: BTreeMap<String, String>,
+    /// When each node was first published, for nodes published since this was tracked
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub published_at: BTreeMap<String, DateTime<Local>>,
+    /// Hash of the canvas version each node was last delivered from, for provenance
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub canvas_hashes: BTreeMap<String, String>,
+    /// Nodes never to be published to the sink, e.g. for being too old when first routed to it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_node_ids: Vec<String>,
+    /// Nodes that live elsewhere instead of being published, with their URL
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub external_urls: BTreeMap<String, String>,
+}
+
+/// State of a node in a sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishState {
+    /// Published and live
+    Published,
+    /// Published, then archived: still reachable but unlisted
+    Archived,
+}
+
+/// Path of a hidden state file stored next to the canvas: `.<canvas-name>.<suffix>`
+pub(crate) fn canvas_state_path(canvas_path: &Path, suffix: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let canvas_dir = canvas_path.parent().ok_or("Canvas path has no parent directory")?;
+    let canvas_filename = canvas_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or("Invalid canvas filename")?;
+
+    Ok(canvas_dir.join(format!(".{}.{}", canvas_filename, suffix)))
 }
 
 /// Tracks which nodes have been published to a specific sink
@@ -19,6 +58,18 @@ pub struct SyndicationTracker {
     path: PathBuf,
     /// In-memory set of published node IDs for O(1) lookup
     published_ids: HashSet<String>,
+    /// Subset of `published_ids` that has been archived
+    archived_ids: HashSet<String>,
+    /// Content hash of published nodes as last delivered
+    content_hashes: HashMap<String, String>,
+    /// When published nodes were first published, if recorded
+    published_at: HashMap<String, DateTime<Local>>,
+    /// Canvas version published nodes were last delivered from, if recorded
+    canvas_hashes: HashMap<String, String>,
+    /// Unpublished nodes settled as never to be published
+    skipped_ids: HashSet<String>,
+    /// URLs of the nodes that live elsewhere
+    external_urls: HashMap<String, String>,
 }
 
 impl SyndicationTracker {
@@ -26,35 +77,126 @@ impl SyndicationTracker {
     ///
     /// File naming: `.<canvas-name>.canvas.syndication.<sink-name>.toml`
     pub fn new(canvas_path: &Path, sink_name: &str) -> Result<Self, Box<dyn Error>> {
-        let canvas_dir = canvas_path.parent().ok_or("Canvas path has no parent directory")?;
-        let canvas_filename = canvas_path
-            .file_name()
-            .and_then(|s| s.to_str())
-            .ok_or("Invalid canvas filename")?;
-
         // Build tracker filename: .<canvas-name>.syndication.<sink-name>.toml
-        let tracker_filename = format!(".{}.syndication.{}.toml", canvas_filename, sink_name);
-        let path = canvas_dir.join(tracker_filename);
+        let path = canvas_state_path(canvas_path, &format!("syndication.{}.toml", sink_name))?;
 
         // Load existing tracker or create empty
-        let published_ids = if path.exists() {
-            let content = std::fs::read_to_string(&path)?;
-            let tracker: TrackerFile = toml::from_str(&content)?;
-            tracker.published_node_ids.into_iter().collect()
+        let tracker: TrackerFile = if path.exists() {
+            toml::from_str(&std::fs::read_to_string(&path)?)?
         } else {
-            HashSet::new()
+            TrackerFile::default()
         };
+        let published_ids: HashSet<String> = tracker.published_node_ids.into_iter().collect();
 
         info!(tracker_path = %path.display(), published_count = published_ids.len(), "Loaded tracker");
 
-        Ok(Self { path, published_ids })
+        Ok(Self {
+            path,
+            published_ids,
+            archived_ids: tracker.archived_node_ids.into_iter().collect(),
+            content_hashes: tracker.content_hashes.into_iter().collect(),
+            published_at: tracker.published_at.into_iter().collect(),
+            canvas_hashes: tracker.canvas_hashes.into_iter().collect(),
+            skipped_ids: tracker.skipped_node_ids.into_iter().collect(),
+            external_urls: tracker.external_urls.into_iter().collect(),
+        })
     }
 
-    /// Check if a node has already been published
+    /// Path of the tracker file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check if a node has already been published (archived nodes count as published)
     pub fn is_published(&self, node_id: &NodeId) -> bool {
         self.published_ids.contains(node_id.as_str())
     }
 
+    /// Check if a node was skipped, so it's never published to the sink
+    ///
+    /// Skipped nodes don't count as published: nothing of them exists to archive or update.
+    pub fn is_skipped(&self, node_id: &NodeId) -> bool {
+        self.skipped_ids.contains(node_id.as_str())
+    }
+
+    /// URL of a node recorded as living elsewhere, which is never published to the sink
+    pub fn external_url(&self, node_id: &NodeId) -> Option<&str> {
+        self.external_urls.get(node_id.as_str()).map(String::as_str)
+    }
+
+    /// Iterate over the published node IDs (including archived ones)
+    pub fn published(&self) -> impl Iterator<Item = &str> {
+        self.published_ids.iter().map(String::as_str)
+    }
+
+    /// Number of published nodes that aren't archived
+    pub fn live_count(&self) -> usize {
+        self.published_ids.len() - self.archived_ids.len()
+    }
+
+    /// Check if a published node has been archived
+    pub fn is_archived(&self, node_id: &NodeId) -> bool {
+        self.archived_ids.contains(node_id.as_str())
+    }
+
+    /// State of a node, or None if it was never published
+    pub fn state(&self, node_id: &NodeId) -> Option<PublishState> {
+        if self.is_archived(node_id) {
+            Some(PublishState::Archived)
+        } else if self.is_published(node_id) {
+            Some(PublishState::Published)
+        } else {
+            None
+        }
+    }
+
+    /// Content hash of a node as last delivered, if recorded
+    ///
+    /// Nodes published before hashes were tracked have none until their next delivery.
+    pub fn content_hash(&self, node_id: &NodeId) -> Option<&str> {
+        self.content_hashes.get(node_id.as_str()).map(String::as_str)
+    }
+
+    /// When a node was first published to the sink
+    ///
+    /// None if it wasn't, or was published before publish times were tracked.
+    pub fn published_at(&self, node_id: &NodeId) -> Option<DateTime<Local>> {
+        self.published_at.get(node_id.as_str()).copied()
+    }
+
+    /// Hash of the canvas version a node was last delivered from, if recorded
+    ///
+    /// Matches the `canvas_hash` of the run report of that delivery.
+    pub fn canvas_hash(&self, node_id: &NodeId) -> Option<&str> {
+        self.canvas_hashes.get(node_id.as_str()).map(String::as_str)
+    }
+
+    /// Record the canvas versions delivered nodes came from and save to disk
+    pub fn record_canvas_hashes(&mut self, hashes: &[(NodeId, String)]) -> Result<(), Box<dyn Error>> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+
+        for (node_id, hash) in hashes {
+            self.canvas_hashes.insert(node_id.as_str().to_string(), hash.clone());
+        }
+
+        self.save()
+    }
+
+    /// Record the content hashes of delivered nodes and save to disk
+    pub fn record_content_hashes(&mut self, hashes: &[(NodeId, String)]) -> Result<(), Box<dyn Error>> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+
+        for (node_id, hash) in hashes {
+            self.content_hashes.insert(node_id.as_str().to_string(), hash.clone());
+        }
+
+        self.save()
+    }
+
     /// Mark nodes as published and save to disk
     pub fn mark_published(&mut self, node_ids: &[NodeId]) -> Result<(), Box<dyn Error>> {
         if node_ids.is_empty() {
@@ -65,8 +207,103 @@ impl SyndicationTracker {
             self.published_ids.insert(node_id.as_str().to_string());
         }
 
+        self.save()
+    }
+
+    /// Mark nodes as published at `at`, keeping the time of nodes published before, and save
+    /// to disk
+    pub fn mark_published_at(&mut self, node_ids: &[NodeId], at: DateTime<Local>) -> Result<(), Box<dyn Error>> {
+        for node_id in node_ids {
+            self.published_at.entry(node_id.as_str().to_string()).or_insert(at);
+        }
+        self.mark_published(node_ids)
+    }
+
+    /// Mark unpublished nodes as skipped and save to disk
+    pub fn mark_skipped(&mut self, node_ids: &[NodeId]) -> Result<(), Box<dyn Error>> {
+        if node_ids.is_empty() {
+            return Ok(());
+        }
+
+        for node_id in node_ids {
+            self.skipped_ids.insert(node_id.as_str().to_string());
+        }
+
+        self.save()
+    }
+
+    /// Record nodes as living elsewhere, with their URLs, and save to disk if any changed
+    pub fn mark_external(&mut self, links: &[(NodeId, String)]) -> Result<(), Box<dyn Error>> {
+        let mut changed = false;
+        for (node_id, url) in links {
+            if self.external_url(node_id) != Some(url.as_str()) {
+                self.external_urls.insert(node_id.as_str().to_string(), url.clone());
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return Ok(());
+        }
+
+        self.save()
+    }
+
+    /// Mark published nodes as archived and save to disk
+    pub fn mark_archived(&mut self, node_ids: &[NodeId]) -> Result<(), Box<dyn Error>> {
+        if node_ids.is_empty() {
+            return Ok(());
+        }
+
+        for node_id in node_ids {
+            self.published_ids.insert(node_id.as_str().to_string());
+            self.archived_ids.insert(node_id.as_str().to_string());
+        }
+
+        self.save()
+    }
+
+    /// Return archived nodes to the published state and save to disk
+    pub fn mark_unarchived(&mut self, node_ids: &[NodeId]) -> Result<(), Box<dyn Error>> {
+        if node_ids.is_empty() {
+            return Ok(());
+        }
+
+        for node_id in node_ids {
+            self.archived_ids.remove(node_id.as_str());
+        }
+
+        self.save()
+    }
+
+    /// Forget nodes entirely, so they count as never published or skipped, and save to disk
+    pub fn forget(&mut self, node_ids: &[NodeId]) -> Result<(), Box<dyn Error>> {
+        if node_ids.is_empty() {
+            return Ok(());
+        }
+
+        for node_id in node_ids {
+            self.published_ids.remove(node_id.as_str());
+            self.archived_ids.remove(node_id.as_str());
+            self.content_hashes.remove(node_id.as_str());
+            self.published_at.remove(node_id.as_str());
+            self.canvas_hashes.remove(node_id.as_str());
+            self.skipped_ids.remove(node_id.as_str());
+            self.external_urls.remove(node_id.as_str());
+        }
+
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
         let tracker = TrackerFile {
             published_node_ids: self.published_ids.iter().cloned().collect(),
+            archived_node_ids: self.archived_ids.iter().cloned().collect(),
+            content_hashes: self.content_hashes.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            published_at: self.published_at.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+            canvas_hashes: self.canvas_hashes.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            skipped_node_ids: self.skipped_ids.iter().cloned().collect(),
+            external_urls: self.external_urls.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
         };
 
         let toml_content = toml::to_string_pretty(&tracker)?;
@@ -75,7 +312,7 @@ impl SyndicationTracker {
             toml_content
         );
 
-        std::fs::write(&self.path, content_with_header)?;
+        write_atomic(&self.path, content_with_header)?;
         info!(tracker_path = %self.path.display(), total_published = self.published_ids.len(), "Saved tracker");
 
         Ok(())
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::CanvasGraphError;
+use crate::archive::contains;
+use crate::jsoncanvas::node::GenericNodeInfo;
+use crate::jsoncanvas::{JsonCanvas, Node};
+use crate::selection::Selection;
+use crate::snapshot::CanvasSnapshot;
+
+/// What's wrong with the structure of a canvas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CanvasIssueKind {
+    /// Several nodes share an ID, and only one of them is read
+    DuplicateNodeId,
+    /// An edge points at a node that isn't on the canvas
+    DanglingEdge,
+    /// An edge goes from a node back to itself
+    SelfLoop,
+    /// A text node colored for syndication has no text, so nothing is published for it
+    EmptyMarkedNode,
+    /// A group has no nodes in it
+    EmptyGroup,
+}
+
+impl CanvasIssueKind {
+    /// Name of the kind in logs and output, e.g. `dangling-edge`
+    pub fn code(&self) -> &'static str {
+        match self {
+            CanvasIssueKind::DuplicateNodeId => "duplicate-node-id",
+            CanvasIssueKind::DanglingEdge => "dangling-edge",
+            CanvasIssueKind::SelfLoop => "self-loop",
+            CanvasIssueKind::EmptyMarkedNode => "empty-marked-node",
+            CanvasIssueKind::EmptyGroup => "empty-group",
+        }
+    }
+}
+
+/// A structural problem with a canvas, naming the node or edge to fix
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanvasIssue {
+    pub kind: CanvasIssueKind,
+    /// Node the issue is about; for a dangling edge, the one that's missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    /// Edge the issue is about, if it's about one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_id: Option<String>,
+    pub message: String,
+}
+
+impl CanvasIssue {
+    fn new(kind: CanvasIssueKind, message: impl Into<String>) -> Self {
+        Self { kind, node_id: None, edge_id: None, message: message.into() }
+    }
+
+    fn for_node(mut self, node_id: impl Into<String>) -> Self {
+        self.node_id = Some(node_id.into());
+        self
+    }
+
+    fn for_edge(mut self, edge_id: impl Into<String>) -> Self {
+        self.edge_id = Some(edge_id.into());
+        self
+    }
+}
+
+impl fmt::Display for CanvasIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.code(), self.message)
+    }
+}
+
+/// Structural problems with a canvas whose red nodes are syndicated (see [`validate_canvas_with`])
+pub fn validate_canvas(canvas: &JsonCanvas) -> Vec<CanvasIssue> {
+    validate_canvas_with(canvas, &Selection::default())
+}
+
+/// Structural problems with a canvas: dangling and self-loop edges, empty groups, and empty
+/// text nodes `selection` would syndicate, ordered by kind and then ID
+///
+/// Duplicate node IDs are gone once the canvas is parsed; see [`duplicate_node_ids`].
+pub fn validate_canvas_with(canvas: &JsonCanvas, selection: &Selection) -> Vec<CanvasIssue> {
+    let nodes = canvas.get_nodes();
+    let mut issues = Vec::new();
+
+    for (edge_id, edge) in canvas.get_edges() {
+        let missing = [edge.from_node(), edge.to_node()].into_iter().find(|node_id| !nodes.contains_key(*node_id));
+        if let Some(missing_node) = missing {
+            let error = CanvasGraphError::DanglingEdge { edge_id: edge_id.clone(), missing_node: missing_node.clone() };
+            let issue = CanvasIssue::new(CanvasIssueKind::DanglingEdge, error.to_string());
+            issues.push(issue.for_node(missing_node.as_str()).for_edge(edge_id.as_str()));
+        } else if edge.from_node() == edge.to_node() {
+            let message = format!("edge `{}` goes from node `{}` back to itself", edge_id, edge.from_node());
+            let issue = CanvasIssue::new(CanvasIssueKind::SelfLoop, message);
+            issues.push(issue.for_node(edge.from_node().as_str()).for_edge(edge_id.as_str()));
+        }
+    }
+
+    for (node_id, node) in nodes {
+        match node {
+            Node::Text(text_node) if text_node.text().trim().is_empty() && marked(selection, node) => {
+                let message = format!("text node `{}` is colored for syndication but has no text", node_id);
+                issues.push(CanvasIssue::new(CanvasIssueKind::EmptyMarkedNode, message).for_node(node_id.as_str()));
+            }
+            Node::Group(group) if !nodes.values().any(|other| other.id() != node_id && contains(node, other)) => {
+                let name = match group.label().map(|label| label.trim()).filter(|label| !label.is_empty()) {
+                    Some(label) => format!("group `{}` ({})", node_id, label),
+                    None => format!("group `{}`", node_id),
+                };
+                let message = format!("{} has no nodes in it", name);
+                issues.push(CanvasIssue::new(CanvasIssueKind::EmptyGroup, message).for_node(node_id.as_str()));
+            }
+            _ => {}
+        }
+    }
+
+    issues.sort_by(|a, b| (a.kind, &a.node_id, &a.edge_id).cmp(&(b.kind, &b.node_id, &b.edge_id)));
+    issues
+}
+
+/// Whether `selection` picks nodes of `node`'s color; [`Selection::All`] ignores colors
+fn marked(selection: &Selection, node: &Node) -> bool {
+    match selection {
+        Selection::Marked(colors) => colors.matches(node.color().as_ref()),
+        Selection::All => false,
+    }
+}
+
+/// Node IDs used by several nodes in a canvas file's JSON, ordered by ID
+///
+/// Parsing keeps one node per ID, so these can only be found in the JSON. Content that
+/// isn't a canvas has none.
+pub fn duplicate_node_ids(content: &str) -> Vec<CanvasIssue> {
+    let Ok(value) = serde_json::from_str::<Value>(content) else {
+        return Vec::new();
+    };
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for node in value.get("nodes").and_then(Value::as_array).into_iter().flatten() {
+        if let Some(id) = node.get("id").and_then(Value::as_str) {
+            *counts.entry(id).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(id, count)| {
+            let message = format!("{} nodes share the ID `{}`; only one of them is read", count, id);
+            CanvasIssue::new(CanvasIssueKind::DuplicateNodeId, message).for_node(id)
+        })
+        .collect()
+}
+
+/// Every issue of a canvas as read: duplicate node IDs in its file, then the structural ones
+///
+/// Fails with the parse error if the canvas doesn't parse.
+pub fn validate_snapshot(snapshot: &CanvasSnapshot, selection: &Selection) -> Result<Vec<CanvasIssue>, String> {
+    let canvas = snapshot.canvas()?;
+    let mut issues = duplicate_node_ids(&String::from_utf8_lossy(&snapshot.bytes));
+    issues.extend(validate_canvas_with(canvas, selection));
+    Ok(issues)
+}
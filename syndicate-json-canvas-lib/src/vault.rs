@@ -0,0 +1,84 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Error types for resolving vault-relative paths
+#[derive(Debug, thiserror::Error)]
+pub enum VaultError {
+    #[error("Path resolves outside the vault root: {0}")]
+    OutsideVault(String),
+}
+
+/// An Obsidian vault that File nodes and `![[embeds]]` are resolved against
+///
+/// Every feature that reads files referenced from the canvas should go through
+/// [`Vault::resolve`] so paths escaping the vault are rejected in one place.
+#[derive(Debug, Clone)]
+pub struct Vault {
+    root: PathBuf,
+}
+
+impl Vault {
+    /// Create a vault rooted at an explicit directory
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        Self {
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+        }
+    }
+
+    /// Find the vault containing a canvas by walking up looking for an `.obsidian/` directory
+    pub fn detect(canvas_path: &Path) -> Option<Self> {
+        let canvas_path = canvas_path
+            .canonicalize()
+            .unwrap_or_else(|_| canvas_path.to_path_buf());
+        canvas_path
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join(".obsidian").is_dir())
+            .map(Self::new)
+    }
+
+    /// Use the configured vault root if any, else auto-detect, else the canvas's own directory
+    pub fn for_canvas(canvas_path: &Path, vault_root: Option<&Path>) -> Self {
+        if let Some(root) = vault_root {
+            return Self::new(root);
+        }
+        Self::detect(canvas_path).unwrap_or_else(|| {
+            Self::new(canvas_path.parent().unwrap_or_else(|| Path::new(".")))
+        })
+    }
+
+    /// The vault root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolve a vault-relative path, rejecting anything that escapes the vault root
+    ///
+    /// `..` components are resolved lexically, and existing paths are additionally
+    /// canonicalized so symlinks pointing outside the vault are rejected too.
+    pub fn resolve(&self, vault_relative: &str) -> Result<PathBuf, VaultError> {
+        let outside = || VaultError::OutsideVault(vault_relative.to_string());
+
+        let mut resolved = self.root.clone();
+        for component in Path::new(vault_relative).components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if resolved == self.root || !resolved.pop() {
+                        return Err(outside());
+                    }
+                }
+                Component::RootDir | Component::Prefix(_) => return Err(outside()),
+            }
+        }
+
+        if let Ok(canonical) = resolved.canonicalize()
+            && !canonical.starts_with(&self.root)
+        {
+            return Err(outside());
+        }
+
+        Ok(resolved)
+    }
+}
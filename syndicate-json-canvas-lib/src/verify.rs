@@ -0,0 +1,78 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use tracing::debug;
+
+use crate::orchestrator::SinkTarget;
+use crate::sink::SinkError;
+
+/// What is wrong with a published file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileProblem {
+    /// The frontmatter block is missing or doesn't parse
+    InvalidFrontmatter(String),
+    /// A key every post must have is missing or empty
+    MissingKey(String),
+    /// The node ID in the frontmatter doesn't appear in the file name
+    IdMismatch { file_name: String, node_id: String },
+}
+
+impl fmt::Display for FileProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileProblem::InvalidFrontmatter(reason) => write!(f, "invalid frontmatter: {}", reason),
+            FileProblem::MissingKey(key) => write!(f, "missing `{}`", key),
+            FileProblem::IdMismatch { file_name, node_id } => {
+                write!(f, "node_id `{}` doesn't match the file name `{}`", node_id, file_name)
+            }
+        }
+    }
+}
+
+/// A published file that doesn't read back as a valid post
+#[derive(Debug, Clone)]
+pub struct FileIssue {
+    pub path: PathBuf,
+    pub problem: FileProblem,
+}
+
+/// Result of checking a sink's published files
+#[derive(Debug, Clone)]
+pub struct VerifyReport {
+    /// Name of the sink
+    pub sink: String,
+    /// Number of files read
+    pub files_checked: usize,
+    pub issues: Vec<FileIssue>,
+}
+
+impl VerifyReport {
+    /// Whether every file checked is valid
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Number of distinct files with at least one issue
+    pub fn files_with_issues(&self) -> usize {
+        let mut paths: Vec<&PathBuf> = self.issues.iter().map(|issue| &issue.path).collect();
+        paths.dedup();
+        paths.len()
+    }
+}
+
+/// Check the published files of every sink that can inspect its output
+///
+/// Read-only; sinks that can't verify are skipped.
+pub fn verify(targets: &[SinkTarget]) -> Result<Vec<VerifyReport>, SinkError> {
+    let mut reports = Vec::new();
+    for target in targets {
+        match target.sink.verify() {
+            Ok(report) => reports.push(report),
+            Err(e) if e.is_unsupported() => {
+                debug!(sink = %target.sink.name(), reason = %e.kind(), "Skipping verification");
+            }
+            Err(e) => return Err(e.in_sink(target.sink.name())),
+        }
+    }
+    Ok(reports)
+}
@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::jsoncanvas::NodeId;
+
+/// Codes of the warnings raised by the pipeline and the bundled sinks
+pub mod codes {
+    /// A node the canvas parser couldn't read was skipped
+    pub const SKIPPED_NODE: &str = "skipped-node";
+    /// A footnote marker has no definition
+    pub const FOOTNOTE_UNDEFINED: &str = "footnote-undefined";
+    /// A footnote definition is never referenced
+    pub const FOOTNOTE_UNUSED: &str = "footnote-unused";
+    /// A node matched the filter but is on the exclusion list
+    pub const EXCLUDED_MATCH: &str = "excluded-match";
+    /// A node is a duplicate of another
+    pub const DUPLICATE: &str = "duplicate";
+    /// Raw HTML was stripped from a node's text
+    pub const HTML_STRIPPED: &str = "html-stripped";
+    /// A node to (un)archive has no published file
+    pub const ARCHIVE_MISSING_FILE: &str = "archive-missing-file";
+    /// A node is too long for the sink and was skipped
+    pub const TOO_LONG: &str = "too-long";
+    /// A File node's file couldn't be read, or has no section its subpath names, and was skipped
+    pub const FILE_UNREADABLE: &str = "file-unreadable";
+    /// A node's text is over the size limit and was skipped
+    pub const OVERSIZE_SKIPPED: &str = "oversize-skipped";
+    /// A node's text is over the size limit and was cut down to it
+    pub const OVERSIZE_TRUNCATED: &str = "oversize-truncated";
+    /// A `{{...}}` token in a node's text means nothing and was left as is
+    pub const UNKNOWN_TOKEN: &str = "unknown-token";
+    /// An image has no alt text
+    pub const MISSING_ALT: &str = "missing-alt";
+    /// A link's visible text is a long bare URL
+    pub const BARE_URL_LINK: &str = "bare-url-link";
+    /// A heading skips levels
+    pub const HEADING_JUMP: &str = "heading-jump";
+    /// A node had nothing to publish once its text was transformed, or converted for a sink
+    pub const EMPTY_ITEM: &str = "empty-item";
+    /// A group's label has settings that don't parse, and they were ignored
+    pub const GROUP_SETTINGS: &str = "group-settings";
+    /// A link errors or times out
+    pub const BROKEN_LINK: &str = "broken-link";
+    /// A link redirects elsewhere
+    pub const REDIRECTED_LINK: &str = "redirected-link";
+    /// A node was edited after it went out where it can't be changed anymore (e.g. a sent email)
+    pub const EDIT_NOT_DELIVERED: &str = "edit-not-delivered";
+    /// An edge ends at a node that isn't on the canvas, and was left out
+    pub const DANGLING_EDGE: &str = "dangling-edge";
+    /// A wikilink's title matches posts of several other canvases, and one was picked
+    pub const AMBIGUOUS_WIKILINK: &str = "ambiguous-wikilink";
+
+    /// Every code above, for checking configured codes
+    pub const ALL: &[&str] = &[
+        SKIPPED_NODE,
+        FOOTNOTE_UNDEFINED,
+        FOOTNOTE_UNUSED,
+        EXCLUDED_MATCH,
+        DUPLICATE,
+        HTML_STRIPPED,
+        ARCHIVE_MISSING_FILE,
+        TOO_LONG,
+        FILE_UNREADABLE,
+        OVERSIZE_SKIPPED,
+        OVERSIZE_TRUNCATED,
+        UNKNOWN_TOKEN,
+        MISSING_ALT,
+        BARE_URL_LINK,
+        HEADING_JUMP,
+        EMPTY_ITEM,
+        GROUP_SETTINGS,
+        BROKEN_LINK,
+        REDIRECTED_LINK,
+        EDIT_NOT_DELIVERED,
+        DANGLING_EDGE,
+        AMBIGUOUS_WIKILINK,
+    ];
+}
+
+/// Something worth a look about one node (or the whole run), kept for the end-of-run
+/// summary and the report instead of only scrolling past in the log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Warning {
+    /// What kind of warning it is (see [`codes`]), for promoting or silencing it
+    pub code: String,
+    /// Node it is about, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    pub message: String,
+    /// Feature or sink that raised it, e.g. `footnotes` or `twitter`
+    pub source: String,
+}
+
+impl Warning {
+    pub fn new(code: &str, source: &str, message: impl Into<String>) -> Self {
+        Self { code: code.to_string(), node_id: None, message: message.into(), source: source.to_string() }
+    }
+
+    pub fn for_node(mut self, node_id: &NodeId) -> Self {
+        self.node_id = Some(node_id.as_str().to_string());
+        self
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.code)?;
+        if let Some(node_id) = &self.node_id {
+            write!(f, "{}: ", node_id)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Warnings of one code, for the end-of-run summary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarningGroup {
+    pub code: String,
+    pub source: String,
+    pub warnings: Vec<Warning>,
+}
+
+/// Group warnings by code, in code order
+pub fn group_warnings<'a>(warnings: impl IntoIterator<Item = &'a Warning>) -> Vec<WarningGroup> {
+    let mut groups: BTreeMap<&str, WarningGroup> = BTreeMap::new();
+    for warning in warnings {
+        groups
+            .entry(warning.code.as_str())
+            .or_insert_with(|| WarningGroup {
+                code: warning.code.clone(),
+                source: warning.source.clone(),
+                warnings: Vec::new(),
+            })
+            .warnings
+            .push(warning.clone());
+    }
+    groups.into_values().collect()
+}
+
+/// Log a grouped summary of a run's warnings
+pub fn log_summary<'a>(warnings: impl IntoIterator<Item = &'a Warning>) {
+    for group in group_warnings(warnings) {
+        let nodes: Vec<&str> = group.warnings.iter().filter_map(|w| w.node_id.as_deref()).collect();
+        warn!(
+            code = %group.code,
+            source = %group.source,
+            count = group.warnings.len(),
+            nodes = ?nodes,
+            "{}",
+            group.warnings[0].message
+        );
+    }
+}
+
+/// Which warning codes fail the run and which are dropped
+///
+/// Promoted warnings raised before publishing stop the run before any sink is touched.
+/// Sinks raise theirs while delivering, so those mark the sink's outcome as failed after
+/// the fact.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarningPolicy {
+    promoted: Vec<String>,
+    silenced: Vec<String>,
+}
+
+impl WarningPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treat warnings of `code` as errors
+    pub fn promote(mut self, code: impl Into<String>) -> Self {
+        self.promoted.push(code.into());
+        self
+    }
+
+    /// Drop warnings of `code` without reporting them
+    pub fn silence(mut self, code: impl Into<String>) -> Self {
+        self.silenced.push(code.into());
+        self
+    }
+
+    pub fn is_promoted(&self, code: &str) -> bool {
+        self.promoted.iter().any(|c| c == code)
+    }
+
+    pub fn is_silenced(&self, code: &str) -> bool {
+        self.silenced.iter().any(|c| c == code)
+    }
+
+    /// Drop silenced warnings and return the promoted ones, as an error message if any
+    pub fn apply(&self, warnings: &mut Vec<Warning>) -> Option<String> {
+        warnings.retain(|w| !self.is_silenced(&w.code));
+        let promoted: Vec<String> = warnings.iter().filter(|w| self.is_promoted(&w.code)).map(Warning::to_string).collect();
+        if promoted.is_empty() {
+            return None;
+        }
+        Some(format!("warnings promoted to errors: {}", promoted.join("; ")))
+    }
+}
@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use notify_debouncer_mini::{DebouncedEvent, DebouncedEventKind};
+
+use crate::atomic::is_own_write;
+
+/// How the file watcher decides which changes start a run
+#[derive(Debug, Clone, Default)]
+pub struct WatchOptions {
+    /// Glob patterns of paths whose events are ignored
+    ///
+    /// `*` and `?` match within a path segment and `**` across segments. Patterns without
+    /// a `/` are matched against file names, others against the end of the path.
+    pub ignore: Vec<String>,
+    /// Least time between the starts of two runs, however often the canvas changes
+    pub min_interval: Duration,
+}
+
+impl WatchOptions {
+    /// Ignore events of paths matching a glob pattern
+    pub fn with_ignore(mut self, pattern: impl Into<String>) -> Self {
+        self.ignore.push(pattern.into());
+        self
+    }
+
+    /// Wait at least `min_interval` between the starts of two runs
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+}
+
+/// Watcher events that were dropped instead of starting a run, by reason
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilteredEvents {
+    /// Events matching an ignore pattern
+    pub ignored: usize,
+    /// Events of other files in the canvas's directory
+    pub other_files: usize,
+    /// Events of files as this process itself wrote them
+    pub own_writes: usize,
+    /// Events of the canvas that didn't change it (e.g. a run opening it)
+    pub unchanged: usize,
+}
+
+impl FilteredEvents {
+    pub fn total(&self) -> usize {
+        self.ignored + self.other_files + self.own_writes + self.unchanged
+    }
+
+    /// Add the counts of another batch of events
+    pub fn add(&mut self, other: FilteredEvents) {
+        self.ignored += other.ignored;
+        self.other_files += other.other_files;
+        self.own_writes += other.own_writes;
+        self.unchanged += other.unchanged;
+    }
+}
+
+/// Modification time and size of a file
+pub(crate) type FileStamp = (SystemTime, u64);
+
+pub(crate) fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Decides which watcher events are changes to one canvas
+#[derive(Clone)]
+pub(crate) struct EventFilter {
+    canvas_path: PathBuf,
+    ignore: Vec<String>,
+    /// Stamp of the canvas when the latest run started, or as this process last wrote it
+    run_stamp: Arc<Mutex<Option<FileStamp>>>,
+}
+
+impl EventFilter {
+    pub(crate) fn new(canvas_path: &Path, options: &WatchOptions, run_stamp: Arc<Mutex<Option<FileStamp>>>) -> Self {
+        Self { canvas_path: canvas_path.to_path_buf(), ignore: options.ignore.clone(), run_stamp }
+    }
+
+    /// Whether the events include a change to the canvas since the latest run started,
+    /// with the events dropped on the way
+    ///
+    /// Sibling files (state files, temp files of atomic saves) share the directory, and on
+    /// Linux merely opening the canvas (as every run does) produces an event, so the file's
+    /// stamp is compared with the one the latest run saw. A canvas as this process wrote it
+    /// is taken as seen, so reading it to compare contents doesn't set off more events.
+    ///
+    /// Called on the watcher's thread, so a change can cancel a run in progress.
+    pub(crate) fn check(&self, events: &[DebouncedEvent]) -> (bool, FilteredEvents) {
+        let mut filtered = FilteredEvents::default();
+        let mut touched = 0;
+        for event in events.iter().filter(|event| matches!(event.kind, DebouncedEventKind::Any)) {
+            if self.ignore.iter().any(|pattern| glob_match(pattern, &event.path)) {
+                filtered.ignored += 1;
+            } else if !same_file(&event.path, &self.canvas_path) {
+                filtered.other_files += 1;
+            } else {
+                touched += 1;
+            }
+        }
+        if touched == 0 {
+            return (false, filtered);
+        }
+
+        let stamp = file_stamp(&self.canvas_path);
+        let mut run_stamp = self.run_stamp.lock().expect("stamp lock poisoned");
+        if stamp == *run_stamp {
+            filtered.unchanged += touched;
+            return (false, filtered);
+        }
+        if is_own_write(&self.canvas_path) {
+            filtered.own_writes += touched;
+            *run_stamp = stamp;
+            return (false, filtered);
+        }
+        (true, filtered)
+    }
+}
+
+/// Whether a watcher event path is the canvas
+///
+/// Events carry absolute paths while canvas paths may be relative, so the file names are
+/// compared first and the directories only when both resolve.
+fn same_file(event_path: &Path, canvas_path: &Path) -> bool {
+    if event_path.file_name() != canvas_path.file_name() {
+        return false;
+    }
+    let dir = |path: &Path| {
+        let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        parent.canonicalize().ok()
+    };
+    match (dir(event_path), dir(canvas_path)) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    }
+}
+
+/// Whether a path matches an ignore pattern (see [`WatchOptions::ignore`])
+fn glob_match(pattern: &str, path: &Path) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if !pattern.contains('/') {
+        return segments.last().is_some_and(|name| wildcard_match(pattern, name));
+    }
+
+    let mut parts: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    if !pattern.starts_with('/') {
+        parts.insert(0, "**");
+    }
+    segments_match(&parts, &segments)
+}
+
+fn segments_match(pattern: &[&str], segments: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => (0..=segments.len()).any(|skip| segments_match(rest, &segments[skip..])),
+        Some((part, rest)) => segments
+            .split_first()
+            .is_some_and(|(segment, remaining)| wildcard_match(part, segment) && segments_match(rest, remaining)),
+    }
+}
+
+/// `*` and `?` matching within one path segment
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it's matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((after_star, matched)) => {
+                    p = after_star;
+                    t = matched + 1;
+                    star = Some((after_star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atomic::write_atomic;
+
+    fn events(paths: &[PathBuf]) -> Vec<DebouncedEvent> {
+        paths.iter().map(|path| DebouncedEvent::new(path.clone(), DebouncedEventKind::Any)).collect()
+    }
+
+    #[test]
+    fn only_new_changes_to_the_canvas_start_a_run() {
+        let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-watch-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(".obsidian")).unwrap();
+        let canvas = dir.join("notes.canvas");
+        std::fs::write(&canvas, "{}").unwrap();
+        let run_stamp = Arc::new(Mutex::new(None));
+        let filter = EventFilter::new(&canvas, &WatchOptions::default().with_ignore(".obsidian/**"), run_stamp.clone());
+
+        // Obsidian's workspace churn and the tool's own state files
+        let noise = [dir.join(".obsidian/workspace.json"), dir.join(".notes.canvas.syndication.jj.toml")];
+        let expected = FilteredEvents { ignored: 1, other_files: 1, ..Default::default() };
+        assert_eq!(filter.check(&events(&noise)), (false, expected));
+
+        // An edit, seen once by the run it starts
+        assert_eq!(filter.check(&events(&[canvas.clone(), canvas.clone()])), (true, FilteredEvents::default()));
+        *run_stamp.lock().unwrap() = file_stamp(&canvas);
+        let expected = FilteredEvents { unchanged: 1, ..Default::default() };
+        assert_eq!(filter.check(&events(std::slice::from_ref(&canvas))), (false, expected));
+
+        // Write-back by this process
+        write_atomic(&canvas, r#"{"nodes": []}"#).unwrap();
+        let expected = FilteredEvents { own_writes: 1, ..Default::default() };
+        assert_eq!(filter.check(&events(std::slice::from_ref(&canvas))), (false, expected));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ignore_patterns_match_names_and_path_suffixes() {
+        assert!(glob_match("*.tmp", Path::new("/vault/.notes.canvas.tmp")));
+        assert!(!glob_match("*.tmp", Path::new("/vault/tmp/notes.canvas")));
+        assert!(glob_match(".obsidian/**", Path::new("/vault/.obsidian/plugins/x/data.json")));
+        assert!(glob_match("drafts/*.canvas", Path::new("C:\\vault\\drafts\\a.canvas")));
+        assert!(!glob_match("/drafts/*.canvas", Path::new("/vault/drafts/a.canvas")));
+        assert!(glob_match("workspace?.json", Path::new("workspace2.json")));
+    }
+}
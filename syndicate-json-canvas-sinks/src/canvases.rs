@@ -0,0 +1,78 @@
+//! Which canvas each post in a post folder was published from, so several canvases can
+//! publish into one folder and link to each other's posts by title
+//!
+//! Kept in a manifest in the folder as a sorted list of `{canvas-stem}:{node-id}` keys,
+//! the stem being the canvas file name without `.canvas`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use syndicate_json_canvas_lib::{SinkError, jsoncanvas::NodeId, write_atomic};
+
+/// Name of the manifest in the post folder; hidden, so site generators skip it
+pub(crate) const MANIFEST_FILE: &str = ".syndication-canvases.json";
+
+/// Canvas of each recorded post, by node ID; `None` if the folder has no manifest yet
+pub(crate) fn load(folder: &Path) -> Result<Option<HashMap<NodeId, String>>, SinkError> {
+    let path = folder.join(MANIFEST_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let keys: Vec<String> = serde_json::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|e| SinkError::Serialization(format!("{}: {}", path.display(), e)))?;
+    Ok(Some(keys.iter().filter_map(|key| parse_key(key)).collect()))
+}
+
+/// Write the manifest
+pub(crate) fn save(folder: &Path, canvases: &HashMap<NodeId, String>) -> Result<(), SinkError> {
+    let mut keys: Vec<String> = canvases.iter().map(|(node_id, canvas)| key(canvas, node_id)).collect();
+    keys.sort();
+    let json = serde_json::to_string_pretty(&keys).map_err(|e| SinkError::Serialization(e.to_string()))?;
+    write_atomic(&folder.join(MANIFEST_FILE), json + "\n")?;
+    Ok(())
+}
+
+/// Stem of a canvas file name, e.g. `reading` for `reading.canvas`
+pub(crate) fn stem(canvas_name: &str) -> &str {
+    canvas_name.strip_suffix(".canvas").unwrap_or(canvas_name)
+}
+
+/// Namespaced key of a node, e.g. `reading:9e50a1c2b3d4e5f6`
+pub(crate) fn key(canvas: &str, node_id: &NodeId) -> String {
+    format!("{}:{}", canvas, node_id)
+}
+
+/// Node and canvas of a namespaced key; node IDs hold no `:`, canvas stems might
+fn parse_key(key: &str) -> Option<(NodeId, String)> {
+    let (canvas, node_id) = key.rsplit_once(':')?;
+    Some((node_id.parse().ok()?, canvas.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_round_trip_through_the_manifest() {
+        let folder = std::env::temp_dir().join(format!("syndicate-json-canvas-canvases-{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        assert_eq!(load(&folder).unwrap(), None);
+
+        let canvases: HashMap<NodeId, String> = [
+            ("b".parse().unwrap(), "reading".to_string()),
+            ("a".parse().unwrap(), "notes:2024".to_string()),
+        ]
+        .into();
+        save(&folder, &canvases).unwrap();
+        let written = std::fs::read_to_string(folder.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(written, "[\n  \"notes:2024:a\",\n  \"reading:b\"\n]\n");
+        assert_eq!(load(&folder).unwrap(), Some(canvases));
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn stems_drop_the_extension() {
+        assert_eq!(stem("reading.canvas"), "reading");
+        assert_eq!(stem("reading"), "reading");
+    }
+}
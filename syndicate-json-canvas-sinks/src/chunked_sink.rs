@@ -0,0 +1,259 @@
+use crate::{SinkError, SyndicationSink};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use syndicate_json_canvas_lib::{
+    DestructionPlan, PlannedCommit, PlannedWrite, StateRegistry, SyndicationFormat, SyndicationLinks, VerifyReport,
+    Warning, cmp_node_ids, jsoncanvas::NodeId,
+};
+use tracing::{debug, info, warn};
+
+/// Wraps a sink so each publish call hands it at most `chunk_size` items
+///
+/// Items are split into chunks ordered by NodeId, so membership is stable between runs.
+/// Every chunk is published with the full batch as context, keeping cross-references
+/// between chunks intact.
+pub struct ChunkedSink<S> {
+    /// The wrapped sink
+    inner: S,
+    /// Maximum number of items per call to the inner sink
+    chunk_size: usize,
+    /// Pause between consecutive chunks
+    delay: Duration,
+}
+
+impl<S: SyndicationSink> ChunkedSink<S> {
+    /// Wrap `inner`, publishing at most `chunk_size` items per call (minimum 1)
+    pub fn new(inner: S, chunk_size: usize) -> Self {
+        Self {
+            inner,
+            chunk_size: chunk_size.max(1),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Wait `delay` between chunks (skipped in dry-run mode)
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// The wrapped sink
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Split items into chunks of at most `chunk_size`, ordered by NodeId
+    pub fn chunks(&self, items: &HashMap<NodeId, SyndicationFormat>) -> Vec<HashMap<NodeId, SyndicationFormat>> {
+        let mut ids: Vec<&NodeId> = items.keys().collect();
+        ids.sort_by(|a, b| cmp_node_ids(a, b));
+
+        ids.chunks(self.chunk_size)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .map(|id| ((*id).clone(), items[*id].clone()))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl<S: SyndicationSink> SyndicationSink for ChunkedSink<S> {
+    fn begin(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        self.inner.begin(dry_run)
+    }
+
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.publish_chunk(items, items, dry_run)
+    }
+
+    fn publish_chunk(
+        &mut self,
+        items: &HashMap<NodeId, SyndicationFormat>,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+        dry_run: bool,
+    ) -> Result<(), SinkError> {
+        let chunks = self.chunks(items);
+        let total = chunks.len();
+        let mut published = 0;
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            if index > 0 && !dry_run && !self.delay.is_zero() {
+                debug!(delay_ms = self.delay.as_millis() as u64, "Waiting before next chunk");
+                std::thread::sleep(self.delay);
+            }
+
+            info!(sink = %self.inner.name(), chunk = index + 1, of = total, items = chunk.len(), "Publishing chunk");
+            if let Err(e) = self.inner.publish_chunk(chunk, all_items, dry_run) {
+                warn!(
+                    sink = %self.inner.name(),
+                    chunk = index + 1,
+                    of = total,
+                    published_before_failure = published,
+                    "Chunk failed"
+                );
+                return Err(e);
+            }
+            published += chunk.len();
+        }
+
+        Ok(())
+    }
+
+    /// Updates are delivered in one go: they revise existing posts rather than add to a feed
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.inner.update(items, dry_run)
+    }
+
+    fn supports_updates(&self) -> bool {
+        self.inner.supports_updates()
+    }
+
+    fn take_post_urls(&mut self) -> Vec<(NodeId, String)> {
+        self.inner.take_post_urls()
+    }
+
+    fn take_planned_writes(&mut self) -> Vec<PlannedWrite> {
+        self.inner.take_planned_writes()
+    }
+
+    fn take_planned_commit(&mut self) -> Option<PlannedCommit> {
+        self.inner.take_planned_commit()
+    }
+
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        self.inner.take_warnings()
+    }
+
+    fn add_syndication_links(
+        &mut self,
+        links: &SyndicationLinks,
+        dry_run: bool,
+    ) -> Result<usize, SinkError> {
+        self.inner.add_syndication_links(links, dry_run)
+    }
+
+    fn import_legacy(
+        &mut self,
+        is_tracked: &dyn Fn(&NodeId) -> bool,
+        dry_run: bool,
+    ) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        self.inner.import_legacy(is_tracked, dry_run)
+    }
+
+    fn archive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        for chunk in node_ids.chunks(self.chunk_size) {
+            self.inner.archive(chunk, dry_run)?;
+        }
+        Ok(())
+    }
+
+    fn unarchive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        for chunk in node_ids.chunks(self.chunk_size) {
+            self.inner.unarchive(chunk, dry_run)?;
+        }
+        Ok(())
+    }
+
+    fn plan_removal(&self, node_ids: &[NodeId]) -> Result<DestructionPlan, SinkError> {
+        self.inner.plan_removal(node_ids)
+    }
+
+    fn remove(&mut self, plan: &DestructionPlan, dry_run: bool) -> Result<(), SinkError> {
+        self.inner.remove(plan, dry_run)
+    }
+
+    fn commit(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        self.inner.commit(dry_run)
+    }
+
+    fn abort(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        self.inner.abort(dry_run)
+    }
+
+    fn list_published(&self) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        self.inner.list_published()
+    }
+
+    fn verify(&self) -> Result<VerifyReport, SinkError> {
+        self.inner.verify()
+    }
+
+    fn register_state(&self, registry: &mut StateRegistry) {
+        self.inner.register_state(registry)
+    }
+
+    /// The inner sink's sandbox, unchunked: chunking doesn't change the output, only pacing
+    fn sandboxed(&self, root: &Path) -> Result<Box<dyn SyndicationSink + Send>, SinkError> {
+        self.inner.sandboxed(root)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+    use syndicate_json_canvas_lib::{CanvasBuilder, default_process_node, to_syndication_format};
+
+    /// Records each chunk's node IDs and the size of its context; fails on chunk `fail_on`
+    #[derive(Default)]
+    struct Mock {
+        chunks: Vec<(Vec<String>, usize)>,
+        fail_on: Option<usize>,
+    }
+
+    impl SyndicationSink for Mock {
+        fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+            self.publish_chunk(items, items, dry_run)
+        }
+
+        fn publish_chunk(
+            &mut self,
+            items: &HashMap<NodeId, SyndicationFormat>,
+            all_items: &HashMap<NodeId, SyndicationFormat>,
+            _dry_run: bool,
+        ) -> Result<(), SinkError> {
+            if self.fail_on == Some(self.chunks.len()) {
+                return Err(SinkError::Api("payload too large".to_string()));
+            }
+            let mut ids: Vec<String> = items.keys().map(|id| id.to_string()).collect();
+            ids.sort();
+            self.chunks.push((ids, all_items.len()));
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "mock"
+        }
+    }
+
+    fn items() -> HashMap<NodeId, SyndicationFormat> {
+        let mut builder = CanvasBuilder::new();
+        for n in 1..=5 {
+            let id = format!("c4c000000000000{}", n);
+            builder = builder.text_node(id, format!("Note {}.", n)).color(PresetColor::Red);
+        }
+        to_syndication_format(&builder.build().unwrap(), Some(default_process_node)).unwrap()
+    }
+
+    #[test]
+    fn chunks_are_ordered_and_keep_the_whole_batch_as_context() {
+        let mut sink = ChunkedSink::new(Mock::default(), 2);
+        sink.publish(&items(), false).unwrap();
+        let last_digits = |ids: &[String]| ids.iter().map(|id| &id[15..]).collect::<Vec<_>>().concat();
+        let chunks: Vec<(String, usize)> = sink.inner().chunks.iter().map(|(ids, n)| (last_digits(ids), *n)).collect();
+        assert_eq!(chunks, [("12".to_string(), 5), ("34".to_string(), 5), ("5".to_string(), 5)]);
+    }
+
+    #[test]
+    fn failed_chunk_stops_the_rest() {
+        let mut sink = ChunkedSink::new(Mock { fail_on: Some(1), ..Default::default() }, 2);
+        assert!(sink.publish(&items(), false).is_err());
+        assert_eq!(sink.inner().chunks.len(), 1);
+    }
+}
@@ -0,0 +1,39 @@
+use std::path::Path;
+use std::process::{Command, ExitStatus, Output};
+
+use tracing::info;
+
+/// Runs external commands on behalf of a sink
+///
+/// Sinks that shell out (e.g. to `jj`) go through this trait so tests can substitute
+/// a scripted runner instead of spawning real processes.
+pub trait CommandRunner: Send {
+    fn run(&self, program: &str, args: &[&str], current_dir: &Path) -> std::io::Result<Output>;
+}
+
+/// Runs commands with [`std::process::Command`]
+///
+/// Programs are given without an extension; on Windows the `PATH` lookup resolves `jj` to
+/// `jj.exe`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemCommandRunner;
+
+impl CommandRunner for SystemCommandRunner {
+    fn run(&self, program: &str, args: &[&str], current_dir: &Path) -> std::io::Result<Output> {
+        Command::new(program)
+            .args(args)
+            .current_dir(current_dir)
+            .output()
+    }
+}
+
+/// Logs commands instead of running them and reports success, for simulated runs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SkippedCommandRunner;
+
+impl CommandRunner for SkippedCommandRunner {
+    fn run(&self, program: &str, args: &[&str], current_dir: &Path) -> std::io::Result<Output> {
+        info!(command = %format!("{} {}", program, args.join(" ")), dir = %current_dir.display(), "[SIMULATE] Skipping command");
+        Ok(Output { status: ExitStatus::default(), stdout: Vec::new(), stderr: Vec::new() })
+    }
+}
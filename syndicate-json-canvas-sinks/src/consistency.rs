@@ -0,0 +1,138 @@
+//! Invariants published output keeps from run to run, for checking a sink end to end
+//!
+//! Each check returns every [`Inconsistency`] it finds, so one pass lists everything that
+//! broke. Folder checks apply to the sinks writing `<slug>-<node-id>.md` posts; a sink that
+//! can list its posts ([`SyndicationSink::list_published`]) can be held to its tracker with
+//! [`check_tracker`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::tracker::SyndicationTracker;
+use syndicate_json_canvas_lib::{ChangeKind, compare_trees, read_tree};
+
+use crate::posts::PERMALINK_PREFIX;
+use crate::{SinkError, SyndicationSink};
+
+/// A broken invariant of published output
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Inconsistency {
+    /// A link in a post points to a permalink with no post behind it
+    #[error("{}: link to `{href}` resolves to no published file", .file.display())]
+    DanglingLink { file: PathBuf, href: String },
+    /// The tracker has the node as published, but the sink has no post of it
+    #[error("{sink}: `{node_id}` is published per the tracker, but has no post")]
+    MissingPost { sink: String, node_id: String },
+    /// The sink has a post of a node its tracker doesn't have as published
+    #[error("{sink}: {} is a post of `{node_id}`, which isn't in the tracker", .file.display())]
+    UntrackedPost { sink: String, node_id: String, file: PathBuf },
+    /// A run that had nothing to publish changed a file anyway
+    #[error("{}: {} by a run that should have changed nothing", .path.display(), change_verb(.kind))]
+    Changed { path: PathBuf, kind: ChangeKind },
+}
+
+fn change_verb(kind: &ChangeKind) -> &'static str {
+    match kind {
+        ChangeKind::Added => "added",
+        ChangeKind::Modified => "modified",
+        ChangeKind::Removed => "removed",
+    }
+}
+
+/// Check that every site link in the posts of `folder` leads to a post in it
+///
+/// Covers the `href`s of the `context_for_this`/`further_thinking` frontmatter and the
+/// markdown links of the body; links to other sites are left alone.
+pub fn check_links(folder: &Path) -> Result<Vec<Inconsistency>, SinkError> {
+    let mut problems = Vec::new();
+    for file in posts_in(folder)? {
+        let contents = std::fs::read_to_string(&file)?;
+        for href in site_links(&contents) {
+            let Some(name) = href.strip_prefix(PERMALINK_PREFIX) else {
+                continue;
+            };
+            if !folder.join(name).is_file() {
+                problems.push(Inconsistency::DanglingLink { file: file.clone(), href: href.to_string() });
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Check that a sink has a post of every node its tracker has as published, and no others
+///
+/// Fails with [`SinkError::Unsupported`] for sinks that can't list their posts.
+pub fn check_tracker(
+    sink: &dyn SyndicationSink,
+    tracker: &SyndicationTracker,
+) -> Result<Vec<Inconsistency>, SinkError> {
+    let posts: HashMap<NodeId, PathBuf> = sink.list_published()?.into_iter().collect();
+    let mut problems = Vec::new();
+
+    let mut published: Vec<&str> = tracker.published().collect();
+    published.sort();
+    for node_id in published {
+        if !node_id.parse::<NodeId>().is_ok_and(|node_id| posts.contains_key(&node_id)) {
+            problems.push(Inconsistency::MissingPost { sink: sink.name().to_string(), node_id: node_id.to_string() });
+        }
+    }
+
+    let mut untracked: Vec<(&NodeId, &PathBuf)> =
+        posts.iter().filter(|(node_id, _)| !tracker.is_published(node_id)).collect();
+    untracked.sort_by(|a, b| a.1.cmp(b.1));
+    for (node_id, file) in untracked {
+        problems.push(Inconsistency::UntrackedPost {
+            sink: sink.name().to_string(),
+            node_id: node_id.as_str().to_string(),
+            file: file.clone(),
+        });
+    }
+    Ok(problems)
+}
+
+/// Check that nothing under `root` changed since `before`, a [`read_tree`] of it taken
+/// before a run that had nothing new to publish
+pub fn check_unchanged(root: &Path, before: &BTreeMap<PathBuf, Vec<u8>>) -> std::io::Result<Vec<Inconsistency>> {
+    let after = read_tree(root)?;
+    Ok(compare_trees(before, &after)
+        .into_iter()
+        .map(|change| Inconsistency::Changed { path: root.join(change.path), kind: change.kind })
+        .collect())
+}
+
+/// Markdown posts directly in `folder`, sorted; hidden files (manifests) are left out
+pub(crate) fn posts_in(folder: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut posts = Vec::new();
+    if !folder.is_dir() {
+        return Ok(posts);
+    }
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        let visible = path.file_name().and_then(|n| n.to_str()).is_some_and(|n| !n.starts_with('.'));
+        if visible && path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            posts.push(path);
+        }
+    }
+    posts.sort();
+    Ok(posts)
+}
+
+/// Targets of the frontmatter `href`s and the body's `[text](target)` links of a post
+fn site_links(contents: &str) -> Vec<&str> {
+    let mut links: Vec<&str> = contents
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("href:"))
+        .map(|value| value.trim().trim_matches('"'))
+        .collect();
+    let mut rest = contents;
+    while let Some(start) = rest.find("](") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find(')') else {
+            break;
+        };
+        links.push(&rest[..end]);
+        rest = &rest[end + 1..];
+    }
+    links
+}
@@ -0,0 +1,482 @@
+use crate::{SinkError, SyndicationSink};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use syndicate_json_canvas_lib::{
+    Clock, DestructionPlan, PlannedFile, RemovalPolicy, SyndicationFormat, SystemClock, TextPolicy, copy_dir_all,
+    cmp_node_ids, duplicates::content_hash, hashtags, jsoncanvas::NodeId,
+};
+use tracing::{debug, info, warn};
+
+/// Version of the record envelope written by [`ContentLakeSink`]
+///
+/// 3 added `withdrawn_at`, set on tombstones.
+pub const CONTENT_LAKE_SCHEMA: u32 = 3;
+
+/// In- and out-neighbors of a record's node
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LakeNeighbors {
+    #[serde(default)]
+    pub incoming: Vec<String>,
+    #[serde(default)]
+    pub outgoing: Vec<String>,
+}
+
+/// One line of a content lake file
+///
+/// Fields missing from older records take their defaults and fields added by newer
+/// writers are kept in `extra`, so readers keep working across schema versions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LakeRecord {
+    pub schema: u32,
+    /// Unique ID of this record, referenced by `supersedes`
+    pub record_id: String,
+    pub node_id: String,
+    #[serde(default)]
+    pub canvas_path: Option<PathBuf>,
+    pub published_at: DateTime<Local>,
+    /// Names of the sinks the item was syndicated to
+    #[serde(default)]
+    pub sink_targets: Vec<String>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub text: String,
+    /// `#tags` found in the text, without the `#`, then `category/<name>` if categorized
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Topic of the content warning the item was published behind
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_warning: Option<String>,
+    #[serde(default)]
+    pub neighbors: LakeNeighbors,
+    /// `record_id` of the previous record of the same node, if any
+    #[serde(default)]
+    pub supersedes: Option<String>,
+    /// When the node was withdrawn, if this record is its tombstone; tombstones keep the
+    /// `published_at` of the record they supersede and have no text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withdrawn_at: Option<DateTime<Local>>,
+    /// Fields this version doesn't know about
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Reads the records of a content lake directory
+pub struct ContentLakeReader {
+    dir: PathBuf,
+}
+
+impl ContentLakeReader {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self { dir: dir.as_ref().to_path_buf() }
+    }
+
+    /// The monthly `content-lake-YYYY-MM.jsonl` files, oldest first
+    pub fn files(&self) -> Result<Vec<PathBuf>, SinkError> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("content-lake-") && n.ends_with(".jsonl"))
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// Every record in write order
+    ///
+    /// Lines that don't parse (e.g. a write cut short by a crash) are skipped with a warning.
+    pub fn records(&self) -> Result<Vec<LakeRecord>, SinkError> {
+        let mut records = Vec::new();
+        for file in self.files()? {
+            for (index, line) in std::fs::read_to_string(&file)?.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(line) {
+                    Ok(record) => records.push(record),
+                    Err(e) => warn!(file = %file.display(), line = index + 1, error = %e, "Skipping unreadable record"),
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// The newest record of every node, by node ID, tombstones included
+    pub fn latest(&self) -> Result<HashMap<String, LakeRecord>, SinkError> {
+        let mut latest = HashMap::new();
+        for record in self.records()? {
+            latest.insert(record.node_id.clone(), record);
+        }
+        Ok(latest)
+    }
+}
+
+/// Appends every published item to newline-delimited JSON files, one per month
+///
+/// Records are never rewritten: publishing a node again appends a new record that
+/// `supersedes` the previous one, and removing a node under [`RemovalPolicy::Tombstone`]
+/// appends a tombstone. Appends hold an exclusive lock on the file, so several
+/// writers can share a lake.
+pub struct ContentLakeSink {
+    /// Directory of the `content-lake-YYYY-MM.jsonl` files
+    dir: PathBuf,
+    /// Canvas the items came from, recorded in each record
+    canvas_path: Option<PathBuf>,
+    /// Sink names recorded in each record's `sink_targets`
+    sink_targets: Vec<String>,
+    /// Source of `published_at` and the month of the file
+    clock: Arc<dyn Clock>,
+    /// How record titles are cut from node text
+    text: TextPolicy,
+    /// Whether pruned nodes get a tombstone record; records can't be deleted, so under
+    /// [`RemovalPolicy::Delete`] pruning leaves the lake alone
+    removal_policy: RemovalPolicy,
+}
+
+impl ContentLakeSink {
+    /// Create a sink appending to files in `dir`, which is created on first publish
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            canvas_path: None,
+            sink_targets: Vec::new(),
+            clock: Arc::new(SystemClock),
+            text: TextPolicy::default(),
+            removal_policy: RemovalPolicy::Delete,
+        }
+    }
+
+    /// Record the canvas the items came from
+    pub fn with_canvas_path(mut self, canvas_path: impl AsRef<Path>) -> Self {
+        self.canvas_path = Some(canvas_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Record the sinks the items are syndicated to (e.g. `["jj", "twitter"]`)
+    pub fn with_sink_targets(mut self, sink_targets: Vec<String>) -> Self {
+        self.sink_targets = sink_targets;
+        self
+    }
+
+    /// Use a custom clock for `published_at` and monthly rollover
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Cut record titles with a shared text policy
+    pub fn with_text_policy(mut self, text: TextPolicy) -> Self {
+        self.text = text;
+        self
+    }
+
+    /// Append a tombstone record for each pruned node
+    pub fn with_removal_policy(mut self, policy: RemovalPolicy) -> Self {
+        self.removal_policy = policy;
+        self
+    }
+
+    /// A reader over this sink's files
+    pub fn reader(&self) -> ContentLakeReader {
+        ContentLakeReader::new(&self.dir)
+    }
+
+    /// `#tags` in the text, without the `#`, and the item's category as `category/<name>`
+    fn tags(item: &SyndicationFormat) -> Vec<String> {
+        let mut tags = hashtags(&item.text);
+        if let Some(category) = &item.category {
+            tags.push(format!("category/{}", category));
+        }
+        tags
+    }
+
+    /// The file records are appended to this month
+    fn current_file(&self) -> PathBuf {
+        self.dir.join(format!("content-lake-{}.jsonl", self.clock.now().format("%Y-%m")))
+    }
+
+    /// Append lines to a file under an exclusive lock, in one write
+    fn append(path: &Path, lines: &str) -> Result<(), SinkError> {
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        file.lock()?;
+        let written = file.write_all(lines.as_bytes()).and_then(|()| file.sync_data());
+        file.unlock()?;
+        written?;
+        Ok(())
+    }
+}
+
+impl SyndicationSink for ContentLakeSink {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        info!(item_count = items.len(), "Appending to content lake");
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let latest = self.reader().latest()?;
+        let now = self.clock.now();
+        let path = self.current_file();
+
+        let mut ordered: Vec<&SyndicationFormat> = items.values().collect();
+        ordered.sort_by(|a, b| cmp_node_ids(&a.id, &b.id));
+
+        let mut lines = String::new();
+        for item in ordered {
+            let record = LakeRecord {
+                schema: CONTENT_LAKE_SCHEMA,
+                record_id: uuid::Uuid::new_v4().to_string(),
+                node_id: item.id.as_str().to_string(),
+                canvas_path: self.canvas_path.clone(),
+                published_at: now,
+                sink_targets: self.sink_targets.clone(),
+                title: self.text.title(&item.text),
+                text: item.text.clone(),
+                tags: Self::tags(item),
+                content_warning: item.content_warning.clone(),
+                neighbors: LakeNeighbors {
+                    incoming: item.in_neighbor_ids.iter().map(|id| id.as_str().to_string()).collect(),
+                    outgoing: item.out_neighbor_ids.iter().map(|id| id.as_str().to_string()).collect(),
+                },
+                supersedes: latest.get(item.id.as_str()).map(|prior| prior.record_id.clone()),
+                withdrawn_at: None,
+                extra: serde_json::Map::new(),
+            };
+            let line = serde_json::to_string(&record).map_err(|e| SinkError::Serialization(e.to_string()))?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+
+        if dry_run {
+            debug!(file = %path.display(), records = %lines, "[DRY RUN] Would append records");
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.dir)?;
+        Self::append(&path, &lines)?;
+        info!(file = %path.display(), count = items.len(), "Appended records");
+        Ok(())
+    }
+
+    /// An edited item gets a new record superseding its previous one, like a republish
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.publish(items, dry_run)
+    }
+
+    fn supports_updates(&self) -> bool {
+        true
+    }
+
+    /// Nodes whose newest record isn't a tombstone, with the file holding that record
+    fn list_published(&self) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        let mut published = HashMap::new();
+        let reader = self.reader();
+        for file in reader.files()? {
+            for line in std::fs::read_to_string(&file)?.lines() {
+                if let Ok(record) = serde_json::from_str::<LakeRecord>(line)
+                    && let Ok(node_id) = record.node_id.parse::<NodeId>()
+                {
+                    published.insert(node_id, (file.clone(), record.withdrawn_at.is_some()));
+                }
+            }
+        }
+        Ok(published
+            .into_iter()
+            .filter(|(_, (_, withdrawn))| !withdrawn)
+            .map(|(node_id, (file, _))| (node_id, file))
+            .collect())
+    }
+
+    /// The newest record of each node, to be superseded by a tombstone
+    ///
+    /// Only under [`RemovalPolicy::Tombstone`]; records can't be deleted otherwise.
+    fn plan_removal(&self, node_ids: &[NodeId]) -> Result<DestructionPlan, SinkError> {
+        if self.removal_policy != RemovalPolicy::Tombstone {
+            return Err(SinkError::Unsupported(format!("{} is append-only and only leaves tombstones", self.name())));
+        }
+        let latest = self.reader().latest()?;
+        let mut plan = DestructionPlan::new(self.name()).with_policy(RemovalPolicy::Tombstone);
+        for node_id in node_ids {
+            let Some(record) = latest.get(node_id.as_str()).filter(|r| r.withdrawn_at.is_none()) else {
+                continue;
+            };
+            plan.files.push(PlannedFile {
+                node_id: node_id.clone(),
+                path: self.current_file(),
+                title: Some(record.title.clone()).filter(|title| !title.is_empty()),
+                content_hash: content_hash(&record.record_id),
+            });
+        }
+        Ok(plan)
+    }
+
+    /// Append a tombstone superseding the newest record of each planned node
+    fn remove(&mut self, plan: &DestructionPlan, dry_run: bool) -> Result<(), SinkError> {
+        if plan.files.is_empty() {
+            return Ok(());
+        }
+        let latest = self.reader().latest()?;
+        let now = self.clock.now();
+        let path = self.current_file();
+
+        let mut lines = String::new();
+        for file in &plan.files {
+            let Some(prior) = latest.get(file.node_id.as_str()) else {
+                continue;
+            };
+            let record = LakeRecord {
+                schema: CONTENT_LAKE_SCHEMA,
+                record_id: uuid::Uuid::new_v4().to_string(),
+                node_id: prior.node_id.clone(),
+                canvas_path: prior.canvas_path.clone(),
+                published_at: prior.published_at,
+                sink_targets: prior.sink_targets.clone(),
+                title: String::new(),
+                text: String::new(),
+                tags: Vec::new(),
+                content_warning: None,
+                neighbors: LakeNeighbors::default(),
+                supersedes: Some(prior.record_id.clone()),
+                withdrawn_at: Some(now),
+                extra: serde_json::Map::new(),
+            };
+            let line = serde_json::to_string(&record).map_err(|e| SinkError::Serialization(e.to_string()))?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+
+        if dry_run {
+            debug!(file = %path.display(), records = %lines, "[DRY RUN] Would append tombstones");
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.dir)?;
+        Self::append(&path, &lines)?;
+        info!(file = %path.display(), count = plan.files.len(), "Appended tombstones");
+        Ok(())
+    }
+
+    fn sandboxed(&self, root: &Path) -> Result<Box<dyn SyndicationSink + Send>, SinkError> {
+        copy_dir_all(&self.dir, root)?;
+        Ok(Box::new(Self {
+            dir: root.to_path_buf(),
+            canvas_path: self.canvas_path.clone(),
+            sink_targets: self.sink_targets.clone(),
+            clock: self.clock.clone(),
+            text: self.text.clone(),
+            removal_policy: self.removal_policy,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        "content-lake"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+    use syndicate_json_canvas_lib::{CanvasBuilder, MockClock, default_process_node, to_syndication_format};
+
+    const NOTE: &str = "1a4e000000000001";
+
+    fn items_with(text: &str) -> HashMap<NodeId, SyndicationFormat> {
+        let canvas = CanvasBuilder::new().text_node(NOTE, text).color(PresetColor::Red).build().unwrap();
+        to_syndication_format(&canvas, Some(default_process_node)).unwrap()
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-lake-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn republishing_across_a_month_boundary_rolls_over_and_supersedes() {
+        let dir = scratch("rollover");
+        let clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 1, 31, 23, 59, 0).unwrap()));
+        let mut sink = ContentLakeSink::new(&dir).with_clock(clock.clone());
+        sink.publish(&items_with("First draft"), false).unwrap();
+        clock.advance(chrono::Duration::minutes(2));
+        sink.update(&items_with("Second draft"), false).unwrap();
+
+        let files: Vec<String> = sink
+            .reader()
+            .files()
+            .unwrap()
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(files, ["content-lake-2026-01.jsonl", "content-lake-2026-02.jsonl"]);
+        let records = sink.reader().records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].supersedes, None);
+        assert_eq!(records[1].supersedes.as_deref(), Some(records[0].record_id.as_str()));
+        assert_eq!(sink.reader().latest().unwrap()[NOTE].text, "Second draft");
+        // The January file is never rewritten
+        assert!(std::fs::read_to_string(dir.join(&files[0])).unwrap().contains("First draft"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_appends_keep_every_record_whole() {
+        let dir = scratch("concurrent");
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let dir = dir.clone();
+                std::thread::spawn(move || {
+                    let mut sink = ContentLakeSink::new(&dir);
+                    for run in 0..10 {
+                        let text = format!("Writer {} run {} {}", writer, run, "padding ".repeat(500));
+                        sink.publish(&items_with(&text), false).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let lines: usize = ContentLakeReader::new(&dir)
+            .files()
+            .unwrap()
+            .iter()
+            .map(|file| std::fs::read_to_string(file).unwrap().lines().count())
+            .sum();
+        assert_eq!(lines, 80);
+        assert_eq!(ContentLakeReader::new(&dir).records().unwrap().len(), 80);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn records_from_other_schema_versions_are_read() {
+        let dir = scratch("schemas");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = concat!(
+            r#"{"schema":1,"record_id":"a","node_id":"1a4e000000000001","published_at":"2025-01-01T00:00:00Z"}"#,
+            "\n",
+            r#"{"schema":9,"record_id":"b","node_id":"1a4e000000000001","published_at":"2030-01-01T00:00:00Z","#,
+            r#""supersedes":"a","mood":"hopeful"}"#,
+            "\n",
+        );
+        std::fs::write(dir.join("content-lake-2025-01.jsonl"), file).unwrap();
+
+        let records = ContentLakeReader::new(&dir).records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].text.is_empty() && records[0].tags.is_empty());
+        assert_eq!(records[1].supersedes.as_deref(), Some("a"));
+        assert_eq!(records[1].extra["mood"], "hopeful");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+use crate::filename::FilenameTemplate;
+use crate::posts::PostWriter;
+use crate::{SinkError, SyndicationSink};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use syndicate_json_canvas_lib::{
+    Clock, DestructionPlan, HtmlPolicy, PlannedWrite, RemovalPolicy, SyndicationFormat, SyndicationLinks, TextPolicy, VerifyReport, Warning, jsoncanvas::NodeId,
+};
+use tracing::info;
+
+/// Writes markdown files into a plain directory
+///
+/// Produces the same `<slug>-<node-id>.md` files as [`crate::JjRepositorySink`] without any
+/// version control, for local previews and static site builds that pick files up directly.
+pub struct DirectorySink {
+    /// Writes the files into the directory
+    posts: PostWriter,
+}
+
+impl DirectorySink {
+    /// Create a sink writing into `folder_path`, which is created on first publish
+    pub fn new(folder_path: impl AsRef<Path>) -> Self {
+        Self {
+            posts: PostWriter::new(folder_path.as_ref().to_path_buf()),
+        }
+    }
+
+    /// Emit footnote references as a `references:` frontmatter list
+    pub fn with_reference_frontmatter(mut self, enabled: bool) -> Self {
+        self.posts.reference_frontmatter = enabled;
+        self
+    }
+
+    /// Add the `weight` of the edge, and its `emphasis` label if mapped, to each
+    /// `context_for_this`/`further_thinking` entry (see [`syndicate_json_canvas_lib::EdgeWeights`])
+    pub fn with_link_weights(mut self, enabled: bool) -> Self {
+        self.posts.link_weights = enabled;
+        self
+    }
+
+    /// Add `canvas`, `node_id` and `canvas_hash` frontmatter to each post, naming the canvas
+    /// file, node and canvas version it was published from; posts are then matched to their
+    /// nodes by `node_id` rather than by file name
+    pub fn with_provenance(mut self, enabled: bool) -> Self {
+        self.posts.provenance = enabled;
+        self
+    }
+
+    /// Add `noindex: true` alongside `archived: true` when archiving
+    pub fn with_noindex_archived(mut self, enabled: bool) -> Self {
+        self.posts.noindex_archived = enabled;
+        self
+    }
+
+    /// Leave a `withdrawn: true` stub in place of each pruned post instead of deleting it
+    /// (default [`RemovalPolicy::Delete`])
+    pub fn with_removal_policy(mut self, policy: RemovalPolicy) -> Self {
+        self.posts.removal_policy = policy;
+        self
+    }
+
+    /// Use a custom clock for the `date` frontmatter
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.posts.clock = clock;
+        self
+    }
+
+    /// Set how raw HTML in node text is treated before writing files
+    pub fn with_html_policy(mut self, html_policy: HtmlPolicy) -> Self {
+        self.posts.html_policy = html_policy;
+        self
+    }
+
+    /// Name files with a template instead of `{slug}-{id}.md`
+    ///
+    /// With any other template, files get a `node_id` frontmatter field so they can be
+    /// matched back to their nodes.
+    pub fn with_filename_template(mut self, filename_template: FilenameTemplate) -> Self {
+        self.posts.filename_template = filename_template;
+        self
+    }
+
+    /// Cut titles, slugs and commit message previews with a shared text policy
+    ///
+    /// Posts that already exist keep their file names.
+    pub fn with_text_policy(mut self, text: TextPolicy) -> Self {
+        self.posts.text = text;
+        self
+    }
+
+    /// Lines of diff kept per modified file when comparing dry runs with the published
+    /// files (default [`syndicate_json_canvas_lib::DEFAULT_PREVIEW_DIFF_LINES`])
+    pub fn with_preview_diff_lines(mut self, lines: usize) -> Self {
+        self.posts.preview_diff_lines = lines;
+        self
+    }
+
+    /// Keep a page per tag in a `tags` subfolder, listing the posts with the tag newest
+    /// first, and a `tags/index` page listing the tags with their counts
+    ///
+    /// The pages are rebuilt from the posts' `tags` frontmatter at the end of every batch
+    /// that touches the folder, and a tag's page goes once no post has the tag. For sites
+    /// whose generator doesn't build tag pages itself.
+    pub fn with_tag_pages(mut self, enabled: bool) -> Self {
+        self.posts.tag_pages = enabled;
+        self
+    }
+
+    /// The directory files are written to
+    pub fn folder(&self) -> &Path {
+        &self.posts.folder
+    }
+}
+
+impl SyndicationSink for DirectorySink {
+    /// Rebuild the tag pages, if kept, once the batch's posts are all written
+    fn commit(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        self.posts.write_tag_pages(dry_run)
+    }
+
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.publish_chunk(items, items, dry_run)
+    }
+
+    fn publish_chunk(
+        &mut self,
+        items: &HashMap<NodeId, SyndicationFormat>,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+        dry_run: bool,
+    ) -> Result<(), SinkError> {
+        info!(item_count = items.len(), folder = %self.posts.folder.display(), "Publishing to directory");
+        self.posts.write_posts(items, all_items, dry_run)?;
+        Ok(())
+    }
+
+    fn take_planned_writes(&mut self) -> Vec<PlannedWrite> {
+        self.posts.take_planned_writes()
+    }
+
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        self.posts.take_warnings()
+    }
+
+    fn archive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        self.posts.change_archive_state(node_ids, true, dry_run)?;
+        Ok(())
+    }
+
+    fn unarchive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        self.posts.change_archive_state(node_ids, false, dry_run)?;
+        Ok(())
+    }
+
+    fn plan_removal(&self, node_ids: &[NodeId]) -> Result<DestructionPlan, SinkError> {
+        self.posts.plan_removal(self.name(), node_ids)
+    }
+
+    fn remove(&mut self, plan: &DestructionPlan, dry_run: bool) -> Result<(), SinkError> {
+        self.posts.remove_files(plan, dry_run)?;
+        Ok(())
+    }
+
+    fn add_syndication_links(
+        &mut self,
+        links: &SyndicationLinks,
+        dry_run: bool,
+    ) -> Result<usize, SinkError> {
+        self.posts.add_syndication_links(links, dry_run)
+    }
+
+    fn import_legacy(
+        &mut self,
+        is_tracked: &dyn Fn(&NodeId) -> bool,
+        dry_run: bool,
+    ) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        Ok(self.posts.import_legacy(is_tracked, dry_run)?.0)
+    }
+
+    fn list_published(&self) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        self.posts.list_published()
+    }
+
+    fn verify(&self) -> Result<VerifyReport, SinkError> {
+        self.posts.verify(self.name())
+    }
+
+    fn sandboxed(&self, root: &Path) -> Result<Box<dyn SyndicationSink + Send>, SinkError> {
+        Ok(Box::new(Self { posts: self.posts.sandboxed(root)? }))
+    }
+
+    /// The folder exists or can be created
+    fn preflight(&self) -> Result<(), SinkError> {
+        self.posts.preflight()
+    }
+
+    fn name(&self) -> &str {
+        "directory"
+    }
+}
@@ -0,0 +1,326 @@
+use crate::{SinkError, SyndicationSink};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use syndicate_json_canvas_lib::{ItemChange, SyndicationFormat, cmp_node_ids, idempotency::key_of, jsoncanvas::NodeId};
+use tracing::{debug, info, warn};
+
+/// How long a script may take over one batch by default
+pub const DEFAULT_EXEC_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a running script is checked for having exited
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// One line of the script's stdin: the item with how it changed and its idempotency key
+#[derive(Serialize)]
+struct ExecInput<'a> {
+    /// `new` or `updated`
+    change: &'static str,
+    idempotency_key: String,
+    #[serde(flatten)]
+    item: &'a SyndicationFormat,
+}
+
+/// Whether the script delivered an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecStatus {
+    Ok,
+    Error,
+}
+
+/// One line of the script's stdout, about the item of `node_id`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecResult {
+    pub node_id: String,
+    pub status: ExecStatus,
+    /// Where the item was posted, for the run report and `syndication:` links
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// ID the destination gave the post
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Why delivery failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Publishes by running a user-provided script, for destinations without a sink of their own
+///
+/// For every batch the command is run once with the items written to its stdin as JSON
+/// Lines, each a [`SyndicationFormat`] with `change` and `idempotency_key` added. It answers
+/// with one [`ExecResult`] line per item on stdout. The batch fails if any item failed or got
+/// no result, if the script exits non-zero, or if it outlives the timeout, in which case it
+/// is killed. Failed batches are retried with the same idempotency keys, so the script can
+/// skip items it already delivered.
+///
+/// `{sink}`, `{canvas}` and `{dry_run}` in the arguments are replaced by the sink's name,
+/// the canvas path and `1` or `0`. The same values are in the `SYNDICATE_SINK`,
+/// `SYNDICATE_CANVAS` and `SYNDICATE_DRY_RUN` environment variables. Dry runs run the
+/// script too; it's up to it not to publish when `SYNDICATE_DRY_RUN` is `1`.
+pub struct ExecSink {
+    name: String,
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+    canvas_path: Option<PathBuf>,
+    /// Further environment variables set for the script
+    env: Vec<(String, String)>,
+    /// Where the items of the last batches ended up, until taken for the run report
+    post_urls: Vec<(NodeId, String)>,
+}
+
+impl ExecSink {
+    /// Create a sink called `name` running `program` with `args`
+    pub fn new(name: impl Into<String>, program: impl Into<String>, args: &[&str]) -> Self {
+        Self {
+            name: name.into(),
+            program: program.into(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            timeout: DEFAULT_EXEC_TIMEOUT,
+            canvas_path: None,
+            env: Vec::new(),
+            post_urls: Vec::new(),
+        }
+    }
+
+    /// Kill the script if a batch takes longer (default [`DEFAULT_EXEC_TIMEOUT`])
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Canvas path handed to the script; empty unless set
+    pub fn with_canvas_path(mut self, canvas_path: impl Into<PathBuf>) -> Self {
+        self.canvas_path = Some(canvas_path.into());
+        self
+    }
+
+    /// Set an environment variable for the script, next to the `SYNDICATE_*` ones
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Hand items to the script and return its result for each, in the order given
+    ///
+    /// Fails only if the script couldn't be run, exited non-zero, timed out or wrote
+    /// something other than results; items it didn't answer for get an error result.
+    pub fn run(
+        &self,
+        items: &[&SyndicationFormat],
+        change: ItemChange,
+        dry_run: bool,
+    ) -> Result<Vec<ExecResult>, SinkError> {
+        let step = match change {
+            ItemChange::Updated => "update",
+            _ => "publish",
+        };
+        let mut input = String::new();
+        for item in items {
+            let line = ExecInput { change: change.as_str(), idempotency_key: key_of(item, &self.name), item };
+            input.push_str(&serde_json::to_string(&line).map_err(|e| SinkError::Serialization(e.to_string()))?);
+            input.push('\n');
+        }
+
+        let canvas = self.canvas_path.as_ref().map(|path| path.display().to_string()).unwrap_or_default();
+        let dry_run_flag = if dry_run { "1" } else { "0" };
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| {
+                arg.replace("{sink}", &self.name).replace("{canvas}", &canvas).replace("{dry_run}", dry_run_flag)
+            })
+            .collect();
+        let failure = |exit_code, stdout: String, stderr: String| SinkError::CommandFailed {
+            step,
+            program: self.program.clone(),
+            args: args.clone(),
+            exit_code,
+            stdout,
+            stderr,
+        };
+
+        let command = format!("{} {}", self.program, args.join(" "));
+        debug!(sink = %self.name, command = %command, items = items.len(), "Running script");
+        let mut command = Command::new(&self.program);
+        command
+            .args(&args)
+            .env("SYNDICATE_SINK", &self.name)
+            .env("SYNDICATE_CANVAS", &canvas)
+            .env("SYNDICATE_DRY_RUN", dry_run_flag)
+            .envs(self.env.iter().map(|(key, value)| (key, value)));
+        let finished = run_with_input(&mut command, input, self.timeout)
+            .map_err(|e| failure(None, String::new(), format!("Failed to execute {}: {}", self.program, e)))?;
+        let Some(Finished { status, stdout, stderr }) = finished else {
+            warn!(sink = %self.name, timeout_ms = self.timeout.as_millis() as u64, "Script timed out, killed");
+            let message = format!("timed out after {} ms and was killed", self.timeout.as_millis());
+            return Err(failure(None, String::new(), message));
+        };
+        if !status.success() {
+            return Err(failure(status.code(), stdout, stderr));
+        }
+
+        let mut answered: HashMap<String, ExecResult> = HashMap::new();
+        for line in stdout.lines().filter(|line| !line.trim().is_empty()) {
+            let result: ExecResult = serde_json::from_str(line)
+                .map_err(|e| SinkError::Serialization(format!("{} printed `{}`: {}", self.program, line, e)))?;
+            answered.insert(result.node_id.clone(), result);
+        }
+        let results = items
+            .iter()
+            .map(|item| {
+                answered.remove(item.id.as_str()).unwrap_or_else(|| ExecResult {
+                    node_id: item.id.to_string(),
+                    status: ExecStatus::Error,
+                    url: None,
+                    id: None,
+                    error: Some("no result from the script".to_string()),
+                })
+            })
+            .collect();
+        for node_id in answered.keys() {
+            warn!(sink = %self.name, node_id = %node_id, "Script answered for an item it wasn't given");
+        }
+        Ok(results)
+    }
+
+    /// Run a batch, keeping the URLs of delivered items and failing if any item failed
+    fn deliver(
+        &mut self,
+        items: &HashMap<NodeId, SyndicationFormat>,
+        change: ItemChange,
+        dry_run: bool,
+    ) -> Result<(), SinkError> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        let mut batch: Vec<&SyndicationFormat> = items.values().collect();
+        batch.sort_by(|a, b| cmp_node_ids(&a.id, &b.id));
+
+        let results = self.run(&batch, change, dry_run)?;
+        let mut failed = Vec::new();
+        for (item, result) in batch.iter().zip(&results) {
+            match result.status {
+                ExecStatus::Ok => {
+                    info!(sink = %self.name, node_id = %item.id, url = ?result.url, "Script delivered item");
+                    if let Some(url) = &result.url {
+                        self.post_urls.push((item.id.clone(), url.clone()));
+                    }
+                }
+                ExecStatus::Error => {
+                    let reason = result.error.as_deref().unwrap_or("no reason given");
+                    warn!(sink = %self.name, node_id = %item.id, reason, "Script failed to deliver item");
+                    failed.push((item.id.clone(), reason.to_string()));
+                }
+            }
+        }
+
+        match failed.as_slice() {
+            [] => Ok(()),
+            [(node_id, reason)] => Err(SinkError::Api(reason.clone()).for_item(node_id)),
+            _ => {
+                let reasons: Vec<String> =
+                    failed.iter().map(|(node_id, reason)| format!("{}: {}", node_id, reason)).collect();
+                let message = format!("{} of {} items failed ({})", failed.len(), batch.len(), reasons.join("; "));
+                Err(SinkError::Api(message))
+            }
+        }
+    }
+}
+
+/// What a program printed before it exited
+pub(crate) struct Finished {
+    pub(crate) status: ExitStatus,
+    pub(crate) stdout: String,
+    pub(crate) stderr: String,
+}
+
+/// Run `command` with `input` on its stdin until it exits; None if it outlived `timeout`
+/// and was killed
+pub(crate) fn run_with_input(command: &mut Command, input: String, timeout: Duration) -> io::Result<Option<Finished>> {
+    let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+
+    // Written and read on threads, so a program filling one pipe while we block on the
+    // other can't deadlock, and a hung one is still killed on time
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let stdout = read_all(child.stdout.take().expect("stdout is piped"));
+    let stderr = read_all(child.stderr.take().expect("stderr is piped"));
+
+    let Some(status) = wait_timeout(&mut child, timeout)? else {
+        return Ok(None);
+    };
+    // A program that exits without reading all of its input closes the pipe; that's its call
+    let _ = writer.join();
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    Ok(Some(Finished { status, stdout, stderr }))
+}
+
+/// Read a pipe to its end on a thread of its own
+fn read_all(mut pipe: impl Read + Send + 'static) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+/// Wait for a child to exit, killing it if it takes longer than `timeout`; None if killed
+fn wait_timeout(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+impl SyndicationSink for ExecSink {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.deliver(items, ItemChange::New, dry_run)
+    }
+
+    /// The script gets edited items with `change` set to `updated`
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.deliver(items, ItemChange::Updated, dry_run)
+    }
+
+    fn supports_updates(&self) -> bool {
+        true
+    }
+
+    /// The program exists, as a path or on `PATH`
+    fn preflight(&self) -> Result<(), SinkError> {
+        let program = Path::new(&self.program);
+        let found = if program.components().count() > 1 {
+            program.is_file()
+        } else {
+            std::env::var_os("PATH")
+                .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        };
+        if !found {
+            return Err(SinkError::Config(format!("program `{}` not found", self.program)));
+        }
+        Ok(())
+    }
+
+    fn take_post_urls(&mut self) -> Vec<(NodeId, String)> {
+        std::mem::take(&mut self.post_urls)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+
+/// Longest file name [`FilenameTemplate::render`] produces, in bytes
+///
+/// Below the 255-byte limit of common filesystems, leaving room for editors' temp suffixes.
+pub const MAX_FILENAME_BYTES: usize = 200;
+
+/// Names Windows reserves regardless of extension
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Characters not allowed in file names on at least one supported platform
+const UNSAFE_CHARS: &[char] = &['/', '\\', '<', '>', ':', '"', '|', '?', '*'];
+
+/// Error types for filename templates and the names they render
+#[derive(Debug, thiserror::Error)]
+pub enum FilenameError {
+    #[error("Unknown token `{{{token}}}` in filename template `{template}` (expected {{slug}}, {{id}}, {{short_id}}, {{date}} or {{title}})")]
+    UnknownToken { template: String, token: String },
+
+    #[error("Unclosed `{{` in filename template `{0}`")]
+    Unclosed(String),
+
+    #[error("Invalid extension `{0}`: expected letters and digits only, like `md`")]
+    InvalidExtension(String),
+
+    #[error("File name `{name}` is not safe to write: {reason}")]
+    Unsafe { name: String, reason: String },
+}
+
+/// Values substituted for the tokens of a [`FilenameTemplate`]
+#[derive(Debug, Clone, Copy)]
+pub struct FilenameParts<'a> {
+    /// `{slug}`: slug of the item's text
+    pub slug: &'a str,
+    /// `{id}`: canvas node ID
+    pub id: &'a str,
+    /// `{short_id}`: the node ID cut short, as long as it stays unique among the items
+    pub short_id: &'a str,
+    /// `{date}`: publish date, `YYYY-MM-DD`
+    pub date: &'a str,
+    /// `{title}`: the item's title (its first words)
+    pub title: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Slug,
+    Id,
+    ShortId,
+    Date,
+    Title,
+}
+
+/// How the file of a post is named, e.g. `{slug}-{id}` (the default) or `{date}-{slug}`
+///
+/// Tokens are `{slug}`, `{id}`, `{short_id}`, `{date}` and `{title}`; everything else is copied
+/// as is.
+/// The extension (`md` by default) is appended after rendering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilenameTemplate {
+    template: String,
+    segments: Vec<Segment>,
+    extension: String,
+}
+
+impl Default for FilenameTemplate {
+    /// `{slug}-{id}.md`
+    fn default() -> Self {
+        Self::parse("{slug}-{id}").expect("default filename template is valid")
+    }
+}
+
+impl fmt::Display for FilenameTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.template, self.extension)
+    }
+}
+
+impl FilenameTemplate {
+    /// Parse a template, without extension
+    pub fn parse(template: &str) -> Result<Self, FilenameError> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..].find('}').ok_or_else(|| FilenameError::Unclosed(template.to_string()))?;
+            let token = &rest[start + 1..start + end];
+            segments.push(match token {
+                "slug" => Segment::Slug,
+                "id" => Segment::Id,
+                "short_id" => Segment::ShortId,
+                "date" => Segment::Date,
+                "title" => Segment::Title,
+                _ => {
+                    return Err(FilenameError::UnknownToken {
+                        template: template.to_string(),
+                        token: token.to_string(),
+                    });
+                }
+            });
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+
+        Ok(Self { template: template.to_string(), segments, extension: "md".to_string() })
+    }
+
+    /// Use another extension than `md` (without the dot)
+    pub fn with_extension(mut self, extension: &str) -> Result<Self, FilenameError> {
+        let extension = extension.trim_start_matches('.');
+        if extension.is_empty() || !extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(FilenameError::InvalidExtension(extension.to_string()));
+        }
+        self.extension = extension.to_string();
+        Ok(self)
+    }
+
+    /// The extension, without the dot
+    pub fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    /// Whether the rendered name contains the node ID, so a node's file can be found by name
+    pub fn includes_id(&self) -> bool {
+        self.segments.contains(&Segment::Id)
+    }
+
+    /// Render a file name, checking that it is safe to write on every platform
+    ///
+    /// `{title}` has the characters that are never allowed in file names removed; every
+    /// other part must already be safe.
+    pub fn render(&self, parts: FilenameParts<'_>) -> Result<String, FilenameError> {
+        let mut stem = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => stem.push_str(literal),
+                Segment::Slug => stem.push_str(parts.slug),
+                Segment::Id => stem.push_str(parts.id),
+                Segment::ShortId => stem.push_str(parts.short_id),
+                Segment::Date => stem.push_str(parts.date),
+                Segment::Title => stem.extend(parts.title.chars().filter(|c| !UNSAFE_CHARS.contains(c))),
+            }
+        }
+        let name = format!("{}.{}", stem.trim(), self.extension);
+        check_safe(&name)?;
+        Ok(name)
+    }
+}
+
+/// Reject names with separators, reserved characters or names, or over the length cap
+fn check_safe(name: &str) -> Result<(), FilenameError> {
+    let unsafe_name = |reason: String| FilenameError::Unsafe { name: name.to_string(), reason };
+
+    if let Some(c) = name.chars().find(|c| UNSAFE_CHARS.contains(c) || c.is_control()) {
+        return Err(unsafe_name(format!("contains `{}`", c.escape_default())));
+    }
+    let stem = name.split('.').next().unwrap_or_default();
+    if stem.is_empty() {
+        return Err(unsafe_name("the name before the extension is empty".to_string()));
+    }
+    if WINDOWS_RESERVED.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem.trim_end())) {
+        return Err(unsafe_name(format!("`{}` is a reserved name on Windows", stem)));
+    }
+    if name.len() > MAX_FILENAME_BYTES {
+        return Err(unsafe_name(format!("longer than {} bytes", MAX_FILENAME_BYTES)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARTS: FilenameParts<'static> = FilenameParts {
+        slug: "a-short-note",
+        id: "f11e000000000001",
+        short_id: "f11e",
+        date: "2026-03-01",
+        title: "A short note: why?",
+    };
+
+    fn render(template: &str) -> Result<String, FilenameError> {
+        FilenameTemplate::parse(template)?.render(PARTS)
+    }
+
+    #[test]
+    fn every_token_renders() {
+        assert_eq!(FilenameTemplate::default().render(PARTS).unwrap(), "a-short-note-f11e000000000001.md");
+        assert_eq!(render("{date}-{slug}").unwrap(), "2026-03-01-a-short-note.md");
+        assert_eq!(render("{id}").unwrap(), "f11e000000000001.md");
+        assert_eq!(render("note-{short_id}").unwrap(), "note-f11e.md");
+        assert_eq!(render("{title}").unwrap(), "A short note why.md");
+        let html = FilenameTemplate::parse("{id}").unwrap().with_extension(".html").unwrap();
+        assert_eq!(html.render(PARTS).unwrap(), "f11e000000000001.html");
+        assert!(FilenameTemplate::parse("{id}").unwrap().includes_id());
+        assert!(!FilenameTemplate::parse("{date}-{slug}").unwrap().includes_id());
+    }
+
+    #[test]
+    fn invalid_templates_and_names_are_rejected() {
+        let error = render("{slug}-{uuid}").unwrap_err();
+        assert!(matches!(&error, FilenameError::UnknownToken { token, .. } if token == "uuid"), "{}", error);
+        assert!(error.to_string().contains("{uuid}"), "{}", error);
+        assert!(matches!(render("{slug"), Err(FilenameError::Unclosed(_))));
+        assert!(FilenameTemplate::default().with_extension("m/d").is_err());
+
+        assert!(matches!(render("posts/{slug}"), Err(FilenameError::Unsafe { .. })));
+        assert!(matches!(render("con"), Err(FilenameError::Unsafe { .. })));
+        assert!(matches!(render(&"x".repeat(MAX_FILENAME_BYTES)), Err(FilenameError::Unsafe { .. })));
+    }
+}
@@ -0,0 +1,319 @@
+//! A validating reader for the YAML frontmatter of published posts
+//!
+//! Covers the subset the post writer emits and people commonly hand-edit: top-level
+//! `key: value` pairs, block lists of scalars or small maps under a `key:` line, and
+//! `|`/`>` block scalars. Anything outside it is reported as an error rather than guessed at.
+
+/// Top-level keys of a post's frontmatter, with scalar values unquoted and unescaped
+///
+/// Keys introducing a list have an empty value.
+pub(crate) fn parse(contents: &str) -> Result<Vec<(String, String)>, String> {
+    let mut lines = contents.lines().enumerate();
+    match lines.next() {
+        Some((_, "---")) => {}
+        _ => return Err("file doesn't start with a `---` line".to_string()),
+    }
+
+    let mut keys: Vec<(String, String)> = Vec::new();
+    // Whether the previous top-level key opened a block (`key:` with no value)
+    let mut in_block = false;
+    // Whether it opened a block scalar (`key: |`), whose lines are taken verbatim
+    let mut in_block_scalar = false;
+    for (index, line) in lines {
+        let line_number = index + 1;
+        let fail = |reason: &str| Err(format!("line {}: {}", line_number, reason));
+
+        if line == "---" {
+            return Ok(keys);
+        }
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if line.starts_with('\t') {
+            return fail("tabs can't be used for indentation");
+        }
+
+        if line.starts_with(' ') {
+            if in_block_scalar {
+                continue;
+            }
+            if !in_block {
+                return fail("indented line outside a list or map");
+            }
+            let entry = line.trim_start();
+            let entry = entry.strip_prefix("- ").or_else(|| (entry == "-").then_some("")).unwrap_or(entry);
+            if let Err(reason) = parse_entry(entry) {
+                return fail(&reason);
+            }
+            continue;
+        }
+
+        let Some((key, value)) = split_key(line) else {
+            return fail("expected `key: value`");
+        };
+        if keys.iter().any(|(k, _)| k == key) {
+            return fail(&format!("duplicate key `{}`", key));
+        }
+        in_block = value.is_empty();
+        in_block_scalar = matches!(value, "|" | "|-" | "|+" | ">" | ">-" | ">+");
+        if in_block_scalar {
+            keys.push((key.to_string(), String::new()));
+            continue;
+        }
+        match parse_scalar(value) {
+            Ok(value) => keys.push((key.to_string(), value)),
+            Err(reason) => return fail(&reason),
+        }
+    }
+
+    Err("no closing `---` line".to_string())
+}
+
+/// The value of `key` in parsed frontmatter
+pub(crate) fn get<'a>(keys: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    keys.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+/// A list item or map entry: `value`, or `key: value`
+fn parse_entry(entry: &str) -> Result<(), String> {
+    match split_key(entry) {
+        Some((_, value)) => parse_scalar(value).map(|_| ()),
+        None if entry.starts_with('"') || entry.starts_with('\'') || !entry.contains(": ") => {
+            parse_scalar(entry).map(|_| ())
+        }
+        None => Err("expected `key: value`".to_string()),
+    }
+}
+
+/// Split `key: value` (or `key:`) with a plain key
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = match line.split_once(": ") {
+        Some((key, value)) => (key, value.trim()),
+        None => (line.strip_suffix(':')?, ""),
+    };
+    let valid_key = !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    valid_key.then_some((key, value))
+}
+
+/// A scalar value, unquoted
+fn parse_scalar(value: &str) -> Result<String, String> {
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut unescaped = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    let trailing = chars.as_str().trim();
+                    if !trailing.is_empty() && !trailing.starts_with('#') {
+                        return Err(format!("unexpected `{}` after closing quote", trailing));
+                    }
+                    return Ok(unescaped);
+                }
+                '\\' => match chars.next() {
+                    Some('"') => unescaped.push('"'),
+                    Some('\\') => unescaped.push('\\'),
+                    Some('/') => unescaped.push('/'),
+                    Some('n') => unescaped.push('\n'),
+                    Some('t') => unescaped.push('\t'),
+                    Some(other) => return Err(format!("unknown escape `\\{}` in quoted string", other)),
+                    None => return Err("unterminated escape in quoted string".to_string()),
+                },
+                c => unescaped.push(c),
+            }
+        }
+        return Err("unterminated quoted string".to_string());
+    }
+
+    if let Some(rest) = value.strip_prefix('\'') {
+        let Some(end) = rest.rfind('\'') else {
+            return Err("unterminated quoted string".to_string());
+        };
+        return Ok(rest[..end].replace("''", "'"));
+    }
+
+    if value.contains(": ") {
+        return Err(format!("`{}` needs quotes: it contains `: `", value));
+    }
+    if let Some(indicator) = value.chars().next().filter(|c| matches!(c, '@' | '`' | '%' | '&' | '*' | '!' | '|' | '>')) {
+        return Err(format!("`{}` needs quotes: it starts with `{}`", value, indicator));
+    }
+    if (value.starts_with('[') && !value.ends_with(']')) || (value.starts_with('{') && !value.ends_with('}')) {
+        return Err(format!("unbalanced brackets in `{}`", value));
+    }
+    let value = value.split(" #").next().unwrap_or_default().trim_end();
+    Ok(value.to_string())
+}
+
+/// A post split into top-level frontmatter entries and the rest, for editing single keys
+///
+/// Entries keep their original lines, so keys that aren't edited (and the body) are
+/// written back byte for byte.
+pub(crate) struct Document {
+    /// The opening `---` line and anything before the first key (comments, blank lines)
+    head: String,
+    /// Each top-level key with its lines: the `key:` line and everything up to the next key
+    entries: Vec<(String, String)>,
+    /// The closing `---` line and the body
+    tail: String,
+}
+
+impl Document {
+    /// Split a post whose frontmatter [`parse`]s
+    pub(crate) fn parse(contents: &str) -> Result<Self, String> {
+        parse(contents)?;
+
+        let mut lines = contents.split_inclusive('\n');
+        let mut head = lines.next().unwrap_or_default().to_string();
+        let mut entries: Vec<(String, String)> = Vec::new();
+        let mut consumed = head.len();
+        for line in lines {
+            let content = line.trim_end_matches(['\n', '\r']);
+            if content == "---" {
+                break;
+            }
+            consumed += line.len();
+            let is_key = !line.starts_with(' ') && !content.trim().is_empty() && !content.starts_with('#');
+            match (is_key, entries.last_mut()) {
+                (true, _) => {
+                    let (key, _) = split_key(content).expect("validated by parse");
+                    entries.push((key.to_string(), line.to_string()));
+                }
+                (false, Some((_, lines))) => lines.push_str(line),
+                (false, None) => head.push_str(line),
+            }
+        }
+
+        Ok(Self { head, entries, tail: contents[consumed..].to_string() })
+    }
+
+    /// The top-level keys, in order
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(key, _)| key.as_str())
+    }
+
+    /// The post below the frontmatter
+    pub(crate) fn body(&self) -> &str {
+        let tail = self.tail.strip_prefix("---").unwrap_or(&self.tail);
+        tail.strip_prefix("\r\n").or_else(|| tail.strip_prefix('\n')).unwrap_or(tail)
+    }
+
+    /// The items of the block list `key` that are small maps, as their `key: value` pairs
+    pub(crate) fn maps(&self, key: &str) -> Vec<Vec<(String, String)>> {
+        let Some((_, lines)) = self.entries.iter().find(|(k, _)| k == key) else {
+            return Vec::new();
+        };
+        let mut maps: Vec<Vec<(String, String)>> = Vec::new();
+        for line in lines.lines().skip(1) {
+            let entry = line.trim_start();
+            let (starts_item, entry) = match entry.strip_prefix("- ") {
+                Some(entry) => (true, entry),
+                None => (false, entry),
+            };
+            let Some((field, value)) = split_key(entry) else {
+                continue;
+            };
+            let Ok(value) = parse_scalar(value) else {
+                continue;
+            };
+            match maps.last_mut() {
+                Some(map) if !starts_item => map.push((field.to_string(), value)),
+                _ => maps.push(vec![(field.to_string(), value)]),
+            }
+        }
+        maps
+    }
+
+    /// The scalar values of `key`: a block list, a `[a, b]` flow list, or a single value
+    pub(crate) fn list(&self, key: &str) -> Vec<String> {
+        let Some((_, lines)) = self.entries.iter().find(|(k, _)| k == key) else {
+            return Vec::new();
+        };
+        let mut lines = lines.lines();
+        let first = lines.next().and_then(split_key).map(|(_, value)| value).unwrap_or_default();
+        if let Some(flow) = first.strip_prefix('[').and_then(|f| f.strip_suffix(']')) {
+            return flow
+                .split(',')
+                .filter_map(|value| parse_scalar(value.trim()).ok())
+                .filter(|value| !value.is_empty())
+                .collect();
+        }
+        if !first.is_empty() {
+            return parse_scalar(first).into_iter().collect();
+        }
+        lines
+            .filter_map(|line| line.trim_start().strip_prefix("- "))
+            .filter_map(|value| parse_scalar(value.trim()).ok())
+            .collect()
+    }
+
+    /// Replace `key` with a block list of quoted `values`, appending it if it's new
+    pub(crate) fn set_list(&mut self, key: &str, values: &[String]) {
+        let mut lines = format!("{}:\n", key);
+        for value in values {
+            lines.push_str(&format!("  - \"{}\"\n", value.replace('\\', "\\\\").replace('"', "\\\"")));
+        }
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, existing)) => {
+                // Comments and blank lines after the old value stay where they were
+                let parts: Vec<&str> = existing.split_inclusive('\n').collect();
+                let value_end = parts.iter().rposition(|line| line.starts_with(' ')).unwrap_or(0) + 1;
+                lines.push_str(&parts[value_end..].concat());
+                *existing = lines;
+            }
+            None => {
+                if let Some((_, last)) = self.entries.last_mut()
+                    && !last.ends_with('\n')
+                {
+                    last.push('\n');
+                }
+                self.entries.push((key.to_string(), lines));
+            }
+        }
+    }
+
+    /// Drop the items of the block list `key` with a line `matches`, and the key itself if
+    /// none are left; returns whether anything was dropped
+    pub(crate) fn remove_items(&mut self, key: &str, matches: impl Fn(&str) -> bool) -> bool {
+        let Some(index) = self.entries.iter().position(|(k, _)| k == key) else {
+            return false;
+        };
+        let mut parts = self.entries[index].1.split_inclusive('\n');
+        let first = parts.next().unwrap_or_default().to_string();
+        let mut items: Vec<String> = Vec::new();
+        // Comments and blank lines, kept after the remaining items
+        let mut rest = String::new();
+        for line in parts {
+            match items.last_mut() {
+                _ if line.trim_start().starts_with("- ") => items.push(line.to_string()),
+                Some(item) if line.starts_with(' ') => item.push_str(line),
+                _ => rest.push_str(line),
+            }
+        }
+
+        let before = items.len();
+        items.retain(|item| !item.lines().any(&matches));
+        if items.len() == before {
+            return false;
+        }
+        if !items.is_empty() {
+            self.entries[index].1 = first + &items.concat() + &rest;
+        } else {
+            self.entries.remove(index);
+            match index.checked_sub(1).and_then(|previous| self.entries.get_mut(previous)) {
+                Some((_, previous)) => previous.push_str(&rest),
+                None => self.head.push_str(&rest),
+            }
+        }
+        true
+    }
+
+    /// The post with any edits
+    pub(crate) fn render(&self) -> String {
+        let mut contents = self.head.clone();
+        for (_, lines) in &self.entries {
+            contents.push_str(lines);
+        }
+        contents.push_str(&self.tail);
+        contents
+    }
+}
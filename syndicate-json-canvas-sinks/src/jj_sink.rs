@@ -1,11 +1,28 @@
+use crate::command::{CommandRunner, SkippedCommandRunner, SystemCommandRunner};
+use crate::filename::FilenameTemplate;
+use crate::posts::PostWriter;
 use crate::{SinkError, SyndicationSink};
-use chrono::Local;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use syndicate_json_canvas_lib::{SyndicationFormat, jsoncanvas::NodeId};
+use std::sync::Arc;
+use syndicate_json_canvas_lib::{
+    Clock, DestructionPlan, HtmlPolicy, PlannedCommit, PlannedWrite, RemovalPolicy, SyndicationFormat, SyndicationLinks,
+    TextPolicy, VerifyReport, Warning, jsoncanvas::NodeId,
+};
 use tracing::{debug, info};
 
+/// Template printing one commit ID per line, for resolving bookmarks with `jj log`
+const COMMIT_ID_TEMPLATE: &str = r#"commit_id ++ "\n""#;
+
+/// Whether a `jj` command changes anything
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandClass {
+    /// Only reads the repository (or refreshes it from the remote), so dry runs still run it
+    ReadOnly,
+    /// Changes the working copy, a bookmark or the remote, so dry runs only log it
+    Mutating,
+}
+
 /// Configuration for JJ repository syndication sink
 pub struct JjRepositorySink {
     /// Path to the JJ repository
@@ -14,8 +31,18 @@ pub struct JjRepositorySink {
     bookmark_name: String,
     /// Remote name to push to (e.g., "origin")
     remote_name: String,
-    /// Folder path within the repository to put files in
-    folder_path: PathBuf,
+    /// Writes the files into the folder within the repository
+    posts: PostWriter,
+    /// Commit message parts for the change opened by `begin`
+    pending_messages: Vec<String>,
+    /// Runs the `jj` commands
+    runner: Box<dyn CommandRunner>,
+    /// Skip read-only commands in dry runs as well
+    paranoid_dry_run: bool,
+    /// Local and remote commit of the bookmark, as the dry batch's `begin` found them
+    bookmark_position: (Option<String>, Option<String>),
+    /// Commit the last dry batch would have made
+    planned_commit: Option<PlannedCommit>,
 }
 
 impl JjRepositorySink {
@@ -50,233 +77,240 @@ impl JjRepositorySink {
         }
 
         Ok(Self {
-            repo_path,
             bookmark_name: bookmark_name.into(),
             remote_name: remote_name.into(),
-            folder_path: folder_path.as_ref().to_path_buf(),
+            posts: PostWriter::new(repo_path.join(folder_path.as_ref())),
+            repo_path,
+            pending_messages: Vec::new(),
+            runner: Box::new(SystemCommandRunner),
+            paranoid_dry_run: false,
+            bookmark_position: (None, None),
+            planned_commit: None,
         })
     }
 
-    /// Generate a slug from the content text (first 8 words)
-    fn generate_slug(text: &str) -> String {
-        text.split_whitespace()
-            .take(8)
-            .map(|word| {
-                // Remove punctuation and convert to lowercase
-                word.chars()
-                    .filter(|c| c.is_alphanumeric() || *c == '-')
-                    .collect::<String>()
-                    .to_lowercase()
-            })
-            .filter(|word| !word.is_empty())
-            .collect::<Vec<_>>()
-            .join("-")
+    /// Emit footnote references as a `references:` frontmatter list
+    pub fn with_reference_frontmatter(mut self, enabled: bool) -> Self {
+        self.posts.reference_frontmatter = enabled;
+        self
     }
 
-    /// Generate the filename for a syndication item
-    fn generate_filename(slug: &str, node_id: &NodeId) -> String {
-        format!("{}-{}.md", slug, node_id.as_str())
+    /// Add the `weight` of the edge, and its `emphasis` label if mapped, to each
+    /// `context_for_this`/`further_thinking` entry (see [`syndicate_json_canvas_lib::EdgeWeights`])
+    pub fn with_link_weights(mut self, enabled: bool) -> Self {
+        self.posts.link_weights = enabled;
+        self
     }
 
-    /// Escape double quotes and backslashes for YAML string values
-    fn escape_yaml_string(s: &str) -> String {
-        s.replace('\\', "\\\\").replace('"', "\\\"")
+    /// Add `canvas`, `node_id` and `canvas_hash` frontmatter to each post, naming the canvas
+    /// file, node and canvas version it was published from; posts are then matched to their
+    /// nodes by `node_id` rather than by file name
+    pub fn with_provenance(mut self, enabled: bool) -> Self {
+        self.posts.provenance = enabled;
+        self
     }
 
-    /// Generate file contents with frontmatter including cross-references
-    fn generate_file_contents(
-        item: &SyndicationFormat,
-        _slug: &str,
-        slugs: &HashMap<NodeId, String>,
-        all_items: &HashMap<NodeId, SyndicationFormat>,
-    ) -> String {
-        let date = Local::now().format("%Y-%m-%d").to_string();
-
-        // Use first 8 words for title, or full text if shorter
-        let title: String = item.text
-            .split_whitespace()
-            .take(8)
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        // Build context_for_this list (in-neighbors with /t/ prefix)
-        // Each item is an object with link_text and href
-        let context_for_this: Vec<(String, String)> = item.in_neighbor_ids
-            .iter()
-            .filter_map(|node_id| {
-                let neighbor_slug = slugs.get(node_id)?;
-                let neighbor_item = all_items.get(node_id)?;
-                let link_text: String = neighbor_item.text
-                    .split_whitespace()
-                    .take(8)
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                let href = format!("/t/{}-{}.md", neighbor_slug, node_id.as_str());
-                Some((link_text, href))
-            })
-            .collect();
+    /// Add `noindex: true` alongside `archived: true` when archiving
+    pub fn with_noindex_archived(mut self, enabled: bool) -> Self {
+        self.posts.noindex_archived = enabled;
+        self
+    }
 
-        // Build further_thinking list (out-neighbors with /t/ prefix)
-        // Each item is an object with link_text and href
-        let further_thinking: Vec<(String, String)> = item.out_neighbor_ids
-            .iter()
-            .filter_map(|node_id| {
-                let neighbor_slug = slugs.get(node_id)?;
-                let neighbor_item = all_items.get(node_id)?;
-                let link_text: String = neighbor_item.text
-                    .split_whitespace()
-                    .take(8)
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                let href = format!("/t/{}-{}.md", neighbor_slug, node_id.as_str());
-                Some((link_text, href))
-            })
-            .collect();
+    /// Leave a `withdrawn: true` stub in place of each pruned post instead of deleting it
+    /// (default [`RemovalPolicy::Delete`])
+    pub fn with_removal_policy(mut self, policy: RemovalPolicy) -> Self {
+        self.posts.removal_policy = policy;
+        self
+    }
 
-        // Format frontmatter with escaped strings
-        let mut frontmatter = format!(
-            "---\ntitle: \"{}\"\ndate: {}\n",
-            Self::escape_yaml_string(&title), date
-        );
-
-        if !context_for_this.is_empty() {
-            frontmatter.push_str("context_for_this:\n");
-            for (link_text, href) in context_for_this {
-                frontmatter.push_str(&format!("  - link_text: \"{}\"\n", Self::escape_yaml_string(&link_text)));
-                frontmatter.push_str(&format!("    href: \"{}\"\n", href));
-            }
-        }
+    /// Use a custom clock for the `date` frontmatter
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.posts.clock = clock;
+        self
+    }
 
-        if !further_thinking.is_empty() {
-            frontmatter.push_str("further_thinking:\n");
-            for (link_text, href) in further_thinking {
-                frontmatter.push_str(&format!("  - link_text: \"{}\"\n", Self::escape_yaml_string(&link_text)));
-                frontmatter.push_str(&format!("    href: \"{}\"\n", href));
-            }
-        }
+    /// Use a custom runner for the `jj` commands
+    pub fn with_command_runner(mut self, runner: impl CommandRunner + 'static) -> Self {
+        self.runner = Box::new(runner);
+        self
+    }
 
-        frontmatter.push_str("---\n\n");
+    /// Skip every `jj` command in dry runs, the read-only ones too (default false)
+    ///
+    /// Dry runs otherwise fetch and look up the bookmark, so they fail where a real run
+    /// would, e.g. on a missing bookmark.
+    pub fn with_paranoid_dry_run(mut self, paranoid: bool) -> Self {
+        self.paranoid_dry_run = paranoid;
+        self
+    }
+
+    /// Set how raw HTML in node text is treated before writing files
+    pub fn with_html_policy(mut self, html_policy: HtmlPolicy) -> Self {
+        self.posts.html_policy = html_policy;
+        self
+    }
+
+    /// Name files with a template instead of `{slug}-{id}.md`
+    ///
+    /// With any other template, files get a `node_id` frontmatter field so they can be
+    /// matched back to their nodes.
+    pub fn with_filename_template(mut self, filename_template: FilenameTemplate) -> Self {
+        self.posts.filename_template = filename_template;
+        self
+    }
+
+    /// Cut titles, slugs and commit message previews with a shared text policy
+    ///
+    /// Posts that already exist keep their file names.
+    pub fn with_text_policy(mut self, text: TextPolicy) -> Self {
+        self.posts.text = text;
+        self
+    }
 
-        format!("{}{}", frontmatter, item.text)
+    /// Lines of diff kept per modified file when comparing dry runs with the published
+    /// files (default [`syndicate_json_canvas_lib::DEFAULT_PREVIEW_DIFF_LINES`])
+    pub fn with_preview_diff_lines(mut self, lines: usize) -> Self {
+        self.posts.preview_diff_lines = lines;
+        self
     }
 
     /// Run a JJ command in the repository
-    fn run_jj_command(&self, args: &[&str], dry_run: bool) -> Result<String, SinkError> {
+    ///
+    /// `step` names the command (e.g. "push") in errors, so failures can be told apart. Dry
+    /// runs skip mutating commands, and read-only ones too when paranoid.
+    fn run_jj_command(
+        &self,
+        step: &'static str,
+        class: CommandClass,
+        args: &[&str],
+        dry_run: bool,
+    ) -> Result<String, SinkError> {
         let args_str = args.join(" ");
 
-        if dry_run {
-            debug!(command = %format!("jj {}", args_str), "[DRY RUN] Would execute command");
+        if dry_run && (class == CommandClass::Mutating || self.paranoid_dry_run) {
+            debug!(step, command = %format!("jj {}", args_str), "[DRY RUN] Would execute command");
             return Ok(String::new());
         }
 
-        debug!(command = %format!("jj {}", args_str), "Executing command");
+        debug!(step, command = %format!("jj {}", args_str), "Executing command");
 
-        let output = Command::new("jj")
-            .args(args)
-            .current_dir(&self.repo_path)
-            .output()
-            .map_err(|e| SinkError::CommandFailed(format!("Failed to execute jj: {}", e)))?;
+        let failure = |exit_code, stdout: String, stderr: String| SinkError::CommandFailed {
+            step,
+            program: "jj".to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            exit_code,
+            stdout,
+            stderr,
+        };
+
+        let output = self
+            .runner
+            .run("jj", args, &self.repo_path)
+            .map_err(|e| failure(None, String::new(), format!("Failed to execute jj: {}", e)))?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(SinkError::CommandFailed(format!(
-                "jj {} failed: {}",
-                args_str, stderr
-            )));
+            return Err(failure(
+                output.status.code(),
+                String::from_utf8_lossy(&output.stdout).into_owned(),
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ));
         }
 
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
-    /// Write the file to the repository
-    fn write_file(&self, filename: &str, contents: &str, dry_run: bool) -> Result<(), SinkError> {
-        let file_path = self.repo_path.join(&self.folder_path).join(filename);
-
-        if dry_run {
-            debug!(
-                file = %file_path.display(),
-                contents = %contents,
-                "[DRY RUN] Would write file"
-            );
-            return Ok(());
-        }
-
-        // Ensure the folder exists
-        if let Some(parent) = file_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-
-        std::fs::write(&file_path, contents)?;
-        debug!(file = %file_path.display(), "Wrote file");
+    /// Commit the bookmark points at locally and on the remote (None if it isn't there)
+    fn resolve_bookmark(&self, dry_run: bool) -> Result<(Option<String>, Option<String>), SinkError> {
+        let first_line = |stdout: String| stdout.lines().next().map(str::to_string).filter(|id| !id.is_empty());
+        let local = self.run_jj_command(
+            "resolve bookmark",
+            CommandClass::ReadOnly,
+            &["log", "--ignore-working-copy", "--no-graph", "-r", &self.bookmark_name, "-T", COMMIT_ID_TEMPLATE],
+            dry_run,
+        )?;
+        let revset = format!("remote_bookmarks(exact:{:?}, exact:{:?})", self.bookmark_name, self.remote_name);
+        let remote = self.run_jj_command(
+            "resolve remote bookmark",
+            CommandClass::ReadOnly,
+            &["log", "--ignore-working-copy", "--no-graph", "-r", &revset, "-T", COMMIT_ID_TEMPLATE],
+            dry_run,
+        )?;
+        Ok((first_line(local), first_line(remote)))
+    }
 
-        Ok(())
+    /// The commit `commit` would make in a real run, with the bookmark where `begin` found it
+    fn plan_commit(&mut self, message: String) -> PlannedCommit {
+        let (current, remote) = std::mem::take(&mut self.bookmark_position);
+        let files = self
+            .posts
+            .planned_changes()
+            .map(|path| path.strip_prefix(&self.repo_path).unwrap_or(path).to_path_buf())
+            .collect();
+        let target = match &current {
+            Some(id) => format!("a new change after `{}`", &id[..id.len().min(12)]),
+            None => format!("a new change after `{}`", self.bookmark_name),
+        };
+        PlannedCommit { message, files, bookmark: self.bookmark_name.clone(), current, remote, target }
     }
 }
 
-impl SyndicationSink for JjRepositorySink {
-    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
-        info!(item_count = items.len(), "Publishing to JJ repository");
 
-        if items.is_empty() {
-            info!("No items to publish");
-            return Ok(());
-        }
+impl SyndicationSink for JjRepositorySink {
+    /// Fetch and open a new change after the bookmark for the batch's files
+    fn begin(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        self.pending_messages.clear();
+        self.planned_commit = None;
 
         // Step 1: jj git fetch
-        self.run_jj_command(&["git", "fetch"], dry_run)?;
+        self.run_jj_command("fetch", CommandClass::ReadOnly, &["git", "fetch"], dry_run)?;
 
-        // Step 2: Pre-compute slugs for all items
-        let slugs: HashMap<NodeId, String> = items
-            .iter()
-            .map(|(node_id, item)| (node_id.clone(), Self::generate_slug(&item.text)))
-            .collect();
+        // Dry runs look the bookmark up instead of building on it, so a missing one still fails
+        if dry_run {
+            self.bookmark_position = self.resolve_bookmark(dry_run)?;
+        }
 
-        // Step 3: Generate commit message
-        let commit_message = if items.len() == 1 {
-            let item = items.values().next().unwrap();
-            let slug = slugs.get(&item.id).unwrap();
-            let preview = if item.text.len() > 50 {
-                format!("{}...", &item.text[..50])
-            } else {
-                item.text.clone()
-            };
-            format!("Adding microblog `{}`\n\n{}", slug, preview)
-        } else {
-            format!("Update microblogs ({} posts)", items.len())
-        };
+        // Step 2: jj new --insert-after <bookmark> (described on commit)
+        self.run_jj_command("new", CommandClass::Mutating, &["new", "--insert-after", &self.bookmark_name], dry_run)?;
+        Ok(())
+    }
 
-        // Step 4: jj new --insert-after <bookmark> -m <message>
-        self.run_jj_command(
-            &[
-                "new",
-                "--insert-after",
-                &self.bookmark_name,
-                "-m",
-                &commit_message,
-            ],
-            dry_run,
-        )?;
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.publish_chunk(items, items, dry_run)
+    }
 
-        // Step 5: Write all files
-        for (node_id, item) in items.iter() {
-            let slug = slugs.get(node_id).unwrap();
-            let filename = Self::generate_filename(slug, node_id);
-            let contents = Self::generate_file_contents(item, slug, &slugs, items);
+    fn publish_chunk(
+        &mut self,
+        items: &HashMap<NodeId, SyndicationFormat>,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+        dry_run: bool,
+    ) -> Result<(), SinkError> {
+        info!(item_count = items.len(), "Publishing to JJ repository");
 
-            debug!(
-                filename = %filename,
-                slug = %slug,
-                "Generated content"
-            );
+        if let Some(message) = self.posts.write_posts(items, all_items, dry_run)? {
+            self.pending_messages.push(message);
+        }
+        Ok(())
+    }
 
-            self.write_file(&filename, &contents, dry_run)?;
+    /// Describe the change, move the bookmark to it and push
+    fn commit(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        if self.pending_messages.is_empty() {
+            debug!("Nothing changed in this batch, abandoning the empty change");
+            return self.abort(dry_run);
         }
 
-        // Step 6: jj bookmark move <bookmark>
-        self.run_jj_command(&["bookmark", "move", &self.bookmark_name], dry_run)?;
+        // Step 3: jj describe -m <message>
+        let commit_message = self.pending_messages.join("\n\n");
+        self.run_jj_command("describe", CommandClass::Mutating, &["describe", "-m", &commit_message], dry_run)?;
+
+        // Step 4: jj bookmark move <bookmark>
+        let bookmark_move = ["bookmark", "move", &self.bookmark_name];
+        self.run_jj_command("bookmark move", CommandClass::Mutating, &bookmark_move, dry_run)?;
 
-        // Step 7: jj git push --remote <remote> --bookmark <bookmark>
+        // Step 5: jj git push --remote <remote> --bookmark <bookmark>
         self.run_jj_command(
+            "push",
+            CommandClass::Mutating,
             &[
                 "git",
                 "push",
@@ -288,11 +322,183 @@ impl SyndicationSink for JjRepositorySink {
             dry_run,
         )?;
 
+        self.pending_messages.clear();
+        if dry_run {
+            let planned = self.plan_commit(commit_message);
+            info!(
+                bookmark = %planned.bookmark,
+                current = planned.current.as_deref().unwrap_or("not looked up"),
+                files = planned.files.len(),
+                "[DRY RUN] Would commit and move the bookmark to {}",
+                planned.target
+            );
+            self.planned_commit = Some(planned);
+            return Ok(());
+        }
         info!("Successfully published to JJ repository");
         Ok(())
     }
 
+    /// Abandon the batch's change so nothing is pushed
+    fn abort(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        self.pending_messages.clear();
+        self.bookmark_position = (None, None);
+        self.run_jj_command("abandon", CommandClass::Mutating, &["abandon", "@"], dry_run)?;
+        Ok(())
+    }
+
+    fn take_planned_writes(&mut self) -> Vec<PlannedWrite> {
+        self.posts.take_planned_writes()
+    }
+
+    fn take_planned_commit(&mut self) -> Option<PlannedCommit> {
+        self.planned_commit.take()
+    }
+
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        self.posts.take_warnings()
+    }
+
+    fn archive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        if let Some(message) = self.posts.change_archive_state(node_ids, true, dry_run)? {
+            self.pending_messages.push(message);
+        }
+        Ok(())
+    }
+
+    fn unarchive(&mut self, node_ids: &[NodeId], dry_run: bool) -> Result<(), SinkError> {
+        if let Some(message) = self.posts.change_archive_state(node_ids, false, dry_run)? {
+            self.pending_messages.push(message);
+        }
+        Ok(())
+    }
+
+    fn plan_removal(&self, node_ids: &[NodeId]) -> Result<DestructionPlan, SinkError> {
+        self.posts.plan_removal(self.name(), node_ids)
+    }
+
+    fn remove(&mut self, plan: &DestructionPlan, dry_run: bool) -> Result<(), SinkError> {
+        if let Some(message) = self.posts.remove_files(plan, dry_run)? {
+            self.pending_messages.push(message);
+        }
+        Ok(())
+    }
+
+    fn add_syndication_links(
+        &mut self,
+        links: &SyndicationLinks,
+        dry_run: bool,
+    ) -> Result<usize, SinkError> {
+        let changed = self.posts.add_syndication_links(links, dry_run)?;
+        if changed > 0 {
+            self.pending_messages.push(format!("Add syndication links ({} posts)", changed));
+        }
+        Ok(changed)
+    }
+
+    fn import_legacy(
+        &mut self,
+        is_tracked: &dyn Fn(&NodeId) -> bool,
+        dry_run: bool,
+    ) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        let (imported, message) = self.posts.import_legacy(is_tracked, dry_run)?;
+        self.pending_messages.extend(message);
+        Ok(imported)
+    }
+
+    /// Files in `folder_path` named `<slug>-<node-id>.md`
+    ///
+    /// Node IDs are taken from after the last `-`, which holds for Obsidian's hex IDs.
+    fn list_published(&self) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        self.posts.list_published()
+    }
+
+    fn verify(&self) -> Result<VerifyReport, SinkError> {
+        self.posts.verify(self.name())
+    }
+
+    /// A copy writing into `root` whose `jj` commands are only logged
+    fn sandboxed(&self, root: &Path) -> Result<Box<dyn SyndicationSink + Send>, SinkError> {
+        Ok(Box::new(Self {
+            repo_path: root.to_path_buf(),
+            bookmark_name: self.bookmark_name.clone(),
+            remote_name: self.remote_name.clone(),
+            posts: self.posts.sandboxed(root)?,
+            pending_messages: Vec::new(),
+            runner: Box::new(SkippedCommandRunner),
+            paranoid_dry_run: self.paranoid_dry_run,
+            bookmark_position: (None, None),
+            planned_commit: None,
+        }))
+    }
+
+    /// `jj root` succeeds in the repository, which changes nothing
+    fn preflight(&self) -> Result<(), SinkError> {
+        self.run_jj_command("preflight", CommandClass::ReadOnly, &["root"], false)?;
+        Ok(())
+    }
+
     fn name(&self) -> &str {
         "jj"
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+    use syndicate_json_canvas_lib::{CanvasBuilder, default_process_node, to_syndication_format};
+
+    /// Runs nothing, recording each `jj` command; `git push` is rejected by the remote
+    #[derive(Clone, Default)]
+    struct RejectingRemote {
+        commands: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl CommandRunner for RejectingRemote {
+        fn run(&self, program: &str, args: &[&str], _current_dir: &Path) -> std::io::Result<Output> {
+            self.commands.lock().unwrap().push(format!("{} {}", program, args[..2.min(args.len())].join(" ")));
+            if args.starts_with(&["git", "push"]) {
+                return Ok(Output {
+                    status: ExitStatus::from_raw(1 << 8),
+                    stdout: b"Pushing bookmark main\n".to_vec(),
+                    stderr: b"Error: remote rejected: permission denied\n".to_vec(),
+                });
+            }
+            Ok(Output { status: ExitStatus::default(), stdout: Vec::new(), stderr: Vec::new() })
+        }
+    }
+
+    #[test]
+    fn failed_push_keeps_its_step_exit_code_and_output() {
+        let repo = std::env::temp_dir().join(format!("syndicate-json-canvas-jj-push-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&repo);
+        std::fs::create_dir_all(&repo).unwrap();
+        let remote = RejectingRemote::default();
+        let mut sink = JjRepositorySink::new(&repo, "main", "origin", "t").unwrap().with_command_runner(remote.clone());
+        let canvas = CanvasBuilder::new().text_node("0b5e000000000001", "A note.").color(PresetColor::Red).build();
+        let items = to_syndication_format(&canvas.unwrap(), Some(default_process_node)).unwrap();
+
+        sink.begin(false).unwrap();
+        sink.publish(&items, false).unwrap();
+        let error = sink.commit(false).unwrap_err();
+        assert_eq!((error.step(), error.exit_code()), (Some("push"), Some(1)));
+        let SinkError::CommandFailed { stdout, stderr, .. } = &error else {
+            panic!("expected a command failure, got {:?}", error);
+        };
+        assert_eq!(stdout, "Pushing bookmark main\n");
+        assert_eq!(stderr, "Error: remote rejected: permission denied\n");
+        let message = error.to_string();
+        assert!(message.starts_with("push failed: `jj git push --remote origin --bookmark main` exited with code 1"));
+        assert!(message.ends_with("remote rejected: permission denied"), "{}", message);
+
+        let commands = remote.commands.lock().unwrap().clone();
+        let steps = ["jj git fetch", "jj new --insert-after", "jj describe -m", "jj bookmark move", "jj git push"];
+        assert_eq!(commands, steps);
+        std::fs::remove_dir_all(&repo).unwrap();
+    }
+}
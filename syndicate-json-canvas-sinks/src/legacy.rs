@@ -0,0 +1,57 @@
+//! Posts in a post folder that weren't published from the canvas, e.g. written by hand
+//! before it, listed in a manifest kept in the folder
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use syndicate_json_canvas_lib::{SinkError, duplicates::content_hash, jsoncanvas::NodeId, write_atomic};
+
+/// Name of the manifest in the post folder; hidden, so site generators skip it
+pub(crate) const MANIFEST_FILE: &str = ".syndication-legacy.json";
+
+/// A post recorded by `import_legacy`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct LegacyPost {
+    /// Its `node_id` frontmatter, the node ID in its name, or a synthetic `legacy-...` ID
+    pub(crate) node_id: NodeId,
+    /// File name in the folder
+    pub(crate) file: String,
+    pub(crate) title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) date: Option<String>,
+}
+
+/// The legacy posts of a folder, none if it has no manifest
+pub(crate) fn load(folder: &Path) -> Result<Vec<LegacyPost>, SinkError> {
+    let path = folder.join(MANIFEST_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|e| SinkError::Serialization(format!("{}: {}", path.display(), e)))
+}
+
+/// Write the manifest, sorted by file name
+pub(crate) fn save(folder: &Path, posts: &mut [LegacyPost]) -> Result<(), SinkError> {
+    posts.sort_by(|a, b| a.file.cmp(&b.file));
+    let json = serde_json::to_string_pretty(posts).map_err(|e| SinkError::Serialization(e.to_string()))?;
+    write_atomic(&folder.join(MANIFEST_FILE), json + "\n")?;
+    Ok(())
+}
+
+/// ID of a post without a `node_id`: the Obsidian node ID its name ends in, else one
+/// derived from the name, so importing again gives the same ID
+pub(crate) fn id_from_name(file_name: &str, stem: &str) -> NodeId {
+    let suffix = stem.rsplit('-').next().unwrap_or(stem);
+    let id = if is_obsidian_id(suffix) {
+        suffix.to_string()
+    } else {
+        format!("legacy-{}", &content_hash(file_name)[..12])
+    };
+    id.parse().expect("ID is not empty")
+}
+
+/// Whether a string looks like the 16 hex digit IDs Obsidian gives canvas nodes
+pub(crate) fn is_obsidian_id(s: &str) -> bool {
+    s.len() == 16 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
@@ -4,17 +4,57 @@
 //! for publishing content to various destinations:
 //!
 //! - [`JjRepositorySink`] - Publishes to a Jujutsu (jj) git repository
+//! - [`DirectorySink`] - Writes the same markdown files into a plain directory
 //! - [`TwitterSink`] - Publishes to Twitter/X via API v2
+//! - [`ContentLakeSink`] - Appends versioned JSON records to monthly files, read back with
+//!   [`ContentLakeReader`]
+//! - [`NewsletterSink`] - Drafts emails on Buttondown, per item or as weekly or monthly digests
+//! - [`ExecSink`] - Hands items to a user-provided script as JSON Lines
+//! - [`PluginSink`] - An out-of-tree script found at runtime that describes, configures and checks itself
+//! - [`ChunkedSink`] - Wraps another sink to publish in size-limited chunks
+//!
+//! The markdown-writing sinks name their files with a [`FilenameTemplate`], and
+//! [`consistency`] checks that their output still holds together after a run.
+//! [`preview_server`] serves their output locally as HTML for a look before publishing.
 //!
 //! The [`SyndicationSink`] trait and [`SinkError`] type are defined in
 //! `syndicate-json-canvas-lib` and re-exported here for convenience.
 
+mod canvases;
+pub mod chunked_sink;
+pub mod command;
+pub mod consistency;
+pub mod content_lake_sink;
+pub mod directory_sink;
+pub mod exec_sink;
+pub mod filename;
+mod frontmatter;
 pub mod jj_sink;
+mod legacy;
+pub mod newsletter_sink;
+pub mod plugin_sink;
+mod posts;
+pub mod preview_server;
+mod tag_pages;
 pub mod twitter_sink;
 
+pub use command::{CommandRunner, SkippedCommandRunner, SystemCommandRunner};
+
 // Re-export sink implementations
+pub use chunked_sink::ChunkedSink;
+pub use consistency::Inconsistency;
+pub use content_lake_sink::{ContentLakeReader, ContentLakeSink, LakeRecord};
+pub use directory_sink::DirectorySink;
+pub use exec_sink::{DEFAULT_EXEC_TIMEOUT, ExecResult, ExecSink, ExecStatus};
+pub use filename::{FilenameError, FilenameParts, FilenameTemplate};
 pub use jj_sink::JjRepositorySink;
-pub use twitter_sink::TwitterSink;
+pub use newsletter_sink::{DigestPeriod, NewsletterMode, NewsletterSink};
+pub use plugin_sink::{
+    DEFAULT_PLUGIN_TIMEOUT, PLUGIN_ABI_VERSION, PLUGIN_CONFIG_VAR, PluginError, PluginManifest, PluginSink,
+    discover_plugins,
+};
+pub use preview_server::{PreviewHandle, PreviewServer, PreviewSite};
+pub use twitter_sink::{DEFAULT_MAX_THREAD_TWEETS, TwitterSink};
 
 // Re-export trait and error from lib crate for convenience
 pub use syndicate_json_canvas_lib::{SinkError, SyndicationSink};
@@ -0,0 +1,534 @@
+//! Newsletter sink: items become email drafts on a newsletter service
+//!
+//! [`NewsletterSink`] talks to Buttondown's API, the first backend. Each item gets a draft of
+//! its own, or the items delivered in one week or month are collected in one digest draft.
+
+use crate::{SinkError, SyndicationSink};
+use chrono::{DateTime, Datelike, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use syndicate_json_canvas_lib::{
+    Clock, HttpClient, HttpError, HttpRequest, HttpResponse, NetworkPolicy, PoliteClient, ReqwestClient,
+    StateRegistry, SyndicationFormat, SystemClock, TextPolicy, Warning, cmp_node_ids, jsoncanvas::NodeId,
+    warnings::codes, write_atomic,
+};
+use tracing::{debug, info, warn};
+
+/// Base URL of Buttondown's API
+pub const BUTTONDOWN_API: &str = "https://api.buttondown.com/v1";
+/// Subject of digest emails by default
+pub const DEFAULT_DIGEST_SUBJECT: &str = "Notes for {period}";
+/// Subject of per-item emails by default
+pub const DEFAULT_ITEM_SUBJECT: &str = "{title}";
+/// Where a draft is opened in Buttondown, by email ID
+const BUTTONDOWN_EMAIL_URL: &str = "https://buttondown.com/emails/";
+/// Version of the layout of the state file, for state bundles
+const STATE_SCHEMA: u32 = 1;
+
+/// How items are grouped into emails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewsletterMode {
+    /// Each item is an email of its own
+    PerItem,
+    /// The items delivered in one period are collected in one email
+    Digest(DigestPeriod),
+}
+
+/// Period a digest collects items over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestPeriod {
+    /// ISO weeks, Monday to Sunday
+    Weekly,
+    Monthly,
+}
+
+impl DigestPeriod {
+    /// Key of the period `at` falls in, e.g. `2026-W42` or `2026-10`
+    pub fn key(&self, at: DateTime<Local>) -> String {
+        match self {
+            DigestPeriod::Weekly => {
+                let week = at.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            DigestPeriod::Monthly => at.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// Emails the sink created, which doubles as its record of what was published
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct NewsletterState {
+    /// Emails by key: the period of a digest (`2026-W42`), or the node ID of an item's own
+    emails: BTreeMap<String, Email>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Email {
+    /// ID of the email at the service, once it was created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    /// Period of a digest; None for an item's own email
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    period: Option<String>,
+    /// A request creating the email was sent, but its answer wasn't recorded
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    in_flight: bool,
+    /// Whether the email was sent, after which it isn't changed
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    sent: bool,
+    /// The items in it, in delivery order
+    sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Section {
+    node_id: String,
+    title: String,
+    /// The item's text, with its permalink if configured
+    markdown: String,
+}
+
+impl Email {
+    fn contains(&self, node_id: &NodeId) -> bool {
+        self.sections.iter().any(|section| section.node_id == node_id.as_str())
+    }
+
+    /// Add a section, or replace the node's earlier one
+    fn put(&mut self, section: Section) {
+        match self.sections.iter_mut().find(|s| s.node_id == section.node_id) {
+            Some(existing) => *existing = section,
+            None => self.sections.push(section),
+        }
+    }
+}
+
+/// Publishes items as email drafts on Buttondown
+///
+/// Drafts are created once and then updated: re-runs in the same digest period change the
+/// period's draft rather than adding another, and edited items are changed in their drafts
+/// until those are sent. Nothing is sent unless [`with_send`](Self::with_send) is on; then
+/// an item's own email is sent right away, and a digest with the sink's first delivery after
+/// its period ends.
+pub struct NewsletterSink {
+    /// Buttondown API key
+    api_key: String,
+    api_base: String,
+    mode: NewsletterMode,
+    /// Subject template (`{period}`, `{count}`, `{title}`, `{date}` and `{reading_time}`
+    /// placeholders)
+    subject: String,
+    /// Whether emails are sent, not only drafted
+    send: bool,
+    /// Template of the canonical post URL (`{slug}` and `{id}` placeholders)
+    canonical_url: Option<String>,
+    /// How titles and the `{slug}` of canonical URLs are cut from node text
+    text: TextPolicy,
+    /// Path to the JSON state file
+    state_path: PathBuf,
+    state: NewsletterState,
+    clock: Arc<dyn Clock>,
+    /// If set, emails are written here as `<key>.md` instead of being created
+    preview_dir: Option<PathBuf>,
+    /// URLs of the drafts delivered to since the last `take_post_urls`
+    post_urls: Vec<(NodeId, String)>,
+    /// Warnings raised since the last `take_warnings`
+    warnings: Vec<Warning>,
+    /// Sends the API requests
+    http: Arc<dyn HttpClient>,
+}
+
+impl NewsletterSink {
+    /// Create a sink drafting emails on Buttondown
+    ///
+    /// # Arguments
+    /// * `api_key` - Buttondown API key
+    /// * `state_path` - Path to the JSON file recording the emails created and their items
+    pub fn buttondown(api_key: impl Into<String>, state_path: impl AsRef<Path>) -> Result<Self, SinkError> {
+        let state_path = state_path.as_ref().to_path_buf();
+        let state = load_state(&state_path)?;
+        Ok(Self {
+            api_key: api_key.into(),
+            api_base: BUTTONDOWN_API.to_string(),
+            mode: NewsletterMode::PerItem,
+            subject: DEFAULT_ITEM_SUBJECT.to_string(),
+            send: false,
+            canonical_url: None,
+            text: TextPolicy::default(),
+            state_path,
+            state,
+            clock: Arc::new(SystemClock),
+            preview_dir: None,
+            post_urls: Vec::new(),
+            warnings: Vec::new(),
+            http: Arc::new(PoliteClient::new(ReqwestClient::new(), NetworkPolicy::default())),
+        })
+    }
+
+    /// Collect items in a digest per period (default: an email per item), with the subject
+    /// switched to [`DEFAULT_DIGEST_SUBJECT`] unless one was set
+    pub fn with_mode(mut self, mode: NewsletterMode) -> Self {
+        if self.subject == DEFAULT_ITEM_SUBJECT && matches!(mode, NewsletterMode::Digest(_)) {
+            self.subject = DEFAULT_DIGEST_SUBJECT.to_string();
+        }
+        self.mode = mode;
+        self
+    }
+
+    /// Set the subject template; `{period}`, `{count}` (items), `{title}` (of the first item),
+    /// `{date}` and `{reading_time}` (of the whole email) are filled in, the date, count and
+    /// reading time per the text policy's locale
+    pub fn with_subject(mut self, template: impl Into<String>) -> Self {
+        self.subject = template.into();
+        self
+    }
+
+    /// Send emails instead of leaving them as drafts
+    pub fn with_send(mut self, send: bool) -> Self {
+        self.send = send;
+        self
+    }
+
+    /// End each item with a link to its canonical post, e.g. `https://example.com/t/{slug}-{id}`
+    pub fn with_canonical_url(mut self, template: impl Into<String>) -> Self {
+        self.canonical_url = Some(template.into());
+        self
+    }
+
+    /// Cut titles and the `{slug}` of canonical URLs with a shared text policy, so links
+    /// match the file names of the markdown sinks, and write subject dates in its locale
+    pub fn with_text_policy(mut self, text: TextPolicy) -> Self {
+        self.text = text;
+        self
+    }
+
+    /// Send API requests through a client shared with other network sinks, so their
+    /// [`NetworkPolicy`] limits apply together (default: a [`PoliteClient`] of its own)
+    pub fn with_http_client(mut self, http: Arc<dyn HttpClient>) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Use another API base URL, e.g. a proxy
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Take the current time from `clock`, which decides the digest period
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Whether an email the service has holds the node; one in flight doesn't count, so
+    /// delivering it again runs into the in-flight check
+    fn is_published(&self, node_id: &NodeId) -> bool {
+        self.state.emails.values().any(|email| !email.in_flight && email.contains(node_id))
+    }
+
+    fn save_state(&self) -> Result<(), SinkError> {
+        let json = serde_json::to_string_pretty(&self.state).map_err(|e| SinkError::Serialization(e.to_string()))?;
+        write_atomic(&self.state_path, json)?;
+        Ok(())
+    }
+
+    fn section(&self, item: &SyndicationFormat) -> Section {
+        let mut markdown = item.text.trim().to_string();
+        if let Some(template) = &self.canonical_url {
+            markdown.push_str(&format!("\n\n[Permalink]({})", self.text.canonical_url(template, item)));
+        }
+        Section { node_id: item.id.as_str().to_string(), title: self.text.title(&item.text), markdown }
+    }
+
+    fn subject(&self, email: &Email) -> String {
+        let now = self.clock.now();
+        let mut subject = self
+            .subject
+            .replace("{period}", email.period.as_deref().unwrap_or_default())
+            .replace("{count}", &self.text.human_number(email.sections.len() as u64))
+            .replace("{title}", email.sections.first().map_or("", |section| section.title.as_str()))
+            .replace("{date}", &self.text.human_date(now.date_naive()));
+        if subject.contains("{reading_time}") {
+            subject = subject.replace("{reading_time}", &self.text.reading_time(&Self::body(email)));
+        }
+        subject
+    }
+
+    /// A digest lists its items under their titles; an item's own email is just its text
+    fn body(email: &Email) -> String {
+        if email.period.is_none() {
+            return email.sections.iter().map(|s| s.markdown.as_str()).collect::<Vec<_>>().join("\n\n");
+        }
+        let sections: Vec<String> =
+            email.sections.iter().map(|section| format!("## {}\n\n{}", section.title, section.markdown)).collect();
+        sections.join("\n\n")
+    }
+
+    /// Key of the digest the items delivered now go into
+    fn digest_key(&self, period: DigestPeriod) -> String {
+        let period = period.key(self.clock.now());
+        // A digest only goes out once its period is over, unless the clock went back
+        let mut key = period.clone();
+        let mut n = 1;
+        while self.state.emails.get(&key).is_some_and(|email| email.sent) {
+            n += 1;
+            key = format!("{}-{}", period, n);
+        }
+        key
+    }
+
+    /// Create or update the email under `key` as `email`, recording it once the service has it
+    fn deliver(&mut self, key: &str, mut email: Email, dry_run: bool) -> Result<(), SinkError> {
+        let subject = self.subject(&email);
+        let body = Self::body(&email);
+
+        if let Some(preview_dir) = &self.preview_dir {
+            let path = preview_dir.join(format!("{}.md", key));
+            if !dry_run {
+                std::fs::create_dir_all(preview_dir)?;
+                write_atomic(&path, format!("# {}\n\n{}\n", subject, body))?;
+                self.state.emails.insert(key.to_string(), email);
+                self.save_state()?;
+            }
+            debug!(file = %path.display(), "Rendered email instead of drafting it");
+            return Ok(());
+        }
+        if dry_run {
+            debug!(key = %key, subject = %subject, "[DRY RUN] Would draft email");
+            return Ok(());
+        }
+
+        let id = match email.id.clone() {
+            Some(id) => {
+                self.send_request(HttpRequest::patch(self.email_url(&id), draft(&subject, &body)), "update draft")?;
+                id
+            }
+            None if email.in_flight => {
+                return Err(SinkError::Api(format!(
+                    "an earlier request may already have created this email; check Buttondown, then clear `in_flight` of {} in {} to retry",
+                    key,
+                    self.state_path.display()
+                )));
+            }
+            None => {
+                // Recorded first, so an answer lost with the connection doesn't lead to a second draft
+                self.state.emails.insert(key.to_string(), Email { in_flight: true, ..email.clone() });
+                self.save_state()?;
+                let request = HttpRequest::post(format!("{}/emails", self.api_base), draft(&subject, &body));
+                let created = self.send_request(request, "create draft");
+                if created.as_ref().is_err_and(|e| !e.is_transient()) {
+                    // Rejected, so nothing was created and it's safe to try again
+                    self.state.emails.remove(key);
+                    self.save_state()?;
+                }
+                let id = email_id(&created?)?;
+                info!(key = %key, email_id = %id, "Created newsletter draft");
+                id
+            }
+        };
+
+        let url = format!("{}{}", BUTTONDOWN_EMAIL_URL, id);
+        for section in &email.sections {
+            if let Ok(node_id) = section.node_id.parse() {
+                self.post_urls.push((node_id, url.clone()));
+            }
+        }
+        email.id = Some(id);
+        email.in_flight = false;
+        self.state.emails.insert(key.to_string(), email);
+        self.save_state()
+    }
+
+    /// Send the emails due: an item's own email right away, a digest once its period is over
+    fn send_due(&mut self, dry_run: bool) -> Result<(), SinkError> {
+        if !self.send || self.preview_dir.is_some() {
+            return Ok(());
+        }
+        let current = match self.mode {
+            NewsletterMode::Digest(period) => Some(period.key(self.clock.now())),
+            NewsletterMode::PerItem => None,
+        };
+        let due: Vec<(String, String)> = self
+            .state
+            .emails
+            .iter()
+            .filter(|(_, email)| !email.sent && email.period.is_none() == current.is_none())
+            .filter(|(_, email)| email.period.is_none() || email.period != current)
+            .filter_map(|(key, email)| Some((key.clone(), email.id.clone()?)))
+            .collect();
+
+        for (key, id) in due {
+            if dry_run {
+                info!(key = %key, "[DRY RUN] Would send email");
+                continue;
+            }
+            let body = serde_json::json!({"status": "about_to_send"}).to_string();
+            let request = HttpRequest::patch(self.email_url(&id), body);
+            self.send_request(request, "send email")?;
+            info!(key = %key, email_id = %id, "Sent newsletter email");
+            if let Some(email) = self.state.emails.get_mut(&key) {
+                email.sent = true;
+            }
+            self.save_state()?;
+        }
+        Ok(())
+    }
+
+    fn email_url(&self, id: &str) -> String {
+        format!("{}/emails/{}", self.api_base, id)
+    }
+
+    /// Send a request to the API, telling errors worth retrying from those that aren't
+    fn send_request(&self, request: HttpRequest, action: &str) -> Result<HttpResponse, SinkError> {
+        let request = request
+            .header("Authorization", format!("Token {}", self.api_key))
+            .header("Content-Type", "application/json");
+        let response = self.http.send(&request).map_err(|e| match e {
+            HttpError::InvalidRequest(_) => SinkError::Rejected(format!("Failed to {}: {}", action, e)),
+            HttpError::Transport(_) => SinkError::Api(format!("Failed to {}: {}", action, e)),
+        })?;
+        if response.is_success() {
+            return Ok(response);
+        }
+        let message =
+            format!("Failed to {}: Buttondown answered {}: {}", action, response.status, response.body.trim());
+        match response.status {
+            408 | 429 | 500..=599 => Err(SinkError::Api(message)),
+            _ => Err(SinkError::Rejected(message)),
+        }
+    }
+}
+
+/// Request body of a draft
+fn draft(subject: &str, body: &str) -> String {
+    serde_json::json!({"subject": subject, "body": body, "status": "draft"}).to_string()
+}
+
+/// ID of the email in the answer to creating it
+fn email_id(response: &HttpResponse) -> Result<String, SinkError> {
+    // The draft exists from here on, even if its ID can't be read
+    let json: serde_json::Value = serde_json::from_str(&response.body)
+        .map_err(|e| SinkError::Api(format!("Failed to parse response: {}", e)))?;
+    json["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| SinkError::Api("No email ID in response".to_string()))
+}
+
+fn load_state(path: &Path) -> Result<NewsletterState, SinkError> {
+    if !path.exists() {
+        return Ok(NewsletterState::default());
+    }
+    serde_json::from_str(&std::fs::read_to_string(path)?).map_err(|e| SinkError::Serialization(e.to_string()))
+}
+
+/// Items in node ID order, so digests list them the same way on every run
+fn sorted<'a>(items: impl Iterator<Item = &'a SyndicationFormat>) -> Vec<&'a SyndicationFormat> {
+    let mut items: Vec<&SyndicationFormat> = items.collect();
+    items.sort_by(|a, b| cmp_node_ids(&a.id, &b.id));
+    items
+}
+
+impl SyndicationSink for NewsletterSink {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        let new_items = sorted(items.values().filter(|item| !self.is_published(&item.id)));
+        info!(new_items = new_items.len(), already_published = items.len() - new_items.len(), "Drafting newsletter");
+
+        match self.mode {
+            NewsletterMode::PerItem => {
+                for item in new_items {
+                    let key = item.id.as_str().to_string();
+                    let email = Email { sections: vec![self.section(item)], ..Email::default() };
+                    self.deliver(&key, email, dry_run).map_err(|e| e.for_item(&item.id))?;
+                }
+            }
+            NewsletterMode::Digest(period) if !new_items.is_empty() => {
+                let key = self.digest_key(period);
+                let mut email = self.state.emails.get(&key).cloned().unwrap_or_else(|| Email {
+                    period: Some(period.key(self.clock.now())),
+                    ..Email::default()
+                });
+                for item in new_items {
+                    email.put(self.section(item));
+                }
+                self.deliver(&key, email, dry_run)?;
+            }
+            NewsletterMode::Digest(_) => {}
+        }
+        self.send_due(dry_run)
+    }
+
+    /// Changes edited items in their emails, unless those were sent already
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        for item in sorted(items.values()) {
+            let Some((key, email)) = self.state.emails.iter().find(|(_, email)| email.contains(&item.id)) else {
+                continue;
+            };
+            if email.sent {
+                warn!(node_id = %item.id, key = %key, "Email was sent already, edit not delivered");
+                let message = format!("Email {} was sent already, so the edit isn't in it", key);
+                self.warnings.push(Warning::new(codes::EDIT_NOT_DELIVERED, self.name(), message).for_node(&item.id));
+                continue;
+            }
+            let (key, mut email) = (key.clone(), email.clone());
+            email.put(self.section(item));
+            self.deliver(&key, email, dry_run).map_err(|e| e.for_item(&item.id))?;
+        }
+        Ok(())
+    }
+
+    fn supports_updates(&self) -> bool {
+        true
+    }
+
+    /// A copy with its own state under `root` that renders emails into `root/emails`
+    fn sandboxed(&self, root: &Path) -> Result<Box<dyn SyndicationSink + Send>, SinkError> {
+        std::fs::create_dir_all(root)?;
+        let state_path = root.join("state.json");
+        if self.state_path.exists() {
+            std::fs::copy(&self.state_path, &state_path)?;
+        }
+        Ok(Box::new(Self {
+            api_key: String::new(),
+            api_base: self.api_base.clone(),
+            mode: self.mode,
+            subject: self.subject.clone(),
+            send: self.send,
+            canonical_url: self.canonical_url.clone(),
+            text: self.text.clone(),
+            state: load_state(&state_path)?,
+            state_path,
+            clock: self.clock.clone(),
+            preview_dir: Some(root.join("emails")),
+            post_urls: Vec::new(),
+            warnings: Vec::new(),
+            http: self.http.clone(),
+        }))
+    }
+
+    fn take_post_urls(&mut self) -> Vec<(NodeId, String)> {
+        std::mem::take(&mut self.post_urls)
+    }
+
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn register_state(&self, registry: &mut StateRegistry) {
+        registry.register(format!("{}.state", self.name()), &self.state_path, STATE_SCHEMA);
+    }
+
+    /// An API key is set; whether Buttondown accepts it is only known when drafting
+    fn preflight(&self) -> Result<(), SinkError> {
+        if self.api_key.trim().is_empty() {
+            return Err(SinkError::Config("API key is empty".to_string()));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "newsletter"
+    }
+}
@@ -0,0 +1,284 @@
+//! Out-of-tree sinks loaded at runtime as plugin programs
+//!
+//! A plugin is an executable of its own, in any language, found in a plugins directory, not
+//! a library loaded over a C ABI or compiled to wasm. A library crashing inside the process
+//! would take the watcher down with it, which only a process boundary rules out, and wasm
+//! would need a runtime for one use. So a plugin is a script sink (see [`ExecSink`]) that
+//! describes itself: loading it adds discovery, a version handshake, configuration and a
+//! check, and its batches go through `ExecSink` unchanged.
+//!
+//! Every call runs the plugin once with the call's name as its argument:
+//!
+//! | Call | Stdin | Stdout |
+//! | --- | --- | --- |
+//! | `describe` | nothing | a [`PluginManifest`] |
+//! | `configure` | `{"config": ...}` | `{"ok": true}` or `{"ok": false, "error": "..."}` |
+//! | `check` | `{"config": ...}` | `{"ok": true}` or `{"ok": false, "error": "..."}` |
+//! | `publish` | the items as JSON Lines | an [`ExecResult`](crate::exec_sink::ExecResult) line per item |
+//!
+//! `publish` is an [`ExecSink`] batch: the configuration is in `SYNDICATE_PLUGIN_CONFIG`,
+//! next to the `SYNDICATE_*` variables every script gets. A plugin whose manifest names
+//! another [`PLUGIN_ABI_VERSION`] isn't loaded.
+//!
+//! Since each call is a process of its own, a plugin that crashes, hangs or prints garbage
+//! fails only that call of its own sink; the watcher and the other sinks carry on.
+
+use crate::exec_sink::{ExecSink, Finished, run_with_input};
+use crate::{SinkError, SyndicationSink};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use syndicate_json_canvas_lib::{SyndicationFormat, jsoncanvas::NodeId};
+use tracing::info;
+
+/// Version of the protocol this build speaks; plugins must name the same one
+///
+/// Version 2 hands `publish` batches over as script sinks do.
+pub const PLUGIN_ABI_VERSION: u32 = 2;
+
+/// How long a plugin may take over one call by default
+pub const DEFAULT_PLUGIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long `describe`, `configure` and `check` may take
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Environment variable holding a plugin's configuration as JSON in `publish` calls
+pub const PLUGIN_CONFIG_VAR: &str = "SYNDICATE_PLUGIN_CONFIG";
+
+/// What a plugin says about itself when asked to `describe`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginManifest {
+    /// Protocol version the plugin speaks
+    pub abi: u32,
+    /// Sink name, as in reports, trackers and `PUBLIC_SINKS`
+    pub name: String,
+    /// Version of the plugin itself, for logs
+    #[serde(default)]
+    pub version: String,
+    /// Whether edited items are sent again with `change` set to `updated`
+    #[serde(default)]
+    pub supports_updates: bool,
+}
+
+/// Why a plugin couldn't be loaded
+#[derive(Debug, thiserror::Error)]
+pub enum PluginError {
+    #[error("Failed to run plugin {path}: {source}")]
+    Spawn { path: PathBuf, source: std::io::Error },
+    #[error("Plugin {path} failed to `{call}`: {reason}")]
+    Failed { path: PathBuf, call: &'static str, reason: String },
+    #[error("Plugin {path} speaks protocol version {found}, but this build speaks version {expected}")]
+    AbiMismatch { path: PathBuf, found: u32, expected: u32 },
+    #[error("Plugin `{name}` refused its configuration: {reason}")]
+    Refused { name: String, reason: String },
+}
+
+/// Answer to `configure` and `check`
+#[derive(Debug, Deserialize)]
+struct Verdict {
+    ok: bool,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Executables directly in `dir`, sorted by name; plugins are loaded from these
+///
+/// Hidden files and files that aren't executable (on Unix) are left out.
+pub fn discover_plugins(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut plugins = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path.file_name().and_then(|name| name.to_str()).is_none_or(|name| name.starts_with('.'));
+        if !hidden && path.is_file() && is_executable(&path) {
+            plugins.push(path);
+        }
+    }
+    plugins.sort();
+    Ok(plugins)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+/// Publishes through a plugin program: an [`ExecSink`] running `<plugin> publish`, loaded
+/// and checked as described in [this module](self)
+pub struct PluginSink {
+    path: PathBuf,
+    manifest: PluginManifest,
+    /// Handed to the plugin with every call
+    config: serde_json::Value,
+    /// Runs the plugin's `publish` batches
+    exec: ExecSink,
+}
+
+impl PluginSink {
+    /// Load the plugin at `path`: ask it to `describe` itself, check it speaks
+    /// [`PLUGIN_ABI_VERSION`], and `configure` it with `config`
+    pub fn load(path: impl AsRef<Path>, config: serde_json::Value) -> Result<Self, PluginError> {
+        let path = path.as_ref().to_path_buf();
+        let manifest = Self::describe(&path)?;
+        let exec = ExecSink::new(&manifest.name, path.display().to_string(), &["publish"])
+            .with_timeout(DEFAULT_PLUGIN_TIMEOUT)
+            .with_env(PLUGIN_CONFIG_VAR, config.to_string());
+        let sink = Self { path, manifest, config, exec };
+        sink.verdict("configure")?;
+        info!(
+            plugin = %sink.manifest.name,
+            version = %sink.manifest.version,
+            path = %sink.path.display(),
+            "Loaded plugin"
+        );
+        Ok(sink)
+    }
+
+    /// Ask the plugin at `path` to `describe` itself, failing unless it speaks
+    /// [`PLUGIN_ABI_VERSION`]
+    pub fn describe(path: &Path) -> Result<PluginManifest, PluginError> {
+        let manifest: PluginManifest = call(path, "describe", String::new(), HANDSHAKE_TIMEOUT)?;
+        if manifest.abi != PLUGIN_ABI_VERSION {
+            let path = path.to_path_buf();
+            return Err(PluginError::AbiMismatch { path, found: manifest.abi, expected: PLUGIN_ABI_VERSION });
+        }
+        Ok(manifest)
+    }
+
+    /// Kill the plugin if a batch takes longer (default [`DEFAULT_PLUGIN_TIMEOUT`])
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.exec = self.exec.with_timeout(timeout);
+        self
+    }
+
+    /// What the plugin said about itself
+    pub fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    /// Send the configuration with a `configure` or `check` call, failing unless it's ok
+    fn verdict(&self, name: &'static str) -> Result<(), PluginError> {
+        let request = serde_json::json!({ "config": &self.config }).to_string();
+        let verdict: Verdict = call(&self.path, name, request, HANDSHAKE_TIMEOUT)?;
+        if !verdict.ok {
+            let reason = verdict.error.unwrap_or_else(|| "no reason given".to_string());
+            return Err(PluginError::Refused { name: self.manifest.name.clone(), reason });
+        }
+        Ok(())
+    }
+}
+
+/// Run one call of a plugin and read its answer
+fn call<T: for<'de> Deserialize<'de>>(
+    path: &Path,
+    name: &'static str,
+    request: String,
+    timeout: Duration,
+) -> Result<T, PluginError> {
+    let failed = |reason: String| PluginError::Failed { path: path.to_path_buf(), call: name, reason };
+    let finished = run_with_input(Command::new(path).arg(name), request, timeout)
+        .map_err(|source| PluginError::Spawn { path: path.to_path_buf(), source })?;
+    let Some(Finished { status, stdout, stderr }) = finished else {
+        return Err(failed(format!("timed out after {} ms and was killed", timeout.as_millis())));
+    };
+    if !status.success() {
+        let exit = match status.code() {
+            Some(code) => format!("exited {}", code),
+            None => "was killed by a signal".to_string(),
+        };
+        let stderr = stderr.trim();
+        return Err(failed(if stderr.is_empty() { exit } else { format!("{}: {}", exit, stderr) }));
+    }
+    serde_json::from_str(stdout.trim()).map_err(|e| failed(format!("printed `{}`: {}", stdout.trim(), e)))
+}
+
+impl SyndicationSink for PluginSink {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.exec.publish(items, dry_run)
+    }
+
+    /// The plugin gets edited items with `change` set to `updated`, if it said it takes them
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.exec.update(items, dry_run)
+    }
+
+    fn supports_updates(&self) -> bool {
+        self.manifest.supports_updates
+    }
+
+    /// The plugin's own `check` passes
+    fn preflight(&self) -> Result<(), SinkError> {
+        self.verdict("check").map_err(|e| SinkError::Config(e.to_string()))
+    }
+
+    fn take_post_urls(&mut self) -> Vec<(NodeId, String)> {
+        self.exec.take_post_urls()
+    }
+
+    fn name(&self) -> &str {
+        &self.manifest.name
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+    use syndicate_json_canvas_lib::{CanvasBuilder, default_process_node, to_syndication_format};
+
+    /// Answers `publish` with a result per item and keeps its configuration next to itself
+    const SHELL_PLUGIN: &str = r#"#!/bin/sh
+case "$1" in
+    describe) echo '{"abi": ABI, "name": "shell"}' ;;
+    configure|check) echo '{"ok": true}' ;;
+    publish)
+        echo "$SYNDICATE_PLUGIN_CONFIG" > "$(dirname "$0")/config.json"
+        while read -r line; do
+            id=$(echo "$line" | grep -o '"id":"[^"]*"' | head -n 1 | cut -d '"' -f 4)
+            echo "{\"node_id\": \"$id\", \"status\": \"ok\", \"url\": \"shell://$id\"}"
+        done ;;
+esac
+"#;
+
+    fn plugin(name: &str, abi: u32) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let dir_name = format!("syndicate-json-canvas-plugin-test-{}-{}", name, std::process::id());
+        let dir = std::env::temp_dir().join(dir_name);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("shell");
+        std::fs::write(&path, SHELL_PLUGIN.replace("ABI", &abi.to_string())).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn round_trip_through_exec_batches() {
+        let path = plugin("round-trip", PLUGIN_ABI_VERSION);
+        let mut sink = PluginSink::load(&path, serde_json::json!({"token": "secret"})).unwrap();
+        let canvas = CanvasBuilder::new().text_node("a1e9000000000001", "Hello.").color(PresetColor::Red).build();
+        let items = to_syndication_format(&canvas.unwrap(), Some(default_process_node)).unwrap();
+
+        sink.publish(&items, false).unwrap();
+        let urls = sink.take_post_urls();
+        assert_eq!(urls.len(), 1);
+        assert_eq!(urls[0].1, "shell://a1e9000000000001");
+        let config = std::fs::read_to_string(path.with_file_name("config.json")).unwrap();
+        assert_eq!(config.trim(), r#"{"token":"secret"}"#);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn other_protocol_versions_are_rejected() {
+        let path = plugin("mismatch", 1);
+        let loaded = PluginSink::load(&path, serde_json::json!({}));
+        assert!(matches!(loaded, Err(PluginError::AbiMismatch { found: 1, expected: PLUGIN_ABI_VERSION, .. })));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
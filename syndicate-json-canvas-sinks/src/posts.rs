@@ -0,0 +1,1163 @@
+use crate::SinkError;
+use crate::canvases;
+use crate::filename::{FilenameParts, FilenameTemplate};
+use crate::frontmatter;
+use crate::legacy::{self, LegacyPost};
+use crate::tag_pages::{self, TaggedPost};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use syndicate_json_canvas_lib::{
+    Clock, DEFAULT_PREVIEW_DIFF_LINES, DestructionPlan, EdgeEmphasis, FileIssue, FileProblem, HtmlPolicy, PlannedFile,
+    PlannedWrite, RemovalPolicy, ShortIds, copy_dir_all, SyndicationFormat, SyndicationLinks, SystemClock, VerifyReport,
+    TextPolicy, Warning, WriteKind, cmp_node_ids, duplicates::content_hash, jsoncanvas::NodeId, sanitize_html,
+    warnings::codes, write_atomic,
+};
+use tracing::{debug, info, warn};
+
+/// Site path posts are served under; a post's permalink is this followed by its file name
+pub(crate) const PERMALINK_PREFIX: &str = "/t/";
+/// Hex digits of the canvas hash kept in `canvas_hash` frontmatter
+const PROVENANCE_HASH_LEN: usize = 12;
+
+/// Writes items as markdown files with frontmatter into a folder
+///
+/// Shared by the sinks that publish `<slug>-<node-id>.md` files (or names from another
+/// [`FilenameTemplate`]); version control, if any, is left to the sink.
+#[derive(Clone)]
+pub(crate) struct PostWriter {
+    /// Folder the files are written to
+    pub(crate) folder: PathBuf,
+    /// How raw HTML in node text is treated before writing files
+    pub(crate) html_policy: HtmlPolicy,
+    /// Whether footnote references are emitted as `references:` frontmatter
+    pub(crate) reference_frontmatter: bool,
+    /// Whether cross-reference links carry the `weight` of their edge
+    pub(crate) link_weights: bool,
+    /// Whether files carry `canvas`, `node_id` and `canvas_hash` frontmatter naming what
+    /// they were published from
+    pub(crate) provenance: bool,
+    /// Whether archived files also get `noindex: true` frontmatter
+    pub(crate) noindex_archived: bool,
+    /// Whether pruned posts are deleted or left as `withdrawn: true` stubs
+    pub(crate) removal_policy: RemovalPolicy,
+    /// Source of the `date` frontmatter
+    pub(crate) clock: Arc<dyn Clock>,
+    /// How files are named
+    pub(crate) filename_template: FilenameTemplate,
+    /// How titles, slugs and commit message previews are cut from node text
+    pub(crate) text: TextPolicy,
+    /// Lines of diff kept per modified file in dry-run previews
+    pub(crate) preview_diff_lines: usize,
+    /// Whether a page per tag, and one listing the tags, are kept in a `tags` subfolder
+    pub(crate) tag_pages: bool,
+    /// Files dry runs would have written, since the last `take_planned_writes`
+    planned_writes: Vec<PlannedWrite>,
+    /// Warnings raised since the last `take_warnings`
+    warnings: Vec<Warning>,
+}
+
+/// A post a wikilink resolves to, with its link text and href
+type LinkedPost = (NodeId, (String, String));
+
+/// A post wikilinks can name by its title
+struct TitledPost {
+    /// Stem of the canvas it was published from, `None` for legacy posts and posts written
+    /// before the folder's canvas manifest
+    canvas: Option<String>,
+    post: LinkedPost,
+}
+
+/// Posts by lowercased title, at most one per canvas
+type TitleIndex = HashMap<String, Vec<TitledPost>>;
+
+/// A legacy post recorded in the manifest, and its path
+type LegacyFile = (NodeId, PathBuf);
+
+impl PostWriter {
+    pub(crate) fn new(folder: PathBuf) -> Self {
+        Self {
+            folder,
+            // Plain markdown output, so raw HTML is passed through unless configured otherwise
+            html_policy: HtmlPolicy::AllowAll,
+            reference_frontmatter: false,
+            link_weights: false,
+            provenance: false,
+            noindex_archived: false,
+            removal_policy: RemovalPolicy::Delete,
+            clock: Arc::new(SystemClock),
+            filename_template: FilenameTemplate::default(),
+            text: TextPolicy::default(),
+            preview_diff_lines: DEFAULT_PREVIEW_DIFF_LINES,
+            tag_pages: false,
+            planned_writes: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// A writer for a copy of the folder at `root`, for simulated runs
+    pub(crate) fn sandboxed(&self, root: &Path) -> std::io::Result<Self> {
+        copy_dir_all(&self.folder, root)?;
+        Ok(Self { folder: root.to_path_buf(), planned_writes: Vec::new(), warnings: Vec::new(), ..self.clone() })
+    }
+
+    /// Check that the folder is one, or can be created in an existing folder
+    pub(crate) fn preflight(&self) -> Result<(), SinkError> {
+        if self.folder.exists() {
+            if !self.folder.is_dir() {
+                return Err(SinkError::Config(format!("{} is not a folder", self.folder.display())));
+            }
+            return Ok(());
+        }
+        match self.folder.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => {
+                Err(SinkError::Config(format!("parent folder {} does not exist", parent.display())))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Warnings raised since the last call
+    pub(crate) fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Files dry runs would have written since the last call, compared with the ones on disk
+    pub(crate) fn take_planned_writes(&mut self) -> Vec<PlannedWrite> {
+        std::mem::take(&mut self.planned_writes)
+    }
+
+    /// Files the dry runs since the last `take_planned_writes` would have created or changed
+    pub(crate) fn planned_changes(&self) -> impl Iterator<Item = &Path> {
+        self.planned_writes.iter().filter(|w| w.kind != WriteKind::Identical).map(|w| w.path.as_path())
+    }
+
+    /// Title of an item, as the text policy cuts it
+    fn title(&self, text: &str) -> String {
+        self.text.title(text)
+    }
+
+    /// Whether files carry a `node_id` frontmatter field
+    ///
+    /// Needed when names don't end in the node ID, so published files can still be matched
+    /// to their nodes, and part of the provenance keys. The default names are otherwise left
+    /// as they were.
+    fn writes_node_id(&self) -> bool {
+        self.provenance || self.filename_template != FilenameTemplate::default()
+    }
+
+    /// File names of all items, checked before anything is written
+    ///
+    /// A node that already has a post keeps its file name, so changing the template or
+    /// the text policy never moves published posts. Fails if a name is unsafe, if two
+    /// items would get the same name, or if a name is taken by a file that isn't this
+    /// node's post.
+    fn filenames(
+        &self,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+        date: &str,
+    ) -> Result<HashMap<NodeId, String>, SinkError> {
+        let mut published = self.list_posts()?;
+        published.sort_by(|a, b| b.1.cmp(&a.1));
+        let owners: HashMap<PathBuf, NodeId> =
+            published.iter().map(|(node_id, path)| (path.clone(), node_id.clone())).collect();
+        // Sorted in reverse, so a node with several posts keeps the first name
+        let existing: HashMap<NodeId, String> = published
+            .iter()
+            .filter_map(|(node_id, path)| Some((node_id.clone(), path.file_name()?.to_str()?.to_string())))
+            .collect();
+
+        let mut ordered: Vec<&SyndicationFormat> = all_items.values().collect();
+        ordered.sort_by(|a, b| cmp_node_ids(&a.id, &b.id));
+        // Unique among published nodes too, so a new short name never matches an old post's
+        let short_ids = ShortIds::new(all_items.keys().chain(existing.keys()));
+
+        let mut filenames: HashMap<NodeId, String> = HashMap::new();
+        let mut claimed: HashMap<String, &NodeId> = HashMap::new();
+        for item in ordered {
+            let filename = match existing.get(&item.id) {
+                Some(filename) => filename.clone(),
+                None => {
+                    let slug = self.text.slug(&item.text);
+                    let title = self.title(&item.text);
+                    let parts = FilenameParts {
+                        slug: &slug,
+                        id: item.id.as_str(),
+                        short_id: short_ids.get(&item.id),
+                        date,
+                        title: &title,
+                    };
+                    self.template_for(item)?
+                        .render(parts)
+                        .map_err(|e| SinkError::Config(e.to_string()).for_item(&item.id))?
+                }
+            };
+
+            if let Some(other) = claimed.insert(filename.clone(), &item.id) {
+                return Err(SinkError::Config(format!(
+                    "filename template `{}` gives nodes {} and {} the same file name `{}`",
+                    self.template_for(item)?, other, item.id, filename
+                )));
+            }
+            let path = self.folder.join(&filename);
+            if path.exists() && owners.get(&path) != Some(&item.id) {
+                let owner = owners
+                    .get(&path)
+                    .map_or_else(|| "a file that isn't a published post".to_string(), |n| format!("node {}", n));
+                return Err(SinkError::Config(format!("file name `{}` is already taken by {}", filename, owner))
+                    .for_item(&item.id));
+            }
+            filenames.insert(item.id.clone(), filename);
+        }
+        Ok(filenames)
+    }
+
+    /// How an item's file is named: by the template of its settings (with the writer's
+    /// extension), else by the writer's
+    fn template_for(&self, item: &SyndicationFormat) -> Result<FilenameTemplate, SinkError> {
+        let Some(template) = &item.settings.template else {
+            return Ok(self.filename_template.clone());
+        };
+        FilenameTemplate::parse(template)
+            .and_then(|template| template.with_extension(self.filename_template.extension()))
+            .map_err(|e| SinkError::Config(e.to_string()).for_item(&item.id))
+    }
+
+    /// Site URL of a published file
+    ///
+    /// Built from the file name alone, so platform path separators of the folder never
+    /// leak into links.
+    fn permalink(filename: &str) -> String {
+        format!("{}{}", PERMALINK_PREFIX, filename)
+    }
+
+    /// Add the `weight` (and `emphasis` label, if any) of a link's edge to its entry
+    fn push_emphasis(frontmatter: &mut String, emphasis: Option<&EdgeEmphasis>) {
+        let Some(emphasis) = emphasis else {
+            return;
+        };
+        frontmatter.push_str(&format!("    weight: {}\n", emphasis.weight));
+        if let Some(label) = &emphasis.label {
+            frontmatter.push_str(&format!("    emphasis: \"{}\"\n", Self::escape_yaml_string(label)));
+        }
+    }
+
+    /// Escape double quotes and backslashes for YAML string values
+    pub(crate) fn escape_yaml_string(s: &str) -> String {
+        s.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+
+    /// Generate file contents with frontmatter including cross-references
+    ///
+    /// `links` maps every linkable node (this batch and earlier ones) to its link text and
+    /// href; neighbors without an entry were never published and are left out.
+    fn generate_file_contents(
+        &self,
+        item: &SyndicationFormat,
+        title: &str,
+        links: &HashMap<NodeId, (String, String)>,
+        date: &str,
+    ) -> String {
+        // Each link is an object with link_text and href, and the emphasis of its edge
+        // when weights are included
+        let link_entries = |node_ids: &[NodeId]| -> Vec<(String, String, Option<&EdgeEmphasis>)> {
+            node_ids
+                .iter()
+                .filter_map(|node_id| {
+                    let (link_text, href) = links.get(node_id)?.clone();
+                    let emphasis = item.neighbor_emphasis.get(node_id).filter(|_| self.link_weights);
+                    Some((link_text, href, emphasis))
+                })
+                .collect()
+        };
+
+        // Build context_for_this list (in-neighbors with /t/ prefix)
+        let context_for_this = link_entries(&item.in_neighbor_ids);
+
+        // Build further_thinking list (out-neighbors with /t/ prefix)
+        let further_thinking = link_entries(&item.out_neighbor_ids);
+
+        // Format frontmatter with escaped strings
+        let mut frontmatter = format!(
+            "---\ntitle: \"{}\"\ndate: {}\n",
+            Self::escape_yaml_string(title), date
+        );
+
+        if self.provenance
+            && let Some(canvas) = &item.canvas_name
+        {
+            frontmatter.push_str(&format!("canvas: \"{}\"\n", Self::escape_yaml_string(canvas)));
+        }
+        if self.writes_node_id() || item.settings.template.is_some() {
+            frontmatter.push_str(&format!("node_id: \"{}\"\n", item.id.as_str()));
+        }
+        if self.provenance
+            && let Some(hash) = &item.canvas_hash
+        {
+            frontmatter.push_str(&format!("canvas_hash: \"{}\"\n", &hash[..hash.len().min(PROVENANCE_HASH_LEN)]));
+        }
+
+        // For the theme to render a link post as a link to the page
+        if let Some(url) = &item.url {
+            frontmatter.push_str(&format!("external_url: \"{}\"\n", Self::escape_yaml_string(url)));
+        }
+
+        if let Some(link_title) = item.link_meta.as_ref().and_then(|m| m.title.as_ref()) {
+            frontmatter.push_str(&format!("link_title: \"{}\"\n", Self::escape_yaml_string(link_title)));
+        }
+
+        if let Some(language) = &item.language {
+            frontmatter.push_str(&format!("lang: {}\n", language));
+        }
+
+        // For the theme to collapse the post behind the warning
+        if let Some(topic) = &item.content_warning {
+            frontmatter.push_str(&format!("content_warning: \"{}\"\n", Self::escape_yaml_string(topic)));
+        }
+
+        if let Some(category) = &item.category {
+            frontmatter.push_str(&format!("categories:\n  - \"{}\"\n", Self::escape_yaml_string(category)));
+        }
+
+        if let Some(tags) = item.settings.tags.as_ref().filter(|tags| !tags.is_empty()) {
+            frontmatter.push_str("tags:\n");
+            for tag in tags {
+                frontmatter.push_str(&format!("  - \"{}\"\n", Self::escape_yaml_string(tag)));
+            }
+        }
+
+        if !context_for_this.is_empty() {
+            frontmatter.push_str("context_for_this:\n");
+            for (link_text, href, emphasis) in context_for_this {
+                frontmatter.push_str(&format!("  - link_text: \"{}\"\n", Self::escape_yaml_string(&link_text)));
+                frontmatter.push_str(&format!("    href: \"{}\"\n", href));
+                Self::push_emphasis(&mut frontmatter, emphasis);
+            }
+        }
+
+        if !further_thinking.is_empty() {
+            frontmatter.push_str("further_thinking:\n");
+            for (link_text, href, emphasis) in further_thinking {
+                frontmatter.push_str(&format!("  - link_text: \"{}\"\n", Self::escape_yaml_string(&link_text)));
+                frontmatter.push_str(&format!("    href: \"{}\"\n", href));
+                Self::push_emphasis(&mut frontmatter, emphasis);
+            }
+        }
+
+        if self.reference_frontmatter && !item.references.is_empty() {
+            frontmatter.push_str("references:\n");
+            for reference in &item.references {
+                frontmatter.push_str(&format!("  - label: \"{}\"\n", Self::escape_yaml_string(&reference.label)));
+                frontmatter.push_str(&format!("    text: \"{}\"\n", Self::escape_yaml_string(&reference.text)));
+                if let Some(url) = &reference.url {
+                    frontmatter.push_str(&format!("    url: \"{}\"\n", Self::escape_yaml_string(url)));
+                }
+            }
+        }
+
+        frontmatter.push_str("---\n\n");
+
+        format!("{}{}", frontmatter, item.text)
+    }
+
+    /// Write the files of `items`, returning a summary fit for a commit message
+    ///
+    /// `all_items` is the whole batch when `items` is one chunk of it, so links between
+    /// chunks resolve. Returns `None` if there was nothing to write.
+    pub(crate) fn write_posts(
+        &mut self,
+        items: &HashMap<NodeId, SyndicationFormat>,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+        dry_run: bool,
+    ) -> Result<Option<String>, SinkError> {
+        if items.is_empty() {
+            info!("No items to publish");
+            return Ok(None);
+        }
+
+        // Pre-compute slugs for all items, including those in other chunks
+        let slugs: HashMap<NodeId, String> = all_items
+            .iter()
+            .map(|(node_id, item)| (node_id.clone(), self.text.slug(&item.text)))
+            .collect();
+
+        let summary = if items.len() == 1 {
+            let item = items.values().next().unwrap();
+            let slug = slugs.get(&item.id).unwrap();
+            format!("Adding microblog `{}`\n\n{}", slug, self.text.preview(&item.text))
+        } else {
+            format!("Update microblogs ({} posts)", items.len())
+        };
+
+        // Name every file up front, so a bad name fails the batch before anything is written
+        let date = self.clock.now().format("%Y-%m-%d").to_string();
+        let filenames = self.filenames(all_items, &date)?;
+        let published = self.published_index()?;
+        let mut links = self.resolve_links(items, all_items, &filenames, &published)?;
+        let mut canvases = self.post_canvases(all_items)?;
+        let titles = if items.values().any(|item| item.text.contains("[[")) {
+            self.title_index(all_items, &filenames, &canvases)?
+        } else {
+            HashMap::new()
+        };
+
+        // Write all files
+        let mut sanitize_report: Vec<(NodeId, Vec<String>)> = Vec::new();
+        for (node_id, item) in items.iter() {
+            let slug = slugs.get(node_id).unwrap();
+            let filename = &filenames[node_id];
+
+            let outcome = sanitize_html(&item.text, self.html_policy);
+            if !outcome.stripped.is_empty() {
+                sanitize_report.push((node_id.clone(), outcome.stripped));
+            }
+            let title = self.title(&outcome.text);
+            let canvas = item.canvas_name.as_deref().map(canvases::stem);
+            let (text, linked, ambiguous) = Self::link_wikilinks(&outcome.text, &titles, canvas);
+            for message in ambiguous {
+                warn!(node_id = %node_id, "{}", message);
+                self.warnings.push(Warning::new(codes::AMBIGUOUS_WIKILINK, "posts", message).for_node(node_id));
+            }
+            let mut item = SyndicationFormat { text, ..item.clone() };
+            for (linked_id, link) in linked {
+                if linked_id != item.id && !item.out_neighbor_ids.contains(&linked_id) {
+                    item.out_neighbor_ids.push(linked_id.clone());
+                }
+                links.insert(linked_id, link);
+            }
+            // A post written again keeps the date it was first published on
+            let date = self.existing_date(filename).unwrap_or_else(|| date.clone());
+            let contents = self.generate_file_contents(&item, &title, &links, &date);
+
+            debug!(
+                filename = %filename,
+                slug = %slug,
+                "Generated content"
+            );
+
+            self.write_file(node_id, filename, &contents, dry_run).map_err(|e| e.for_item(node_id))?;
+        }
+
+        if !dry_run && all_items.values().any(|item| item.canvas_name.is_some()) {
+            let present: HashSet<NodeId> = self.list_posts()?.into_iter().map(|(node_id, _)| node_id).collect();
+            canvases.retain(|node_id, _| present.contains(node_id));
+            canvases::save(&self.folder, &canvases)?;
+        }
+
+        for (node_id, stripped) in &sanitize_report {
+            warn!(node_id = %node_id, stripped = ?stripped, policy = ?self.html_policy, "Sanitized raw HTML");
+            let message = format!("Stripped raw HTML: {}", stripped.join(", "));
+            self.warnings.push(Warning::new(codes::HTML_STRIPPED, "sanitize", message).for_node(node_id));
+        }
+
+        Ok(Some(summary))
+    }
+
+    /// Link text and href of every node `items` link to, resolving neighbors outside the
+    /// batch against files published in earlier runs, and neighbors living elsewhere to
+    /// their URLs
+    fn resolve_links(
+        &self,
+        items: &HashMap<NodeId, SyndicationFormat>,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+        filenames: &HashMap<NodeId, String>,
+        published: &HashMap<NodeId, PathBuf>,
+    ) -> Result<HashMap<NodeId, (String, String)>, SinkError> {
+        let mut links: HashMap<NodeId, (String, String)> = all_items
+            .iter()
+            .map(|(node_id, item)| {
+                let href = Self::permalink(&filenames[node_id]);
+                (node_id.clone(), (self.title(&item.text), href))
+            })
+            .collect();
+        let legacy: HashMap<NodeId, LegacyPost> =
+            legacy::load(&self.folder)?.into_iter().map(|post| (post.node_id.clone(), post)).collect();
+
+        for item in items.values() {
+            for node_id in item.in_neighbor_ids.iter().chain(&item.out_neighbor_ids) {
+                // Neighbors living elsewhere are linked there, whatever was published of them
+                if let Some(external) = item.external_neighbors.get(node_id) {
+                    links.insert(node_id.clone(), (self.title(&external.text), external.url.clone()));
+                    continue;
+                }
+                if links.contains_key(node_id) {
+                    continue;
+                }
+                if let Some(post) = legacy.get(node_id) {
+                    links.insert(node_id.clone(), (post.title.clone(), Self::permalink(&post.file)));
+                    continue;
+                }
+                let Some(path) = published.get(node_id) else {
+                    continue;
+                };
+                let Some(filename) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                let contents = std::fs::read_to_string(path).map_err(|e| SinkError::from(e).for_item(node_id))?;
+                let link_text = Self::read_frontmatter_string(&contents, "title").unwrap_or_default();
+                debug!(node_id = %node_id, file = %filename, "Linking to previously published neighbor");
+                links.insert(node_id.clone(), (link_text, Self::permalink(filename)));
+            }
+        }
+
+        Ok(links)
+    }
+
+    /// Canvas stem of every post in the folder and in this batch, by node ID
+    ///
+    /// Read from the folder's canvas manifest. A folder without one was written before the
+    /// manifest was kept: its posts are taken to be from the canvas their `canvas`
+    /// frontmatter names, else from the canvas of this batch.
+    fn post_canvases(
+        &self,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+    ) -> Result<HashMap<NodeId, String>, SinkError> {
+        let batch_canvas = all_items.values().find_map(|item| item.canvas_name.as_deref()).map(canvases::stem);
+        let mut post_canvases = match (canvases::load(&self.folder)?, batch_canvas) {
+            (Some(recorded), _) => recorded,
+            (None, Some(canvas)) => {
+                let mut migrated = HashMap::new();
+                for (node_id, path) in self.list_posts()? {
+                    let recorded = std::fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|contents| Self::read_frontmatter_string(&contents, "canvas"));
+                    let recorded = recorded.as_deref().map(canvases::stem).unwrap_or(canvas);
+                    migrated.insert(node_id, recorded.to_string());
+                }
+                migrated
+            }
+            (None, None) => HashMap::new(),
+        };
+        for (node_id, item) in all_items {
+            if let Some(canvas) = &item.canvas_name {
+                post_canvases.insert(node_id.clone(), canvases::stem(canvas).to_string());
+            }
+        }
+        Ok(post_canvases)
+    }
+
+    /// Link text and href of every post by its lowercased title, for resolving wikilinks
+    ///
+    /// Within a canvas, posts of this batch win over earlier ones of the same title, which
+    /// win over legacy posts.
+    fn title_index(
+        &self,
+        all_items: &HashMap<NodeId, SyndicationFormat>,
+        filenames: &HashMap<NodeId, String>,
+        post_canvases: &HashMap<NodeId, String>,
+    ) -> Result<TitleIndex, SinkError> {
+        let mut titles = TitleIndex::new();
+        let mut index = |title: String, node_id: NodeId, href: String| {
+            let canvas = post_canvases.get(&node_id).cloned();
+            let posts = titles.entry(title.to_lowercase()).or_default();
+            posts.retain(|p| p.canvas != canvas && p.post.0 != node_id);
+            posts.push(TitledPost { canvas, post: (node_id, (title, href)) });
+        };
+        for post in legacy::load(&self.folder)? {
+            index(post.title, post.node_id, Self::permalink(&post.file));
+        }
+        for (node_id, path) in self.list_posts()? {
+            let filename = path.file_name().and_then(|n| n.to_str());
+            let (Ok(contents), Some(filename)) = (std::fs::read_to_string(&path), filename) else {
+                continue;
+            };
+            if let Some(title) = Self::read_frontmatter_string(&contents, "title") {
+                index(title, node_id, Self::permalink(filename));
+            }
+        }
+        for (node_id, item) in all_items {
+            index(self.title(&item.text), node_id.clone(), Self::permalink(&filenames[node_id]));
+        }
+        Ok(titles)
+    }
+
+    /// The post a wikilink target names, and whether it was picked from several canvases
+    ///
+    /// `reading#Title` names the post titled `Title` of the `reading` canvas; if there is
+    /// none, `#` starts a heading as usual. A bare title names the post of the linking
+    /// `canvas`, else the only canvas with one, else the first canvas by name.
+    fn resolve_wikilink<'a>(
+        titles: &'a TitleIndex,
+        target: &str,
+        canvas: Option<&str>,
+    ) -> Option<(&'a TitledPost, Vec<&'a str>)> {
+        let (title, heading) = target.split_once('#').map_or((target, None), |(t, h)| (t, Some(h)));
+        if let Some(heading) = heading {
+            let named = titles
+                .get(&heading.trim().to_lowercase())
+                .and_then(|posts| posts.iter().find(|p| p.canvas.as_deref() == Some(title.trim())));
+            if let Some(post) = named {
+                return Some((post, Vec::new()));
+            }
+        }
+        let posts = titles.get(&title.trim().to_lowercase())?;
+        if let Some(own) = posts.iter().find(|p| p.canvas.is_some() && p.canvas.as_deref() == canvas) {
+            return Some((own, Vec::new()));
+        }
+        let mut known: Vec<&TitledPost> = posts.iter().filter(|p| p.canvas.is_some()).collect();
+        known.sort_by(|a, b| a.canvas.cmp(&b.canvas).then_with(|| cmp_node_ids(&a.post.0, &b.post.0)));
+        match known.as_slice() {
+            [] => posts.first().map(|post| (post, Vec::new())),
+            [post] => Some((post, Vec::new())),
+            [first, ..] => Some((first, known.iter().filter_map(|p| p.canvas.as_deref()).collect())),
+        }
+    }
+
+    /// Turn `[[Title]]` and `[[Title|text]]` wikilinks to posts into markdown links,
+    /// returning the posts linked to and a message per link picked from several canvases
+    ///
+    /// Titles match whatever their case; `[[canvas#Title]]` names another canvas's post
+    /// (see [`Self::resolve_wikilink`]), and otherwise a `#heading` part is dropped. Embeds
+    /// (`![[...]]`) and links to titles of no post are left as written.
+    fn link_wikilinks(
+        text: &str,
+        titles: &TitleIndex,
+        canvas: Option<&str>,
+    ) -> (String, Vec<LinkedPost>, Vec<String>) {
+        if titles.is_empty() {
+            return (text.to_string(), Vec::new(), Vec::new());
+        }
+        let mut linked = Vec::new();
+        let mut ambiguous = Vec::new();
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("[[") {
+            let Some(len) = rest[start + 2..].find("]]") else {
+                break;
+            };
+            let end = start + 2 + len + 2;
+            let inner = &rest[start + 2..end - 2];
+            let (target, alias) = inner.split_once('|').unwrap_or((inner, ""));
+            match Self::resolve_wikilink(titles, target, canvas) {
+                Some((post, candidates)) if !rest[..start].ends_with('!') => {
+                    let (node_id, (title, href)) = &post.post;
+                    let link_text = if alias.trim().is_empty() { title.as_str() } else { alias.trim() };
+                    out.push_str(&rest[..start]);
+                    out.push_str(&format!("[{}]({})", link_text, href));
+                    linked.push((node_id.clone(), (title.clone(), href.clone())));
+                    if !candidates.is_empty() {
+                        ambiguous.push(format!(
+                            "[[{}]] matches posts of canvases {}; linked the one of {}",
+                            target.trim(),
+                            candidates.join(", "),
+                            candidates[0]
+                        ));
+                    }
+                }
+                _ => out.push_str(&rest[..end]),
+            }
+            rest = &rest[end..];
+        }
+        out.push_str(rest);
+        (out, linked, ambiguous)
+    }
+
+    /// A quoted string frontmatter value (e.g. `title`) of a published file
+    fn read_frontmatter_string(contents: &str, key: &str) -> Option<String> {
+        let frontmatter = contents.strip_prefix("---\n")?.split("\n---\n").next()?;
+        let prefix = format!("{}: \"", key);
+        let raw = frontmatter
+            .lines()
+            .find_map(|line| line.strip_prefix(prefix.as_str()))?
+            .strip_suffix('"')?;
+        Some(raw.replace("\\\"", "\"").replace("\\\\", "\\"))
+    }
+
+    /// The published file of every node
+    ///
+    /// If a node has several (its text, and so its slug, changed), any one of them.
+    fn published_index(&self) -> Result<HashMap<NodeId, PathBuf>, SinkError> {
+        Ok(self.list_posts()?.into_iter().collect())
+    }
+
+    /// Add or remove the `archived` (and `noindex`) frontmatter flags
+    fn set_archive_flags(contents: &str, archived: bool, noindex: bool) -> String {
+        let Some(rest) = contents.strip_prefix("---\n") else {
+            return contents.to_string();
+        };
+        let Some(end) = rest.find("---\n") else {
+            return contents.to_string();
+        };
+        let (frontmatter, body) = rest.split_at(end);
+
+        let mut lines: Vec<&str> = frontmatter
+            .lines()
+            .filter(|line| !line.starts_with("archived:") && !line.starts_with("noindex:"))
+            .collect();
+        if archived {
+            lines.push("archived: true");
+            if noindex {
+                lines.push("noindex: true");
+            }
+        }
+
+        format!("---\n{}\n{}", lines.join("\n"), body)
+    }
+
+    /// Files whose contents change when the archive flags are set to `archived`
+    fn archive_edits(&mut self, node_ids: &[NodeId], archived: bool) -> Result<Vec<(PathBuf, String)>, SinkError> {
+        let published = self.published_index()?;
+        let mut edits = Vec::new();
+        for node_id in node_ids {
+            let Some(path) = published.get(node_id) else {
+                warn!(node_id = %node_id, "No published file found to (un)archive");
+                let message = "No published file found to (un)archive";
+                self.warnings.push(Warning::new(codes::ARCHIVE_MISSING_FILE, "archive", message).for_node(node_id));
+                continue;
+            };
+            let contents = std::fs::read_to_string(path).map_err(|e| SinkError::from(e).for_item(node_id))?;
+            let updated = Self::set_archive_flags(&contents, archived, self.noindex_archived);
+            if updated != contents {
+                edits.push((path.clone(), updated));
+            }
+        }
+        Ok(edits)
+    }
+
+    /// Rewrite the files of the given nodes with or without the archive flags
+    ///
+    /// Returns a summary fit for a commit message, or `None` if no file changed.
+    pub(crate) fn change_archive_state(
+        &mut self,
+        node_ids: &[NodeId],
+        archived: bool,
+        dry_run: bool,
+    ) -> Result<Option<String>, SinkError> {
+        let action = if archived { "Archive" } else { "Unarchive" };
+
+        let edits = self.archive_edits(node_ids, archived)?;
+        if edits.is_empty() {
+            info!(count = node_ids.len(), "{} is a no-op, files already up to date", action);
+            return Ok(None);
+        }
+
+        for (path, contents) in &edits {
+            if dry_run {
+                debug!(file = %path.display(), contents = %contents, "[DRY RUN] Would rewrite file");
+                continue;
+            }
+            write_atomic(path, contents)?;
+            debug!(file = %path.display(), archived, "Rewrote file");
+        }
+
+        Ok(Some(format!("{} microblogs ({} posts)", action, edits.len())))
+    }
+
+    /// Every file of the given nodes, with its title and a hash of its contents
+    pub(crate) fn plan_removal(&self, sink: &str, node_ids: &[NodeId]) -> Result<DestructionPlan, SinkError> {
+        let mut plan = DestructionPlan::new(sink).with_policy(self.removal_policy);
+        for (node_id, path) in self.list_posts()? {
+            if !node_ids.contains(&node_id) {
+                continue;
+            }
+            let contents = std::fs::read_to_string(&path)?;
+            plan.files.push(PlannedFile {
+                node_id,
+                title: Self::read_frontmatter_string(&contents, "title"),
+                content_hash: content_hash(&contents),
+                path,
+            });
+        }
+        plan.files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(plan)
+    }
+
+    /// Delete the files listed in a plan, or replace them with tombstones
+    ///
+    /// Returns a summary fit for a commit message, or `None` if the plan has no files.
+    pub(crate) fn remove_files(&self, plan: &DestructionPlan, dry_run: bool) -> Result<Option<String>, SinkError> {
+        if plan.files.is_empty() {
+            return Ok(None);
+        }
+        if plan.policy == RemovalPolicy::Tombstone {
+            return self.leave_tombstones(plan, dry_run);
+        }
+
+        for file in &plan.files {
+            if dry_run {
+                debug!(file = %file.path.display(), "[DRY RUN] Would delete file");
+                continue;
+            }
+            match std::fs::remove_file(&file.path) {
+                Ok(()) => debug!(file = %file.path.display(), "Deleted file"),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if !dry_run {
+            self.unlink_removed(plan)?;
+        }
+
+        Ok(Some(format!("Remove microblogs ({} posts)", plan.files.len())))
+    }
+
+    /// Drop the links to deleted posts from the remaining ones: their `context_for_this`
+    /// and `further_thinking` entries, and body links, which are left as plain text
+    fn unlink_removed(&self, plan: &DestructionPlan) -> Result<(), SinkError> {
+        let hrefs: HashSet<String> = plan
+            .files
+            .iter()
+            .filter_map(|file| file.path.file_name().and_then(|n| n.to_str()))
+            .map(Self::permalink)
+            .collect();
+        let is_link = |line: &str| {
+            line.trim_start()
+                .strip_prefix("href:")
+                .is_some_and(|href| hrefs.contains(href.trim().trim_matches('"')))
+        };
+
+        for (node_id, path) in self.list_posts()? {
+            let contents = std::fs::read_to_string(&path).map_err(|e| SinkError::from(e).for_item(&node_id))?;
+            let Ok(mut document) = frontmatter::Document::parse(&contents) else {
+                continue;
+            };
+            let mut changed = document.remove_items("context_for_this", is_link);
+            changed |= document.remove_items("further_thinking", is_link);
+            let mut unlinked = document.render();
+            for href in &hrefs {
+                changed |= Self::unlink_body(&mut unlinked, href);
+            }
+            if changed {
+                write_atomic(&path, unlinked).map_err(|e| SinkError::from(e).for_item(&node_id))?;
+                debug!(file = %path.display(), "Removed links to deleted posts");
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn every `[text](href)` in `contents` into `text`; returns whether there were any
+    fn unlink_body(contents: &mut String, href: &str) -> bool {
+        let target = format!("]({})", href);
+        let mut changed = false;
+        while let Some(end) = contents.find(&target) {
+            let Some(start) = contents[..end].rfind('[') else {
+                break;
+            };
+            let text = contents[start + 1..end].to_string();
+            contents.replace_range(start..end + target.len(), &text);
+            changed = true;
+        }
+        changed
+    }
+
+    /// The `date` frontmatter of the post already at `filename`, if there is one
+    fn existing_date(&self, filename: &str) -> Option<String> {
+        let contents = std::fs::read_to_string(self.folder.join(filename)).ok()?;
+        let keys = frontmatter::parse(&contents).ok()?;
+        frontmatter::get(&keys, "date").filter(|date| !date.is_empty()).map(str::to_string)
+    }
+
+    /// Replace the files listed in a plan with stubs keeping their name, date and node, but
+    /// none of their text
+    fn leave_tombstones(&self, plan: &DestructionPlan, dry_run: bool) -> Result<Option<String>, SinkError> {
+        let withdrawn_at = self.clock.now().format("%Y-%m-%d").to_string();
+        for file in &plan.files {
+            let contents = std::fs::read_to_string(&file.path).map_err(|e| SinkError::from(e).for_item(&file.node_id))?;
+            let date = frontmatter::parse(&contents)
+                .ok()
+                .and_then(|keys| frontmatter::get(&keys, "date").map(str::to_string))
+                .unwrap_or_else(|| withdrawn_at.clone());
+            let mut stub = format!("---\ntitle: \"Withdrawn\"\ndate: {}\n", date);
+            if self.writes_node_id() {
+                stub.push_str(&format!("node_id: \"{}\"\n", file.node_id.as_str()));
+            }
+            stub.push_str(&format!("withdrawn: true\nwithdrawn_at: {}\n---\n\n", withdrawn_at));
+            stub.push_str("This post was withdrawn.\n");
+
+            if dry_run {
+                debug!(file = %file.path.display(), contents = %stub, "[DRY RUN] Would replace file with a tombstone");
+                continue;
+            }
+            write_atomic(&file.path, stub).map_err(|e| SinkError::from(e).for_item(&file.node_id))?;
+            debug!(file = %file.path.display(), "Replaced file with a tombstone");
+        }
+
+        Ok(Some(format!("Withdraw microblogs ({} posts)", plan.files.len())))
+    }
+
+    /// Whether a post is a tombstone left by [`RemovalPolicy::Tombstone`]
+    fn is_withdrawn(contents: &str) -> bool {
+        frontmatter::parse(contents).is_ok_and(|keys| frontmatter::get(&keys, "withdrawn") == Some("true"))
+    }
+
+    /// Merge the URLs of posts on other sites into the `syndication:` list of each node's
+    /// file, leaving other keys and the body untouched
+    ///
+    /// URLs already listed are skipped, so backfilling again changes nothing. Files whose
+    /// frontmatter doesn't parse are left alone with a warning. Returns the number of
+    /// files changed.
+    pub(crate) fn add_syndication_links(
+        &self,
+        links: &SyndicationLinks,
+        dry_run: bool,
+    ) -> Result<usize, SinkError> {
+        let published = self.published_index()?;
+        let mut changed = 0;
+        for (node_id, urls) in links {
+            let Some(path) = published.get(node_id) else {
+                debug!(node_id = %node_id, "No published file to add syndication links to");
+                continue;
+            };
+            let contents = std::fs::read_to_string(path).map_err(|e| SinkError::from(e).for_item(node_id))?;
+            let mut document = match frontmatter::Document::parse(&contents) {
+                Ok(document) => document,
+                Err(reason) => {
+                    warn!(file = %path.display(), reason = %reason, "Can't add syndication links, frontmatter doesn't parse");
+                    continue;
+                }
+            };
+
+            let mut syndication = document.list("syndication");
+            let before = syndication.len();
+            for (_, url) in urls {
+                if !syndication.contains(url) {
+                    syndication.push(url.clone());
+                }
+            }
+            if syndication.len() == before {
+                continue;
+            }
+            document.set_list("syndication", &syndication);
+            let updated = document.render();
+
+            changed += 1;
+            if dry_run {
+                debug!(file = %path.display(), contents = %updated, "[DRY RUN] Would add syndication links");
+                continue;
+            }
+            write_atomic(path, updated).map_err(|e| SinkError::from(e).for_item(node_id))?;
+            debug!(file = %path.display(), links = syndication.len() - before, "Added syndication links");
+        }
+        Ok(changed)
+    }
+
+    /// Posts in the folder, by the node they were published from, without tombstones
+    ///
+    /// Only looked for under [`RemovalPolicy::Tombstone`], since finding them means reading
+    /// every post.
+    pub(crate) fn list_published(&self) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        let mut published = self.list_posts()?;
+        if self.removal_policy == RemovalPolicy::Tombstone {
+            published.retain(|(_, path)| {
+                !std::fs::read_to_string(path).is_ok_and(|contents| Self::is_withdrawn(&contents))
+            });
+        }
+        Ok(published)
+    }
+
+    /// Posts in the folder, tombstones included, by the node they were published from
+    ///
+    /// A post's node is its `node_id` frontmatter if it has one, else the part of the name
+    /// after the last `-` (which holds for Obsidian's hex IDs in `<slug>-<node-id>.md`).
+    /// Legacy posts are left out, so nothing that goes by this list touches them.
+    fn list_posts(&self) -> Result<Vec<(NodeId, PathBuf)>, SinkError> {
+        if !self.folder.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let suffix = format!(".{}", self.filename_template.extension());
+        let legacy: HashSet<String> = legacy::load(&self.folder)?.into_iter().map(|post| post.file).collect();
+        let mut published = Vec::new();
+        for entry in std::fs::read_dir(&self.folder)? {
+            let path = entry?.path();
+            let Some((file_name, stem)) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| Some((n, n.strip_suffix(suffix.as_str())?)))
+            else {
+                continue;
+            };
+            if legacy.contains(file_name) {
+                continue;
+            }
+            // Names from another template (the sink's or a group's) may not end in the node ID
+            let last_part = stem.rsplit('-').next().unwrap_or(stem);
+            let from_frontmatter = if self.writes_node_id() || !legacy::is_obsidian_id(last_part) {
+                std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|contents| Self::read_frontmatter_string(&contents, "node_id"))
+            } else {
+                None
+            };
+            let id = from_frontmatter.unwrap_or_else(|| last_part.to_string());
+            if let Ok(node_id) = id.parse::<NodeId>() {
+                published.push((node_id, path));
+            }
+        }
+        Ok(published)
+    }
+
+    /// Record the posts in the folder that no tracked node published, and that aren't
+    /// recorded yet, in the legacy manifest
+    ///
+    /// A post's ID is its `node_id` frontmatter, else the Obsidian node ID its name ends in,
+    /// else a synthetic `legacy-...` one; its title is its `title` frontmatter, else cut from
+    /// its first line. Returns the posts recorded and a summary fit for a commit message, if any.
+    pub(crate) fn import_legacy(
+        &self,
+        is_tracked: &dyn Fn(&NodeId) -> bool,
+        dry_run: bool,
+    ) -> Result<(Vec<LegacyFile>, Option<String>), SinkError> {
+        let mut manifest = legacy::load(&self.folder)?;
+        let mut candidates = self.list_posts()?;
+        candidates.sort_by(|a, b| a.1.cmp(&b.1));
+
+        let mut imported = Vec::new();
+        for (listed_id, path) in candidates {
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let contents = std::fs::read_to_string(&path)?;
+            if is_tracked(&listed_id) || Self::is_withdrawn(&contents) {
+                continue;
+            }
+            let keys = frontmatter::parse(&contents).unwrap_or_default();
+            let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+            let node_id = frontmatter::get(&keys, "node_id")
+                .and_then(|id| id.parse::<NodeId>().ok())
+                .unwrap_or_else(|| legacy::id_from_name(file_name, stem));
+            if is_tracked(&node_id) {
+                continue;
+            }
+            let title = match frontmatter::get(&keys, "title").filter(|t| !t.is_empty()) {
+                Some(title) => title.to_string(),
+                None => {
+                    let first_line = Self::body(&contents).lines().find(|line| !line.trim().is_empty());
+                    self.title(first_line.unwrap_or_default())
+                }
+            };
+            let date = frontmatter::get(&keys, "date").filter(|d| !d.is_empty()).map(str::to_string);
+
+            debug!(file = %file_name, node_id = %node_id, title = %title, "Importing legacy post");
+            manifest.push(LegacyPost { node_id: node_id.clone(), file: file_name.to_string(), title, date });
+            imported.push((node_id, path));
+        }
+
+        if imported.is_empty() {
+            return Ok((imported, None));
+        }
+        if dry_run {
+            debug!(count = imported.len(), "[DRY RUN] Would record legacy posts");
+        } else {
+            legacy::save(&self.folder, &mut manifest)?;
+        }
+        let summary = format!("Import legacy microblogs ({} posts)", imported.len());
+        Ok((imported, Some(summary)))
+    }
+
+    /// Rebuild the tag pages from the `tags` frontmatter of the posts, if they're kept
+    ///
+    /// Tombstones are left out, like posts whose frontmatter doesn't parse. Dry runs only
+    /// log, as the posts on disk aren't the ones the run would have written.
+    pub(crate) fn write_tag_pages(&self, dry_run: bool) -> Result<(), SinkError> {
+        if !self.tag_pages {
+            return Ok(());
+        }
+        if dry_run {
+            debug!(folder = %self.folder.display(), "[DRY RUN] Would rebuild tag pages");
+            return Ok(());
+        }
+
+        let mut posts = Vec::new();
+        for (node_id, path) in self.list_published()? {
+            let contents = std::fs::read_to_string(&path).map_err(|e| SinkError::from(e).for_item(&node_id))?;
+            let (Ok(keys), Ok(document)) = (frontmatter::parse(&contents), frontmatter::Document::parse(&contents))
+            else {
+                continue;
+            };
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            posts.push(TaggedPost {
+                title: frontmatter::get(&keys, "title").unwrap_or(file_name).to_string(),
+                date: frontmatter::get(&keys, "date").unwrap_or_default().to_string(),
+                href: Self::permalink(file_name),
+                tags: document.list("tags"),
+            });
+        }
+        let extension = self.filename_template.extension();
+        let changed = tag_pages::sync(&self.folder, &posts, &self.text, PERMALINK_PREFIX, extension)?;
+        if changed > 0 {
+            info!(folder = %self.folder.display(), files = changed, "Rebuilt tag pages");
+        }
+        Ok(())
+    }
+
+    /// Text of a post after its frontmatter, or all of it if it has none
+    fn body(contents: &str) -> &str {
+        contents
+            .strip_prefix("---\n")
+            .and_then(|rest| rest.split_once("\n---\n"))
+            .map_or(contents, |(_, body)| body)
+    }
+
+    /// Check that every post in the folder has parseable frontmatter with a title and date,
+    /// and that a `node_id` in the frontmatter matches the file name. Read-only.
+    pub(crate) fn verify(&self, sink: &str) -> Result<VerifyReport, SinkError> {
+        let mut report = VerifyReport { sink: sink.to_string(), files_checked: 0, issues: Vec::new() };
+        let mut paths: Vec<PathBuf> = self.list_posts()?.into_iter().map(|(_, path)| path).collect();
+        paths.sort();
+        paths.dedup();
+
+        for path in paths {
+            report.files_checked += 1;
+            let contents = std::fs::read_to_string(&path)?;
+            let mut problems = Vec::new();
+            match frontmatter::parse(&contents) {
+                Err(reason) => problems.push(FileProblem::InvalidFrontmatter(reason)),
+                Ok(keys) => {
+                    for key in ["title", "date"] {
+                        if frontmatter::get(&keys, key).is_none_or(str::is_empty) {
+                            problems.push(FileProblem::MissingKey(key.to_string()));
+                        }
+                    }
+                    if let Some(date) = frontmatter::get(&keys, "date").filter(|d| !d.is_empty())
+                        && chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err()
+                    {
+                        problems.push(FileProblem::InvalidFrontmatter(format!("`date: {}` isn't YYYY-MM-DD", date)));
+                    }
+                    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                    if let Some(node_id) = frontmatter::get(&keys, "node_id")
+                        && self.filename_template.includes_id()
+                        && !file_name.contains(node_id)
+                    {
+                        problems.push(FileProblem::IdMismatch {
+                            file_name: file_name.to_string(),
+                            node_id: node_id.to_string(),
+                        });
+                    }
+                }
+            }
+            report.issues.extend(problems.into_iter().map(|problem| FileIssue { path: path.clone(), problem }));
+        }
+        Ok(report)
+    }
+
+    /// Write a file into the folder
+    fn write_file(&mut self, node_id: &NodeId, filename: &str, contents: &str, dry_run: bool) -> Result<(), SinkError> {
+        let file_path = self.folder.join(filename);
+
+        // When dry, compare with the published file instead of writing
+        if dry_run {
+            let planned = PlannedWrite::classify(&file_path, contents, self.preview_diff_lines)?;
+            debug!(
+                file = %file_path.display(),
+                kind = ?planned.kind,
+                contents = %contents,
+                "[DRY RUN] Would write file"
+            );
+            self.planned_writes.push(planned.with_node_id(node_id.as_str()));
+            return Ok(());
+        }
+
+        // Ensure the folder exists
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        write_atomic(&file_path, contents)?;
+        debug!(file = %file_path.display(), "Wrote file");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+//! A small local web server for eyeballing the posts a run would write before pushing them
+//!
+//! [`PreviewSite`] renders a folder of markdown posts (usually a sink's output in a
+//! [`Syndicator::simulate_in`] sandbox), and [`PreviewServer`] serves it over plain HTTP:
+//!
+//! - `/` lists every post
+//! - `/posts/<file>` shows one: its frontmatter as a table, then its body as HTML
+//! - `/version` is the site's version; pages poll it and reload once the site is replaced
+//!
+//! Site permalinks (`/t/<file>`) are rewired to `/posts/<file>`, so links between posts
+//! work within the preview.
+//!
+//! [`Syndicator::simulate_in`]: syndicate_json_canvas_lib::Syndicator::simulate_in
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use syndicate_json_canvas_lib::MarkdownRenderer;
+use tracing::{debug, warn};
+
+use crate::SinkError;
+use crate::consistency::posts_in;
+use crate::frontmatter::Document;
+use crate::posts::PERMALINK_PREFIX;
+
+/// Route a post is served under, followed by its file name
+pub const POSTS_ROUTE: &str = "/posts/";
+/// Route serving the site's version, polled by pages for live reload
+pub const VERSION_ROUTE: &str = "/version";
+/// How often pages poll [`VERSION_ROUTE`]
+const RELOAD_POLL_MS: u32 = 1000;
+/// How long a connection may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A post rendered for the preview
+#[derive(Debug, Clone)]
+pub struct PreviewPost {
+    /// File name, which is also its route below [`POSTS_ROUTE`]
+    pub file: String,
+    /// The `title` frontmatter, or the file name
+    pub title: String,
+    /// Each frontmatter key with its value as HTML
+    pub frontmatter: Vec<(String, String)>,
+    /// The body rendered as HTML, with site links rewired
+    pub html: String,
+}
+
+/// The posts of a folder, rendered for [`PreviewServer`]
+#[derive(Debug, Clone, Default)]
+pub struct PreviewSite {
+    posts: Vec<PreviewPost>,
+}
+
+impl PreviewSite {
+    /// Render every markdown post directly in `folder`, sorted by file name
+    ///
+    /// A post whose frontmatter doesn't parse is shown whole as its body.
+    pub fn load(folder: &Path, renderer: &dyn MarkdownRenderer) -> Result<Self, SinkError> {
+        let mut posts = Vec::new();
+        for path in posts_in(folder)? {
+            let file = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+            let contents = std::fs::read_to_string(&path)?;
+            let post = match Document::parse(&contents) {
+                Ok(document) => PreviewPost {
+                    title: document.list("title").into_iter().next().unwrap_or_else(|| file.clone()),
+                    frontmatter: document
+                        .keys()
+                        .map(|key| (key.to_string(), frontmatter_cell(&document, key)))
+                        .collect(),
+                    html: rewire_links(&renderer.render(document.body())),
+                    file,
+                },
+                Err(reason) => {
+                    warn!(file = %path.display(), reason = %reason, "Previewing post with invalid frontmatter as is");
+                    PreviewPost {
+                        title: file.clone(),
+                        frontmatter: Vec::new(),
+                        html: rewire_links(&renderer.render(&contents)),
+                        file,
+                    }
+                }
+            };
+            posts.push(post);
+        }
+        Ok(Self { posts })
+    }
+
+    pub fn posts(&self) -> &[PreviewPost] {
+        &self.posts
+    }
+
+    fn index_page(&self, version: u64) -> String {
+        let mut body = format!("<h1>{} posts</h1>\n<ul>\n", self.posts.len());
+        for post in &self.posts {
+            body.push_str(&format!(
+                "<li><a href=\"{}{}\">{}</a> <small>{}</small></li>\n",
+                POSTS_ROUTE,
+                escape(&post.file),
+                escape(&post.title),
+                escape(&post.file)
+            ));
+        }
+        body.push_str("</ul>\n");
+        page("Preview", &body, version)
+    }
+
+    fn post_page(&self, file: &str, version: u64) -> Option<String> {
+        let post = self.posts.iter().find(|post| post.file == file)?;
+        let mut body = format!("<p><a href=\"/\">All posts</a></p>\n<h1>{}</h1>\n<table>\n", escape(&post.title));
+        for (key, value) in &post.frontmatter {
+            body.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", escape(key), value));
+        }
+        body.push_str("</table>\n<hr>\n");
+        body.push_str(&post.html);
+        Some(page(&post.title, &body, version))
+    }
+}
+
+/// The site being served and its version, bumped each time it's replaced
+type SharedSite = Arc<Mutex<(u64, PreviewSite)>>;
+
+/// Serves a [`PreviewSite`] on a local port, one connection at a time
+pub struct PreviewServer {
+    listener: TcpListener,
+    site: SharedSite,
+}
+
+impl PreviewServer {
+    /// Listen on `addr`, e.g. `127.0.0.1:0` for any free port
+    pub fn bind(addr: impl ToSocketAddrs, site: PreviewSite) -> std::io::Result<Self> {
+        Ok(Self { listener: TcpListener::bind(addr)?, site: Arc::new(Mutex::new((1, site))) })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// A handle for replacing the site while it's served
+    pub fn handle(&self) -> PreviewHandle {
+        PreviewHandle { site: self.site.clone() }
+    }
+
+    /// Answer requests until the listener fails; a failed connection is logged and dropped
+    pub fn serve(&self) -> std::io::Result<()> {
+        for stream in self.listener.incoming() {
+            if let Err(e) = self.answer(stream?) {
+                warn!(error = %e, "Failed to answer preview request");
+            }
+        }
+        Ok(())
+    }
+
+    fn answer(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // Skip the headers; every route is a plain GET
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+        let path = percent_decode(target.split(['?', '#']).next().unwrap_or_default());
+        debug!(method = %method, path = %path, "Preview request");
+
+        let (status, content_type, body) = if method != "GET" {
+            ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string())
+        } else {
+            let (version, site) = &*self.site.lock().unwrap();
+            let found = match path.as_str() {
+                "/" => Some(("text/html", site.index_page(*version))),
+                VERSION_ROUTE => Some(("text/plain", version.to_string())),
+                _ => path
+                    .strip_prefix(POSTS_ROUTE)
+                    .and_then(|file| site.post_page(file, *version))
+                    .map(|page| ("text/html", page)),
+            };
+            match found {
+                Some((content_type, body)) => ("200 OK", content_type, body),
+                None => ("404 Not Found", "text/plain", format!("No post at {}\n", path)),
+            }
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\n\
+             Connection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+}
+
+/// Replaces the site a [`PreviewServer`] serves, from another thread
+#[derive(Clone)]
+pub struct PreviewHandle {
+    site: SharedSite,
+}
+
+impl PreviewHandle {
+    /// Serve `site` from now on; open pages reload on their next poll
+    pub fn replace(&self, site: PreviewSite) {
+        let mut shared = self.site.lock().unwrap();
+        *shared = (shared.0 + 1, site);
+    }
+
+    /// The version served at [`VERSION_ROUTE`]
+    pub fn version(&self) -> u64 {
+        self.site.lock().unwrap().0
+    }
+}
+
+/// A frontmatter value as HTML: a list of links for `link_text`/`href` maps, the fields of
+/// other maps, or the scalar values joined
+fn frontmatter_cell(document: &Document, key: &str) -> String {
+    let maps = document.maps(key);
+    if maps.is_empty() {
+        return escape(&document.list(key).join(", "));
+    }
+
+    let mut cell = String::from("<ul>");
+    for map in maps {
+        let field = |name: &str| map.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str());
+        let item = match (field("link_text"), field("href")) {
+            (Some(text), Some(href)) => {
+                format!("<a href=\"{}\">{}</a>", escape(&rewire_href(href)), escape(text))
+            }
+            _ => map.iter().map(|(k, v)| format!("{}: {}", escape(k), escape(v))).collect::<Vec<_>>().join(", "),
+        };
+        cell.push_str(&format!("<li>{}</li>", item));
+    }
+    cell.push_str("</ul>");
+    cell
+}
+
+/// A site permalink as its preview route; other links are left alone
+fn rewire_href(href: &str) -> String {
+    match href.strip_prefix(PERMALINK_PREFIX) {
+        Some(file) => format!("{}{}", POSTS_ROUTE, file),
+        None => href.to_string(),
+    }
+}
+
+/// Rendered HTML with its links to site permalinks rewired
+fn rewire_links(html: &str) -> String {
+    html.replace(&format!("href=\"{}", PERMALINK_PREFIX), &format!("href=\"{}", POSTS_ROUTE))
+}
+
+/// A whole page, polling [`VERSION_ROUTE`] to reload once the site is replaced
+fn page(title: &str, body: &str, version: u64) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n\
+         body {{ max-width: 46rem; margin: 2rem auto; padding: 0 1rem; font-family: sans-serif; line-height: 1.5; }}\n\
+         th, td {{ text-align: left; vertical-align: top; padding: 0.2rem 0.6rem; }}\n\
+         th {{ font-family: monospace; }}\n</style>\n</head>\n<body>\n{body}<script>\n\
+         setInterval(() => fetch(\"{route}\").then(r => r.text())\n\
+         .then(v => {{ if (v !== \"{version}\") location.reload(); }}).catch(() => {{}}), {poll});\n\
+         </script>\n</body>\n</html>\n",
+        title = escape(title),
+        body = body,
+        route = VERSION_ROUTE,
+        version = version,
+        poll = RELOAD_POLL_MS,
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A request path with its `%XX` escapes decoded; invalid escapes are kept as they are
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,153 @@
+//! Index pages of a post folder's tags: one per tag listing its posts, and one listing the
+//! tags, kept in a `tags` subfolder and rebuilt from the posts' `tags` frontmatter
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use chrono::NaiveDate;
+use crate::posts::PostWriter;
+use syndicate_json_canvas_lib::{SinkError, TextPolicy, duplicates::content_hash, write_atomic};
+use tracing::debug;
+
+/// Subfolder of the post folder the pages are kept in
+pub(crate) const TAG_FOLDER: &str = "tags";
+/// Name of the page listing every tag, without its extension
+const INDEX_NAME: &str = "index";
+
+/// A post as listed on the pages of its tags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TaggedPost {
+    pub(crate) title: String,
+    /// `date` frontmatter, `YYYY-MM-DD`
+    pub(crate) date: String,
+    /// Site URL of the post
+    pub(crate) href: String,
+    /// Its tags, without `#`
+    pub(crate) tags: Vec<String>,
+}
+
+/// The posts of one tag
+struct Tag<'a> {
+    /// How the tag is written; of several spellings with the same slug, the first in order
+    name: &'a str,
+    posts: Vec<&'a TaggedPost>,
+}
+
+/// File name stem of a tag's page: its slug, or one made from a hash of the tag if it has
+/// none or would take the index's name
+fn tag_slug(text: &TextPolicy, tag: &str) -> String {
+    let slug = text.slug(tag);
+    if slug.is_empty() || slug == INDEX_NAME {
+        format!("tag-{}", &content_hash(tag)[..8])
+    } else {
+        slug
+    }
+}
+
+/// Link text with the characters that would end it escaped
+fn link_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]")
+}
+
+/// A post's date as listed, per the text policy's locale; left as it is if it isn't
+/// `YYYY-MM-DD`
+fn listed_date(text: &TextPolicy, date: &str) -> String {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_or_else(|_| date.to_string(), |date| text.human_date(date))
+}
+
+/// A tag's page: its posts newest first, then by title
+fn tag_page(tag: &Tag, text: &TextPolicy) -> String {
+    let mut page = format!(
+        "---\ntitle: \"#{}\"\ntag: \"{}\"\nposts: {}\n---\n\n",
+        PostWriter::escape_yaml_string(tag.name),
+        PostWriter::escape_yaml_string(tag.name),
+        tag.posts.len()
+    );
+    for post in &tag.posts {
+        page.push_str(&format!("- [{}]({}) ({})\n", link_text(&post.title), post.href, listed_date(text, &post.date)));
+    }
+    page
+}
+
+/// The page listing every tag, by slug, with how many posts each has
+fn index_page(tags: &BTreeMap<String, Tag>, text: &TextPolicy, href_prefix: &str, extension: &str) -> String {
+    let mut page = "---\ntitle: \"Tags\"\n---\n\n".to_string();
+    for (slug, tag) in tags {
+        let href = format!("{}{}/{}.{}", href_prefix, TAG_FOLDER, slug, extension);
+        let count = text.human_number(tag.posts.len() as u64);
+        page.push_str(&format!("- [#{}]({}) ({})\n", link_text(tag.name), href, count));
+    }
+    page
+}
+
+/// Rebuild the tag pages of `folder` from its posts, writing only pages that changed and
+/// removing those of tags no post has any more; returns how many files were written or
+/// removed
+///
+/// Pages are `<slug>.<extension>` in [`TAG_FOLDER`], with tag slugs cut by `text` like post
+/// slugs, so tags differing only in case share a page. Links to pages start with
+/// `href_prefix`, as links to posts do. Dates and counts are written for people per the
+/// policy's locale; the pages' frontmatter keeps machine formats.
+pub(crate) fn sync(
+    folder: &Path,
+    posts: &[TaggedPost],
+    text: &TextPolicy,
+    href_prefix: &str,
+    extension: &str,
+) -> Result<usize, SinkError> {
+    let mut sorted: Vec<&TaggedPost> = posts.iter().collect();
+    sorted.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.title.cmp(&b.title)).then_with(|| a.href.cmp(&b.href)));
+
+    let mut tags: BTreeMap<String, Tag> = BTreeMap::new();
+    for post in sorted {
+        let unique: BTreeSet<&str> = post.tags.iter().map(String::as_str).filter(|tag| !tag.is_empty()).collect();
+        let mut slugs = BTreeSet::new();
+        for name in unique {
+            let slug = tag_slug(text, name);
+            let tag = tags.entry(slug.clone()).or_insert_with(|| Tag { name, posts: Vec::new() });
+            tag.name = tag.name.min(name);
+            // A post tagged with two spellings of the tag is listed once
+            if slugs.insert(slug) {
+                tag.posts.push(post);
+            }
+        }
+    }
+
+    let dir = folder.join(TAG_FOLDER);
+    let mut pages: BTreeMap<String, String> =
+        tags.iter().map(|(slug, tag)| (format!("{}.{}", slug, extension), tag_page(tag, text))).collect();
+    if !tags.is_empty() {
+        pages.insert(format!("{}.{}", INDEX_NAME, extension), index_page(&tags, text, href_prefix, extension));
+    }
+
+    let mut changed = 0;
+    if dir.is_dir() {
+        let suffix = format!(".{}", extension);
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if name.ends_with(&suffix) && !pages.contains_key(name) && path.is_file() {
+                std::fs::remove_file(&path)?;
+                debug!(file = %path.display(), "Removed tag page");
+                changed += 1;
+            }
+        }
+    }
+    for (name, contents) in &pages {
+        let path = dir.join(name);
+        if std::fs::read_to_string(&path).is_ok_and(|existing| existing == *contents) {
+            continue;
+        }
+        std::fs::create_dir_all(&dir)?;
+        write_atomic(&path, contents)?;
+        debug!(file = %path.display(), "Wrote tag page");
+        changed += 1;
+    }
+    // Left behind once the last tag is gone, unless something else lives there
+    if pages.is_empty() && dir.is_dir() {
+        let _ = std::fs::remove_dir(&dir);
+    }
+    Ok(changed)
+}
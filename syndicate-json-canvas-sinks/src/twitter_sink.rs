@@ -1,7 +1,12 @@
 use crate::{SinkError, SyndicationSink};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use syndicate_json_canvas_lib::{SyndicationFormat, jsoncanvas::NodeId};
+use std::sync::Arc;
+use syndicate_json_canvas_lib::{
+    Clock, ContinuationPolicy, HttpClient, HttpError, HttpRequest, NetworkPolicy, PlainTextOptions, PoliteClient,
+    ReqwestClient, SentKeys, StateRegistry, SyndicationFormat, SystemClock, TextPolicy, Warning, idempotency::key_of,
+    jsoncanvas::NodeId, smart_truncate, to_plain_text_with, warnings::codes, write_atomic,
+};
 use tracing::{debug, info, warn};
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +18,58 @@ pub struct TwitterSink {
     tracker_path: PathBuf,
     /// In-memory tracker of published node IDs
     published_ids: HashSet<String>,
+    /// If set, markdown is converted to plain text before splitting into tweets
+    plain_text: Option<PlainTextOptions>,
+    /// What to do with items longer than one tweet
+    continuation: ContinuationPolicy,
+    /// Most tweets in a thread; longer items are skipped
+    max_thread_tweets: usize,
+    /// Template of the canonical post URL (`{slug}` and `{id}` placeholders)
+    canonical_url: Option<String>,
+    /// Appended to truncated tweets; `{url}` is replaced with the canonical URL
+    continuation_suffix: String,
+    /// How the `{slug}` of canonical URLs is cut from node text
+    text: TextPolicy,
+    /// Whether items behind a content warning start with `CW: <topic>`
+    content_warnings: bool,
+    /// If set, tweets are written here as `<node-id>.json` instead of being posted
+    preview_dir: Option<PathBuf>,
+    /// URLs of the threads posted since the last `take_post_urls`
+    post_urls: Vec<(NodeId, String)>,
+    /// Warnings raised since the last `take_warnings`
+    warnings: Vec<Warning>,
+    /// Tweets posted per idempotency key, recorded around every request
+    sent_keys: SentKeys,
+    /// Sends the API requests
+    http: Arc<dyn HttpClient>,
+    clock: Arc<dyn Clock>,
+}
+
+/// Maximum tweet length, as weighted by Twitter
+const MAX_TWEET_LENGTH: usize = 280;
+/// Most tweets in a thread by default
+pub const DEFAULT_MAX_THREAD_TWEETS: usize = 25;
+/// Length Twitter counts for any URL (they're all shortened to t.co links)
+const TWEET_URL_LENGTH: usize = 23;
+/// URL of a tweet by ID, which resolves without knowing the account's handle
+const TWEET_URL: &str = "https://x.com/i/status/";
+/// Version of the layout of the tracker and sent keys files, for state bundles
+const STATE_SCHEMA: u32 = 1;
+
+/// Why posting a tweet failed
+enum TweetError {
+    /// Twitter answered with an error, so nothing was posted
+    Rejected(SinkError),
+    /// The request may have gone through (e.g. the connection dropped)
+    Unknown(SinkError),
+}
+
+impl From<TweetError> for SinkError {
+    fn from(error: TweetError) -> Self {
+        match error {
+            TweetError::Rejected(e) | TweetError::Unknown(e) => e,
+        }
+    }
 }
 
 /// Tracker for published tweets
@@ -42,7 +99,7 @@ impl TwitterTracker {
             "# Generated by syndicate-json-canvas - Do not edit manually\n\n{}",
             toml_content
         );
-        std::fs::write(path, content_with_header)?;
+        write_atomic(path, content_with_header)?;
         Ok(())
     }
 }
@@ -52,7 +109,8 @@ impl TwitterSink {
     ///
     /// # Arguments
     /// * `bearer_token` - Twitter API v2 Bearer Token
-    /// * `tracker_path` - Path to TOML file for tracking published tweets
+    /// * `tracker_path` - Path to TOML file for tracking published tweets; the tweets of
+    ///   each delivery are logged beside it in `<tracker-file-name>.sent-keys.json`
     pub fn new(
         bearer_token: impl Into<String>,
         tracker_path: impl AsRef<Path>,
@@ -62,14 +120,154 @@ impl TwitterSink {
         // Load existing tracker
         let tracker = TwitterTracker::load(&tracker_path)?;
         let published_ids: HashSet<String> = tracker.published_node_ids.into_iter().collect();
+        let sent_keys = SentKeys::beside(&tracker_path)?;
 
         Ok(Self {
             bearer_token: bearer_token.into(),
             tracker_path,
             published_ids,
+            plain_text: None,
+            continuation: ContinuationPolicy::Thread,
+            max_thread_tweets: DEFAULT_MAX_THREAD_TWEETS,
+            canonical_url: None,
+            continuation_suffix: "… more: {url}".to_string(),
+            text: TextPolicy::default(),
+            content_warnings: false,
+            preview_dir: None,
+            post_urls: Vec::new(),
+            warnings: Vec::new(),
+            sent_keys,
+            http: Arc::new(PoliteClient::new(ReqwestClient::new(), NetworkPolicy::default())),
+            clock: Arc::new(SystemClock),
         })
     }
 
+    /// Send API requests through a client shared with other network sinks, so their
+    /// [`NetworkPolicy`] limits apply together (default: a [`PoliteClient`] of its own)
+    pub fn with_http_client(mut self, http: Arc<dyn HttpClient>) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Use a custom clock for the times deliveries are logged at
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.sent_keys = self.sent_keys.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Set how items longer than one tweet are handled
+    pub fn with_continuation(mut self, policy: ContinuationPolicy) -> Self {
+        self.continuation = policy;
+        self
+    }
+
+    /// Skip items that would take more than `max` tweets as a thread (default
+    /// [`DEFAULT_MAX_THREAD_TWEETS`])
+    pub fn with_max_thread_tweets(mut self, max: usize) -> Self {
+        self.max_thread_tweets = max;
+        self
+    }
+
+    /// Link truncated tweets to the canonical post, e.g. `https://example.com/t/{slug}-{id}`
+    pub fn with_canonical_url(mut self, template: impl Into<String>) -> Self {
+        self.canonical_url = Some(template.into());
+        self
+    }
+
+    /// Set the suffix of truncated tweets (default `… more: {url}`)
+    pub fn with_continuation_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.continuation_suffix = suffix.into();
+        self
+    }
+
+    /// Build the `{slug}` of canonical URLs with a shared text policy, so links match the
+    /// file names of the markdown sinks
+    pub fn with_text_policy(mut self, text: TextPolicy) -> Self {
+        self.text = text;
+        self
+    }
+
+    /// Start the first tweet of items behind a content warning with `CW: <topic>` and a
+    /// blank line; Twitter has no field of its own for it
+    pub fn with_content_warnings(mut self, enabled: bool) -> Self {
+        self.content_warnings = enabled;
+        self
+    }
+
+    /// Length of a tweet as Twitter counts it: every URL weighs [`TWEET_URL_LENGTH`], and
+    /// characters past Latin and general punctuation (CJK, emoji) weigh two
+    fn weighted_length(text: &str) -> usize {
+        let urls: Vec<&str> = text
+            .split_whitespace()
+            .filter(|word| word.starts_with("http://") || word.starts_with("https://"))
+            .collect();
+        let url_weight: usize = urls.iter().map(|url| url.chars().map(Self::char_weight).sum::<usize>()).sum();
+        text.chars().map(Self::char_weight).sum::<usize>() - url_weight + urls.len() * TWEET_URL_LENGTH
+    }
+
+    /// Weight of one character, from the ranges of Twitter's text configuration
+    fn char_weight(c: char) -> usize {
+        match c as u32 {
+            0..=4351 | 8192..=8205 | 8208..=8223 | 8242..=8247 => 1,
+            _ => 2,
+        }
+    }
+
+    /// A word cut into pieces of at most `limit` weighted length; only words without spaces
+    /// longer than a tweet, like CJK sentences, are cut
+    fn word_pieces(word: &str, limit: usize) -> Vec<String> {
+        if Self::weighted_length(word) <= limit {
+            return vec![word.to_string()];
+        }
+        let mut pieces = vec![String::new()];
+        let mut length = 0;
+        for c in word.chars() {
+            if length + Self::char_weight(c) > limit {
+                pieces.push(String::new());
+                length = 0;
+            }
+            pieces.last_mut().unwrap().push(c);
+            length += Self::char_weight(c);
+        }
+        pieces
+    }
+
+    /// Truncate to one tweet ending in the continuation suffix
+    ///
+    /// Without a canonical URL the suffix is just an ellipsis.
+    fn truncate_with_continuation(&self, text: &str, item: &SyndicationFormat) -> String {
+        let suffix = match &self.canonical_url {
+            Some(template) => self
+                .continuation_suffix
+                .replace("{url}", &self.text.canonical_url(template, item)),
+            None => "…".to_string(),
+        };
+        let budget = MAX_TWEET_LENGTH.saturating_sub(Self::weighted_length(&suffix) + 1);
+        let cut = smart_truncate(text, budget, "");
+        format!("{} {}", cut, suffix)
+    }
+
+    /// Convert markdown to plain text before posting (e.g. `**bold**` becomes `bold`)
+    pub fn with_plain_text(mut self, options: PlainTextOptions) -> Self {
+        self.plain_text = Some(options);
+        self
+    }
+
+    /// Prepare the text that will be posted, converting markdown if configured
+    fn prepare_text(&self, text: &str) -> String {
+        match &self.plain_text {
+            Some(options) => to_plain_text_with(text, options),
+            None => text.to_string(),
+        }
+    }
+
+    /// `CW: <topic>` for an item behind a content warning, if configured
+    fn content_warning_line(&self, item: &SyndicationFormat) -> Option<String> {
+        let topic = item.content_warning.as_ref().filter(|_| self.content_warnings)?;
+        Some(format!("CW: {}", topic.split_whitespace().collect::<Vec<_>>().join(" ")))
+    }
+
     /// Check if a node has already been published
     fn is_published(&self, node_id: &NodeId) -> bool {
         self.published_ids.contains(node_id.as_str())
@@ -89,32 +287,46 @@ impl TwitterSink {
     }
 
     /// Split text into tweet-sized chunks (280 characters, accounting for thread numbering)
+    ///
+    /// Splits between words, keeping the text's line breaks, and a blank line between
+    /// paragraphs, within each tweet.
     fn split_into_tweets(text: &str) -> Vec<String> {
-        const MAX_TWEET_LENGTH: usize = 280;
         const THREAD_SUFFIX_LENGTH: usize = 10; // " (1/N)" plus safety margin
 
         let available_length = MAX_TWEET_LENGTH - THREAD_SUFFIX_LENGTH;
 
-        // Simple word-boundary splitting
         let mut tweets = Vec::new();
         let mut current_tweet = String::new();
+        // Weighted, as Twitter counts it
+        let mut current_length = 0;
+        // What goes before the next word: a space, or the line breaks since the last one
+        let mut separator = " ";
+
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                separator = "\n\n";
+                continue;
+            }
+            for word in line.split_whitespace().flat_map(|word| Self::word_pieces(word, available_length)) {
+                let word_length = Self::weighted_length(&word);
+                if !current_tweet.is_empty() && current_length + separator.len() + word_length > available_length {
+                    tweets.push(std::mem::take(&mut current_tweet));
+                    current_length = 0;
+                }
 
-        for word in text.split_whitespace() {
-            if current_tweet.len() + word.len() + 1 > available_length {
                 if !current_tweet.is_empty() {
-                    tweets.push(current_tweet.trim().to_string());
-                    current_tweet = String::new();
+                    current_tweet.push_str(separator);
+                    current_length += separator.len();
                 }
+                current_tweet.push_str(&word);
+                current_length += word_length;
+                separator = " ";
             }
-
-            if !current_tweet.is_empty() {
-                current_tweet.push(' ');
-            }
-            current_tweet.push_str(word);
+            separator = "\n";
         }
 
         if !current_tweet.is_empty() {
-            tweets.push(current_tweet.trim().to_string());
+            tweets.push(current_tweet);
         }
 
         // Add thread numbering if multiple tweets
@@ -130,8 +342,36 @@ impl TwitterSink {
         tweets
     }
 
+    /// Post one tweet of a delivery, logging the request in the sent keys before it's sent
+    /// and its tweet ID right after
+    fn send_tweet(
+        &mut self,
+        key: &str,
+        node_id: &NodeId,
+        text: &str,
+        reply_to_id: Option<String>,
+        dry_run: bool,
+    ) -> Result<String, SinkError> {
+        if dry_run {
+            return Ok(self.post_tweet(text, reply_to_id, dry_run)?);
+        }
+
+        self.sent_keys.begin_request(key, node_id)?;
+        match self.post_tweet(text, reply_to_id, dry_run) {
+            Ok(tweet_id) => {
+                self.sent_keys.record_part(key, &tweet_id)?;
+                Ok(tweet_id)
+            }
+            Err(TweetError::Rejected(e)) => {
+                self.sent_keys.record_rejected(key)?;
+                Err(e)
+            }
+            Err(TweetError::Unknown(e)) => Err(e),
+        }
+    }
+
     /// Post a tweet using Twitter API v2
-    fn post_tweet(&self, text: &str, reply_to_id: Option<String>, dry_run: bool) -> Result<String, SinkError> {
+    fn post_tweet(&self, text: &str, reply_to_id: Option<String>, dry_run: bool) -> Result<String, TweetError> {
         if dry_run {
             debug!(text = %text, reply_to = ?reply_to_id, "[DRY RUN] Would post tweet");
             // Return a fake tweet ID in dry run mode
@@ -150,31 +390,32 @@ impl TwitterSink {
         }
 
         // Make the API request
-        let client = reqwest::blocking::Client::new();
-        let response = client
-            .post("https://api.twitter.com/2/tweets")
+        let request = HttpRequest::post("https://api.twitter.com/2/tweets", body.to_string())
             .header("Authorization", format!("Bearer {}", self.bearer_token))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .map_err(|e| SinkError::CommandFailed(format!("Failed to post tweet: {}", e)))?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(SinkError::CommandFailed(format!(
+            .header("Content-Type", "application/json");
+        let response = self.http.send(&request).map_err(|e| {
+            let error = SinkError::Api(format!("Failed to post tweet: {}", e));
+            match e {
+                HttpError::InvalidRequest(_) => TweetError::Rejected(error),
+                HttpError::Transport(_) => TweetError::Unknown(error),
+            }
+        })?;
+
+        if !response.is_success() {
+            return Err(TweetError::Rejected(SinkError::Api(format!(
                 "Twitter API error: {}",
-                error_text
-            )));
+                response.body
+            ))));
         }
 
-        let response_json: serde_json::Value = response
-            .json()
-            .map_err(|e| SinkError::CommandFailed(format!("Failed to parse response: {}", e)))?;
+        // The tweet is up from here on, even if its ID can't be read
+        let response_json: serde_json::Value = serde_json::from_str(&response.body)
+            .map_err(|e| TweetError::Unknown(SinkError::Api(format!("Failed to parse response: {}", e))))?;
 
         // Extract the tweet ID from the response
         let tweet_id = response_json["data"]["id"]
             .as_str()
-            .ok_or_else(|| SinkError::CommandFailed("No tweet ID in response".to_string()))?
+            .ok_or_else(|| TweetError::Unknown(SinkError::Api("No tweet ID in response".to_string())))?
             .to_string();
 
         info!(tweet_id = %tweet_id, "Posted tweet");
@@ -182,37 +423,121 @@ impl TwitterSink {
     }
 
     /// Publish a single item as a tweet or thread
-    fn publish_item(&mut self, item: &SyndicationFormat, dry_run: bool) -> Result<(), SinkError> {
-        if self.is_published(&item.id) {
+    ///
+    /// A re-surfaced item is posted though it was published before, and neither tracked nor
+    /// linked as the node's post.
+    fn publish_item(&mut self, item: &SyndicationFormat, resurfaced: bool, dry_run: bool) -> Result<(), SinkError> {
+        if !resurfaced && self.is_published(&item.id) {
             debug!(node_id = %item.id, "Already published, skipping");
             return Ok(());
         }
 
+        // Deliveries interrupted before the tracker was saved are in the sent keys
+        let key = key_of(item, self.name());
+        if let Some(entry) = self.sent_keys.get(&key) {
+            if entry.complete {
+                info!(node_id = %item.id, "Posted before the tracker was saved, not posting again");
+                let first = entry.parts.first().cloned();
+                self.delivered(item, first, resurfaced);
+                return Ok(());
+            }
+            if entry.in_flight {
+                return Err(SinkError::Api(format!(
+                    "an earlier request may already have posted this; check the account, then remove key {} from {} to retry",
+                    key,
+                    self.sent_keys.path().display()
+                )));
+            }
+        }
+
         info!(node_id = %item.id, "Publishing to Twitter");
 
-        // Split into tweets if needed
-        let tweets = Self::split_into_tweets(&item.text);
+        // Split into tweets if needed (after conversion, so the length limit applies to what is posted)
+        let cw = self.content_warning_line(item);
+        let mut body = self.prepare_text(&item.text);
+        // A link post's URL goes in the tweet even if its template leaves it out, for the card
+        if let Some(url) = item.url.as_ref().filter(|url| !body.contains(url.as_str())) {
+            body = format!("{} {}", body.trim_end(), url);
+        }
+        let text = match &cw {
+            Some(cw) => format!("{}\n\n{}", cw, body),
+            None => body,
+        };
+        let tweets = match self.continuation {
+            ContinuationPolicy::Thread => Self::split_into_tweets(&text),
+            _ if Self::weighted_length(&text) <= MAX_TWEET_LENGTH => vec![text.trim().to_string()],
+            ContinuationPolicy::Truncate => vec![self.truncate_with_continuation(&text, item)],
+            ContinuationPolicy::Skip => {
+                let length = Self::weighted_length(&text);
+                warn!(node_id = %item.id, length, "Too long for one tweet, skipping");
+                let message = format!("{} characters is too long for one tweet, skipped", length);
+                self.warnings.push(Warning::new(codes::TOO_LONG, self.name(), message).for_node(&item.id));
+                return Ok(());
+            }
+        };
+        if tweets.len() > self.max_thread_tweets {
+            warn!(node_id = %item.id, tweet_count = tweets.len(), "Too long for a thread, skipping");
+            let message =
+                format!("{} tweets is too long for a thread of at most {}, skipped", tweets.len(), self.max_thread_tweets);
+            self.warnings.push(Warning::new(codes::TOO_LONG, self.name(), message).for_node(&item.id));
+            return Ok(());
+        }
 
         if tweets.is_empty() {
             warn!(node_id = %item.id, "No content to tweet");
+            self.warnings.push(Warning::new(codes::EMPTY_ITEM, self.name(), "No content to tweet").for_node(&item.id));
+            return Ok(());
+        }
+
+        if let Some(preview_dir) = &self.preview_dir {
+            let path = preview_dir.join(format!("{}.json", item.id.as_str()));
+            let payload = serde_json::to_string_pretty(&tweets)
+                .map_err(|e| SinkError::Serialization(e.to_string()))?;
+            if !dry_run {
+                std::fs::create_dir_all(preview_dir)?;
+                write_atomic(&path, payload)?;
+            }
+            debug!(file = %path.display(), tweet_count = tweets.len(), "Rendered tweets instead of posting");
+            self.delivered(item, None, resurfaced);
             return Ok(());
         }
 
         info!(tweet_count = tweets.len(), "Publishing as thread");
 
-        // Post the first tweet
-        let mut last_tweet_id = self.post_tweet(&tweets[0], None, dry_run)?;
+        // A thread cut short by an error goes on from the last tweet that went out
+        let posted = self.sent_keys.get(&key).map(|entry| entry.parts.clone()).unwrap_or_default();
+        if !posted.is_empty() {
+            info!(node_id = %item.id, posted = posted.len(), "Resuming thread");
+        }
 
-        // Post remaining tweets as replies
-        for tweet in &tweets[1..] {
-            last_tweet_id = self.post_tweet(tweet, Some(last_tweet_id), dry_run)?;
+        // Post the remaining tweets, each replying to the one before
+        let mut last_tweet_id = posted.last().cloned();
+        for tweet in tweets.iter().skip(posted.len()) {
+            last_tweet_id = Some(self.send_tweet(&key, &item.id, tweet, last_tweet_id, dry_run)?);
         }
 
-        // Mark as published
-        self.mark_published(&item.id);
+        // The first tweet is where the thread is linked from
+        let mut first = None;
+        if !dry_run {
+            self.sent_keys.complete(&key)?;
+            first = self.sent_keys.get(&key).and_then(|entry| entry.parts.first().cloned());
+        }
+        self.delivered(item, first, resurfaced);
 
         Ok(())
     }
+
+    /// Mark a delivered item as published, linked from the first tweet of its thread, unless
+    /// it was re-surfaced
+    fn delivered(&mut self, item: &SyndicationFormat, first_tweet: Option<String>, resurfaced: bool) {
+        if resurfaced {
+            return;
+        }
+        if let Some(first) = first_tweet {
+            self.post_urls.push((item.id.clone(), format!("{}{}", TWEET_URL, first)));
+        }
+        self.mark_published(&item.id);
+    }
 }
 
 impl SyndicationSink for TwitterSink {
@@ -244,14 +569,14 @@ impl SyndicationSink for TwitterSink {
         // Publish each new item
         let mut published_count = 0;
         for item in new_items {
-            match self.publish_item(item, dry_run) {
+            match self.publish_item(item, false, dry_run).map_err(|e| e.for_item(&item.id).in_sink(self.name())) {
                 Ok(()) => {
                     info!(node_id = %item.id, "Published to Twitter");
                     published_count += 1;
                 }
                 Err(e) => {
                     // Log error but continue with other items
-                    tracing::error!(node_id = %item.id, error = %e, "Failed to publish to Twitter");
+                    tracing::error!(error = %e, "Failed to publish to Twitter");
                 }
             }
         }
@@ -259,6 +584,7 @@ impl SyndicationSink for TwitterSink {
         // Save tracker if we published anything
         if published_count > 0 {
             self.save_tracker()?;
+            self.sent_keys.prune_complete()?;
             info!(
                 total_published = self.published_ids.len(),
                 "Saved tracker"
@@ -269,7 +595,224 @@ impl SyndicationSink for TwitterSink {
         Ok(())
     }
 
+    /// Posts the item again as a new tweet or thread, whatever the tracker says
+    fn resurface(&mut self, item: &SyndicationFormat, dry_run: bool) -> Result<(), SinkError> {
+        info!(node_id = %item.id, "Re-surfacing on Twitter");
+        self.publish_item(item, true, dry_run)
+    }
+
+    /// A copy with its own tracker under `root` that renders tweets into `root/tweets`
+    fn sandboxed(&self, root: &Path) -> Result<Box<dyn SyndicationSink + Send>, SinkError> {
+        std::fs::create_dir_all(root)?;
+        let tracker_path = root.join("tracker.toml");
+        if self.tracker_path.exists() {
+            std::fs::copy(&self.tracker_path, &tracker_path)?;
+        }
+        let sent_keys_path = root.join("tracker.toml.sent-keys.json");
+        if self.sent_keys.path().exists() {
+            std::fs::copy(self.sent_keys.path(), &sent_keys_path)?;
+        }
+
+        Ok(Box::new(Self {
+            bearer_token: String::new(),
+            tracker_path,
+            published_ids: self.published_ids.clone(),
+            plain_text: self.plain_text.clone(),
+            continuation: self.continuation,
+            max_thread_tweets: self.max_thread_tweets,
+            canonical_url: self.canonical_url.clone(),
+            continuation_suffix: self.continuation_suffix.clone(),
+            text: self.text.clone(),
+            content_warnings: self.content_warnings,
+            preview_dir: Some(root.join("tweets")),
+            post_urls: Vec::new(),
+            warnings: Vec::new(),
+            sent_keys: SentKeys::load(sent_keys_path)?.with_clock(self.clock.clone()),
+            http: self.http.clone(),
+            clock: self.clock.clone(),
+        }))
+    }
+
+    fn take_post_urls(&mut self) -> Vec<(NodeId, String)> {
+        std::mem::take(&mut self.post_urls)
+    }
+
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Its own tracker and the sent keys beside it
+    fn register_state(&self, registry: &mut StateRegistry) {
+        registry.register(format!("{}.tracker", self.name()), &self.tracker_path, STATE_SCHEMA);
+        registry.register(format!("{}.sent-keys", self.name()), self.sent_keys.path(), STATE_SCHEMA);
+    }
+
+    /// A bearer token is set; whether Twitter accepts it is only known when posting
+    fn preflight(&self) -> Result<(), SinkError> {
+        if self.bearer_token.trim().is_empty() {
+            return Err(SinkError::Config("bearer token is empty".to_string()));
+        }
+        Ok(())
+    }
+
     fn name(&self) -> &str {
         "twitter"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use syndicate_json_canvas_lib::{CanvasBuilder, HttpResponse, default_process_node, to_syndication_format};
+    use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+
+    const NOTE: &str = "1d30000000000001";
+
+    /// The Twitter API, keeping the text of every tweet; while `dropping`, requests go
+    /// through but their responses are lost
+    #[derive(Debug, Default)]
+    struct MockTwitter {
+        tweets: Mutex<Vec<String>>,
+        dropping: bool,
+    }
+
+    impl HttpClient for MockTwitter {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+            let body: serde_json::Value = serde_json::from_str(request.body.as_deref().unwrap_or("{}"))
+                .map_err(|e| HttpError::InvalidRequest(e.to_string()))?;
+            let mut tweets = self.tweets.lock().unwrap();
+            tweets.push(body["text"].as_str().unwrap_or_default().to_string());
+            if self.dropping {
+                return Err(HttpError::Transport("connection reset".to_string()));
+            }
+            let body = format!(r#"{{"data": {{"id": "{}"}}}}"#, tweets.len());
+            Ok(HttpResponse { status: 201, headers: Vec::new(), body })
+        }
+    }
+
+    fn items() -> HashMap<NodeId, SyndicationFormat> {
+        items_with("A short note.")
+    }
+
+    fn items_with(text: &str) -> HashMap<NodeId, SyndicationFormat> {
+        let canvas = CanvasBuilder::new().text_node(NOTE, text).color(PresetColor::Red).build().unwrap();
+        to_syndication_format(&canvas, Some(default_process_node)).unwrap()
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-twitter-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn crash_before_tracker_save_does_not_repost() {
+        let dir = scratch("crash");
+        let tracker_path = dir.join("tracker.toml");
+        let twitter = Arc::new(MockTwitter::default());
+        let mut sink = TwitterSink::new("token", &tracker_path).unwrap().with_http_client(twitter.clone());
+        // A directory in the tracker's place makes saving it fail, as a crash right after
+        // the tweet went out would
+        std::fs::create_dir_all(tracker_path.join("blocked")).unwrap();
+        assert!(sink.publish(&items(), false).is_err());
+        assert_eq!(twitter.tweets.lock().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&tracker_path).unwrap();
+        let mut restarted = TwitterSink::new("token", &tracker_path).unwrap().with_http_client(twitter.clone());
+        restarted.publish(&items(), false).unwrap();
+        assert_eq!(twitter.tweets.lock().unwrap().len(), 1, "posted again");
+        assert!(restarted.is_published(&NOTE.parse().unwrap()));
+        assert_eq!(restarted.take_post_urls(), [(NOTE.parse().unwrap(), format!("{}1", TWEET_URL))]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn request_of_unknown_outcome_is_reported_not_retried() {
+        let dir = scratch("unknown");
+        let tracker_path = dir.join("tracker.toml");
+        let lost = Arc::new(MockTwitter { dropping: true, ..Default::default() });
+        let mut sink = TwitterSink::new("token", &tracker_path).unwrap().with_http_client(lost.clone());
+        sink.publish(&items(), false).unwrap();
+        assert_eq!(lost.tweets.lock().unwrap().len(), 1);
+
+        let twitter = Arc::new(MockTwitter::default());
+        let mut restarted = TwitterSink::new("token", &tracker_path).unwrap().with_http_client(twitter.clone());
+        let error = restarted.publish_item(&items()[&NOTE.parse().unwrap()], false, false).unwrap_err();
+        assert!(error.to_string().contains("may already have posted"), "{}", error);
+        assert!(twitter.tweets.lock().unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn urls_and_cjk_are_weighted() {
+        let url = format!("https://example.com/{}", "a".repeat(60));
+        assert_eq!(TwitterSink::weighted_length(&format!("see {}", url)), 4 + TWEET_URL_LENGTH);
+        assert_eq!(TwitterSink::weighted_length("日本語です"), 10);
+    }
+
+    #[test]
+    fn threads_fit_the_weighted_limit() {
+        let links: Vec<String> = (0..20).map(|i| format!("https://example.com/{}/{}", i, "a".repeat(60))).collect();
+        let cjk = "漢字".repeat(150);
+        for text in [links.join(" "), cjk.clone(), format!("Intro line.\n\n{} and more", cjk)] {
+            let tweets = TwitterSink::split_into_tweets(&text);
+            let lengths: Vec<usize> = tweets.iter().map(|t| TwitterSink::weighted_length(t)).collect();
+            assert!(lengths.iter().all(|&length| length <= MAX_TWEET_LENGTH), "{:?}", lengths);
+        }
+    }
+
+    #[test]
+    fn links_are_threaded_by_weight_not_characters() {
+        // 20 links of 85 characters each weigh 20 * 24, so two tweets rather than seven
+        let links: Vec<String> = (0..20).map(|i| format!("https://example.com/{:02}/{}", i, "a".repeat(60))).collect();
+        assert_eq!(TwitterSink::split_into_tweets(&links.join(" ")).len(), 2);
+    }
+
+    #[test]
+    fn cjk_is_threaded_without_losing_text() {
+        let cjk = "漢字".repeat(150);
+        let tweets = TwitterSink::split_into_tweets(&cjk);
+        assert_eq!(tweets.len(), 3);
+        let joined: String = tweets.iter().map(|t| t.rsplit_once(" (").unwrap().0).collect();
+        assert_eq!(joined, cjk);
+    }
+
+    #[test]
+    fn long_item_is_truncated_with_a_read_more_link() {
+        let dir = scratch("truncate");
+        let twitter = Arc::new(MockTwitter::default());
+        let mut sink = TwitterSink::new("token", dir.join("tracker.toml"))
+            .unwrap()
+            .with_http_client(twitter.clone())
+            .with_continuation(ContinuationPolicy::Truncate)
+            .with_canonical_url("https://example.com/t/{slug}-{id}");
+        let text = format!("Long thoughts. {}", "More words here. ".repeat(40));
+        sink.publish(&items_with(&text), false).unwrap();
+
+        let tweets = twitter.tweets.lock().unwrap();
+        assert_eq!(tweets.len(), 1);
+        assert!(TwitterSink::weighted_length(&tweets[0]) <= MAX_TWEET_LENGTH, "{}", tweets[0]);
+        let (cut, link) = tweets[0].split_once(" … more: ").unwrap();
+        assert!(cut.starts_with("Long thoughts.") && cut.ends_with("here."), "{}", cut);
+        assert!(link.starts_with("https://example.com/t/long-thoughts-") && link.ends_with(NOTE), "{}", link);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn long_item_under_skip_is_not_posted() {
+        let dir = scratch("skip");
+        let twitter = Arc::new(MockTwitter::default());
+        let mut sink = TwitterSink::new("token", dir.join("tracker.toml"))
+            .unwrap()
+            .with_http_client(twitter.clone())
+            .with_continuation(ContinuationPolicy::Skip);
+        sink.publish(&items_with(&"Word ".repeat(100)), false).unwrap();
+
+        assert!(twitter.tweets.lock().unwrap().is_empty());
+        let warnings = sink.take_warnings();
+        assert!(warnings.len() == 1 && warnings[0].code == codes::TOO_LONG, "{:?}", warnings);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+//! Items with accessibility issues: warned about everywhere, and under the block policy
+//! held back from public-facing sinks until they're fixed.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    AccessibilityOptions, AccessibilityPolicy, CanvasBuilder, Config, ProcessReport, SinkError, SinkTarget,
+    SyndicationFormat, SyndicationSink, SyndicationTracker, Syndicator,
+};
+
+const CLEAN: &str = "acce000000000001";
+const NO_ALT: &str = "acce000000000002";
+
+/// Node IDs a sink was given
+type Received = Arc<Mutex<Vec<String>>>;
+
+struct Mock {
+    name: &'static str,
+    received: Received,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        self.received.lock().unwrap().extend(items.keys().map(ToString::to_string));
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn write_canvas(dir: &Path, no_alt_text: &str) -> Result<(), Box<dyn Error>> {
+    let canvas = CanvasBuilder::new()
+        .text_node(CLEAN, "Nothing to see here")
+        .color(PresetColor::Red)
+        .text_node(NO_ALT, no_alt_text)
+        .color(PresetColor::Red)
+        .build()?;
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+    Ok(())
+}
+
+/// A run's report and the node IDs its public and private sinks were given
+type Run = (ProcessReport, Vec<String>, Vec<String>);
+
+/// Run into a public-facing `site` mock and a private `archive` mock
+fn run(dir: &Path, policy: AccessibilityPolicy) -> Result<Run, Box<dyn Error>> {
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.accessibility = AccessibilityOptions::default().with_policy(policy);
+    let mut syndicator = Syndicator::new(config)?;
+    let (public, private) = (Received::default(), Received::default());
+    let tracker = SyndicationTracker::new(&dir.join("notes.canvas"), "site")?;
+    let site = Mock { name: "site", received: public.clone() };
+    syndicator.add_target(SinkTarget::new(site, tracker).with_public_facing(true));
+    syndicator.add_sink(Mock { name: "archive", received: private.clone() })?;
+    let report = syndicator.process_once()?;
+    let sorted = |received: &Received| {
+        let mut ids = received.lock().unwrap().clone();
+        ids.sort();
+        ids
+    };
+    Ok((report, sorted(&public), sorted(&private)))
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-accessibility-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    write_canvas(&dir, "A photo ![[owl.png]]")?;
+    Ok(Scratch(dir))
+}
+
+#[test]
+fn warn_policy_publishes_everywhere_with_a_warning() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("warn")?;
+    let (report, public, private) = run(&scratch.0, AccessibilityPolicy::Warn)?;
+    assert_eq!(public, [CLEAN, NO_ALT]);
+    assert_eq!(private, [CLEAN, NO_ALT]);
+    let warnings: Vec<_> = report.all_warnings().map(|w| (w.code.as_str(), w.node_id.as_deref())).collect();
+    assert_eq!(warnings, [("missing-alt", Some(NO_ALT))]);
+    Ok(())
+}
+
+#[test]
+fn block_policy_holds_items_back_from_public_sinks_until_fixed() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("block")?;
+    let (report, public, private) = run(&scratch.0, AccessibilityPolicy::Block)?;
+    assert_eq!(public, [CLEAN]);
+    assert_eq!(private, [CLEAN, NO_ALT]);
+    let warning = report.all_warnings().next().ok_or("no warning")?;
+    assert!(warning.message.contains("![[owl.png|alt text]]"), "{}", warning.message);
+
+    // Still held back while nothing changed
+    let (_, public, private) = run(&scratch.0, AccessibilityPolicy::Block)?;
+    assert!(public.is_empty() && private.is_empty(), "{:?} {:?}", public, private);
+
+    write_canvas(&scratch.0, "A photo ![[owl.png|An owl on a branch]]")?;
+    let (report, public, _) = run(&scratch.0, AccessibilityPolicy::Block)?;
+    assert_eq!(public, [NO_ALT]);
+    assert_eq!(report.all_warnings().count(), 0);
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! Archiving through the canvas: moving a published node into the Archive group archives it
+//! once however many runs follow, and moving it out (still red) un-archives it.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, PublishState, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+    default_process_node, to_syndication_format,
+};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTE: &str = "a2c0000000000001";
+const GROUP: &str = "a2c0000000000000";
+
+/// Each call the sink took: the operation and its node IDs
+type Calls = Arc<Mutex<Vec<(&'static str, Vec<String>)>>>;
+
+struct Mock {
+    calls: Calls,
+}
+
+impl Mock {
+    fn record(&self, operation: &'static str, node_ids: impl Iterator<Item = String>) {
+        let mut ids: Vec<String> = node_ids.collect();
+        ids.sort();
+        self.calls.lock().unwrap().push((operation, ids));
+    }
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        self.record("publish", items.keys().map(|id| id.to_string()));
+        Ok(())
+    }
+
+    fn archive(&mut self, node_ids: &[NodeId], _dry_run: bool) -> Result<(), SinkError> {
+        self.record("archive", node_ids.iter().map(|id| id.to_string()));
+        Ok(())
+    }
+
+    fn unarchive(&mut self, node_ids: &[NodeId], _dry_run: bool) -> Result<(), SinkError> {
+        self.record("unarchive", node_ids.iter().map(|id| id.to_string()));
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-archive-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Scratch(dir))
+}
+
+/// Save the canvas with the red note inside the Archive group or beside it, and run once
+fn run(dir: &Path, archived: bool, calls: &Calls) -> Result<Option<PublishState>, Box<dyn Error>> {
+    let canvas_path = dir.join("notes.canvas");
+    let x = if archived { 100 } else { 2000 };
+    let canvas = CanvasBuilder::new()
+        .group_node(GROUP)
+        .label("Archive")
+        .at(0, 0)
+        .size(1000, 1000)
+        .text_node(NOTE, "A note to archive.")
+        .color(PresetColor::Red)
+        .at(x, 100)
+        .size(200, 100)
+        .build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+
+    let mut syndicator = Syndicator::new(Config::new(&canvas_path))?;
+    syndicator.add_sink(Mock { calls: calls.clone() })?;
+    syndicator.process_once()?;
+    Ok(syndicator.tracker("mock").ok_or("no tracker")?.state(&NOTE.parse()?))
+}
+
+#[test]
+fn archive_is_idempotent_and_reversible() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("cycle")?;
+    let calls = Calls::default();
+    assert_eq!(run(&scratch.0, false, &calls)?, Some(PublishState::Published));
+    assert_eq!(run(&scratch.0, true, &calls)?, Some(PublishState::Archived));
+    // Runs while it stays in the group leave it alone
+    assert_eq!(run(&scratch.0, true, &calls)?, Some(PublishState::Archived));
+    assert_eq!(run(&scratch.0, true, &calls)?, Some(PublishState::Archived));
+    assert_eq!(run(&scratch.0, false, &calls)?, Some(PublishState::Published));
+    assert_eq!(run(&scratch.0, false, &calls)?, Some(PublishState::Published));
+
+    let note = vec![NOTE.to_string()];
+    let expected = [("publish", note.clone()), ("archive", note.clone()), ("unarchive", note)];
+    assert_eq!(*calls.lock().unwrap(), expected);
+    Ok(())
+}
+
+#[test]
+fn unpublished_node_in_the_archive_is_never_published() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("unpublished")?;
+    let calls = Calls::default();
+    assert_eq!(run(&scratch.0, true, &calls)?, None);
+    assert!(calls.lock().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn directory_sink_archives_a_post_once_and_restores_it() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("directory")?;
+    let canvas = CanvasBuilder::new().text_node(NOTE, "A note to archive.").color(PresetColor::Red).build()?;
+    let items = to_syndication_format(&canvas, Some(default_process_node))?;
+    let mut sink = DirectorySink::new(scratch.0.join("site"));
+    sink.publish(&items, false)?;
+    let post = std::fs::read_dir(scratch.0.join("site"))?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.to_string_lossy().ends_with(&format!("-{}.md", NOTE)))
+        .ok_or("no post")?;
+    let published = std::fs::read_to_string(&post)?;
+
+    let note: NodeId = NOTE.parse()?;
+    sink.archive(std::slice::from_ref(&note), false)?;
+    let archived = std::fs::read_to_string(&post)?;
+    assert_eq!(archived.matches("archived: true").count(), 1, "{}", archived);
+    sink.archive(std::slice::from_ref(&note), false)?;
+    assert_eq!(std::fs::read_to_string(&post)?, archived, "archiving again changed the post");
+
+    sink.unarchive(std::slice::from_ref(&note), false)?;
+    assert_eq!(std::fs::read_to_string(&post)?, published);
+    Ok(())
+}
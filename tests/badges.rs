@@ -0,0 +1,91 @@
+//! Badge files written after each run: the count of published nodes from the tracker, and
+//! the days since the last post from the history log, as time passes.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::{Local, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    BadgeSpec, CanvasBuilder, Config, MockClock, SinkError, SinkTarget, SyndicationFormat, SyndicationSink,
+    SyndicationTracker, Syndicator,
+};
+
+const NOTES: [&str; 2] = ["bad9000000000001", "bad9000000000002"];
+
+struct Mock(&'static str);
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, _items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.0
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn badges_count_posts_and_go_stale() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-badges-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let mut builder = CanvasBuilder::new();
+    for id in NOTES {
+        builder = builder.text_node(id, format!("Note {}", id)).color(PresetColor::Red);
+    }
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+
+    let badges = scratch.0.join("badges");
+    let clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, 1, 23, 0, 0).unwrap()));
+    let syndicator = |dry_run: bool| -> Result<Syndicator, Box<dyn Error>> {
+        let mut config = Config::new(scratch.0.join("notes.canvas"));
+        config.dry_run = dry_run;
+        config.options.clock = clock.clone();
+        config.options.badges = vec![
+            BadgeSpec::published_count("site", badges.join("count.json")).with_label("notes").with_color("green"),
+            BadgeSpec::days_since_last_post("site", badges.join("stale.json")),
+            BadgeSpec::days_since_last_post("elsewhere", badges.join("never.json")),
+        ];
+        let mut syndicator = Syndicator::new(config)?;
+        syndicator.add_sink(Mock("site"))?;
+        // Kept dry, so it never posts
+        let tracker = SyndicationTracker::new(&scratch.0.join("notes.canvas"), "elsewhere")?;
+        syndicator.add_target(SinkTarget::new(Mock("elsewhere"), tracker).with_dry_run(true));
+        Ok(syndicator)
+    };
+    let read = |name: &str| std::fs::read_to_string(badges.join(name));
+
+    // Dry runs leave no badges behind
+    syndicator(true)?.process_once()?;
+    assert!(!badges.exists());
+
+    syndicator(false)?.process_once()?;
+    let count = "{\"schemaVersion\":1,\"label\":\"notes\",\"message\":\"2\",\"color\":\"green\"}\n";
+    assert_eq!(read("count.json")?, count);
+    let stale = "{\"schemaVersion\":1,\"label\":\"days since last post\",\"message\":\"0\",\"color\":\"blue\"}\n";
+    assert_eq!(read("stale.json")?, stale);
+    assert!(read("never.json")?.contains("\"message\":\"never\",\"color\":\"lightgrey\""));
+
+    // Two hours later is another calendar day; nothing new was posted since
+    clock.advance(chrono::Duration::hours(2));
+    syndicator(false)?.process_once()?;
+    assert!(read("stale.json")?.contains("\"message\":\"1\""));
+    clock.advance(chrono::Duration::days(6));
+    syndicator(false)?.process_once()?;
+    assert!(read("stale.json")?.contains("\"message\":\"7\""));
+    assert!(read("count.json")?.contains("\"message\":\"2\""));
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Batched publishing with a mock clock: edits made inside a window are published together
+//! when it closes, with the latest text, even across restarts; a manual batch waits for
+//! `drain`.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{Local, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    BatchPolicy, CanvasBuilder, Config, MockClock, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+};
+
+const NOTES: [&str; 3] = ["ba7c000000000001", "ba7c000000000002", "ba7c000000000003"];
+const WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Each publish call: node IDs and texts, sorted by ID
+type Calls = Arc<Mutex<Vec<Vec<(String, String)>>>>;
+
+struct Mock {
+    calls: Calls,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let mut call: Vec<(String, String)> = items.values().map(|i| (i.id.to_string(), i.text.clone())).collect();
+        call.sort();
+        self.calls.lock().unwrap().push(call);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-batch-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Scratch(dir))
+}
+
+/// Save the canvas with the given red notes
+fn save(dir: &Path, notes: &[(&str, &str)]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (id, text) in notes {
+        builder = builder.text_node(*id, *text).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+/// A fresh process's syndicator under `batching`, as after a restart
+fn syndicator(
+    dir: &Path,
+    batching: BatchPolicy,
+    clock: &Arc<MockClock>,
+    calls: &Calls,
+) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.batching = batching;
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(Mock { calls: calls.clone() })?;
+    Ok(syndicator)
+}
+
+#[test]
+fn three_edits_inside_a_window_publish_once() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("window")?;
+    let clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()));
+    let calls = Calls::default();
+    let batching = BatchPolicy::Window(WINDOW);
+
+    save(&scratch.0, &[(NOTES[0], "First draft.")])?;
+    syndicator(&scratch.0, batching, &clock, &calls)?.process_once()?;
+    clock.advance(chrono::Duration::minutes(3));
+    save(&scratch.0, &[(NOTES[0], "First, edited."), (NOTES[1], "Second.")])?;
+    syndicator(&scratch.0, batching, &clock, &calls)?.process_once()?;
+    clock.advance(chrono::Duration::minutes(3));
+    save(&scratch.0, &[(NOTES[0], "First, edited."), (NOTES[1], "Second."), (NOTES[2], "Third.")])?;
+    let report = syndicator(&scratch.0, batching, &clock, &calls)?.process_once()?;
+    assert!(calls.lock().unwrap().is_empty(), "published before the window closed");
+    assert_eq!(report.batched, NOTES);
+
+    // The window runs from the last addition, so nine minutes on it's still open
+    clock.advance(chrono::Duration::minutes(9));
+    syndicator(&scratch.0, batching, &clock, &calls)?.process_once()?;
+    assert!(calls.lock().unwrap().is_empty());
+    clock.advance(chrono::Duration::minutes(2));
+    syndicator(&scratch.0, batching, &clock, &calls)?.process_once()?;
+    syndicator(&scratch.0, batching, &clock, &calls)?.process_once()?;
+
+    let published = vec![
+        (NOTES[0].to_string(), "First, edited.".to_string()),
+        (NOTES[1].to_string(), "Second.".to_string()),
+        (NOTES[2].to_string(), "Third.".to_string()),
+    ];
+    assert_eq!(*calls.lock().unwrap(), [published]);
+    Ok(())
+}
+
+#[test]
+fn manual_batch_waits_for_drain() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("manual")?;
+    let clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()));
+    let calls = Calls::default();
+
+    save(&scratch.0, &[(NOTES[0], "First."), (NOTES[1], "Second.")])?;
+    syndicator(&scratch.0, BatchPolicy::Manual, &clock, &calls)?.process_once()?;
+    clock.advance(chrono::Duration::days(30));
+    syndicator(&scratch.0, BatchPolicy::Manual, &clock, &calls)?.process_once()?;
+    assert!(calls.lock().unwrap().is_empty());
+
+    syndicator(&scratch.0, BatchPolicy::Manual, &clock, &calls)?.drain(false)?;
+    syndicator(&scratch.0, BatchPolicy::Manual, &clock, &calls)?.process_once()?;
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.len(), 1, "{:?}", calls);
+    assert_eq!(calls[0].iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), NOTES[..2]);
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Categories from hashtags reach the sinks: a `categories:` field in posts and a
+//! `category/<name>` tag on content lake records.
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, CategoryMap, Config, Syndicator};
+use syndicate_json_canvas_sinks::{ContentLakeReader, ContentLakeSink, DirectorySink};
+
+const NOTE: &str = "ca7e000000000001";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn categories_are_written_to_posts_and_the_lake() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-categories-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let canvas = CanvasBuilder::new().text_node(NOTE, "Borrowing #life #rust").color(PresetColor::Red).build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let mut config = Config::new(scratch.0.join("notes.canvas"));
+    config.options.categories = Some(CategoryMap::new().map("#rust", "engineering").map("#life", "personal"));
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(scratch.0.join("site")))?;
+    syndicator.add_sink(ContentLakeSink::new(scratch.0.join("lake")))?;
+    syndicator.process_once()?;
+
+    let post = std::fs::read_dir(scratch.0.join("site"))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>, std::io::Error>>()?
+        .into_iter()
+        .find(|path| path.to_string_lossy().ends_with(&format!("-{}.md", NOTE)))
+        .ok_or("no post")?;
+    let post = std::fs::read_to_string(post)?;
+    assert!(post.contains("categories:\n  - \"engineering\"\n"), "{}", post);
+    let records = ContentLakeReader::new(scratch.0.join("lake")).records()?;
+    assert_eq!(records[0].tags, ["life", "rust", "category/engineering"]);
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Watch-mode commands: the console maps what is typed onto `WatchCommand`s, and each
+//! command has its effect on a running watch loop.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CONSOLE_HELP, CanvasBuilder, Config, PipelineStatus, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+    WatchCommand, run_console,
+};
+
+/// The `dry_run` flag of each publish call
+type Publishes = Arc<Mutex<Vec<bool>>>;
+
+struct Mock {
+    publishes: Publishes,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, _items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.publishes.lock().unwrap().push(dry_run);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Status of the pipeline once a run started after `since` is done
+///
+/// Statuses are only sent between runs, so one showing such a run shows it finished.
+fn run_since(commands: &Sender<WatchCommand>, since: Instant) -> Result<PipelineStatus, Box<dyn Error>> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        commands.send(WatchCommand::Status(reply_tx))?;
+        let status = reply_rx.recv()?.remove(0);
+        if status.last_run.is_some_and(|ago| ago < since.elapsed()) {
+            return Ok(status);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    Err("no run".into())
+}
+
+#[test]
+fn console_sends_commands_and_prints_help() -> Result<(), Box<dyn Error>> {
+    let (commands, received) = mpsc::channel();
+    let replier = std::thread::spawn(move || {
+        let mut seen = Vec::new();
+        for command in received {
+            if let WatchCommand::Status(reply) = &command {
+                reply.send(Vec::new()).unwrap();
+            }
+            seen.push(format!("{:?}", command).split('(').next().unwrap().to_string());
+        }
+        seen
+    });
+
+    let mut output = Vec::new();
+    run_console(Cursor::new("x\n\np\n d \ns\nq\np\n"), &mut output, commands)?;
+    assert_eq!(replier.join().unwrap(), ["Process", "ToggleDryRun", "Status", "Shutdown"]);
+    assert_eq!(String::from_utf8(output)?, format!("unknown command `x`; {}\n", CONSOLE_HELP));
+    Ok(())
+}
+
+#[test]
+fn commands_drive_a_watch_loop() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-console-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let canvas = CanvasBuilder::new().text_node("c0de000000000001", "A note").color(PresetColor::Red).build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let mut config = Config::new(scratch.0.join("notes.canvas"));
+    config.dry_run = true;
+    let mut syndicator = Syndicator::new(config)?;
+    let publishes = Publishes::default();
+    syndicator.add_sink(Mock { publishes: publishes.clone() })?;
+    let (commands, receiver) = mpsc::channel();
+    let started = Instant::now();
+    let watcher = std::thread::spawn(move || syndicator.watch(receiver).map_err(|e| e.to_string()));
+
+    // The startup run is dry
+    assert!(run_since(&commands, started)?.dry_run);
+    assert_eq!(*publishes.lock().unwrap(), [true]);
+
+    commands.send(WatchCommand::ToggleDryRun)?;
+    let requested = Instant::now();
+    commands.send(WatchCommand::Process)?;
+    assert!(!run_since(&commands, requested)?.dry_run);
+    assert_eq!(*publishes.lock().unwrap(), [true, false]);
+
+    // Nothing left to publish
+    let requested = Instant::now();
+    commands.send(WatchCommand::Process)?;
+    run_since(&commands, requested)?;
+    assert_eq!(publishes.lock().unwrap().len(), 2);
+
+    commands.send(WatchCommand::Shutdown)?;
+    watcher.join().unwrap()?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Content warnings through every sink: the same flagged items come out as post frontmatter,
+//! lake record fields and a `CW:` line on tweets, and unflagged items carry none of them.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, HttpClient, HttpError, HttpRequest, HttpResponse, Syndicator};
+use syndicate_json_canvas_sinks::{ContentLakeReader, ContentLakeSink, DirectorySink, TwitterSink};
+
+const FLAGGED: &str = "c3a0000000000001";
+const TOKENED: &str = "c3a0000000000002";
+const PLAIN: &str = "c3a0000000000003";
+
+/// The Twitter API, keeping the text of every tweet
+#[derive(Debug, Default)]
+struct MockTwitter {
+    tweets: Mutex<Vec<String>>,
+}
+
+impl HttpClient for MockTwitter {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        let body: serde_json::Value = serde_json::from_str(request.body.as_deref().unwrap_or("{}"))
+            .map_err(|e| HttpError::InvalidRequest(e.to_string()))?;
+        let mut tweets = self.tweets.lock().unwrap();
+        tweets.push(body["text"].as_str().unwrap_or_default().to_string());
+        let body = format!(r#"{{"data": {{"id": "{}"}}}}"#, tweets.len());
+        Ok(HttpResponse { status: 201, headers: Vec::new(), body })
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Contents of the post published from `id`
+fn post_of(site: &Path, id: &str) -> Result<String, Box<dyn Error>> {
+    for entry in std::fs::read_dir(site)? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(&format!("-{}.md", id)) {
+            return Ok(std::fs::read_to_string(path)?);
+        }
+    }
+    Err(format!("no post of {}", id).into())
+}
+
+#[test]
+fn each_sink_shows_the_warning_its_own_way() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-content-warnings-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let canvas = CanvasBuilder::new()
+        .text_node(FLAGGED, "---\ncw: politics\n---\nA take on the election")
+        .color(PresetColor::Red)
+        .text_node(TOKENED, "The ending of the film #cw:spoilers")
+        .color(PresetColor::Red)
+        .text_node(PLAIN, "Nothing sensitive here")
+        .color(PresetColor::Red)
+        .build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let twitter = Arc::new(MockTwitter::default());
+    let mut syndicator = Syndicator::new(Config::new(scratch.0.join("notes.canvas")))?;
+    syndicator.add_sink(DirectorySink::new(scratch.0.join("site")))?;
+    syndicator.add_sink(ContentLakeSink::new(scratch.0.join("lake")))?;
+    syndicator.add_sink(
+        TwitterSink::new("token", scratch.0.join("twitter.toml"))?
+            .with_http_client(twitter.clone())
+            .with_content_warnings(true),
+    )?;
+    let report = syndicator.process_once()?;
+    assert!(report.sinks.iter().all(|sink| sink.error.is_none()), "{:?}", report.sinks);
+
+    let flagged: Vec<(&str, &str)> = report.content_warnings.iter().map(|(id, t)| (id.as_str(), t.as_str())).collect();
+    assert_eq!(flagged, [(FLAGGED, "politics"), (TOKENED, "spoilers")]);
+    let markdown = report.to_markdown();
+    assert!(markdown.contains(&format!("## Content warnings\n\n- `{}`: politics\n", FLAGGED)), "{}", markdown);
+
+    let site = scratch.0.join("site");
+    assert!(post_of(&site, FLAGGED)?.contains("content_warning: \"politics\"\n"));
+    let tokened = post_of(&site, TOKENED)?;
+    assert!(tokened.contains("content_warning: \"spoilers\"\n"), "{}", tokened);
+    assert!(!tokened.contains("#cw:"), "{}", tokened);
+    assert!(!post_of(&site, PLAIN)?.contains("content_warning"));
+
+    let lake = ContentLakeReader::new(scratch.0.join("lake")).latest()?;
+    let topic_of = |id: &str| lake[id].content_warning.as_deref();
+    assert_eq!((topic_of(FLAGGED), topic_of(TOKENED), topic_of(PLAIN)), (Some("politics"), Some("spoilers"), None));
+
+    let mut tweets = twitter.tweets.lock().unwrap().clone();
+    tweets.sort();
+    assert_eq!(tweets.len(), 3, "{:?}", tweets);
+    assert!(tweets[0].starts_with("CW: politics\n\n") && tweets[0].ends_with("A take on the election"), "{:?}", tweets);
+    assert!(tweets[1].starts_with("CW: spoilers\n\nThe ending of the film"), "{:?}", tweets);
+    assert!(tweets[2].starts_with("Nothing sensitive here") && !tweets[2].contains("CW"), "{:?}", tweets);
+    Ok(())
+}
@@ -0,0 +1,126 @@
+//! Wikilinks between canvases publishing into one folder: `[[canvas#Title]]` and bare
+//! titles resolving to the other canvas's post, the folder's canvas manifest, and the pick
+//! among several canvases with the same title.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::warnings::codes;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, ProcessReport, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const READING: &str = "7ead000000000001";
+const NOTES: &str = "4073000000000001";
+const IDEA: &str = "1dea000000000001";
+
+/// A scratch directory removed when dropped
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-cross-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Write `<stem>.canvas` holding red notes and publish it into the shared `site` folder
+fn publish(dir: &Path, stem: &str, notes: &[(&str, &str)]) -> Result<ProcessReport, Box<dyn Error>> {
+    let canvas_path = dir.join(format!("{}.canvas", stem));
+    let mut builder = CanvasBuilder::new();
+    for (id, text) in notes {
+        builder = builder.text_node(*id, *text).color(PresetColor::Red);
+    }
+    let canvas = builder.build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+    let mut syndicator = Syndicator::new(Config::new(canvas_path))?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator.process_once()?)
+}
+
+/// Name of the post published from `id`
+fn post_file(dir: &Path, id: &str) -> Result<String, Box<dyn Error>> {
+    for entry in std::fs::read_dir(dir.join("site"))? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name.ends_with(&format!("-{}.md", id)) {
+            return Ok(name);
+        }
+    }
+    Err(format!("no post of {}", id).into())
+}
+
+/// Messages of the `ambiguous-wikilink` warnings the sinks raised
+fn ambiguous(report: &ProcessReport) -> Vec<String> {
+    let warnings = report.sinks.iter().flat_map(|outcome| &outcome.warnings);
+    warnings.filter(|w| w.code == codes::AMBIGUOUS_WIKILINK).map(|w| w.message.clone()).collect()
+}
+
+#[test]
+fn canvas_qualified_wikilink_links_the_other_canvas_post() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch::new("qualified");
+    publish(&scratch.0, "reading", &[(READING, "Some note title")])?;
+    publish(&scratch.0, "ideas", &[(IDEA, "Thinking about [[reading#Some note title]] today.")])?;
+
+    let href = format!("/t/{}", post_file(&scratch.0, READING)?);
+    let idea = std::fs::read_to_string(scratch.0.join("site").join(post_file(&scratch.0, IDEA)?))?;
+    assert!(idea.contains(&format!("[Some note title]({})", href)), "{}", idea);
+    assert!(idea.contains(&href), "listed under further_thinking: {}", idea);
+
+    let manifest = std::fs::read_to_string(scratch.0.join("site/.syndication-canvases.json"))?;
+    let keys: Vec<String> = serde_json::from_str(&manifest)?;
+    assert_eq!(keys, [format!("ideas:{}", IDEA), format!("reading:{}", READING)]);
+    Ok(())
+}
+
+#[test]
+fn folder_without_manifest_is_migrated() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch::new("migrated");
+    publish(&scratch.0, "reading", &[(READING, "Some note title")])?;
+    // As left by a version that kept no manifest
+    std::fs::remove_file(scratch.0.join("site/.syndication-canvases.json"))?;
+
+    publish(&scratch.0, "reading", &[(READING, "Some note title"), ("7ead000000000002", "Another note")])?;
+    let manifest = std::fs::read_to_string(scratch.0.join("site/.syndication-canvases.json"))?;
+    let keys: Vec<String> = serde_json::from_str(&manifest)?;
+    assert_eq!(keys, [format!("reading:{}", READING), "reading:7ead000000000002".to_string()]);
+    Ok(())
+}
+
+#[test]
+fn ambiguous_title_picks_the_first_canvas_and_warns() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch::new("ambiguous");
+    publish(&scratch.0, "reading", &[(READING, "Shared title")])?;
+    publish(&scratch.0, "notes", &[(NOTES, "Shared title")])?;
+    let report = publish(&scratch.0, "ideas", &[(IDEA, "See [[Shared title]].")])?;
+
+    let idea = std::fs::read_to_string(scratch.0.join("site").join(post_file(&scratch.0, IDEA)?))?;
+    let href = format!("/t/{}", post_file(&scratch.0, NOTES)?);
+    assert!(idea.contains(&format!("[Shared title]({})", href)), "{}", idea);
+    let warnings = ambiguous(&report);
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    assert!(warnings[0].contains("canvases notes, reading; linked the one of notes"), "{}", warnings[0]);
+    Ok(())
+}
+
+#[test]
+fn own_canvas_post_wins_without_warning() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch::new("own");
+    publish(&scratch.0, "reading", &[(READING, "Shared title")])?;
+    publish(&scratch.0, "notes", &[(NOTES, "Shared title")])?;
+    let notes = [(READING, "Shared title"), ("7ead000000000002", "Back to [[Shared title]].")];
+    let report = publish(&scratch.0, "reading", &notes)?;
+
+    let post = post_file(&scratch.0, "7ead000000000002")?;
+    let text = std::fs::read_to_string(scratch.0.join("site").join(post))?;
+    assert!(text.contains(&format!("(/t/{})", post_file(&scratch.0, READING)?)), "{}", text);
+    assert_eq!(ambiguous(&report), Vec::<String>::new());
+    Ok(())
+}
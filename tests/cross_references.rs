@@ -0,0 +1,67 @@
+//! Frontmatter cross-references only name neighbors that are published: ones from this run
+//! or earlier runs, never a neighbor that stays off the site.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const EARLIER: &str = "c2f0000000000001";
+const LATER: &str = "c2f0000000000002";
+const PRIVATE: &str = "c2f0000000000003";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Save the canvas, `EARLIER -> LATER -> PRIVATE` with only the first colored red until
+/// `later_is_red`, and publish it into `site`
+fn publish(dir: &Path, later_is_red: bool) -> Result<(), Box<dyn Error>> {
+    let canvas_path = dir.join("notes.canvas");
+    let mut builder = CanvasBuilder::new().text_node(EARLIER, "Published first").color(PresetColor::Red);
+    builder = builder.text_node(LATER, "Published second");
+    if later_is_red {
+        builder = builder.color(PresetColor::Red);
+    }
+    let canvas = builder.text_node(PRIVATE, "Never published").edge(EARLIER, LATER).edge(LATER, PRIVATE).build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+    let mut syndicator = Syndicator::new(Config::new(canvas_path))?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    syndicator.process_once()?;
+    Ok(())
+}
+
+/// Contents of the post published from `id`
+fn post(dir: &Path, id: &str) -> Result<String, Box<dyn Error>> {
+    for entry in std::fs::read_dir(dir.join("site"))? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(&format!("-{}.md", id)) {
+            return Ok(std::fs::read_to_string(path)?);
+        }
+    }
+    Err(format!("no post of {}", id).into())
+}
+
+#[test]
+fn only_published_neighbors_are_cross_referenced() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-cross-references-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+
+    publish(&scratch.0, false)?;
+    let earlier = post(&scratch.0, EARLIER)?;
+    assert!(!earlier.contains("further_thinking"), "links an unpublished neighbor: {}", earlier);
+
+    publish(&scratch.0, true)?;
+    let later = post(&scratch.0, LATER)?;
+    assert!(later.contains("context_for_this:\n  - link_text: \"Published first\""), "{}", later);
+    assert!(!later.contains("further_thinking") && !later.contains("Never published"), "{}", later);
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Date tokens in node text: every sink gets the expanded text, the canvas keeps the
+//! tokens, and a later day's different expansion doesn't republish the node.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use chrono::{Local, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, MockClock, SinkError, SyndicationFormat, SyndicationSink, Syndicator, TokenOptions,
+};
+
+const NOTE: &str = "da7e000000000001";
+
+/// Texts of the items a sink was given, published or updated
+type Texts = Arc<Mutex<Vec<String>>>;
+
+struct Mock {
+    texts: Texts,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        self.texts.lock().unwrap().extend(items.values().map(|item| item.text.clone()));
+        Ok(())
+    }
+
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.publish(items, dry_run)
+    }
+
+    fn supports_updates(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn tokens_expand_without_republishing_the_next_day() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-date-tokens-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let write = |text: &str| -> Result<String, Box<dyn Error>> {
+        let canvas = CanvasBuilder::new().text_node(NOTE, text).color(PresetColor::Red).build()?;
+        let json = serde_json::to_string(&canvas)?;
+        std::fs::write(scratch.0.join("notes.canvas"), &json)?;
+        Ok(json)
+    };
+    let saved = write("Started {{today}}, review {{+7d}}, not {{soon}}")?;
+
+    let clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()));
+    let mut config = Config::new(scratch.0.join("notes.canvas"));
+    config.options.clock = clock.clone();
+    config.options.tokens = Some(TokenOptions::default());
+    let mut syndicator = Syndicator::new(config)?;
+    let texts = Texts::default();
+    syndicator.add_sink(Mock { texts: texts.clone() })?;
+
+    let report = syndicator.process_once()?;
+    assert_eq!(*texts.lock().unwrap(), ["Started 2026-03-01, review 2026-03-08, not {{soon}}"]);
+    let warnings: Vec<_> = report.all_warnings().map(|w| (w.code.as_str(), w.message.as_str())).collect();
+    assert_eq!(warnings, [("unknown-token", "Unknown token {{soon}} left as is")]);
+    assert_eq!(std::fs::read_to_string(scratch.0.join("notes.canvas"))?, saved);
+
+    // The expansion changes the next day, but the text as written didn't
+    clock.advance(chrono::Duration::days(1));
+    assert!(syndicator.process_once()?.updated_items.is_empty());
+    assert_eq!(texts.lock().unwrap().len(), 1);
+
+    // An edit is still an edit
+    write("Started {{today}}, review {{+14d}}")?;
+    assert_eq!(syndicator.process_once()?.updated_items, [NOTE]);
+    assert_eq!(texts.lock().unwrap()[1], "Started 2026-03-02, review 2026-03-16");
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! The bundled example canvas and the `demo` command: four posts come out, the excluded and
+//! the uncolored node don't, and cross-references between the posts are kept.
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::Command;
+
+use syndicate_json_canvas_lib::{Config, EXAMPLE_EXCLUDED_NODE_ID, Syndicator, write_example_canvas};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn example_canvas_publishes_four_linked_posts() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch(std::env::temp_dir().join(format!("syndicate-json-canvas-example-{}", std::process::id())));
+    let _ = std::fs::remove_dir_all(&scratch.0);
+    let canvas_path = write_example_canvas(&scratch.0)?;
+    let mut syndicator = Syndicator::new(Config::new(canvas_path))?;
+    syndicator.add_sink(DirectorySink::new(scratch.0.join("site")))?;
+    let report = syndicator.process_once()?;
+
+    assert_eq!(report.excluded, [EXAMPLE_EXCLUDED_NODE_ID]);
+    let mut posts: Vec<String> = std::fs::read_dir(scratch.0.join("site"))?
+        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+        .collect::<Result<_, std::io::Error>>()?;
+    posts.retain(|name| !name.starts_with('.'));
+    posts.sort_by_key(|name| name[name.len() - "0001.md".len()..].to_string());
+    let ids: Vec<&str> = posts.iter().map(|name| &name[name.len() - "0001.md".len()..name.len() - 3]).collect();
+    assert_eq!(ids, ["0001", "0002", "0003", "0004"], "{:?}", posts);
+
+    let first = std::fs::read_to_string(scratch.0.join("site").join(&posts[0]))?;
+    assert!(first.contains("context_for_this:") && first.contains(&posts[3]), "{}", first);
+    assert!(first.contains("further_thinking:") && first.contains(&posts[1]), "{}", first);
+    Ok(())
+}
+
+#[test]
+fn demo_command_reports_what_it_published() -> Result<(), Box<dyn Error>> {
+    let output = Command::new(env!("CARGO_BIN_EXE_syndicate-obsidian-canvas")).arg("demo").output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    let scratch = stdout.lines().find_map(|line| line.strip_prefix("Delete ")?.strip_suffix(" when done"));
+    let _cleanup = scratch.map(|dir| Scratch(PathBuf::from(dir)));
+    assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+    // Logs go to stdout too
+    assert!(stdout.lines().any(|line| line.starts_with("Published 4 example posts from ")), "{}", stdout);
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! A wet and a dry sink in one run: only the wet one's tracker records what was published,
+//! so the dry one is offered the same items again once it's armed.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, SinkError, SinkTarget, SyndicationFormat, SyndicationSink, SyndicationTracker, Syndicator,
+};
+
+const NOTES: [&str; 2] = ["d270000000000001", "d270000000000002"];
+
+/// Node IDs a sink was given, each with the dry-run flag it came with
+type Calls = Arc<Mutex<Vec<(String, bool)>>>;
+
+struct Mock {
+    name: &'static str,
+    calls: Calls,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.calls.lock().unwrap().extend(items.keys().map(|id| (id.to_string(), dry_run)));
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-dry-run-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let mut builder = CanvasBuilder::new();
+    for id in NOTES {
+        builder = builder.text_node(id, format!("Note {}.", id)).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    Ok(Scratch(dir))
+}
+
+/// A run with a wet `jj` mock and a `twitter` mock kept dry unless `armed`
+fn syndicator(dir: &Path, global_dry_run: bool, armed: bool) -> Result<(Syndicator, Calls, Calls), Box<dyn Error>> {
+    let canvas_path = dir.join("notes.canvas");
+    let mut config = Config::new(&canvas_path);
+    config.dry_run = global_dry_run;
+    let mut syndicator = Syndicator::new(config)?;
+    let (wet, dry) = (Calls::default(), Calls::default());
+    syndicator.add_sink(Mock { name: "jj", calls: wet.clone() })?;
+    let tracker = SyndicationTracker::new(&canvas_path, "twitter")?;
+    let twitter = SinkTarget::new(Mock { name: "twitter", calls: dry.clone() }, tracker).with_dry_run(!armed);
+    syndicator.add_target(twitter);
+    Ok((syndicator, wet, dry))
+}
+
+fn published(syndicator: &Syndicator, sink: &str) -> usize {
+    let tracker = syndicator.tracker(sink).unwrap();
+    NOTES.iter().filter(|id| tracker.is_published(&id.parse().unwrap())).count()
+}
+
+#[test]
+fn only_wet_sinks_track_what_they_published() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("divergent")?;
+    let (mut first, wet, dry) = syndicator(&scratch.0, false, false)?;
+    first.process_once()?;
+
+    let (wet, dry) = (wet.lock().unwrap().clone(), dry.lock().unwrap().clone());
+    assert!(wet.len() == NOTES.len() && wet.iter().all(|(_, dry_run)| !dry_run), "{:?}", wet);
+    assert!(dry.len() == NOTES.len() && dry.iter().all(|(_, dry_run)| *dry_run), "{:?}", dry);
+    assert_eq!((published(&first, "jj"), published(&first, "twitter")), (NOTES.len(), 0));
+
+    // Armed, the sink that ran dry is offered everything again, and the wet one nothing
+    let (mut armed, wet, dry) = syndicator(&scratch.0, false, true)?;
+    armed.process_once()?;
+    assert!(wet.lock().unwrap().is_empty());
+    assert_eq!(dry.lock().unwrap().len(), NOTES.len());
+    assert_eq!(published(&armed, "twitter"), NOTES.len());
+    Ok(())
+}
+
+#[test]
+fn global_dry_run_overrides_armed_sinks() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("global")?;
+    let (mut forced, wet, dry) = syndicator(&scratch.0, true, true)?;
+    forced.process_once()?;
+
+    let calls: Vec<(String, bool)> = wet.lock().unwrap().iter().chain(dry.lock().unwrap().iter()).cloned().collect();
+    assert_eq!(calls.len(), 2 * NOTES.len());
+    assert!(calls.iter().all(|(_, dry_run)| *dry_run));
+    assert_eq!((published(&forced, "jj"), published(&forced, "twitter")), (0, 0));
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! Dry runs compare each file they would write with the published one: new files are
+//! creates, changed ones modifies with a diff, and unchanged ones identical.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{Local, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, MockClock, Syndicator, WriteKind, read_tree};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const SAME: &str = "d1ff000000000001";
+const EDITED: &str = "d1ff000000000002";
+const NEW: &str = "d1ff000000000003";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Save the canvas with its notes and their texts, and run it into `site`, always at the same time
+fn run(dir: &Path, notes: &[(&str, &str)], dry_run: bool) -> Result<Syndicator, Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (id, text) in notes {
+        builder = builder.text_node(*id, *text).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.dry_run = dry_run;
+    config.options.clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()));
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator)
+}
+
+#[test]
+fn dry_run_classifies_each_file_against_the_published_one() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-dry-run-diff-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    run(&scratch.0, &[(SAME, "Unchanged\n\nAs it was."), (EDITED, "Edited\n\nFirst draft.")], false)?.process_once()?;
+    let published = read_tree(&scratch.0.join("site"))?;
+
+    // Without the tracker every note is offered again
+    std::fs::remove_file(scratch.0.join(".notes.canvas.syndication.directory.toml"))?;
+    let notes = [(SAME, "Unchanged\n\nAs it was."), (EDITED, "Edited\n\nSecond draft."), (NEW, "Brand new")];
+    let report = run(&scratch.0, &notes, true)?.process_once()?;
+
+    let writes = &report.sinks[0].planned_writes;
+    let kind_of = |id: &str| writes.iter().find(|w| w.node_id.as_deref() == Some(id)).map(|w| w.kind);
+    assert_eq!(kind_of(SAME), Some(WriteKind::Identical), "{:?}", writes);
+    assert_eq!(kind_of(EDITED), Some(WriteKind::Modify), "{:?}", writes);
+    assert_eq!(kind_of(NEW), Some(WriteKind::Create), "{:?}", writes);
+
+    let edited = writes.iter().find(|w| w.node_id.as_deref() == Some(EDITED)).ok_or("no write")?;
+    let diff = edited.diff.as_deref().ok_or("no diff")?;
+    assert!(diff.contains("-First draft.") && diff.contains("+Second draft."), "{}", diff);
+    assert!(writes.iter().all(|w| w.kind == WriteKind::Modify || w.diff.is_none()));
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("### Would write: 1 to create, 1 to modify, 1 identical"), "{}", markdown);
+
+    assert_eq!(read_tree(&scratch.0.join("site"))?, published, "the dry run changed the site");
+    Ok(())
+}
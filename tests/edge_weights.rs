@@ -0,0 +1,67 @@
+//! Edge colors weight cross-references: neighbors are listed heaviest first, with the
+//! weight and emphasis label of the edge when asked for.
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, EdgeWeights, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const HUB: &str = "ed9e000000000001";
+const PLAIN: &str = "ed9e000000000002";
+const RELATED: &str = "ed9e000000000003";
+const STRONG: &str = "ed9e000000000004";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn neighbors_are_listed_heaviest_first_with_their_weight() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-edge-weights-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let mut builder = CanvasBuilder::new().text_node(HUB, "Hub").color(PresetColor::Red);
+    for (id, text) in [(PLAIN, "Plain"), (RELATED, "Related"), (STRONG, "Strong")] {
+        builder = builder.text_node(id, text).color(PresetColor::Red);
+    }
+    // In canvas order the lightest edge comes first
+    let canvas = builder
+        .edge(HUB, PLAIN)
+        .edge(HUB, RELATED)
+        .color(PresetColor::Cyan)
+        .edge(HUB, STRONG)
+        .color(PresetColor::Red)
+        .build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let mut config = Config::new(scratch.0.join("notes.canvas"));
+    config.options.edge_weights = Some(EdgeWeights::parse(&[("red", 3.0, Some("strong")), ("cyan", 2.0, None)])?);
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(scratch.0.join("site")).with_link_weights(true))?;
+    syndicator.process_once()?;
+
+    let post = std::fs::read_to_string(scratch.0.join("site").join(format!("hub-{}.md", HUB)))?;
+    let expected = format!(
+        concat!(
+            "further_thinking:\n",
+            "  - link_text: \"Strong\"\n    href: \"/t/strong-{}.md\"\n    weight: 3\n    emphasis: \"strong\"\n",
+            "  - link_text: \"Related\"\n    href: \"/t/related-{}.md\"\n    weight: 2\n",
+            "  - link_text: \"Plain\"\n    href: \"/t/plain-{}.md\"\n    weight: 1\n",
+        ),
+        STRONG, RELATED, PLAIN
+    );
+    assert!(post.contains(&expected), "{}", post);
+
+    // The other end sees the same edge as context
+    let strong = std::fs::read_to_string(scratch.0.join("site").join(format!("strong-{}.md", STRONG)))?;
+    assert!(strong.contains("context_for_this:\n  - link_text: \"Hub\"\n"), "{}", strong);
+    assert!(strong.contains("    weight: 3\n    emphasis: \"strong\"\n"), "{}", strong);
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! The library facade as another program would drive it: `process_once` end to end with a
+//! mock sink, its errors, and a watch that stops on command.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, SinkError, SyndicationError, SyndicationFormat, SyndicationSink, Syndicator, WatchCommand,
+};
+
+const NOTES: [&str; 2] = ["e3b0000000000001", "e3b0000000000002"];
+
+type Published = Arc<Mutex<Vec<String>>>;
+
+struct Mock {
+    published: Published,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        self.published.lock().unwrap().extend(items.keys().map(|id| id.to_string()));
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-embedded-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let mut builder = CanvasBuilder::new().text_node("e3b0000000000000", "Not red");
+    for id in NOTES {
+        builder = builder.text_node(id, format!("Note {}", id)).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    Ok(Scratch(dir))
+}
+
+fn syndicator(dir: &Path, published: &Published) -> Result<Syndicator, Box<dyn Error>> {
+    let mut syndicator = Syndicator::new(Config::new(dir.join("notes.canvas")))?;
+    syndicator.add_sink(Mock { published: published.clone() })?;
+    Ok(syndicator)
+}
+
+#[test]
+fn process_once_publishes_and_reports() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("process")?;
+    let published = Published::default();
+    let mut syndicator = syndicator(&scratch.0, &published)?;
+
+    let report = syndicator.process_once()?;
+    assert_eq!((report.nodes_considered, report.items_matched), (3, 2));
+    let mut new_items = report.new_items.clone();
+    new_items.sort();
+    assert_eq!(new_items, NOTES);
+    assert_eq!(report.sinks.len(), 1);
+    assert!(report.sinks[0].error.is_none());
+    let tracker = syndicator.tracker("mock").ok_or("no tracker")?;
+    assert!(NOTES.iter().all(|id| tracker.is_published(&id.parse().unwrap())));
+    assert!(syndicator.queue()?.is_empty());
+
+    let again = syndicator.process_once()?;
+    assert!(again.new_items.is_empty());
+    assert_eq!(again.already_published, NOTES.len());
+    assert_eq!(published.lock().unwrap().len(), NOTES.len());
+    Ok(())
+}
+
+#[test]
+fn failures_come_back_as_errors() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("errors")?;
+    let not_a_canvas = Syndicator::new(Config::new(scratch.0.join("notes.md")));
+    assert!(matches!(not_a_canvas, Err(SyndicationError::InvalidCanvas(_))));
+
+    std::fs::write(scratch.0.join("notes.canvas"), "{ not json")?;
+    let published = Published::default();
+    let broken = syndicator(&scratch.0, &published)?.process_once();
+    assert!(matches!(broken, Err(SyndicationError::Process(_))), "{:?}", broken.map(|r| r.new_items));
+    assert!(published.lock().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn watch_returns_on_shutdown() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("watch")?;
+    let published = Published::default();
+    let (commands, receiver) = mpsc::channel();
+    commands.send(WatchCommand::Shutdown)?;
+    syndicator(&scratch.0, &published)?.watch(receiver)?;
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Nodes left blank by replacement rules: they're skipped with a warning, dropped from other
+//! posts' links, and published normally once they have content again.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, ProcessReport, Replacements, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+};
+
+const KEPT: &str = "e3b7000000000001";
+const BLANKED: &str = "e3b7000000000002";
+
+/// Text and outgoing links of each item a sink was given
+type Items = Arc<Mutex<HashMap<String, (String, Vec<String>)>>>;
+
+struct Mock {
+    items: Items,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let mut seen = self.items.lock().unwrap();
+        for (id, item) in items {
+            let links = item.out_neighbor_ids.iter().map(|id| id.to_string()).collect();
+            seen.insert(id.to_string(), (item.text.clone(), links));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn run(dir: &Path, blanked_text: &str, items: &Items) -> Result<ProcessReport, Box<dyn Error>> {
+    let canvas = CanvasBuilder::new()
+        .text_node(KEPT, "A finished thought")
+        .color(PresetColor::Red)
+        .text_node(BLANKED, blanked_text)
+        .color(PresetColor::Red)
+        .edge(KEPT, BLANKED)
+        .build()?;
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.replacements = Some(Replacements::from_toml("[[replace]]\nfind = \"TODO\"\nwith = \"\"\n")?);
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(Mock { items: items.clone() })?;
+    Ok(syndicator.process_once()?)
+}
+
+#[test]
+fn blanked_nodes_are_skipped_until_they_have_content() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-empty-items-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let items = Items::default();
+
+    let report = run(&scratch.0, "TODO  TODO", &items)?;
+    assert_eq!(items.lock().unwrap().keys().collect::<Vec<_>>(), [KEPT]);
+    assert!(items.lock().unwrap()[KEPT].1.is_empty());
+    let warnings: Vec<_> =
+        report.all_warnings().map(|w| (w.code.as_str(), w.node_id.as_deref(), w.message.as_str())).collect();
+    assert_eq!(warnings, [("empty-item", Some(BLANKED), "Nothing left to publish after replacements, skipped")]);
+
+    // Not recorded as published, so it goes out once there's something left of it
+    let report = run(&scratch.0, "TODO Water the plants", &items)?;
+    assert_eq!(report.new_items, [BLANKED]);
+    assert_eq!(items.lock().unwrap()[BLANKED].0, " Water the plants");
+    assert_eq!(report.all_warnings().count(), 0);
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Script sinks: each item's outcome comes from the line the script prints for it, and a
+//! script that outlives its timeout is killed rather than hanging the run.
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, Syndicator};
+use syndicate_json_canvas_sinks::ExecSink;
+
+const DELIVERED: &str = "e0ec000000000001";
+const REFUSED: &str = "e0ec000000000002";
+
+/// Answers `ok` with a URL for each item, except `error` for those mentioning "refuse"
+const SCRIPT: &str = r#"
+echo "$SYNDICATE_SINK $SYNDICATE_DRY_RUN $1" > "$(dirname "$0")/called"
+while IFS= read -r line; do
+    id=$(printf '%s' "$line" | grep -o '"id":"[0-9a-f]*"' | head -n 1 | cut -d '"' -f 4)
+    case "$line" in
+        *refuse*) printf '{"node_id":"%s","status":"error","error":"quota exceeded"}\n' "$id" ;;
+        *) printf '{"node_id":"%s","status":"ok","url":"https://example.com/%s"}\n' "$id" "$id" ;;
+    esac
+done
+"#;
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(topic: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-{}-{}", topic, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Scratch(dir))
+}
+
+#[test]
+fn each_item_gets_the_outcome_the_script_printed() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("exec-outcomes")?;
+    let script = scratch.0.join("deliver.sh");
+    std::fs::write(&script, SCRIPT)?;
+    let canvas = CanvasBuilder::new()
+        .text_node(DELIVERED, "Posted fine")
+        .color(PresetColor::Red)
+        .text_node(REFUSED, "The site will refuse this one")
+        .color(PresetColor::Red)
+        .build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let mut syndicator = Syndicator::new(Config::new(scratch.0.join("notes.canvas")))?;
+    syndicator.add_sink(ExecSink::new("script", "sh", &[script.to_str().unwrap(), "{sink}"]))?;
+    let report = syndicator.process_once()?;
+
+    let outcome = &report.sinks[0];
+    assert_eq!(outcome.error_node_id.as_deref(), Some(REFUSED));
+    assert!(outcome.error.as_deref().is_some_and(|e| e.contains("quota exceeded")), "{:?}", outcome.error);
+    let urls: Vec<_> = outcome.urls.iter().map(|(id, url)| (id.as_str(), url.as_str())).collect();
+    assert_eq!(urls, [(DELIVERED, "https://example.com/e0ec000000000001")]);
+    assert_eq!(std::fs::read_to_string(scratch.0.join("called"))?, "script 0 script\n");
+    Ok(())
+}
+
+#[test]
+fn a_script_past_its_timeout_is_killed() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("exec-timeout")?;
+    let canvas = CanvasBuilder::new().text_node(DELIVERED, "Posted slowly").color(PresetColor::Red).build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let mut syndicator = Syndicator::new(Config::new(scratch.0.join("notes.canvas")))?;
+    let sink = ExecSink::new("slow", "sh", &["-c", "exec sleep 5"]).with_timeout(Duration::from_millis(200));
+    syndicator.add_sink(sink)?;
+    let started = Instant::now();
+    let report = syndicator.process_once()?;
+
+    assert!(started.elapsed() < Duration::from_secs(4), "took {:?}", started.elapsed());
+    let error = report.sinks[0].error.as_deref().unwrap_or_default();
+    assert!(error.contains("timed out after 200 ms and was killed"), "{}", error);
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! Nodes hosted elsewhere: linked to at their URL from the posts next to them, never written
+//! out themselves, and never up for pruning.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, SyndicationTracker, Syndicator, read_tree};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const POST: &str = "e87e000000000001";
+const ESSAY: &str = "e87e000000000002";
+const REPO: &str = "e87e000000000003";
+const ESSAY_TEXT: &str = "---\nexternal_url: https://example.com/essays/gardens\n---\nOn Gardens";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn syndicator(dir: &Path) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.removal_guard.accept_mass_removal = true;
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator)
+}
+
+#[test]
+fn external_neighbors_are_linked_and_never_written() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-external-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("site"))?;
+    let scratch = Scratch(dir);
+    let site = scratch.0.join("site");
+    let canvas = CanvasBuilder::new()
+        .text_node(POST, "More on what I wrote before")
+        .color(PresetColor::Red)
+        .text_node(ESSAY, ESSAY_TEXT)
+        .color(PresetColor::Red)
+        .link_node(REPO, "https://github.com/example/garden")
+        .edge(POST, ESSAY)
+        .edge(POST, REPO)
+        .build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let report = syndicator(&scratch.0)?.process_once()?;
+    assert_eq!(report.external, [ESSAY]);
+    assert_eq!(report.new_items, [POST]);
+    let files: Vec<PathBuf> =
+        read_tree(&site)?.into_keys().filter(|path| path.extension().is_some_and(|ext| ext == "md")).collect();
+    assert_eq!(files.len(), 1, "{:?}", files);
+    assert!(files[0].to_string_lossy().ends_with(&format!("-{}.md", POST)), "{:?}", files);
+    let post = std::fs::read_to_string(site.join(&files[0]))?;
+    for href in ["https://example.com/essays/gardens", "https://github.com/example/garden"] {
+        assert!(post.contains(&format!("href: \"{}\"", href)), "{}", post);
+    }
+    assert!(post.contains("link_text: \"On Gardens\""), "{}", post);
+    assert!(!post.contains("/t/"), "{}", post);
+
+    let tracker = SyndicationTracker::new(&scratch.0.join("notes.canvas"), "directory")?;
+    assert_eq!(tracker.external_url(&ESSAY.parse()?), Some("https://example.com/essays/gardens"));
+
+    // With the canvas emptied, only the generated post is up for pruning
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&CanvasBuilder::new().build()?)?)?;
+    let plans = syndicator(&scratch.0)?.plan_prune()?;
+    let candidates: Vec<String> = plans.iter().flat_map(|plan| &plan.tracker_entries).map(|id| id.to_string()).collect();
+    assert_eq!(candidates, [POST]);
+    Ok(())
+}
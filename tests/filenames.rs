@@ -0,0 +1,66 @@
+//! Filename templates end to end: cross-references link to the rendered names, and two
+//! nodes rendering to the same name fail the batch before anything is written.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, ProcessReport, Syndicator};
+use syndicate_json_canvas_sinks::{DirectorySink, FilenameTemplate};
+
+const FIRST: &str = "f11e000000000001";
+const SECOND: &str = "f11e000000000002";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-filenames-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Scratch(dir))
+}
+
+/// Publish `FIRST -> SECOND` with the given texts into `site`, naming files by `template`
+fn publish(dir: &Path, texts: [&str; 2], template: &str) -> Result<ProcessReport, Box<dyn Error>> {
+    let canvas = CanvasBuilder::new()
+        .text_node(FIRST, texts[0])
+        .color(PresetColor::Red)
+        .text_node(SECOND, texts[1])
+        .color(PresetColor::Red)
+        .edge(FIRST, SECOND)
+        .build()?;
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+    let mut syndicator = Syndicator::new(Config::new(dir.join("notes.canvas")))?;
+    let sink = DirectorySink::new(dir.join("site")).with_filename_template(FilenameTemplate::parse(template)?);
+    syndicator.add_sink(sink)?;
+    Ok(syndicator.process_once()?)
+}
+
+#[test]
+fn cross_references_use_the_rendered_names() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("links")?;
+    publish(&scratch.0, ["The question", "The answer"], "{id}")?;
+
+    let first = std::fs::read_to_string(scratch.0.join("site").join(format!("{}.md", FIRST)))?;
+    assert!(first.contains(&format!("href: \"/t/{}.md\"", SECOND)), "{}", first);
+    assert!(first.contains(&format!("node_id: \"{}\"", FIRST)), "{}", first);
+    Ok(())
+}
+
+#[test]
+fn colliding_names_fail_before_anything_is_written() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("collision")?;
+    let report = publish(&scratch.0, ["Same words", "Same words"], "{slug}")?;
+
+    let error = report.sinks[0].error.as_deref().ok_or("no error")?;
+    assert!(error.contains("the same file name `same-words.md`"), "{}", error);
+    let site = scratch.0.join("site");
+    assert!(!site.exists() || std::fs::read_dir(&site)?.next().is_none(), "a post was written");
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! Golden output check: run the pipeline over every fixture canvas and compare the output
+//! byte-for-byte with the committed expectations.
+//!
+//! ```sh
+//! cargo test --test golden                  # check, also run by `cargo test`; fails on any difference
+//! UPDATE_GOLDEN=1 cargo test --test golden  # rewrite the expectations after an intended change
+//! ```
+//!
+//! Each `tests/fixtures/<case>.canvas` is copied into a scratch directory, along with the
+//! files in `tests/fixtures/<case>/vault/` if there are any, and published once to a
+//! [`DirectorySink`] with the clock pinned; the files it writes are compared with
+//! `tests/fixtures/<case>/directory/`.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::TimeZone;
+use syndicate_json_canvas_lib::{Config, MockClock, Syndicator, line_diff};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+/// Time every fixture is published at
+fn pinned_clock() -> Arc<MockClock> {
+    Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap()))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some_and(|v| v != "0");
+
+    let mut cases: Vec<PathBuf> = std::fs::read_dir(&fixtures)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "canvas"))
+        .collect();
+    cases.sort();
+
+    let mut failed = 0;
+    for canvas in &cases {
+        let case = canvas.file_stem().and_then(|s| s.to_str()).ok_or("Invalid fixture name")?;
+        let actual = run_case(canvas, case)?;
+        let expected_dir = fixtures.join(case).join("directory");
+
+        if update {
+            if expected_dir.exists() {
+                std::fs::remove_dir_all(&expected_dir)?;
+            }
+            std::fs::create_dir_all(&expected_dir)?;
+            for (name, contents) in &actual {
+                std::fs::write(expected_dir.join(name), contents)?;
+            }
+            println!("updated {} ({} files)", case, actual.len());
+            continue;
+        }
+
+        let differences = compare(&read_files(&expected_dir)?, &actual);
+        if differences.is_empty() {
+            println!("ok      {}", case);
+        } else {
+            failed += 1;
+            println!("FAILED  {}", case);
+            for difference in differences {
+                print!("{}", difference);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} golden cases differ; rerun with UPDATE_GOLDEN=1 if the change is intended",
+            failed,
+            cases.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Publish a fixture in a scratch directory and return the files the sink wrote
+fn run_case(canvas: &Path, case: &str) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let scratch = std::env::temp_dir().join(format!("syndicate-json-canvas-golden-{}-{}", std::process::id(), case));
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    std::fs::create_dir_all(&scratch)?;
+    let canvas_path = scratch.join(format!("{}.canvas", case));
+    std::fs::copy(canvas, &canvas_path)?;
+    // Files the canvas's File nodes point at, with the canvas at the root of their vault
+    let vault = canvas.with_extension("").join("vault");
+    if vault.is_dir() {
+        copy_dir(&vault, &scratch)?;
+    }
+
+    let clock = pinned_clock();
+    let mut config = Config::new(&canvas_path);
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(scratch.join("site")).with_clock(clock))?;
+    let report = syndicator.process_once()?;
+
+    let files = read_files(&scratch.join("site"));
+    std::fs::remove_dir_all(&scratch)?;
+    if let Some(error) = report.error {
+        return Err(format!("{}: {}", case, error).into());
+    }
+    files
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let path = entry?.path();
+        let target = to.join(path.file_name().ok_or("Invalid file name")?);
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            std::fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Contents of the files directly in `dir`, by name, hidden manifests left out; empty if
+/// it doesn't exist
+fn read_files(dir: &Path) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    let mut files = BTreeMap::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).ok_or("Invalid file name")?;
+        if path.is_file() && !name.starts_with('.') {
+            files.insert(name.to_string(), std::fs::read_to_string(&path)?);
+        }
+    }
+    Ok(files)
+}
+
+/// A readable description of every file that is missing, unexpected or different
+fn compare(expected: &BTreeMap<String, String>, actual: &BTreeMap<String, String>) -> Vec<String> {
+    let mut differences = Vec::new();
+    for (name, expected_contents) in expected {
+        match actual.get(name) {
+            None => differences.push(format!("  missing {}\n", name)),
+            Some(actual_contents) if actual_contents != expected_contents => {
+                let mut diff = line_diff(expected_contents, actual_contents);
+                if diff.is_empty() {
+                    diff = "  (line endings or trailing newline differ)\n".to_string();
+                }
+                differences.push(format!("  changed {}\n{}", name, diff));
+            }
+            Some(_) => {}
+        }
+    }
+    for name in actual.keys().filter(|name| !expected.contains_key(*name)) {
+        differences.push(format!("  unexpected {}\n", name));
+    }
+    differences
+}
@@ -0,0 +1,121 @@
+//! Settings from nested groups in a run: a node's frontmatter wins over the innermost group,
+//! which wins over the groups around it, which win over the global configuration.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, CategoryMap, Config, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+};
+
+const OUTER: &str = "6e05000000000001";
+const INNER: &str = "6e05000000000002";
+const BROKEN: &str = "6e05000000000003";
+const IN_INNER: &str = "6e05000000000011";
+const OVERRIDING: &str = "6e05000000000012";
+const IN_OUTER: &str = "6e05000000000013";
+const OUTSIDE: &str = "6e05000000000014";
+const IN_BROKEN: &str = "6e05000000000015";
+
+/// Category and set tags of each item a sink was given, by sink and node ID
+type Given = Arc<Mutex<BTreeMap<(&'static str, String), (Option<String>, Option<Vec<String>>)>>>;
+
+struct Mock {
+    name: &'static str,
+    given: Given,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let mut given = self.given.lock().unwrap();
+        for item in items.values() {
+            given.insert((self.name, item.id.to_string()), (item.category.clone(), item.settings.tags.clone()));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn frontmatter_beats_inner_groups_beat_outer_groups_beat_config() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-group-settings-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let canvas = CanvasBuilder::new()
+        .group_node(OUTER)
+        .label("Writing [category: Writing] [sinks: site] [tags: essays]")
+        .at(0, 0)
+        .size(2000, 2000)
+        .group_node(INNER)
+        .label("Drafts [category: Drafts]")
+        .at(100, 100)
+        .size(800, 800)
+        .group_node(BROKEN)
+        .label("Elsewhere [sinks: nowhere]")
+        .at(5000, 0)
+        .size(500, 500)
+        .text_node(IN_INNER, "In the inner group")
+        .color(PresetColor::Red)
+        .at(200, 200)
+        .size(100, 100)
+        .text_node(OVERRIDING, "---\ncategory: Mine\nsinks: archive\n---\nOverriding both groups")
+        .color(PresetColor::Red)
+        .at(400, 400)
+        .size(100, 100)
+        .text_node(IN_OUTER, "In the outer group only")
+        .color(PresetColor::Red)
+        .at(1200, 1200)
+        .size(100, 100)
+        .text_node(OUTSIDE, "In no group")
+        .color(PresetColor::Red)
+        .at(3000, 0)
+        .size(100, 100)
+        .text_node(IN_BROKEN, "In a group with broken settings")
+        .color(PresetColor::Red)
+        .at(5100, 100)
+        .size(100, 100)
+        .build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let mut config = Config::new(scratch.0.join("notes.canvas"));
+    config.options.categories = Some(CategoryMap::new().with_default("General"));
+    let mut syndicator = Syndicator::new(config)?;
+    let given = Given::default();
+    syndicator.add_sink(Mock { name: "site", given: given.clone() })?;
+    syndicator.add_sink(Mock { name: "archive", given: given.clone() })?;
+    let report = syndicator.process_once()?;
+
+    let essays = || Some(vec!["essays".to_string()]);
+    let expected = BTreeMap::from([
+        (("archive", OUTSIDE.to_string()), (Some("General".to_string()), None)),
+        (("archive", IN_BROKEN.to_string()), (Some("General".to_string()), None)),
+        (("archive", OVERRIDING.to_string()), (Some("Mine".to_string()), essays())),
+        (("site", OUTSIDE.to_string()), (Some("General".to_string()), None)),
+        (("site", IN_BROKEN.to_string()), (Some("General".to_string()), None)),
+        (("site", IN_INNER.to_string()), (Some("Drafts".to_string()), essays())),
+        (("site", IN_OUTER.to_string()), (Some("Writing".to_string()), essays())),
+    ]);
+    assert_eq!(*given.lock().unwrap(), expected);
+
+    let warnings: Vec<_> =
+        report.all_warnings().map(|w| (w.code.as_str(), w.node_id.as_deref(), w.message.as_str())).collect();
+    let message = "Settings of group `Elsewhere [sinks: nowhere]` ignored: no sink named nowhere";
+    assert_eq!(warnings, [("group-settings", Some(BROKEN), message)]);
+    Ok(())
+}
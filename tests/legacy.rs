@@ -0,0 +1,106 @@
+//! Hand-written posts from before the canvas: imported once, linked to by title from new
+//! posts, and never touched by a prune.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, Syndicator, read_tree};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const FIRST: &str = "1e9a000000000001";
+const LINKING: &str = "1e9a000000000002";
+const MUSINGS: &str = "---\ntitle: \"Old Musings\"\ndate: 2019-04-01\n---\n\nWritten long ago.\n";
+const THOUGHT: &str = "---\ntitle: \"A Thought\"\n---\n\nAlso by hand.\n";
+const THOUGHT_ID: &str = "0123456789abcdef";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn write_canvas(dir: &Path, notes: &[(&str, &str)]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (id, text) in notes {
+        builder = builder.text_node(*id, *text).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+fn syndicator(dir: &Path) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.removal_guard.accept_mass_removal = true;
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator)
+}
+
+/// Contents of the post published from `id`
+fn post_of(site: &Path, id: &str) -> Result<String, Box<dyn Error>> {
+    for entry in std::fs::read_dir(site)? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(&format!("-{}.md", id)) {
+            return Ok(std::fs::read_to_string(path)?);
+        }
+    }
+    Err(format!("no post of {}", id).into())
+}
+
+#[test]
+fn legacy_posts_are_imported_linked_and_left_alone() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-legacy-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("site"))?;
+    let scratch = Scratch(dir);
+    let site = scratch.0.join("site");
+    write_canvas(&scratch.0, &[(FIRST, "From the canvas")])?;
+    syndicator(&scratch.0)?.process_once()?;
+    std::fs::write(site.join("old-musings.md"), MUSINGS)?;
+    std::fs::write(site.join(format!("a-thought-{}.md", THOUGHT_ID)), THOUGHT)?;
+
+    let imports = syndicator(&scratch.0)?.import_legacy();
+    let mut imported: Vec<String> = imports[0]
+        .imported
+        .as_ref()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|(node_id, path)| format!("{} {}", node_id, path.file_name().unwrap_or_default().to_string_lossy()))
+        .collect();
+    imported.sort();
+    assert_eq!(imported.len(), 2, "the canvas's own post was imported: {:?}", imported);
+    assert_eq!(imported[0], format!("{} a-thought-{}.md", THOUGHT_ID, THOUGHT_ID));
+    assert!(imported[1].starts_with("legacy-") && imported[1].ends_with(" old-musings.md"), "{}", imported[1]);
+    // Importing again finds nothing new
+    let again = syndicator(&scratch.0)?.import_legacy();
+    assert_eq!(again[0].imported.as_ref().map_err(|e| e.to_string())?.len(), 0);
+
+    write_canvas(
+        &scratch.0,
+        &[(FIRST, "From the canvas"), (LINKING, "Building on [[Old Musings]] and [[A Thought|that one]]")],
+    )?;
+    syndicator(&scratch.0)?.process_once()?;
+    let post = post_of(&site, LINKING)?;
+    let body = format!("Building on [Old Musings](/t/old-musings.md) and [that one](/t/a-thought-{}.md)", THOUGHT_ID);
+    assert!(post.contains(&body), "{}", post);
+    let further = format!(
+        "further_thinking:\n  - link_text: \"Old Musings\"\n    href: \"/t/old-musings.md\"\n\
+         \x20 - link_text: \"A Thought\"\n    href: \"/t/a-thought-{}.md\"\n",
+        THOUGHT_ID
+    );
+    assert!(post.contains(&further), "{}", post);
+
+    // Dropping every canvas node prunes only the canvas's posts
+    write_canvas(&scratch.0, &[])?;
+    let mut pruning = syndicator(&scratch.0)?;
+    let plans = pruning.plan_prune()?;
+    pruning.prune(&plans)?;
+    let left: Vec<PathBuf> = read_tree(&site)?.into_keys().collect();
+    assert!(left.contains(&PathBuf::from("old-musings.md")), "{:?}", left);
+    assert_eq!(std::fs::read_to_string(site.join("old-musings.md"))?, MUSINGS);
+    assert!(!left.iter().any(|path| path.to_string_lossy().contains(FIRST)), "{:?}", left);
+    Ok(())
+}
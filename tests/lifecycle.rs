@@ -0,0 +1,89 @@
+//! Sink lifecycle hooks around a run's batch: begin, publish and commit when it works,
+//! abort instead of commit when a step fails, with nothing recorded as published.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, SinkError, SyndicationFormat, SyndicationSink, Syndicator};
+
+const NOTE: &str = "11fe000000000001";
+
+/// Hooks in the order the sink saw them
+type Calls = Arc<Mutex<Vec<&'static str>>>;
+
+/// Records its hooks; fails in `commit` while `failing`
+struct Mock {
+    failing: bool,
+    calls: Calls,
+}
+
+impl SyndicationSink for Mock {
+    fn begin(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        self.calls.lock().unwrap().push("begin");
+        Ok(())
+    }
+
+    fn publish(&mut self, _items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        self.calls.lock().unwrap().push("publish");
+        Ok(())
+    }
+
+    fn commit(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        self.calls.lock().unwrap().push("commit");
+        if self.failing {
+            return Err(SinkError::Api("push rejected".to_string()));
+        }
+        Ok(())
+    }
+
+    fn abort(&mut self, _dry_run: bool) -> Result<(), SinkError> {
+        self.calls.lock().unwrap().push("abort");
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-lifecycle-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let canvas = CanvasBuilder::new().text_node(NOTE, "A note.").color(PresetColor::Red).build()?;
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+    Ok(Scratch(dir))
+}
+
+/// Run once, returning the hooks called and whether the note is now tracked as published
+fn run(dir: &Path, failing: bool) -> Result<(Vec<&'static str>, bool), Box<dyn Error>> {
+    let calls = Calls::default();
+    let mut syndicator = Syndicator::new(Config::new(dir.join("notes.canvas")))?;
+    syndicator.add_sink(Mock { failing, calls: calls.clone() })?;
+    syndicator.process_once()?;
+    let published = syndicator.tracker("mock").ok_or("no tracker")?.is_published(&NOTE.parse()?);
+    let calls = calls.lock().unwrap().clone();
+    Ok((calls, published))
+}
+
+#[test]
+fn failed_commit_aborts_the_batch_and_the_next_run_retries() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("retry")?;
+    assert_eq!(run(&scratch.0, true)?, (vec!["begin", "publish", "commit", "abort"], false));
+    assert_eq!(run(&scratch.0, false)?, (vec!["begin", "publish", "commit"], true));
+    // Nothing to do, so no batch
+    assert_eq!(run(&scratch.0, false)?, (vec![], true));
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! A sink with a maximum item age under a mock clock: old items are skipped for it for good,
+//! dated by their frontmatter or by when their node was first seen, while other sinks get them.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::{Local, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, MockClock, ProcessReport, SinkError, SinkTarget, SyndicationFormat, SyndicationSink,
+    SyndicationTracker, Syndicator,
+};
+
+const SEEN_EARLY: &str = "a9e0000000000001";
+const BACKDATED: &str = "a9e0000000000002";
+const FRESH: &str = "a9e0000000000003";
+
+/// Node IDs each sink was given, by sink name
+type Delivered = Arc<Mutex<HashMap<&'static str, Vec<String>>>>;
+
+struct Mock {
+    name: &'static str,
+    delivered: Delivered,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let mut delivered = self.delivered.lock().unwrap();
+        let ids = delivered.entry(self.name).or_default();
+        ids.extend(items.keys().map(|id| id.to_string()));
+        ids.sort();
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn write_canvas(path: &Path, nodes: &[(&str, &str)]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (id, text) in nodes {
+        builder = builder.text_node(*id, *text).color(PresetColor::Red);
+    }
+    std::fs::write(path, serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+#[test]
+fn old_items_skip_the_age_limited_sink_for_good() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-max-item-age-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let path = scratch.0.join("notes.canvas");
+    let clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()));
+    let delivered = Delivered::default();
+    let run = |social_too: bool| -> Result<ProcessReport, Box<dyn Error>> {
+        let mut config = Config::new(&path);
+        config.options.clock = clock.clone();
+        let mut syndicator = Syndicator::new(config)?;
+        let archive = Mock { name: "archive", delivered: delivered.clone() };
+        syndicator.add_target(SinkTarget::new(archive, SyndicationTracker::new(&path, "archive")?));
+        if social_too {
+            let social = Mock { name: "social", delivered: delivered.clone() };
+            let tracker = SyndicationTracker::new(&path, "social")?;
+            syndicator.add_target(SinkTarget::new(social, tracker).with_max_item_age(30));
+        }
+        Ok(syndicator.process_once()?)
+    };
+
+    write_canvas(&path, &[(SEEN_EARLY, "An early thought")])?;
+    run(false)?;
+
+    // Forty days on, the social sink is added alongside a new node and an old vault note
+    clock.advance(chrono::Duration::days(40));
+    let backdated = "---\ndate: 2024-01-01\n---\nImported from the vault";
+    write_canvas(&path, &[(SEEN_EARLY, "An early thought"), (BACKDATED, backdated), (FRESH, "A new thought")])?;
+    let report = run(true)?;
+
+    let delivered_to = |sink| delivered.lock().unwrap().get(sink).cloned().unwrap_or_default();
+    assert_eq!(delivered_to("archive"), [SEEN_EARLY, BACKDATED, FRESH]);
+    assert_eq!(delivered_to("social"), [FRESH]);
+    let social = report.sinks.iter().find(|s| s.sink == "social").unwrap();
+    let mut age_skipped = social.age_skipped.clone();
+    age_skipped.sort();
+    assert_eq!(age_skipped, [SEEN_EARLY, BACKDATED]);
+    assert!(report.sinks.iter().find(|s| s.sink == "archive").unwrap().age_skipped.is_empty());
+
+    let tracker = SyndicationTracker::new(&path, "social")?;
+    for old in [SEEN_EARLY, BACKDATED] {
+        assert!(tracker.is_skipped(&old.parse()?) && !tracker.is_published(&old.parse()?));
+    }
+    assert!(tracker.is_published(&FRESH.parse()?) && !tracker.is_skipped(&FRESH.parse()?));
+
+    // Skipped ones never come up for the sink again
+    let report = run(true)?;
+    assert!(report.sinks.iter().all(|s| s.age_skipped.is_empty() && s.items.is_empty()));
+    assert_eq!(delivered_to("social"), [FRESH]);
+    Ok(())
+}
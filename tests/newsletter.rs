@@ -0,0 +1,178 @@
+//! The newsletter sink against a mock Buttondown API: a period's items make one draft that later
+//! runs update in place, and a digest is only sent when sending is on and its week is over.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::TimeZone;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, HttpClient, HttpError, HttpRequest, HttpResponse, MockClock, Syndicator,
+};
+use syndicate_json_canvas_sinks::{DigestPeriod, NewsletterMode, NewsletterSink};
+
+const API: &str = "https://buttondown.test/v1";
+
+/// A request the mock API got: method, path and body
+type Request = (String, String, String);
+
+/// The Buttondown API, recording its requests; creating a draft answers `create_status`
+#[derive(Debug)]
+struct MockButtondown {
+    requests: Mutex<Vec<Request>>,
+    create_status: Mutex<u16>,
+}
+
+impl MockButtondown {
+    fn new() -> Self {
+        Self { requests: Mutex::new(Vec::new()), create_status: Mutex::new(201) }
+    }
+
+    /// Requests since the last call
+    fn take(&self) -> Vec<Request> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+impl HttpClient for MockButtondown {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        let path = request.url.trim_start_matches(API).to_string();
+        let mut requests = self.requests.lock().unwrap();
+        requests.push((request.method.clone(), path, request.body.clone().unwrap_or_default()));
+        let status = match request.method.as_str() {
+            "POST" => *self.create_status.lock().unwrap(),
+            _ => 200,
+        };
+        let body = match status {
+            201 => format!(r#"{{"id": "em{}"}}"#, requests.len()),
+            _ => r#"{"detail": "nope"}"#.to_string(),
+        };
+        Ok(HttpResponse { status, headers: Vec::new(), body })
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(topic: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-{}-{}", topic, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Scratch(dir))
+}
+
+fn write_canvas(dir: &Path, notes: &[(&str, &str)]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (id, text) in notes {
+        builder = builder.text_node(*id, *text).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+/// A weekly digest to the mock API, on a clock pinned to a Wednesday
+fn syndicator(
+    dir: &Path,
+    api: Arc<MockButtondown>,
+    send: bool,
+) -> Result<(Syndicator, Arc<MockClock>), Box<dyn Error>> {
+    let clock = Arc::new(MockClock::new(chrono::Local.with_ymd_and_hms(2026, 10, 14, 12, 0, 0).unwrap()));
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(
+        NewsletterSink::buttondown("test-key", dir.join("newsletter.json"))?
+            .with_api_base(API)
+            .with_mode(NewsletterMode::Digest(DigestPeriod::Weekly))
+            .with_send(send)
+            .with_clock(clock.clone())
+            .with_http_client(api),
+    )?;
+    Ok((syndicator, clock))
+}
+
+fn methods(requests: &[Request]) -> Vec<String> {
+    requests.iter().map(|(method, path, _)| format!("{} {}", method, path)).collect()
+}
+
+#[test]
+fn a_weeks_items_make_one_draft_updated_in_place() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("newsletter-drafts")?;
+    write_canvas(&scratch.0, &[("4e50000000000001", "First note"), ("4e50000000000002", "Second note")])?;
+    let api = Arc::new(MockButtondown::new());
+    let (mut syndicator, clock) = syndicator(&scratch.0, api.clone(), false)?;
+
+    syndicator.process_once()?;
+    let requests = api.take();
+    assert_eq!(methods(&requests), ["POST /emails"]);
+    for text in ["Notes for 2026-W42", "First note", "Second note"] {
+        assert!(requests[0].2.contains(text), "{}", requests[0].2);
+    }
+
+    syndicator.process_once()?;
+    assert_eq!(api.take(), []);
+
+    let mut notes = vec![
+        ("4e50000000000001", "First note, edited"),
+        ("4e50000000000002", "Second note"),
+        ("4e50000000000003", "Third"),
+    ];
+    write_canvas(&scratch.0, &notes)?;
+    syndicator.process_once()?;
+    let requests = api.take();
+    assert!(!requests.is_empty());
+    assert!(methods(&requests).iter().all(|r| r == "PATCH /emails/em1"), "{:?}", requests);
+    let last = &requests.last().unwrap().2;
+    assert!(last.contains("First note, edited") && last.contains("Third"), "{}", last);
+
+    // A new week starts a new draft, and with sending off the old one stays a draft
+    clock.advance(chrono::Duration::days(7));
+    notes.push(("4e50000000000004", "Next week"));
+    write_canvas(&scratch.0, &notes)?;
+    syndicator.process_once()?;
+    let requests = api.take();
+    assert_eq!(methods(&requests), ["POST /emails"]);
+    assert!(!requests.iter().any(|r| r.2.contains("about_to_send")), "{:?}", requests);
+    Ok(())
+}
+
+#[test]
+fn a_digest_is_sent_with_the_first_delivery_after_its_week() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("newsletter-send")?;
+    write_canvas(&scratch.0, &[("4e50000000000011", "First note")])?;
+    let api = Arc::new(MockButtondown::new());
+    let (mut syndicator, clock) = syndicator(&scratch.0, api.clone(), true)?;
+
+    syndicator.process_once()?;
+    assert_eq!(methods(&api.take()), ["POST /emails"]);
+
+    clock.advance(chrono::Duration::days(7));
+    write_canvas(&scratch.0, &[("4e50000000000011", "First note"), ("4e50000000000012", "Next week")])?;
+    syndicator.process_once()?;
+    let requests = api.take();
+    assert_eq!(methods(&requests), ["POST /emails", "PATCH /emails/em1"]);
+    assert!(requests[1].2.contains("about_to_send"), "{}", requests[1].2);
+    Ok(())
+}
+
+#[test]
+fn rejected_drafts_are_permanent_and_server_errors_transient() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("newsletter-errors")?;
+    write_canvas(&scratch.0, &[("4e50000000000021", "First note")])?;
+    let api = Arc::new(MockButtondown::new());
+    let (mut syndicator, _) = syndicator(&scratch.0, api.clone(), false)?;
+    for (status, permanent) in [(400, true), (503, false)] {
+        *api.create_status.lock().unwrap() = status;
+        let report = syndicator.process_once()?;
+        let outcome = report.sinks.iter().find(|o| o.sink == "newsletter").ok_or("the newsletter sink didn't run")?;
+        assert!(outcome.error.is_some(), "{:?}", outcome);
+        assert_eq!(outcome.error_permanent, permanent, "{:?}", outcome);
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! Two pipelines over two canvases in one process, each with its own mock sinks, keeping
+//! their tracker state and failures to themselves.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, Pipelines, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+};
+
+const WORK: [&str; 2] = ["30c0000000000001", "30c0000000000002"];
+const PERSONAL: [&str; 2] = ["9e50000000000001", "9e50000000000002"];
+
+type Published = Arc<Mutex<Vec<String>>>;
+
+/// Records the node IDs it's given, or fails every publish
+struct Mock {
+    name: &'static str,
+    failure: Option<&'static str>,
+    published: Published,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        match self.failure {
+            Some("panic") => panic!("{} blew up", self.name),
+            Some(message) => Err(SinkError::Api(message.to_string())),
+            None => {
+                self.published.lock().unwrap().extend(items.keys().map(|id| id.to_string()));
+                Ok(())
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Scratch {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-pipelines-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    Scratch(dir)
+}
+
+fn write_canvas(path: &Path, node_ids: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for id in node_ids {
+        builder = builder.text_node(*id, format!("Note {}.", id)).color(PresetColor::Red);
+    }
+    std::fs::write(path, serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+/// A pipeline named `work` publishing to a recording mock, and one named `personal` whose
+/// mock fails as `failure` says
+fn pipelines(dir: &Path, failure: Option<&'static str>) -> Result<(Pipelines, Published), Box<dyn Error>> {
+    let (work_canvas, personal_canvas) = (dir.join("work.canvas"), dir.join("personal.canvas"));
+    write_canvas(&work_canvas, &WORK)?;
+    write_canvas(&personal_canvas, &PERSONAL)?;
+    let published = Published::default();
+    let mut pipelines = Pipelines::new();
+    let work = pipelines.add("work", Syndicator::new(Config::new(&work_canvas))?)?;
+    work.add_sink(Mock { name: "confluence", failure: None, published: published.clone() })?;
+    let personal = pipelines.add("personal", Syndicator::new(Config::new(&personal_canvas))?)?;
+    personal.add_sink(Mock { name: "mastodon", failure, published: Arc::default() })?;
+    Ok((pipelines, published))
+}
+
+#[test]
+fn tracker_state_stays_in_its_pipeline() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("trackers");
+    let (mut pipelines, published) = pipelines(&scratch.0, None)?;
+    for (name, result) in pipelines.process_once() {
+        assert!(result.is_ok(), "{}: {:?}", name, result.err());
+    }
+
+    let mut work: Vec<String> = published.lock().unwrap().clone();
+    work.sort();
+    assert_eq!(work, WORK);
+    let tracked = |pipeline: &str, sink: &str| -> Vec<String> {
+        let tracker = pipelines.get(pipeline).and_then(|s| s.tracker(sink));
+        let mut ids: Vec<String> = tracker.map(|t| t.published().map(str::to_string).collect()).unwrap_or_default();
+        ids.sort();
+        ids
+    };
+    assert_eq!(tracked("work", "confluence"), WORK);
+    assert_eq!(tracked("personal", "mastodon"), PERSONAL);
+    // Neither pipeline knows the other's sink
+    assert!(pipelines.get("work").unwrap().tracker("mastodon").is_none());
+    assert!(pipelines.get("personal").unwrap().tracker("confluence").is_none());
+    Ok(())
+}
+
+#[test]
+fn a_failing_pipeline_leaves_the_other_alone() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("failure");
+    let (mut pipelines, published) = pipelines(&scratch.0, Some("the server went away"))?;
+    let results: HashMap<String, _> = pipelines.process_once().into_iter().collect();
+
+    let work = results["work"].as_ref().expect("work runs");
+    assert!(work.sinks.iter().all(|outcome| outcome.error.is_none()));
+    assert_eq!(published.lock().unwrap().len(), WORK.len());
+    let personal = results["personal"].as_ref().map(|report| &report.sinks);
+    let failed = personal.is_ok_and(|sinks| sinks.iter().all(|outcome| outcome.error.is_some()));
+    assert!(failed, "{:?}", personal.err());
+    assert_eq!(pipelines.get("personal").unwrap().tracker("mastodon").unwrap().published().count(), 0);
+    Ok(())
+}
+
+#[test]
+fn a_panicking_pipeline_leaves_the_other_alone() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("panic");
+    let (mut pipelines, published) = pipelines(&scratch.0, Some("panic"))?;
+    let results: HashMap<String, _> = pipelines.process_once().into_iter().collect();
+
+    assert!(results["work"].is_ok());
+    assert_eq!(published.lock().unwrap().len(), WORK.len());
+    let error = results["personal"].as_ref().err().map(|e| e.to_string()).unwrap_or_default();
+    assert!(error.contains("panicked"), "{}", error);
+    Ok(())
+}
+
+#[test]
+fn pipelines_cannot_share_a_canvas() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("shared");
+    let canvas = scratch.0.join("work.canvas");
+    write_canvas(&canvas, &WORK)?;
+    let mut pipelines = Pipelines::new();
+    pipelines.add("work", Syndicator::new(Config::new(&canvas))?)?;
+    let error = pipelines.add("again", Syndicator::new(Config::new(&canvas))?).err().map(|e| e.to_string());
+    assert!(error.is_some_and(|e| e.contains("both use canvas")));
+    Ok(())
+}
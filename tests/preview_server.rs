@@ -0,0 +1,84 @@
+//! The preview server over a simulated run: the index lists every would-be post, a post page
+//! shows its frontmatter and rendered body, and links between posts stay within the preview.
+
+use std::error::Error;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, PulldownRenderer, Syndicator};
+use syndicate_json_canvas_sinks::{DirectorySink, PreviewServer, PreviewSite};
+
+const FIRST: &str = "9e71000000000001";
+const SECOND: &str = "9e71000000000002";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Status line and body of a GET of `path`
+fn get(addr: SocketAddr, path: &str) -> Result<(String, String), Box<dyn Error>> {
+    let mut stream = TcpStream::connect(addr)?;
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, addr)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let (head, body) = response.split_once("\r\n\r\n").ok_or("no end of headers")?;
+    Ok((head.lines().next().unwrap_or_default().to_string(), body.to_string()))
+}
+
+#[test]
+fn preview_serves_posts_with_links_kept_inside() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-preview-server-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let canvas = CanvasBuilder::new()
+        .text_node(FIRST, "Gardens need **patience**")
+        .color(PresetColor::Red)
+        .text_node(SECOND, "Patience needs gardens\n\nAs I said [before](/t/older.md)")
+        .color(PresetColor::Red)
+        .edge(FIRST, SECOND)
+        .build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+    let mut syndicator = Syndicator::new(Config::new(scratch.0.join("notes.canvas")))?;
+    syndicator.add_sink(DirectorySink::new(scratch.0.join("site")))?;
+
+    let sandbox = scratch.0.join("sandbox");
+    syndicator.simulate_in(&sandbox)?;
+    let site = PreviewSite::load(&sandbox.join("sinks").join("directory"), &PulldownRenderer::default())?;
+    let file_of = |id: &str| site.posts().iter().find(|post| post.file.ends_with(&format!("-{}.md", id))).cloned();
+    let (first, second) = (file_of(FIRST).ok_or("no first post")?.file, file_of(SECOND).ok_or("no second post")?.file);
+    let server = PreviewServer::bind("127.0.0.1:0", site)?;
+    let addr = server.local_addr()?;
+    let handle = server.handle();
+    // Serves until the test process exits
+    std::thread::spawn(move || server.serve());
+
+    let (status, index) = get(addr, "/")?;
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(index.contains("<h1>2 posts</h1>"), "{}", index);
+    assert!(index.contains(&format!("<a href=\"/posts/{}\">", first)), "{}", index);
+    assert!(index.contains(&format!("<a href=\"/posts/{}\">", second)), "{}", index);
+
+    let (status, page) = get(addr, &format!("/posts/{}", first))?;
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(page.contains("<p>Gardens need <strong>patience</strong></p>"), "{}", page);
+    assert!(page.contains("<tr><th>title</th><td>Gardens need **patience**</td></tr>"), "{}", page);
+    // The link to the other post goes to its preview page, not the site's permalink
+    assert!(page.contains(&format!("<li><a href=\"/posts/{}\">Patience needs gardens", second)), "{}", page);
+    let (_, page) = get(addr, &format!("/posts/{}", second))?;
+    assert!(page.contains("<p>As I said <a href=\"/posts/older.md\">before</a></p>"), "{}", page);
+    assert!(!page.contains("href=\"/t/"), "{}", page);
+
+    assert_eq!(get(addr, "/posts/missing.md")?.0, "HTTP/1.1 404 Not Found");
+    assert_eq!(get(addr, "/version")?.1, "1");
+    handle.replace(PreviewSite::default());
+    assert_eq!(get(addr, "/version")?.1, "2");
+    assert!(get(addr, "/")?.1.contains("<h1>0 posts</h1>"));
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! Provenance frontmatter: posts name their canvas, node and canvas version when the sink asks
+//! for it, and the keys stay single through a republish and a syndication-link backfill.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, SyndicationLinks, SyndicationSink, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTE: &str = "9407000000000001";
+const OTHER: &str = "9407000000000002";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(topic: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-{}-{}", topic, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Scratch(dir))
+}
+
+fn write_canvas(dir: &Path, nodes: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for id in nodes {
+        builder = builder.text_node(*id, format!("Where this came from {}", id)).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+/// Publish the canvas in `dir` to its site, returning the run's canvas hash and the note's post
+fn publish(dir: &Path, provenance: bool) -> Result<(String, String), Box<dyn Error>> {
+    let mut syndicator = Syndicator::new(Config::new(dir.join("notes.canvas")))?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")).with_provenance(provenance))?;
+    let report = syndicator.process_once()?;
+    let hash = report.canvas_hash.ok_or("no canvas hash")?;
+    Ok((hash, std::fs::read_to_string(post_path(dir)?)?))
+}
+
+fn post_path(dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    for entry in std::fs::read_dir(dir.join("site"))? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(&format!("-{}.md", NOTE)) {
+            return Ok(path);
+        }
+    }
+    Err("no post".into())
+}
+
+/// The provenance keys as they should appear for a canvas version
+fn keys(hash: &str) -> String {
+    format!("canvas: \"notes.canvas\"\nnode_id: \"{}\"\ncanvas_hash: \"{}\"\n", NOTE, &hash[..12])
+}
+
+#[test]
+fn provenance_keys_are_written_only_when_enabled() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("provenance-off")?;
+    write_canvas(&scratch.0, &[NOTE])?;
+    let (_, post) = publish(&scratch.0, false)?;
+    for key in ["canvas:", "node_id:", "canvas_hash:"] {
+        assert!(!post.contains(key), "{}", post);
+    }
+    Ok(())
+}
+
+#[test]
+fn provenance_keys_follow_republishes_and_backfills() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("provenance-on")?;
+    write_canvas(&scratch.0, &[NOTE])?;
+    let (first_hash, post) = publish(&scratch.0, true)?;
+    assert!(post.contains(&keys(&first_hash)), "{}", post);
+
+    // Republished from a later canvas version, the same post records that version instead
+    write_canvas(&scratch.0, &[NOTE, OTHER])?;
+    std::fs::remove_file(scratch.0.join(".notes.canvas.syndication.directory.toml"))?;
+    let (second_hash, post) = publish(&scratch.0, true)?;
+    assert_ne!(first_hash, second_hash);
+    assert!(post.contains(&keys(&second_hash)), "{}", post);
+    assert_eq!(post.matches("canvas_hash:").count(), 1, "{}", post);
+
+    let mut sink = DirectorySink::new(scratch.0.join("site")).with_provenance(true);
+    let urls = [("twitter".to_string(), "https://x.com/i/status/1".to_string())].into_iter().collect();
+    assert_eq!(sink.add_syndication_links(&SyndicationLinks::from([(NOTE.parse()?, urls)]), false)?, 1);
+    let backfilled = std::fs::read_to_string(post_path(&scratch.0)?)?;
+    assert!(backfilled.contains(&keys(&second_hash)), "{}", backfilled);
+    for key in ["canvas:", "node_id:", "canvas_hash:", "syndication:"] {
+        assert_eq!(backfilled.matches(&format!("\n{}", key)).count(), 1, "{}", backfilled);
+    }
+    Ok(())
+}
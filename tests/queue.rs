@@ -0,0 +1,112 @@
+//! The outbound queue surviving restarts: items queued by one process are delivered by the
+//! next, and each reaches the sink once even if the queue was saved before the delivery.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, DeliveryOptions, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+};
+
+const NOTES: [&str; 3] = ["90e0000000000001", "90e0000000000002", "90e0000000000003"];
+
+type Published = Arc<Mutex<Vec<String>>>;
+
+/// Records the node IDs it's given, or fails every publish while `down`
+struct Mock {
+    down: bool,
+    published: Published,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        if self.down {
+            return Err(SinkError::Api("connection refused".to_string()));
+        }
+        self.published.lock().unwrap().extend(items.keys().map(|id| id.to_string()));
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-queue-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let mut builder = CanvasBuilder::new();
+    for id in NOTES {
+        builder = builder.text_node(id, format!("Note {}.", id)).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    Ok(Scratch(dir))
+}
+
+/// A fresh process's syndicator in queue mode, as after a restart
+fn syndicator(dir: &Path, down: bool, published: &Published) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.queue = Some(DeliveryOptions::default());
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(Mock { down, published: published.clone() })?;
+    Ok(syndicator)
+}
+
+fn sorted(published: &Published) -> Vec<String> {
+    let mut ids = published.lock().unwrap().clone();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn items_queued_before_a_restart_are_delivered_once() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("restart")?;
+    let published = Published::default();
+    syndicator(&scratch.0, true, &published)?.process_once()?;
+    assert_eq!(syndicator(&scratch.0, true, &published)?.queue()?.len(), NOTES.len());
+
+    let mut restarted = syndicator(&scratch.0, false, &published)?;
+    restarted.drain(true)?;
+    assert!(restarted.queue()?.is_empty());
+    // Another restart finds nothing left to do
+    let mut again = syndicator(&scratch.0, false, &published)?;
+    again.process_once()?;
+    again.drain(true)?;
+    assert_eq!(sorted(&published), NOTES);
+    Ok(())
+}
+
+#[test]
+fn queue_left_behind_by_a_crash_is_deduplicated() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("crash")?;
+    let published = Published::default();
+    syndicator(&scratch.0, true, &published)?.process_once()?;
+    let queue_file = std::fs::read_dir(&scratch.0)?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.to_string_lossy().ends_with("syndication-queue.json"))
+        .ok_or("no queue file")?;
+    let before_delivery = std::fs::read(&queue_file)?;
+
+    syndicator(&scratch.0, false, &published)?.drain(true)?;
+    // As if the process died after the sink took the items but before the queue was saved
+    std::fs::write(&queue_file, before_delivery)?;
+    let mut restarted = syndicator(&scratch.0, false, &published)?;
+    restarted.drain(true)?;
+    assert!(restarted.queue()?.is_empty());
+    assert_eq!(sorted(&published), NOTES);
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Publish times under a mock clock: a sink taking only recent items skips ones published
+//! elsewhere long ago, and re-surfacing never picks an item again within its cooldown.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{Local, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, MockClock, ResurfaceOptions, SinkError, SinkTarget, SyndicationFormat, SyndicationSink,
+    SyndicationTracker, Syndicator,
+};
+
+const OLD: &str = "4ec0000000000001";
+const RECENT: &str = "4ec0000000000002";
+const NEW: &str = "4ec0000000000003";
+
+/// Texts of the items a sink published, and of those it re-surfaced
+type Posts = Arc<Mutex<(Vec<String>, Vec<String>)>>;
+
+struct Mock {
+    name: &'static str,
+    posts: Posts,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let mut ids: Vec<String> = items.keys().map(|id| id.to_string()).collect();
+        ids.sort();
+        self.posts.lock().unwrap().0.extend(ids);
+        Ok(())
+    }
+
+    fn resurface(&mut self, item: &SyndicationFormat, _dry_run: bool) -> Result<(), SinkError> {
+        self.posts.lock().unwrap().1.push(item.text.clone());
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(topic: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-{}-{}", topic, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Scratch(dir))
+}
+
+fn write_canvas(path: &Path, nodes: &[(&str, &str)]) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (id, text) in nodes {
+        builder = builder.text_node(*id, *text).color(PresetColor::Red);
+    }
+    std::fs::write(path, serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+fn target(path: &Path, name: &'static str, posts: &Posts) -> Result<SinkTarget, Box<dyn Error>> {
+    let sink = Mock { name, posts: posts.clone() };
+    Ok(SinkTarget::new(sink, SyndicationTracker::new(path, name)?))
+}
+
+#[test]
+fn recent_only_sinks_skip_items_published_elsewhere_long_ago() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("recency")?;
+    let path = scratch.0.join("notes.canvas");
+    let clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, 1, 23, 30, 0).unwrap()));
+    let (site, digest) = (Posts::default(), Posts::default());
+    let run = |digest_too: bool| -> Result<(), Box<dyn Error>> {
+        let mut config = Config::new(&path);
+        config.options.clock = clock.clone();
+        let mut syndicator = Syndicator::new(config)?;
+        syndicator.add_target(target(&path, "site", &site)?);
+        if digest_too {
+            syndicator.add_target(target(&path, "digest", &digest)?.with_published_within(7));
+        }
+        syndicator.process_once()?;
+        Ok(())
+    };
+
+    write_canvas(&path, &[(OLD, "Old")])?;
+    run(false)?;
+    // Late on the 4th is six calendar days before the 10th, however many hours
+    clock.advance(chrono::Duration::days(3));
+    write_canvas(&path, &[(OLD, "Old"), (RECENT, "Recent")])?;
+    run(false)?;
+    clock.advance(chrono::Duration::days(6) - chrono::Duration::hours(23));
+    write_canvas(&path, &[(OLD, "Old"), (RECENT, "Recent"), (NEW, "New")])?;
+    run(true)?;
+
+    assert_eq!(site.lock().unwrap().0, [OLD, RECENT, NEW]);
+    assert_eq!(digest.lock().unwrap().0, [RECENT, NEW]);
+    Ok(())
+}
+
+#[test]
+fn resurfacing_waits_for_age_and_keeps_to_the_cooldown() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("resurface")?;
+    let path = scratch.0.join("notes.canvas");
+    write_canvas(&path, &[(OLD, "First"), (RECENT, "Second")])?;
+    let clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()));
+    let posts = Posts::default();
+    let mut resurfaced = Vec::new();
+    for _ in 0..10 {
+        let mut config = Config::new(&path);
+        config.options.clock = clock.clone();
+        config.options.resurface = vec![
+            ResurfaceOptions::new("social", Duration::from_secs(24 * 3600)).with_min_age_days(5).with_cooldown_days(3),
+        ];
+        let mut syndicator = Syndicator::new(config)?;
+        syndicator.add_target(target(&path, "social", &posts)?);
+        syndicator.process_once()?;
+        resurfaced.push(posts.lock().unwrap().1.drain(..).collect::<Vec<_>>());
+        clock.advance(chrono::Duration::days(1));
+    }
+
+    // Nothing until the 7th, then each once before the first is out of its cooldown again
+    assert!(resurfaced[..6].iter().all(Vec::is_empty), "{:?}", resurfaced);
+    let (first, second) = (resurfaced[6].clone(), resurfaced[7].clone());
+    let mut both = [first.concat(), second.concat()];
+    both.sort();
+    assert_eq!(both, ["From the archive: First", "From the archive: Second"]);
+    assert!(resurfaced[8].is_empty());
+    assert_eq!(resurfaced[9], first);
+    Ok(())
+}
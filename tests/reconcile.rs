@@ -0,0 +1,80 @@
+//! Reconciling trackers with the published folder: posts the tracker lost are found again,
+//! and tracker entries whose post was deleted are cleared so the next run republishes them.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, ReconcileFix, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTES: [&str; 2] = ["5ec0000000000001", "5ec0000000000002"];
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-reconcile-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let mut builder = CanvasBuilder::new();
+    for id in NOTES {
+        builder = builder.text_node(id, format!("Note {}", id)).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    Ok(Scratch(dir))
+}
+
+/// A fresh process's syndicator publishing into `site`
+fn syndicator(dir: &Path) -> Result<Syndicator, Box<dyn Error>> {
+    let mut syndicator = Syndicator::new(Config::new(dir.join("notes.canvas")))?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator)
+}
+
+fn ids(node_ids: impl IntoIterator<Item = NodeId>) -> Vec<String> {
+    node_ids.into_iter().map(|id| id.to_string()).collect()
+}
+
+#[test]
+fn lost_tracker_is_rebuilt_from_the_published_posts() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("lost")?;
+    syndicator(&scratch.0)?.process_once()?;
+    std::fs::remove_file(scratch.0.join(".notes.canvas.syndication.directory.toml"))?;
+
+    let mut syndicator = syndicator(&scratch.0)?;
+    let found = syndicator.reconcile(ReconcileFix::default())?;
+    assert_eq!(ids(found[0].untracked.iter().map(|(id, _)| id.clone())), NOTES);
+    assert!(!found[0].fixed && !syndicator.tracker("directory").ok_or("no tracker")?.is_published(&NOTES[0].parse()?));
+
+    syndicator.reconcile(ReconcileFix { mark_found: true, ..Default::default() })?;
+    let report = syndicator.process_once()?;
+    assert!(report.new_items.is_empty(), "republished {:?}", report.new_items);
+    Ok(())
+}
+
+#[test]
+fn deleted_post_is_cleared_and_republished() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("deleted")?;
+    syndicator(&scratch.0)?.process_once()?;
+    for entry in std::fs::read_dir(scratch.0.join("site"))? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(&format!("-{}.md", NOTES[1])) {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    let mut syndicator = syndicator(&scratch.0)?;
+    let found = syndicator.reconcile(ReconcileFix { clear_missing: true, ..Default::default() })?;
+    assert_eq!(ids(found[0].missing.clone()), [NOTES[1]]);
+    assert!(found[0].untracked.is_empty() && found[0].fixed);
+    assert_eq!(syndicator.process_once()?.new_items, [NOTES[1]]);
+    assert!(syndicator.reconcile(ReconcileFix::default())?[0].is_empty());
+    Ok(())
+}
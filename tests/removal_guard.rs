@@ -0,0 +1,127 @@
+//! The removal guard refusing to act on a canvas that lost most of its published nodes at
+//! once, and letting an accepted removal through.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTES: [&str; 4] = ["9a4d000000000001", "9a4d000000000002", "9a4d000000000003", "9a4d000000000004"];
+
+/// A scratch directory removed when dropped
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-guard-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Write a canvas holding the first `count` notes
+fn write_canvas(canvas_path: &Path, count: usize) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for (i, id) in NOTES.iter().take(count).enumerate() {
+        builder = builder.text_node(*id, format!("Note {}.", i + 1)).color(PresetColor::Red);
+    }
+    std::fs::write(canvas_path, serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+fn syndicator(dir: &Path, accept_mass_removal: bool) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.removal_guard.accept_mass_removal = accept_mass_removal;
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator)
+}
+
+/// Publish every note, then cut the canvas down to one
+fn published_then_truncated(dir: &Path) -> Result<(), Box<dyn Error>> {
+    let canvas_path = dir.join("notes.canvas");
+    write_canvas(&canvas_path, NOTES.len())?;
+    syndicator(dir, false)?.process_once()?;
+    assert_eq!(posts(dir), NOTES.len());
+    write_canvas(&canvas_path, 1)?;
+    Ok(())
+}
+
+/// Posts in the site folder, hidden manifests left out
+fn posts(dir: &Path) -> usize {
+    let visible = |entry: &std::fs::DirEntry| !entry.file_name().to_string_lossy().starts_with('.');
+    std::fs::read_dir(dir.join("site")).map(|entries| entries.flatten().filter(visible).count()).unwrap_or(0)
+}
+
+#[test]
+fn run_on_truncated_canvas_is_refused() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch::new("run");
+    published_then_truncated(&scratch.0)?;
+
+    let error = syndicator(&scratch.0, false)?.process_once().unwrap_err().to_string();
+    assert!(error.contains("3 of 4 published nodes vanished"), "{}", error);
+    // Refused again on the next run: nothing was recorded
+    let error = syndicator(&scratch.0, false)?.process_once().unwrap_err().to_string();
+    assert!(error.contains("vanished"), "{}", error);
+    assert_eq!(posts(&scratch.0), NOTES.len());
+    Ok(())
+}
+
+#[test]
+fn accepted_run_records_the_smaller_canvas() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch::new("run-accepted");
+    published_then_truncated(&scratch.0)?;
+
+    let report = syndicator(&scratch.0, true)?.process_once()?;
+    assert!(report.error.is_none());
+    // Later runs need no override
+    syndicator(&scratch.0, false)?.process_once()?;
+    Ok(())
+}
+
+#[test]
+fn a_few_removals_pass() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch::new("run-few");
+    let canvas_path = scratch.0.join("notes.canvas");
+    write_canvas(&canvas_path, NOTES.len())?;
+    syndicator(&scratch.0, false)?.process_once()?;
+    write_canvas(&canvas_path, 2)?;
+    syndicator(&scratch.0, false)?.process_once()?;
+    Ok(())
+}
+
+#[test]
+fn prune_of_truncated_canvas_is_refused() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch::new("prune");
+    published_then_truncated(&scratch.0)?;
+
+    let error = syndicator(&scratch.0, false)?.plan_prune().unwrap_err().to_string();
+    assert!(error.contains("3 of 4 published nodes vanished"), "{}", error);
+    assert_eq!(posts(&scratch.0), NOTES.len());
+    Ok(())
+}
+
+#[test]
+fn accepted_prune_removes_and_records() -> Result<(), Box<dyn Error>> {
+    let scratch = Scratch::new("prune-accepted");
+    published_then_truncated(&scratch.0)?;
+
+    let mut accepting = syndicator(&scratch.0, true)?;
+    let plans = accepting.plan_prune()?;
+    assert_eq!(plans.iter().map(|plan| plan.tracker_entries.len()).sum::<usize>(), 3);
+    accepting.prune(&plans)?;
+    assert_eq!(posts(&scratch.0), 1);
+    // The pruned canvas is recorded, so runs and prunes after it need no override
+    syndicator(&scratch.0, false)?.process_once()?;
+    assert!(syndicator(&scratch.0, false)?.plan_prune()?.iter().all(|plan| plan.is_empty()));
+    Ok(())
+}
@@ -0,0 +1,99 @@
+//! Replacement rules in a run: group-scoped rules only touch nodes inside the group, every
+//! rule that fires is reported, and changing the rules doesn't republish anything.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, ProcessReport, Replacements, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+};
+
+const GROUP: &str = "4e91000000000001";
+const INSIDE: &str = "4e91000000000002";
+const OUTSIDE: &str = "4e91000000000003";
+
+/// Text each node was last published or updated with
+type Texts = Arc<Mutex<HashMap<String, String>>>;
+
+struct Mock {
+    texts: Texts,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let mut texts = self.texts.lock().unwrap();
+        texts.extend(items.iter().map(|(id, item)| (id.to_string(), item.text.clone())));
+        Ok(())
+    }
+
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, dry_run: bool) -> Result<(), SinkError> {
+        self.publish(items, dry_run)
+    }
+
+    fn supports_updates(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn run(dir: &Path, rules: &str, texts: &Texts) -> Result<ProcessReport, Box<dyn Error>> {
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.replacements = Some(Replacements::from_toml(rules)?);
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(Mock { texts: texts.clone() })?;
+    Ok(syndicator.process_once()?)
+}
+
+#[test]
+fn group_rules_apply_inside_and_rule_edits_dont_republish() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-replacements-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let canvas = CanvasBuilder::new()
+        .group_node(GROUP)
+        .label("Family")
+        .at(0, 0)
+        .size(1000, 1000)
+        .text_node(INSIDE, "Dinner with Bob Smith, then Bob left")
+        .color(PresetColor::Red)
+        .at(100, 100)
+        .size(300, 200)
+        .text_node(OUTSIDE, "Bob Smith from accounting")
+        .color(PresetColor::Red)
+        .at(2000, 100)
+        .size(300, 200)
+        .build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let rules = "[[replace]]\nname = \"bob\"\nregex = \"Bob( Smith)?\"\nwith = \"B.\"\ngroup = \"family\"\n\n\
+                 [[replace]]\nfind = \"accounting\"\nwith = \"work\"\n";
+    let texts = Texts::default();
+    let report = run(&scratch.0, rules, &texts)?;
+    assert_eq!(texts.lock().unwrap()[INSIDE], "Dinner with B., then B. left");
+    assert_eq!(texts.lock().unwrap()[OUTSIDE], "Bob Smith from work");
+    let fired: Vec<(&str, &str, usize)> =
+        report.redactions.iter().map(|r| (r.node_id.as_str(), r.rule.as_str(), r.count)).collect();
+    assert_eq!(fired, [(INSIDE, "bob", 2), (OUTSIDE, "accounting", 1)]);
+
+    // A new rule changes what would be published, but the nodes weren't edited
+    let report = run(&scratch.0, &format!("{}\n[[replace]]\nfind = \"Dinner\"\nwith = \"Lunch\"\n", rules), &texts)?;
+    assert!(report.updated_items.is_empty() && report.new_items.is_empty());
+    assert_eq!(texts.lock().unwrap()[INSIDE], "Dinner with B., then B. left");
+    Ok(())
+}
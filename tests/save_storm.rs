@@ -0,0 +1,118 @@
+//! A burst of saves while a run is publishing: the watcher lets the run finish and then
+//! runs exactly once more, with the canvas as the last save left it. Runs are counted by
+//! the reports they leave.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, ProcessingReason, ProcessingRequest, ProcessingSender, ReportFormat,
+    ReportWriter, SinkError, SyndicationFormat, SyndicationSink, Syndicator, WatchCommand,
+};
+
+const FIRST: &str = "5a0e000000000000";
+const SAVES: usize = 5;
+
+/// Node IDs of each publish call
+type Calls = Arc<Mutex<Vec<Vec<String>>>>;
+
+/// On its first publish, saves the canvas `SAVES` times, adding a note each time, and
+/// reports each save as the watcher would
+struct Mock {
+    canvas_path: PathBuf,
+    sender: ProcessingSender,
+    calls: Calls,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let mut ids: Vec<String> = items.keys().map(|id| id.to_string()).collect();
+        ids.sort();
+        let first = {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(ids);
+            calls.len() == 1
+        };
+        if first {
+            for save in 1..=SAVES {
+                write_canvas(&self.canvas_path, save).map_err(|e| SinkError::Api(e.to_string()))?;
+                let _ = self.sender.request(ProcessingRequest::new(ProcessingReason::CanvasChanged));
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+fn note(save: usize) -> String {
+    format!("5a0e00000000000{}", save)
+}
+
+/// The canvas after `saves` saves: the first note and one more per save
+fn write_canvas(canvas_path: &Path, saves: usize) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new().text_node(FIRST, "Before the storm.").color(PresetColor::Red);
+    for save in 1..=saves {
+        builder = builder.text_node(note(save), format!("Save {}.", save)).color(PresetColor::Red);
+    }
+    std::fs::write(canvas_path, serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+#[test]
+fn burst_of_saves_during_publishing_runs_once_more() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-save-storm-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let canvas_path = dir.join("notes.canvas");
+    write_canvas(&canvas_path, 0)?;
+
+    let mut config = Config::new(&canvas_path);
+    config.debounce = Duration::from_millis(50);
+    // Outside the canvas's folder, so the watcher doesn't see them
+    let reports = std::env::temp_dir().join(format!("syndicate-json-canvas-save-storm-reports-{}", std::process::id()));
+    config.options.report_writer = Some(ReportWriter::new(&reports, ReportFormat::Json, 100));
+    let mut syndicator = Syndicator::new(config)?;
+    let calls = Calls::default();
+    let sender = syndicator.processing_sender();
+    syndicator.add_sink(Mock { canvas_path: canvas_path.clone(), sender, calls: calls.clone() })?;
+
+    let (commands, receiver) = mpsc::channel();
+    let watched = std::thread::scope(|scope| {
+        let watcher = scope.spawn(|| syndicator.watch(receiver));
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while calls.lock().unwrap().len() < 2 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        // Long enough for the watcher's own events of the saves to come through
+        std::thread::sleep(Duration::from_millis(500));
+        let _ = commands.send(WatchCommand::Shutdown);
+        watcher.join().map_err(|_| "the watcher panicked")
+    });
+    let _ = std::fs::remove_dir_all(&dir);
+    watched??;
+    let mut runs = Vec::new();
+    for entry in std::fs::read_dir(&reports)? {
+        let path = entry?.path();
+        // Run journals are kept next to the reports
+        if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("report-")) {
+            runs.push(serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(path)?)?);
+        }
+    }
+    let _ = std::fs::remove_dir_all(&reports);
+
+    let full_runs = runs.iter().filter(|report| report["cancelled"] != true).count();
+    assert!(full_runs <= 2, "{} full runs for {} saves", full_runs, SAVES);
+    let calls = calls.lock().unwrap();
+    let latest: Vec<String> = (1..=SAVES).map(note).collect();
+    assert_eq!(*calls, [vec![FIRST.to_string()], latest], "the second run publishes every save");
+    Ok(())
+}
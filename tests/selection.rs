@@ -0,0 +1,93 @@
+//! Which nodes a run takes: every node only when the canvas's file name is confirmed, marked
+//! ones by color, optionally with uncolored ones, and the run report says which it used.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, ColorFilter, Config, Selection, SyndicationError, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const PLAIN: &str = "5e1e000000000001";
+const GREEN: &str = "5e1e000000000002";
+const RED: &str = "5e1e000000000003";
+const BLANK: &str = "5e1e000000000004";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-selection-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let canvas = CanvasBuilder::new()
+        .text_node(PLAIN, "No color at all")
+        .text_node(GREEN, "Colored green")
+        .color(PresetColor::Green)
+        .text_node(RED, "Marked for publishing")
+        .color(PresetColor::Red)
+        .text_node(BLANK, "  \n ")
+        .build()?;
+    std::fs::write(dir.join("Public.canvas"), serde_json::to_string(&canvas)?)?;
+    Ok(Scratch(dir))
+}
+
+fn syndicator(dir: &Path, selection: Selection, confirm: Option<&str>) -> Result<Syndicator, SyndicationError> {
+    let mut config = Config::new(dir.join("Public.canvas"));
+    config.options.selection = selection;
+    config.options.confirm_canvas_name = confirm.map(str::to_string);
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator)
+}
+
+#[test]
+fn confirmed_canvas_publishes_every_node() -> Result<(), Box<dyn Error>> {
+    let all = scratch("all")?;
+    let report = syndicator(&all.0, Selection::All, Some("Public.canvas"))?.process_once()?;
+    let mut items = report.new_items.clone();
+    items.sort();
+    assert_eq!(items, [PLAIN, GREEN, RED]);
+    assert_eq!(report.selection.as_deref(), Some("all nodes"));
+    assert!(report.to_markdown().contains("all nodes"));
+
+    // The usual convention still takes only the marked node
+    let marked = scratch("marked")?;
+    let report = syndicator(&marked.0, Selection::default(), None)?.process_once()?;
+    assert_eq!(report.new_items, [RED]);
+    assert_eq!(report.selection.as_deref(), Some("marked (red)"));
+    Ok(())
+}
+
+#[test]
+fn uncolored_nodes_pass_only_when_asked_for() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("uncolored")?;
+    let filter = ColorFilter::parse(&["red"])?.with_uncolored(true);
+    let report = syndicator(&scratch.0, Selection::Marked(filter), None)?.process_once()?;
+    let mut items = report.new_items.clone();
+    items.sort();
+    assert_eq!(items, [PLAIN, RED]);
+    assert_eq!(report.selection.as_deref(), Some("marked (red, uncolored)"));
+    Ok(())
+}
+
+#[test]
+fn unconfirmed_or_mismatched_canvas_is_refused() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("refused")?;
+    for (confirm, expected) in [
+        (Some("Thoughts.canvas"), "`confirm_canvas_name` is `Thoughts.canvas` but the canvas is `Public.canvas`"),
+        (None, "needs `confirm_canvas_name` set to the canvas's file name, `Public.canvas`"),
+    ] {
+        let Err(error) = syndicator(&scratch.0, Selection::All, confirm) else {
+            return Err(format!("{:?} was accepted", confirm).into());
+        };
+        assert!(matches!(error, SyndicationError::Selection(_)) && error.to_string().contains(expected), "{}", error);
+    }
+    assert!(!scratch.0.join("site").exists(), "a refused canvas was published");
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! Simulated runs: sinks write into a sandbox copy of their state, nothing real changes,
+//! and a real run afterwards produces exactly the files the simulation reported.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{Local, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, ChangeKind, Config, MockClock, Syndicator, read_tree};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTES: [&str; 2] = ["51a0000000000001", "51a0000000000002"];
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A fresh process's syndicator publishing into `site`, always at the same time
+fn syndicator(dir: &Path) -> Result<Syndicator, Box<dyn Error>> {
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()));
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")))?;
+    Ok(syndicator)
+}
+
+#[test]
+fn simulation_matches_the_real_run_and_changes_nothing() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-simulate-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("site"))?;
+    let scratch = Scratch(dir);
+    let mut builder = CanvasBuilder::new();
+    for id in NOTES {
+        builder = builder.text_node(id, format!("Note {}", id)).color(PresetColor::Red);
+    }
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+
+    let sandbox = scratch.0.join("sandbox");
+    let simulation = syndicator(&scratch.0)?.simulate_in(&sandbox)?;
+    let mut simulated_items = simulation.report.new_items.clone();
+    simulated_items.sort();
+    assert_eq!(simulated_items, NOTES);
+    assert!(simulation.unsupported_sinks.is_empty());
+    let posts: Vec<&Path> = simulation
+        .changes
+        .iter()
+        .filter(|change| change.path.starts_with("sinks/directory") && change.path.extension() == Some("md".as_ref()))
+        .map(|change| change.path.as_path())
+        .collect();
+    assert_eq!(posts.len(), NOTES.len(), "{}", simulation.to_text());
+    assert!(simulation.changes.iter().all(|change| change.kind == ChangeKind::Added));
+    assert!(std::fs::read_dir(scratch.0.join("site"))?.next().is_none(), "the simulation wrote to the site");
+    assert!(!scratch.0.join(".notes.canvas.syndication.directory.toml").exists());
+
+    assert_eq!(syndicator(&scratch.0)?.process_once()?.new_items.len(), NOTES.len());
+    let site = read_tree(&scratch.0.join("site"))?;
+    for post in posts {
+        let simulated = std::fs::read(sandbox.join(post))?;
+        let real = site.get(post.strip_prefix("sinks/directory")?).ok_or("post missing from the real run")?;
+        assert_eq!(&simulated, real, "{}", post.display());
+    }
+    Ok(())
+}
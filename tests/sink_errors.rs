@@ -0,0 +1,74 @@
+//! Sink errors name the sink and node they happened for, through the one definition the
+//! sinks crate re-exports.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, SyndicationFormat, Syndicator};
+// Only the re-exported path, as a third-party sink would use it
+use syndicate_json_canvas_sinks::{DirectorySink, SinkError, SyndicationSink};
+
+const NOTE: &str = "e770000000000001";
+
+/// Fails on the first item it's given
+struct Failing;
+
+impl SyndicationSink for Failing {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let node_id = items.keys().next().expect("an item");
+        Err(SinkError::Api("503 Service Unavailable".to_string()).for_item(node_id))
+    }
+
+    fn name(&self) -> &str {
+        "failing"
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn display_names_the_sink_and_node() -> Result<(), Box<dyn Error>> {
+    let node_id: NodeId = NOTE.parse()?;
+    let error = SinkError::Config("missing token".to_string()).for_item(&node_id).in_sink("twitter");
+    assert_eq!(error.to_string(), format!("twitter: node {}: Configuration error: missing token", NOTE));
+    assert_eq!((error.sink(), error.node_id()), (Some("twitter"), Some(&node_id)));
+
+    // Context never nests, and the first sink attached wins
+    let error = error.in_sink("outer").for_item(&"e770000000000002".parse()?);
+    assert_eq!(error.to_string(), format!("twitter: node {}: Configuration error: missing token", NOTE));
+    assert!(matches!(error.kind(), SinkError::Config(_)) && !error.is_transient());
+    assert_eq!(SinkError::Unsupported("no".to_string()).in_sink("x").to_string(), "x: Unsupported operation: no");
+
+    // The built-in sinks implement the same trait
+    let sink: Box<dyn SyndicationSink> = Box::new(DirectorySink::new("site"));
+    assert_eq!(sink.name(), "directory");
+    Ok(())
+}
+
+#[test]
+fn run_reports_keep_the_failing_node() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-sink-errors-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let canvas = CanvasBuilder::new().text_node(NOTE, "Unlucky").color(PresetColor::Red).build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+    let mut syndicator = Syndicator::new(Config::new(scratch.0.join("notes.canvas")))?;
+    syndicator.add_sink(Failing)?;
+
+    let report = syndicator.process_once()?;
+    let outcome = &report.sinks[0];
+    assert_eq!(outcome.error_node_id.as_deref(), Some(NOTE));
+    let error = outcome.error.as_deref().ok_or("no error")?;
+    assert!(error.contains("503 Service Unavailable"), "{}", error);
+    Ok(())
+}
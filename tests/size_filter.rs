@@ -0,0 +1,48 @@
+//! The size filter in a run: only red nodes shaped like developed notes are published.
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, SizeFilter, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTE: &str = "512e000000000001";
+const CARD: &str = "512e000000000002";
+const BANNER: &str = "512e000000000003";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn only_note_sized_nodes_are_published() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-size-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let canvas = CanvasBuilder::new()
+        .text_node(NOTE, "A developed note")
+        .color(PresetColor::Red)
+        .size(400, 300)
+        .text_node(CARD, "A quick card")
+        .color(PresetColor::Red)
+        .size(250, 60)
+        .text_node(BANNER, "A banner")
+        .color(PresetColor::Red)
+        .size(1200, 100)
+        .build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+
+    let mut config = Config::new(scratch.0.join("notes.canvas"));
+    config.options.size = SizeFilter::looks_like_note();
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(scratch.0.join("site")))?;
+    let report = syndicator.process_once()?;
+    assert_eq!(report.new_items, [NOTE]);
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//! One canvas version per run: a save landing mid-run is seen by none of the run's steps,
+//! and every delivered node records the hash of the version it came from.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use chrono::Local;
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, CanvasSnapshot, Config, SinkError, SinkTarget, SyndicationFormat, SyndicationSink,
+    SyndicationTracker, Syndicator,
+};
+
+const FIRST: &str = "5a90000000000001";
+const SECOND: &str = "5a90000000000002";
+
+/// Node ID, text and canvas hash of each item a sink was given
+type Seen = Arc<Mutex<Vec<(String, String, Option<String>)>>>;
+
+/// Records what it's given, and saves `save` over the canvas while publishing, once
+struct Mock {
+    name: &'static str,
+    seen: Seen,
+    save: Option<(PathBuf, String)>,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        let mut seen = self.seen.lock().unwrap();
+        for item in items.values() {
+            seen.push((item.id.to_string(), item.text.clone(), item.canvas_hash.clone()));
+        }
+        if let Some((path, json)) = self.save.take() {
+            std::fs::write(path, json)?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn a_save_mid_run_waits_for_the_next_run() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-snapshot-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let path = scratch.0.join("notes.canvas");
+    let before = serde_json::to_string(&CanvasBuilder::new().text_node(FIRST, "As read").color(PresetColor::Red).build()?)?;
+    let after = serde_json::to_string(
+        &CanvasBuilder::new()
+            .text_node(FIRST, "Saved mid-run")
+            .color(PresetColor::Red)
+            .text_node(SECOND, "Added mid-run")
+            .color(PresetColor::Red)
+            .build()?,
+    )?;
+    std::fs::write(&path, &before)?;
+    let hash_of = |json: &str| CanvasSnapshot::from_bytes(&path, json.as_bytes().to_vec(), None, Local::now()).hash;
+
+    // The first sink saves the canvas; the second runs after and must still see the old one
+    let (saver, later) = (Seen::default(), Seen::default());
+    let mut syndicator = Syndicator::new(Config::new(&path))?;
+    let sink = Mock { name: "saver", seen: saver.clone(), save: Some((path.clone(), after.clone())) };
+    syndicator.add_target(SinkTarget::new(sink, SyndicationTracker::new(&path, "saver")?));
+    let sink = Mock { name: "later", seen: later.clone(), save: None };
+    syndicator.add_target(SinkTarget::new(sink, SyndicationTracker::new(&path, "later")?).after("saver"));
+    let report = syndicator.process_once()?;
+
+    let as_read = vec![(FIRST.to_string(), "As read".to_string(), Some(hash_of(&before)))];
+    assert_eq!(*saver.lock().unwrap(), as_read);
+    assert_eq!(*later.lock().unwrap(), as_read);
+    assert_eq!(report.canvas_hash, Some(hash_of(&before)));
+    for sink in ["saver", "later"] {
+        let tracker = SyndicationTracker::new(&path, sink)?;
+        assert_eq!(tracker.canvas_hash(&FIRST.parse()?), Some(hash_of(&before).as_str()));
+    }
+
+    // The next run reads the saved version and records its hash for what it delivers
+    let report = syndicator.process_once()?;
+    assert_eq!(report.new_items, [SECOND]);
+    assert_eq!(report.canvas_hash, Some(hash_of(&after)));
+    let tracker = SyndicationTracker::new(&path, "later")?;
+    assert_eq!(tracker.canvas_hash(&SECOND.parse()?), Some(hash_of(&after).as_str()));
+    assert_eq!(tracker.canvas_hash(&FIRST.parse()?), Some(hash_of(&before).as_str()));
+    Ok(())
+}
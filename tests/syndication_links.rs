@@ -0,0 +1,68 @@
+//! Backfilling `syndication:` links into published posts: the key is created, later links
+//! are merged into it, the rest of the post is kept, and backfilling again changes nothing.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, SyndicationLinks, SyndicationSink, Syndicator};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const NOTE: &str = "5ead000000000001";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn links(urls: &[(&str, &str)]) -> Result<SyndicationLinks, Box<dyn Error>> {
+    let urls = urls.iter().map(|(sink, url)| (sink.to_string(), url.to_string())).collect();
+    Ok(SyndicationLinks::from([(NOTE.parse()?, urls)]))
+}
+
+/// Path of the note's published post
+fn post_path(site: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    for entry in std::fs::read_dir(site)? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(&format!("-{}.md", NOTE)) {
+            return Ok(path);
+        }
+    }
+    Err("no post".into())
+}
+
+#[test]
+fn links_are_added_merged_and_never_duplicated() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-syndication-links-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let site = scratch.0.join("site");
+    let canvas = CanvasBuilder::new().text_node(NOTE, "Posted everywhere").color(PresetColor::Red).build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+    let mut syndicator = Syndicator::new(Config::new(scratch.0.join("notes.canvas")))?;
+    syndicator.add_sink(DirectorySink::new(&site))?;
+    syndicator.process_once()?;
+    let path = post_path(&site)?;
+    let original = std::fs::read_to_string(&path)?;
+
+    let mut sink = DirectorySink::new(&site);
+    let tweet = "https://x.com/i/status/1";
+    assert_eq!(sink.add_syndication_links(&links(&[("twitter", tweet)])?, false)?, 1);
+    let created = std::fs::read_to_string(&path)?;
+    assert!(created.contains(&format!("syndication:\n  - \"{}\"\n", tweet)), "{}", created);
+    let body = original.split("\n---\n").last().ok_or("no body")?;
+    assert!(created.contains("title: \"Posted everywhere\"") && created.ends_with(body), "{}", created);
+
+    let toot = "https://mastodon.example/@me/2";
+    assert_eq!(sink.add_syndication_links(&links(&[("twitter", tweet), ("mastodon", toot)])?, false)?, 1);
+    let merged = std::fs::read_to_string(&path)?;
+    assert!(merged.contains(&format!("syndication:\n  - \"{}\"\n  - \"{}\"\n", tweet, toot)), "{}", merged);
+
+    assert_eq!(sink.add_syndication_links(&links(&[("mastodon", toot), ("twitter", tweet)])?, false)?, 0);
+    assert_eq!(std::fs::read_to_string(&path)?, merged);
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! A custom text policy reaches the post and content lake titles, while posts published
+//! under the old policy keep their file names.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, Syndicator, TextPolicy};
+use syndicate_json_canvas_sinks::{ContentLakeReader, ContentLakeSink, DirectorySink};
+
+const OLD: &str = "7e70000000000001";
+const NEW: &str = "7e70000000000002";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Publish the red notes into `site` and `lake` under `text`
+fn publish(dir: &Path, notes: &[&str], text: &TextPolicy) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for id in notes {
+        builder = builder.text_node(*id, format!("The Story of {}", id)).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.text = text.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    syndicator.add_sink(DirectorySink::new(dir.join("site")).with_text_policy(text.clone()))?;
+    syndicator.add_sink(ContentLakeSink::new(dir.join("lake")).with_text_policy(text.clone()))?;
+    syndicator.process_once()?;
+    Ok(())
+}
+
+/// File name and contents of the post of `id`
+fn post(dir: &Path, id: &str) -> Result<(String, String), Box<dyn Error>> {
+    for entry in std::fs::read_dir(dir.join("site"))? {
+        let path = entry?.path();
+        let name = path.file_name().ok_or("no file name")?.to_string_lossy().into_owned();
+        if name.ends_with(&format!("-{}.md", id)) {
+            return Ok((name, std::fs::read_to_string(&path)?));
+        }
+    }
+    Err(format!("no post of {}", id).into())
+}
+
+#[test]
+fn custom_policy_propagates_and_old_slugs_stay() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-text-policy-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    publish(&scratch.0, &[OLD], &TextPolicy::default())?;
+    let (old_name, _) = post(&scratch.0, OLD)?;
+    assert_eq!(old_name, format!("the-story-of-{}-{}.md", OLD, OLD));
+
+    let custom = TextPolicy::from_toml(
+        "[text]\ntitle_max_words = 3\ntitle_case = \"lowercase\"\nstopwords = [\"the\", \"of\"]\n",
+    )?;
+    // Losing the tracker republishes the old note under the new policy
+    std::fs::remove_file(scratch.0.join(".notes.canvas.syndication.directory.toml"))?;
+    publish(&scratch.0, &[OLD, NEW], &custom)?;
+
+    let (name, contents) = post(&scratch.0, NEW)?;
+    assert_eq!(name, format!("story-{}-{}.md", NEW, NEW));
+    assert!(contents.contains("title: \"the story of\""), "{}", contents);
+    assert_eq!(post(&scratch.0, OLD)?.0, old_name);
+    let entries = std::fs::read_dir(scratch.0.join("site"))?.collect::<Result<Vec<_>, _>>()?;
+    let posts = entries.iter().filter(|entry| entry.path().extension() == Some("md".as_ref())).count();
+    assert_eq!(posts, 2, "the old post was written again under a new name");
+    let lake = ContentLakeReader::new(scratch.0.join("lake")).latest()?;
+    assert_eq!(lake[NEW].title, "the story of");
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! Pruning under the tombstone policy: the directory keeps a withdrawn stub under the
+//! post's name, the content lake appends a tombstone record, and a node that comes back is
+//! published over its stub.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{Local, TimeZone};
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, MockClock, RemovalPolicy, Syndicator};
+use syndicate_json_canvas_sinks::{ContentLakeReader, ContentLakeSink, DirectorySink};
+
+const KEPT: [&str; 2] = ["7b00000000000001", "7b00000000000002"];
+const GONE: &str = "7b00000000000003";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn write_canvas(dir: &Path, with_gone: bool) -> Result<(), Box<dyn Error>> {
+    let mut builder = CanvasBuilder::new();
+    for id in KEPT.iter().chain(with_gone.then_some(&GONE)) {
+        builder = builder.text_node(*id, format!("Thoughts on {}", id)).color(PresetColor::Red);
+    }
+    std::fs::write(dir.join("notes.canvas"), serde_json::to_string(&builder.build()?)?)?;
+    Ok(())
+}
+
+/// Syndicator publishing into `site` and `lake` on `day` of March, leaving tombstones
+fn syndicator(dir: &Path, day: u32) -> Result<Syndicator, Box<dyn Error>> {
+    let clock = Arc::new(MockClock::new(Local.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap()));
+    let mut config = Config::new(dir.join("notes.canvas"));
+    config.options.clock = clock.clone();
+    let mut syndicator = Syndicator::new(config)?;
+    let site = DirectorySink::new(dir.join("site")).with_clock(clock.clone());
+    syndicator.add_sink(site.with_removal_policy(RemovalPolicy::Tombstone))?;
+    let lake = ContentLakeSink::new(dir.join("lake")).with_clock(clock);
+    syndicator.add_sink(lake.with_removal_policy(RemovalPolicy::Tombstone))?;
+    Ok(syndicator)
+}
+
+#[test]
+fn pruned_nodes_leave_tombstones_in_the_site_and_lake() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-tombstones-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    write_canvas(&scratch.0, true)?;
+    syndicator(&scratch.0, 1)?.process_once()?;
+    let post_path = scratch.0.join("site").join(format!("thoughts-on-{}-{}.md", GONE, GONE));
+    let published = std::fs::read_to_string(&post_path)?;
+    let prior = ContentLakeReader::new(scratch.0.join("lake")).latest()?[GONE].clone();
+
+    write_canvas(&scratch.0, false)?;
+    let mut pruning = syndicator(&scratch.0, 5)?;
+    let plans = pruning.plan_prune()?;
+    let printed: String = plans.iter().map(ToString::to_string).collect();
+    assert!(printed.contains("leave tombstones for 1 files"), "{}", printed);
+    pruning.prune(&plans)?;
+
+    let stub = std::fs::read_to_string(&post_path)?;
+    assert!(stub.starts_with("---\ntitle: \"Withdrawn\"\ndate: 2026-03-01\n"), "{}", stub);
+    assert!(stub.contains("withdrawn: true\nwithdrawn_at: 2026-03-05\n"), "{}", stub);
+    assert!(!stub.contains("Thoughts on"), "{}", stub);
+    let lake = ContentLakeReader::new(scratch.0.join("lake")).latest()?;
+    let tombstone = &lake[GONE];
+    assert_eq!(tombstone.supersedes.as_deref(), Some(prior.record_id.as_str()));
+    assert_eq!(tombstone.published_at, prior.published_at);
+    assert!(tombstone.withdrawn_at.is_some() && tombstone.text.is_empty());
+    assert!(KEPT.iter().all(|id| lake[*id].withdrawn_at.is_none()));
+
+    // The node comes back, and is published over its stub
+    write_canvas(&scratch.0, true)?;
+    syndicator(&scratch.0, 1)?.process_once()?;
+    assert_eq!(std::fs::read_to_string(&post_path)?, published);
+    assert!(ContentLakeReader::new(scratch.0.join("lake")).latest()?[GONE].withdrawn_at.is_none());
+    Ok(())
+}
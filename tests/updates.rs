@@ -0,0 +1,115 @@
+//! One node taken through new, edited and unchanged runs: sinks that take updates get the
+//! edit once, sinks that don't only publish it, and unchanged runs send nothing anywhere.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, ProcessReport, SinkError, SyndicationFormat, SyndicationSink, Syndicator,
+};
+use syndicate_json_canvas_sinks::{ContentLakeReader, ContentLakeSink};
+
+const NOTE: &str = "ed17000000000001";
+
+/// Each call a sink took: the operation and the texts it was given
+type Calls = Arc<Mutex<Vec<(&'static str, Vec<String>)>>>;
+
+struct Mock {
+    name: &'static str,
+    updates: bool,
+    calls: Calls,
+}
+
+impl Mock {
+    fn record(&self, operation: &'static str, items: &HashMap<NodeId, SyndicationFormat>) {
+        let texts = items.values().map(|item| item.text.clone()).collect();
+        self.calls.lock().unwrap().push((operation, texts));
+    }
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        self.record("publish", items);
+        Ok(())
+    }
+
+    fn update(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        if !self.updates {
+            return Err(SinkError::Unsupported(format!("{} does not support updates", self.name)));
+        }
+        self.record("update", items);
+        Ok(())
+    }
+
+    fn supports_updates(&self) -> bool {
+        self.updates
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn scratch(name: &str) -> Result<Scratch, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-updates-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    Ok(Scratch(dir))
+}
+
+/// Save the canvas with the note reading `text` and run once with every sink
+fn run(dir: &Path, text: &str, feed: &Calls, social: &Calls) -> Result<ProcessReport, Box<dyn Error>> {
+    let canvas_path = dir.join("notes.canvas");
+    let canvas = CanvasBuilder::new().text_node(NOTE, text).color(PresetColor::Red).build()?;
+    std::fs::write(&canvas_path, serde_json::to_string(&canvas)?)?;
+
+    let mut syndicator = Syndicator::new(Config::new(&canvas_path))?;
+    syndicator.add_sink(Mock { name: "feed", updates: true, calls: feed.clone() })?;
+    syndicator.add_sink(Mock { name: "social", updates: false, calls: social.clone() })?;
+    syndicator.add_sink(ContentLakeSink::new(dir.join("lake")))?;
+    Ok(syndicator.process_once()?)
+}
+
+fn updated(report: &ProcessReport, sink: &str) -> Vec<String> {
+    report.sinks.iter().filter(|outcome| outcome.sink == sink).flat_map(|outcome| outcome.updated.clone()).collect()
+}
+
+#[test]
+fn edits_are_delivered_once_as_updates() -> Result<(), Box<dyn Error>> {
+    let scratch = scratch("cycle")?;
+    let (feed, social) = (Calls::default(), Calls::default());
+
+    let new = run(&scratch.0, "First draft.", &feed, &social)?;
+    assert_eq!(new.new_items, [NOTE]);
+    let edited = run(&scratch.0, "Second draft.", &feed, &social)?;
+    assert!(edited.new_items.is_empty());
+    assert_eq!(edited.updated_items, [NOTE]);
+    assert_eq!(updated(&edited, "feed"), [NOTE]);
+    assert_eq!(updated(&edited, "content-lake"), [NOTE]);
+    assert!(updated(&edited, "social").is_empty());
+    for _ in 0..2 {
+        let unchanged = run(&scratch.0, "Second draft.", &feed, &social)?;
+        assert!(unchanged.new_items.is_empty() && unchanged.updated_items.is_empty(), "{:?}", unchanged);
+    }
+
+    let text = |text: &str| vec![text.to_string()];
+    assert_eq!(*feed.lock().unwrap(), [("publish", text("First draft.")), ("update", text("Second draft."))]);
+    assert_eq!(*social.lock().unwrap(), [("publish", text("First draft."))]);
+
+    let records = ContentLakeReader::new(scratch.0.join("lake")).records()?;
+    assert_eq!(records.iter().map(|r| r.text.as_str()).collect::<Vec<_>>(), ["First draft.", "Second draft."]);
+    assert_eq!(records[1].supersedes.as_ref(), Some(&records[0].record_id));
+    Ok(())
+}
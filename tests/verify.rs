@@ -0,0 +1,54 @@
+//! Verifying published posts: broken frontmatter and a node ID that doesn't match the file
+//! name are reported, good posts aren't, and nothing is modified.
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use syndicate_json_canvas_lib::{CanvasBuilder, Config, FileProblem, Syndicator, read_tree};
+use syndicate_json_canvas_sinks::DirectorySink;
+
+const GOOD: &str = "5e1f000000000001";
+const BROKEN: &str = "5e1f000000000002";
+const MISMATCHED: &str = "5e1f000000000003";
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn broken_and_mismatched_posts_are_reported_without_changes() -> Result<(), Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-verify-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let scratch = Scratch(dir);
+    let site = scratch.0.join("site");
+    std::fs::create_dir_all(&site)?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&CanvasBuilder::new().build()?)?)?;
+
+    let post = |title: &str| format!("---\ntitle: \"{}\"\ndate: 2026-03-01\n---\n\nText\n", title);
+    std::fs::write(site.join(format!("good-{}.md", GOOD)), post("Good"))?;
+    std::fs::write(site.join(format!("broken-{}.md", BROKEN)), "---\ntitle: \"Unclosed\ndate: [2026\n---\n")?;
+    let mismatched = post("Moved").replace("date:", &format!("node_id: \"{}\"\ndate:", GOOD));
+    std::fs::write(site.join(format!("moved-{}.md", MISMATCHED)), mismatched)?;
+    let before = read_tree(&site)?;
+
+    let mut syndicator = Syndicator::new(Config::new(scratch.0.join("notes.canvas")))?;
+    syndicator.add_sink(DirectorySink::new(&site))?;
+    let reports = syndicator.verify()?;
+
+    assert_eq!((reports.len(), reports[0].files_checked, reports[0].files_with_issues()), (1, 3, 2));
+    let problems_of = |id: &str| -> Vec<&FileProblem> {
+        let suffix = format!("-{}.md", id);
+        let issues = reports[0].issues.iter().filter(|issue| issue.path.to_string_lossy().ends_with(&suffix));
+        issues.map(|issue| &issue.problem).collect()
+    };
+    assert!(problems_of(GOOD).is_empty(), "{:?}", problems_of(GOOD));
+    assert!(matches!(problems_of(BROKEN)[..], [FileProblem::InvalidFrontmatter(_)]), "{:?}", problems_of(BROKEN));
+    let mismatch = &problems_of(MISMATCHED)[..];
+    assert!(matches!(mismatch, [FileProblem::IdMismatch { node_id, .. }] if node_id == GOOD), "{:?}", mismatch);
+    assert_eq!(read_tree(&site)?, before, "verify modified the site");
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Warnings from the pipeline and from sinks are gathered on the run report and grouped by
+//! code; promoted codes fail the run or the sink, and silenced ones are dropped.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use syndicate_json_canvas_lib::jsoncanvas::NodeId;
+use syndicate_json_canvas_lib::jsoncanvas::color::PresetColor;
+use syndicate_json_canvas_lib::warnings::codes;
+use syndicate_json_canvas_lib::{
+    CanvasBuilder, Config, ProcessReport, SinkError, SyndicationFormat, SyndicationSink, Syndicator, Warning,
+    WarningPolicy, group_warnings,
+};
+
+const UNDEFINED: &str = "3a40000000000001";
+const UNUSED: &str = "3a40000000000002";
+const LONG: &str = "3a40000000000003";
+
+/// Items the mock was given, by node ID
+type Published = Arc<Mutex<Vec<String>>>;
+
+/// Publishes everything, warning that the item of `LONG` is too long
+struct Mock {
+    published: Published,
+    warnings: Vec<Warning>,
+}
+
+impl SyndicationSink for Mock {
+    fn publish(&mut self, items: &HashMap<NodeId, SyndicationFormat>, _dry_run: bool) -> Result<(), SinkError> {
+        for node_id in items.keys() {
+            self.published.lock().unwrap().push(node_id.to_string());
+            if node_id.as_str() == LONG {
+                self.warnings.push(Warning::new(codes::TOO_LONG, "mock", "Too long, skipped").for_node(node_id));
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+}
+
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// The outcome of a run raising one warning of each kind under `policy`
+type Run = (Result<ProcessReport, String>, Published);
+
+fn run(name: &str, policy: WarningPolicy) -> Result<Run, Box<dyn Error>> {
+    let dir = std::env::temp_dir().join(format!("syndicate-json-canvas-warnings-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let scratch = Scratch(dir);
+    let canvas = CanvasBuilder::new()
+        .text_node(UNDEFINED, "Cites a note[^1] that isn't there")
+        .color(PresetColor::Red)
+        .text_node(UNUSED, "Says nothing of it\n\n[^1]: A lonely note")
+        .color(PresetColor::Red)
+        .text_node(LONG, "Goes on and on")
+        .color(PresetColor::Red)
+        .build()?;
+    std::fs::write(scratch.0.join("notes.canvas"), serde_json::to_string(&canvas)?)?;
+    let mut config = Config::new(scratch.0.join("notes.canvas"));
+    config.options.warnings = policy;
+    let mut syndicator = Syndicator::new(config)?;
+    let published = Published::default();
+    syndicator.add_sink(Mock { published: published.clone(), warnings: Vec::new() })?;
+    Ok((syndicator.process_once().map_err(|e| e.to_string()), published))
+}
+
+#[test]
+fn warnings_of_a_run_are_grouped_by_code() -> Result<(), Box<dyn Error>> {
+    let (report, published) = run("grouped", WarningPolicy::new())?;
+    let report = report?;
+    assert!(report.error.is_none() && report.sinks[0].error.is_none());
+    assert_eq!(published.lock().unwrap().len(), 3);
+
+    let groups = group_warnings(report.all_warnings());
+    let summary: Vec<(&str, &str, Option<&str>)> = groups
+        .iter()
+        .map(|group| (group.code.as_str(), group.source.as_str(), group.warnings[0].node_id.as_deref()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (codes::FOOTNOTE_UNDEFINED, "footnotes", Some(UNDEFINED)),
+            (codes::FOOTNOTE_UNUSED, "footnotes", Some(UNUSED)),
+            (codes::TOO_LONG, "mock", Some(LONG)),
+        ]
+    );
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("\n## Warnings\n"), "{}", markdown);
+    assert!(markdown.contains(&format!("### `too-long` from mock (1)\n\n- `{}`: Too long, skipped\n", LONG)));
+    Ok(())
+}
+
+#[test]
+fn promoted_warnings_fail_and_silenced_ones_go() -> Result<(), Box<dyn Error>> {
+    // A promoted pipeline warning stops the run before any sink is given anything
+    let (report, published) = run("promoted", WarningPolicy::new().promote(codes::FOOTNOTE_UNDEFINED))?;
+    let error = report.err().ok_or("the run didn't fail")?;
+    assert!(error.contains("warnings promoted to errors") && error.contains(UNDEFINED), "{}", error);
+    assert!(published.lock().unwrap().is_empty());
+
+    // A promoted sink warning fails that sink once it has delivered
+    let (report, published) = run("promoted-sink", WarningPolicy::new().promote(codes::TOO_LONG))?;
+    let report = report?;
+    assert!(report.error.is_none());
+    let error = report.sinks[0].error.as_deref().ok_or("the sink didn't fail")?;
+    assert!(error.contains("[too-long]"), "{}", error);
+    assert_eq!(published.lock().unwrap().len(), 3);
+
+    let policy = WarningPolicy::new().silence(codes::FOOTNOTE_UNUSED).silence(codes::TOO_LONG);
+    let report = run("silenced", policy)?.0?;
+    let codes: Vec<&str> = report.all_warnings().map(|w| w.code.as_str()).collect();
+    assert_eq!(codes, [codes::FOOTNOTE_UNDEFINED]);
+    Ok(())
+}